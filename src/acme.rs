@@ -0,0 +1,54 @@
+//! ACME DNS-01 challenges.
+//!
+//! Certbot, lego and similar clients prove control of a domain by asking
+//! for a TXT record at `_acme-challenge.<domain>`. These helpers publish and
+//! remove that record with the credentials of a configured provider, so the
+//! agent can serve as their DNS hook.
+
+use crate::error::ProviderError;
+use crate::provider::{DnsProvider, ascii_name};
+use log::info;
+
+const CHALLENGE_LABEL: &str = "_acme-challenge";
+
+/// Name of the challenge record for `domain`, which may already be the
+/// challenge name (as lego passes it) and may end with a dot. A wildcard
+/// domain shares the record of its base domain, and internationalized ones
+/// are written in punycode.
+pub fn challenge_name(domain: &str) -> String {
+    let domain = domain.trim_end_matches('.');
+    let domain = domain.strip_prefix("*.").unwrap_or(domain);
+    let domain = ascii_name(domain).unwrap_or_else(|| domain.to_string());
+    let domain = domain.as_str();
+    if domain.starts_with(&format!("{}.", CHALLENGE_LABEL)) {
+        domain.to_string()
+    } else {
+        format!("{}.{}", CHALLENGE_LABEL, domain)
+    }
+}
+
+/// Publish the challenge `value` for `domain` next to any other pending one
+pub async fn set_txt(
+    provider: &dyn DnsProvider,
+    domain: &str,
+    value: &str,
+) -> Result<(), ProviderError> {
+    let name = challenge_name(domain);
+    let zone = provider.lookup_zone(&name).await?;
+    provider.add_txt(&zone, &name, value).await?;
+    info!("TXT record added: {} at {}", name, provider.name());
+    Ok(())
+}
+
+/// Remove the challenge `value` for `domain`
+pub async fn clear_txt(
+    provider: &dyn DnsProvider,
+    domain: &str,
+    value: &str,
+) -> Result<(), ProviderError> {
+    let name = challenge_name(domain);
+    let zone = provider.lookup_zone(&name).await?;
+    provider.delete_txt(&zone, &name, value).await?;
+    info!("TXT record removed: {} at {}", name, provider.name());
+    Ok(())
+}
@@ -0,0 +1,2828 @@
+//! The update loop tying an [`IpSource`] to a [`DnsProvider`].
+
+use crate::cache::IdCache;
+use crate::config::{
+    DriftAction, DriftCheckConfig, HumanDuration, RecordConfig, RetryConfig, VerifyConfig,
+};
+use crate::error::{IpDetectionError, ProviderError, Result};
+use crate::health::{self, HealthCheckConfig};
+use crate::ip::{IpSource, IpVersion, dns};
+use crate::notify::{ChangedRecord, IpChange, Notifier, NotifyConfig};
+use crate::owner;
+use crate::provider::{DnsProvider, Record, RecordType, SrvRecord, Zone};
+use crate::retry::{self, MAX_RETRY_AFTER};
+use futures::future;
+use futures::stream::{self, StreamExt};
+use log::{error, info, warn};
+use std::collections::{BTreeMap, HashMap};
+use std::net::IpAddr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+use tokio::time::{Duration, Instant, sleep, sleep_until};
+
+/// Default number of records updated at the same time
+pub const DEFAULT_CONCURRENCY: usize = 4;
+
+/// Pause used when a rate limited provider doesn't say how long to wait
+const DEFAULT_RETRY_AFTER: Duration = Duration::from_secs(5);
+/// Factor by which rate limits may stretch the interval between checks
+const MAX_SLOWDOWN: u32 = 8;
+/// How long resolvers may cache records whose TTL the agent doesn't know
+const DRIFT_CHECK_TTL: u32 = 300;
+/// Time a resolver has to answer the drift check
+const DRIFT_CHECK_TIMEOUT: Duration = Duration::from_secs(5);
+/// Resolver asked for the nameservers of a zone when verifying updates
+/// without resolvers of their own
+const NAMESERVER_RESOLVER: &str = "1.1.1.1";
+/// Time a nameserver has to answer while verifying an update
+const VERIFY_QUERY_TIMEOUT: Duration = Duration::from_secs(5);
+/// Pause between the queries verifying an update
+const VERIFY_INTERVAL: Duration = Duration::from_secs(5);
+
+/// A configured record together with what the agent knows about it.
+///
+/// Dual-stack records are tracked as two managed records, one per type.
+#[derive(Clone)]
+pub struct ManagedRecord {
+    pub config: RecordConfig,
+    pub kind: RecordType,
+    pub provider: Arc<dyn DnsProvider>,
+    pub zone: Option<Zone>,
+    pub current: Option<Record>,
+    pub last_ip: Option<IpAddr>,
+    /// When the record was last written
+    pub last_update: Option<Instant>,
+    /// When the record was last written, also by an earlier run of the
+    /// agent, as remembered by the cache
+    pub written_at: Option<SystemTime>,
+    /// Since when the agent manages the record, standing in for the last
+    /// write if that is unknown
+    pub tracked_since: Instant,
+    /// The record at the providers taking over while `provider` is down, in
+    /// order. Their zones and records are looked up on first use.
+    pub failover: Vec<ManagedRecord>,
+    /// Whether the last update went to a failover provider
+    pub failed_over: bool,
+    /// Whether the configured SRV records were found or written
+    pub srv_synced: bool,
+    /// Since when the health check has been failing
+    pub unhealthy_since: Option<Instant>,
+    /// Whether the record is withdrawn because its health check failed
+    pub withdrawn: bool,
+    /// Whether public resolvers answered another address than the one
+    /// written, and the record is to be written again
+    pub drifted: bool,
+    /// Whether the agent created the record, so garbage collection deletes
+    /// it once it is removed from the config
+    pub created: bool,
+    /// Whether the record was looked up before, so one missing now was
+    /// deleted since rather than never there
+    pub looked_up: bool,
+}
+
+impl ManagedRecord {
+    /// Whether the record or its provider wants it written again although
+    /// the IP is unchanged, given the `force_update_interval` of the
+    /// provider instance. A record whose last write is unknown counts as
+    /// written when the agent started managing it.
+    fn needs_refresh(&self, provider_interval: Option<Duration>) -> bool {
+        let interval = self
+            .config
+            .force_update_interval
+            .map(|d| d.0)
+            .or(provider_interval)
+            .or(self.provider.refresh_interval());
+        let Some(interval) = interval else {
+            return false;
+        };
+        let since = match (self.last_update, self.written_at) {
+            (Some(last), _) => last.elapsed(),
+            // A clock set back makes the write look recent
+            (None, Some(written)) => written.elapsed().unwrap_or_default(),
+            (None, None) => self.tracked_since.elapsed(),
+        };
+        since >= interval
+    }
+
+    /// Remember that the record was written just now
+    fn mark_written(&mut self) {
+        self.last_update = Some(Instant::now());
+        self.written_at = Some(SystemTime::now());
+    }
+
+    /// TTL the provider stores for the configured one, `None` if it can't
+    /// be set there
+    fn stored_ttl(&self) -> Option<u32> {
+        self.provider.effective_ttl(&Record {
+            id: None,
+            name: self.config.name.clone(),
+            kind: self.kind,
+            content: String::new(),
+            ttl: self.config.ttl,
+            proxied: self.proxied(),
+        })
+    }
+
+    /// The current record pointing at `ip`, with the configured settings
+    fn desired(&self, ip: IpAddr) -> Record {
+        let current = self.current.clone().expect("agent is prepared");
+        Record {
+            content: ip.to_string(),
+            ttl: self.config.ttl,
+            proxied: self.proxied(),
+            ..current
+        }
+    }
+
+    /// Proxy status to write: the configured one, or the one the record
+    /// already has
+    fn proxied(&self) -> Option<bool> {
+        self.config
+            .proxied
+            .or_else(|| self.current.as_ref().and_then(|r| r.proxied))
+    }
+
+    /// How the record at the provider differs from its configured TTL and
+    /// proxy status, e.g. after an edit in the dashboard
+    fn drift(&self) -> Option<String> {
+        // The other addresses of a shared record set its TTL too
+        if self.config.round_robin {
+            return None;
+        }
+        let current = self.current.as_ref().filter(|r| !r.content.is_empty())?;
+        if let Some(ttl) = self.stored_ttl().filter(|ttl| *ttl != current.ttl) {
+            return Some(format!("TTL is {} instead of {}", current.ttl, ttl));
+        }
+        match (current.proxied, self.config.proxied) {
+            (Some(proxied), Some(wanted)) if proxied != wanted => {
+                Some(format!("proxied is {} instead of {}", proxied, wanted))
+            }
+            _ => None,
+        }
+    }
+
+    fn new(config: RecordConfig, kind: RecordType, provider: Arc<dyn DnsProvider>) -> Self {
+        let managed = Self {
+            config,
+            kind,
+            provider,
+            zone: None,
+            current: None,
+            last_ip: None,
+            last_update: None,
+            written_at: None,
+            tracked_since: Instant::now(),
+            failover: Vec::new(),
+            failed_over: false,
+            srv_synced: false,
+            unhealthy_since: None,
+            withdrawn: false,
+            drifted: false,
+            created: false,
+            looked_up: false,
+        };
+        if managed.config.ttl != 1 {
+            match managed.stored_ttl() {
+                None => warn!(
+                    "{} doesn't support setting the TTL, ignoring ttl {} of {}",
+                    managed.provider.name(),
+                    managed.config.ttl,
+                    managed.config.name
+                ),
+                Some(ttl) if ttl != managed.config.ttl => warn!(
+                    "{} doesn't support ttl {} of {}, using {}",
+                    managed.provider.name(),
+                    managed.config.ttl,
+                    managed.config.name,
+                    ttl
+                ),
+                Some(_) => {}
+            }
+        }
+        managed
+    }
+
+    fn with_failover(mut self, failover: &[Arc<dyn DnsProvider>]) -> Self {
+        self.failover = failover
+            .iter()
+            .map(|provider| ManagedRecord::new(self.config.clone(), self.kind, provider.clone()))
+            .collect();
+        self
+    }
+}
+
+/// A record configuration with the provider managing it and its failover
+/// providers, as passed to [`Agent::reload`]
+pub type RecordSetup = (
+    RecordConfig,
+    Arc<dyn DnsProvider>,
+    Vec<Arc<dyn DnsProvider>>,
+);
+
+/// Outcome of a single [`Agent::sync`] pass
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SyncReport {
+    /// Records written to the provider
+    pub updated: usize,
+    /// Records already pointing at the detected IP
+    pub unchanged: usize,
+    /// Records that could not be updated, including those skipped because
+    /// their address family could not be detected
+    pub failed: usize,
+}
+
+impl SyncReport {
+    pub fn is_success(&self) -> bool {
+        self.failed == 0
+    }
+}
+
+/// What a sync would do to a record, see [`Agent::plan`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlannedAction {
+    /// The record doesn't exist yet
+    Create,
+    /// The record points at another address or has other settings
+    Update,
+    /// The record is up to date
+    Unchanged,
+}
+
+/// A record compared with what a sync would write
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlannedChange {
+    pub name: String,
+    pub kind: RecordType,
+    pub provider: String,
+    pub action: PlannedAction,
+    /// Address of the record at the provider, `None` if it doesn't exist or
+    /// isn't known, like with a configured `record_id`
+    pub current: Option<String>,
+    pub desired: IpAddr,
+    /// Address detected by the IP source of the record, which `desired` is
+    /// derived from
+    pub detected: IpAddr,
+    /// How the TTL or proxy status differs from the configured ones
+    pub drift: Option<String>,
+}
+
+impl PlannedChange {
+    /// Whether the change is about `managed`
+    fn is_for(&self, managed: &ManagedRecord) -> bool {
+        self.name == managed.config.name
+            && self.kind == managed.kind
+            && self.provider == managed.provider.name()
+    }
+}
+
+/// Records [`Agent::sync_group`] writes
+#[derive(Clone, Copy)]
+enum Writes<'a> {
+    /// Those whose address or settings changed, or that are due a refresh
+    Changed,
+    /// All of them, with the address unchanged too
+    All,
+    /// Those `plan` creates or updates, with the addresses planned
+    Planned(&'a [PlannedChange]),
+}
+
+/// Tracks providers that asked us to slow down, so concurrent updates to
+/// the same provider all wait instead of hammering it
+#[derive(Default)]
+struct Backoff {
+    until: Mutex<HashMap<String, Instant>>,
+    /// Whether a provider paused requests since the last check
+    limited: AtomicBool,
+}
+
+impl Backoff {
+    /// Wait until requests to `provider` are allowed again
+    async fn wait(&self, provider: &str) {
+        let until = self.until.lock().unwrap().get(provider).copied();
+        if let Some(until) = until {
+            sleep_until(until).await;
+        }
+    }
+
+    /// Hold off requests to `provider` for `delay`
+    fn pause(&self, provider: &str, delay: Duration) {
+        let until = Instant::now() + delay;
+        let mut paused = self.until.lock().unwrap();
+        let entry = paused.entry(provider.to_string()).or_insert(until);
+        *entry = (*entry).max(until);
+        self.limited.store(true, Ordering::Relaxed);
+    }
+
+    /// Whether a provider paused requests since the last call
+    fn take_limited(&self) -> bool {
+        self.limited.swap(false, Ordering::Relaxed)
+    }
+
+    /// Time until every provider accepts requests again
+    fn remaining(&self) -> Duration {
+        let now = Instant::now();
+        let paused = self.until.lock().unwrap();
+        paused
+            .values()
+            .map(|until| until.saturating_duration_since(now))
+            .max()
+            .unwrap_or_default()
+    }
+}
+
+/// Whether two provider handles point at the same provider instance
+fn same_provider(a: &Arc<dyn DnsProvider>, b: &Arc<dyn DnsProvider>) -> bool {
+    std::ptr::addr_eq(Arc::as_ptr(a), Arc::as_ptr(b))
+}
+
+pub struct Agent {
+    source: Arc<dyn IpSource>,
+    /// Sources records pick by name instead of `source`
+    named_sources: HashMap<String, Arc<dyn IpSource>>,
+    records: Vec<ManagedRecord>,
+    zones: Vec<(Arc<dyn DnsProvider>, Zone)>,
+    prepared: bool,
+    dry_run: bool,
+    concurrency: usize,
+    backoff: Backoff,
+    /// Factor stretching the interval between checks while rate limited
+    slowdown: u32,
+    cache: Option<IdCache>,
+    owner: Option<String>,
+    takeover: bool,
+    drift_check: Option<DriftCheckConfig>,
+    last_drift_check: Option<Instant>,
+    verify: Option<VerifyConfig>,
+    garbage_collect: bool,
+    retry: RetryConfig,
+    notifier: Notifier,
+    /// Interval after which the records of a provider instance are written
+    /// again though unchanged, by instance name
+    force_update_interval: BTreeMap<String, HumanDuration>,
+    /// Created records removed from the config whose deletion failed, with
+    /// the name of their provider
+    garbage: Vec<(String, Zone, Record)>,
+}
+
+impl Agent {
+    pub fn new(source: Arc<dyn IpSource>) -> Self {
+        Self {
+            source,
+            named_sources: HashMap::new(),
+            records: Vec::new(),
+            zones: Vec::new(),
+            prepared: false,
+            dry_run: false,
+            concurrency: DEFAULT_CONCURRENCY,
+            backoff: Backoff::default(),
+            slowdown: 1,
+            cache: None,
+            owner: None,
+            takeover: false,
+            drift_check: None,
+            last_drift_check: None,
+            verify: None,
+            garbage_collect: false,
+            retry: RetryConfig::default(),
+            force_update_interval: BTreeMap::new(),
+            notifier: Notifier::new(NotifyConfig::default()),
+            garbage: Vec::new(),
+        }
+    }
+
+    /// Manage `config` through `provider`.
+    ///
+    /// Dual-stack records add one managed record per address family.
+    pub fn add_record(&mut self, config: RecordConfig, provider: Arc<dyn DnsProvider>) {
+        self.add_record_with_failover(config, provider, Vec::new());
+    }
+
+    /// Manage `config` through `provider`, updating it through the first
+    /// working provider of `failover` while `provider` is unavailable.
+    ///
+    /// The record is written to `provider` again once it recovers.
+    pub fn add_record_with_failover(
+        &mut self,
+        config: RecordConfig,
+        provider: Arc<dyn DnsProvider>,
+        failover: Vec<Arc<dyn DnsProvider>>,
+    ) {
+        for version in config.ip_version.unwrap_or_default().versions() {
+            let kind = RecordType::for_version(*version);
+            self.records.push(
+                ManagedRecord::new(config.clone(), kind, provider.clone()).with_failover(&failover),
+            );
+        }
+        self.prepared = false;
+    }
+
+    /// Builder-style variant of [`Agent::add_record`]
+    pub fn with_record(mut self, config: RecordConfig, provider: Arc<dyn DnsProvider>) -> Self {
+        self.add_record(config, provider);
+        self
+    }
+
+    /// Detect the public IP with `source` from now on
+    pub fn set_source(&mut self, source: Arc<dyn IpSource>) {
+        self.source = source;
+    }
+
+    /// Detect the address of records setting `ip_source` with the source of
+    /// that name from now on
+    pub fn set_named_sources(&mut self, sources: HashMap<String, Arc<dyn IpSource>>) {
+        self.named_sources = sources;
+    }
+
+    /// Start from the zones and records remembered in `cache` instead of
+    /// looking them up, and keep it up to date
+    pub fn set_cache(&mut self, cache: IdCache) {
+        self.cache = Some(cache);
+    }
+
+    /// Only log the changes that would be made instead of writing them
+    pub fn set_dry_run(&mut self, dry_run: bool) {
+        self.dry_run = dry_run;
+    }
+
+    /// Builder-style variant of [`Agent::set_dry_run`]
+    pub fn with_dry_run(mut self, dry_run: bool) -> Self {
+        self.set_dry_run(dry_run);
+        self
+    }
+
+    /// Mark the records as managed by `owner` with a companion TXT record
+    /// and refuse to touch those marked by another owner
+    pub fn with_owner(mut self, owner: Option<String>) -> Self {
+        self.owner = owner;
+        self
+    }
+
+    /// Replace the markers of other owners instead of failing
+    pub fn with_takeover(mut self, takeover: bool) -> Self {
+        self.takeover = takeover;
+        self
+    }
+
+    /// Ask public resolvers for the records every now and then, warning
+    /// about or rewriting those changed outside the agent
+    pub fn set_drift_check(&mut self, drift_check: Option<DriftCheckConfig>) {
+        self.drift_check = drift_check;
+    }
+
+    /// Builder-style variant of [`Agent::set_drift_check`]
+    pub fn with_drift_check(mut self, drift_check: Option<DriftCheckConfig>) -> Self {
+        self.set_drift_check(drift_check);
+        self
+    }
+
+    /// Only count an update as done once the nameservers of its zone
+    /// answer with the new address
+    pub fn set_verify(&mut self, verify: Option<VerifyConfig>) {
+        self.verify = verify;
+    }
+
+    /// Builder-style variant of [`Agent::set_verify`]
+    pub fn with_verify(mut self, verify: Option<VerifyConfig>) -> Self {
+        self.set_verify(verify);
+        self
+    }
+
+    /// Report records updated for a new IP to the targets of `notify`
+    pub fn set_notify(&mut self, notify: NotifyConfig) {
+        self.notifier = Notifier::new(notify);
+    }
+
+    /// Builder-style variant of [`Agent::set_notify`]
+    pub fn with_notify(mut self, notify: NotifyConfig) -> Self {
+        self.set_notify(notify);
+        self
+    }
+
+    /// Delete the records the agent created once they are removed from the
+    /// config, remembered by the cache
+    pub fn set_garbage_collect(&mut self, garbage_collect: bool) {
+        self.garbage_collect = garbage_collect;
+    }
+
+    /// Builder-style variant of [`Agent::set_garbage_collect`]
+    pub fn with_garbage_collect(mut self, garbage_collect: bool) -> Self {
+        self.set_garbage_collect(garbage_collect);
+        self
+    }
+
+    /// Retry failed provider requests and IP detections according to
+    /// `retry`
+    pub fn set_retry(&mut self, retry: RetryConfig) {
+        self.retry = retry;
+    }
+
+    /// Builder-style variant of [`Agent::set_retry`]
+    pub fn with_retry(mut self, retry: RetryConfig) -> Self {
+        self.set_retry(retry);
+        self
+    }
+
+    /// Write the records of the provider instances named in `intervals`
+    /// again after their interval without a change, unless a record sets
+    /// its own
+    pub fn set_force_update_interval(&mut self, intervals: BTreeMap<String, HumanDuration>) {
+        self.force_update_interval = intervals;
+    }
+
+    /// Builder-style variant of [`Agent::set_force_update_interval`]
+    pub fn with_force_update_interval(
+        mut self,
+        intervals: BTreeMap<String, HumanDuration>,
+    ) -> Self {
+        self.set_force_update_interval(intervals);
+        self
+    }
+
+    /// Update at most `limit` records at the same time
+    pub fn set_concurrency(&mut self, limit: usize) {
+        self.concurrency = limit.max(1);
+    }
+
+    /// Builder-style variant of [`Agent::set_concurrency`]
+    pub fn with_concurrency(mut self, limit: usize) -> Self {
+        self.set_concurrency(limit);
+        self
+    }
+
+    /// The managed records and their last known state
+    pub fn records(&self) -> &[ManagedRecord] {
+        &self.records
+    }
+
+    /// Find an already known zone of `provider` containing `dns_name`,
+    /// preferring the most specific one
+    fn known_zone(&self, provider: &Arc<dyn DnsProvider>, dns_name: &str) -> Option<Zone> {
+        self.zones
+            .iter()
+            .filter(|(p, _)| same_provider(p, provider))
+            .map(|(_, z)| z)
+            .filter(|z| dns_name == z.name || dns_name.ends_with(&format!(".{}", z.name)))
+            .max_by_key(|z| z.name.len())
+            .cloned()
+    }
+
+    /// Replace the managed records, e.g. after the configuration changed.
+    ///
+    /// Records whose name, type and provider are unchanged keep their zone,
+    /// record ID and last IP, so only new records are looked up again. A
+    /// record whose settings (TTL, proxying) changed is rewritten on the next
+    /// sync.
+    pub fn reload(&mut self, records: Vec<RecordSetup>) {
+        let mut old = std::mem::take(&mut self.records);
+        let (mut added, mut kept) = (0, 0);
+        for (config, provider, failover) in records {
+            for version in config.ip_version.unwrap_or_default().versions() {
+                let kind = RecordType::for_version(*version);
+                let mut managed = ManagedRecord::new(config.clone(), kind, provider.clone())
+                    .with_failover(&failover);
+                let previous = old.iter().position(|r| {
+                    r.config.name == config.name
+                        && r.kind == kind
+                        && r.provider.name() == provider.name()
+                });
+                match previous.map(|i| old.swap_remove(i)) {
+                    Some(previous) => {
+                        managed.zone = previous.zone;
+                        managed.current = previous.current;
+                        managed.created = previous.created;
+                        managed.looked_up = previous.looked_up;
+                        managed.written_at = previous.written_at;
+                        managed.tracked_since = previous.tracked_since;
+                        if previous.config == managed.config {
+                            managed.last_ip = previous.last_ip;
+                            managed.last_update = previous.last_update;
+                            managed.srv_synced = previous.srv_synced;
+                            managed.unhealthy_since = previous.unhealthy_since;
+                            managed.withdrawn = previous.withdrawn;
+                        }
+                        kept += 1;
+                    }
+                    None => added += 1,
+                }
+                self.records.push(managed);
+            }
+        }
+        info!(
+            "Records reloaded: {} added, {} removed, {} unchanged",
+            added,
+            old.len(),
+            kept
+        );
+
+        // Provider instances may have been replaced, so rebuild the zone cache
+        self.zones = self
+            .records
+            .iter()
+            .filter_map(|r| Some((r.provider.clone(), r.zone.clone()?)))
+            .collect();
+        self.prepared = self.records.iter().all(|r| r.current.is_some());
+    }
+
+    /// Fetch Zone ID and Record ID of every record not looked up yet
+    pub async fn prepare(&mut self) -> Result<()> {
+        // Before the cache forgets the records no longer configured
+        self.collect_garbage().await;
+        for i in 0..self.records.len() {
+            if self.records[i].current.is_some() {
+                continue;
+            }
+            let name = self.records[i].config.name.clone();
+            let provider = self.records[i].provider.clone();
+            let kind = self.records[i].kind;
+
+            if self.records[i].config.record_id.is_none()
+                && let Some((zone, record, created, written_at)) = self
+                    .cache
+                    .as_ref()
+                    .and_then(|cache| cache.get(provider.name(), &name, kind))
+            {
+                info!(
+                    "Using cached Zone ID {} and Record ID {} for: {} ({})",
+                    zone.id,
+                    record.id.as_deref().unwrap_or("-"),
+                    name,
+                    kind
+                );
+                if self.known_zone(&provider, &name).is_none() {
+                    self.zones.push((provider.clone(), zone.clone()));
+                }
+                self.claim(i, &zone).await?;
+                let managed = &mut self.records[i];
+                managed.zone = Some(zone);
+                managed.last_ip = record.content.parse().ok();
+                managed.current = Some(record);
+                managed.created = created;
+                managed.written_at = written_at;
+                managed.looked_up = true;
+                continue;
+            }
+
+            let config = &self.records[i].config;
+            let zone = match (&config.zone_id, self.known_zone(&provider, &name)) {
+                // The zone can't be looked up, so its name is the configured
+                // one or assumed to be the root domain
+                (Some(id), _) => Zone {
+                    id: id.clone(),
+                    name: config.zone_name(),
+                },
+                (None, Some(zone)) => zone,
+                (None, None) => {
+                    info!("Fetching Zone ID for: {} ({})", name, provider.name());
+                    let lookup = config.zone_lookup_name();
+                    let zone = retry::retry(
+                        &self.retry.policy_for(provider.name()),
+                        &format!("Looking up the zone of {}", name),
+                        || provider.lookup_zone(&lookup),
+                    )
+                    .await?;
+                    info!("Zone ID: {}", zone.id);
+                    self.zones.push((provider.clone(), zone.clone()));
+                    zone
+                }
+            };
+
+            let config = &self.records[i].config;
+            let mut missing = false;
+            let record = match &config.record_id {
+                // Its current content is unknown, so the first sync writes it
+                Some(id) => Record {
+                    id: Some(id.clone()),
+                    ..new_record(config, kind)
+                },
+                // Which address of the set is ours isn't known, so the first
+                // sync adds it without removing any other
+                None if config.round_robin => new_record(config, kind),
+                None => {
+                    info!("Fetching Record ID for: {} ({})", name, kind);
+                    let record = retry::retry(
+                        &self.retry.policy_for(provider.name()),
+                        &format!("Looking up {} ({})", name, kind),
+                        || provider.read_record(&zone, &name, kind),
+                    )
+                    .await?;
+                    match record {
+                        Some(record) => record,
+                        None => {
+                            missing = true;
+                            missing_record(config, kind, provider.name())?
+                        }
+                    }
+                }
+            };
+            info!("Record ID: {}", record.id.as_deref().unwrap_or("-"));
+
+            self.claim(i, &zone).await?;
+            let managed = &mut self.records[i];
+            managed.zone = Some(zone);
+            // A record already pointing at the address isn't written again
+            managed.last_ip = record.content.parse().ok();
+            managed.current = Some(record);
+            // Creating the record on the first update, not recreating it
+            managed.created |= missing;
+            managed.looked_up = true;
+        }
+
+        self.save_cache();
+        self.prepared = true;
+        Ok(())
+    }
+
+    /// With garbage collection, delete the records the cache remembers the
+    /// agent created that are no longer configured. Records marked by
+    /// another owner since are left alone, records whose deletion fails are
+    /// kept in the cache to try again.
+    async fn collect_garbage(&mut self) {
+        let Some(cache) = self.cache.as_ref().filter(|_| self.garbage_collect) else {
+            return;
+        };
+        let configured = |provider: &str, record: &Record| {
+            self.records.iter().any(|r| {
+                r.provider.name() == provider
+                    && r.config.name == record.name
+                    && r.kind == record.kind
+            })
+        };
+        let removed: Vec<_> = cache
+            .created()
+            .into_iter()
+            .filter(|(provider, _, record)| !configured(provider, record))
+            .collect();
+
+        let mut failed = Vec::new();
+        for (name, zone, record) in removed {
+            let provider = self
+                .records
+                .iter()
+                .flat_map(|r| std::iter::once(r).chain(&r.failover))
+                .map(|r| r.provider.clone())
+                .find(|p| p.name() == name);
+            let Some(provider) = provider else {
+                warn!(
+                    "Not deleting {} {} removed from the config: {} is no longer configured",
+                    record.name, record.kind, name
+                );
+                continue;
+            };
+            if let Err(e) = self.delete_removed(provider.as_ref(), &zone, &record).await {
+                error!(
+                    "Failed to delete {} {} at {}: {}",
+                    record.name, record.kind, name, e
+                );
+                failed.push((name, zone, record));
+            }
+        }
+        self.garbage = failed;
+    }
+
+    /// Delete `record`, created by the agent and removed from the config,
+    /// and the marker of its name once no configured record uses it
+    async fn delete_removed(
+        &self,
+        provider: &dyn DnsProvider,
+        zone: &Zone,
+        record: &Record,
+    ) -> Result<(), ProviderError> {
+        if let Some(owner) = &self.owner
+            && let Some(other) = owner::other_owner(provider, zone, &record.name, owner).await?
+        {
+            warn!(
+                "Not deleting {} {} removed from the config: it is managed by `{}` now",
+                record.name, record.kind, other
+            );
+            return Ok(());
+        }
+        if self.dry_run {
+            info!(
+                "[dry-run] Would delete {} {} at {}, it was removed from the config",
+                record.name,
+                record.kind,
+                provider.name()
+            );
+            return Ok(());
+        }
+
+        match provider.delete_address(zone, record).await {
+            Err(e) if !e.is_not_found() => return Err(e),
+            _ => info!(
+                "DNS record deleted: {} {} at {}, it was removed from the config",
+                record.name,
+                record.kind,
+                provider.name()
+            ),
+        }
+        // The A and AAAA records of a name share their marker
+        if let Some(owner) = &self.owner
+            && !self
+                .records
+                .iter()
+                .any(|r| r.provider.name() == provider.name() && r.config.name == record.name)
+        {
+            owner::release(provider, zone, &record.name, owner).await?;
+        }
+        Ok(())
+    }
+
+    /// Mark the `i`th record as ours, if the agent has an owner ID. The A
+    /// and AAAA records of a name share their marker.
+    async fn claim(&self, i: usize, zone: &Zone) -> Result<()> {
+        let Some(owner) = &self.owner else {
+            return Ok(());
+        };
+        let managed = &self.records[i];
+        let shared = self.records[..i].iter().any(|r| {
+            r.config.name == managed.config.name && r.provider.name() == managed.provider.name()
+        });
+        if !shared {
+            owner::claim(
+                &*managed.provider,
+                zone,
+                &managed.config.name,
+                owner,
+                self.takeover,
+                self.dry_run,
+            )
+            .await?;
+        }
+        Ok(())
+    }
+
+    /// Write the zones and records looked up so far to the ID cache, if any
+    fn save_cache(&mut self) {
+        let Some(cache) = &mut self.cache else {
+            return;
+        };
+        if self.dry_run {
+            return;
+        }
+        // Records that can't be read have nothing worth remembering
+        let records = self.records.iter().filter_map(|r| {
+            let current = r.current.as_ref().filter(|c| !c.content.is_empty())?;
+            Some((
+                r.provider.name(),
+                r.zone.as_ref()?,
+                current,
+                r.created,
+                r.written_at,
+            ))
+        });
+        // Garbage that couldn't be deleted yet, to try again
+        let garbage = self
+            .garbage
+            .iter()
+            .map(|(provider, zone, record)| (provider.as_str(), zone, record, true, None));
+        if let Err(e) = cache.store(records.chain(garbage)) {
+            warn!("Could not write the ID cache: {}", e);
+        }
+    }
+
+    /// Address families used by at least one managed record
+    pub fn versions(&self) -> Vec<IpVersion> {
+        [IpVersion::V4, IpVersion::V6]
+            .into_iter()
+            .filter(|v| {
+                self.records
+                    .iter()
+                    .any(|r| r.kind == RecordType::for_version(*v))
+            })
+            .collect()
+    }
+
+    /// Detect the current public IP of the given family
+    pub async fn detect(&self, version: IpVersion) -> Result<IpAddr, IpDetectionError> {
+        self.detect_with(None, version).await
+    }
+
+    /// Detect the address of the given family with the named source, or
+    /// the public IP when `None`
+    async fn detect_with(
+        &self,
+        source: Option<&str>,
+        version: IpVersion,
+    ) -> Result<IpAddr, IpDetectionError> {
+        let source = match source {
+            None => &self.source,
+            Some(name) => self.named_sources.get(name).ok_or_else(|| {
+                IpDetectionError::Invalid(format!("IP source {} is not configured", name))
+            })?,
+        };
+        retry::retry(
+            &self.retry.policy(),
+            &format!("Detecting the {} address", version),
+            || source.detect(version),
+        )
+        .await?
+        .ok_or(IpDetectionError::NotFound(version))
+    }
+
+    /// Look up the records and detect the public IP, then compare every
+    /// record with what a sync would write, without writing anything. SRV
+    /// records and health checks are left out.
+    pub async fn plan(&mut self) -> Result<Vec<PlannedChange>> {
+        if !self.prepared {
+            self.prepare().await?;
+        }
+
+        let mut changes = Vec::new();
+        for version in self.versions() {
+            let kind = RecordType::for_version(version);
+            let mut detected: HashMap<Option<String>, IpAddr> = HashMap::new();
+            for managed in self.records.iter().filter(|r| r.kind == kind) {
+                let group = &managed.config.ip_source;
+                let ip = match detected.get(group) {
+                    Some(ip) => *ip,
+                    None => {
+                        let ip = self.detect_with(group.as_deref(), version).await?;
+                        detected.insert(group.clone(), ip);
+                        ip
+                    }
+                };
+                let desired = managed.config.address_for(ip);
+                let current = managed.current.as_ref().expect("agent is prepared");
+                let drift = managed.drift();
+                let action = if current.id.is_none() && current.content.is_empty() {
+                    PlannedAction::Create
+                } else if managed.last_ip != Some(desired) || drift.is_some() {
+                    PlannedAction::Update
+                } else {
+                    PlannedAction::Unchanged
+                };
+                changes.push(PlannedChange {
+                    name: managed.config.name.clone(),
+                    kind,
+                    provider: managed.provider.name().to_string(),
+                    action,
+                    current: Some(current.content.clone()).filter(|c| !c.is_empty()),
+                    desired,
+                    detected: ip,
+                    drift,
+                });
+            }
+        }
+        Ok(changes)
+    }
+
+    /// Detect the public IP once and update every record whose IP changed.
+    ///
+    /// Records are updated independently, and a failure to detect one
+    /// address family only affects records of that family; both are
+    /// reported in the returned [`SyncReport`]. An error is only returned
+    /// if the records could not be looked up in the first place.
+    pub async fn sync(&mut self) -> Result<SyncReport> {
+        self.sync_inner(false).await
+    }
+
+    /// Detect the public IP and update all records even if it is unchanged
+    pub async fn force_update(&mut self) -> Result<SyncReport> {
+        self.sync_inner(true).await
+    }
+
+    /// Write the creates and updates of `plan`, made by [`Agent::plan`], as
+    /// shown: with the addresses detected then, and without looking up the
+    /// records again. Ownership markers and garbage collection left out by
+    /// a dry-run plan are done first.
+    pub async fn apply(&mut self, plan: &[PlannedChange]) -> Result<SyncReport> {
+        if !self.prepared {
+            self.prepare().await?;
+        } else {
+            if self.garbage_collect {
+                self.collect_garbage().await;
+            }
+            for i in 0..self.records.len() {
+                if let Some(zone) = self.records[i].zone.clone() {
+                    self.claim(i, &zone).await?;
+                }
+            }
+        }
+
+        let mut report = SyncReport::default();
+        for version in self.versions() {
+            for group in self.groups(RecordType::for_version(version)) {
+                self.sync_group(version, group, Writes::Planned(plan), &mut report)
+                    .await;
+            }
+        }
+        Ok(report)
+    }
+
+    /// IP sources used by the records of type `kind`, each once
+    fn groups(&self, kind: RecordType) -> Vec<Option<String>> {
+        let mut groups: Vec<Option<String>> = Vec::new();
+        for managed in self.records.iter().filter(|r| r.kind == kind) {
+            if !groups.contains(&managed.config.ip_source) {
+                groups.push(managed.config.ip_source.clone());
+            }
+        }
+        groups
+    }
+
+    async fn sync_inner(&mut self, force: bool) -> Result<SyncReport> {
+        if !self.prepared {
+            self.prepare().await?;
+        } else if self.garbage_collect {
+            // E.g. records removed by a reload
+            self.collect_garbage().await;
+            self.save_cache();
+        }
+
+        let mut report = SyncReport::default();
+        self.check_health().await;
+        self.check_resolvers().await;
+        self.sync_srv(&mut report).await;
+        let writes = if force { Writes::All } else { Writes::Changed };
+        for version in self.versions() {
+            // Records of this family share a detection per IP source
+            for group in self.groups(RecordType::for_version(version)) {
+                self.sync_group(version, group, writes, &mut report).await;
+            }
+        }
+
+        Ok(report)
+    }
+
+    /// Run the health checks of the records, withdrawing those whose
+    /// service has been down for `fail_after_secs` and restoring those
+    /// whose service is back. Withdrawn records without a fallback address
+    /// are deleted here, the others are repointed by the sync.
+    async fn check_health(&mut self) {
+        // The records of a name usually share a check, run each one once
+        let mut checks: Vec<HealthCheckConfig> = Vec::new();
+        for check in self
+            .records
+            .iter()
+            .filter_map(|r| r.config.health_check.as_ref())
+        {
+            if !checks.contains(check) {
+                checks.push(check.clone());
+            }
+        }
+        if checks.is_empty() {
+            return;
+        }
+        let results = future::join_all(checks.iter().map(health::check)).await;
+
+        let dry_run = self.dry_run;
+        for managed in self.records.iter_mut() {
+            let Some(check) = managed.config.health_check.clone() else {
+                continue;
+            };
+            let i = checks
+                .iter()
+                .position(|c| *c == check)
+                .expect("check was run");
+            match &results[i] {
+                Ok(()) => {
+                    managed.unhealthy_since = None;
+                    if managed.withdrawn {
+                        info!(
+                            "{} ({}) is healthy again, restoring it at {}",
+                            managed.config.name,
+                            managed.kind,
+                            managed.provider.name()
+                        );
+                        managed.withdrawn = false;
+                    }
+                }
+                Err(e) => {
+                    let since = *managed.unhealthy_since.get_or_insert_with(Instant::now);
+                    if managed.withdrawn
+                        || since.elapsed() < Duration::from_secs(check.fail_after_secs)
+                    {
+                        warn!("Health check of {} failed: {}", managed.config.name, e);
+                        continue;
+                    }
+                    warn!(
+                        "Health check of {} failed for {}s, withdrawing {} at {}: {}",
+                        managed.config.name,
+                        check.fail_after_secs,
+                        managed.kind,
+                        managed.provider.name(),
+                        e
+                    );
+                    managed.withdrawn = true;
+                }
+            }
+            if managed.withdrawn && check.fallback_for(managed.kind).is_none() {
+                delete_record(managed, dry_run).await;
+                if managed.current.is_none() {
+                    // Created again once the service is back
+                    managed.current = Some(new_record(&managed.config, managed.kind));
+                }
+            }
+        }
+        self.save_cache();
+    }
+
+    /// Every `interval_secs` of the drift check, ask its resolvers for the
+    /// records and compare their answers with the addresses written. Records
+    /// resolving to other addresses, like shared, proxied and load balanced
+    /// ones, and records written more recently than their TTL are left out.
+    async fn check_resolvers(&mut self) {
+        let Some(config) = &self.drift_check else {
+            return;
+        };
+        let interval = Duration::from_secs(config.interval_secs);
+        if self
+            .last_drift_check
+            .is_some_and(|last| last.elapsed() < interval)
+        {
+            return;
+        }
+        self.last_drift_check = Some(Instant::now());
+
+        let due = |managed: &ManagedRecord| {
+            let ttl = Duration::from_secs(managed.stored_ttl().unwrap_or(DRIFT_CHECK_TTL).into());
+            managed.last_ip.is_some()
+                && managed.provider.publishes_records()
+                && !managed.config.round_robin
+                && !managed.failed_over
+                && managed.proxied() != Some(true)
+                && managed.last_update.is_none_or(|last| last.elapsed() >= ttl)
+        };
+        let checks = self.records.iter().filter(|r| due(r)).flat_map(|managed| {
+            config.resolvers.iter().map(move |resolver| async move {
+                let answer = dns::resolve(
+                    resolver,
+                    &managed.config.name,
+                    managed.kind == RecordType::A,
+                    DRIFT_CHECK_TIMEOUT,
+                )
+                .await;
+                (managed.config.name.clone(), managed.kind, resolver, answer)
+            })
+        });
+        let answers = stream::iter(checks)
+            .buffer_unordered(self.concurrency)
+            .collect::<Vec<_>>()
+            .await;
+
+        let reassert = config.action == DriftAction::Reassert;
+        for managed in self.records.iter_mut() {
+            for (_, _, resolver, answer) in answers
+                .iter()
+                .filter(|(name, kind, ..)| *name == managed.config.name && *kind == managed.kind)
+            {
+                let expected = managed.last_ip.expect("checked records were written");
+                let seen = match answer {
+                    Ok(Some(ip)) if *ip == expected => continue,
+                    Ok(Some(ip)) => ip.to_string(),
+                    Ok(None) => "nothing".to_string(),
+                    Err(e) => {
+                        warn!(
+                            "Couldn't resolve {} ({}) at {}: {}",
+                            managed.config.name, managed.kind, resolver, e
+                        );
+                        continue;
+                    }
+                };
+                warn!(
+                    "{} ({}) resolves to {} at {} instead of {}",
+                    managed.config.name, managed.kind, seen, resolver, expected
+                );
+                managed.drifted |= reassert;
+            }
+        }
+    }
+
+    /// Write the configured SRV records that are missing or point at
+    /// another port or target. They only depend on the configuration, so
+    /// each is checked once.
+    async fn sync_srv(&mut self, report: &mut SyncReport) {
+        for i in 0..self.records.len() {
+            let managed = &self.records[i];
+            // The A and AAAA records of a name share its SRV records
+            let shared = self.records[..i].iter().any(|r| {
+                r.config.name == managed.config.name && r.provider.name() == managed.provider.name()
+            });
+            if managed.srv_synced || managed.config.srv.is_empty() || shared {
+                continue;
+            }
+            let Some(zone) = &managed.zone else {
+                continue;
+            };
+
+            let mut synced = true;
+            for srv in &managed.config.srv {
+                let record = SrvRecord {
+                    name: format!("{}.{}", srv.service, managed.config.name),
+                    priority: srv.priority,
+                    weight: srv.weight,
+                    port: srv.port,
+                    target: managed.config.name.clone(),
+                    ttl: managed.config.ttl,
+                };
+                match sync_srv_record(&*managed.provider, zone, &record, self.dry_run).await {
+                    Ok(true) => report.updated += 1,
+                    Ok(false) => report.unchanged += 1,
+                    Err(e) => {
+                        error!(
+                            "Failed to update {} (SRV) at {}: {}",
+                            record.name,
+                            managed.provider.name(),
+                            e
+                        );
+                        report.failed += 1;
+                        synced = false;
+                    }
+                }
+            }
+            self.records[i].srv_synced = synced && !self.dry_run;
+        }
+    }
+
+    /// Detect the `version` address of the records using the `group` IP
+    /// source and update those `writes` selects
+    async fn sync_group(
+        &mut self,
+        version: IpVersion,
+        group: Option<String>,
+        writes: Writes<'_>,
+        report: &mut SyncReport,
+    ) {
+        let kind = RecordType::for_version(version);
+        let in_group = |r: &ManagedRecord| r.kind == kind && r.config.ip_source == group;
+        let detected = match writes {
+            Writes::Planned(plan) => {
+                let Some(change) = self
+                    .records
+                    .iter()
+                    .filter(|r| in_group(r))
+                    .find_map(|r| plan.iter().find(|c| c.is_for(r)))
+                else {
+                    return;
+                };
+                Ok(change.detected)
+            }
+            _ => self.detect_with(group.as_deref(), version).await,
+        };
+        let ip = match detected {
+            Ok(ip) => ip,
+            Err(e) => {
+                error!("{}", e);
+                report.failed += self.records.iter().filter(|r| in_group(r)).count();
+                return;
+            }
+        };
+
+        let mut pending = Vec::new();
+        let mut changed = false;
+        for managed in self.records.iter_mut().filter(|r| in_group(r)) {
+            if let Writes::Planned(plan) = writes {
+                match plan.iter().find(|c| c.is_for(managed)) {
+                    Some(change) if change.action != PlannedAction::Unchanged => {
+                        changed |= managed.last_ip != Some(change.desired);
+                        pending.push((managed, change.desired));
+                    }
+                    _ => report.unchanged += 1,
+                }
+                continue;
+            }
+            let target = match &managed.config.health_check {
+                Some(check) if managed.withdrawn => match check.fallback_for(kind) {
+                    Some(fallback) => fallback,
+                    // Deleted by the health check, unless that failed
+                    None => {
+                        let deleted = managed
+                            .current
+                            .as_ref()
+                            .is_some_and(|r| r.content.is_empty());
+                        if deleted || self.dry_run {
+                            report.unchanged += 1;
+                        } else {
+                            report.failed += 1;
+                        }
+                        continue;
+                    }
+                },
+                _ => managed.config.address_for(ip),
+            };
+            if matches!(writes, Writes::All) || managed.last_ip != Some(target) {
+                changed = true;
+                pending.push((managed, target));
+            } else if managed.drifted {
+                info!(
+                    "Rewriting {} ({}) at {} with {}",
+                    managed.config.name,
+                    kind,
+                    managed.provider.name(),
+                    target
+                );
+                managed.drifted = false;
+                pending.push((managed, target));
+            } else if let Some(drift) = managed.drift() {
+                info!(
+                    "{} ({}) at {}: {}, rewriting it",
+                    managed.config.name,
+                    kind,
+                    managed.provider.name(),
+                    drift
+                );
+                pending.push((managed, target));
+            } else if managed.needs_refresh(
+                self.force_update_interval
+                    .get(managed.provider.name())
+                    .map(|d| d.0),
+            ) {
+                info!(
+                    "Refreshing {} ({}) at {}",
+                    managed.config.name,
+                    kind,
+                    managed.provider.name()
+                );
+                pending.push((managed, target));
+            } else {
+                report.unchanged += 1;
+            }
+        }
+        if pending.is_empty() {
+            return;
+        }
+        if changed {
+            match &group {
+                None => info!("New IP: {}", ip),
+                Some(name) => info!("New IP from {}: {}", name, ip),
+            }
+        }
+
+        let dry_run = self.dry_run;
+        let backoff = &self.backoff;
+        let retry = &self.retry;
+        let started = Instant::now();
+        if !dry_run {
+            let (rest, updated) = update_in_batches(pending, backoff).await;
+            report.updated += updated;
+            pending = rest;
+        }
+        let results: Vec<bool> = stream::iter(pending)
+            .map(|(managed, target)| async move {
+                match update_with_failover(managed, target, dry_run, backoff, retry).await {
+                    Ok(()) => true,
+                    Err(e) => {
+                        error!(
+                            "{} ({}) at {}: {}",
+                            managed.config.name,
+                            kind,
+                            managed.provider.name(),
+                            e
+                        );
+                        false
+                    }
+                }
+            })
+            .buffer_unordered(self.concurrency)
+            .collect()
+            .await;
+        let updated = results.iter().filter(|ok| **ok).count();
+        report.updated += updated;
+        report.failed += results.len() - updated;
+
+        if let Some(config) = &self.verify
+            && !dry_run
+        {
+            // Shared, proxied, load balanced and failed over records answer
+            // with other addresses than the one written
+            let written: Vec<&mut ManagedRecord> = self
+                .records
+                .iter_mut()
+                .filter(|r| in_group(r) && r.last_update.is_some_and(|last| last >= started))
+                .filter(|r| r.provider.publishes_records() && !r.failed_over)
+                .filter(|r| !r.config.round_robin && r.proxied() != Some(true))
+                .collect();
+            let results: Vec<_> = stream::iter(written.iter().map(|m| verify_update(m, config)))
+                .buffered(self.concurrency)
+                .collect()
+                .await;
+            for (managed, result) in written.into_iter().zip(results) {
+                let ip = managed.last_ip.expect("record was written");
+                match result {
+                    Ok(()) => info!(
+                        "Verified {} {} -> {} at the nameservers",
+                        managed.config.name, managed.kind, ip
+                    ),
+                    Err(e) => {
+                        error!(
+                            "{} ({}) at {}: {}",
+                            managed.config.name,
+                            managed.kind,
+                            managed.provider.name(),
+                            e
+                        );
+                        // Written again on the next sync
+                        managed.last_ip = None;
+                        report.updated -= 1;
+                        report.failed += 1;
+                    }
+                }
+            }
+        }
+
+        if changed && !dry_run && self.notifier.is_enabled() {
+            let records: Vec<ChangedRecord> = self
+                .records
+                .iter()
+                .filter(|r| in_group(r) && r.last_update.is_some_and(|last| last >= started))
+                .filter_map(|r| {
+                    Some(ChangedRecord {
+                        name: r.config.name.clone(),
+                        kind: r.kind.to_string(),
+                        provider: r.provider.name().to_string(),
+                        address: r.last_ip?,
+                    })
+                })
+                .collect();
+            if !records.is_empty() {
+                let change = IpChange {
+                    ip,
+                    source: group.clone(),
+                    records,
+                };
+                self.notifier.send(&change).await;
+            }
+        }
+        self.save_cache();
+    }
+
+    /// Run [`Agent::sync`] forever, sleeping `interval` between checks, or
+    /// longer while providers rate limit the agent
+    pub async fn run(mut self, interval: Duration) -> Result<()> {
+        self.prepare().await?;
+
+        info!("Starting IP sync loop...");
+        loop {
+            self.tick().await;
+            sleep(self.next_interval(interval)).await;
+        }
+    }
+
+    /// Time to sleep before the next check instead of `interval`. Each check
+    /// during which a provider rate limited the agent doubles it, up to 8
+    /// times, and each check without halves it again. It also lasts at
+    /// least until paused providers accept requests again.
+    pub fn next_interval(&self, interval: Duration) -> Duration {
+        interval
+            .saturating_mul(self.slowdown)
+            .max(self.backoff.remaining())
+    }
+
+    /// Delete the records configured as `ephemeral`, e.g. when the agent is
+    /// stopped, logging failures instead of returning them
+    pub async fn remove_ephemeral(&mut self) {
+        let dry_run = self.dry_run;
+        for managed in self.records.iter_mut().filter(|r| r.config.ephemeral) {
+            delete_record(managed, dry_run).await;
+            // Failover providers only hold the record if they wrote it
+            for standby in managed
+                .failover
+                .iter_mut()
+                .filter(|standby| standby.last_update.is_some())
+            {
+                delete_record(standby, dry_run).await;
+            }
+        }
+        self.save_cache();
+        self.prepared = self.records.iter().all(|r| r.current.is_some());
+    }
+
+    /// Run a single [`Agent::sync`] pass, logging failures instead of
+    /// returning them
+    pub async fn tick(&mut self) {
+        match self.sync().await {
+            Ok(report) if !report.is_success() => error!(
+                "{} of {} records failed to update",
+                report.failed,
+                self.records.len()
+            ),
+            Ok(_) => {}
+            Err(e) => error!("{}", e),
+        }
+
+        if self.backoff.take_limited() {
+            if self.slowdown < MAX_SLOWDOWN {
+                self.slowdown *= 2;
+                warn!(
+                    "Rate limited by a provider, checking {}x less often",
+                    self.slowdown
+                );
+            }
+        } else if self.slowdown > 1 {
+            self.slowdown /= 2;
+            if self.slowdown == 1 {
+                info!("No longer rate limited, checking at the configured interval");
+            }
+        }
+    }
+}
+
+/// Create or correct the SRV record `record`, returning whether it had to
+/// be written
+async fn sync_srv_record(
+    provider: &dyn DnsProvider,
+    zone: &Zone,
+    record: &SrvRecord,
+    dry_run: bool,
+) -> Result<bool, ProviderError> {
+    if let Some(current) = provider.read_srv(zone, &record.name).await?
+        && current.same_target(record)
+    {
+        return Ok(false);
+    }
+    if dry_run {
+        info!(
+            "[dry-run] Would update {} (SRV) at {}: {} {} {} {}",
+            record.name,
+            provider.name(),
+            record.priority,
+            record.weight,
+            record.port,
+            record.target
+        );
+        return Ok(true);
+    }
+    provider.upsert_srv(zone, record).await?;
+    info!(
+        "{} (SRV) updated at {}: port {}, target {}",
+        record.name,
+        provider.name(),
+        record.port,
+        record.target
+    );
+    Ok(true)
+}
+
+/// Records to update paired with their new address
+type Pending<'a> = Vec<(&'a mut ManagedRecord, IpAddr)>;
+
+/// Update the existing records sharing a provider and zone with a single
+/// request where the provider supports it. Returns the records left to
+/// update one by one, including those of failed batches, and the number
+/// of records written.
+async fn update_in_batches<'a>(pending: Pending<'a>, backoff: &Backoff) -> (Pending<'a>, usize) {
+    // Shared and failed over records take their own paths
+    let (mut batchable, mut rest): (Pending, Pending) = pending.into_iter().partition(|(m, _)| {
+        !m.config.round_robin
+            && !m.failed_over
+            && m.current.as_ref().is_some_and(|r| r.id.is_some())
+    });
+    let mut updated = 0;
+    while let Some((first, _)) = batchable.first() {
+        let provider = first.provider.clone();
+        let zone = first.zone.clone().expect("agent is prepared");
+        let (batch, others): (Pending, Pending) = batchable.into_iter().partition(|(m, _)| {
+            same_provider(&m.provider, &provider)
+                && m.zone.as_ref().is_some_and(|z| z.id == zone.id)
+        });
+        batchable = others;
+        if batch.len() < 2 {
+            rest.extend(batch);
+            continue;
+        }
+
+        let records: Vec<Record> = batch.iter().map(|(m, ip)| m.desired(*ip)).collect();
+        backoff.wait(provider.name()).await;
+        match provider.update_records(&zone, &records).await {
+            Ok(stored) => {
+                info!(
+                    "Updated {} records of {} at {} in one batch",
+                    stored.len(),
+                    zone.name,
+                    provider.name()
+                );
+                for ((managed, ip), record) in batch.into_iter().zip(stored) {
+                    info!(
+                        "DNS updated: {} {} -> {} at {}",
+                        managed.config.name,
+                        managed.kind,
+                        ip,
+                        provider.name()
+                    );
+                    managed.current = Some(record);
+                    managed.last_ip = Some(ip);
+                    managed.mark_written();
+                    updated += 1;
+                }
+            }
+            Err(ProviderError::Unsupported { .. }) => rest.extend(batch),
+            Err(e) => {
+                warn!(
+                    "Batch update of {} records at {} failed, updating them one by one: {}",
+                    batch.len(),
+                    provider.name(),
+                    e
+                );
+                if let ProviderError::RateLimited { retry_after } = e {
+                    let delay = retry_after
+                        .unwrap_or(DEFAULT_RETRY_AFTER)
+                        .min(MAX_RETRY_AFTER);
+                    backoff.pause(provider.name(), delay);
+                }
+                rest.extend(batch);
+            }
+        }
+    }
+    (rest, updated)
+}
+
+/// [`update_with_backoff`], falling back to the failover providers in turn
+/// while the record's provider is unavailable.
+///
+/// The record's own `last_ip` is only set by its provider, so it is retried
+/// on every sync until it has caught up.
+async fn update_with_failover(
+    managed: &mut ManagedRecord,
+    ip: IpAddr,
+    dry_run: bool,
+    backoff: &Backoff,
+    retry: &RetryConfig,
+) -> Result<(), ProviderError> {
+    let err = match update_with_backoff(managed, ip, dry_run, backoff, retry).await {
+        Ok(()) => {
+            if managed.failed_over {
+                info!(
+                    "{} ({}) is back at {}",
+                    managed.config.name,
+                    managed.kind,
+                    managed.provider.name()
+                );
+                managed.failed_over = false;
+            }
+            return Ok(());
+        }
+        Err(e) if e.is_outage() && !managed.failover.is_empty() => e,
+        Err(e) => return Err(e),
+    };
+
+    warn!(
+        "{} ({}) at {}: {}, failing over",
+        managed.config.name,
+        managed.kind,
+        managed.provider.name(),
+        err
+    );
+    for standby in managed.failover.iter_mut() {
+        if standby.last_ip == Some(ip) {
+            managed.failed_over = true;
+            return Ok(());
+        }
+        let result = match discover(standby, retry).await {
+            Ok(()) if standby.last_ip == Some(ip) => Ok(()),
+            Ok(()) => update_with_backoff(standby, ip, dry_run, backoff, retry).await,
+            Err(e) => Err(e),
+        };
+        match result {
+            Ok(()) => {
+                warn!(
+                    "{} ({}) published at {} until {} recovers",
+                    managed.config.name,
+                    managed.kind,
+                    standby.provider.name(),
+                    managed.provider.name()
+                );
+                managed.failed_over = true;
+                return Ok(());
+            }
+            Err(e) => warn!(
+                "{} ({}) at {}: {}",
+                managed.config.name,
+                managed.kind,
+                standby.provider.name(),
+                e
+            ),
+        }
+    }
+    Err(err)
+}
+
+/// Wait until the authoritative nameservers of its zone and the resolvers
+/// of `config` answer with the address just written to `managed`
+async fn verify_update(managed: &ManagedRecord, config: &VerifyConfig) -> Result<(), String> {
+    let ip = managed.last_ip.expect("record was written");
+    let zone = managed.zone.as_ref().expect("agent is prepared");
+    let resolver = config
+        .resolvers
+        .first()
+        .map_or(NAMESERVER_RESOLVER, String::as_str);
+    let mut servers = dns::nameservers(resolver, &zone.name, VERIFY_QUERY_TIMEOUT)
+        .await
+        .map_err(|e| format!("looking up the nameservers of {}: {}", zone.name, e))?;
+    servers.extend(config.resolvers.iter().cloned());
+    servers.sort();
+    servers.dedup();
+
+    let deadline = Instant::now() + Duration::from_secs(config.timeout_secs);
+    loop {
+        let answers = future::join_all(servers.iter().map(|server| {
+            dns::resolve(
+                server,
+                &managed.config.name,
+                managed.kind == RecordType::A,
+                VERIFY_QUERY_TIMEOUT,
+            )
+        }))
+        .await;
+        servers = servers
+            .into_iter()
+            .zip(answers)
+            .filter(|(_, answer)| !matches!(answer, Ok(Some(seen)) if *seen == ip))
+            .map(|(server, _)| server)
+            .collect();
+        if servers.is_empty() {
+            return Ok(());
+        }
+        if Instant::now() + VERIFY_INTERVAL > deadline {
+            return Err(format!(
+                "{} not visible at {} after {}s",
+                ip,
+                servers.join(", "),
+                config.timeout_secs
+            ));
+        }
+        sleep(VERIFY_INTERVAL).await;
+    }
+}
+
+/// Look up the zone and record of a failover target on first use, or of a
+/// record whose IDs went stale
+async fn discover(managed: &mut ManagedRecord, retry: &RetryConfig) -> Result<(), ProviderError> {
+    if managed.current.is_some() {
+        return Ok(());
+    }
+    let name = &managed.config.name;
+    let provider = &managed.provider;
+    let policy = retry.policy_for(provider.name());
+    let zone = match &managed.zone {
+        Some(zone) => zone.clone(),
+        None => {
+            let lookup = managed.config.zone_lookup_name();
+            retry::retry(&policy, &format!("Looking up the zone of {}", name), || {
+                provider.lookup_zone(&lookup)
+            })
+            .await?
+        }
+    };
+    let mut missing = false;
+    // Which address of a shared record is ours isn't known after a restart
+    let record = if managed.config.round_robin {
+        new_record(&managed.config, managed.kind)
+    } else {
+        let record = retry::retry(
+            &policy,
+            &format!("Looking up {} ({})", name, managed.kind),
+            || provider.read_record(&zone, name, managed.kind),
+        )
+        .await?;
+        match record {
+            Some(record) => record,
+            None => {
+                missing = true;
+                missing_record(&managed.config, managed.kind, managed.provider.name())?
+            }
+        }
+    };
+    managed.zone = Some(zone);
+    managed.last_ip = record.content.parse().ok();
+    managed.current = Some(record);
+    // A record deleted since it was looked up is recreated, not created
+    managed.created |= missing && !managed.looked_up;
+    managed.looked_up = true;
+    Ok(())
+}
+
+/// Delete the record of `managed` at its provider, if it was found or
+/// written, logging failures
+async fn delete_record(managed: &mut ManagedRecord, dry_run: bool) {
+    let (Some(zone), Some(record)) = (&managed.zone, &managed.current) else {
+        return;
+    };
+    if record.content.is_empty() {
+        return;
+    }
+    let provider = managed.provider.name();
+    if dry_run {
+        info!(
+            "[dry-run] Would delete {} {} at {}",
+            record.name, record.kind, provider
+        );
+        return;
+    }
+    match managed.provider.delete_address(zone, record).await {
+        Ok(()) => {
+            info!(
+                "DNS record deleted: {} {} at {}",
+                record.name, record.kind, provider
+            );
+            managed.current = None;
+            managed.last_ip = None;
+        }
+        Err(e) => error!(
+            "Failed to delete {} {} at {}: {}",
+            record.name, record.kind, provider, e
+        ),
+    }
+}
+
+/// Record to start from when `config` doesn't exist at the provider yet: a
+/// new one, created by the first update, if the record may be created
+fn missing_record(
+    config: &RecordConfig,
+    kind: RecordType,
+    provider: &str,
+) -> Result<Record, ProviderError> {
+    if !config.create_missing && !config.ephemeral {
+        return Err(ProviderError::RecordNotFound {
+            name: config.name.clone(),
+            kind,
+        });
+    }
+    info!(
+        "{} ({}) doesn't exist at {}, creating it with the first update",
+        config.name, kind, provider
+    );
+    Ok(new_record(config, kind))
+}
+
+/// Record of `config` yet to be written, with unknown ID and content
+fn new_record(config: &RecordConfig, kind: RecordType) -> Record {
+    Record {
+        id: None,
+        name: config.name.clone(),
+        kind,
+        content: String::new(),
+        ttl: config.ttl,
+        proxied: config.proxied,
+    }
+}
+
+/// [`update_record`], retrying transient failures with the retry policy of
+/// the provider. A rate limited provider is paused for as long as it asks,
+/// for all records.
+async fn update_with_backoff(
+    managed: &mut ManagedRecord,
+    ip: IpAddr,
+    dry_run: bool,
+    backoff: &Backoff,
+    retry: &RetryConfig,
+) -> Result<(), ProviderError> {
+    let provider = managed.provider.clone();
+    let policy = retry.policy_for(provider.name());
+    let started = Instant::now();
+    let mut attempt = 1;
+    let mut rediscovered = false;
+    loop {
+        backoff.wait(provider.name()).await;
+        match update_record(managed, ip, dry_run).await {
+            // E.g. deleted and recreated in the dashboard under a new ID
+            Err(e) if e.is_not_found() && !rediscovered => {
+                warn!(
+                    "{} ({}) at {}: {}, looking it up again",
+                    managed.config.name,
+                    managed.kind,
+                    provider.name(),
+                    e
+                );
+                if matches!(e, ProviderError::ZoneNotFound(_)) && managed.config.zone_id.is_none() {
+                    managed.zone = None;
+                }
+                managed.current = None;
+                discover(managed, retry).await?;
+                rediscovered = true;
+                if managed.last_ip == Some(ip) {
+                    return Ok(());
+                }
+            }
+            Err(e) if e.is_transient() => {
+                let Some(delay) = policy.delay_for(attempt, started, e.retry_after()) else {
+                    return Err(e);
+                };
+                warn!(
+                    "{} ({}) at {}: {}, retrying in {:.1}s",
+                    managed.config.name,
+                    managed.kind,
+                    provider.name(),
+                    e,
+                    delay.as_secs_f64()
+                );
+                if matches!(e, ProviderError::RateLimited { .. }) {
+                    backoff.pause(provider.name(), delay);
+                } else {
+                    sleep(delay).await;
+                }
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+async fn update_record(
+    managed: &mut ManagedRecord,
+    ip: IpAddr,
+    dry_run: bool,
+) -> Result<(), ProviderError> {
+    if managed.config.round_robin {
+        return update_member(managed, ip, dry_run).await;
+    }
+    let zone = managed.zone.as_ref().expect("agent is prepared");
+    let current = managed.current.clone().expect("agent is prepared");
+    let desired = managed.desired(ip);
+
+    if dry_run {
+        if current.id.is_none() && current.content.is_empty() {
+            info!(
+                "[dry-run] Would create {} {} at {}: {}, ttl {}, proxied {}",
+                managed.config.name,
+                managed.kind,
+                managed.provider.name(),
+                desired.content,
+                desired.ttl,
+                desired.proxied.unwrap_or(false)
+            );
+        } else {
+            info!(
+                "[dry-run] Would update {} {} at {}: {} -> {}, ttl {} -> {}, proxied {} -> {}",
+                managed.config.name,
+                managed.kind,
+                managed.provider.name(),
+                current.content,
+                desired.content,
+                current.ttl,
+                desired.ttl,
+                current.proxied.unwrap_or(false),
+                desired.proxied.unwrap_or(false)
+            );
+        }
+        managed.last_ip = Some(ip);
+        managed.mark_written();
+        return Ok(());
+    }
+
+    let updated = managed.provider.upsert_record(zone, &desired).await?;
+    info!(
+        "DNS updated: {} {} -> {} at {}",
+        managed.config.name,
+        managed.kind,
+        ip,
+        managed.provider.name()
+    );
+
+    managed.current = Some(updated);
+    managed.last_ip = Some(ip);
+    managed.mark_written();
+    Ok(())
+}
+
+/// Add `ip` to the record set of a shared record and remove the address
+/// published before, leaving the addresses of other agents alone
+async fn update_member(
+    managed: &mut ManagedRecord,
+    ip: IpAddr,
+    dry_run: bool,
+) -> Result<(), ProviderError> {
+    let zone = managed.zone.as_ref().expect("agent is prepared");
+    let current = managed.current.clone().expect("agent is prepared");
+    let provider = managed.provider.clone();
+    let members = provider
+        .read_record_set(zone, &managed.config.name, managed.kind)
+        .await?;
+    let is = |record: &Record, ip: IpAddr| record.content.parse::<IpAddr>().ok() == Some(ip);
+    let present = members.iter().find(|r| is(r, ip)).cloned();
+    let previous = current
+        .content
+        .parse::<IpAddr>()
+        .ok()
+        .filter(|previous| *previous != ip)
+        .and_then(|previous| members.iter().find(|r| is(r, previous)));
+
+    if dry_run {
+        if present.is_none() {
+            info!(
+                "[dry-run] Would add {} to {} {} at {}",
+                ip,
+                managed.config.name,
+                managed.kind,
+                provider.name()
+            );
+        }
+        if let Some(previous) = previous {
+            info!(
+                "[dry-run] Would remove {} from {} {} at {}",
+                previous.content,
+                managed.config.name,
+                managed.kind,
+                provider.name()
+            );
+        }
+        managed.last_ip = Some(ip);
+        managed.mark_written();
+        return Ok(());
+    }
+
+    let mine = match present {
+        Some(record) => record,
+        None => {
+            let record = Record {
+                content: ip.to_string(),
+                proxied: managed.proxied(),
+                ..new_record(&managed.config, managed.kind)
+            };
+            let added = provider.add_address(zone, &record).await?;
+            info!(
+                "DNS updated: {} {} + {} at {}",
+                managed.config.name,
+                managed.kind,
+                ip,
+                provider.name()
+            );
+            managed.created = true;
+            added
+        }
+    };
+    if let Some(previous) = previous {
+        provider.delete_address(zone, previous).await?;
+        info!(
+            "DNS updated: {} {} - {} at {}",
+            managed.config.name,
+            managed.kind,
+            previous.content,
+            provider.name()
+        );
+    }
+
+    managed.current = Some(mine);
+    managed.last_ip = Some(ip);
+    managed.mark_written();
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::Error;
+    use async_trait::async_trait;
+    use std::net::Ipv4Addr;
+    use std::path::{Path, PathBuf};
+    use std::sync::atomic::AtomicUsize;
+    use tokio::net::{TcpListener, UdpSocket};
+
+    const NAME: &str = "home.example.com";
+    const OTHER_NAME: &str = "nas.example.com";
+    const OLD_IP: &str = "192.0.2.1";
+    const NEW_IP: &str = "192.0.2.2";
+    /// Address published by another agent, or detected after a plan
+    const OTHER_IP: &str = "192.0.2.3";
+
+    fn ip(s: &str) -> IpAddr {
+        s.parse().unwrap()
+    }
+
+    fn zone() -> Zone {
+        Zone {
+            id: "zone-1".to_string(),
+            name: "example.com".to_string(),
+        }
+    }
+
+    /// Source answering with a fixed address
+    struct Fixed(IpAddr);
+
+    #[async_trait]
+    impl IpSource for Fixed {
+        async fn detect(&self, _: IpVersion) -> Result<Option<IpAddr>, IpDetectionError> {
+            Ok(Some(self.0))
+        }
+    }
+
+    /// Error constructor an operation set to fail returns, given a message
+    type Failure = fn(String) -> ProviderError;
+
+    /// Provider keeping the records of `zone()` in memory and logging the
+    /// requests it gets. Operations set to fail with [`Mock::fail`] return
+    /// that error.
+    struct Mock {
+        name: &'static str,
+        publishes: bool,
+        batches: bool,
+        records: Mutex<Vec<Record>>,
+        txt: Mutex<Vec<(String, String)>>,
+        calls: Mutex<Vec<String>>,
+        failing: Mutex<HashMap<&'static str, Failure>>,
+        next_id: AtomicUsize,
+    }
+
+    impl Mock {
+        fn new(name: &'static str) -> Self {
+            Self {
+                name,
+                publishes: true,
+                batches: false,
+                records: Mutex::new(Vec::new()),
+                txt: Mutex::new(Vec::new()),
+                calls: Mutex::new(Vec::new()),
+                failing: Mutex::new(HashMap::new()),
+                next_id: AtomicUsize::new(1),
+            }
+        }
+
+        /// Hold an A record of `name` pointing at `ip`
+        fn serve(&self, name: &str, ip: &str) {
+            self.store(&Record {
+                id: None,
+                name: name.to_string(),
+                kind: RecordType::A,
+                content: ip.to_string(),
+                ttl: 1,
+                proxied: None,
+            });
+        }
+
+        /// Fail `op` with `error` from now on
+        fn fail(&self, op: &'static str, error: Failure) {
+            self.failing.lock().unwrap().insert(op, error);
+        }
+
+        fn recover(&self, op: &'static str) {
+            self.failing.lock().unwrap().remove(op);
+        }
+
+        /// Addresses of the A records of `name`
+        fn addresses(&self, name: &str) -> Vec<String> {
+            let records = self.records.lock().unwrap();
+            records
+                .iter()
+                .filter(|r| r.name == name)
+                .map(|r| r.content.clone())
+                .collect()
+        }
+
+        fn calls(&self) -> Vec<String> {
+            self.calls.lock().unwrap().clone()
+        }
+
+        /// Whether any request but a lookup was sent
+        fn wrote(&self) -> bool {
+            self.calls()
+                .iter()
+                .any(|c| !c.starts_with("lookup") && !c.starts_with("read"))
+        }
+
+        /// Log `op` on `target`, failing if it is set to
+        fn call(&self, op: &'static str, target: &str) -> Result<(), ProviderError> {
+            self.calls
+                .lock()
+                .unwrap()
+                .push(format!("{} {}", op, target));
+            match self.failing.lock().unwrap().get(op) {
+                Some(error) => Err(error(format!("{} failed", op))),
+                None => Ok(()),
+            }
+        }
+
+        /// Write `record`, giving new ones an ID
+        fn store(&self, record: &Record) -> Record {
+            let mut stored = record.clone();
+            let id = stored.id.get_or_insert_with(|| {
+                format!("record-{}", self.next_id.fetch_add(1, Ordering::Relaxed))
+            });
+            let mut records = self.records.lock().unwrap();
+            match records.iter_mut().find(|r| r.id.as_ref() == Some(id)) {
+                Some(existing) => *existing = stored.clone(),
+                None => records.push(stored.clone()),
+            }
+            stored
+        }
+    }
+
+    #[async_trait]
+    impl DnsProvider for Mock {
+        fn name(&self) -> &str {
+            self.name
+        }
+
+        fn publishes_records(&self) -> bool {
+            self.publishes
+        }
+
+        async fn lookup_zone(&self, dns_name: &str) -> Result<Zone, ProviderError> {
+            self.call("lookup_zone", dns_name)?;
+            Ok(zone())
+        }
+
+        async fn read_record(
+            &self,
+            _zone: &Zone,
+            dns_name: &str,
+            kind: RecordType,
+        ) -> Result<Option<Record>, ProviderError> {
+            self.call("read_record", dns_name)?;
+            let records = self.records.lock().unwrap();
+            Ok(records
+                .iter()
+                .find(|r| r.name == dns_name && r.kind == kind)
+                .cloned())
+        }
+
+        async fn upsert_record(
+            &self,
+            _zone: &Zone,
+            record: &Record,
+        ) -> Result<Record, ProviderError> {
+            self.call(
+                "upsert_record",
+                &format!("{} {}", record.name, record.content),
+            )?;
+            Ok(self.store(record))
+        }
+
+        async fn update_records(
+            &self,
+            _zone: &Zone,
+            records: &[Record],
+        ) -> Result<Vec<Record>, ProviderError> {
+            if !self.batches {
+                return Err(ProviderError::Unsupported {
+                    provider: self.name.to_string(),
+                    action: "update records in batches",
+                });
+            }
+            self.call("update_records", &records.len().to_string())?;
+            Ok(records.iter().map(|r| self.store(r)).collect())
+        }
+
+        async fn read_record_set(
+            &self,
+            _zone: &Zone,
+            dns_name: &str,
+            kind: RecordType,
+        ) -> Result<Vec<Record>, ProviderError> {
+            self.call("read_record_set", dns_name)?;
+            let records = self.records.lock().unwrap();
+            Ok(records
+                .iter()
+                .filter(|r| r.name == dns_name && r.kind == kind)
+                .cloned()
+                .collect())
+        }
+
+        async fn add_address(
+            &self,
+            _zone: &Zone,
+            record: &Record,
+        ) -> Result<Record, ProviderError> {
+            self.call("add_address", &record.content)?;
+            Ok(self.store(&Record {
+                id: None,
+                ..record.clone()
+            }))
+        }
+
+        async fn delete_address(&self, _zone: &Zone, record: &Record) -> Result<(), ProviderError> {
+            self.call("delete_address", &record.content)?;
+            self.records.lock().unwrap().retain(|r| {
+                r.name != record.name || r.kind != record.kind || r.content != record.content
+            });
+            Ok(())
+        }
+
+        async fn read_txt(
+            &self,
+            _zone: &Zone,
+            dns_name: &str,
+        ) -> Result<Vec<String>, ProviderError> {
+            self.call("read_txt", dns_name)?;
+            let txt = self.txt.lock().unwrap();
+            Ok(txt
+                .iter()
+                .filter(|(name, _)| name == dns_name)
+                .map(|(_, value)| value.clone())
+                .collect())
+        }
+
+        async fn add_txt(
+            &self,
+            _zone: &Zone,
+            dns_name: &str,
+            value: &str,
+        ) -> Result<(), ProviderError> {
+            self.call("add_txt", dns_name)?;
+            let mut txt = self.txt.lock().unwrap();
+            txt.push((dns_name.to_string(), value.to_string()));
+            Ok(())
+        }
+
+        async fn delete_txt(
+            &self,
+            _zone: &Zone,
+            dns_name: &str,
+            value: &str,
+        ) -> Result<(), ProviderError> {
+            self.call("delete_txt", dns_name)?;
+            let mut txt = self.txt.lock().unwrap();
+            txt.retain(|(name, v)| name != dns_name || v != value);
+            Ok(())
+        }
+    }
+
+    fn provider(mock: &Arc<Mock>) -> Arc<dyn DnsProvider> {
+        mock.clone()
+    }
+
+    /// Agent detecting `detected`, reporting failures at once instead of
+    /// retrying them
+    fn agent(detected: &str) -> Agent {
+        Agent::new(Arc::new(Fixed(ip(detected)))).with_retry(RetryConfig {
+            max_attempts: 1,
+            ..RetryConfig::default()
+        })
+    }
+
+    /// Nameserver answering A queries with `ip` and other queries without
+    /// records, counting the queries it gets
+    async fn nameserver(ip: Ipv4Addr) -> (String, Arc<AtomicUsize>) {
+        let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let addr = socket.local_addr().unwrap().to_string();
+        let queries = Arc::new(AtomicUsize::new(0));
+        let counted = queries.clone();
+        tokio::spawn(async move {
+            let mut buf = [0; 512];
+            while let Ok((len, peer)) = socket.recv_from(&mut buf).await {
+                counted.fetch_add(1, Ordering::Relaxed);
+                let request = &buf[..len];
+                let is_a = request[len - 4..len - 2] == [0, 1];
+                let mut response = request[..2].to_vec();
+                response.extend([0x81, 0x80, 0, 1, 0, u8::from(is_a), 0, 0, 0, 0]);
+                response.extend(&request[12..]);
+                if is_a {
+                    // Pointer to the question name, TTL 60
+                    response.extend([0xC0, 12, 0, 1, 0, 1, 0, 0, 0, 60, 0, 4]);
+                    response.extend(ip.octets());
+                }
+                socket.send_to(&response, peer).await.unwrap();
+            }
+        });
+        (addr, queries)
+    }
+
+    /// Zones `record` is looked up by when the agent is prepared
+    async fn zone_lookups(record: RecordConfig) -> Vec<String> {
+        let mock = Arc::new(Mock::new("mock"));
+        mock.serve(NAME, OLD_IP);
+        let mut agent = agent(NEW_IP).with_record(record, provider(&mock));
+        agent.prepare().await.unwrap();
+        mock.calls()
+            .into_iter()
+            .filter_map(|c| c.strip_prefix("lookup_zone ").map(str::to_string))
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn configured_zone_is_looked_up_by_its_name() {
+        let record = RecordConfig {
+            zone: Some("Example.com.".to_string()),
+            ..RecordConfig::new(NAME)
+        };
+        assert_eq!(zone_lookups(record).await, ["example.com"]);
+        // Otherwise the provider finds the zone of the name
+        assert_eq!(zone_lookups(RecordConfig::new(NAME)).await, [NAME]);
+    }
+
+    #[tokio::test]
+    async fn failover_publishes_at_the_first_working_standby() {
+        let primary = Arc::new(Mock::new("primary"));
+        let down = Arc::new(Mock::new("down"));
+        let standby = Arc::new(Mock::new("standby"));
+        let spare = Arc::new(Mock::new("spare"));
+        for mock in [&primary, &down, &standby, &spare] {
+            mock.serve(NAME, OLD_IP);
+        }
+        primary.fail("upsert_record", ProviderError::Unavailable);
+        down.fail("upsert_record", ProviderError::Unavailable);
+
+        let mut agent = agent(NEW_IP);
+        agent.add_record_with_failover(
+            RecordConfig::new(NAME),
+            provider(&primary),
+            vec![provider(&down), provider(&standby), provider(&spare)],
+        );
+        let report = agent.sync().await.unwrap();
+        assert_eq!(report.updated, 1);
+        assert_eq!(standby.addresses(NAME), [NEW_IP]);
+        assert_eq!(down.addresses(NAME), [OLD_IP]);
+        assert_eq!(primary.addresses(NAME), [OLD_IP]);
+        // The chain stops at the standby taking over
+        assert!(spare.calls().is_empty());
+        assert!(agent.records()[0].failed_over);
+    }
+
+    #[tokio::test]
+    async fn refused_update_doesnt_fail_over() {
+        let primary = Arc::new(Mock::new("primary"));
+        let standby = Arc::new(Mock::new("standby"));
+        primary.serve(NAME, OLD_IP);
+        primary.fail("upsert_record", ProviderError::Api);
+
+        let mut agent = agent(NEW_IP);
+        agent.add_record_with_failover(
+            RecordConfig::new(NAME),
+            provider(&primary),
+            vec![provider(&standby)],
+        );
+        let report = agent.sync().await.unwrap();
+        assert_eq!(report.failed, 1);
+        assert!(standby.calls().is_empty());
+        assert!(!agent.records()[0].failed_over);
+    }
+
+    #[tokio::test]
+    async fn verified_update_counts_once_visible() {
+        let (server, _) = nameserver(NEW_IP.parse().unwrap()).await;
+        let mock = Arc::new(Mock::new("mock"));
+        mock.serve(NAME, OLD_IP);
+
+        let mut agent = agent(NEW_IP).with_verify(Some(VerifyConfig {
+            resolvers: vec![server],
+            timeout_secs: 0,
+        }));
+        agent.add_record(RecordConfig::new(NAME), provider(&mock));
+        let report = agent.sync().await.unwrap();
+        assert_eq!(report.updated, 1);
+        assert_eq!(agent.records()[0].last_ip, Some(ip(NEW_IP)));
+    }
+
+    #[tokio::test]
+    async fn update_not_visible_in_time_fails() {
+        let (server, _) = nameserver(OLD_IP.parse().unwrap()).await;
+        let mock = Arc::new(Mock::new("mock"));
+        mock.serve(NAME, OLD_IP);
+
+        let mut agent = agent(NEW_IP).with_verify(Some(VerifyConfig {
+            resolvers: vec![server],
+            timeout_secs: 0,
+        }));
+        agent.add_record(RecordConfig::new(NAME), provider(&mock));
+        let report = agent.sync().await.unwrap();
+        assert_eq!((report.updated, report.failed), (0, 1));
+        // Written again on the next sync
+        assert_eq!(agent.records()[0].last_ip, None);
+    }
+
+    fn reassert(resolver: String) -> Option<DriftCheckConfig> {
+        Some(DriftCheckConfig {
+            resolvers: vec![resolver],
+            interval_secs: 3600,
+            action: DriftAction::Reassert,
+        })
+    }
+
+    #[tokio::test]
+    async fn drift_check_rewrites_records_resolving_elsewhere() {
+        let (server, queries) = nameserver(OLD_IP.parse().unwrap()).await;
+        let mock = Arc::new(Mock::new("mock"));
+        mock.serve(NAME, NEW_IP);
+
+        let mut agent = agent(NEW_IP).with_drift_check(reassert(server));
+        agent.add_record(RecordConfig::new(NAME), provider(&mock));
+        let report = agent.sync().await.unwrap();
+        assert_eq!(report.updated, 1);
+        assert_eq!(queries.load(Ordering::Relaxed), 1);
+        assert!(
+            mock.calls()
+                .contains(&format!("upsert_record {} {}", NAME, NEW_IP))
+        );
+    }
+
+    #[tokio::test]
+    async fn drift_check_leaves_load_balancer_origins_out() {
+        let (server, queries) = nameserver(OLD_IP.parse().unwrap()).await;
+        let pool = Arc::new(Mock {
+            publishes: false,
+            ..Mock::new("pool")
+        });
+        pool.serve(NAME, NEW_IP);
+
+        let mut agent = agent(NEW_IP).with_drift_check(reassert(server));
+        agent.add_record(RecordConfig::new(NAME), provider(&pool));
+        let report = agent.sync().await.unwrap();
+        assert_eq!(report.unchanged, 1);
+        assert_eq!(queries.load(Ordering::Relaxed), 0);
+        assert!(!pool.wrote());
+    }
+
+    #[tokio::test]
+    async fn apply_writes_the_addresses_planned() {
+        let mock = Arc::new(Mock::new("mock"));
+        mock.serve(NAME, OLD_IP);
+        let mut agent = agent(NEW_IP);
+        agent.add_record(RecordConfig::new(NAME), provider(&mock));
+
+        let plan = agent.plan().await.unwrap();
+        assert_eq!(plan.len(), 1);
+        assert_eq!(plan[0].action, PlannedAction::Update);
+        assert_eq!(plan[0].current.as_deref(), Some(OLD_IP));
+        assert!(!mock.wrote());
+
+        // The address changes again before the plan is applied
+        agent.set_source(Arc::new(Fixed(ip(OTHER_IP))));
+        let report = agent.apply(&plan).await.unwrap();
+        assert_eq!(report.updated, 1);
+        assert_eq!(mock.addresses(NAME), [NEW_IP]);
+    }
+
+    #[tokio::test]
+    async fn apply_leaves_unchanged_records_alone() {
+        let mock = Arc::new(Mock::new("mock"));
+        mock.serve(NAME, NEW_IP);
+        let mut agent = agent(NEW_IP);
+        agent.add_record(RecordConfig::new(NAME), provider(&mock));
+
+        let plan = agent.plan().await.unwrap();
+        assert_eq!(plan[0].action, PlannedAction::Unchanged);
+        agent.set_source(Arc::new(Fixed(ip(OTHER_IP))));
+        let report = agent.apply(&plan).await.unwrap();
+        assert_eq!(report.unchanged, 1);
+        assert!(!mock.wrote());
+    }
+
+    #[tokio::test]
+    async fn records_of_a_zone_are_updated_in_one_batch() {
+        let mock = Arc::new(Mock {
+            batches: true,
+            ..Mock::new("mock")
+        });
+        mock.serve(NAME, OLD_IP);
+        mock.serve(OTHER_NAME, OLD_IP);
+        let mut agent = agent(NEW_IP)
+            .with_record(RecordConfig::new(NAME), provider(&mock))
+            .with_record(RecordConfig::new(OTHER_NAME), provider(&mock));
+
+        let report = agent.sync().await.unwrap();
+        assert_eq!(report.updated, 2);
+        assert_eq!(mock.addresses(NAME), [NEW_IP]);
+        assert_eq!(mock.addresses(OTHER_NAME), [NEW_IP]);
+        let calls = mock.calls();
+        assert!(calls.contains(&"update_records 2".to_string()));
+        assert!(!calls.iter().any(|c| c.starts_with("upsert_record")));
+    }
+
+    #[tokio::test]
+    async fn failed_batch_falls_back_to_single_updates() {
+        let mock = Arc::new(Mock {
+            batches: true,
+            ..Mock::new("mock")
+        });
+        mock.serve(NAME, OLD_IP);
+        mock.serve(OTHER_NAME, OLD_IP);
+        mock.fail("update_records", ProviderError::Unavailable);
+        let mut agent = agent(NEW_IP)
+            .with_record(RecordConfig::new(NAME), provider(&mock))
+            .with_record(RecordConfig::new(OTHER_NAME), provider(&mock));
+
+        let report = agent.sync().await.unwrap();
+        assert_eq!(report.updated, 2);
+        assert_eq!(mock.addresses(NAME), [NEW_IP]);
+        assert_eq!(mock.addresses(OTHER_NAME), [NEW_IP]);
+        let upserts = mock.calls();
+        let upserts = upserts.iter().filter(|c| c.starts_with("upsert_record"));
+        assert_eq!(upserts.count(), 2);
+    }
+
+    fn health_checked(tcp: String, fallback: Vec<IpAddr>) -> RecordConfig {
+        RecordConfig {
+            health_check: Some(HealthCheckConfig {
+                tcp: Some(tcp),
+                http: None,
+                command: None,
+                timeout_secs: 5,
+                fail_after_secs: 0,
+                fallback,
+            }),
+            ..RecordConfig::new(NAME)
+        }
+    }
+
+    /// Address of a port nothing listens on
+    async fn closed_port() -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        listener.local_addr().unwrap().to_string()
+    }
+
+    #[tokio::test]
+    async fn healthy_record_stays_published() {
+        let service = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let mock = Arc::new(Mock::new("mock"));
+        mock.serve(NAME, OLD_IP);
+        let mut agent = agent(NEW_IP);
+        let addr = service.local_addr().unwrap().to_string();
+        agent.add_record(health_checked(addr, Vec::new()), provider(&mock));
+
+        let report = agent.sync().await.unwrap();
+        assert_eq!(report.updated, 1);
+        assert_eq!(mock.addresses(NAME), [NEW_IP]);
+        assert!(!agent.records()[0].withdrawn);
+    }
+
+    #[tokio::test]
+    async fn failing_health_check_withdraws_the_record() {
+        let mock = Arc::new(Mock::new("mock"));
+        mock.serve(NAME, OLD_IP);
+        let mut agent = agent(NEW_IP);
+        agent.add_record(
+            health_checked(closed_port().await, Vec::new()),
+            provider(&mock),
+        );
+
+        let report = agent.sync().await.unwrap();
+        assert!(report.is_success());
+        assert!(mock.addresses(NAME).is_empty());
+        assert!(agent.records()[0].withdrawn);
+    }
+
+    #[tokio::test]
+    async fn withdrawn_record_points_at_the_fallback() {
+        let mock = Arc::new(Mock::new("mock"));
+        mock.serve(NAME, OLD_IP);
+        let mut agent = agent(NEW_IP);
+        agent.add_record(
+            health_checked(closed_port().await, vec![ip(OTHER_IP)]),
+            provider(&mock),
+        );
+
+        let report = agent.sync().await.unwrap();
+        assert_eq!(report.updated, 1);
+        assert_eq!(mock.addresses(NAME), [OTHER_IP]);
+    }
+
+    fn shared() -> RecordConfig {
+        RecordConfig {
+            round_robin: true,
+            ..RecordConfig::new(NAME)
+        }
+    }
+
+    #[tokio::test]
+    async fn shared_record_swaps_only_its_own_address() {
+        let mock = Arc::new(Mock::new("mock"));
+        mock.serve(NAME, OTHER_IP);
+        let mut agent = agent(OLD_IP);
+        agent.add_record(shared(), provider(&mock));
+        agent.sync().await.unwrap();
+        assert_eq!(mock.addresses(NAME), [OTHER_IP, OLD_IP]);
+
+        agent.set_source(Arc::new(Fixed(ip(NEW_IP))));
+        let report = agent.sync().await.unwrap();
+        assert_eq!(report.updated, 1);
+        assert_eq!(mock.addresses(NAME), [OTHER_IP, NEW_IP]);
+    }
+
+    #[tokio::test]
+    async fn failed_addition_keeps_the_published_address() {
+        let mock = Arc::new(Mock::new("mock"));
+        mock.serve(NAME, OTHER_IP);
+        let mut agent = agent(OLD_IP);
+        agent.add_record(shared(), provider(&mock));
+        agent.sync().await.unwrap();
+
+        mock.fail("add_address", ProviderError::Unavailable);
+        agent.set_source(Arc::new(Fixed(ip(NEW_IP))));
+        let report = agent.sync().await.unwrap();
+        assert_eq!(report.failed, 1);
+        assert_eq!(mock.addresses(NAME), [OTHER_IP, OLD_IP]);
+        assert!(!mock.calls().iter().any(|c| c.starts_with("delete_address")));
+    }
+
+    /// ID cache at a fresh path, remembering that the agent created
+    /// `OTHER_NAME` at `mock`
+    fn cache_with_created(test: &str) -> (PathBuf, IdCache) {
+        let path =
+            std::env::temp_dir().join(format!("ddns-agent-{}-{}.json", test, std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let mut cache = IdCache::load(&path);
+        let record = Record {
+            id: Some("record-0".to_string()),
+            content: OLD_IP.to_string(),
+            ..new_record(&RecordConfig::new(OTHER_NAME), RecordType::A)
+        };
+        cache
+            .store([("mock", &zone(), &record, true, None)])
+            .unwrap();
+        (path, cache)
+    }
+
+    /// Names the cache at `path` remembers the agent created
+    fn created(path: &Path) -> Vec<String> {
+        IdCache::load(path)
+            .created()
+            .into_iter()
+            .map(|(_, _, record)| record.name)
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn removed_records_are_collected() {
+        let (path, cache) = cache_with_created("collected");
+        let mock = Arc::new(Mock::new("mock"));
+        mock.serve(NAME, NEW_IP);
+        mock.serve(OTHER_NAME, OLD_IP);
+        let mut agent = agent(NEW_IP).with_garbage_collect(true);
+        agent.set_cache(cache);
+        agent.add_record(RecordConfig::new(NAME), provider(&mock));
+
+        agent.prepare().await.unwrap();
+        assert!(mock.addresses(OTHER_NAME).is_empty());
+        assert!(created(&path).is_empty());
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn failed_collection_is_tried_again() {
+        let (path, cache) = cache_with_created("retried");
+        let mock = Arc::new(Mock::new("mock"));
+        mock.serve(NAME, NEW_IP);
+        mock.serve(OTHER_NAME, OLD_IP);
+        mock.fail("delete_address", ProviderError::Unavailable);
+        let mut agent = agent(NEW_IP).with_garbage_collect(true);
+        agent.set_cache(cache);
+        agent.add_record(RecordConfig::new(NAME), provider(&mock));
+
+        agent.prepare().await.unwrap();
+        assert_eq!(mock.addresses(OTHER_NAME), [OLD_IP]);
+        assert_eq!(created(&path), [OTHER_NAME]);
+
+        mock.recover("delete_address");
+        agent.sync().await.unwrap();
+        assert!(mock.addresses(OTHER_NAME).is_empty());
+        assert!(created(&path).is_empty());
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn records_are_marked_as_ours() {
+        let mock = Arc::new(Mock::new("mock"));
+        mock.serve(NAME, OLD_IP);
+        let mut agent = agent(NEW_IP).with_owner(Some("laptop".to_string()));
+        agent.add_record(RecordConfig::new(NAME), provider(&mock));
+
+        let report = agent.sync().await.unwrap();
+        assert_eq!(report.updated, 1);
+        let txt = mock.txt.lock().unwrap().clone();
+        assert_eq!(
+            txt,
+            [(owner::marker_name(NAME), owner::marker_value("laptop"))]
+        );
+    }
+
+    #[tokio::test]
+    async fn records_of_another_owner_are_left_alone() {
+        let mock = Arc::new(Mock::new("mock"));
+        mock.serve(NAME, OLD_IP);
+        mock.txt
+            .lock()
+            .unwrap()
+            .push((owner::marker_name(NAME), owner::marker_value("desktop")));
+        let mut agent = agent(NEW_IP).with_owner(Some("laptop".to_string()));
+        agent.add_record(RecordConfig::new(NAME), provider(&mock));
+
+        let err = agent.sync().await.unwrap_err();
+        assert!(
+            matches!(&err, Error::Provider(ProviderError::Owned { owner, .. }) if owner == "desktop"),
+            "{:?}",
+            err
+        );
+        assert_eq!(mock.addresses(NAME), [OLD_IP]);
+    }
+
+    fn ephemeral() -> RecordConfig {
+        RecordConfig {
+            ephemeral: true,
+            ..RecordConfig::new(NAME)
+        }
+    }
+
+    #[tokio::test]
+    async fn ephemeral_record_is_deleted_on_shutdown() {
+        let mock = Arc::new(Mock::new("mock"));
+        let mut agent = agent(NEW_IP);
+        agent.add_record(ephemeral(), provider(&mock));
+        agent.sync().await.unwrap();
+        assert_eq!(mock.addresses(NAME), [NEW_IP]);
+
+        agent.remove_ephemeral().await;
+        assert!(mock.addresses(NAME).is_empty());
+    }
+
+    #[tokio::test]
+    async fn failed_deletion_keeps_the_ephemeral_record() {
+        let mock = Arc::new(Mock::new("mock"));
+        mock.fail("delete_address", ProviderError::Unavailable);
+        let mut agent = agent(NEW_IP);
+        agent.add_record(ephemeral(), provider(&mock));
+        agent.sync().await.unwrap();
+
+        agent.remove_ephemeral().await;
+        assert_eq!(mock.addresses(NAME), [NEW_IP]);
+        assert!(agent.records()[0].current.is_some());
+    }
+}
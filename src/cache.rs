@@ -0,0 +1,159 @@
+//! Zone and record IDs remembered across restarts.
+//!
+//! The agent writes the records it manages to the cache file whenever they
+//! change and starts from there instead of looking them up again. An ID the
+//! provider no longer knows, e.g. after the record was recreated in its
+//! dashboard, is looked up again on the next update.
+//!
+//! Records the agent created are flagged, so garbage collection can delete
+//! them once they are removed from the config. When a record was last
+//! written is kept too, so `force_update_interval` survives restarts.
+
+use crate::provider::{Record, RecordType, Zone};
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+struct Entry {
+    provider: String,
+    name: String,
+    #[serde(rename = "type")]
+    kind: String,
+    zone_id: String,
+    zone_name: String,
+    record_id: Option<String>,
+    content: String,
+    ttl: u32,
+    proxied: Option<bool>,
+    #[serde(default)]
+    created: bool,
+    /// Unix time of the last write
+    #[serde(default)]
+    written: Option<u64>,
+}
+
+impl Entry {
+    fn zone(&self) -> Zone {
+        Zone {
+            id: self.zone_id.clone(),
+            name: self.zone_name.clone(),
+        }
+    }
+
+    fn written(&self) -> Option<SystemTime> {
+        UNIX_EPOCH.checked_add(Duration::from_secs(self.written?))
+    }
+
+    fn record(&self, kind: RecordType) -> Record {
+        Record {
+            id: self.record_id.clone(),
+            name: self.name.clone(),
+            kind,
+            content: self.content.clone(),
+            ttl: self.ttl,
+            proxied: self.proxied,
+        }
+    }
+}
+
+/// Cached zones and records, keyed by provider, name and type
+pub struct IdCache {
+    path: PathBuf,
+    entries: Vec<Entry>,
+}
+
+impl IdCache {
+    /// Cache stored at `path`, empty if the file doesn't exist yet or can't
+    /// be parsed
+    pub fn load(path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        let entries = match fs::read_to_string(&path) {
+            Ok(data) => serde_json::from_str(&data).unwrap_or_else(|e| {
+                warn!("Ignoring the ID cache {}: {}", path.display(), e);
+                Vec::new()
+            }),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => {
+                warn!("Ignoring the ID cache {}: {}", path.display(), e);
+                Vec::new()
+            }
+        };
+        Self { path, entries }
+    }
+
+    /// Zone and record of `name` at `provider` as last seen, whether the
+    /// agent created the record and when it last wrote it
+    pub fn get(
+        &self,
+        provider: &str,
+        name: &str,
+        kind: RecordType,
+    ) -> Option<(Zone, Record, bool, Option<SystemTime>)> {
+        let entry = self
+            .entries
+            .iter()
+            .find(|e| e.provider == provider && e.name == name && e.kind == kind.as_str())?;
+        Some((
+            entry.zone(),
+            entry.record(kind),
+            entry.created,
+            entry.written(),
+        ))
+    }
+
+    /// Records the agent created, with the name of their provider
+    pub fn created(&self) -> Vec<(String, Zone, Record)> {
+        self.entries
+            .iter()
+            .filter(|e| e.created)
+            .filter_map(|e| {
+                let kind = [RecordType::A, RecordType::Aaaa]
+                    .into_iter()
+                    .find(|kind| kind.as_str() == e.kind)?;
+                Some((e.provider.clone(), e.zone(), e.record(kind)))
+            })
+            .collect()
+    }
+
+    /// Replace the cached records with `records`, given with the name of
+    /// their provider, whether the agent created them and when it last
+    /// wrote them, and write the file if anything changed
+    pub fn store<'a>(
+        &mut self,
+        records: impl IntoIterator<Item = (&'a str, &'a Zone, &'a Record, bool, Option<SystemTime>)>,
+    ) -> io::Result<()> {
+        let entries: Vec<Entry> = records
+            .into_iter()
+            .map(|(provider, zone, record, created, written)| Entry {
+                provider: provider.to_string(),
+                name: record.name.clone(),
+                kind: record.kind.as_str().to_string(),
+                zone_id: zone.id.clone(),
+                zone_name: zone.name.clone(),
+                record_id: record.id.clone(),
+                content: record.content.clone(),
+                ttl: record.ttl,
+                proxied: record.proxied,
+                created,
+                written: written
+                    .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+                    .map(|d| d.as_secs()),
+            })
+            .collect();
+        if entries == self.entries {
+            return Ok(());
+        }
+
+        // Write a sibling file first so a crash can't leave half a cache
+        let data = serde_json::to_string_pretty(&entries)?;
+        let tmp = self.path.with_extension("tmp");
+        fs::write(&tmp, data)?;
+        fs::rename(&tmp, &self.path)?;
+        self.entries = entries;
+        Ok(())
+    }
+}
@@ -0,0 +1,158 @@
+//! Command line interface of the `ddns_agent` binary.
+
+use anyhow::Context;
+use clap::{Args, Parser, Subcommand};
+use ddns_agent::Config;
+use ddns_agent::config::RecordConfig;
+use ddns_agent::ip::IpMode;
+#[cfg(feature = "cloudflare")]
+use ddns_agent::provider::CloudflareConfig;
+use log::LevelFilter;
+use std::path::PathBuf;
+
+#[derive(Debug, Parser)]
+#[command(name = "ddns-agent", version, about)]
+pub struct Cli {
+    /// Path to a TOML configuration file
+    #[arg(short, long, global = true)]
+    pub config: Option<PathBuf>,
+
+    /// Cloudflare API token (overrides CF_API_TOKEN)
+    #[cfg(feature = "cloudflare")]
+    #[arg(long, global = true, value_name = "TOKEN")]
+    pub cf_api_token: Option<String>,
+
+    /// DNS name to update, may be repeated (overrides DNS_NAME)
+    #[arg(long, global = true, value_name = "NAME")]
+    pub dns_name: Vec<String>,
+
+    /// Milliseconds between IP checks (overrides DURATION_SLEEP_MS)
+    #[arg(long, global = true, value_name = "MS")]
+    pub interval_ms: Option<u64>,
+
+    /// Address families to update: ipv4, ipv6 or dual (overrides IP_VERSION)
+    #[arg(long, global = true, value_name = "MODE")]
+    pub ip_version: Option<IpMode>,
+
+    /// Perform a single detection and update pass, then exit (for cron)
+    #[arg(long, global = true)]
+    pub once: bool,
+
+    /// Detect the IP and look up records, but only print the planned changes
+    #[arg(long, global = true)]
+    pub dry_run: bool,
+
+    /// Take over records marked as managed by another agent
+    #[arg(long, global = true)]
+    pub force: bool,
+
+    /// Log level (overrides RUST_LOG)
+    #[arg(long, global = true, value_name = "LEVEL", value_parser = parse_level)]
+    pub log_level: Option<LevelFilter>,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Keep the DNS record in sync with the public IP (default)
+    Run,
+    /// Update the DNS record once, even if the IP is unchanged, and exit
+    Update,
+    /// Validate credentials and print the detected public IP
+    Check,
+    /// Print how each record differs from what an update would write
+    Plan,
+    /// Print the plan, then write the changes once confirmed
+    Apply {
+        /// Don't ask for confirmation
+        #[arg(short, long)]
+        yes: bool,
+    },
+    /// Publish or remove ACME DNS-01 challenge records, e.g. as a certbot
+    /// or lego hook
+    Acme {
+        #[command(subcommand)]
+        command: AcmeCommand,
+    },
+    /// Print version information
+    Version,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum AcmeCommand {
+    /// Add the challenge TXT record
+    SetTxt(ChallengeArgs),
+    /// Remove the challenge TXT record
+    ClearTxt(ChallengeArgs),
+}
+
+#[derive(Debug, Args)]
+pub struct ChallengeArgs {
+    /// Domain being validated or the full `_acme-challenge` name (defaults
+    /// to CERTBOT_DOMAIN)
+    pub domain: Option<String>,
+
+    /// Challenge value (defaults to CERTBOT_VALIDATION)
+    pub value: Option<String>,
+
+    /// Provider holding the zone, required when several are configured
+    #[arg(long, value_name = "NAME")]
+    pub provider: Option<String>,
+}
+
+impl ChallengeArgs {
+    /// Domain and value, falling back to the variables certbot passes to
+    /// its hooks
+    pub fn challenge(&self) -> anyhow::Result<(String, String)> {
+        let domain = match &self.domain {
+            Some(domain) => domain.clone(),
+            None => std::env::var("CERTBOT_DOMAIN").context("No domain given")?,
+        };
+        let value = match &self.value {
+            Some(value) => value.clone(),
+            None => std::env::var("CERTBOT_VALIDATION").context("No challenge value given")?,
+        };
+        Ok((domain, value))
+    }
+}
+
+fn parse_level(value: &str) -> Result<LevelFilter, String> {
+    value
+        .parse()
+        .map_err(|_| format!("invalid log level: {}", value))
+}
+
+impl Cli {
+    /// Load the config file (if any) and apply env var and flag overrides,
+    /// in increasing order of precedence
+    pub fn load_config(&self) -> anyhow::Result<Config> {
+        let mut config = match &self.config {
+            Some(path) => Config::load(path)?,
+            None => Config::default(),
+        };
+        config.apply_env();
+
+        #[cfg(feature = "cloudflare")]
+        if let Some(token) = &self.cf_api_token {
+            match &mut config.providers.cloudflare {
+                Some(cloudflare) => cloudflare.api_token = token.clone(),
+                None => config.providers.cloudflare = Some(CloudflareConfig::new(token)),
+            }
+        }
+        if !self.dns_name.is_empty() {
+            config.records = self.dns_name.iter().map(RecordConfig::new).collect();
+        }
+        if let Some(ms) = self.interval_ms {
+            config.agent.interval_ms = ms;
+        }
+        if let Some(mode) = self.ip_version {
+            config.agent.ip_version = mode;
+        }
+        if let Some(level) = self.log_level {
+            config.log.level = level;
+        }
+        Ok(config)
+    }
+}
@@ -0,0 +1,1367 @@
+//! Agent configuration.
+//!
+//! Settings are read from an optional TOML file and then overridden by
+//! environment variables, so existing `.env` based setups keep working.
+
+use crate::agent::DEFAULT_CONCURRENCY;
+use crate::error::ConfigError;
+use crate::health::HealthCheckConfig;
+use crate::ip::IpMode;
+use crate::notify::NotifyConfig;
+#[cfg(feature = "azure")]
+use crate::provider::azure::AzureConfig;
+#[cfg(feature = "cloudflare")]
+use crate::provider::cloudflare::CloudflareConfig;
+#[cfg(feature = "cloudns")]
+use crate::provider::cloudns::ClouDnsConfig;
+#[cfg(feature = "desec")]
+use crate::provider::desec::DesecConfig;
+#[cfg(feature = "digitalocean")]
+use crate::provider::digitalocean::DigitalOceanConfig;
+#[cfg(feature = "dnsimple")]
+use crate::provider::dnsimple::DnsimpleConfig;
+#[cfg(feature = "dnsomatic")]
+use crate::provider::dnsomatic::DnsOMaticConfig;
+#[cfg(feature = "duckdns")]
+use crate::provider::duckdns::DuckDnsConfig;
+#[cfg(feature = "dyndns2")]
+use crate::provider::dyndns2::DynDns2Config;
+#[cfg(feature = "dynu")]
+use crate::provider::dynu::DynuConfig;
+#[cfg(feature = "exec")]
+use crate::provider::exec::ExecConfig;
+#[cfg(feature = "freedns")]
+use crate::provider::freedns::FreeDnsConfig;
+#[cfg(feature = "gandi")]
+use crate::provider::gandi::GandiConfig;
+#[cfg(feature = "gcloud")]
+use crate::provider::gcloud::GcloudConfig;
+#[cfg(feature = "godaddy")]
+use crate::provider::godaddy::GoDaddyConfig;
+#[cfg(feature = "hetzner")]
+use crate::provider::hetzner::HetznerConfig;
+#[cfg(feature = "hurricane")]
+use crate::provider::hurricane::HurricaneConfig;
+#[cfg(feature = "infomaniak")]
+use crate::provider::infomaniak::InfomaniakConfig;
+#[cfg(feature = "ionos")]
+use crate::provider::ionos::IonosConfig;
+#[cfg(feature = "linode")]
+use crate::provider::linode::LinodeConfig;
+#[cfg(feature = "loopia")]
+use crate::provider::loopia::LoopiaConfig;
+#[cfg(feature = "namecheap")]
+use crate::provider::namecheap::NamecheapConfig;
+#[cfg(feature = "namecom")]
+use crate::provider::namecom::NameComConfig;
+#[cfg(feature = "netlify")]
+use crate::provider::netlify::NetlifyConfig;
+#[cfg(feature = "njalla")]
+use crate::provider::njalla::NjallaConfig;
+#[cfg(feature = "ovh")]
+use crate::provider::ovh::OvhConfig;
+#[cfg(feature = "pihole")]
+use crate::provider::pihole::PiholeConfig;
+#[cfg(feature = "porkbun")]
+use crate::provider::porkbun::PorkbunConfig;
+#[cfg(feature = "powerdns")]
+use crate::provider::powerdns::PowerDnsConfig;
+#[cfg(feature = "rfc2136")]
+use crate::provider::rfc2136::Rfc2136Config;
+#[cfg(feature = "route53")]
+use crate::provider::route53::Route53Config;
+#[cfg(feature = "scaleway")]
+use crate::provider::scaleway::ScalewayConfig;
+#[cfg(feature = "script")]
+use crate::provider::script::ScriptConfig;
+#[cfg(feature = "technitium")]
+use crate::provider::technitium::TechnitiumConfig;
+#[cfg(feature = "tunnelbroker")]
+use crate::provider::tunnelbroker::TunnelbrokerConfig;
+#[cfg(feature = "vultr")]
+use crate::provider::vultr::VultrConfig;
+#[cfg(feature = "webhook")]
+use crate::provider::webhook::WebhookConfig;
+use crate::provider::{ascii_name, extract_root_domain, wildcard_base};
+use crate::retry::RetryPolicy;
+use log::LevelFilter;
+use serde::{Deserialize, Deserializer};
+use std::collections::BTreeMap;
+use std::net::{IpAddr, Ipv6Addr};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use std::{env, fmt, fs};
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub agent: AgentConfig,
+    pub ip: IpConfig,
+    pub log: LogConfig,
+    pub notify: NotifyConfig,
+    pub providers: ProvidersConfig,
+    pub retry: RetryConfig,
+    pub records: Vec<RecordConfig>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AgentConfig {
+    /// Milliseconds between IP checks
+    pub interval_ms: u64,
+    /// Address families to keep in sync for records that don't set their own
+    pub ip_version: IpMode,
+    /// Maximum number of records updated at the same time
+    pub concurrency: usize,
+    /// Also check right after the network configuration changes (Linux,
+    /// macOS and Windows)
+    pub watch_network: bool,
+    /// Milliseconds between the safety checks replacing the `interval_ms`
+    /// ones while the network is watched
+    pub watch_interval_ms: u64,
+    /// File remembering the looked up zone and record IDs across restarts
+    pub cache_file: Option<PathBuf>,
+    /// Mark the records as managed by this agent with a TXT record, and
+    /// leave those marked by another one alone
+    pub owner_id: Option<String>,
+    /// Delete the records the agent created once they are removed from the
+    /// config, which needs `cache_file` to remember them
+    pub garbage_collect: bool,
+    /// Compare the records with what public resolvers answer
+    pub drift_check: Option<DriftCheckConfig>,
+    /// Wait for updates to be visible at the nameservers
+    pub verify: Option<VerifyConfig>,
+    /// Write the records of a provider instance again after this long
+    /// without a change, unless they set their own interval
+    pub force_update_interval: BTreeMap<String, HumanDuration>,
+}
+
+impl Default for AgentConfig {
+    fn default() -> Self {
+        Self {
+            interval_ms: 5000,
+            ip_version: IpMode::default(),
+            concurrency: DEFAULT_CONCURRENCY,
+            watch_network: false,
+            watch_interval_ms: 1_800_000,
+            cache_file: None,
+            owner_id: None,
+            garbage_collect: false,
+            drift_check: None,
+            verify: None,
+            force_update_interval: BTreeMap::new(),
+        }
+    }
+}
+
+/// Length of time written like `25d`, `1h 30m` or `90 minutes`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HumanDuration(pub Duration);
+
+impl std::str::FromStr for HumanDuration {
+    type Err = ConfigError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || ConfigError::Invalid(format!("Invalid duration: {}", s));
+        let mut rest = s.trim();
+        if rest.is_empty() {
+            return Err(invalid());
+        }
+        let mut secs: u64 = 0;
+        while !rest.is_empty() {
+            let digits = rest
+                .find(|c: char| !c.is_ascii_digit())
+                .unwrap_or(rest.len());
+            let value: u64 = rest[..digits].parse().map_err(|_| invalid())?;
+            rest = rest[digits..].trim_start();
+            let letters = rest
+                .find(|c: char| !c.is_ascii_alphabetic())
+                .unwrap_or(rest.len());
+            let unit: u64 = match &rest[..letters] {
+                "s" | "sec" | "secs" | "second" | "seconds" => 1,
+                "m" | "min" | "mins" | "minute" | "minutes" => 60,
+                "h" | "hr" | "hrs" | "hour" | "hours" => 3600,
+                "d" | "day" | "days" => 86400,
+                "w" | "week" | "weeks" => 7 * 86400,
+                _ => return Err(invalid()),
+            };
+            secs = value
+                .checked_mul(unit)
+                .and_then(|part| secs.checked_add(part))
+                .ok_or_else(invalid)?;
+            rest = rest[letters..].trim_start();
+        }
+        Ok(Self(Duration::from_secs(secs)))
+    }
+}
+
+impl<'de> Deserialize<'de> for HumanDuration {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// `[agent.drift_check]` section: ask public resolvers for the records now
+/// and then, catching changes made outside the agent
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DriftCheckConfig {
+    /// Resolvers to ask, `host` or `host:port`
+    #[serde(default = "default_resolvers")]
+    pub resolvers: Vec<String>,
+    /// Seconds between checks
+    #[serde(default = "default_drift_interval_secs")]
+    pub interval_secs: u64,
+    /// What to do about a record resolving to another address
+    #[serde(default)]
+    pub action: DriftAction,
+}
+
+fn default_resolvers() -> Vec<String> {
+    vec!["1.1.1.1".to_string(), "8.8.8.8".to_string()]
+}
+
+fn default_drift_interval_secs() -> u64 {
+    3600
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DriftAction {
+    /// Log a warning
+    #[default]
+    Warn,
+    /// Log a warning and write the record again
+    Reassert,
+}
+
+/// `[agent.verify]` section: only count an update as done once the
+/// nameservers answer with the new address
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct VerifyConfig {
+    /// Resolvers waited for on top of the authoritative nameservers of the
+    /// zone, `host` or `host:port`
+    #[serde(default)]
+    pub resolvers: Vec<String>,
+    /// Give up on an update not visible after this long
+    #[serde(default = "default_verify_timeout_secs")]
+    pub timeout_secs: u64,
+}
+
+fn default_verify_timeout_secs() -> u64 {
+    120
+}
+
+/// `[retry]` section: how failed provider requests and IP detections are
+/// retried
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RetryConfig {
+    /// Attempts in total, 1 turns retries off
+    pub max_attempts: u32,
+    /// Milliseconds before the first retry, doubled for every further one
+    pub initial_delay_ms: u64,
+    /// Upper bound of a single delay in milliseconds
+    pub max_delay_ms: u64,
+    /// Seconds after the first attempt when no further one is made
+    pub max_elapsed_secs: u64,
+    /// Settings replacing the ones above for a provider, by name
+    pub providers: BTreeMap<String, RetryOverride>,
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        let policy = RetryPolicy::default();
+        Self {
+            max_attempts: policy.max_attempts,
+            initial_delay_ms: policy.initial_delay.as_millis() as u64,
+            max_delay_ms: policy.max_delay.as_millis() as u64,
+            max_elapsed_secs: policy.max_elapsed.as_secs(),
+            providers: BTreeMap::new(),
+        }
+    }
+}
+
+/// `[retry.providers.<name>]` table, unset fields keep the `[retry]` ones
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RetryOverride {
+    pub max_attempts: Option<u32>,
+    pub initial_delay_ms: Option<u64>,
+    pub max_delay_ms: Option<u64>,
+    pub max_elapsed_secs: Option<u64>,
+}
+
+impl RetryConfig {
+    /// Policy for IP detections and providers without settings of their own
+    pub fn policy(&self) -> RetryPolicy {
+        RetryPolicy {
+            max_attempts: self.max_attempts,
+            initial_delay: Duration::from_millis(self.initial_delay_ms),
+            max_delay: Duration::from_millis(self.max_delay_ms),
+            max_elapsed: Duration::from_secs(self.max_elapsed_secs),
+        }
+    }
+
+    /// Policy for requests to the provider instance `provider`
+    pub fn policy_for(&self, provider: &str) -> RetryPolicy {
+        let policy = self.policy();
+        let Some(custom) = self.providers.get(provider) else {
+            return policy;
+        };
+        RetryPolicy {
+            max_attempts: custom.max_attempts.unwrap_or(policy.max_attempts),
+            initial_delay: custom
+                .initial_delay_ms
+                .map_or(policy.initial_delay, Duration::from_millis),
+            max_delay: custom
+                .max_delay_ms
+                .map_or(policy.max_delay, Duration::from_millis),
+            max_elapsed: custom
+                .max_elapsed_secs
+                .map_or(policy.max_elapsed, Duration::from_secs),
+        }
+    }
+}
+
+/// `[ip]` section: where the public IP is detected
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct IpConfig {
+    /// Sources asked in order until one returns an address, the resolvers
+    /// of the `public-ip` crate when empty
+    pub sources: Vec<IpSourceConfig>,
+    /// Ask all sources at once instead and only accept an address at least
+    /// this many of them agree on
+    pub quorum: Option<usize>,
+    /// Prefixes accepted even though they are private or reserved, e.g. a
+    /// lab network
+    pub allow: Vec<String>,
+    /// Prefixes never published, whatever a source reports
+    pub deny: Vec<String>,
+    /// Further sources by name, for records publishing another address
+    /// than the public IP, e.g. that of a VPN interface
+    pub named: BTreeMap<String, Vec<IpSourceConfig>>,
+    /// Router or modem reporting the WAN address, compared with the public
+    /// IP to detect carrier-grade NAT; the interface carrying the default
+    /// route when unset
+    pub wan: Option<IpSourceConfig>,
+}
+
+/// One `[[ip.sources]]` entry
+#[derive(Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct IpSourceConfig {
+    /// Web service answering with the address as plain text, e.g.
+    /// `https://ifconfig.me/ip`, STUN server as `stun:host[:port]`, or DNS
+    /// resolver as `dns:opendns`, `dns:cloudflare` or `dns:name@server`,
+    /// local interface as `interface:eth0`, router as `upnp:`, `natpmp:`,
+    /// `fritzbox:`, `mikrotik:<address>`, `opnsense:<address>` or
+    /// `pfsense:<address>`, shell command as `command:<command line>`, or
+    /// file or named pipe written by another process as `file:<path>`
+    pub url: String,
+    /// Milliseconds to wait for an answer before moving on to the next
+    /// source
+    #[serde(default = "default_source_timeout_ms")]
+    pub timeout_ms: u64,
+    /// Local address or interface (Linux only) to send the requests of a
+    /// web service, STUN or DNS source from, e.g. to keep them off a VPN
+    #[serde(default)]
+    pub bind: Option<String>,
+    /// Prefer addresses inside this prefix, e.g. `2001:db8:1::/48`, of an
+    /// `interface:` source, taking private ones inside it too
+    #[serde(default)]
+    pub prefix: Option<String>,
+    /// Pick the address out of the answer of a web service with a regex,
+    /// using the first capture group if there is one
+    #[serde(default)]
+    pub regex: Option<String>,
+    /// Pick the address out of the JSON answer of a web service, e.g. `/ip`
+    #[serde(default)]
+    pub json_pointer: Option<String>,
+    /// Interface whose address a router source reports, e.g. `ether1`
+    #[serde(default)]
+    pub interface: Option<String>,
+    /// Credentials of a router source
+    #[serde(default)]
+    pub username: Option<String>,
+    #[serde(default)]
+    pub password: Option<String>,
+    /// API key of a `pfsense:` source
+    #[serde(default)]
+    pub api_key: Option<String>,
+}
+
+impl fmt::Debug for IpSourceConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("IpSourceConfig")
+            .field("url", &self.url)
+            .field("timeout_ms", &self.timeout_ms)
+            .field("bind", &self.bind)
+            .field("prefix", &self.prefix)
+            .field("regex", &self.regex)
+            .field("json_pointer", &self.json_pointer)
+            .field("interface", &self.interface)
+            .field("username", &self.username)
+            .field("password", &self.password.as_ref().map(|_| "<redacted>"))
+            .field("api_key", &self.api_key.as_ref().map(|_| "<redacted>"))
+            .finish()
+    }
+}
+
+fn default_source_timeout_ms() -> u64 {
+    5000
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LogConfig {
+    pub level: LevelFilter,
+}
+
+impl Default for LogConfig {
+    fn default() -> Self {
+        Self {
+            level: LevelFilter::Info,
+        }
+    }
+}
+
+/// Credentials for the supported DNS providers
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ProvidersConfig {
+    #[cfg(feature = "azure")]
+    pub azure: Option<AzureConfig>,
+    #[cfg(feature = "cloudflare")]
+    pub cloudflare: Option<CloudflareConfig>,
+    #[cfg(feature = "cloudns")]
+    pub cloudns: Option<ClouDnsConfig>,
+    #[cfg(feature = "desec")]
+    pub desec: Option<DesecConfig>,
+    #[cfg(feature = "digitalocean")]
+    pub digitalocean: Option<DigitalOceanConfig>,
+    #[cfg(feature = "dnsimple")]
+    pub dnsimple: Option<DnsimpleConfig>,
+    #[cfg(feature = "dnsomatic")]
+    pub dnsomatic: Option<DnsOMaticConfig>,
+    #[cfg(feature = "duckdns")]
+    pub duckdns: Option<DuckDnsConfig>,
+    #[cfg(feature = "dyndns2")]
+    pub dyndns2: Option<DynDns2Config>,
+    #[cfg(feature = "dynu")]
+    pub dynu: Option<DynuConfig>,
+    #[cfg(feature = "exec")]
+    pub exec: Option<ExecConfig>,
+    #[cfg(feature = "freedns")]
+    pub freedns: Option<FreeDnsConfig>,
+    #[cfg(feature = "gandi")]
+    pub gandi: Option<GandiConfig>,
+    #[cfg(feature = "gcloud")]
+    pub gcloud: Option<GcloudConfig>,
+    #[cfg(feature = "godaddy")]
+    pub godaddy: Option<GoDaddyConfig>,
+    #[cfg(feature = "hetzner")]
+    pub hetzner: Option<HetznerConfig>,
+    #[cfg(feature = "hurricane")]
+    pub hurricane: Option<HurricaneConfig>,
+    #[cfg(feature = "infomaniak")]
+    pub infomaniak: Option<InfomaniakConfig>,
+    #[cfg(feature = "ionos")]
+    pub ionos: Option<IonosConfig>,
+    #[cfg(feature = "linode")]
+    pub linode: Option<LinodeConfig>,
+    #[cfg(feature = "loopia")]
+    pub loopia: Option<LoopiaConfig>,
+    #[cfg(feature = "namecheap")]
+    pub namecheap: Option<NamecheapConfig>,
+    #[cfg(feature = "namecom")]
+    pub namecom: Option<NameComConfig>,
+    #[cfg(feature = "netlify")]
+    pub netlify: Option<NetlifyConfig>,
+    #[cfg(feature = "njalla")]
+    pub njalla: Option<NjallaConfig>,
+    #[cfg(feature = "ovh")]
+    pub ovh: Option<OvhConfig>,
+    #[cfg(feature = "pihole")]
+    pub pihole: Option<PiholeConfig>,
+    #[cfg(feature = "porkbun")]
+    pub porkbun: Option<PorkbunConfig>,
+    #[cfg(feature = "powerdns")]
+    pub powerdns: Option<PowerDnsConfig>,
+    #[cfg(feature = "rfc2136")]
+    pub rfc2136: Option<Rfc2136Config>,
+    #[cfg(feature = "route53")]
+    pub route53: Option<Route53Config>,
+    #[cfg(feature = "scaleway")]
+    pub scaleway: Option<ScalewayConfig>,
+    #[cfg(feature = "technitium")]
+    pub technitium: Option<TechnitiumConfig>,
+    #[cfg(feature = "tunnelbroker")]
+    pub tunnelbroker: Option<TunnelbrokerConfig>,
+    #[cfg(feature = "vultr")]
+    pub vultr: Option<VultrConfig>,
+    #[cfg(feature = "webhook")]
+    pub webhook: Option<WebhookConfig>,
+    #[cfg(feature = "script")]
+    pub script: Option<ScriptConfig>,
+}
+
+impl ProvidersConfig {
+    /// Names of the configured providers
+    #[allow(unused_mut)] // every provider is an optional feature
+    pub fn names(&self) -> Vec<&'static str> {
+        let mut names = Vec::new();
+        #[cfg(feature = "azure")]
+        if self.azure.is_some() {
+            names.push("azure");
+        }
+        #[cfg(feature = "cloudflare")]
+        if self.cloudflare.is_some() {
+            names.push("cloudflare");
+        }
+        #[cfg(feature = "cloudns")]
+        if self.cloudns.is_some() {
+            names.push("cloudns");
+        }
+        #[cfg(feature = "desec")]
+        if self.desec.is_some() {
+            names.push("desec");
+        }
+        #[cfg(feature = "digitalocean")]
+        if self.digitalocean.is_some() {
+            names.push("digitalocean");
+        }
+        #[cfg(feature = "dnsimple")]
+        if self.dnsimple.is_some() {
+            names.push("dnsimple");
+        }
+        #[cfg(feature = "dnsomatic")]
+        if self.dnsomatic.is_some() {
+            names.push("dnsomatic");
+        }
+        #[cfg(feature = "duckdns")]
+        if self.duckdns.is_some() {
+            names.push("duckdns");
+        }
+        #[cfg(feature = "dyndns2")]
+        if self.dyndns2.is_some() {
+            names.push("dyndns2");
+        }
+        #[cfg(feature = "dynu")]
+        if self.dynu.is_some() {
+            names.push("dynu");
+        }
+        #[cfg(feature = "exec")]
+        if self.exec.is_some() {
+            names.push("exec");
+        }
+        #[cfg(feature = "freedns")]
+        if self.freedns.is_some() {
+            names.push("freedns");
+        }
+        #[cfg(feature = "gandi")]
+        if self.gandi.is_some() {
+            names.push("gandi");
+        }
+        #[cfg(feature = "gcloud")]
+        if self.gcloud.is_some() {
+            names.push("gcloud");
+        }
+        #[cfg(feature = "godaddy")]
+        if self.godaddy.is_some() {
+            names.push("godaddy");
+        }
+        #[cfg(feature = "hetzner")]
+        if self.hetzner.is_some() {
+            names.push("hetzner");
+        }
+        #[cfg(feature = "hurricane")]
+        if self.hurricane.is_some() {
+            names.push("hurricane");
+        }
+        #[cfg(feature = "infomaniak")]
+        if self.infomaniak.is_some() {
+            names.push("infomaniak");
+        }
+        #[cfg(feature = "ionos")]
+        if self.ionos.is_some() {
+            names.push("ionos");
+        }
+        #[cfg(feature = "linode")]
+        if self.linode.is_some() {
+            names.push("linode");
+        }
+        #[cfg(feature = "loopia")]
+        if self.loopia.is_some() {
+            names.push("loopia");
+        }
+        #[cfg(feature = "namecheap")]
+        if self.namecheap.is_some() {
+            names.push("namecheap");
+        }
+        #[cfg(feature = "namecom")]
+        if self.namecom.is_some() {
+            names.push("namecom");
+        }
+        #[cfg(feature = "netlify")]
+        if self.netlify.is_some() {
+            names.push("netlify");
+        }
+        #[cfg(feature = "njalla")]
+        if self.njalla.is_some() {
+            names.push("njalla");
+        }
+        #[cfg(feature = "ovh")]
+        if self.ovh.is_some() {
+            names.push("ovh");
+        }
+        #[cfg(feature = "pihole")]
+        if self.pihole.is_some() {
+            names.push("pihole");
+        }
+        #[cfg(feature = "porkbun")]
+        if self.porkbun.is_some() {
+            names.push("porkbun");
+        }
+        #[cfg(feature = "powerdns")]
+        if self.powerdns.is_some() {
+            names.push("powerdns");
+        }
+        #[cfg(feature = "rfc2136")]
+        if self.rfc2136.is_some() {
+            names.push("rfc2136");
+        }
+        #[cfg(feature = "route53")]
+        if self.route53.is_some() {
+            names.push("route53");
+        }
+        #[cfg(feature = "scaleway")]
+        if self.scaleway.is_some() {
+            names.push("scaleway");
+        }
+        #[cfg(feature = "technitium")]
+        if self.technitium.is_some() {
+            names.push("technitium");
+        }
+        #[cfg(feature = "tunnelbroker")]
+        if self.tunnelbroker.is_some() {
+            names.push("tunnelbroker");
+        }
+        #[cfg(feature = "vultr")]
+        if self.vultr.is_some() {
+            names.push("vultr");
+        }
+        #[cfg(feature = "webhook")]
+        if self.webhook.is_some() {
+            names.push("webhook");
+        }
+        #[cfg(feature = "script")]
+        if self.script.is_some() {
+            names.push("script");
+        }
+        names
+    }
+
+    /// Names records can use to refer to the configured providers: those of
+    /// [`ProvidersConfig::names`] plus `cloudflare.<account>` for every
+    /// further Cloudflare account and `cloudflare.load_balancer`
+    #[allow(unused_mut)] // only Cloudflare has accounts
+    pub fn instances(&self) -> Vec<String> {
+        let mut instances: Vec<String> = self.names().into_iter().map(String::from).collect();
+        #[cfg(feature = "cloudflare")]
+        if let Some(cloudflare) = &self.cloudflare {
+            instances.extend(
+                cloudflare
+                    .accounts
+                    .keys()
+                    .map(|name| format!("cloudflare.{}", name)),
+            );
+            if cloudflare.load_balancer.is_some() {
+                instances.push("cloudflare.load_balancer".to_string());
+            }
+        }
+        instances
+    }
+
+    /// Instance of `provider` serving records in `zone`, i.e. the Cloudflare
+    /// `account` chosen by the record or else the one listing the zone
+    #[allow(unused_variables)] // only Cloudflare has accounts
+    pub fn instance(&self, provider: &str, zone: &str, account: Option<&str>) -> String {
+        #[cfg(feature = "cloudflare")]
+        if provider == "cloudflare"
+            && let Some(account) =
+                account.or_else(|| self.cloudflare.as_ref().and_then(|c| c.account_for(zone)))
+        {
+            return format!("cloudflare.{}", account);
+        }
+        provider.to_string()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RecordConfig {
+    /// Full DNS name to update (e.g. `home.example.com`), or `@` for the
+    /// apex of `zone`
+    pub name: String,
+    /// Name of the zone the record lives in, needed for `@`. The zone is
+    /// looked up by this name, and assumed to be the root domain with
+    /// `zone_id` if unset.
+    #[serde(default)]
+    pub zone: Option<String>,
+    /// TTL in seconds, 1 means "automatic"
+    #[serde(default = "default_ttl")]
+    pub ttl: u32,
+    /// Whether the record is proxied through Cloudflare, `None` keeps the
+    /// proxy status the record already has
+    #[serde(default)]
+    pub proxied: Option<bool>,
+    /// Create the record on the first update if it doesn't exist yet
+    /// instead of failing
+    #[serde(default)]
+    pub create_missing: bool,
+    /// ID of the record's zone at the provider, skipping the zone lookup,
+    /// e.g. for API tokens that can't list zones
+    #[serde(default)]
+    pub zone_id: Option<String>,
+    /// ID of the record at the provider, skipping the record lookup. Needs
+    /// `zone_id` and a single address family.
+    #[serde(default)]
+    pub record_id: Option<String>,
+    /// Address families to keep in sync, defaults to `agent.ip_version`
+    #[serde(default)]
+    pub ip_version: Option<IpMode>,
+    /// Provider managing this record, may be omitted when only one
+    /// provider is configured
+    #[serde(default)]
+    pub provider: Option<String>,
+    /// Publish the record through several providers at once, e.g. a primary
+    /// and a secondary DNS host. Used instead of `provider`.
+    #[serde(default)]
+    pub providers: Vec<String>,
+    /// Providers taking over, in order, while the record's provider is
+    /// unavailable
+    #[serde(default)]
+    pub failover: Vec<String>,
+    /// Write the record again after this long without a change, e.g.
+    /// `25d`, for services expiring hostnames nobody updates
+    #[serde(default)]
+    pub force_update_interval: Option<HumanDuration>,
+    /// `[providers.cloudflare.accounts]` entry whose token updates this
+    /// record at Cloudflare, whatever zones the accounts list
+    #[serde(default)]
+    pub account: Option<String>,
+    /// `[ip.named]` sources detecting the address of this record instead of
+    /// the `[ip]` sources
+    #[serde(default)]
+    pub ip_source: Option<String>,
+    /// Interface identifier of another host, e.g. `::1a2b:3cff:fe4d:5e6f`.
+    /// The AAAA record then points at this identifier inside the prefix of
+    /// the detected address.
+    #[serde(default)]
+    pub ipv6_suffix: Option<Ipv6Addr>,
+    /// Length of the network prefix taken from the detected address
+    #[serde(default = "default_ipv6_prefix_length")]
+    pub ipv6_prefix_length: u8,
+    /// Create the record at startup and delete it when the agent is
+    /// stopped, e.g. for laptops and spot instances
+    #[serde(default)]
+    pub ephemeral: bool,
+    /// Share the name with other agents: add the detected address to its
+    /// record set and only remove the address published before, instead of
+    /// replacing the record
+    #[serde(default)]
+    pub round_robin: bool,
+    /// SRV records pointing at this name, kept in sync with their ports
+    #[serde(default)]
+    pub srv: Vec<SrvConfig>,
+    /// Check of the service behind this name, withdrawing the record while
+    /// the service is down
+    #[serde(default)]
+    pub health_check: Option<HealthCheckConfig>,
+}
+
+/// `[[records.srv]]` entry: a service offered on the record's name
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SrvConfig {
+    /// Service and protocol labels, e.g. `_minecraft._tcp`
+    pub service: String,
+    pub port: u16,
+    #[serde(default)]
+    pub priority: u16,
+    #[serde(default)]
+    pub weight: u16,
+}
+
+fn default_ttl() -> u32 {
+    1
+}
+
+fn default_ipv6_prefix_length() -> u8 {
+    64
+}
+
+impl RecordConfig {
+    /// Record with default settings for `name`
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            zone: None,
+            ttl: default_ttl(),
+            proxied: None,
+            ip_version: None,
+            provider: None,
+            providers: Vec::new(),
+            failover: Vec::new(),
+            account: None,
+            force_update_interval: None,
+            create_missing: false,
+            zone_id: None,
+            record_id: None,
+            ip_source: None,
+            ipv6_suffix: None,
+            ipv6_prefix_length: default_ipv6_prefix_length(),
+            ephemeral: false,
+            round_robin: false,
+            srv: Vec::new(),
+            health_check: None,
+        }
+    }
+
+    /// Full DNS name of the record, resolving `@` to the zone apex.
+    /// Lowercase, as providers build relative names by stripping the zone,
+    /// and in punycode for internationalized names.
+    pub fn dns_name(&self) -> String {
+        let name = match (self.name.as_str(), &self.zone) {
+            ("@", Some(zone)) => zone,
+            (name, _) => name,
+        };
+        normalize(name)
+    }
+
+    /// Name of the zone the record lives in
+    pub fn zone_name(&self) -> String {
+        match &self.zone {
+            Some(zone) => normalize(zone),
+            None => extract_root_domain(&self.dns_name()),
+        }
+    }
+
+    /// Name to look the zone up by: the configured zone, so providers don't
+    /// have to guess it from the record name
+    pub fn zone_lookup_name(&self) -> String {
+        match &self.zone {
+            Some(zone) => normalize(zone),
+            None => self.dns_name(),
+        }
+    }
+
+    /// Address the record should point at when `detected` is the public IP,
+    /// which differs from it for IPv6 records of another host
+    pub fn address_for(&self, detected: IpAddr) -> IpAddr {
+        match (detected, self.ipv6_suffix) {
+            (IpAddr::V6(detected), Some(suffix)) => {
+                let mask = u128::MAX
+                    .checked_shl(128 - u32::from(self.ipv6_prefix_length))
+                    .unwrap_or(0);
+                let ip = detected.to_bits() & mask | suffix.to_bits() & !mask;
+                IpAddr::V6(Ipv6Addr::from_bits(ip))
+            }
+            _ => detected,
+        }
+    }
+}
+
+/// `name` without a trailing dot, in lowercase ASCII. Invalid IDNs are only
+/// lowercased and rejected by [`Config::validate`].
+fn normalize(name: &str) -> String {
+    let name = name.trim_end_matches('.');
+    ascii_name(name).unwrap_or_else(|| name.to_lowercase())
+}
+
+/// Parse a comma separated list of DNS names, as accepted by `DNS_NAME`
+pub fn parse_names(value: &str) -> Vec<RecordConfig> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .map(RecordConfig::new)
+        .collect()
+}
+
+impl Config {
+    /// Load configuration from a TOML file
+    pub fn load(path: &Path) -> Result<Config, ConfigError> {
+        let raw = fs::read_to_string(path).map_err(|source| ConfigError::Read {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        toml::from_str(&raw).map_err(|source| ConfigError::Parse {
+            path: path.to_path_buf(),
+            source,
+        })
+    }
+
+    /// Override file settings with environment variables.
+    ///
+    /// `CF_API_TOKEN`, `DNS_NAME`, `DURATION_SLEEP_MS`, `IP_VERSION` and
+    /// `RUST_LOG` take precedence over the corresponding config file values.
+    /// `DNS_NAME` may hold several comma separated names.
+    pub fn apply_env(&mut self) {
+        #[cfg(feature = "cloudflare")]
+        if let Ok(token) = env::var("CF_API_TOKEN") {
+            match &mut self.providers.cloudflare {
+                Some(cloudflare) => cloudflare.api_token = token,
+                None => self.providers.cloudflare = Some(CloudflareConfig::new(token)),
+            }
+        }
+        if let Ok(names) = env::var("DNS_NAME") {
+            self.records = parse_names(&names);
+        }
+        if let Some(ms) = env::var("DURATION_SLEEP_MS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+        {
+            self.agent.interval_ms = ms;
+        }
+        if let Some(mode) = env::var("IP_VERSION").ok().and_then(|v| v.parse().ok()) {
+            self.agent.ip_version = mode;
+        }
+        if let Some(level) = env::var("RUST_LOG").ok().and_then(|v| v.parse().ok()) {
+            self.log.level = level;
+        }
+    }
+
+    /// Configured records with agent-wide defaults filled in.
+    ///
+    /// Records listing several `providers` are returned once per provider,
+    /// each with `provider` set. `@` names are resolved to their zone, and
+    /// Cloudflare records to the account listing their zone.
+    pub fn records(&self) -> Vec<RecordConfig> {
+        let providers = self.providers.names();
+        let default_provider = match providers.as_slice() {
+            [only] => Some(only.to_string()),
+            _ => None,
+        };
+
+        self.records
+            .iter()
+            .flat_map(|record| {
+                let targets = if record.providers.is_empty() {
+                    vec![record.provider.clone().or_else(|| default_provider.clone())]
+                } else {
+                    record.providers.iter().cloned().map(Some).collect()
+                };
+                let zone = record.zone_name();
+                let failover: Vec<String> = record
+                    .failover
+                    .iter()
+                    .map(|name| {
+                        self.providers
+                            .instance(name, &zone, record.account.as_deref())
+                    })
+                    .collect();
+                targets.into_iter().map(move |provider| RecordConfig {
+                    name: record.dns_name(),
+                    ip_version: Some(record.ip_version.unwrap_or(self.agent.ip_version)),
+                    provider: provider.map(|name| {
+                        self.providers
+                            .instance(&name, &zone, record.account.as_deref())
+                    }),
+                    providers: Vec::new(),
+                    failover: failover.clone(),
+                    ..record.clone()
+                })
+            })
+            .collect()
+    }
+
+    /// Check that everything required to run the agent is present
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        let providers = self.providers.instances();
+        if providers.is_empty() {
+            return Err(ConfigError::Missing(
+                "CF_API_TOKEN is required (or a [providers.*] section in the config file)".into(),
+            ));
+        }
+        if self.records.is_empty() {
+            return Err(ConfigError::Missing(
+                "DNS_NAME is required (or a [[records]] entry in the config file)".into(),
+            ));
+        }
+        if let Some(name) = self
+            .retry
+            .providers
+            .keys()
+            .find(|name| !providers.contains(name))
+        {
+            return Err(ConfigError::Invalid(format!(
+                "Retry settings for provider `{}` which is not configured",
+                name
+            )));
+        }
+        for (name, interval) in &self.agent.force_update_interval {
+            if !providers.contains(name) {
+                return Err(ConfigError::Invalid(format!(
+                    "`force_update_interval` for provider `{}` which is not configured",
+                    name
+                )));
+            }
+            if interval.0.is_zero() {
+                return Err(ConfigError::Invalid(format!(
+                    "Provider `{}` has a `force_update_interval` of 0",
+                    name
+                )));
+            }
+        }
+        if self
+            .notify
+            .command
+            .as_ref()
+            .is_some_and(|command| command.is_empty())
+        {
+            return Err(ConfigError::Invalid(
+                "The notification `command` is empty".into(),
+            ));
+        }
+        if self.retry.max_attempts == 0
+            || self
+                .retry
+                .providers
+                .values()
+                .any(|r| r.max_attempts == Some(0))
+        {
+            return Err(ConfigError::Invalid(
+                "Retry max_attempts must be at least 1".into(),
+            ));
+        }
+        if self.agent.garbage_collect && self.agent.cache_file.is_none() {
+            return Err(ConfigError::Missing(
+                "garbage_collect needs a cache_file remembering the records created".into(),
+            ));
+        }
+        #[cfg(feature = "cloudflare")]
+        if let Some(cloudflare) = &self.providers.cloudflare {
+            if cloudflare.accounts.contains_key("load_balancer") {
+                return Err(ConfigError::Invalid(
+                    "Cloudflare account name `load_balancer` is reserved".into(),
+                ));
+            }
+            let mut zones = Vec::new();
+            for (name, account) in &cloudflare.accounts {
+                for zone in &account.zones {
+                    let zone = normalize(zone);
+                    if zones.contains(&zone) {
+                        return Err(ConfigError::Invalid(format!(
+                            "Zone {} is listed by several Cloudflare accounts, last by `{}`",
+                            zone, name
+                        )));
+                    }
+                    zones.push(zone);
+                }
+            }
+        }
+        #[cfg(feature = "route53")]
+        if let Some((name, _)) = self
+            .providers
+            .route53
+            .iter()
+            .flat_map(|r| &r.routing)
+            .find(|(_, routing)| routing.weight.is_some() == routing.region.is_some())
+        {
+            return Err(ConfigError::Invalid(format!(
+                "Route53 routing of {} must set exactly one of `weight` and `region`",
+                name
+            )));
+        }
+        if let Some(record) = self
+            .records
+            .iter()
+            .find(|r| r.providers.len() > 1 && r.zone_id.is_some())
+        {
+            return Err(ConfigError::Invalid(format!(
+                "Record {} sets `zone_id` but is published through several providers",
+                record.name
+            )));
+        }
+        if let Some(record) = self
+            .records
+            .iter()
+            .find(|r| r.provider.is_some() && !r.providers.is_empty())
+        {
+            return Err(ConfigError::Invalid(format!(
+                "Record {} sets both `provider` and `providers`",
+                record.name
+            )));
+        }
+        // A name may be kept on several providers, e.g. a public one and a
+        // local resolver
+        let records = self.records();
+        for (i, record) in records.iter().enumerate() {
+            if records[..i]
+                .iter()
+                .any(|r| r.name == record.name && r.provider == record.provider)
+            {
+                return Err(ConfigError::Invalid(format!(
+                    "Duplicate record: {}",
+                    record.name
+                )));
+            }
+            if let Some(account) = &record.account
+                && !providers.contains(&format!("cloudflare.{}", account))
+            {
+                return Err(ConfigError::Invalid(format!(
+                    "Record {} uses Cloudflare account `{}` which is not configured",
+                    record.name, account
+                )));
+            }
+            match &record.provider {
+                None => {
+                    return Err(ConfigError::Missing(format!(
+                        "Record {} must set `provider`, several providers are configured",
+                        record.name
+                    )));
+                }
+                Some(name) if !providers.contains(name) => {
+                    return Err(ConfigError::Invalid(format!(
+                        "Record {} uses provider `{}` which is not configured",
+                        record.name, name
+                    )));
+                }
+                Some(_) => {}
+            }
+            if let Some(name) = &record.ip_source
+                && !self.ip.named.contains_key(name)
+            {
+                return Err(ConfigError::Invalid(format!(
+                    "Record {} uses IP source `{}` which is not configured under [ip.named]",
+                    record.name, name
+                )));
+            }
+            if record.record_id.is_some() && record.zone_id.is_none() {
+                return Err(ConfigError::Invalid(format!(
+                    "Record {} sets `record_id` without `zone_id`",
+                    record.name
+                )));
+            }
+            if record.record_id.is_some() && record.round_robin {
+                return Err(ConfigError::Invalid(format!(
+                    "Record {} sets `record_id` but shares a record set with `round_robin`",
+                    record.name
+                )));
+            }
+            if record.record_id.is_some() && record.ip_version == Some(IpMode::Dual) {
+                return Err(ConfigError::Invalid(format!(
+                    "Record {} sets `record_id` but keeps both A and AAAA records",
+                    record.name
+                )));
+            }
+            // The A and AAAA records would both write the one address of
+            // the origin named like them
+            let pool = "cloudflare.load_balancer";
+            if record.ip_version == Some(IpMode::Dual)
+                && (record.provider.as_deref() == Some(pool)
+                    || record.failover.iter().any(|f| f == pool))
+            {
+                return Err(ConfigError::Invalid(format!(
+                    "Record {} keeps both A and AAAA records, but a load balancer origin takes one address",
+                    record.name
+                )));
+            }
+            if let Some(name) = std::iter::once(&record.name)
+                .chain(&record.zone)
+                .find(|name| ascii_name(name.trim_end_matches('.')).is_none())
+            {
+                return Err(ConfigError::Invalid(format!(
+                    "Record {}: {} is not a valid internationalized name",
+                    record.name, name
+                )));
+            }
+            if record.name == "@" && record.zone.is_none() {
+                return Err(ConfigError::Invalid(
+                    "Record @ needs `zone` to name the apex it stands for".into(),
+                ));
+            }
+            if record.zone.is_some() {
+                let (name, zone) = (record.dns_name(), record.zone_name());
+                if name != zone && !name.ends_with(&format!(".{}", zone)) {
+                    return Err(ConfigError::Invalid(format!(
+                        "Record {} is outside its zone {}",
+                        record.name, zone
+                    )));
+                }
+            }
+            let base = wildcard_base(&record.name).unwrap_or(&record.name);
+            if base.contains('*') {
+                return Err(ConfigError::Invalid(format!(
+                    "Record {} may only use `*` as its first label",
+                    record.name
+                )));
+            }
+            if let Some(srv) = record.srv.iter().find(|s| !s.service.starts_with('_')) {
+                return Err(ConfigError::Invalid(format!(
+                    "Record {} has an SRV service `{}` not starting with `_`",
+                    record.name, srv.service
+                )));
+            }
+            if record.force_update_interval.is_some_and(|d| d.0.is_zero()) {
+                return Err(ConfigError::Invalid(format!(
+                    "Record {} sets a `force_update_interval` of 0",
+                    record.name
+                )));
+            }
+            if let Some(check) = &record.health_check
+                && check.checks() != 1
+            {
+                return Err(ConfigError::Invalid(format!(
+                    "Record {} must set exactly one of `tcp`, `http` and `command` in its health check",
+                    record.name
+                )));
+            }
+            if record.ipv6_prefix_length > 128 {
+                return Err(ConfigError::Invalid(format!(
+                    "Record {} has an `ipv6_prefix_length` above 128",
+                    record.name
+                )));
+            }
+            for name in &record.failover {
+                if !providers.contains(name) {
+                    return Err(ConfigError::Invalid(format!(
+                        "Record {} fails over to provider `{}` which is not configured",
+                        record.name, name
+                    )));
+                }
+                if record.provider.as_ref() == Some(name) {
+                    return Err(ConfigError::Invalid(format!(
+                        "Record {} fails over to its own provider `{}`",
+                        record.name, name
+                    )));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn with_suffix(suffix: &str, prefix_length: u8) -> RecordConfig {
+        RecordConfig {
+            ipv6_suffix: Some(suffix.parse().unwrap()),
+            ipv6_prefix_length: prefix_length,
+            ..RecordConfig::new("host.example.com")
+        }
+    }
+
+    fn ip(s: &str) -> IpAddr {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn address_for_keeps_the_detected_address_without_suffix() {
+        let record = RecordConfig::new("host.example.com");
+        assert_eq!(
+            record.address_for(ip("2001:db8:1:2::1")),
+            ip("2001:db8:1:2::1")
+        );
+    }
+
+    #[test]
+    fn address_for_keeps_ipv4_addresses() {
+        let record = with_suffix("::211:32ff:fe12:3456", 64);
+        assert_eq!(record.address_for(ip("203.0.113.7")), ip("203.0.113.7"));
+    }
+
+    #[test]
+    fn address_for_combines_prefix_and_suffix() {
+        let record = with_suffix("::211:32ff:fe12:3456", 64);
+        assert_eq!(
+            record.address_for(ip("2001:db8:1:2:aaaa:bbbb:cccc:dddd")),
+            ip("2001:db8:1:2:211:32ff:fe12:3456")
+        );
+
+        // The suffix reaches into the subnet bits of a /56
+        let record = with_suffix("::ab:0:0:0:1", 56);
+        assert_eq!(
+            record.address_for(ip("2001:db8:1:2ff::1")),
+            ip("2001:db8:1:2ab::1")
+        );
+    }
+
+    #[test]
+    fn address_for_at_the_prefix_length_bounds() {
+        let detected = ip("2001:db8::1");
+        assert_eq!(with_suffix("::42", 0).address_for(detected), ip("::42"));
+        assert_eq!(with_suffix("::42", 128).address_for(detected), detected);
+    }
+
+    #[cfg(feature = "cloudflare")]
+    #[test]
+    fn load_balancer_records_keep_one_address_family() {
+        let config = |ip_version: &str| -> Config {
+            toml::from_str(&format!(
+                r#"
+                [providers.cloudflare]
+                api_token = "token"
+
+                [providers.cloudflare.load_balancer]
+                account_id = "account"
+                pool_id = "pool"
+
+                [[records]]
+                name = "home"
+                provider = "cloudflare.load_balancer"
+                ip_version = "{}"
+                "#,
+                ip_version
+            ))
+            .unwrap()
+        };
+        config("ipv4").validate().unwrap();
+        config("ipv6").validate().unwrap();
+        assert!(matches!(
+            config("dual").validate(),
+            Err(ConfigError::Invalid(_))
+        ));
+    }
+
+    fn duration(s: &str) -> Option<Duration> {
+        s.parse::<HumanDuration>().ok().map(|d| d.0)
+    }
+
+    #[test]
+    fn durations_in_each_unit() {
+        assert_eq!(duration("90s"), Some(Duration::from_secs(90)));
+        assert_eq!(duration("5m"), Some(Duration::from_secs(300)));
+        assert_eq!(duration("36h"), Some(Duration::from_secs(36 * 3600)));
+        assert_eq!(duration("25d"), Some(Duration::from_secs(25 * 86400)));
+        assert_eq!(duration("2w"), Some(Duration::from_secs(14 * 86400)));
+        assert_eq!(duration("90 minutes"), Some(Duration::from_secs(5400)));
+        assert_eq!(duration("1 day"), Some(Duration::from_secs(86400)));
+    }
+
+    #[test]
+    fn durations_combine_units() {
+        assert_eq!(duration("1h30m"), Some(Duration::from_secs(5400)));
+        assert_eq!(
+            duration(" 1w 2d 3h "),
+            Some(Duration::from_secs(9 * 86400 + 3 * 3600))
+        );
+    }
+
+    #[test]
+    fn durations_rejected() {
+        for s in ["", "25", "d", "5 fortnights", "1.5h", "-1d", "1d,2h"] {
+            assert_eq!(duration(s), None, "{:?}", s);
+        }
+        // Overflowing instead of wrapping around
+        assert_eq!(duration("18446744073709551615w"), None);
+        assert_eq!(duration("18446744073709551615s 1s"), None);
+    }
+}
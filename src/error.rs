@@ -0,0 +1,187 @@
+//! Error types of the library API.
+
+use crate::ip::IpVersion;
+use crate::provider::RecordType;
+use reqwest::StatusCode;
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// Errors reported by a [`DnsProvider`](crate::DnsProvider)
+#[derive(Debug, thiserror::Error)]
+pub enum ProviderError {
+    /// The credentials were rejected or lack the required permissions
+    #[error("Authentication failed: {0}")]
+    Auth(String),
+
+    #[error("Zone not found for domain: {0}")]
+    ZoneNotFound(String),
+
+    /// Several zones have the name, e.g. in different accounts the
+    /// credentials can access
+    #[error("Several zones found for domain: {0}")]
+    AmbiguousZone(String),
+
+    #[error("DNS {kind} record not found for: {name}")]
+    RecordNotFound { name: String, kind: RecordType },
+
+    /// The provider can't do what was asked, e.g. manage TXT records
+    #[error("{provider} can't {action}")]
+    Unsupported {
+        provider: String,
+        action: &'static str,
+    },
+
+    /// The record carries the ownership marker of another agent
+    #[error("{name} is managed by `{owner}`, use --force to take it over")]
+    Owned { name: String, owner: String },
+
+    /// A zone or record addressed by ID doesn't exist (anymore)
+    #[error("Not found: {0}")]
+    NotFound(String),
+
+    /// The provider asked us to slow down
+    #[error("Rate limited by provider")]
+    RateLimited { retry_after: Option<Duration> },
+
+    /// The request could not be sent or the connection failed
+    #[error("Network error: {0}")]
+    Network(#[from] reqwest::Error),
+
+    /// The provider answered with something we couldn't make sense of
+    #[error("Invalid response: {0}")]
+    InvalidResponse(String),
+
+    /// The provider failed with a server error, likely for a moment
+    #[error("Provider unavailable: {0}")]
+    Unavailable(String),
+
+    /// The provider understood the request but refused or failed it
+    #[error("{0}")]
+    Api(String),
+}
+
+impl ProviderError {
+    /// Error for a request answered with `status` and `message`, telling
+    /// server errors and missing zones or records apart from refused
+    /// requests
+    pub fn for_status(status: StatusCode, message: String) -> Self {
+        if status == StatusCode::NOT_FOUND {
+            ProviderError::NotFound(message)
+        } else if status.is_server_error() {
+            ProviderError::Unavailable(message)
+        } else {
+            ProviderError::Api(message)
+        }
+    }
+
+    /// Whether the provider looks unavailable rather than misconfigured, so
+    /// another provider may take over
+    pub fn is_outage(&self) -> bool {
+        matches!(
+            self,
+            ProviderError::RateLimited { .. }
+                | ProviderError::Network(_)
+                | ProviderError::Unavailable(_)
+        )
+    }
+
+    /// Whether the request may succeed when sent again shortly, so it is
+    /// retried
+    pub fn is_transient(&self) -> bool {
+        match self {
+            ProviderError::RateLimited { .. } | ProviderError::Unavailable(_) => true,
+            ProviderError::Network(e) => !e.is_builder() && !e.is_decode(),
+            _ => false,
+        }
+    }
+
+    /// Delay a rate limited provider asked for
+    pub fn retry_after(&self) -> Option<Duration> {
+        match self {
+            ProviderError::RateLimited { retry_after } => *retry_after,
+            _ => None,
+        }
+    }
+
+    /// Whether the zone or record the agent knows doesn't exist at the
+    /// provider, so its IDs have to be looked up again
+    pub fn is_not_found(&self) -> bool {
+        matches!(
+            self,
+            ProviderError::ZoneNotFound(_)
+                | ProviderError::RecordNotFound { .. }
+                | ProviderError::NotFound(_)
+        )
+    }
+}
+
+/// Errors reported by an [`IpSource`](crate::IpSource)
+#[derive(Debug, thiserror::Error)]
+pub enum IpDetectionError {
+    /// No source returned an address of the requested family
+    #[error("Could not determine public {0} address")]
+    NotFound(IpVersion),
+
+    #[error("Network error: {0}")]
+    Network(#[from] reqwest::Error),
+
+    /// Sending to or receiving from a source failed
+    #[error("Socket error: {0}")]
+    Socket(#[from] std::io::Error),
+
+    /// A source answered with something that isn't a usable address
+    #[error("Invalid address: {0}")]
+    Invalid(String),
+}
+
+impl IpDetectionError {
+    /// Whether asking again shortly may succeed, so the detection is retried
+    pub fn is_transient(&self) -> bool {
+        match self {
+            IpDetectionError::Network(e) => !e.is_builder() && !e.is_decode(),
+            IpDetectionError::Socket(_) => true,
+            _ => false,
+        }
+    }
+}
+
+/// Errors in the agent configuration
+#[derive(Debug, thiserror::Error)]
+pub enum ConfigError {
+    #[error("Failed to read config file {}", path.display())]
+    Read {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+
+    #[error("Failed to parse config file {}", path.display())]
+    Parse {
+        path: PathBuf,
+        #[source]
+        source: toml::de::Error,
+    },
+
+    /// A required setting is missing
+    #[error("{0}")]
+    Missing(String),
+
+    /// A setting has an unusable value
+    #[error("{0}")]
+    Invalid(String),
+}
+
+/// Any error returned by the library
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error(transparent)]
+    Provider(#[from] ProviderError),
+
+    #[error(transparent)]
+    IpDetection(#[from] IpDetectionError),
+
+    #[error(transparent)]
+    Config(#[from] ConfigError),
+}
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
@@ -0,0 +1,117 @@
+//! Local health checks gating records.
+//!
+//! A record with a health check is withdrawn while the service behind it is
+//! down: pointed at a fallback address, or deleted if there is none. It is
+//! written again once the check passes, a poor man's failover for services
+//! hosted at home.
+
+use crate::provider::RecordType;
+use serde::Deserialize;
+use std::net::IpAddr;
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::net::TcpStream;
+use tokio::process::Command;
+use tokio::time::timeout;
+
+fn default_timeout_secs() -> u64 {
+    5
+}
+
+fn default_fail_after_secs() -> u64 {
+    60
+}
+
+/// `[records.health_check]` table: how to check the service behind a
+/// record. Exactly one of `tcp`, `http` and `command` is set.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct HealthCheckConfig {
+    /// `host:port` accepting TCP connections
+    #[serde(default)]
+    pub tcp: Option<String>,
+    /// URL answering a GET request with a 2xx status
+    #[serde(default)]
+    pub http: Option<String>,
+    /// Program and arguments exiting with 0
+    #[serde(default)]
+    pub command: Option<Vec<String>>,
+    /// Fail a check taking longer than this
+    #[serde(default = "default_timeout_secs")]
+    pub timeout_secs: u64,
+    /// Withdraw the record once the checks failed for this long
+    #[serde(default = "default_fail_after_secs")]
+    pub fail_after_secs: u64,
+    /// Addresses to publish while withdrawn, at most one per family. A
+    /// record without a fallback of its family is deleted instead.
+    #[serde(default)]
+    pub fallback: Vec<IpAddr>,
+}
+
+impl HealthCheckConfig {
+    /// Number of checks set, valid configs set exactly one
+    pub fn checks(&self) -> usize {
+        usize::from(self.tcp.is_some())
+            + usize::from(self.http.is_some())
+            + usize::from(self.command.is_some())
+    }
+
+    /// Fallback address for records of type `kind`
+    pub fn fallback_for(&self, kind: RecordType) -> Option<IpAddr> {
+        self.fallback
+            .iter()
+            .copied()
+            .find(|ip| ip.is_ipv4() == (kind == RecordType::A))
+    }
+}
+
+/// Run the check of `config`, describing why it failed
+pub async fn check(config: &HealthCheckConfig) -> Result<(), String> {
+    let limit = Duration::from_secs(config.timeout_secs);
+    let result = if let Some(addr) = &config.tcp {
+        timeout(limit, check_tcp(addr)).await
+    } else if let Some(url) = &config.http {
+        timeout(limit, check_http(url)).await
+    } else if let Some(command) = &config.command {
+        timeout(limit, check_command(command)).await
+    } else {
+        return Ok(());
+    };
+    result.unwrap_or_else(|_| Err(format!("timed out after {}s", config.timeout_secs)))
+}
+
+async fn check_tcp(addr: &str) -> Result<(), String> {
+    TcpStream::connect(addr)
+        .await
+        .map(drop)
+        .map_err(|e| format!("connecting to {}: {}", addr, e))
+}
+
+async fn check_http(url: &str) -> Result<(), String> {
+    let resp = reqwest::get(url)
+        .await
+        .map_err(|e| format!("GET {}: {}", url, e))?;
+    if !resp.status().is_success() {
+        return Err(format!("GET {}: HTTP {}", url, resp.status().as_u16()));
+    }
+    Ok(())
+}
+
+async fn check_command(command: &[String]) -> Result<(), String> {
+    let Some((program, args)) = command.split_first() else {
+        return Ok(());
+    };
+    let status = Command::new(program)
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .kill_on_drop(true)
+        .status()
+        .await
+        .map_err(|e| format!("running {}: {}", program, e))?;
+    if !status.success() {
+        return Err(format!("{} exited with {}", program, status));
+    }
+    Ok(())
+}
@@ -0,0 +1,71 @@
+//! Binding detection requests to a local address or network interface, so
+//! they leave over the WAN link even while a VPN tunnel holds the default
+//! route.
+
+use super::IpVersion;
+use crate::error::ConfigError;
+use std::io;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use tokio::net::UdpSocket;
+
+/// Where a source sends its requests from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Bind {
+    /// Local address; only requests of its family are sent
+    Address(IpAddr),
+    /// Network interface, e.g. `eth0` (Linux only)
+    Interface(String),
+}
+
+impl Bind {
+    /// Local address to bind a socket for `version` requests to, `None` if
+    /// bound to an address of the other family
+    pub(crate) fn local(bind: Option<&Bind>, version: IpVersion) -> Option<IpAddr> {
+        match (bind, version) {
+            (Some(Bind::Address(ip)), IpVersion::V4) if ip.is_ipv4() => Some(*ip),
+            (Some(Bind::Address(ip)), IpVersion::V6) if ip.is_ipv6() => Some(*ip),
+            (Some(Bind::Address(_)), _) => None,
+            (_, IpVersion::V4) => Some(Ipv4Addr::UNSPECIFIED.into()),
+            (_, IpVersion::V6) => Some(Ipv6Addr::UNSPECIFIED.into()),
+        }
+    }
+}
+
+impl std::str::FromStr for Bind {
+    type Err = ConfigError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Ok(ip) = s.parse() {
+            return Ok(Bind::Address(ip));
+        }
+        if s.is_empty() || !cfg!(target_os = "linux") {
+            return Err(ConfigError::Invalid(format!(
+                "Can't bind to {:?}: expected a local address{}",
+                s,
+                if cfg!(target_os = "linux") {
+                    " or an interface name"
+                } else {
+                    ", interfaces are only supported on Linux"
+                }
+            )));
+        }
+        Ok(Bind::Interface(s.to_string()))
+    }
+}
+
+/// UDP socket for `version` requests, `None` if bound to an address of the
+/// other family
+pub(crate) async fn udp_socket(
+    bind: Option<&Bind>,
+    version: IpVersion,
+) -> io::Result<Option<UdpSocket>> {
+    let Some(local) = Bind::local(bind, version) else {
+        return Ok(None);
+    };
+    let socket = UdpSocket::bind((local, 0)).await?;
+    #[cfg(target_os = "linux")]
+    if let Some(Bind::Interface(interface)) = bind {
+        socket.bind_device(Some(interface.as_bytes()))?;
+    }
+    Ok(Some(socket))
+}
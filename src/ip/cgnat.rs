@@ -0,0 +1,110 @@
+//! Warns when the network sits behind carrier-grade NAT.
+//!
+//! Behind CGNAT the WAN side of the router (or a modem interface) gets an
+//! address from the shared `100.64.0.0/10` range, and the public IP belongs
+//! to the ISP's NAT. Publishing that IP still works, but inbound
+//! connections to the DNS name never reach this network.
+
+use super::{IpSource, IpVersion};
+use crate::error::IpDetectionError;
+use async_trait::async_trait;
+use log::{debug, info, warn};
+use std::net::{IpAddr, Ipv4Addr};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Whether `ip` is in the shared address space `100.64.0.0/10` (RFC 6598)
+pub fn is_shared(ip: Ipv4Addr) -> bool {
+    ip.octets()[0] == 100 && ip.octets()[1] & 0xC0 == 64
+}
+
+/// Interface carrying the IPv4 default route, from the kernel routing table
+#[cfg(target_os = "linux")]
+fn default_route_interface() -> Option<String> {
+    let table = std::fs::read_to_string("/proc/net/route").ok()?;
+    table.lines().skip(1).find_map(|line| {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        (fields.get(1) == Some(&"00000000")).then(|| fields[0].to_string())
+    })
+}
+
+#[cfg(not(target_os = "linux"))]
+fn default_route_interface() -> Option<String> {
+    None
+}
+
+/// Shared address on the interface carrying the default route
+fn local_shared_address() -> Option<Ipv4Addr> {
+    let interface = default_route_interface()?;
+    if_addrs::get_if_addrs()
+        .ok()?
+        .into_iter()
+        .filter(|i| i.name == interface)
+        .find_map(|i| match i.ip() {
+            IpAddr::V4(ip) if is_shared(ip) => Some(ip),
+            _ => None,
+        })
+}
+
+/// Passes on the addresses of a source, comparing detected IPv4 addresses
+/// with the WAN address the router (`wan`) or the default route interface
+/// reports
+pub struct CgnatCheck {
+    source: Arc<dyn IpSource>,
+    /// WAN address source with the name used in logs
+    wan: Option<(String, Arc<dyn IpSource>)>,
+    /// Behind CGNAT at the last detection, to warn only once
+    behind: AtomicBool,
+}
+
+impl CgnatCheck {
+    pub fn new(source: Arc<dyn IpSource>, wan: Option<(String, Arc<dyn IpSource>)>) -> Self {
+        Self {
+            source,
+            wan,
+            behind: AtomicBool::new(false),
+        }
+    }
+
+    async fn wan_address(&self) -> Option<Ipv4Addr> {
+        let Some((name, wan)) = &self.wan else {
+            return local_shared_address();
+        };
+        match wan.detect(IpVersion::V4).await {
+            Ok(Some(IpAddr::V4(ip))) => Some(ip),
+            Ok(_) => None,
+            Err(e) => {
+                debug!("{}: {}", name, e);
+                None
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl IpSource for CgnatCheck {
+    async fn detect(&self, version: IpVersion) -> Result<Option<IpAddr>, IpDetectionError> {
+        let detected = self.source.detect(version).await?;
+        let Some(IpAddr::V4(public)) = detected else {
+            return Ok(detected);
+        };
+
+        let shared = self
+            .wan_address()
+            .await
+            .filter(|wan| is_shared(*wan) && *wan != public);
+        let was_behind = self.behind.swap(shared.is_some(), Ordering::Relaxed);
+        match shared {
+            Some(wan) if !was_behind => warn!(
+                "The WAN address {} is in the carrier-grade NAT range 100.64.0.0/10 while the \
+                 public IP is {}: the ISP shares that IP, so inbound connections to the DNS \
+                 names won't reach this network. Ask the ISP for a public IPv4 address or \
+                 use IPv6",
+                wan, public
+            ),
+            None if was_behind => info!("The WAN address is no longer behind carrier-grade NAT"),
+            _ => {}
+        }
+        Ok(detected)
+    }
+}
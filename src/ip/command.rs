@@ -0,0 +1,101 @@
+//! IP source running a shell command and reading the address from its
+//! output, e.g. to ask a modem over SSH.
+//!
+//! The command runs with `sh -c` (`cmd /C` on Windows) and gets the
+//! requested family in `DDNS_IP_VERSION` (`ipv4` or `ipv6`). It prints the
+//! bare address, or nothing when it has none of that family; a non-zero exit
+//! fails the detection.
+
+use super::{IpSource, IpVersion, parse_answer};
+use crate::error::IpDetectionError;
+use async_trait::async_trait;
+use log::{debug, warn};
+use std::io;
+use std::net::IpAddr;
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::process::Command;
+
+pub struct CommandSource {
+    command: String,
+    timeout: Duration,
+}
+
+impl CommandSource {
+    pub fn new(command: impl Into<String>, timeout: Duration) -> Self {
+        Self {
+            command: command.into(),
+            timeout,
+        }
+    }
+
+    fn shell(&self) -> Command {
+        #[cfg(windows)]
+        let mut shell = {
+            let mut shell = Command::new("cmd");
+            shell.arg("/C");
+            shell
+        };
+        #[cfg(not(windows))]
+        let mut shell = {
+            let mut shell = Command::new("sh");
+            shell.arg("-c");
+            shell
+        };
+        shell.arg(&self.command);
+        shell
+    }
+
+    fn failed(&self, e: io::Error) -> IpDetectionError {
+        IpDetectionError::Invalid(format!("Failed to run {}: {}", self.command, e))
+    }
+}
+
+#[async_trait]
+impl IpSource for CommandSource {
+    async fn detect(&self, version: IpVersion) -> Result<Option<IpAddr>, IpDetectionError> {
+        let family = match version {
+            IpVersion::V4 => "ipv4",
+            IpVersion::V6 => "ipv6",
+        };
+        let child = self
+            .shell()
+            .env("DDNS_IP_VERSION", family)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|e| self.failed(e))?;
+        let output = tokio::time::timeout(self.timeout, child.wait_with_output())
+            .await
+            .map_err(|_| {
+                IpDetectionError::Invalid(format!(
+                    "{} timed out after {}ms",
+                    self.command,
+                    self.timeout.as_millis()
+                ))
+            })?
+            .map_err(|e| self.failed(e))?;
+
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        if !output.status.success() {
+            for line in stderr.lines().filter(|l| !l.trim().is_empty()) {
+                warn!("[command] {}: {}", self.command, line);
+            }
+            return Err(IpDetectionError::Invalid(format!(
+                "{} failed with {}",
+                self.command, output.status
+            )));
+        }
+        if !stderr.trim().is_empty() {
+            debug!("[command] {}: {}", self.command, stderr.trim());
+        }
+
+        if stdout.trim().is_empty() {
+            return Ok(None);
+        }
+        parse_answer(&self.command, &stdout, version).map(Some)
+    }
+}
@@ -0,0 +1,185 @@
+//! IP source asking several sources at once and only accepting an address
+//! enough of them agree on.
+
+use super::{IpSource, IpVersion};
+use crate::error::IpDetectionError;
+use async_trait::async_trait;
+use futures::future::join_all;
+use log::{debug, warn};
+use std::net::IpAddr;
+use std::sync::Arc;
+
+/// Guards against a single source returning a wrong or poisoned answer by
+/// requiring `quorum` sources to report the same address
+pub struct ConsensusSource {
+    /// Sources with the name used in logs
+    sources: Vec<(String, Arc<dyn IpSource>)>,
+    quorum: usize,
+}
+
+impl ConsensusSource {
+    pub fn new(sources: Vec<(String, Arc<dyn IpSource>)>, quorum: usize) -> Self {
+        Self { sources, quorum }
+    }
+}
+
+/// Addresses with the sources reporting them, most votes first
+fn tally(answers: Vec<(&str, IpAddr)>) -> Vec<(IpAddr, Vec<&str>)> {
+    let mut votes: Vec<(IpAddr, Vec<&str>)> = Vec::new();
+    for (name, ip) in answers {
+        match votes.iter_mut().find(|(voted, _)| *voted == ip) {
+            Some((_, names)) => names.push(name),
+            None => votes.push((ip, vec![name])),
+        }
+    }
+    votes.sort_by_key(|(_, names)| std::cmp::Reverse(names.len()));
+    votes
+}
+
+fn describe(votes: &[(IpAddr, Vec<&str>)]) -> String {
+    votes
+        .iter()
+        .map(|(ip, names)| format!("{} ({})", ip, names.join(", ")))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[async_trait]
+impl IpSource for ConsensusSource {
+    async fn detect(&self, version: IpVersion) -> Result<Option<IpAddr>, IpDetectionError> {
+        let results = join_all(
+            self.sources
+                .iter()
+                .map(|(_, source)| source.detect(version)),
+        )
+        .await;
+
+        let mut answers = Vec::new();
+        for ((name, _), result) in self.sources.iter().zip(results) {
+            match result {
+                Ok(Some(ip)) => answers.push((name.as_str(), ip)),
+                Ok(None) => debug!("{} found no {} address", name, version),
+                Err(e) => warn!("{}: {}", name, e),
+            }
+        }
+        let votes = tally(answers);
+        if votes.len() > 1 {
+            warn!(
+                "IP sources disagree on the {} address: {}",
+                version,
+                describe(&votes)
+            );
+        }
+
+        match votes.as_slice() {
+            [] => Ok(None),
+            [(ip, names), rest @ ..]
+                if names.len() >= self.quorum
+                    && rest.first().is_none_or(|(_, r)| r.len() < names.len()) =>
+            {
+                debug!(
+                    "Detected {} address {} via {} of {} sources",
+                    version,
+                    ip,
+                    names.len(),
+                    self.sources.len()
+                );
+                Ok(Some(*ip))
+            }
+            _ => Err(IpDetectionError::Invalid(format!(
+                "sources didn't agree on the {} address (quorum {}): {}",
+                version,
+                self.quorum,
+                describe(&votes)
+            ))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Source answering with a fixed address, or failing for `None`
+    struct Fixed(Option<IpAddr>);
+
+    #[async_trait]
+    impl IpSource for Fixed {
+        async fn detect(&self, _: IpVersion) -> Result<Option<IpAddr>, IpDetectionError> {
+            self.0
+                .map(Some)
+                .ok_or_else(|| IpDetectionError::Invalid("down".into()))
+        }
+    }
+
+    fn ip(s: &str) -> IpAddr {
+        s.parse().unwrap()
+    }
+
+    fn consensus(answers: &[Option<&str>], quorum: usize) -> ConsensusSource {
+        let sources = answers
+            .iter()
+            .enumerate()
+            .map(|(i, answer)| {
+                let source: Arc<dyn IpSource> = Arc::new(Fixed(answer.map(ip)));
+                (format!("source-{}", i), source)
+            })
+            .collect();
+        ConsensusSource::new(sources, quorum)
+    }
+
+    #[test]
+    fn tally_puts_most_votes_first() {
+        let votes = tally(vec![
+            ("a", ip("192.0.2.1")),
+            ("b", ip("192.0.2.2")),
+            ("c", ip("192.0.2.2")),
+        ]);
+        assert_eq!(
+            votes,
+            vec![
+                (ip("192.0.2.2"), vec!["b", "c"]),
+                (ip("192.0.2.1"), vec!["a"])
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn accepts_an_address_reaching_the_quorum() {
+        let source = consensus(
+            &[Some("192.0.2.1"), Some("192.0.2.1"), Some("192.0.2.9")],
+            2,
+        );
+        assert_eq!(
+            source.detect(IpVersion::V4).await.unwrap(),
+            Some(ip("192.0.2.1"))
+        );
+    }
+
+    #[tokio::test]
+    async fn failed_sources_dont_vote() {
+        let source = consensus(&[Some("192.0.2.1"), None, Some("192.0.2.1")], 2);
+        assert_eq!(
+            source.detect(IpVersion::V4).await.unwrap(),
+            Some(ip("192.0.2.1"))
+        );
+
+        let source = consensus(&[Some("192.0.2.1"), None, None], 2);
+        assert!(source.detect(IpVersion::V4).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn rejects_a_tie() {
+        let source = consensus(&[Some("192.0.2.1"), Some("192.0.2.2")], 1);
+        assert!(source.detect(IpVersion::V4).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn rejects_an_address_below_the_quorum() {
+        let source = consensus(
+            &[Some("192.0.2.1"), Some("192.0.2.2"), Some("192.0.2.3")],
+            2,
+        );
+        assert!(source.detect(IpVersion::V4).await.is_err());
+    }
+}
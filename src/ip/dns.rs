@@ -0,0 +1,391 @@
+//! IP source asking a DNS resolver that answers with the address of the
+//! client, like OpenDNS (`myip.opendns.com`) or Cloudflare
+//! (`whoami.cloudflare`, class CH TXT).
+//!
+//! The query is a single UDP datagram, which is faster and more reliable
+//! than scraping a web page.
+
+use super::bind::{Bind, udp_socket};
+use super::{IpSource, IpVersion};
+use crate::error::IpDetectionError;
+use async_trait::async_trait;
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::io;
+use std::net::{IpAddr, SocketAddr};
+use std::ops::Range;
+use std::time::Duration;
+use tokio::net::{UdpSocket, lookup_host};
+use tokio::time::{Instant, timeout_at};
+
+const DNS_PORT: u16 = 53;
+const TYPE_A: u16 = 1;
+const TYPE_NS: u16 = 2;
+const TYPE_TXT: u16 = 16;
+const TYPE_AAAA: u16 = 28;
+const CLASS_IN: u16 = 1;
+const CLASS_CH: u16 = 3;
+/// Delay before an unanswered query is sent again, UDP being lossy
+const RETRANSMIT: Duration = Duration::from_millis(500);
+/// Compression pointers followed in a name before giving up on it
+const MAX_POINTERS: usize = 64;
+
+pub struct DnsSource {
+    /// Name whose answer is our address
+    name: String,
+    /// Ask for the CH TXT record instead of the A or AAAA record
+    chaos_txt: bool,
+    /// Resolvers as `host:port`, the first one of the detected family is used
+    servers: Vec<String>,
+    timeout: Duration,
+    bind: Option<Bind>,
+}
+
+impl DnsSource {
+    /// OpenDNS, answering `myip.opendns.com` with the client address
+    pub fn opendns(timeout: Duration) -> Self {
+        Self {
+            name: "myip.opendns.com".to_string(),
+            chaos_txt: false,
+            servers: vec!["208.67.222.222:53".into(), "[2620:119:35::35]:53".into()],
+            timeout,
+            bind: None,
+        }
+    }
+
+    /// Cloudflare, answering `whoami.cloudflare` (CH TXT) with the client
+    /// address
+    pub fn cloudflare(timeout: Duration) -> Self {
+        Self {
+            name: "whoami.cloudflare".to_string(),
+            chaos_txt: true,
+            servers: vec!["1.1.1.1:53".into(), "[2606:4700:4700::1111]:53".into()],
+            timeout,
+            bind: None,
+        }
+    }
+
+    /// Ask `server` (`host` or `host:port`) for the A or AAAA record of `name`
+    pub fn new(name: &str, server: &str, timeout: Duration) -> Self {
+        Self {
+            name: name.trim_end_matches('.').to_string(),
+            chaos_txt: false,
+            servers: vec![with_port(server)],
+            timeout,
+            bind: None,
+        }
+    }
+
+    /// Send queries from this local address or interface
+    pub fn with_bind(mut self, bind: Bind) -> Self {
+        self.bind = Some(bind);
+        self
+    }
+
+    fn question(&self, version: IpVersion) -> (u16, u16) {
+        match (self.chaos_txt, version) {
+            (true, _) => (TYPE_TXT, CLASS_CH),
+            (false, IpVersion::V4) => (TYPE_A, CLASS_IN),
+            (false, IpVersion::V6) => (TYPE_AAAA, CLASS_IN),
+        }
+    }
+
+    /// First resolver reachable over `version`
+    async fn server(&self, version: IpVersion) -> Result<Option<SocketAddr>, IpDetectionError> {
+        for server in &self.servers {
+            let found = lookup_host(server).await?.find(|a| match version {
+                IpVersion::V4 => a.is_ipv4(),
+                IpVersion::V6 => a.is_ipv6(),
+            });
+            if found.is_some() {
+                return Ok(found);
+            }
+        }
+        Ok(None)
+    }
+}
+
+/// `server` as `host:port`, adding the DNS port if it has none
+fn with_port(server: &str) -> String {
+    let has_port = match server.rsplit_once(':') {
+        Some((host, port)) => {
+            port.parse::<u16>().is_ok() && (!host.contains(':') || host.ends_with(']'))
+        }
+        None => false,
+    };
+    if has_port {
+        server.to_string()
+    } else if server.contains(':') && !server.starts_with('[') {
+        format!("[{}]:{}", server, DNS_PORT)
+    } else {
+        format!("{}:{}", server, DNS_PORT)
+    }
+}
+
+fn query(id: u16, name: &str, kind: u16, class: u16) -> Vec<u8> {
+    let mut msg = id.to_be_bytes().to_vec();
+    // Recursion desired, one question
+    msg.extend([1, 0, 0, 1, 0, 0, 0, 0, 0, 0]);
+    for label in name.split('.').filter(|l| !l.is_empty()) {
+        msg.push(label.len() as u8);
+        msg.extend(label.bytes());
+    }
+    msg.push(0);
+    msg.extend(kind.to_be_bytes());
+    msg.extend(class.to_be_bytes());
+    msg
+}
+
+fn u16_at(msg: &[u8], at: usize) -> Option<u16> {
+    Some(u16::from_be_bytes([*msg.get(at)?, *msg.get(at + 1)?]))
+}
+
+/// Offset after the possibly compressed name at `pos`
+fn skip_name(msg: &[u8], mut pos: usize) -> Option<usize> {
+    loop {
+        match *msg.get(pos)? {
+            0 => return Some(pos + 1),
+            len if len & 0xC0 == 0xC0 => return Some(pos + 2),
+            len => pos += 1 + len as usize,
+        }
+    }
+}
+
+/// Possibly compressed name at `pos`
+fn read_name(msg: &[u8], mut pos: usize) -> Option<String> {
+    let mut labels = Vec::new();
+    for _ in 0..MAX_POINTERS {
+        loop {
+            match *msg.get(pos)? {
+                0 => return Some(labels.join(".")),
+                len if len & 0xC0 == 0xC0 => {
+                    pos = usize::from(u16_at(msg, pos)? & 0x3FFF);
+                    break;
+                }
+                len => {
+                    let label = msg.get(pos + 1..pos + 1 + len as usize)?;
+                    labels.push(String::from_utf8_lossy(label).into_owned());
+                    pos += 1 + len as usize;
+                }
+            }
+        }
+    }
+    None
+}
+
+/// Type of an answer and where its data is in the response
+type Answer = (u16, Range<usize>);
+
+/// Answers in a response to query `id`, `None` for other datagrams
+fn answers(msg: &[u8], id: u16) -> Option<Result<Vec<Answer>, IpDetectionError>> {
+    if u16_at(msg, 0)? != id || msg.get(2)? & 0x80 == 0 {
+        return None;
+    }
+    let invalid = |what: &str| Some(Err(IpDetectionError::Invalid(what.to_string())));
+    match msg.get(3)? & 0x0F {
+        0 => {}
+        // NXDOMAIN, e.g. OpenDNS asked over a family it has no answer for
+        3 => return Some(Ok(Vec::new())),
+        rcode => return invalid(&format!("DNS error response (rcode {})", rcode)),
+    }
+
+    let count = u16_at(msg, 6)?;
+    let Some(mut pos) = skip_name(msg, 12).map(|p| p + 4) else {
+        return invalid("truncated DNS response");
+    };
+    let mut found = Vec::new();
+    for _ in 0..count {
+        let Some(record) = skip_name(msg, pos) else {
+            return invalid("truncated DNS response");
+        };
+        let (Some(rtype), Some(len)) = (u16_at(msg, record), u16_at(msg, record + 8)) else {
+            return invalid("truncated DNS response");
+        };
+        let rdata = record + 10..record + 10 + len as usize;
+        if rdata.end > msg.len() {
+            return invalid("truncated DNS response");
+        }
+        pos = rdata.end;
+        found.push((rtype, rdata));
+    }
+    Some(Ok(found))
+}
+
+/// Address in the first matching answer of a response to query `id`, `None`
+/// for other datagrams
+fn parse_address(
+    msg: &[u8],
+    id: u16,
+    kind: u16,
+) -> Option<Result<Option<IpAddr>, IpDetectionError>> {
+    let answers = match answers(msg, id)? {
+        Ok(answers) => answers,
+        Err(e) => return Some(Err(e)),
+    };
+    let Some((_, rdata)) = answers.into_iter().find(|(rtype, _)| *rtype == kind) else {
+        return Some(Ok(None));
+    };
+    let rdata = &msg[rdata];
+    let ip = match kind {
+        TYPE_A => <[u8; 4]>::try_from(rdata).ok().map(IpAddr::from),
+        TYPE_AAAA => <[u8; 16]>::try_from(rdata).ok().map(IpAddr::from),
+        // Character strings, each prefixed with its length
+        _ => rdata
+            .split_first()
+            .and_then(|(len, rest)| rest.get(..*len as usize))
+            .and_then(|text| String::from_utf8_lossy(text).trim().parse().ok()),
+    };
+    Some(
+        ip.map(Some)
+            .ok_or_else(|| IpDetectionError::Invalid("DNS answer is not an address".to_string())),
+    )
+}
+
+/// Names in the NS answers of a response to query `id`, `None` for other
+/// datagrams
+fn parse_names(msg: &[u8], id: u16, _kind: u16) -> Option<Result<Vec<String>, IpDetectionError>> {
+    let answers = match answers(msg, id)? {
+        Ok(answers) => answers,
+        Err(e) => return Some(Err(e)),
+    };
+    let names = answers
+        .into_iter()
+        .filter(|(rtype, _)| *rtype == TYPE_NS)
+        .map(|(_, rdata)| read_name(msg, rdata.start))
+        .collect::<Option<Vec<_>>>()
+        .ok_or_else(|| IpDetectionError::Invalid("malformed name in DNS answer".to_string()));
+    Some(names)
+}
+
+/// Response parser given the query ID and type, `None` for other datagrams
+type Parser<T> = fn(&[u8], u16, u16) -> Option<Result<T, IpDetectionError>>;
+
+/// Send the query for `name` to `server` over `socket` until it answers or
+/// `timeout` elapses, returning the answer read by `parse`
+async fn ask<T>(
+    socket: &UdpSocket,
+    server: SocketAddr,
+    name: &str,
+    (kind, class): (u16, u16),
+    timeout: Duration,
+    parse: Parser<T>,
+) -> Result<T, IpDetectionError> {
+    socket.connect(server).await?;
+    let id = RandomState::new().build_hasher().finish() as u16;
+    let request = query(id, name, kind, class);
+    let deadline = Instant::now() + timeout;
+    let mut buf = [0; 1500];
+    loop {
+        socket.send(&request).await?;
+        let retransmit = (Instant::now() + RETRANSMIT).min(deadline);
+        while let Ok(received) = timeout_at(retransmit, socket.recv(&mut buf)).await {
+            if let Some(result) = parse(&buf[..received?], id, kind) {
+                return result;
+            }
+        }
+        if Instant::now() >= deadline {
+            return Err(io::Error::new(
+                io::ErrorKind::TimedOut,
+                format!("no answer from {}", server),
+            )
+            .into());
+        }
+    }
+}
+
+/// Socket to ask `server` (`host` or `host:port`) over, and its address
+async fn connect(server: &str) -> Result<(UdpSocket, SocketAddr), IpDetectionError> {
+    let server = with_port(server);
+    let addr = lookup_host(&server).await?.next().ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("{} has no address", server),
+        )
+    })?;
+    let transport = if addr.is_ipv4() {
+        IpVersion::V4
+    } else {
+        IpVersion::V6
+    };
+    let Some(socket) = udp_socket(None, transport).await? else {
+        return Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            format!("can't reach {} over {}", addr, transport),
+        )
+        .into());
+    };
+    Ok((socket, addr))
+}
+
+/// Address `server` (`host` or `host:port`) resolves `name` to, the A record
+/// if `ipv4` and the AAAA record otherwise; `None` if there is none
+pub async fn resolve(
+    server: &str,
+    name: &str,
+    ipv4: bool,
+    timeout: Duration,
+) -> Result<Option<IpAddr>, IpDetectionError> {
+    let (socket, addr) = connect(server).await?;
+    let kind = if ipv4 { TYPE_A } else { TYPE_AAAA };
+    let name = name.trim_end_matches('.');
+    ask(
+        &socket,
+        addr,
+        name,
+        (kind, CLASS_IN),
+        timeout,
+        parse_address,
+    )
+    .await
+}
+
+/// Nameservers `server` (`host` or `host:port`) knows for `zone`, as host
+/// names
+pub async fn nameservers(
+    server: &str,
+    zone: &str,
+    timeout: Duration,
+) -> Result<Vec<String>, IpDetectionError> {
+    let (socket, addr) = connect(server).await?;
+    let zone = zone.trim_end_matches('.');
+    ask(
+        &socket,
+        addr,
+        zone,
+        (TYPE_NS, CLASS_IN),
+        timeout,
+        parse_names,
+    )
+    .await
+}
+
+#[async_trait]
+impl IpSource for DnsSource {
+    async fn detect(&self, version: IpVersion) -> Result<Option<IpAddr>, IpDetectionError> {
+        let Some(server) = self.server(version).await? else {
+            return Ok(None);
+        };
+        let Some(socket) = udp_socket(self.bind.as_ref(), version).await? else {
+            return Ok(None);
+        };
+        let ip = ask(
+            &socket,
+            server,
+            &self.name,
+            self.question(version),
+            self.timeout,
+            parse_address,
+        )
+        .await?;
+        // The TXT answer may be of the other family
+        match ip {
+            Some(ip) if ip.is_ipv4() != (version == IpVersion::V4) => {
+                Err(IpDetectionError::Invalid(format!(
+                    "{} answered {} when asked for an {} address",
+                    server, ip, version
+                )))
+            }
+            ip => Ok(ip),
+        }
+    }
+}
@@ -0,0 +1,50 @@
+//! IP source trying several sources in order.
+
+use super::{IpSource, IpVersion};
+use crate::error::IpDetectionError;
+use async_trait::async_trait;
+use log::{debug, warn};
+use std::net::IpAddr;
+use std::sync::Arc;
+
+/// Asks each source in turn until one returns an address, so a service that
+/// is down or answers garbage doesn't stop detection
+pub struct FallbackSource {
+    /// Sources with the name used in logs
+    sources: Vec<(String, Arc<dyn IpSource>)>,
+}
+
+impl FallbackSource {
+    pub fn new(sources: Vec<(String, Arc<dyn IpSource>)>) -> Self {
+        Self { sources }
+    }
+}
+
+#[async_trait]
+impl IpSource for FallbackSource {
+    async fn detect(&self, version: IpVersion) -> Result<Option<IpAddr>, IpDetectionError> {
+        // When no source answered at all, the detection may be retried
+        let mut failure = None;
+        let mut answered = false;
+        for (name, source) in &self.sources {
+            match source.detect(version).await {
+                Ok(Some(ip)) => {
+                    debug!("Detected {} address {} via {}", version, ip, name);
+                    return Ok(Some(ip));
+                }
+                Ok(None) => {
+                    debug!("{} found no {} address", name, version);
+                    answered = true;
+                }
+                Err(e) => {
+                    warn!("{}: {}", name, e);
+                    failure = Some(e);
+                }
+            }
+        }
+        match failure {
+            Some(e) if !answered => Err(e),
+            _ => Ok(None),
+        }
+    }
+}
@@ -0,0 +1,177 @@
+//! IP source reading the address another process writes to a file or named
+//! pipe, so the agent can run as a pure publisher next to a router script or
+//! in a sidecar container.
+//!
+//! A regular file is read at every detection and holds one address per
+//! line, the first of the requested family winning. A named pipe is read
+//! continuously in the background instead, and the last address of each
+//! family written to it is used.
+
+use super::{IpSource, IpVersion, truncate};
+use crate::error::IpDetectionError;
+use async_trait::async_trait;
+use std::net::IpAddr;
+use std::path::PathBuf;
+#[cfg(unix)]
+use std::sync::{Arc, Mutex};
+#[cfg(unix)]
+use tokio::task::JoinHandle;
+
+/// Delay before opening a named pipe again after it failed
+#[cfg(unix)]
+const REOPEN_DELAY: std::time::Duration = std::time::Duration::from_secs(1);
+
+/// Last addresses written to a named pipe
+#[cfg(unix)]
+#[derive(Default)]
+struct Latest {
+    v4: Option<IpAddr>,
+    v6: Option<IpAddr>,
+}
+
+/// Background task reading a named pipe, stopped with the source
+#[cfg(unix)]
+struct PipeReader {
+    latest: Arc<Mutex<Latest>>,
+    task: JoinHandle<()>,
+}
+
+#[cfg(unix)]
+impl Drop for PipeReader {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+pub struct FileSource {
+    path: PathBuf,
+    /// Background reader of a named pipe, started at the first detection
+    #[cfg(unix)]
+    pipe: Mutex<Option<PipeReader>>,
+}
+
+impl FileSource {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            #[cfg(unix)]
+            pipe: Mutex::new(None),
+        }
+    }
+
+    fn origin(&self) -> String {
+        self.path.display().to_string()
+    }
+
+    /// Latest address of `version` written to the pipe, starting the reader
+    /// if it isn't running yet
+    #[cfg(unix)]
+    fn pipe_address(&self, version: IpVersion) -> Option<IpAddr> {
+        let mut pipe = self.pipe.lock().unwrap();
+        let reader = pipe.get_or_insert_with(|| {
+            let latest = Arc::new(Mutex::new(Latest::default()));
+            let task = tokio::spawn(read_pipe(self.path.clone(), latest.clone()));
+            PipeReader { latest, task }
+        });
+        let latest = reader.latest.lock().unwrap();
+        match version {
+            IpVersion::V4 => latest.v4,
+            IpVersion::V6 => latest.v6,
+        }
+    }
+}
+
+/// Parse one line of the file, `None` for blank lines
+fn parse_line(origin: &str, line: &str) -> Result<Option<IpAddr>, IpDetectionError> {
+    let line = line.trim();
+    if line.is_empty() {
+        return Ok(None);
+    }
+    line.parse()
+        .map(Some)
+        .map_err(|_| IpDetectionError::Invalid(format!("{} holds {:?}", origin, truncate(line))))
+}
+
+fn family_matches(ip: &IpAddr, version: IpVersion) -> bool {
+    match version {
+        IpVersion::V4 => ip.is_ipv4(),
+        IpVersion::V6 => ip.is_ipv6(),
+    }
+}
+
+/// Keep reading lines from the named pipe at `path`, reopening it whenever
+/// it fails
+#[cfg(unix)]
+async fn read_pipe(path: PathBuf, latest: Arc<Mutex<Latest>>) {
+    use log::warn;
+    use tokio::io::{AsyncBufReadExt, BufReader};
+    use tokio::net::unix::pipe;
+
+    let origin = path.display().to_string();
+    loop {
+        let mut options = pipe::OpenOptions::new();
+        // Holding a write end too keeps the pipe from reporting EOF whenever
+        // the writer closes it
+        #[cfg(target_os = "linux")]
+        options.read_write(true);
+        match options.open_receiver(&path) {
+            Ok(receiver) => {
+                let mut lines = BufReader::new(receiver).lines();
+                loop {
+                    match lines.next_line().await {
+                        Ok(Some(line)) => match parse_line(&origin, &line) {
+                            Ok(Some(ip)) => {
+                                let mut latest = latest.lock().unwrap();
+                                match ip {
+                                    IpAddr::V4(_) => latest.v4 = Some(ip),
+                                    IpAddr::V6(_) => latest.v6 = Some(ip),
+                                }
+                            }
+                            Ok(None) => {}
+                            Err(e) => warn!("{}", e),
+                        },
+                        // The writer closed the pipe
+                        Ok(None) => break,
+                        Err(e) => {
+                            warn!("Failed to read {}: {}", origin, e);
+                            break;
+                        }
+                    }
+                }
+            }
+            Err(e) => warn!("Failed to open {}: {}", origin, e),
+        }
+        tokio::time::sleep(REOPEN_DELAY).await;
+    }
+}
+
+#[async_trait]
+impl IpSource for FileSource {
+    async fn detect(&self, version: IpVersion) -> Result<Option<IpAddr>, IpDetectionError> {
+        let origin = self.origin();
+        let failed = |e: std::io::Error| {
+            IpDetectionError::Invalid(format!("Failed to read {}: {}", origin, e))
+        };
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::FileTypeExt;
+            let metadata = tokio::fs::metadata(&self.path).await.map_err(failed)?;
+            if metadata.file_type().is_fifo() {
+                return Ok(self.pipe_address(version));
+            }
+        }
+
+        let contents = tokio::fs::read_to_string(&self.path)
+            .await
+            .map_err(failed)?;
+        for line in contents.lines() {
+            if let Some(ip) = parse_line(&origin, line)?
+                && family_matches(&ip, version)
+            {
+                return Ok(Some(ip));
+            }
+        }
+        Ok(None)
+    }
+}
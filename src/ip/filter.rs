@@ -0,0 +1,173 @@
+//! Rejects detected addresses that can't be the public IP.
+
+use super::interface::{Prefix, is_global};
+use super::{IpSource, IpVersion};
+use crate::error::IpDetectionError;
+use async_trait::async_trait;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::sync::Arc;
+
+/// Reserved IPv4 ranges not covered by [`is_global`]
+const RESERVED_V4: &[(Ipv4Addr, u8)] = &[
+    (Ipv4Addr::new(0, 0, 0, 0), 8),
+    (Ipv4Addr::new(192, 0, 0, 0), 24),
+    (Ipv4Addr::new(192, 0, 2, 0), 24),
+    (Ipv4Addr::new(198, 18, 0, 0), 15),
+    (Ipv4Addr::new(198, 51, 100, 0), 24),
+    (Ipv4Addr::new(203, 0, 113, 0), 24),
+    (Ipv4Addr::new(240, 0, 0, 0), 4),
+];
+/// Documentation prefix inside the global unicast range
+const DOCUMENTATION_V6: Ipv6Addr = Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 0);
+
+/// Whether `ip` is private, shared (CGNAT), reserved or otherwise never
+/// routed on the internet
+pub fn is_bogon(ip: IpAddr) -> bool {
+    let in_prefix = |network: IpAddr, len: u8| Prefix::new(network, len).contains(ip);
+    if !is_global(ip) {
+        return true;
+    }
+    match ip {
+        IpAddr::V4(_) => RESERVED_V4
+            .iter()
+            .any(|&(network, len)| in_prefix(network.into(), len)),
+        // Only 2000::/3 is allocated for global unicast
+        IpAddr::V6(_) => {
+            !in_prefix(Ipv6Addr::new(0x2000, 0, 0, 0, 0, 0, 0, 0).into(), 3)
+                || in_prefix(DOCUMENTATION_V6.into(), 32)
+        }
+    }
+}
+
+/// Addresses accepted as the public IP: no bogons unless explicitly allowed,
+/// and nothing inside a denied prefix
+#[derive(Debug, Clone, Default)]
+pub struct AddressFilter {
+    allow: Vec<Prefix>,
+    deny: Vec<Prefix>,
+}
+
+impl AddressFilter {
+    pub fn new(allow: Vec<Prefix>, deny: Vec<Prefix>) -> Self {
+        Self { allow, deny }
+    }
+
+    /// Why `ip` must not be published, `None` if it may
+    pub fn rejects(&self, ip: IpAddr) -> Option<String> {
+        if let Some(prefix) = self.deny.iter().find(|p| p.contains(ip)) {
+            return Some(format!("{} is inside the denied prefix {}", ip, prefix));
+        }
+        if self.allow.iter().any(|p| p.contains(ip)) || !is_bogon(ip) {
+            return None;
+        }
+        Some(format!(
+            "{} is a private or reserved address, not a public IP",
+            ip
+        ))
+    }
+}
+
+/// Passes on the addresses of a source that the filter accepts and turns
+/// the others into errors
+pub struct FilteredSource {
+    source: Arc<dyn IpSource>,
+    filter: Arc<AddressFilter>,
+}
+
+impl FilteredSource {
+    pub fn new(source: Arc<dyn IpSource>, filter: Arc<AddressFilter>) -> Self {
+        Self { source, filter }
+    }
+}
+
+#[async_trait]
+impl IpSource for FilteredSource {
+    async fn detect(&self, version: IpVersion) -> Result<Option<IpAddr>, IpDetectionError> {
+        match self.source.detect(version).await? {
+            Some(ip) => match self.filter.rejects(ip) {
+                Some(reason) => Err(IpDetectionError::Invalid(reason)),
+                None => Ok(Some(ip)),
+            },
+            None => Ok(None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ip(s: &str) -> IpAddr {
+        s.parse().unwrap()
+    }
+
+    fn prefixes(list: &[&str]) -> Vec<Prefix> {
+        list.iter().map(|p| p.parse().unwrap()).collect()
+    }
+
+    #[test]
+    fn public_addresses_are_no_bogons() {
+        for addr in ["8.8.8.8", "1.1.1.1", "2606:4700:4700::1111", "2a00:1450::1"] {
+            assert!(!is_bogon(ip(addr)), "{}", addr);
+        }
+    }
+
+    #[test]
+    fn private_and_reserved_addresses_are_bogons() {
+        for addr in [
+            "10.0.0.1",
+            "127.0.0.1",
+            "169.254.1.1",
+            "172.16.0.1",
+            "192.168.1.1",
+            "100.64.0.1",
+            "0.1.2.3",
+            "192.0.2.1",
+            "203.0.113.7",
+            "240.0.0.1",
+            "255.255.255.255",
+            "::1",
+            "fe80::1",
+            "fd00::1",
+            "2001:db8::1",
+            "4000::1",
+        ] {
+            assert!(is_bogon(ip(addr)), "{}", addr);
+        }
+    }
+
+    #[test]
+    fn bogon_ranges_end_at_their_boundaries() {
+        assert!(!is_bogon(ip("198.17.255.255")));
+        assert!(is_bogon(ip("198.18.0.0")));
+        assert!(is_bogon(ip("198.19.255.255")));
+        assert!(!is_bogon(ip("198.20.0.0")));
+        assert!(!is_bogon(ip("100.63.255.255")));
+        assert!(!is_bogon(ip("100.128.0.0")));
+        assert!(!is_bogon(ip("2001:db9::1")));
+        assert!(!is_bogon(ip("3fff:ffff::1")));
+    }
+
+    #[test]
+    fn filter_rejects_bogons_unless_allowed() {
+        let filter = AddressFilter::default();
+        assert!(filter.rejects(ip("192.168.1.1")).is_some());
+        assert!(filter.rejects(ip("8.8.8.8")).is_none());
+
+        let filter = AddressFilter::new(prefixes(&["192.168.0.0/16"]), Vec::new());
+        assert!(filter.rejects(ip("192.168.1.1")).is_none());
+        assert!(filter.rejects(ip("10.0.0.1")).is_some());
+    }
+
+    #[test]
+    fn denied_prefixes_win_over_allowed_ones() {
+        let filter = AddressFilter::new(
+            prefixes(&["10.0.0.0/8"]),
+            prefixes(&["10.1.0.0/16", "8.8.8.0/24"]),
+        );
+        assert!(filter.rejects(ip("10.1.2.3")).is_some());
+        assert!(filter.rejects(ip("10.2.0.1")).is_none());
+        assert!(filter.rejects(ip("8.8.8.8")).is_some());
+        assert!(filter.rejects(ip("8.8.9.0")).is_none());
+    }
+}
@@ -0,0 +1,114 @@
+//! IP source asking a Fritz!Box for its addresses over TR-064, using the
+//! IGD service with AVM extensions.
+//!
+//! IPv4 is the WAN address of the router. For IPv6 the router reports the
+//! prefix delegated by the ISP, and the address of this host inside it is
+//! used, so records point at the host rather than the router. Hosts without
+//! an address in the prefix get the router's own WAN IPv6 address.
+
+use super::interface::{Prefix, address_in};
+use super::upnp::{soap_call, xml_value};
+use super::{IpSource, IpVersion};
+use crate::error::{ConfigError, IpDetectionError};
+use async_trait::async_trait;
+use reqwest::{Client, Url};
+use std::io;
+use std::net::IpAddr;
+use std::time::Duration;
+
+const DEFAULT_HOST: &str = "fritz.box";
+const TR064_PORT: u16 = 49000;
+const CONTROL_PATH: &str = "/igdupnp/control/WANIPConn1";
+const SERVICE: &str = "urn:schemas-upnp-org:service:WANIPConnection:1";
+
+pub struct FritzBoxSource {
+    client: Client,
+    control_url: String,
+    timeout: Duration,
+}
+
+impl FritzBoxSource {
+    /// `host` is the router's name or address with an optional port,
+    /// `fritz.box:49000` when empty
+    pub fn new(host: &str, timeout: Duration) -> Result<Self, ConfigError> {
+        let host = if host.is_empty() { DEFAULT_HOST } else { host };
+        let mut url = Url::parse(&format!("http://{}{}", host, CONTROL_PATH))
+            .map_err(|e| ConfigError::Invalid(format!("Invalid Fritz!Box {}: {}", host, e)))?;
+        if url.port().is_none() {
+            let _ = url.set_port(Some(TR064_PORT));
+        }
+        Ok(Self {
+            client: Client::new(),
+            control_url: url.to_string(),
+            timeout,
+        })
+    }
+
+    /// Call `action` and parse the `field` of the answer, `None` when empty
+    async fn query(&self, action: &str, field: &str) -> Result<Option<String>, IpDetectionError> {
+        let answer = soap_call(&self.client, &self.control_url, SERVICE, action).await?;
+        Ok(xml_value(&answer, field)
+            .filter(|v| !v.is_empty())
+            .map(str::to_string))
+    }
+
+    async fn ipv4(&self) -> Result<Option<IpAddr>, IpDetectionError> {
+        match self
+            .query("GetExternalIPAddress", "NewExternalIPAddress")
+            .await?
+        {
+            Some(ip) => parse(&ip).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    async fn ipv6(&self) -> Result<Option<IpAddr>, IpDetectionError> {
+        let answer = soap_call(
+            &self.client,
+            &self.control_url,
+            SERVICE,
+            "X_AVM_DE_GetIPv6Prefix",
+        )
+        .await?;
+        let network = xml_value(&answer, "NewIPv6Prefix").unwrap_or_default();
+        let len = xml_value(&answer, "NewPrefixLength").unwrap_or_default();
+        // No delegated prefix, the connection has no IPv6
+        if network.is_empty() {
+            return Ok(None);
+        }
+        let prefix: Prefix = format!("{}/{}", network, len).parse().map_err(|_| {
+            IpDetectionError::Invalid(format!("router answered {}/{} as its prefix", network, len))
+        })?;
+        if let Some(ip) = address_in(&prefix)? {
+            return Ok(Some(ip));
+        }
+
+        match self
+            .query("X_AVM_DE_GetExternalIPv6Address", "NewExternalIPv6Address")
+            .await?
+        {
+            Some(ip) => parse(&ip).map(Some),
+            None => Ok(None),
+        }
+    }
+}
+
+fn parse(ip: &str) -> Result<IpAddr, IpDetectionError> {
+    ip.parse()
+        .map_err(|_| IpDetectionError::Invalid(format!("router answered {:?} as its address", ip)))
+}
+
+#[async_trait]
+impl IpSource for FritzBoxSource {
+    async fn detect(&self, version: IpVersion) -> Result<Option<IpAddr>, IpDetectionError> {
+        let detect = async {
+            match version {
+                IpVersion::V4 => self.ipv4().await,
+                IpVersion::V6 => self.ipv6().await,
+            }
+        };
+        tokio::time::timeout(self.timeout, detect)
+            .await
+            .map_err(|_| io::Error::new(io::ErrorKind::TimedOut, "no answer from the Fritz!Box"))?
+    }
+}
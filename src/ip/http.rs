@@ -0,0 +1,145 @@
+//! IP source asking a "what is my IP" web service, e.g. `https://ifconfig.me/ip`.
+//!
+//! The service answers with the bare address as plain text, unless a regex
+//! or a JSON pointer picks it out of a larger answer such as a router status
+//! page or `{"ip":"1.2.3.4"}`. Requests for each family go out from a socket
+//! bound to that family, so services reachable over both IPv4 and IPv6 can
+//! answer for either.
+
+use super::bind::Bind;
+use super::{IpSource, IpVersion, parse_answer};
+use crate::error::IpDetectionError;
+use async_trait::async_trait;
+use regex::Regex;
+use reqwest::Client;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::time::Duration;
+
+/// Where the address is in the answer
+enum Extract {
+    /// The whole body
+    Body,
+    /// First match (or its first group) holding an address of the family
+    Regex(Regex),
+    /// String at a JSON pointer, e.g. `/ip`
+    JsonPointer(String),
+}
+
+pub struct HttpSource {
+    url: String,
+    timeout: Duration,
+    /// Clients sending requests over each family, `None` for the family
+    /// a bound address doesn't belong to
+    v4: Option<Client>,
+    v6: Option<Client>,
+    extract: Extract,
+}
+
+/// Client sending requests from `local`, over `interface` if given
+fn client(local: IpAddr, interface: Option<&str>) -> Result<Client, reqwest::Error> {
+    let builder = Client::builder().local_address(local);
+    #[cfg(target_os = "linux")]
+    let builder = match interface {
+        Some(interface) => builder.interface(interface),
+        None => builder,
+    };
+    #[cfg(not(target_os = "linux"))]
+    let _ = interface;
+    builder.build()
+}
+
+impl HttpSource {
+    pub fn new(url: impl Into<String>, timeout: Duration) -> Result<Self, reqwest::Error> {
+        Ok(Self {
+            url: url.into(),
+            timeout,
+            v4: Some(client(Ipv4Addr::UNSPECIFIED.into(), None)?),
+            v6: Some(client(Ipv6Addr::UNSPECIFIED.into(), None)?),
+            extract: Extract::Body,
+        })
+    }
+
+    /// Send requests from this local address or interface
+    pub fn with_bind(mut self, bind: Bind) -> Result<Self, reqwest::Error> {
+        let interface = match &bind {
+            Bind::Interface(interface) => Some(interface.as_str()),
+            Bind::Address(_) => None,
+        };
+        let client = |version| {
+            Bind::local(Some(&bind), version)
+                .map(|local| client(local, interface))
+                .transpose()
+        };
+        self.v4 = client(IpVersion::V4)?;
+        self.v6 = client(IpVersion::V6)?;
+        Ok(self)
+    }
+
+    /// Take the address from the first match of `regex`, or of its first
+    /// capture group, that is an address of the requested family
+    pub fn with_regex(mut self, regex: Regex) -> Self {
+        self.extract = Extract::Regex(regex);
+        self
+    }
+
+    /// Take the address from the JSON string at `pointer`, e.g. `/ip`
+    pub fn with_json_pointer(mut self, pointer: impl Into<String>) -> Self {
+        self.extract = Extract::JsonPointer(pointer.into());
+        self
+    }
+
+    fn extract(&self, body: &str, version: IpVersion) -> Result<IpAddr, IpDetectionError> {
+        match &self.extract {
+            Extract::Body => parse_answer(&self.url, body, version),
+            Extract::Regex(regex) => regex
+                .captures_iter(body)
+                .filter_map(|c| c.get(1).or_else(|| c.get(0)))
+                .find_map(|m| parse_answer(&self.url, m.as_str(), version).ok())
+                .ok_or_else(|| {
+                    IpDetectionError::Invalid(format!(
+                        "{} answered without an {} address matching {}",
+                        self.url, version, regex
+                    ))
+                }),
+            Extract::JsonPointer(pointer) => {
+                let json: serde_json::Value = serde_json::from_str(body).map_err(|e| {
+                    IpDetectionError::Invalid(format!("{} answered invalid JSON: {}", self.url, e))
+                })?;
+                let value = json
+                    .pointer(pointer)
+                    .and_then(|v| v.as_str())
+                    .ok_or_else(|| {
+                        IpDetectionError::Invalid(format!(
+                            "{} answered without a string at {}",
+                            self.url, pointer
+                        ))
+                    })?;
+                parse_answer(&self.url, value, version)
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl IpSource for HttpSource {
+    async fn detect(&self, version: IpVersion) -> Result<Option<IpAddr>, IpDetectionError> {
+        let client = match version {
+            IpVersion::V4 => &self.v4,
+            IpVersion::V6 => &self.v6,
+        };
+        // Bound to an address of the other family
+        let Some(client) = client else {
+            return Ok(None);
+        };
+        let body = client
+            .get(&self.url)
+            .timeout(self.timeout)
+            .send()
+            .await?
+            .error_for_status()?
+            .text()
+            .await?;
+
+        self.extract(&body, version).map(Some)
+    }
+}
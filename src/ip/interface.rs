@@ -0,0 +1,180 @@
+//! IP source reading the address of a local network interface, for hosts
+//! with a public address directly on an interface (no NAT).
+//!
+//! Only global addresses count, so private, loopback, link-local and unique
+//! local addresses are skipped unless they are inside the preferred prefix.
+//! With several candidates, addresses inside the preferred prefix win, then
+//! stable addresses over temporary (privacy extension) and deprecated ones.
+
+use super::cgnat::is_shared;
+use super::{IpSource, IpVersion};
+use crate::error::{ConfigError, IpDetectionError};
+use async_trait::async_trait;
+use std::fmt;
+use std::net::IpAddr;
+#[cfg(target_os = "linux")]
+use std::net::Ipv6Addr;
+
+/// `IFA_F_TEMPORARY` in `/proc/net/if_inet6`
+#[cfg(target_os = "linux")]
+const FLAG_TEMPORARY: u8 = 0x01;
+/// `IFA_F_DEPRECATED` in `/proc/net/if_inet6`
+#[cfg(target_os = "linux")]
+const FLAG_DEPRECATED: u8 = 0x20;
+
+/// Network prefix such as `2001:db8:1::/48`
+#[derive(Debug, Clone, Copy)]
+pub struct Prefix {
+    network: IpAddr,
+    len: u8,
+}
+
+impl Prefix {
+    pub(crate) fn new(network: IpAddr, len: u8) -> Self {
+        Prefix { network, len }
+    }
+
+    pub(crate) fn contains(&self, ip: IpAddr) -> bool {
+        let bits = |ip: IpAddr| match ip {
+            IpAddr::V4(ip) => (u128::from(ip.to_bits()) << 96, 32),
+            IpAddr::V6(ip) => (ip.to_bits(), 128),
+        };
+        let ((network, width), (ip, ip_width)) = (bits(self.network), bits(ip));
+        if width != ip_width {
+            return false;
+        }
+        let mask = u128::MAX
+            .checked_shl(128 - u32::from(self.len))
+            .unwrap_or(0);
+        network & mask == ip & mask
+    }
+}
+
+impl fmt::Display for Prefix {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.network, self.len)
+    }
+}
+
+impl std::str::FromStr for Prefix {
+    type Err = ConfigError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || ConfigError::Invalid(format!("Invalid prefix: {}", s));
+        let (network, len) = s.split_once('/').ok_or_else(invalid)?;
+        let network: IpAddr = network.parse().map_err(|_| invalid())?;
+        let len: u8 = len.parse().map_err(|_| invalid())?;
+        let max = if network.is_ipv4() { 32 } else { 128 };
+        if len > max {
+            return Err(invalid());
+        }
+        Ok(Prefix { network, len })
+    }
+}
+
+pub struct InterfaceSource {
+    interface: String,
+    prefix: Option<Prefix>,
+}
+
+impl InterfaceSource {
+    pub fn new(interface: impl Into<String>, prefix: Option<Prefix>) -> Self {
+        Self {
+            interface: interface.into(),
+            prefix,
+        }
+    }
+}
+
+/// Whether `ip` is reachable from the internet
+pub(crate) fn is_global(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            !(ip.is_private()
+                || ip.is_loopback()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_broadcast()
+                || ip.is_multicast()
+                || is_shared(ip))
+        }
+        IpAddr::V6(ip) => {
+            !(ip.is_loopback()
+                || ip.is_unspecified()
+                || ip.is_multicast()
+                || ip.is_unicast_link_local()
+                || ip.is_unique_local())
+        }
+    }
+}
+
+/// Flags of the IPv6 addresses of `interface`, from `/proc/net/if_inet6`
+#[cfg(target_os = "linux")]
+fn ipv6_flags(interface: &str) -> Vec<(Ipv6Addr, u8)> {
+    let Ok(table) = std::fs::read_to_string("/proc/net/if_inet6") else {
+        return Vec::new();
+    };
+    table
+        .lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            if fields.len() < 6 || fields[5] != interface {
+                return None;
+            }
+            let ip = u128::from_str_radix(fields[0], 16).ok()?;
+            let flags = u8::from_str_radix(fields[4], 16).ok()?;
+            Some((Ipv6Addr::from_bits(ip), flags))
+        })
+        .collect()
+}
+
+/// Whether `ip` is a temporary or deprecated address, which should only be
+/// used when nothing else is there
+fn is_transient(ip: IpAddr, interface: &str) -> bool {
+    #[cfg(target_os = "linux")]
+    if let IpAddr::V6(ip) = ip {
+        return ipv6_flags(interface)
+            .iter()
+            .any(|(addr, flags)| *addr == ip && flags & (FLAG_TEMPORARY | FLAG_DEPRECATED) != 0);
+    }
+    let _ = (ip, interface);
+    false
+}
+
+/// Best global address of this host inside `prefix`, on any interface
+pub(crate) fn address_in(prefix: &Prefix) -> Result<Option<IpAddr>, IpDetectionError> {
+    Ok(if_addrs::get_if_addrs()?
+        .into_iter()
+        .filter(|i| is_global(i.ip()) && prefix.contains(i.ip()))
+        .min_by_key(|i| is_transient(i.ip(), &i.name))
+        .map(|i| i.ip()))
+}
+
+#[async_trait]
+impl IpSource for InterfaceSource {
+    async fn detect(&self, version: IpVersion) -> Result<Option<IpAddr>, IpDetectionError> {
+        let addresses: Vec<IpAddr> = if_addrs::get_if_addrs()?
+            .into_iter()
+            .filter(|i| i.name == self.interface)
+            .map(|i| i.ip())
+            .collect();
+        if addresses.is_empty() {
+            return Err(IpDetectionError::Invalid(format!(
+                "interface {} not found or without addresses",
+                self.interface
+            )));
+        }
+
+        Ok(addresses
+            .into_iter()
+            .filter(|ip| match version {
+                IpVersion::V4 => ip.is_ipv4(),
+                IpVersion::V6 => ip.is_ipv6(),
+            })
+            .filter(|ip| is_global(*ip) || self.prefix.is_some_and(|p| p.contains(*ip)))
+            .min_by_key(|ip| {
+                let outside_prefix = self.prefix.is_some_and(|p| !p.contains(*ip));
+                (outside_prefix, is_transient(*ip, &self.interface))
+            }))
+    }
+}
@@ -0,0 +1,113 @@
+//! IP source asking a MikroTik router through the RouterOS v7 REST API.
+//!
+//! Without an interface the address comes from `/ip/cloud`, which reports
+//! the address the router's traffic leaves with (IP Cloud has to be
+//! enabled). With one, the global address assigned to that interface is
+//! read from `/ip/address` or `/ipv6/address`, which keeps working when
+//! "what is my IP" traffic is policy routed through a VPN.
+
+use super::interface::is_global;
+use super::{IpSource, IpVersion, parse_answer};
+use crate::error::IpDetectionError;
+use async_trait::async_trait;
+use reqwest::Client;
+use serde::Deserialize;
+use std::net::IpAddr;
+use std::time::Duration;
+
+#[derive(Deserialize)]
+struct Cloud {
+    #[serde(rename = "public-address")]
+    public_address: Option<String>,
+    #[serde(rename = "public-address-ipv6")]
+    public_address_ipv6: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Address {
+    /// Address with prefix length, e.g. `203.0.113.5/24`
+    address: String,
+    /// RouterOS reports flags as `"true"` or `"false"`
+    #[serde(default)]
+    disabled: String,
+    #[serde(default)]
+    invalid: String,
+}
+
+pub struct MikrotikSource {
+    client: Client,
+    base_url: String,
+    username: String,
+    password: String,
+    interface: Option<String>,
+    timeout: Duration,
+}
+
+impl MikrotikSource {
+    /// `base_url` is the router's web service, e.g. `https://192.168.88.1`
+    pub fn new(
+        base_url: &str,
+        username: impl Into<String>,
+        password: impl Into<String>,
+        interface: Option<String>,
+        timeout: Duration,
+    ) -> Self {
+        Self {
+            client: Client::new(),
+            base_url: base_url.trim_end_matches('/').to_string(),
+            username: username.into(),
+            password: password.into(),
+            interface,
+            timeout,
+        }
+    }
+
+    async fn get<T: serde::de::DeserializeOwned>(
+        &self,
+        path: &str,
+        query: &[(&str, &str)],
+    ) -> Result<T, IpDetectionError> {
+        Ok(self
+            .client
+            .get(format!("{}/rest{}", self.base_url, path))
+            .query(query)
+            .basic_auth(&self.username, Some(&self.password))
+            .timeout(self.timeout)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?)
+    }
+}
+
+#[async_trait]
+impl IpSource for MikrotikSource {
+    async fn detect(&self, version: IpVersion) -> Result<Option<IpAddr>, IpDetectionError> {
+        let Some(interface) = &self.interface else {
+            let cloud: Cloud = self.get("/ip/cloud", &[]).await?;
+            let address = match version {
+                IpVersion::V4 => cloud.public_address,
+                IpVersion::V6 => cloud.public_address_ipv6,
+            };
+            return match address.filter(|a| !a.is_empty()) {
+                Some(address) => parse_answer(&self.base_url, &address, version).map(Some),
+                None => Ok(None),
+            };
+        };
+
+        let path = match version {
+            IpVersion::V4 => "/ip/address",
+            IpVersion::V6 => "/ipv6/address",
+        };
+        let addresses: Vec<Address> = self.get(path, &[("interface", interface)]).await?;
+        Ok(addresses
+            .iter()
+            .filter(|a| a.disabled != "true" && a.invalid != "true")
+            .filter_map(|a| {
+                let ip = a.address.split('/').next()?;
+                parse_answer(&self.base_url, ip, version).ok()
+            })
+            .find(|ip| is_global(*ip)))
+    }
+}
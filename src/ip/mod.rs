@@ -0,0 +1,361 @@
+//! Public IP detection.
+
+use crate::config::{IpConfig, IpSourceConfig};
+use crate::error::{ConfigError, IpDetectionError};
+use async_trait::async_trait;
+use regex::Regex;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fmt;
+use std::net::IpAddr;
+use std::sync::Arc;
+use std::time::Duration;
+
+pub mod bind;
+pub mod cgnat;
+pub mod command;
+pub mod consensus;
+pub mod dns;
+pub mod fallback;
+pub mod file;
+pub mod filter;
+pub mod fritzbox;
+pub mod http;
+pub mod interface;
+pub mod mikrotik;
+pub mod natpmp;
+pub mod opnsense;
+pub mod pfsense;
+pub mod public;
+pub mod stun;
+pub mod upnp;
+
+pub use bind::Bind;
+pub use cgnat::CgnatCheck;
+pub use command::CommandSource;
+pub use consensus::ConsensusSource;
+pub use dns::DnsSource;
+pub use fallback::FallbackSource;
+pub use file::FileSource;
+pub use filter::{AddressFilter, FilteredSource};
+pub use fritzbox::FritzBoxSource;
+pub use http::HttpSource;
+pub use interface::InterfaceSource;
+pub use mikrotik::MikrotikSource;
+pub use natpmp::NatPmpSource;
+pub use opnsense::OpnsenseSource;
+pub use pfsense::PfsenseSource;
+pub use public::PublicIpSource;
+pub use stun::StunSource;
+pub use upnp::UpnpSource;
+
+/// Address family to detect.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum IpVersion {
+    V4,
+    V6,
+}
+
+impl fmt::Display for IpVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            IpVersion::V4 => "IPv4",
+            IpVersion::V6 => "IPv6",
+        })
+    }
+}
+
+/// Which address families a record is kept in sync with.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum IpMode {
+    /// A record only
+    #[default]
+    Ipv4,
+    /// AAAA record only
+    Ipv6,
+    /// A and AAAA records, tracked independently
+    Dual,
+}
+
+impl IpMode {
+    pub fn versions(&self) -> &'static [IpVersion] {
+        match self {
+            IpMode::Ipv4 => &[IpVersion::V4],
+            IpMode::Ipv6 => &[IpVersion::V6],
+            IpMode::Dual => &[IpVersion::V4, IpVersion::V6],
+        }
+    }
+}
+
+impl std::str::FromStr for IpMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "ipv4" | "v4" | "4" => Ok(IpMode::Ipv4),
+            "ipv6" | "v6" | "6" => Ok(IpMode::Ipv6),
+            "dual" => Ok(IpMode::Dual),
+            _ => Err(format!(
+                "invalid IP version: {} (expected ipv4, ipv6 or dual)",
+                s
+            )),
+        }
+    }
+}
+
+#[async_trait]
+pub trait IpSource: Send + Sync {
+    /// Detect the current public address of the given family.
+    ///
+    /// Returns `Ok(None)` when the source could not determine an address.
+    async fn detect(&self, version: IpVersion) -> Result<Option<IpAddr>, IpDetectionError>;
+}
+
+/// Parse the address `origin` answered with, which has to be of the
+/// requested family
+pub(crate) fn parse_answer(
+    origin: &str,
+    answer: &str,
+    version: IpVersion,
+) -> Result<IpAddr, IpDetectionError> {
+    let answer = answer.trim();
+    let ip: IpAddr = answer.parse().map_err(|_| {
+        IpDetectionError::Invalid(format!("{} answered {:?}", origin, truncate(answer)))
+    })?;
+    let family_matches = match version {
+        IpVersion::V4 => ip.is_ipv4(),
+        IpVersion::V6 => ip.is_ipv6(),
+    };
+    if !family_matches {
+        return Err(IpDetectionError::Invalid(format!(
+            "{} answered {} when asked for an {} address",
+            origin, ip, version
+        )));
+    }
+    Ok(ip)
+}
+
+/// Start of a garbage answer, e.g. an HTML error page, for the logs
+fn truncate(answer: &str) -> String {
+    match answer.char_indices().nth(60) {
+        Some((end, _)) => format!("{}...", &answer[..end]),
+        None => answer.to_string(),
+    }
+}
+
+/// Web interface of a router, HTTPS unless a URL is given
+fn router_url(target: &str) -> String {
+    if target.contains("://") {
+        target.to_string()
+    } else {
+        format!("https://{}", target)
+    }
+}
+
+/// `username` and `password` of a source logging in to a router
+fn credentials(source: &IpSourceConfig) -> Result<(&str, &str), ConfigError> {
+    match (&source.username, &source.password) {
+        (Some(username), Some(password)) => Ok((username, password)),
+        _ => Err(ConfigError::Missing(format!(
+            "IP source {} needs `username` and `password`",
+            source.url
+        ))),
+    }
+}
+
+/// Build a single `[[ip.sources]]` entry
+fn build(source: &IpSourceConfig) -> Result<Arc<dyn IpSource>, ConfigError> {
+    let timeout = Duration::from_millis(source.timeout_ms);
+    let (scheme, target) = source.url.split_once(':').unwrap_or_default();
+    let target = target.trim_start_matches("//");
+    let bind: Option<Bind> = source.bind.as_deref().map(str::parse).transpose()?;
+    if bind.is_some() && !matches!(scheme, "http" | "https" | "stun" | "dns") {
+        return Err(ConfigError::Invalid(format!(
+            "IP source {} can't be bound, `bind` only applies to http://, https://, stun: and \
+             dns: sources",
+            source.url
+        )));
+    }
+    Ok(match scheme {
+        "http" | "https" => {
+            let invalid = |e: &dyn fmt::Display| {
+                ConfigError::Invalid(format!("IP source {}: {}", source.url, e))
+            };
+            let mut http = HttpSource::new(&source.url, timeout).map_err(|e| invalid(&e))?;
+            if let Some(bind) = bind {
+                http = http.with_bind(bind).map_err(|e| invalid(&e))?;
+            }
+            Arc::new(match (&source.regex, &source.json_pointer) {
+                (None, None) => http,
+                (Some(regex), None) => http.with_regex(Regex::new(regex).map_err(|e| invalid(&e))?),
+                (None, Some(pointer)) => http.with_json_pointer(pointer),
+                (Some(_), Some(_)) => {
+                    return Err(invalid(&"set either `regex` or `json_pointer`, not both"));
+                }
+            })
+        }
+        "stun" => {
+            let stun = StunSource::new(target, timeout);
+            Arc::new(match bind {
+                Some(bind) => stun.with_bind(bind),
+                None => stun,
+            })
+        }
+        "dns" => {
+            let dns = match target.split_once('@') {
+                Some((name, server)) => DnsSource::new(name, server, timeout),
+                None if target == "opendns" => DnsSource::opendns(timeout),
+                None if target == "cloudflare" => DnsSource::cloudflare(timeout),
+                None => {
+                    return Err(ConfigError::Invalid(format!(
+                        "Unsupported IP source {} (expected dns:opendns, dns:cloudflare or \
+                         dns:name@server)",
+                        source.url
+                    )));
+                }
+            };
+            Arc::new(match bind {
+                Some(bind) => dns.with_bind(bind),
+                None => dns,
+            })
+        }
+        "interface" => Arc::new(InterfaceSource::new(
+            target,
+            source.prefix.as_deref().map(str::parse).transpose()?,
+        )),
+        "natpmp" => {
+            let gateway = match target {
+                "" => None,
+                gateway => Some(gateway.parse().map_err(|_| {
+                    ConfigError::Invalid(format!("Invalid NAT-PMP gateway: {}", gateway))
+                })?),
+            };
+            Arc::new(NatPmpSource::new(gateway, timeout))
+        }
+        "command" => Arc::new(CommandSource::new(target, timeout)),
+        "file" => Arc::new(FileSource::new(target)),
+        "mikrotik" => {
+            let (username, password) = credentials(source)?;
+            Arc::new(MikrotikSource::new(
+                &router_url(target),
+                username,
+                password,
+                source.interface.clone(),
+                timeout,
+            ))
+        }
+        "opnsense" => {
+            let (key, secret) = credentials(source)?;
+            let interface = source.interface.as_deref().ok_or_else(|| {
+                ConfigError::Missing(format!(
+                    "IP source {} needs the `interface` device, e.g. igb0",
+                    source.url
+                ))
+            })?;
+            Arc::new(OpnsenseSource::new(
+                &router_url(target),
+                key,
+                secret,
+                interface,
+                timeout,
+            ))
+        }
+        "pfsense" => {
+            let api_key = source.api_key.as_deref().ok_or_else(|| {
+                ConfigError::Missing(format!("IP source {} needs `api_key`", source.url))
+            })?;
+            Arc::new(PfsenseSource::new(
+                &router_url(target),
+                api_key,
+                source.interface.as_deref().unwrap_or("wan"),
+                timeout,
+            ))
+        }
+        "fritzbox" => Arc::new(FritzBoxSource::new(target, timeout)?),
+        "upnp" => Arc::new(UpnpSource::new(
+            Some(target.to_string()).filter(|t| !t.is_empty()),
+            timeout,
+        )),
+        _ => {
+            return Err(ConfigError::Invalid(format!(
+                "Unsupported IP source {} (expected an http://, https://, stun:, dns:, \
+                 interface:, upnp:, natpmp:, fritzbox:, mikrotik:, opnsense:, pfsense:, \
+                 command: or file: URL)",
+                source.url
+            )));
+        }
+    })
+}
+
+/// Filter applied to every source of `config`
+fn filter(config: &IpConfig) -> Result<Arc<AddressFilter>, ConfigError> {
+    let prefixes = |list: &[String]| list.iter().map(|p| p.parse()).collect::<Result<_, _>>();
+    Ok(Arc::new(AddressFilter::new(
+        prefixes(&config.allow)?,
+        prefixes(&config.deny)?,
+    )))
+}
+
+/// Sources with the name used in logs
+type Chain = Vec<(String, Arc<dyn IpSource>)>;
+
+/// Build `sources`, each behind `filter`
+fn chain(sources: &[IpSourceConfig], filter: &Arc<AddressFilter>) -> Result<Chain, ConfigError> {
+    sources
+        .iter()
+        .map(|source| {
+            let filtered: Arc<dyn IpSource> =
+                Arc::new(FilteredSource::new(build(source)?, filter.clone()));
+            Ok((source.url.clone(), filtered))
+        })
+        .collect()
+}
+
+/// Build the IP source described by `config`
+pub fn from_config(config: &IpConfig) -> Result<Arc<dyn IpSource>, ConfigError> {
+    let filter = filter(config)?;
+    let wan = match &config.wan {
+        Some(wan) => Some((wan.url.clone(), build(wan)?)),
+        None => None,
+    };
+    let source: Arc<dyn IpSource> = if config.sources.is_empty() && config.quorum.is_none() {
+        Arc::new(FilteredSource::new(Arc::new(PublicIpSource), filter))
+    } else {
+        let sources = chain(&config.sources, &filter)?;
+        match config.quorum {
+            None => Arc::new(FallbackSource::new(sources)),
+            Some(quorum) if quorum == 0 || quorum > sources.len() => {
+                return Err(ConfigError::Invalid(format!(
+                    "IP source quorum of {} must be between 1 and the number of sources ({})",
+                    quorum,
+                    sources.len()
+                )));
+            }
+            Some(quorum) => Arc::new(ConsensusSource::new(sources, quorum)),
+        }
+    };
+    Ok(Arc::new(CgnatCheck::new(source, wan)))
+}
+
+/// Build the `[ip.named]` sources records can use instead of the default
+/// one, each asking its sources in order
+pub fn named_from_config(
+    config: &IpConfig,
+) -> Result<HashMap<String, Arc<dyn IpSource>>, ConfigError> {
+    let filter = filter(config)?;
+    config
+        .named
+        .iter()
+        .map(|(name, sources)| {
+            if sources.is_empty() {
+                return Err(ConfigError::Missing(format!(
+                    "IP source {} under [ip.named] has no sources",
+                    name
+                )));
+            }
+            let source: Arc<dyn IpSource> = Arc::new(FallbackSource::new(chain(sources, &filter)?));
+            Ok((name.clone(), source))
+        })
+        .collect()
+}
@@ -0,0 +1,97 @@
+//! IP source asking the router for its WAN address over NAT-PMP (RFC 6886).
+//!
+//! Only IPv4 has a WAN address to report; IPv6 detection returns nothing.
+
+use super::{IpSource, IpVersion};
+use crate::error::IpDetectionError;
+use async_trait::async_trait;
+use std::io;
+use std::net::{IpAddr, Ipv4Addr};
+use std::time::Duration;
+use tokio::net::UdpSocket;
+use tokio::time::{Instant, timeout_at};
+
+const NATPMP_PORT: u16 = 5351;
+/// First retransmission delay, doubled after every attempt as the RFC asks
+const RETRANSMIT: Duration = Duration::from_millis(250);
+
+pub struct NatPmpSource {
+    /// Router address, the default gateway when unset
+    gateway: Option<Ipv4Addr>,
+    timeout: Duration,
+}
+
+impl NatPmpSource {
+    pub fn new(gateway: Option<Ipv4Addr>, timeout: Duration) -> Self {
+        Self { gateway, timeout }
+    }
+}
+
+/// IPv4 default gateway from the kernel routing table
+#[cfg(target_os = "linux")]
+pub(crate) fn default_gateway() -> Option<Ipv4Addr> {
+    let table = std::fs::read_to_string("/proc/net/route").ok()?;
+    table.lines().skip(1).find_map(|line| {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        if fields.get(1) != Some(&"00000000") {
+            return None;
+        }
+        // Hex in host byte order
+        let gateway = u32::from_str_radix(fields.get(2)?, 16).ok()?;
+        Some(Ipv4Addr::from(gateway.to_le_bytes())).filter(|g| !g.is_unspecified())
+    })
+}
+
+#[cfg(not(target_os = "linux"))]
+pub(crate) fn default_gateway() -> Option<Ipv4Addr> {
+    None
+}
+
+#[async_trait]
+impl IpSource for NatPmpSource {
+    async fn detect(&self, version: IpVersion) -> Result<Option<IpAddr>, IpDetectionError> {
+        if version == IpVersion::V6 {
+            return Ok(None);
+        }
+        let gateway = self.gateway.or_else(default_gateway).ok_or_else(|| {
+            IpDetectionError::Invalid("no default gateway found, configure the router".into())
+        })?;
+        let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0)).await?;
+        socket.connect((gateway, NATPMP_PORT)).await?;
+
+        // Version 0, opcode 0: external address request
+        let request = [0, 0];
+        let deadline = Instant::now() + self.timeout;
+        let mut delay = RETRANSMIT;
+        let mut buf = [0; 16];
+        loop {
+            socket.send(&request).await?;
+            let retransmit = (Instant::now() + delay).min(deadline);
+            while let Ok(received) = timeout_at(retransmit, socket.recv(&mut buf)).await {
+                let answer = &buf[..received?];
+                if answer.len() < 12 || answer[0] != 0 || answer[1] != 128 {
+                    continue;
+                }
+                return match u16::from_be_bytes([answer[2], answer[3]]) {
+                    0 => {
+                        let ip = Ipv4Addr::new(answer[8], answer[9], answer[10], answer[11]);
+                        // Routers without a WAN connection answer 0.0.0.0
+                        Ok(Some(IpAddr::V4(ip)).filter(|_| !ip.is_unspecified()))
+                    }
+                    code => Err(IpDetectionError::Invalid(format!(
+                        "NAT-PMP error {} from {}",
+                        code, gateway
+                    ))),
+                };
+            }
+            if Instant::now() >= deadline {
+                return Err(io::Error::new(
+                    io::ErrorKind::TimedOut,
+                    format!("no NAT-PMP answer from {}", gateway),
+                )
+                .into());
+            }
+            delay *= 2;
+        }
+    }
+}
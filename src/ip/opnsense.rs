@@ -0,0 +1,97 @@
+//! IP source reading the address of the WAN interface from the OPNsense API
+//! (`/api/diagnostics/interface/getInterfaceConfig`).
+//!
+//! The API authenticates with an API key and secret as user name and
+//! password, created under System > Access > Users.
+
+use super::interface::is_global;
+use super::{IpSource, IpVersion};
+use crate::error::IpDetectionError;
+use async_trait::async_trait;
+use reqwest::Client;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::time::Duration;
+
+#[derive(Deserialize)]
+struct InterfaceConfig {
+    #[serde(default)]
+    ipv4: Vec<Address>,
+    #[serde(default)]
+    ipv6: Vec<Address>,
+}
+
+#[derive(Deserialize)]
+struct Address {
+    ipaddr: String,
+    #[serde(default)]
+    deprecated: bool,
+    #[serde(default)]
+    tentative: bool,
+}
+
+pub struct OpnsenseSource {
+    client: Client,
+    base_url: String,
+    key: String,
+    secret: String,
+    /// Device of the WAN interface, e.g. `igb0` or `pppoe0`
+    interface: String,
+    timeout: Duration,
+}
+
+impl OpnsenseSource {
+    /// `base_url` is the web interface, e.g. `https://192.168.1.1`
+    pub fn new(
+        base_url: &str,
+        key: impl Into<String>,
+        secret: impl Into<String>,
+        interface: impl Into<String>,
+        timeout: Duration,
+    ) -> Self {
+        Self {
+            client: Client::new(),
+            base_url: base_url.trim_end_matches('/').to_string(),
+            key: key.into(),
+            secret: secret.into(),
+            interface: interface.into(),
+            timeout,
+        }
+    }
+}
+
+#[async_trait]
+impl IpSource for OpnsenseSource {
+    async fn detect(&self, version: IpVersion) -> Result<Option<IpAddr>, IpDetectionError> {
+        let interfaces: HashMap<String, InterfaceConfig> = self
+            .client
+            .get(format!(
+                "{}/api/diagnostics/interface/getInterfaceConfig",
+                self.base_url
+            ))
+            .basic_auth(&self.key, Some(&self.secret))
+            .timeout(self.timeout)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        let interface = interfaces.get(&self.interface).ok_or_else(|| {
+            IpDetectionError::Invalid(format!(
+                "OPNsense at {} has no interface {}",
+                self.base_url, self.interface
+            ))
+        })?;
+
+        let addresses = match version {
+            IpVersion::V4 => &interface.ipv4,
+            IpVersion::V6 => &interface.ipv6,
+        };
+        Ok(addresses
+            .iter()
+            .filter(|a| !a.deprecated && !a.tentative)
+            .filter_map(|a| a.ipaddr.parse().ok())
+            .find(|ip| is_global(*ip)))
+    }
+}
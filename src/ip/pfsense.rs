@@ -0,0 +1,95 @@
+//! IP source reading the address of the WAN interface from pfSense, through
+//! the REST API package (`/api/v2/status/interfaces`).
+//!
+//! The package authenticates with an API key, created under
+//! System > REST API > Keys.
+
+use super::{IpSource, IpVersion, parse_answer};
+use crate::error::IpDetectionError;
+use async_trait::async_trait;
+use reqwest::Client;
+use serde::Deserialize;
+use std::net::IpAddr;
+use std::time::Duration;
+
+#[derive(Deserialize)]
+struct Response {
+    data: Vec<Interface>,
+}
+
+#[derive(Deserialize)]
+struct Interface {
+    /// Internal name, e.g. `wan` or `opt1`
+    name: String,
+    #[serde(default)]
+    descr: String,
+    #[serde(default)]
+    ipaddr: Option<String>,
+    #[serde(default)]
+    ipaddrv6: Option<String>,
+}
+
+pub struct PfsenseSource {
+    client: Client,
+    base_url: String,
+    api_key: String,
+    /// Name or description of the WAN interface
+    interface: String,
+    timeout: Duration,
+}
+
+impl PfsenseSource {
+    /// `base_url` is the web interface, e.g. `https://192.168.1.1`
+    pub fn new(
+        base_url: &str,
+        api_key: impl Into<String>,
+        interface: impl Into<String>,
+        timeout: Duration,
+    ) -> Self {
+        Self {
+            client: Client::new(),
+            base_url: base_url.trim_end_matches('/').to_string(),
+            api_key: api_key.into(),
+            interface: interface.into(),
+            timeout,
+        }
+    }
+}
+
+#[async_trait]
+impl IpSource for PfsenseSource {
+    async fn detect(&self, version: IpVersion) -> Result<Option<IpAddr>, IpDetectionError> {
+        let response: Response = self
+            .client
+            .get(format!("{}/api/v2/status/interfaces", self.base_url))
+            .header("X-API-Key", &self.api_key)
+            .timeout(self.timeout)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        let interface = response
+            .data
+            .into_iter()
+            .find(|i| {
+                i.name.eq_ignore_ascii_case(&self.interface)
+                    || i.descr.eq_ignore_ascii_case(&self.interface)
+            })
+            .ok_or_else(|| {
+                IpDetectionError::Invalid(format!(
+                    "pfSense at {} has no interface {}",
+                    self.base_url, self.interface
+                ))
+            })?;
+
+        let address = match version {
+            IpVersion::V4 => interface.ipaddr,
+            IpVersion::V6 => interface.ipaddrv6,
+        };
+        match address.filter(|a| !a.is_empty()) {
+            Some(address) => parse_answer(&self.base_url, &address, version).map(Some),
+            None => Ok(None),
+        }
+    }
+}
@@ -0,0 +1,21 @@
+//! IP source backed by the `public-ip` crate's default resolvers.
+
+use super::{IpSource, IpVersion};
+use crate::error::IpDetectionError;
+use async_trait::async_trait;
+use std::net::IpAddr;
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PublicIpSource;
+
+#[async_trait]
+impl IpSource for PublicIpSource {
+    async fn detect(&self, version: IpVersion) -> Result<Option<IpAddr>, IpDetectionError> {
+        let ip = match version {
+            IpVersion::V4 => public_ip::addr_v4().await.map(IpAddr::V4),
+            IpVersion::V6 => public_ip::addr_v6().await.map(IpAddr::V6),
+        };
+
+        Ok(ip)
+    }
+}
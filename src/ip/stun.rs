@@ -0,0 +1,179 @@
+//! IP source asking a STUN server (RFC 5389) for the address our requests
+//! come from, e.g. `stun:stun.l.google.com:19302`.
+//!
+//! A single UDP round trip reveals the public side of the NAT mapping, which
+//! is cheaper than an HTTPS request and works where HTTP egress is filtered.
+
+use super::bind::{Bind, udp_socket};
+use super::{IpSource, IpVersion};
+use crate::error::IpDetectionError;
+use async_trait::async_trait;
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::io;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::time::Duration;
+use tokio::net::lookup_host;
+use tokio::time::{Instant, timeout_at};
+
+/// Port used when the server address has none
+const DEFAULT_PORT: u16 = 3478;
+const MAGIC_COOKIE: u32 = 0x2112_A442;
+const BINDING_REQUEST: u16 = 0x0001;
+const BINDING_SUCCESS: u16 = 0x0101;
+const MAPPED_ADDRESS: u16 = 0x0001;
+const XOR_MAPPED_ADDRESS: u16 = 0x0020;
+/// Delay before an unanswered request is sent again, UDP being lossy
+const RETRANSMIT: Duration = Duration::from_millis(500);
+
+pub struct StunSource {
+    /// `host:port` of the server
+    server: String,
+    timeout: Duration,
+    bind: Option<Bind>,
+}
+
+impl StunSource {
+    /// Source asking `server`, given as `host` or `host:port`
+    pub fn new(server: &str, timeout: Duration) -> Self {
+        let has_port = match server.rsplit_once(':') {
+            Some((host, port)) => {
+                port.parse::<u16>().is_ok() && (!host.contains(':') || host.ends_with(']'))
+            }
+            None => false,
+        };
+        let server = if has_port {
+            server.to_string()
+        } else {
+            format!("{}:{}", server, DEFAULT_PORT)
+        };
+        Self {
+            server,
+            timeout,
+            bind: None,
+        }
+    }
+
+    /// Send requests from this local address or interface
+    pub fn with_bind(mut self, bind: Bind) -> Self {
+        self.bind = Some(bind);
+        self
+    }
+}
+
+fn transaction_id() -> [u8; 12] {
+    let random = || RandomState::new().build_hasher().finish().to_be_bytes();
+    let mut id = [0; 12];
+    id[..8].copy_from_slice(&random());
+    id[8..].copy_from_slice(&random()[..4]);
+    id
+}
+
+fn binding_request(id: &[u8; 12]) -> Vec<u8> {
+    let mut msg = Vec::with_capacity(20);
+    msg.extend(BINDING_REQUEST.to_be_bytes());
+    msg.extend(0u16.to_be_bytes());
+    msg.extend(MAGIC_COOKIE.to_be_bytes());
+    msg.extend(id);
+    msg
+}
+
+fn u16_at(msg: &[u8], at: usize) -> u16 {
+    u16::from_be_bytes([msg[at], msg[at + 1]])
+}
+
+/// Address in a (XOR-)MAPPED-ADDRESS attribute value
+fn mapped_address(value: &[u8], xor: Option<&[u8; 12]>) -> Option<IpAddr> {
+    // XOR-MAPPED-ADDRESS masks the address with the cookie and transaction ID
+    let mut mask = [0; 16];
+    if let Some(id) = xor {
+        mask[..4].copy_from_slice(&MAGIC_COOKIE.to_be_bytes());
+        mask[4..].copy_from_slice(id);
+    }
+    match (value.get(1)?, value.len()) {
+        (0x01, 8) => {
+            let octets: [u8; 4] = std::array::from_fn(|i| value[4 + i] ^ mask[i]);
+            Some(Ipv4Addr::from(octets).into())
+        }
+        (0x02, 20) => {
+            let octets: [u8; 16] = std::array::from_fn(|i| value[4 + i] ^ mask[i]);
+            Some(Ipv6Addr::from(octets).into())
+        }
+        _ => None,
+    }
+}
+
+/// Mapped address of a Binding success response to transaction `id`,
+/// `None` for other datagrams
+fn parse_response(msg: &[u8], id: &[u8; 12]) -> Option<Result<IpAddr, IpDetectionError>> {
+    if msg.len() < 20 || msg[4..8] != MAGIC_COOKIE.to_be_bytes() || msg[8..20] != id[..] {
+        return None;
+    }
+    if u16_at(msg, 0) != BINDING_SUCCESS {
+        return Some(Err(IpDetectionError::Invalid(format!(
+            "STUN error response (type {:#06x})",
+            u16_at(msg, 0)
+        ))));
+    }
+
+    let end = (20 + u16_at(msg, 2) as usize).min(msg.len());
+    let (mut pos, mut mapped, mut xor_mapped) = (20, None, None);
+    while pos + 4 <= end {
+        let kind = u16_at(msg, pos);
+        let len = u16_at(msg, pos + 2) as usize;
+        let Some(value) = msg.get(pos + 4..pos + 4 + len) else {
+            break;
+        };
+        match kind {
+            XOR_MAPPED_ADDRESS => xor_mapped = mapped_address(value, Some(id)),
+            MAPPED_ADDRESS => mapped = mapped_address(value, None),
+            _ => {}
+        }
+        // Attributes are padded to a multiple of four bytes
+        pos += 4 + len.div_ceil(4) * 4;
+    }
+    Some(
+        xor_mapped
+            .or(mapped)
+            .ok_or_else(|| IpDetectionError::Invalid("STUN response without address".into())),
+    )
+}
+
+#[async_trait]
+impl IpSource for StunSource {
+    async fn detect(&self, version: IpVersion) -> Result<Option<IpAddr>, IpDetectionError> {
+        let server = lookup_host(&self.server).await?.find(|a| match version {
+            IpVersion::V4 => a.is_ipv4(),
+            IpVersion::V6 => a.is_ipv6(),
+        });
+        // The server can't be reached over this family
+        let Some(server) = server else {
+            return Ok(None);
+        };
+        let Some(socket) = udp_socket(self.bind.as_ref(), version).await? else {
+            return Ok(None);
+        };
+        socket.connect(server).await?;
+
+        let id = transaction_id();
+        let request = binding_request(&id);
+        let deadline = Instant::now() + self.timeout;
+        let mut buf = [0; 1024];
+        loop {
+            socket.send(&request).await?;
+            let retransmit = (Instant::now() + RETRANSMIT).min(deadline);
+            while let Ok(received) = timeout_at(retransmit, socket.recv(&mut buf)).await {
+                if let Some(result) = parse_response(&buf[..received?], &id) {
+                    return result.map(Some);
+                }
+            }
+            if Instant::now() >= deadline {
+                return Err(io::Error::new(
+                    io::ErrorKind::TimedOut,
+                    format!("no answer from {}", self.server),
+                )
+                .into());
+            }
+        }
+    }
+}
@@ -0,0 +1,185 @@
+//! IP source asking the router for its WAN address over UPnP IGD
+//! (`GetExternalIPAddress`).
+//!
+//! The router is found with an SSDP search unless the location of its device
+//! description is configured. Only IPv4 has a WAN address to report; IPv6
+//! detection returns nothing.
+
+use super::{IpSource, IpVersion};
+use crate::error::IpDetectionError;
+use async_trait::async_trait;
+use reqwest::Client;
+use reqwest::header::CONTENT_TYPE;
+use std::io;
+use std::net::{IpAddr, Ipv4Addr};
+use std::time::Duration;
+use tokio::net::UdpSocket;
+use tokio::time::{Instant, timeout_at};
+
+const SSDP_ADDR: (Ipv4Addr, u16) = (Ipv4Addr::new(239, 255, 255, 250), 1900);
+const SEARCH: &str = "M-SEARCH * HTTP/1.1\r\n\
+    HOST: 239.255.255.250:1900\r\n\
+    MAN: \"ssdp:discover\"\r\n\
+    MX: 2\r\n\
+    ST: urn:schemas-upnp-org:device:InternetGatewayDevice:1\r\n\r\n";
+/// Services of an IGD reporting the external address
+const WAN_SERVICES: [&str; 2] = [
+    "urn:schemas-upnp-org:service:WANIPConnection:",
+    "urn:schemas-upnp-org:service:WANPPPConnection:",
+];
+
+pub struct UpnpSource {
+    client: Client,
+    /// URL of the router's device description, discovered when unset
+    location: Option<String>,
+    timeout: Duration,
+}
+
+impl UpnpSource {
+    pub fn new(location: Option<String>, timeout: Duration) -> Self {
+        Self {
+            client: Client::new(),
+            location,
+            timeout,
+        }
+    }
+
+    async fn external_address(
+        &self,
+        deadline: Instant,
+    ) -> Result<Option<IpAddr>, IpDetectionError> {
+        let location = match &self.location {
+            Some(location) => location.clone(),
+            None => discover(deadline).await?,
+        };
+        let description = self
+            .client
+            .get(&location)
+            .send()
+            .await?
+            .error_for_status()?
+            .text()
+            .await?;
+
+        let (service, control) = description
+            .split("<service>")
+            .skip(1)
+            .find_map(|block| {
+                let service = xml_value(block, "serviceType")?;
+                WAN_SERVICES
+                    .iter()
+                    .any(|s| service.starts_with(s))
+                    .then(|| Some((service.to_string(), xml_value(block, "controlURL")?)))?
+            })
+            .ok_or_else(|| {
+                IpDetectionError::Invalid(format!("no WAN connection service at {}", location))
+            })?;
+
+        let answer = soap_call(
+            &self.client,
+            &resolve(&location, control),
+            &service,
+            "GetExternalIPAddress",
+        )
+        .await?;
+        let ip = xml_value(&answer, "NewExternalIPAddress").unwrap_or_default();
+        // Routers without a WAN connection answer with an empty address
+        if ip.is_empty() {
+            return Ok(None);
+        }
+        ip.parse().map(Some).map_err(|_| {
+            IpDetectionError::Invalid(format!("router answered {:?} as its address", ip))
+        })
+    }
+}
+
+/// Location of the first Internet Gateway Device answering an SSDP search
+async fn discover(deadline: Instant) -> Result<String, IpDetectionError> {
+    let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0)).await?;
+    socket.send_to(SEARCH.as_bytes(), SSDP_ADDR).await?;
+
+    let mut buf = [0; 2048];
+    while let Ok(received) = timeout_at(deadline, socket.recv(&mut buf)).await {
+        let answer = String::from_utf8_lossy(&buf[..received?]);
+        let location = answer.lines().find_map(|line| {
+            let (name, value) = line.split_once(':')?;
+            name.trim()
+                .eq_ignore_ascii_case("location")
+                .then(|| value.trim().to_string())
+        });
+        if let Some(location) = location {
+            return Ok(location);
+        }
+    }
+    Err(io::Error::new(io::ErrorKind::TimedOut, "no UPnP gateway answered").into())
+}
+
+/// `url` relative to the device description at `location`
+pub(crate) fn resolve(location: &str, url: &str) -> String {
+    if url.starts_with("http://") || url.starts_with("https://") {
+        return url.to_string();
+    }
+    let (scheme, rest) = location.split_once("://").unwrap_or(("http", location));
+    let host = rest.split('/').next().unwrap_or(rest);
+    format!("{}://{}/{}", scheme, host, url.trim_start_matches('/'))
+}
+
+/// Text of the first `<tag>` element in `xml`, ignoring namespace prefixes
+pub(crate) fn xml_value<'a>(xml: &'a str, tag: &str) -> Option<&'a str> {
+    let open = [format!("<{}>", tag), format!(":{}>", tag)];
+    let start = open
+        .iter()
+        .filter_map(|o| xml.find(o.as_str()).map(|i| i + o.len()))
+        .min()?;
+    let end = xml[start..].find("</")? + start;
+    Some(xml[start..end].trim())
+}
+
+/// Call `action` of `service` at `control` and return the response body
+pub(crate) async fn soap_call(
+    client: &Client,
+    control: &str,
+    service: &str,
+    action: &str,
+) -> Result<String, IpDetectionError> {
+    let body = format!(
+        "<?xml version=\"1.0\"?>\
+         <s:Envelope xmlns:s=\"http://schemas.xmlsoap.org/soap/envelope/\" \
+         s:encodingStyle=\"http://schemas.xmlsoap.org/soap/encoding/\">\
+         <s:Body><u:{action} xmlns:u=\"{service}\"/></s:Body></s:Envelope>"
+    );
+    let resp = client
+        .post(control)
+        .header(CONTENT_TYPE, "text/xml; charset=\"utf-8\"")
+        .header("SOAPAction", format!("\"{}#{}\"", service, action))
+        .body(body)
+        .send()
+        .await?;
+    let status = resp.status();
+    let text = resp.text().await?;
+    if !status.is_success() {
+        let reason = xml_value(&text, "errorDescription").unwrap_or_default();
+        return Err(IpDetectionError::Invalid(format!(
+            "{} failed with HTTP {} {}",
+            action,
+            status.as_u16(),
+            reason
+        )));
+    }
+    Ok(text)
+}
+
+#[async_trait]
+impl IpSource for UpnpSource {
+    async fn detect(&self, version: IpVersion) -> Result<Option<IpAddr>, IpDetectionError> {
+        if version == IpVersion::V6 {
+            return Ok(None);
+        }
+        let deadline = Instant::now() + self.timeout;
+        timeout_at(deadline, self.external_address(deadline))
+            .await
+            .map_err(|_| {
+                io::Error::new(io::ErrorKind::TimedOut, "no answer from the UPnP gateway")
+            })?
+    }
+}
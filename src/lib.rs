@@ -0,0 +1,33 @@
+//! Dynamic DNS agent.
+//!
+//! The library exposes the [`DnsProvider`] and [`IpSource`] traits together
+//! with the [`Agent`] that keeps DNS records in sync with the detected
+//! public IP. Each built-in provider sits behind a cargo feature of its name,
+//! only `cloudflare` being enabled by default, and is built from the
+//! `[providers]` section of a [`Config`] by [`provider::from_config`].
+//!
+//! Every record is updated through its provider, or through its failover
+//! providers in turn while that one is unavailable. [`Agent::plan`] shows
+//! what a sync would change without writing anything, and [`Agent::apply`]
+//! writes exactly that plan. The `ddns_agent` binary wires a config file, IP
+//! sources and providers into the agent and runs it.
+
+pub mod acme;
+pub mod agent;
+pub mod cache;
+pub mod config;
+pub mod error;
+pub mod health;
+pub mod ip;
+pub mod logging;
+pub mod notify;
+pub mod owner;
+pub mod provider;
+pub mod retry;
+pub mod watch;
+
+pub use agent::{Agent, PlannedAction, PlannedChange, SyncReport};
+pub use config::Config;
+pub use error::{ConfigError, Error, IpDetectionError, ProviderError};
+pub use ip::{IpSource, IpVersion};
+pub use provider::{DnsProvider, Record, RecordType, SrvRecord, Zone};
@@ -0,0 +1,47 @@
+//! Minimal `log` backend printing `[LEVEL] message` lines.
+//!
+//! Errors and warnings go to stderr, everything else to stdout, which keeps
+//! the output readable both in a terminal and in the systemd journal.
+
+use log::{Level, LevelFilter, Log, Metadata, Record};
+
+struct Logger;
+
+static LOGGER: Logger = Logger;
+
+impl Log for Logger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        // Only warnings and errors from dependencies, everything from us
+        metadata.level() <= log::max_level()
+            && (metadata.level() <= Level::Warn || metadata.target().starts_with("ddns_agent"))
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        match record.level() {
+            Level::Error => eprintln!("[ERR] {}", record.args()),
+            Level::Warn => eprintln!("[WARN] {}", record.args()),
+            Level::Info => println!("[INFO] {}", record.args()),
+            Level::Debug => println!("[DEBUG] {}", record.args()),
+            Level::Trace => println!("[TRACE] {}", record.args()),
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+/// Install the logger with the given maximum level.
+///
+/// Does nothing if another logger has already been installed.
+pub fn init(level: LevelFilter) {
+    if log::set_logger(&LOGGER).is_ok() {
+        log::set_max_level(level);
+    }
+}
+
+/// Change the maximum level of the installed logger
+pub fn set_level(level: LevelFilter) {
+    log::set_max_level(level);
+}
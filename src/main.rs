@@ -1,182 +1,473 @@
-use anyhow::{anyhow, Context};
+mod cli;
+
+use anyhow::Context;
+use clap::Parser;
+use cli::{AcmeCommand, Cli, Command};
+use ddns_agent::acme;
+use ddns_agent::agent::{ManagedRecord, RecordSetup};
+use ddns_agent::cache::IdCache;
+use ddns_agent::ip;
+use ddns_agent::provider;
+use ddns_agent::watch::NetworkWatcher;
+use ddns_agent::{Agent, Config, PlannedAction, PlannedChange, SyncReport, logging};
+use log::{debug, error, info, warn};
 use reqwest::Client;
-use serde::Deserialize;
-use std::{env, net::Ipv4Addr};
-use tokio::time::{sleep, Duration};
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::process::ExitCode;
+use std::time::SystemTime;
+use tokio::time::{Duration, sleep};
 
-#[derive(Deserialize)]
-struct CfResponse {
-    success: bool,
+/// Pair every configured record with the providers managing it
+fn build_records(config: &Config, client: &Client) -> anyhow::Result<Vec<RecordSetup>> {
+    let providers = provider::from_config(&config.providers, client)?;
+    Ok(config
+        .records()
+        .into_iter()
+        .map(|record| {
+            let name = record.provider.as_deref().expect("validated");
+            let provider = providers[name].clone();
+            let failover = record
+                .failover
+                .iter()
+                .map(|name| providers[name.as_str()].clone())
+                .collect();
+            (record, provider, failover)
+        })
+        .collect())
 }
 
-#[derive(Deserialize)]
-struct CfZonesResponse {
-    success: bool,
-    result: Vec<CfZone>,
+fn build_agent(config: &Config, client: &Client, cli: &Cli) -> anyhow::Result<Agent> {
+    let mut agent = Agent::new(ip::from_config(&config.ip)?)
+        .with_dry_run(cli.dry_run)
+        .with_owner(config.agent.owner_id.clone())
+        .with_takeover(cli.force)
+        .with_drift_check(config.agent.drift_check.clone())
+        .with_verify(config.agent.verify.clone())
+        .with_garbage_collect(config.agent.garbage_collect)
+        .with_retry(config.retry.clone())
+        .with_force_update_interval(config.agent.force_update_interval.clone())
+        .with_notify(config.notify.clone())
+        .with_concurrency(config.agent.concurrency);
+    agent.set_named_sources(ip::named_from_config(&config.ip)?);
+    for (record, provider, failover) in build_records(config, client)? {
+        agent.add_record_with_failover(record, provider, failover);
+    }
+    Ok(agent)
 }
 
-#[derive(Deserialize)]
-struct CfZone {
-    id: String,
+/// How often the config file is checked for changes while waiting
+const CONFIG_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+fn modified(path: Option<&Path>) -> Option<SystemTime> {
+    fs::metadata(path?).and_then(|m| m.modified()).ok()
 }
 
-#[derive(Deserialize)]
-struct CfDnsRecordsResponse {
-    success: bool,
-    result: Vec<CfDnsRecord>,
+/// Return once the config file at `path` was modified since `last`, never
+/// without a config file
+async fn config_changed(path: Option<&Path>, last: Option<SystemTime>) {
+    if path.is_none() {
+        return std::future::pending().await;
+    }
+    loop {
+        sleep(CONFIG_POLL_INTERVAL).await;
+        if modified(path) != last {
+            return;
+        }
+    }
 }
 
-#[derive(Deserialize)]
-struct CfDnsRecord {
-    id: String,
+/// Network change notifications, if enabled and supported
+fn network_watcher(config: &Config) -> Option<NetworkWatcher> {
+    if !config.agent.watch_network {
+        return None;
+    }
+    match NetworkWatcher::new() {
+        Ok(watcher) => Some(watcher),
+        Err(e) => {
+            warn!("Not watching the network, polling only: {}", e);
+            None
+        }
+    }
 }
 
-/// Extract root domain from DNS name (e.g., "sub.example.com" -> "example.com")
-fn extract_root_domain(dns_name: &str) -> String {
-    let parts: Vec<&str> = dns_name.split('.').collect();
-    if parts.len() >= 2 {
-        parts[parts.len() - 2..].join(".")
-    } else {
-        dns_name.to_string()
+/// Next network change, never returning without a watcher
+async fn network_changed(watcher: &mut Option<NetworkWatcher>) -> io::Result<()> {
+    match watcher {
+        Some(watcher) => watcher.changed().await,
+        None => std::future::pending().await,
     }
 }
 
-/// Fetch Zone ID from Cloudflare API based on domain name
-async fn get_zone_id(client: &Client, cf_token: &str, dns_name: &str) -> anyhow::Result<String> {
-    let domain = extract_root_domain(dns_name);
-    let url = format!("https://api.cloudflare.com/client/v4/zones?name={}", domain);
-    let resp = client
-        .get(&url)
-        .bearer_auth(cf_token)
-        .send()
-        .await
-        .context("Failed to fetch zones from Cloudflare")?;
+/// What ended the wait between two checks
+enum Wake {
+    Timer,
+    ConfigChanged,
+    Network(io::Result<()>),
+    #[cfg(unix)]
+    Hangup,
+    Shutdown,
+}
 
-    let data: CfZonesResponse = resp.json().await.context("Failed to parse zones response")?;
+/// Requests to stop: Ctrl-C, or SIGTERM on Unix. Listening for the whole
+/// loop keeps a signal arriving during a check from killing the process
+/// before the ephemeral records are deleted.
+struct ShutdownSignals {
+    #[cfg(unix)]
+    interrupt: tokio::signal::unix::Signal,
+    #[cfg(unix)]
+    terminate: tokio::signal::unix::Signal,
+    #[cfg(windows)]
+    ctrl_c: tokio::signal::windows::CtrlC,
+}
 
-    if !data.success || data.result.is_empty() {
-        return Err(anyhow!("Zone not found for domain: {}", domain));
+impl ShutdownSignals {
+    fn new() -> io::Result<Self> {
+        #[cfg(unix)]
+        {
+            use tokio::signal::unix::{SignalKind, signal};
+            Ok(Self {
+                interrupt: signal(SignalKind::interrupt())?,
+                terminate: signal(SignalKind::terminate())?,
+            })
+        }
+        #[cfg(windows)]
+        {
+            Ok(Self {
+                ctrl_c: tokio::signal::windows::ctrl_c()?,
+            })
+        }
     }
 
-    Ok(data.result[0].id.clone())
+    /// Next request to stop, including one received since the last call
+    async fn recv(&mut self) {
+        #[cfg(unix)]
+        tokio::select! {
+            _ = self.interrupt.recv() => {}
+            _ = self.terminate.recv() => {}
+        }
+        #[cfg(windows)]
+        self.ctrl_c.recv().await;
+    }
 }
 
-/// Fetch DNS Record ID from Cloudflare API based on zone_id and dns_name
-async fn get_record_id(
+/// Keep the records in sync until the process is stopped, reloading the
+/// configuration on SIGHUP or when the config file changes. Ephemeral
+/// records are deleted when stopping.
+async fn run_forever(
+    cli: &Cli,
+    mut config: Config,
     client: &Client,
-    cf_token: &str,
-    zone_id: &str,
-    dns_name: &str,
-) -> anyhow::Result<String> {
-    let url = format!(
-        "https://api.cloudflare.com/client/v4/zones/{}/dns_records?type=A&name={}",
-        zone_id, dns_name
-    );
+    mut agent: Agent,
+) -> anyhow::Result<()> {
+    agent.prepare().await?;
+    let mut shutdown = ShutdownSignals::new()?;
+    #[cfg(unix)]
+    let mut hangup = tokio::signal::unix::signal(tokio::signal::unix::SignalKind::hangup())?;
+    let mut last_modified = modified(cli.config.as_deref());
+    let mut watcher = network_watcher(&config);
 
-    let resp = client
-        .get(&url)
-        .bearer_auth(cf_token)
-        .send()
-        .await
-        .context("Failed to fetch DNS records from Cloudflare")?;
+    info!("Starting IP sync loop...");
+    loop {
+        agent.tick().await;
 
-    let data: CfDnsRecordsResponse = resp
-        .json()
-        .await
-        .context("Failed to parse DNS records response")?;
+        // Network events trigger the checks, the timer only catches changes
+        // the host can't see
+        let interval_ms = match watcher {
+            Some(_) => config.agent.watch_interval_ms,
+            None => config.agent.interval_ms,
+        };
+        let interval = agent.next_interval(Duration::from_millis(interval_ms));
+        #[cfg(unix)]
+        let wake = tokio::select! {
+            _ = sleep(interval) => Wake::Timer,
+            _ = config_changed(cli.config.as_deref(), last_modified) => Wake::ConfigChanged,
+            changed = network_changed(&mut watcher) => Wake::Network(changed),
+            _ = hangup.recv() => Wake::Hangup,
+            _ = shutdown.recv() => Wake::Shutdown,
+        };
+        #[cfg(not(unix))]
+        let wake = tokio::select! {
+            _ = sleep(interval) => Wake::Timer,
+            _ = config_changed(cli.config.as_deref(), last_modified) => Wake::ConfigChanged,
+            changed = network_changed(&mut watcher) => Wake::Network(changed),
+            _ = shutdown.recv() => Wake::Shutdown,
+        };
 
-    if !data.success || data.result.is_empty() {
-        return Err(anyhow!("DNS record not found for: {}", dns_name));
-    }
+        let mut reload = false;
+        match wake {
+            // Reloaded below like a change noticed after a check
+            Wake::Timer | Wake::ConfigChanged => {}
+            Wake::Network(Ok(())) => debug!("Network configuration changed"),
+            Wake::Network(Err(e)) => {
+                warn!("Stopped watching the network, polling only: {}", e);
+                watcher = None;
+            }
+            #[cfg(unix)]
+            Wake::Hangup => {
+                info!("Received SIGHUP, reloading configuration");
+                reload = true;
+            }
+            Wake::Shutdown => {
+                info!("Shutting down");
+                agent.remove_ephemeral().await;
+                return Ok(());
+            }
+        }
+
+        let current = modified(cli.config.as_deref());
+        if current != last_modified {
+            info!("Config file changed, reloading configuration");
+            last_modified = current;
+            reload = true;
+        }
+        if !reload {
+            continue;
+        }
 
-    Ok(data.result[0].id.clone())
+        let reloaded = cli.load_config().and_then(|new| {
+            new.validate()?;
+            let records = build_records(&new, client)?;
+            let source = ip::from_config(&new.ip)?;
+            let named_sources = ip::named_from_config(&new.ip)?;
+            Ok((new, records, source, named_sources))
+        });
+        match reloaded {
+            Ok((new, records, source, named_sources)) => {
+                logging::set_level(new.log.level);
+                agent.set_source(source);
+                agent.set_named_sources(named_sources);
+                agent.set_concurrency(new.agent.concurrency);
+                agent.set_drift_check(new.agent.drift_check.clone());
+                agent.set_verify(new.agent.verify.clone());
+                agent.set_garbage_collect(new.agent.garbage_collect);
+                agent.set_retry(new.retry.clone());
+                agent.set_force_update_interval(new.agent.force_update_interval.clone());
+                agent.set_notify(new.notify.clone());
+                agent.reload(records);
+                if new.agent.watch_network != config.agent.watch_network {
+                    watcher = network_watcher(&new);
+                }
+                config = new;
+            }
+            Err(e) => error!("Keeping the current configuration: {:#}", e),
+        }
+    }
 }
 
-#[inline]
-pub async fn get_public_ip() -> anyhow::Result<Option<Ipv4Addr>> {
-    let public_ip = public_ip::addr_v4().await;
+async fn check(mut agent: Agent) -> anyhow::Result<()> {
+    agent.prepare().await?;
+    println!("Credentials OK");
+    for managed in agent.records() {
+        let zone = managed.zone.as_ref().expect("prepared");
+        let record = managed.current.as_ref().expect("prepared");
+        println!(
+            "  {} {} -> {} ({}, zone {}, record {})",
+            record.name,
+            record.kind,
+            record.content,
+            managed.provider.name(),
+            zone.name,
+            record.id.as_deref().unwrap_or("-")
+        );
+    }
 
-    Ok(public_ip)
+    let mut result = Ok(());
+    for version in agent.versions() {
+        match agent.detect(version).await {
+            Ok(ip) => println!("Detected public {} address: {}", version, ip),
+            Err(e) => {
+                println!("Detected public {} address: none", version);
+                result = Err(e.into());
+            }
+        }
+    }
+    result
 }
 
-async fn update_dns(
-    client: &Client,
-    ip: &str,
-    cf_token: &str,
-    zone_id: &str,
-    record_id: &str,
-    dns_name: &str,
-) -> anyhow::Result<()> {
-    let url = format!(
-        "https://api.cloudflare.com/client/v4/zones/{}/dns_records/{}",
-        zone_id, record_id
+/// Print how each record differs from what an update would write, and
+/// what the plan leaves out, and return the number of records to create or
+/// update
+fn print_plan(changes: &[PlannedChange], records: &[ManagedRecord]) -> usize {
+    let count = |action| changes.iter().filter(|c| c.action == action).count();
+    for change in changes {
+        let record = format!("{} {} at {}", change.name, change.kind, change.provider);
+        match change.action {
+            PlannedAction::Create => println!("  + {}: {}", record, change.desired),
+            PlannedAction::Update => println!(
+                "  ~ {}: {} -> {}{}",
+                record,
+                change.current.as_deref().unwrap_or("unknown"),
+                change.desired,
+                change
+                    .drift
+                    .as_ref()
+                    .map(|drift| format!(" ({})", drift))
+                    .unwrap_or_default()
+            ),
+            PlannedAction::Unchanged => println!("    {}: {}", record, change.desired),
+        }
+    }
+    // Only a sync checks these, so they are left to `run` and `update`
+    for (i, managed) in records.iter().enumerate() {
+        let name = &managed.config.name;
+        let provider = managed.provider.name();
+        let shared = records[..i]
+            .iter()
+            .any(|r| r.config.name == *name && r.provider.name() == provider);
+        if !managed.config.srv.is_empty() && !shared {
+            println!(
+                "  ! {} at {}: SRV records are not planned, `run` and `update` write them",
+                name, provider
+            );
+        }
+        if managed.config.health_check.is_some() {
+            println!(
+                "  ! {} {} at {}: the health check isn't run, so the plan doesn't withdraw it",
+                name, managed.kind, provider
+            );
+        }
+    }
+    let (create, update) = (count(PlannedAction::Create), count(PlannedAction::Update));
+    println!(
+        "Plan: {} to create, {} to update, {} unchanged",
+        create,
+        update,
+        count(PlannedAction::Unchanged)
     );
+    create + update
+}
+
+/// Print the plan of `agent` and, once confirmed, write exactly the
+/// changes shown. Nothing is written before, not even ownership markers.
+async fn apply(mut agent: Agent, yes: bool, dry_run: bool) -> anyhow::Result<ExitCode> {
+    agent.set_dry_run(true);
+    let changes = agent.plan().await?;
+    if print_plan(&changes, agent.records()) == 0 {
+        return Ok(ExitCode::SUCCESS);
+    }
+    if !yes && !dry_run {
+        println!("Apply these changes? Only `yes` is accepted.");
+        let mut answer = String::new();
+        io::stdin().read_line(&mut answer)?;
+        if answer.trim() != "yes" {
+            println!("Apply cancelled");
+            return Ok(ExitCode::from(EXIT_ERROR));
+        }
+    }
+    agent.set_dry_run(dry_run);
+    Ok(exit_code(agent.apply(&changes).await?))
+}
+
+/// Add or remove an ACME challenge record with a configured provider
+async fn acme(config: &Config, command: &AcmeCommand, dry_run: bool) -> anyhow::Result<ExitCode> {
+    let (args, set) = match command {
+        AcmeCommand::SetTxt(args) => (args, true),
+        AcmeCommand::ClearTxt(args) => (args, false),
+    };
+    let (domain, value) = args.challenge()?;
+    let providers = provider::from_config(&config.providers, &Client::new())?;
+    let provider = match (&args.provider, providers.len()) {
+        (Some(name), _) => providers
+            .get(name)
+            .with_context(|| format!("Provider `{}` is not configured", name))?,
+        (None, 1) => providers.values().next().expect("one provider"),
+        (None, 0) => anyhow::bail!(
+            "CF_API_TOKEN is required (or a [providers.*] section in the config file)"
+        ),
+        (None, _) => anyhow::bail!("Several providers are configured, choose one with --provider"),
+    };
 
-    let body = serde_json::json!({
-        "type": "A",
-        "name": dns_name,
-        "content": ip,
-        "ttl": 1, // 1 for auto
-        "proxied": false
-    });
-
-    let resp = client
-        .put(url)
-        .bearer_auth(cf_token)
-        .json(&body)
-        .send()
-        .await?;
-
-    let data: CfResponse = resp.json().await?;
-    if data.success {
-        println!("[OK] DNS updated: {}", ip);
+    if dry_run {
+        info!(
+            "[dry-run] Would {} TXT record {} at {}: {}",
+            if set { "add" } else { "remove" },
+            acme::challenge_name(&domain),
+            provider.name(),
+            value
+        );
+    } else if set {
+        acme::set_txt(provider.as_ref(), &domain, &value).await?;
     } else {
-        eprintln!("[ERR] Failed to update DNS");
+        acme::clear_txt(provider.as_ref(), &domain, &value).await?;
     }
-    Ok(())
+    Ok(ExitCode::SUCCESS)
 }
 
-#[tokio::main(flavor = "current_thread")]
-async fn main() -> anyhow::Result<()> {
-    dotenvy::dotenv().ok();
+/// One-shot runs (`run --once`, `update`) exit with 0 on success, 1 on
+/// configuration, authentication or lookup errors, and 2 if one or more
+/// records could not be updated.
+const EXIT_ERROR: u8 = 1;
+const EXIT_UPDATE_FAILED: u8 = 2;
+
+fn exit_code(report: SyncReport) -> ExitCode {
+    if report.is_success() {
+        info!("{} updated, {} unchanged", report.updated, report.unchanged);
+        ExitCode::SUCCESS
+    } else {
+        error!("{} records failed to update", report.failed);
+        ExitCode::from(EXIT_UPDATE_FAILED)
+    }
+}
+
+async fn run(cli: Cli) -> anyhow::Result<ExitCode> {
+    let command = cli.command.as_ref().unwrap_or(&Command::Run);
+    if let Command::Version = command {
+        println!("ddns-agent {}", env!("CARGO_PKG_VERSION"));
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    let config = cli.load_config()?;
+    logging::init(config.log.level);
+    // Challenges only need a provider, not records
+    if let Command::Acme { command } = command {
+        return acme(&config, command, cli.dry_run).await;
+    }
+    config.validate()?;
     let client = Client::new();
-    let mut last_ip = String::new();
-    let duration_sleep_ms: u64 = env::var("DURATION_SLEEP_MS")
-        .unwrap_or_else(|_| "5000".to_string())
-        .parse()
-        .unwrap_or(5000);
-
-    // Get required env vars
-    let cf_token = env::var("CF_API_TOKEN").context("CF_API_TOKEN is required")?;
-    let dns_name = env::var("DNS_NAME").context("DNS_NAME is required")?;
-
-    // Fetch Zone ID and Record ID dynamically from Cloudflare API
-    println!("[INFO] Fetching Zone ID for: {}", dns_name);
-    let zone_id = get_zone_id(&client, &cf_token, &dns_name).await?;
-    println!("[INFO] Zone ID: {}", zone_id);
-
-    println!("[INFO] Fetching Record ID for: {}", dns_name);
-    let record_id = get_record_id(&client, &cf_token, &zone_id, &dns_name).await?;
-    println!("[INFO] Record ID: {}", record_id);
-
-    println!("[INFO] Starting IP sync loop...");
-    loop {
-        match get_public_ip().await {
-            Ok(Some(ip)) => {
-                let ip_str = ip.to_string();
-                if ip_str != last_ip {
-                    println!("[INFO] New IP: {}", ip_str);
-                    if let Err(e) = update_dns(&client, &ip_str, &cf_token, &zone_id, &record_id, &dns_name).await {
-                        eprintln!("[ERR] {}", e);
-                    } else {
-                        last_ip = ip_str;
-                    }
-                }
-            }
-            Ok(None) => eprintln!("[ERR] Could not determine public IP"),
-            Err(e) => eprintln!("[ERR] {}", e),
+    let mut agent = build_agent(&config, &client, &cli)?;
+    // `check` verifies the credentials and `plan` compares with what is at
+    // the provider, so they always ask it
+    if let Some(path) = &config.agent.cache_file
+        && !matches!(
+            command,
+            Command::Check | Command::Plan | Command::Apply { .. }
+        )
+    {
+        agent.set_cache(IdCache::load(path));
+    }
+
+    match command {
+        Command::Run if cli.once => Ok(exit_code(agent.sync().await?)),
+        Command::Run => {
+            run_forever(&cli, config, &client, agent).await?;
+            Ok(ExitCode::SUCCESS)
+        }
+        Command::Update => Ok(exit_code(agent.force_update().await?)),
+        Command::Check => check(agent).await.map(|_| ExitCode::SUCCESS),
+        Command::Plan => {
+            // Looking up the records doesn't mark them as ours yet
+            agent.set_dry_run(true);
+            let changes = agent.plan().await?;
+            print_plan(&changes, agent.records());
+            Ok(ExitCode::SUCCESS)
         }
+        Command::Apply { yes } => apply(agent, *yes, cli.dry_run).await,
+        Command::Version | Command::Acme { .. } => unreachable!("handled above"),
+    }
+}
 
-        sleep(Duration::from_millis(duration_sleep_ms)).await;
+#[tokio::main(flavor = "current_thread")]
+async fn main() -> ExitCode {
+    dotenvy::dotenv().ok();
+    let cli = Cli::parse();
+
+    match run(cli).await {
+        Ok(code) => code,
+        Err(e) => {
+            eprintln!("Error: {:?}", e);
+            ExitCode::from(EXIT_ERROR)
+        }
     }
 }
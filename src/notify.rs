@@ -0,0 +1,211 @@
+//! Notifications about IP changes.
+//!
+//! Once records were updated for a new address, the agent can POST the
+//! change to a webhook and run a command, e.g. to alert someone or to
+//! reconfigure a firewall. Failed notifications are logged and don't fail
+//! the sync.
+
+use log::{info, warn};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use std::net::IpAddr;
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::process::Command;
+use tokio::time::timeout;
+
+/// `[notify]` section: where IP changes are reported
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct NotifyConfig {
+    /// URL receiving each change as a JSON POST request
+    pub webhook: Option<String>,
+    /// Program and arguments run for each change, which is passed in
+    /// `DDNS_*` environment variables
+    pub command: Option<Vec<String>>,
+    /// Give up on a notification taking longer than this
+    pub timeout_secs: u64,
+}
+
+impl Default for NotifyConfig {
+    fn default() -> Self {
+        Self {
+            webhook: None,
+            command: None,
+            timeout_secs: 10,
+        }
+    }
+}
+
+impl NotifyConfig {
+    /// Whether any target is configured
+    pub fn is_enabled(&self) -> bool {
+        self.webhook.is_some() || self.command.is_some()
+    }
+}
+
+/// Records updated for a new address
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct IpChange {
+    /// Address the records point at now
+    pub ip: IpAddr,
+    /// IP source that detected it, `None` for the default one
+    pub source: Option<String>,
+    pub records: Vec<ChangedRecord>,
+}
+
+/// A record updated for an [`IpChange`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ChangedRecord {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub provider: String,
+    /// Address written, which differs from the detected one for IPv6
+    /// records of another host
+    pub address: IpAddr,
+}
+
+/// Sends IP changes to the targets of a [`NotifyConfig`]
+pub struct Notifier {
+    config: NotifyConfig,
+    client: Client,
+}
+
+impl Notifier {
+    pub fn new(config: NotifyConfig) -> Self {
+        Self {
+            config,
+            client: Client::new(),
+        }
+    }
+
+    /// Whether any target is configured
+    pub fn is_enabled(&self) -> bool {
+        self.config.is_enabled()
+    }
+
+    /// Report `change` to the targets, logging failures
+    pub async fn send(&self, change: &IpChange) {
+        let limit = Duration::from_secs(self.config.timeout_secs);
+        if let Some(url) = &self.config.webhook {
+            let result = timeout(limit, self.post(url, change))
+                .await
+                .unwrap_or_else(|_| Err(format!("timed out after {}s", self.config.timeout_secs)));
+            match result {
+                Ok(()) => info!("Notified {} of the new IP {}", url, change.ip),
+                Err(e) => warn!("Notifying {} failed: {}", url, e),
+            }
+        }
+        if let Some(command) = &self.config.command {
+            let result = timeout(limit, run(command, change))
+                .await
+                .unwrap_or_else(|_| Err(format!("timed out after {}s", self.config.timeout_secs)));
+            if let Err(e) = result {
+                warn!("Notification command failed: {}", e);
+            }
+        }
+    }
+
+    async fn post(&self, url: &str, change: &IpChange) -> Result<(), String> {
+        let resp = self
+            .client
+            .post(url)
+            .json(change)
+            .send()
+            .await
+            .map_err(|e| format!("POST {}: {}", url, e))?;
+        if !resp.status().is_success() {
+            return Err(format!("POST {}: HTTP {}", url, resp.status().as_u16()));
+        }
+        Ok(())
+    }
+}
+
+async fn run(command: &[String], change: &IpChange) -> Result<(), String> {
+    let Some((program, args)) = command.split_first() else {
+        return Ok(());
+    };
+    let names: Vec<&str> = change.records.iter().map(|r| r.name.as_str()).collect();
+    let status = Command::new(program)
+        .args(args)
+        .env("DDNS_IP", change.ip.to_string())
+        .env("DDNS_SOURCE", change.source.as_deref().unwrap_or_default())
+        .env("DDNS_RECORDS", names.join(" "))
+        .stdin(Stdio::null())
+        .kill_on_drop(true)
+        .status()
+        .await
+        .map_err(|e| format!("running {}: {}", program, e))?;
+    if !status.success() {
+        return Err(format!("{} exited with {}", program, status));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use wiremock::matchers::{body_json, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn change() -> IpChange {
+        IpChange {
+            ip: "2001:db8:1:2::1".parse().unwrap(),
+            source: Some("lan".to_string()),
+            records: vec![ChangedRecord {
+                name: "nas.example.com".to_string(),
+                kind: "AAAA".to_string(),
+                provider: "cloudflare".to_string(),
+                address: "2001:db8:1:2::42".parse().unwrap(),
+            }],
+        }
+    }
+
+    fn notifier(server: &MockServer) -> Notifier {
+        Notifier::new(NotifyConfig {
+            webhook: Some(format!("{}/hook", server.uri())),
+            ..NotifyConfig::default()
+        })
+    }
+
+    #[tokio::test]
+    async fn posts_the_change_as_json() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/hook"))
+            .and(body_json(json!({
+                "ip": "2001:db8:1:2::1",
+                "source": "lan",
+                "records": [{
+                    "name": "nas.example.com",
+                    "type": "AAAA",
+                    "provider": "cloudflare",
+                    "address": "2001:db8:1:2::42",
+                }],
+            })))
+            .respond_with(ResponseTemplate::new(204))
+            .expect(2)
+            .mount(&server)
+            .await;
+
+        // The client is reused for every change
+        let notifier = notifier(&server);
+        notifier.send(&change()).await;
+        notifier.send(&change()).await;
+    }
+
+    #[tokio::test]
+    async fn rejected_post_fails_with_the_status() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(500))
+            .mount(&server)
+            .await;
+
+        let url = format!("{}/hook", server.uri());
+        let err = notifier(&server).post(&url, &change()).await.unwrap_err();
+        assert_eq!(err, format!("POST {}: HTTP 500", url));
+    }
+}
@@ -0,0 +1,126 @@
+//! Ownership markers.
+//!
+//! Like external-dns, an agent with an `owner_id` writes a companion TXT
+//! record next to each record it manages, and leaves records marked by
+//! another owner alone. Two agents, or an agent and a human, then can't
+//! keep overwriting each other's addresses.
+
+use crate::error::ProviderError;
+use crate::provider::{DnsProvider, Zone, wildcard_base};
+use log::{info, warn};
+
+const MARKER_LABEL: &str = "_ddns-owner";
+const HERITAGE: &str = "heritage=ddns-agent";
+
+/// Name of the TXT record marking the owner of `dns_name`. The `*` of a
+/// wildcard can't appear in the middle of a name, so it becomes `_wildcard`.
+pub fn marker_name(dns_name: &str) -> String {
+    match wildcard_base(dns_name) {
+        Some(base) => format!("{}._wildcard.{}", MARKER_LABEL, base),
+        None => format!("{}.{}", MARKER_LABEL, dns_name),
+    }
+}
+
+/// Content of the marker of `owner`
+pub fn marker_value(owner: &str) -> String {
+    format!("{},owner={}", HERITAGE, owner)
+}
+
+/// Owner named by a marker, `None` for TXT records that aren't markers
+pub fn parse_marker(value: &str) -> Option<&str> {
+    value.strip_prefix(HERITAGE)?.strip_prefix(",owner=")
+}
+
+/// Make sure `dns_name` is marked as managed by `owner`. A record marked by
+/// another owner is an error, unless `takeover` replaces their marker.
+pub async fn claim(
+    provider: &dyn DnsProvider,
+    zone: &Zone,
+    dns_name: &str,
+    owner: &str,
+    takeover: bool,
+    dry_run: bool,
+) -> Result<(), ProviderError> {
+    let name = marker_name(dns_name);
+    let markers = match provider.read_txt(zone, &name).await {
+        Ok(values) => values,
+        Err(e @ ProviderError::Unsupported { .. }) => {
+            warn!("Not marking the owner of {}: {}", dns_name, e);
+            return Ok(());
+        }
+        Err(e) => return Err(e),
+    };
+    let owners: Vec<&str> = markers.iter().filter_map(|v| parse_marker(v)).collect();
+    if owners.contains(&owner) {
+        return Ok(());
+    }
+    if let Some(other) = owners.first()
+        && !takeover
+    {
+        return Err(ProviderError::Owned {
+            name: dns_name.to_string(),
+            owner: other.to_string(),
+        });
+    }
+
+    if dry_run {
+        info!(
+            "[dry-run] Would mark {} as managed by `{}` at {}",
+            dns_name,
+            owner,
+            provider.name()
+        );
+        return Ok(());
+    }
+    for other in owners {
+        warn!("Taking over {} from `{}`", dns_name, other);
+        provider
+            .delete_txt(zone, &name, &marker_value(other))
+            .await?;
+    }
+    provider.add_txt(zone, &name, &marker_value(owner)).await?;
+    info!(
+        "Marked {} as managed by `{}` at {}",
+        dns_name,
+        owner,
+        provider.name()
+    );
+    Ok(())
+}
+
+/// Owner other than `owner` marking `dns_name`, if any. Providers without
+/// TXT records have no markers.
+pub async fn other_owner(
+    provider: &dyn DnsProvider,
+    zone: &Zone,
+    dns_name: &str,
+    owner: &str,
+) -> Result<Option<String>, ProviderError> {
+    let markers = match provider.read_txt(zone, &marker_name(dns_name)).await {
+        Ok(values) => values,
+        Err(ProviderError::Unsupported { .. }) => return Ok(None),
+        Err(e) => return Err(e),
+    };
+    let owners: Vec<&str> = markers.iter().filter_map(|v| parse_marker(v)).collect();
+    if owners.contains(&owner) {
+        return Ok(None);
+    }
+    Ok(owners.first().map(|other| other.to_string()))
+}
+
+/// Remove the marker of `owner` from `dns_name`, e.g. once its records are
+/// deleted
+pub async fn release(
+    provider: &dyn DnsProvider,
+    zone: &Zone,
+    dns_name: &str,
+    owner: &str,
+) -> Result<(), ProviderError> {
+    match provider
+        .delete_txt(zone, &marker_name(dns_name), &marker_value(owner))
+        .await
+    {
+        Ok(()) | Err(ProviderError::Unsupported { .. }) => Ok(()),
+        Err(e) => Err(e),
+    }
+}
@@ -0,0 +1,144 @@
+//! Microsoft Entra ID access tokens for Azure Resource Manager, from a
+//! service principal secret or the managed identity of the VM.
+
+use super::error_from_response;
+use crate::error::ProviderError;
+use reqwest::{Client, RequestBuilder};
+use serde::{Deserialize, Deserializer};
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+
+/// Audience of ARM tokens
+const RESOURCE: &str = "https://management.azure.com/";
+pub const AUTHORITY_HOST: &str = "https://login.microsoftonline.com";
+const IMDS_ENDPOINT: &str = "http://169.254.169.254/metadata/identity/oauth2/token";
+/// Refresh tokens this long before they expire
+const EXPIRY_MARGIN: Duration = Duration::from_secs(60);
+
+enum TokenSource {
+    ClientSecret {
+        token_url: String,
+        client_id: String,
+        client_secret: String,
+    },
+    /// The system assigned identity, or a user assigned one by client ID
+    ManagedIdentity { client_id: Option<String> },
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    #[serde(deserialize_with = "seconds")]
+    expires_in: u64,
+}
+
+/// The token endpoint returns a number, IMDS the same number as a string
+fn seconds<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u64, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Seconds {
+        Number(u64),
+        Text(String),
+    }
+    match Seconds::deserialize(deserializer)? {
+        Seconds::Number(n) => Ok(n),
+        Seconds::Text(s) => s.parse().map_err(serde::de::Error::custom),
+    }
+}
+
+/// Hands out cached access tokens, fetching a new one when needed
+pub struct Authenticator {
+    source: TokenSource,
+    cached: Mutex<Option<(String, Instant)>>,
+}
+
+impl Authenticator {
+    /// Authenticate as the service principal `client_id` of `tenant_id`
+    pub fn client_secret(
+        authority_host: &str,
+        tenant_id: &str,
+        client_id: String,
+        client_secret: String,
+    ) -> Self {
+        let token_url = format!(
+            "{}/{}/oauth2/v2.0/token",
+            authority_host.trim_end_matches('/'),
+            tenant_id
+        );
+        Self::from_source(TokenSource::ClientSecret {
+            token_url,
+            client_id,
+            client_secret,
+        })
+    }
+
+    /// Authenticate through the Instance Metadata Service
+    pub fn managed_identity(client_id: Option<String>) -> Self {
+        Self::from_source(TokenSource::ManagedIdentity { client_id })
+    }
+
+    fn from_source(source: TokenSource) -> Self {
+        Self {
+            source,
+            cached: Mutex::new(None),
+        }
+    }
+
+    /// A valid access token
+    pub async fn token(&self, client: &Client) -> Result<String, ProviderError> {
+        let mut cached = self.cached.lock().await;
+        if let Some((token, expires)) = cached.as_ref()
+            && Instant::now() + EXPIRY_MARGIN < *expires
+        {
+            return Ok(token.clone());
+        }
+
+        let resp = fetch(self.request(client)).await?;
+        let expires = Instant::now() + Duration::from_secs(resp.expires_in);
+        *cached = Some((resp.access_token.clone(), expires));
+        Ok(resp.access_token)
+    }
+
+    fn request(&self, client: &Client) -> RequestBuilder {
+        match &self.source {
+            TokenSource::ClientSecret {
+                token_url,
+                client_id,
+                client_secret,
+            } => client.post(token_url).form(&[
+                ("grant_type", "client_credentials"),
+                ("client_id", client_id),
+                ("client_secret", client_secret),
+                ("scope", &format!("{}.default", RESOURCE)),
+            ]),
+            TokenSource::ManagedIdentity { client_id } => {
+                let mut request = client
+                    .get(IMDS_ENDPOINT)
+                    .query(&[("api-version", "2018-02-01"), ("resource", RESOURCE)])
+                    .header("Metadata", "true");
+                if let Some(client_id) = client_id {
+                    request = request.query(&[("client_id", client_id)]);
+                }
+                request
+            }
+        }
+    }
+}
+
+async fn fetch(request: RequestBuilder) -> Result<TokenResponse, ProviderError> {
+    let resp = request.send().await?;
+    let status = resp.status();
+    let headers = resp.headers().clone();
+    let body = resp.text().await?;
+
+    if !status.is_success() {
+        return Err(match error_from_response(status, &headers, &body) {
+            ProviderError::Api(message) | ProviderError::NotFound(message) => {
+                ProviderError::Auth(format!("Token request failed: {}", message))
+            }
+            e => e,
+        });
+    }
+    serde_json::from_str(&body)
+        .map_err(|e| ProviderError::InvalidResponse(format!("token response: {}", e)))
+}
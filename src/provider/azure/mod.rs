@@ -0,0 +1,311 @@
+//! Azure DNS provider using the Azure Resource Manager REST API.
+//!
+//! Zones live in a resource group of a subscription; requests are
+//! authenticated with a service principal secret or a managed identity.
+
+mod auth;
+
+use super::{
+    DnsProvider, Record, RecordType, Zone, extract_root_domain, relative_name, retry_after,
+};
+use crate::error::{ConfigError, ProviderError};
+use async_trait::async_trait;
+use auth::Authenticator;
+use reqwest::header::HeaderMap;
+use reqwest::{Client, RequestBuilder, StatusCode};
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use std::{env, fmt};
+
+const API_BASE: &str = "https://management.azure.com";
+const API_VERSION: &str = "2018-05-01";
+/// TTL used for records configured with the automatic TTL (`1`), matching
+/// the Azure portal default
+const AUTO_TTL: u32 = 3600;
+
+/// Map an unsuccessful response to a [`ProviderError`]
+fn error_from_response(status: StatusCode, headers: &HeaderMap, body: &str) -> ProviderError {
+    let json: serde_json::Value = serde_json::from_str(body).unwrap_or_default();
+    let error = &json["error"];
+    // ARM errors are objects, Entra ID errors a plain code with a description
+    let message = match (error["message"].as_str(), error["code"].as_str()) {
+        (Some(message), Some(code)) => Some(format!("{} ({})", message, code)),
+        (message, _) => message
+            .or_else(|| json["error_description"].as_str())
+            .or_else(|| error.as_str())
+            .map(str::to_string),
+    }
+    .unwrap_or_else(|| format!("HTTP {}", status.as_u16()));
+
+    match status {
+        StatusCode::TOO_MANY_REQUESTS => {
+            let retry_after = retry_after(headers);
+            ProviderError::RateLimited { retry_after }
+        }
+        StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => ProviderError::Auth(message),
+        _ => ProviderError::for_status(status, message),
+    }
+}
+
+#[derive(Deserialize)]
+struct ZoneResponse {
+    name: String,
+}
+
+#[derive(Deserialize)]
+struct RecordSetResponse {
+    id: String,
+    properties: RecordSetProperties,
+}
+
+#[derive(Deserialize)]
+struct RecordSetProperties {
+    #[serde(rename = "TTL")]
+    ttl: u32,
+    #[serde(rename = "ARecords", default)]
+    a_records: Vec<ARecord>,
+    #[serde(rename = "AAAARecords", default)]
+    aaaa_records: Vec<AaaaRecord>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ARecord {
+    ipv4_address: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AaaaRecord {
+    ipv6_address: String,
+}
+
+impl RecordSetResponse {
+    fn into_record(self, dns_name: &str, kind: RecordType) -> Record {
+        let content = match kind {
+            RecordType::A => self
+                .properties
+                .a_records
+                .into_iter()
+                .next()
+                .map(|r| r.ipv4_address),
+            RecordType::Aaaa => self
+                .properties
+                .aaaa_records
+                .into_iter()
+                .next()
+                .map(|r| r.ipv6_address),
+        };
+        Record {
+            id: Some(self.id),
+            name: dns_name.to_string(),
+            kind,
+            content: content.unwrap_or_default(),
+            ttl: self.properties.ttl,
+            proxied: None,
+        }
+    }
+}
+
+/// `[providers.azure]` section of the config file
+#[derive(Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AzureConfig {
+    pub subscription_id: String,
+    pub resource_group: String,
+    /// Name of the DNS zone, defaults to the root domain of each record
+    #[serde(default)]
+    pub zone: Option<String>,
+    /// Service principal credentials, default to `AZURE_TENANT_ID`,
+    /// `AZURE_CLIENT_ID` and `AZURE_CLIENT_SECRET`. Without a secret the
+    /// managed identity is used, `client_id` then picks a user assigned one.
+    #[serde(default)]
+    pub tenant_id: Option<String>,
+    #[serde(default)]
+    pub client_id: Option<String>,
+    #[serde(default)]
+    pub client_secret: Option<String>,
+    /// Entra ID endpoint, for national clouds. Defaults to
+    /// `AZURE_AUTHORITY_HOST` or the public cloud.
+    #[serde(default)]
+    pub authority_host: Option<String>,
+}
+
+impl fmt::Debug for AzureConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AzureConfig")
+            .field("subscription_id", &self.subscription_id)
+            .field("resource_group", &self.resource_group)
+            .field("zone", &self.zone)
+            .field("tenant_id", &self.tenant_id)
+            .field("client_id", &self.client_id)
+            .field(
+                "client_secret",
+                &self.client_secret.as_ref().map(|_| "<redacted>"),
+            )
+            .field("authority_host", &self.authority_host)
+            .finish()
+    }
+}
+
+pub struct AzureProvider {
+    client: Client,
+    auth: Authenticator,
+    subscription_id: String,
+    resource_group: String,
+    zone: Option<String>,
+    base_url: String,
+}
+
+impl AzureProvider {
+    pub fn new(client: Client, config: &AzureConfig) -> Result<Self, ConfigError> {
+        let setting =
+            |value: &Option<String>, var: &str| value.clone().or_else(|| env::var(var).ok());
+        let tenant_id = setting(&config.tenant_id, "AZURE_TENANT_ID");
+        let client_id = setting(&config.client_id, "AZURE_CLIENT_ID");
+
+        let auth = match setting(&config.client_secret, "AZURE_CLIENT_SECRET") {
+            Some(client_secret) => {
+                let missing = |key: &str| {
+                    ConfigError::Missing(format!(
+                        "`{}` in [providers.azure] is required with a client secret",
+                        key
+                    ))
+                };
+                let tenant_id = tenant_id.ok_or_else(|| missing("tenant_id"))?;
+                let client_id = client_id.ok_or_else(|| missing("client_id"))?;
+                let authority_host = setting(&config.authority_host, "AZURE_AUTHORITY_HOST")
+                    .unwrap_or_else(|| auth::AUTHORITY_HOST.to_string());
+                Authenticator::client_secret(&authority_host, &tenant_id, client_id, client_secret)
+            }
+            None => Authenticator::managed_identity(client_id),
+        };
+
+        Ok(Self {
+            client,
+            auth,
+            subscription_id: config.subscription_id.clone(),
+            resource_group: config.resource_group.clone(),
+            zone: config.zone.clone(),
+            base_url: API_BASE.to_string(),
+        })
+    }
+
+    /// Talk to a different API endpoint, e.g. a proxy or a mock server
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into().trim_end_matches('/').to_string();
+        self
+    }
+
+    fn zone_url(&self, zone: &str) -> String {
+        format!(
+            "{}/subscriptions/{}/resourceGroups/{}/providers/Microsoft.Network/dnsZones/{}",
+            self.base_url, self.subscription_id, self.resource_group, zone
+        )
+    }
+
+    fn record_url(&self, zone: &Zone, dns_name: &str, kind: RecordType) -> String {
+        format!(
+            "{}/{}/{}",
+            self.zone_url(&zone.id),
+            kind.as_str(),
+            relative_name(dns_name, &zone.name, "@")
+        )
+    }
+
+    /// Send an authenticated request and parse the JSON response, `None` if
+    /// the resource doesn't exist
+    async fn send<T: DeserializeOwned>(
+        &self,
+        request: RequestBuilder,
+    ) -> Result<Option<T>, ProviderError> {
+        let token = self.auth.token(&self.client).await?;
+        let resp = request
+            .query(&[("api-version", API_VERSION)])
+            .bearer_auth(token)
+            .send()
+            .await?;
+        let status = resp.status();
+        let headers = resp.headers().clone();
+        let body = resp.text().await?;
+
+        if status == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        if !status.is_success() {
+            return Err(error_from_response(status, &headers, &body));
+        }
+        serde_json::from_str(&body)
+            .map(Some)
+            .map_err(|e| ProviderError::InvalidResponse(format!("HTTP {}: {}", status.as_u16(), e)))
+    }
+}
+
+/// TTL the API stores for a record configured with `ttl`
+fn stored_ttl(ttl: u32) -> u32 {
+    if ttl <= 1 { AUTO_TTL } else { ttl }
+}
+
+#[async_trait]
+impl DnsProvider for AzureProvider {
+    fn name(&self) -> &str {
+        "azure"
+    }
+
+    fn effective_ttl(&self, record: &Record) -> Option<u32> {
+        Some(stored_ttl(record.ttl))
+    }
+
+    /// Look up the configured zone, or the root domain of `dns_name`
+    async fn lookup_zone(&self, dns_name: &str) -> Result<Zone, ProviderError> {
+        let name = self
+            .zone
+            .clone()
+            .unwrap_or_else(|| extract_root_domain(dns_name));
+        let request = self.client.get(self.zone_url(&name));
+
+        match self.send::<ZoneResponse>(request).await? {
+            Some(zone) => Ok(Zone {
+                id: zone.name.clone(),
+                name: zone.name,
+            }),
+            None => Err(ProviderError::ZoneNotFound(name)),
+        }
+    }
+
+    async fn read_record(
+        &self,
+        zone: &Zone,
+        dns_name: &str,
+        kind: RecordType,
+    ) -> Result<Option<Record>, ProviderError> {
+        let request = self.client.get(self.record_url(zone, dns_name, kind));
+        let set: Option<RecordSetResponse> = self.send(request).await?;
+        Ok(set.map(|set| set.into_record(dns_name, kind)))
+    }
+
+    /// Replace the record set with a single value
+    async fn upsert_record(&self, zone: &Zone, record: &Record) -> Result<Record, ProviderError> {
+        let ttl = stored_ttl(record.ttl);
+        let properties = match record.kind {
+            RecordType::A => serde_json::json!({
+                "TTL": ttl,
+                "ARecords": [{ "ipv4Address": record.content }]
+            }),
+            RecordType::Aaaa => serde_json::json!({
+                "TTL": ttl,
+                "AAAARecords": [{ "ipv6Address": record.content }]
+            }),
+        };
+
+        let request = self
+            .client
+            .put(self.record_url(zone, &record.name, record.kind))
+            .json(&serde_json::json!({ "properties": properties }));
+        let set: RecordSetResponse = self
+            .send(request)
+            .await?
+            .ok_or_else(|| ProviderError::ZoneNotFound(zone.name.clone()))?;
+        Ok(set.into_record(&record.name, record.kind))
+    }
+}
@@ -0,0 +1,778 @@
+//! Cloudflare DNS provider using the v4 REST API.
+//!
+//! [`CloudflarePoolProvider`] updates the origins of a load balancer pool
+//! instead, for hostnames fronted by a Cloudflare load balancer.
+
+use super::{
+    DnsProvider, Record, RecordType, SrvRecord, TXT_TTL, Zone, ascii_name, extract_root_domain,
+    retry_after, timestamp,
+};
+use crate::error::ProviderError;
+use async_trait::async_trait;
+use reqwest::{Client, RequestBuilder, StatusCode};
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::time::SystemTime;
+
+const API_BASE: &str = "https://api.cloudflare.com/client/v4";
+/// Changes per batch request allowed on every plan
+const BATCH_SIZE: usize = 200;
+
+#[derive(Deserialize)]
+struct CfResponse<T> {
+    success: bool,
+    #[serde(default)]
+    errors: Vec<CfError>,
+    result: Option<T>,
+    result_info: Option<CfResultInfo>,
+}
+
+#[derive(Deserialize)]
+struct CfResultInfo {
+    page: u32,
+    total_pages: u32,
+}
+
+#[derive(Deserialize)]
+struct CfError {
+    code: i64,
+    message: String,
+}
+
+#[derive(Deserialize)]
+struct CfZone {
+    id: String,
+    name: String,
+}
+
+#[derive(Deserialize)]
+struct CfTxtRecord {
+    id: String,
+    name: String,
+    content: String,
+}
+
+#[derive(Deserialize)]
+struct CfSrvRecord {
+    id: String,
+    name: String,
+    ttl: u32,
+    data: CfSrvData,
+}
+
+#[derive(Deserialize)]
+struct CfSrvData {
+    priority: u16,
+    weight: u16,
+    port: u16,
+    target: String,
+}
+
+#[derive(Deserialize)]
+struct CfPool {
+    id: String,
+    name: String,
+    /// Kept as is, so updates only change the address of one origin
+    origins: Vec<serde_json::Value>,
+}
+
+#[derive(Deserialize)]
+struct CfBatchResult {
+    #[serde(default)]
+    puts: Vec<CfDnsRecord>,
+}
+
+#[derive(Deserialize)]
+struct CfDnsRecord {
+    id: String,
+    name: String,
+    content: String,
+    ttl: u32,
+    proxied: Option<bool>,
+}
+
+impl CfDnsRecord {
+    fn into_record(self, kind: RecordType) -> Record {
+        Record {
+            id: Some(self.id),
+            name: self.name,
+            kind,
+            content: self.content,
+            ttl: self.ttl,
+            proxied: self.proxied,
+        }
+    }
+}
+
+/// Cloudflare error codes meaning the token is invalid or lacks permissions
+const AUTH_ERROR_CODES: [i64; 3] = [9109, 10000, 10001];
+
+fn error_message(errors: &[CfError]) -> String {
+    if errors.is_empty() {
+        return "unknown error".to_string();
+    }
+    errors
+        .iter()
+        .map(|e| format!("{} ({})", e.message, e.code))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// `[providers.cloudflare]` section of the config file
+#[derive(Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CloudflareConfig {
+    /// API token with DNS edit permissions
+    pub api_token: String,
+    /// Comment set on the address records the agent writes; `{time}` is
+    /// replaced with the time of the update
+    #[serde(default)]
+    pub comment: Option<String>,
+    /// Tags (`name:value`) set on the address records the agent writes
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Further accounts, keyed by a name of choice, whose tokens are used
+    /// for the zones they list instead of `api_token`
+    #[serde(default)]
+    pub accounts: BTreeMap<String, CloudflareAccount>,
+    /// Load balancer pool whose origins records of the
+    /// `cloudflare.load_balancer` provider update
+    #[serde(default)]
+    pub load_balancer: Option<CloudflareLoadBalancer>,
+}
+
+impl CloudflareConfig {
+    pub fn new(api_token: impl Into<String>) -> Self {
+        Self {
+            api_token: api_token.into(),
+            comment: None,
+            tags: Vec::new(),
+            accounts: BTreeMap::new(),
+            load_balancer: None,
+        }
+    }
+
+    /// Name of the account listing `zone`, if any
+    pub fn account_for(&self, zone: &str) -> Option<&str> {
+        self.accounts
+            .iter()
+            .find(|(_, account)| {
+                account
+                    .zones
+                    .iter()
+                    .any(|z| ascii_name(z.trim_end_matches('.')).as_deref() == Some(zone))
+            })
+            .map(|(name, _)| name.as_str())
+    }
+}
+
+/// `[providers.cloudflare.accounts.<name>]` section of the config file
+#[derive(Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CloudflareAccount {
+    /// API token with DNS edit permissions in this account
+    pub api_token: String,
+    /// Zones managed with this token
+    #[serde(default)]
+    pub zones: Vec<String>,
+}
+
+/// `[providers.cloudflare.load_balancer]` section of the config file
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CloudflareLoadBalancer {
+    /// Account owning the pool
+    pub account_id: String,
+    pub pool_id: String,
+}
+
+impl fmt::Debug for CloudflareAccount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CloudflareAccount")
+            .field("api_token", &"<redacted>")
+            .field("zones", &self.zones)
+            .finish()
+    }
+}
+
+impl fmt::Debug for CloudflareConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CloudflareConfig")
+            .field("api_token", &"<redacted>")
+            .field("comment", &self.comment)
+            .field("tags", &self.tags)
+            .field("accounts", &self.accounts)
+            .field("load_balancer", &self.load_balancer)
+            .finish()
+    }
+}
+
+pub struct CloudflareProvider {
+    name: String,
+    client: Client,
+    api_token: String,
+    base_url: String,
+    comment: Option<String>,
+    tags: Vec<String>,
+}
+
+impl CloudflareProvider {
+    pub fn new(client: Client, api_token: impl Into<String>) -> Self {
+        Self {
+            name: "cloudflare".to_string(),
+            client,
+            api_token: api_token.into(),
+            base_url: API_BASE.to_string(),
+            comment: None,
+            tags: Vec::new(),
+        }
+    }
+
+    /// Talk to a different API endpoint, e.g. a proxy or a mock server
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into().trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Call this provider `cloudflare.<account>`, telling the accounts of
+    /// one config apart in logs and the ID cache
+    pub fn with_account(mut self, account: &str) -> Self {
+        self.name = format!("cloudflare.{}", account);
+        self
+    }
+
+    /// Set this comment on written records, `{time}` being replaced with
+    /// the time of the update
+    pub fn with_comment(mut self, comment: Option<String>) -> Self {
+        self.comment = comment;
+        self
+    }
+
+    /// Set these tags on written records
+    pub fn with_tags(mut self, tags: Vec<String>) -> Self {
+        self.tags = tags;
+        self
+    }
+}
+
+/// Current UTC time as `2024-05-01T12:00:00Z`
+fn utc_now() -> String {
+    timestamp::format(SystemTime::now())
+}
+
+impl CloudflareProvider {
+    /// Body creating or overwriting `record`
+    fn record_body(&self, record: &Record) -> serde_json::Value {
+        let mut body = serde_json::json!({
+            "type": record.kind.as_str(),
+            "name": record.name,
+            "content": record.content,
+            "ttl": record.ttl,
+            "proxied": record.proxied.unwrap_or(false)
+        });
+        if let Some(comment) = &self.comment {
+            body["comment"] = comment.replace("{time}", &utc_now()).into();
+        }
+        if !self.tags.is_empty() {
+            body["tags"] = self.tags.clone().into();
+        }
+        body
+    }
+
+    /// Send an authenticated request and unwrap the Cloudflare response envelope
+    async fn send<T: DeserializeOwned>(&self, request: RequestBuilder) -> Result<T, ProviderError> {
+        self.send_page(request).await.map(|(result, _)| result)
+    }
+
+    /// Walk the pages of a list endpoint until an item matching `matches`
+    /// is found
+    async fn find<T: DeserializeOwned>(
+        &self,
+        url: &str,
+        query: &[(&str, &str)],
+        matches: impl Fn(&T) -> bool,
+    ) -> Result<Option<T>, ProviderError> {
+        let mut page = 1;
+        loop {
+            let (items, next) = self.list_page(url, query, page).await?;
+            if let Some(item) = items.into_iter().find(&matches) {
+                return Ok(Some(item));
+            }
+            match next {
+                Some(next) => page = next,
+                None => return Ok(None),
+            }
+        }
+    }
+
+    /// Walk all pages of a list endpoint, collecting the items matching
+    /// `matches`
+    async fn find_all<T: DeserializeOwned>(
+        &self,
+        url: &str,
+        query: &[(&str, &str)],
+        matches: impl Fn(&T) -> bool,
+    ) -> Result<Vec<T>, ProviderError> {
+        let mut found = Vec::new();
+        let mut page = 1;
+        loop {
+            let (items, next) = self.list_page(url, query, page).await?;
+            found.extend(items.into_iter().filter(&matches));
+            match next {
+                Some(next) => page = next,
+                None => return Ok(found),
+            }
+        }
+    }
+
+    /// One page of a list endpoint and the number of the next one, if any
+    async fn list_page<T: DeserializeOwned>(
+        &self,
+        url: &str,
+        query: &[(&str, &str)],
+        page: u32,
+    ) -> Result<(Vec<T>, Option<u32>), ProviderError> {
+        let request = self.client.get(url).query(query).query(&[("page", page)]);
+        let (items, info): (Vec<T>, _) = self.send_page(request).await?;
+        let next = info
+            .filter(|info| info.page < info.total_pages)
+            .map(|info| info.page + 1);
+        Ok((items, next))
+    }
+
+    /// The SRV record of `dns_name` with its ID, if it exists
+    async fn find_srv(
+        &self,
+        zone: &Zone,
+        dns_name: &str,
+    ) -> Result<Option<CfSrvRecord>, ProviderError> {
+        self.find(
+            &format!("{}/zones/{}/dns_records", self.base_url, zone.id),
+            &[("type", "SRV"), ("name", dns_name)],
+            |r: &CfSrvRecord| r.name.eq_ignore_ascii_case(dns_name),
+        )
+        .await
+    }
+
+    /// Like [`CloudflareProvider::send`], also returning the pagination info
+    async fn send_page<T: DeserializeOwned>(
+        &self,
+        request: RequestBuilder,
+    ) -> Result<(T, Option<CfResultInfo>), ProviderError> {
+        let resp = request.bearer_auth(&self.api_token).send().await?;
+        let status = resp.status();
+
+        if status == StatusCode::TOO_MANY_REQUESTS {
+            let retry_after = retry_after(resp.headers());
+            return Err(ProviderError::RateLimited { retry_after });
+        }
+
+        let body = resp.text().await?;
+        let parsed = serde_json::from_str::<CfResponse<T>>(&body);
+        // Gateway errors come as HTML pages
+        if status.is_server_error() {
+            let message = match &parsed {
+                Ok(data) if !data.errors.is_empty() => error_message(&data.errors),
+                _ => format!("HTTP {}", status.as_u16()),
+            };
+            return Err(ProviderError::Unavailable(message));
+        }
+        // Only requests addressing a zone or record by ID answer with 404
+        let data = match parsed {
+            Err(_) if status == StatusCode::NOT_FOUND => {
+                return Err(ProviderError::NotFound(format!("HTTP {}", status.as_u16())));
+            }
+            parsed => parsed,
+        };
+        let data = data.map_err(|e| {
+            ProviderError::InvalidResponse(format!("HTTP {}: {}", status.as_u16(), e))
+        })?;
+
+        if status == StatusCode::UNAUTHORIZED
+            || status == StatusCode::FORBIDDEN
+            || data
+                .errors
+                .iter()
+                .any(|e| AUTH_ERROR_CODES.contains(&e.code))
+        {
+            return Err(ProviderError::Auth(error_message(&data.errors)));
+        }
+        if status == StatusCode::NOT_FOUND {
+            return Err(ProviderError::NotFound(error_message(&data.errors)));
+        }
+        if !data.success {
+            return Err(ProviderError::Api(error_message(&data.errors)));
+        }
+
+        let result = data
+            .result
+            .ok_or_else(|| ProviderError::InvalidResponse("missing result".to_string()))?;
+        Ok((result, data.result_info))
+    }
+}
+
+#[async_trait]
+impl DnsProvider for CloudflareProvider {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Proxied records always have the automatic TTL
+    fn effective_ttl(&self, record: &Record) -> Option<u32> {
+        match record.proxied {
+            Some(true) => Some(1),
+            _ => Some(record.ttl),
+        }
+    }
+
+    /// Fetch Zone ID from Cloudflare API based on domain name. The name has
+    /// to match exactly and only one zone may have it.
+    async fn lookup_zone(&self, dns_name: &str) -> Result<Zone, ProviderError> {
+        let domain = extract_root_domain(dns_name);
+        let mut zones: Vec<CfZone> = self
+            .find_all(
+                &format!("{}/zones", self.base_url),
+                &[("name", &domain)],
+                |z: &CfZone| z.name.eq_ignore_ascii_case(&domain),
+            )
+            .await?;
+        match zones.len() {
+            0 => Err(ProviderError::ZoneNotFound(domain)),
+            1 => {
+                let zone = zones.remove(0);
+                Ok(Zone {
+                    id: zone.id,
+                    name: zone.name,
+                })
+            }
+            _ => {
+                let ids: Vec<_> = zones.into_iter().map(|z| z.id).collect();
+                Err(ProviderError::AmbiguousZone(format!(
+                    "{} (IDs {})",
+                    domain,
+                    ids.join(", ")
+                )))
+            }
+        }
+    }
+
+    /// Fetch DNS record from Cloudflare API based on zone id and dns name
+    async fn read_record(
+        &self,
+        zone: &Zone,
+        dns_name: &str,
+        kind: RecordType,
+    ) -> Result<Option<Record>, ProviderError> {
+        let record: Option<CfDnsRecord> = self
+            .find(
+                &format!("{}/zones/{}/dns_records", self.base_url, zone.id),
+                &[("type", kind.as_str()), ("name", dns_name)],
+                |r: &CfDnsRecord| r.name.eq_ignore_ascii_case(dns_name),
+            )
+            .await?;
+        Ok(record.map(|r| r.into_record(kind)))
+    }
+
+    async fn upsert_record(&self, zone: &Zone, record: &Record) -> Result<Record, ProviderError> {
+        let body = self.record_body(record);
+
+        let request = match &record.id {
+            Some(id) => self.client.put(format!(
+                "{}/zones/{}/dns_records/{}",
+                self.base_url, zone.id, id
+            )),
+            None => self
+                .client
+                .post(format!("{}/zones/{}/dns_records", self.base_url, zone.id)),
+        };
+
+        let updated: CfDnsRecord = self.send(request.json(&body)).await?;
+        Ok(updated.into_record(record.kind))
+    }
+
+    /// Overwrite the records with the `puts` of batch requests, which
+    /// Cloudflare applies atomically
+    async fn update_records(
+        &self,
+        zone: &Zone,
+        records: &[Record],
+    ) -> Result<Vec<Record>, ProviderError> {
+        let url = format!("{}/zones/{}/dns_records/batch", self.base_url, zone.id);
+        let mut updated = Vec::with_capacity(records.len());
+        for chunk in records.chunks(BATCH_SIZE) {
+            let puts = chunk
+                .iter()
+                .map(|record| {
+                    let mut body = self.record_body(record);
+                    body["id"] = record.id.clone().into();
+                    body
+                })
+                .collect::<Vec<_>>();
+            let request = self
+                .client
+                .post(&url)
+                .json(&serde_json::json!({ "puts": puts }));
+            let result: CfBatchResult = self.send(request).await?;
+            if result.puts.len() != chunk.len() {
+                return Err(ProviderError::InvalidResponse(format!(
+                    "batch wrote {} of {} records",
+                    result.puts.len(),
+                    chunk.len()
+                )));
+            }
+            updated.extend(
+                result
+                    .puts
+                    .into_iter()
+                    .zip(chunk)
+                    .map(|(stored, record)| stored.into_record(record.kind)),
+            );
+        }
+        Ok(updated)
+    }
+
+    async fn read_record_set(
+        &self,
+        zone: &Zone,
+        dns_name: &str,
+        kind: RecordType,
+    ) -> Result<Vec<Record>, ProviderError> {
+        let records: Vec<CfDnsRecord> = self
+            .find_all(
+                &format!("{}/zones/{}/dns_records", self.base_url, zone.id),
+                &[("type", kind.as_str()), ("name", dns_name)],
+                |r: &CfDnsRecord| r.name.eq_ignore_ascii_case(dns_name),
+            )
+            .await?;
+        Ok(records.into_iter().map(|r| r.into_record(kind)).collect())
+    }
+
+    /// Every address of a name is a record of its own, so this creates one
+    async fn add_address(&self, zone: &Zone, record: &Record) -> Result<Record, ProviderError> {
+        let record = Record {
+            id: None,
+            ..record.clone()
+        };
+        self.upsert_record(zone, &record).await
+    }
+
+    async fn delete_address(&self, zone: &Zone, record: &Record) -> Result<(), ProviderError> {
+        let Some(id) = &record.id else {
+            return Err(ProviderError::RecordNotFound {
+                name: record.name.clone(),
+                kind: record.kind,
+            });
+        };
+        let request = self.client.delete(format!(
+            "{}/zones/{}/dns_records/{}",
+            self.base_url, zone.id, id
+        ));
+        self.send::<serde_json::Value>(request).await?;
+        Ok(())
+    }
+
+    async fn read_txt(&self, zone: &Zone, dns_name: &str) -> Result<Vec<String>, ProviderError> {
+        let records: Vec<CfTxtRecord> = self
+            .find_all(
+                &format!("{}/zones/{}/dns_records", self.base_url, zone.id),
+                &[("type", "TXT"), ("name", dns_name)],
+                |r: &CfTxtRecord| r.name.eq_ignore_ascii_case(dns_name),
+            )
+            .await?;
+        Ok(records
+            .into_iter()
+            .map(|r| r.content.trim_matches('"').to_string())
+            .collect())
+    }
+
+    async fn add_txt(&self, zone: &Zone, dns_name: &str, value: &str) -> Result<(), ProviderError> {
+        let body = serde_json::json!({
+            "type": "TXT",
+            "name": dns_name,
+            "content": value,
+            "ttl": TXT_TTL
+        });
+        let request = self
+            .client
+            .post(format!("{}/zones/{}/dns_records", self.base_url, zone.id))
+            .json(&body);
+        self.send::<serde_json::Value>(request).await?;
+        Ok(())
+    }
+
+    async fn delete_txt(
+        &self,
+        zone: &Zone,
+        dns_name: &str,
+        value: &str,
+    ) -> Result<(), ProviderError> {
+        let records_url = format!("{}/zones/{}/dns_records", self.base_url, zone.id);
+        // TXT content may come back quoted
+        let records: Vec<CfTxtRecord> = self
+            .find_all(
+                &records_url,
+                &[("type", "TXT"), ("name", dns_name)],
+                |r: &CfTxtRecord| {
+                    r.name.eq_ignore_ascii_case(dns_name) && r.content.trim_matches('"') == value
+                },
+            )
+            .await?;
+        for record in records {
+            let request = self.client.delete(format!("{}/{}", records_url, record.id));
+            self.send::<serde_json::Value>(request).await?;
+        }
+        Ok(())
+    }
+
+    async fn read_srv(
+        &self,
+        zone: &Zone,
+        dns_name: &str,
+    ) -> Result<Option<SrvRecord>, ProviderError> {
+        Ok(self.find_srv(zone, dns_name).await?.map(|r| SrvRecord {
+            name: r.name,
+            priority: r.data.priority,
+            weight: r.data.weight,
+            port: r.data.port,
+            target: r.data.target,
+            ttl: r.ttl,
+        }))
+    }
+
+    async fn upsert_srv(&self, zone: &Zone, record: &SrvRecord) -> Result<(), ProviderError> {
+        let records_url = format!("{}/zones/{}/dns_records", self.base_url, zone.id);
+        let body = serde_json::json!({
+            "type": "SRV",
+            "name": record.name,
+            "ttl": record.ttl,
+            "data": {
+                "priority": record.priority,
+                "weight": record.weight,
+                "port": record.port,
+                "target": record.target
+            }
+        });
+        let request = match self.find_srv(zone, &record.name).await? {
+            Some(existing) => self.client.put(format!("{}/{}", records_url, existing.id)),
+            None => self.client.post(records_url),
+        };
+        self.send::<serde_json::Value>(request.json(&body)).await?;
+        Ok(())
+    }
+}
+
+/// Provider writing the address of records to the origin of the same name
+/// in a load balancer pool, which stands in for the zone
+pub struct CloudflarePoolProvider {
+    api: CloudflareProvider,
+    account_id: String,
+    pool_id: String,
+}
+
+impl CloudflarePoolProvider {
+    pub fn new(
+        client: Client,
+        api_token: impl Into<String>,
+        pool: &CloudflareLoadBalancer,
+    ) -> Self {
+        Self {
+            api: CloudflareProvider::new(client, api_token),
+            account_id: pool.account_id.clone(),
+            pool_id: pool.pool_id.clone(),
+        }
+    }
+
+    /// Talk to a different API endpoint, e.g. a proxy or a mock server
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.api = self.api.with_base_url(base_url);
+        self
+    }
+
+    fn pool_url(&self) -> String {
+        format!(
+            "{}/accounts/{}/load_balancers/pools/{}",
+            self.api.base_url, self.account_id, self.pool_id
+        )
+    }
+
+    async fn pool(&self) -> Result<CfPool, ProviderError> {
+        self.api.send(self.api.client.get(self.pool_url())).await
+    }
+}
+
+/// Name of a pool origin, empty if it has none
+fn origin_name(origin: &serde_json::Value) -> &str {
+    origin["name"].as_str().unwrap_or_default()
+}
+
+#[async_trait]
+impl DnsProvider for CloudflarePoolProvider {
+    fn name(&self) -> &str {
+        "cloudflare.load_balancer"
+    }
+
+    /// Origins have no TTL
+    fn effective_ttl(&self, _record: &Record) -> Option<u32> {
+        None
+    }
+
+    /// The load balancer answers for the name, with any healthy origin
+    fn publishes_records(&self) -> bool {
+        false
+    }
+
+    /// The pool, whatever the name
+    async fn lookup_zone(&self, _dns_name: &str) -> Result<Zone, ProviderError> {
+        let pool = self.pool().await?;
+        Ok(Zone {
+            id: pool.id,
+            name: pool.name,
+        })
+    }
+
+    async fn read_record(
+        &self,
+        _zone: &Zone,
+        dns_name: &str,
+        kind: RecordType,
+    ) -> Result<Option<Record>, ProviderError> {
+        let pool = self.pool().await?;
+        Ok(pool
+            .origins
+            .iter()
+            .find(|o| origin_name(o).eq_ignore_ascii_case(dns_name))
+            .map(|origin| Record {
+                id: Some(origin_name(origin).to_string()),
+                name: dns_name.to_string(),
+                kind,
+                content: origin["address"].as_str().unwrap_or_default().to_string(),
+                ttl: 1,
+                proxied: None,
+            }))
+    }
+
+    /// Set the address of the origin, sending the other origins back as
+    /// they are
+    async fn upsert_record(&self, _zone: &Zone, record: &Record) -> Result<Record, ProviderError> {
+        let mut pool = self.pool().await?;
+        let origin = pool
+            .origins
+            .iter_mut()
+            .find(|o| origin_name(o).eq_ignore_ascii_case(&record.name))
+            .ok_or_else(|| ProviderError::RecordNotFound {
+                name: record.name.clone(),
+                kind: record.kind,
+            })?;
+        origin["address"] = record.content.clone().into();
+
+        let request = self
+            .api
+            .client
+            .patch(self.pool_url())
+            .json(&serde_json::json!({ "origins": pool.origins }));
+        self.api.send::<serde_json::Value>(request).await?;
+        Ok(record.clone())
+    }
+}
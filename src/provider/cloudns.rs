@@ -0,0 +1,297 @@
+//! ClouDNS provider using its HTTP API.
+//!
+//! The API authenticates every call with form parameters: either the main
+//! `auth-id`, or a sub user (`sub-auth-id` or `sub-auth-user`) that can be
+//! limited to a few zones. Calls answer with HTTP 200 and report failures in
+//! a `status` field.
+
+use super::{
+    DnsProvider, Record, RecordType, Zone, extract_root_domain, relative_name, retry_after,
+};
+use crate::error::{ConfigError, ProviderError};
+use async_trait::async_trait;
+use reqwest::{Client, StatusCode};
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use std::fmt;
+
+const API_BASE: &str = "https://api.cloudns.net";
+/// TTL used for records configured with the automatic TTL (`1`), matching
+/// the ClouDNS default
+const AUTO_TTL: u32 = 3600;
+/// Lowest TTL ClouDNS accepts
+const MIN_TTL: u32 = 60;
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Status {
+    #[serde(default)]
+    status: String,
+    #[serde(default)]
+    status_description: String,
+}
+
+#[derive(Deserialize)]
+struct ClouDnsZone {
+    name: String,
+}
+
+#[derive(Deserialize)]
+struct ClouDnsRecord {
+    id: String,
+    /// Name relative to the zone, empty for the apex
+    host: String,
+    #[serde(rename = "type")]
+    kind: String,
+    record: String,
+    /// Numbers are sent as strings
+    ttl: String,
+}
+
+#[derive(Deserialize)]
+struct Created {
+    data: CreatedRecord,
+}
+
+#[derive(Deserialize)]
+struct CreatedRecord {
+    id: Value,
+}
+
+/// `[providers.cloudns]` section of the config file
+#[derive(Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ClouDnsConfig {
+    /// ID of the main API user
+    #[serde(default)]
+    pub auth_id: Option<String>,
+    /// ID of a sub user, which can be restricted to some zones
+    #[serde(default)]
+    pub sub_auth_id: Option<String>,
+    /// Name of a sub user, instead of its ID
+    #[serde(default)]
+    pub sub_auth_user: Option<String>,
+    pub auth_password: String,
+}
+
+impl fmt::Debug for ClouDnsConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ClouDnsConfig")
+            .field("auth_id", &self.auth_id)
+            .field("sub_auth_id", &self.sub_auth_id)
+            .field("sub_auth_user", &self.sub_auth_user)
+            .field("auth_password", &"<redacted>")
+            .finish()
+    }
+}
+
+pub struct ClouDnsProvider {
+    client: Client,
+    /// Name and value of the user parameter
+    user: (&'static str, String),
+    auth_password: String,
+    base_url: String,
+}
+
+impl ClouDnsProvider {
+    /// Fails unless exactly one of the user IDs is set
+    pub fn new(client: Client, config: &ClouDnsConfig) -> Result<Self, ConfigError> {
+        let users: Vec<_> = [
+            ("auth-id", &config.auth_id),
+            ("sub-auth-id", &config.sub_auth_id),
+            ("sub-auth-user", &config.sub_auth_user),
+        ]
+        .into_iter()
+        .filter_map(|(param, value)| value.clone().map(|v| (param, v)))
+        .collect();
+        let user = match <[_; 1]>::try_from(users) {
+            Ok([user]) => user,
+            Err(users) if users.is_empty() => {
+                return Err(ConfigError::Missing(
+                    "[providers.cloudns] needs one of `auth_id`, `sub_auth_id` or `sub_auth_user`"
+                        .into(),
+                ));
+            }
+            Err(_) => {
+                return Err(ConfigError::Invalid(
+                    "[providers.cloudns] takes only one of `auth_id`, `sub_auth_id` and \
+                     `sub_auth_user`"
+                        .into(),
+                ));
+            }
+        };
+
+        Ok(Self {
+            client,
+            user,
+            auth_password: config.auth_password.clone(),
+            base_url: API_BASE.to_string(),
+        })
+    }
+
+    /// Talk to a different API endpoint, e.g. a proxy or a mock server
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into().trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Call `dns/{method}.json` with the credentials and `params` posted as
+    /// a form, and parse the JSON response
+    async fn call<T: DeserializeOwned>(
+        &self,
+        method: &str,
+        params: &[(&str, &str)],
+    ) -> Result<T, ProviderError> {
+        let mut form = vec![
+            (self.user.0, self.user.1.as_str()),
+            ("auth-password", self.auth_password.as_str()),
+        ];
+        form.extend_from_slice(params);
+        let resp = self
+            .client
+            .post(format!("{}/dns/{}.json", self.base_url, method))
+            .form(&form)
+            .send()
+            .await?;
+        let status = resp.status();
+
+        if status == StatusCode::TOO_MANY_REQUESTS {
+            let retry_after = retry_after(resp.headers());
+            return Err(ProviderError::RateLimited { retry_after });
+        }
+
+        let body = resp.text().await?;
+        if !status.is_success() {
+            return Err(match status {
+                StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => {
+                    ProviderError::Auth(format!("HTTP {}", status.as_u16()))
+                }
+                _ => ProviderError::for_status(status, format!("HTTP {}", status.as_u16())),
+            });
+        }
+
+        // Failures come with a 200 and `"status": "Failed"`
+        if let Ok(failed) = serde_json::from_str::<Status>(&body)
+            && failed.status.eq_ignore_ascii_case("failed")
+        {
+            let message = failed.status_description;
+            let lower = message.to_lowercase();
+            return Err(if lower.contains("auth") || lower.contains("permission") {
+                ProviderError::Auth(message)
+            } else {
+                ProviderError::Api(message)
+            });
+        }
+        serde_json::from_str(&body)
+            .map_err(|e| ProviderError::InvalidResponse(format!("HTTP {}: {}", status.as_u16(), e)))
+    }
+}
+
+/// TTL the API stores for a record configured with `ttl`
+fn stored_ttl(ttl: u32) -> u32 {
+    match ttl {
+        1 => AUTO_TTL,
+        ttl => ttl.max(MIN_TTL),
+    }
+}
+
+#[async_trait]
+impl DnsProvider for ClouDnsProvider {
+    fn name(&self) -> &str {
+        "cloudns"
+    }
+
+    fn effective_ttl(&self, record: &Record) -> Option<u32> {
+        Some(stored_ttl(record.ttl))
+    }
+
+    /// Look up the zone of the root domain of `dns_name`; its name doubles
+    /// as the ID
+    async fn lookup_zone(&self, dns_name: &str) -> Result<Zone, ProviderError> {
+        let domain = extract_root_domain(dns_name);
+        let zone: ClouDnsZone = self
+            .call("get-zone-info", &[("domain-name", domain.as_str())])
+            .await
+            .map_err(|e| match e {
+                // Unknown zones are answered like any other failure
+                ProviderError::Api(_) | ProviderError::NotFound(_) => {
+                    ProviderError::ZoneNotFound(domain.clone())
+                }
+                e => e,
+            })?;
+        Ok(Zone {
+            id: zone.name.clone(),
+            name: zone.name,
+        })
+    }
+
+    async fn read_record(
+        &self,
+        zone: &Zone,
+        dns_name: &str,
+        kind: RecordType,
+    ) -> Result<Option<Record>, ProviderError> {
+        let host = relative_name(dns_name, &zone.name, "");
+        let params = [
+            ("domain-name", zone.id.as_str()),
+            ("host", host.as_str()),
+            ("type", kind.as_str()),
+        ];
+        // An object keyed by record ID, or an empty list without matches
+        let records: Value = self.call("records", &params).await?;
+        let records: Vec<ClouDnsRecord> = match records {
+            Value::Object(records) => records
+                .into_iter()
+                .map(|(_, r)| serde_json::from_value(r))
+                .collect::<Result<_, _>>()
+                .map_err(|e| ProviderError::InvalidResponse(e.to_string()))?,
+            _ => Vec::new(),
+        };
+
+        Ok(records
+            .into_iter()
+            .find(|r| r.kind == kind.as_str() && r.host.eq_ignore_ascii_case(&host))
+            .map(|r| Record {
+                id: Some(r.id),
+                name: dns_name.to_string(),
+                kind,
+                content: r.record,
+                ttl: r.ttl.parse().unwrap_or(AUTO_TTL),
+                proxied: None,
+            }))
+    }
+
+    async fn upsert_record(&self, zone: &Zone, record: &Record) -> Result<Record, ProviderError> {
+        let ttl = stored_ttl(record.ttl);
+        let ttl_param = ttl.to_string();
+        let host = relative_name(&record.name, &zone.name, "");
+        let mut params = vec![
+            ("domain-name", zone.id.as_str()),
+            ("host", host.as_str()),
+            ("record", record.content.as_str()),
+            ("ttl", ttl_param.as_str()),
+        ];
+
+        let id = match &record.id {
+            Some(id) => {
+                params.push(("record-id", id));
+                self.call::<Status>("mod-record", &params).await?;
+                id.clone()
+            }
+            None => {
+                params.push(("record-type", record.kind.as_str()));
+                let created: Created = self.call("add-record", &params).await?;
+                match created.data.id {
+                    Value::String(id) => id,
+                    id => id.to_string(),
+                }
+            }
+        };
+        Ok(Record {
+            id: Some(id),
+            ttl,
+            ..record.clone()
+        })
+    }
+}
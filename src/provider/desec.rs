@@ -0,0 +1,203 @@
+//! deSEC provider using the v1 rrsets API.
+//!
+//! deSEC throttles aggressively, answering with a 429 and a `Retry-After`
+//! header, which the agent's rate limit backoff honours.
+
+use super::{DnsProvider, Record, RecordType, Zone, retry_after};
+use crate::error::ProviderError;
+use async_trait::async_trait;
+use reqwest::header::AUTHORIZATION;
+use reqwest::{Client, RequestBuilder, StatusCode};
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use std::fmt;
+
+const API_BASE: &str = "https://desec.io/api/v1";
+/// Lowest TTL deSEC accepts by default, also used for the automatic TTL (`1`)
+const MIN_TTL: u32 = 3600;
+
+#[derive(Deserialize)]
+struct DesecDomain {
+    name: String,
+}
+
+#[derive(Deserialize)]
+struct RecordSet {
+    records: Vec<String>,
+    ttl: u32,
+}
+
+/// Name of `dns_name` relative to `domain`, empty for the apex
+fn subname(dns_name: &str, domain: &str) -> String {
+    if dns_name.eq_ignore_ascii_case(domain) {
+        return String::new();
+    }
+    dns_name
+        .strip_suffix(&format!(".{}", domain))
+        .unwrap_or(dns_name)
+        .to_string()
+}
+
+/// `[providers.desec]` section of the config file
+#[derive(Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DesecConfig {
+    /// Token created under "Token Management"
+    pub token: String,
+}
+
+impl fmt::Debug for DesecConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DesecConfig")
+            .field("token", &"<redacted>")
+            .finish()
+    }
+}
+
+pub struct DesecProvider {
+    client: Client,
+    token: String,
+    base_url: String,
+}
+
+impl DesecProvider {
+    pub fn new(client: Client, token: impl Into<String>) -> Self {
+        Self {
+            client,
+            token: token.into(),
+            base_url: API_BASE.to_string(),
+        }
+    }
+
+    /// Talk to a different API endpoint, e.g. a proxy or a mock server
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into().trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Send an authenticated request and parse the JSON response, `None` if
+    /// the resource doesn't exist
+    async fn send<T: DeserializeOwned>(
+        &self,
+        request: RequestBuilder,
+    ) -> Result<Option<T>, ProviderError> {
+        let resp = request
+            .header(AUTHORIZATION, format!("Token {}", self.token))
+            .send()
+            .await?;
+        let status = resp.status();
+
+        if status == StatusCode::TOO_MANY_REQUESTS {
+            let retry_after = retry_after(resp.headers());
+            return Err(ProviderError::RateLimited { retry_after });
+        }
+        if status == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+
+        let body = resp.text().await?;
+        if !status.is_success() {
+            // `{"detail": ...}`, or field errors for invalid records
+            let json: serde_json::Value = serde_json::from_str(&body).unwrap_or_default();
+            let message = match json["detail"].as_str() {
+                Some(detail) => detail.to_string(),
+                None if !json.is_null() => json.to_string(),
+                None => format!("HTTP {}", status.as_u16()),
+            };
+            return Err(match status {
+                StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => ProviderError::Auth(message),
+                _ => ProviderError::for_status(status, message),
+            });
+        }
+
+        serde_json::from_str(&body)
+            .map(Some)
+            .map_err(|e| ProviderError::InvalidResponse(format!("HTTP {}: {}", status.as_u16(), e)))
+    }
+}
+
+/// TTL the API stores for a record configured with `ttl`
+fn stored_ttl(ttl: u32) -> u32 {
+    ttl.max(MIN_TTL)
+}
+
+#[async_trait]
+impl DnsProvider for DesecProvider {
+    fn name(&self) -> &str {
+        "desec"
+    }
+
+    fn effective_ttl(&self, record: &Record) -> Option<u32> {
+        Some(stored_ttl(record.ttl))
+    }
+
+    /// Ask deSEC which of the account's domains is responsible for
+    /// `dns_name`; its name doubles as the ID
+    async fn lookup_zone(&self, dns_name: &str) -> Result<Zone, ProviderError> {
+        let request = self
+            .client
+            .get(format!("{}/domains/", self.base_url))
+            .query(&[("owns_qname", dns_name)]);
+        let domains: Vec<DesecDomain> = self.send(request).await?.unwrap_or_default();
+
+        match domains.into_iter().next() {
+            Some(domain) => Ok(Zone {
+                id: domain.name.clone(),
+                name: domain.name,
+            }),
+            None => Err(ProviderError::ZoneNotFound(dns_name.to_string())),
+        }
+    }
+
+    async fn read_record(
+        &self,
+        zone: &Zone,
+        dns_name: &str,
+        kind: RecordType,
+    ) -> Result<Option<Record>, ProviderError> {
+        // The apex is addressed as `@` in URLs
+        let subname = match subname(dns_name, &zone.name) {
+            apex if apex.is_empty() => "@".to_string(),
+            subname => subname,
+        };
+        let request = self.client.get(format!(
+            "{}/domains/{}/rrsets/{}/{}/",
+            self.base_url,
+            zone.id,
+            subname,
+            kind.as_str()
+        ));
+        let set: Option<RecordSet> = self.send(request).await?;
+        Ok(set.map(|set| Record {
+            id: None,
+            name: dns_name.to_string(),
+            kind,
+            content: set.records.into_iter().next().unwrap_or_default(),
+            ttl: set.ttl,
+            proxied: None,
+        }))
+    }
+
+    /// Replace the record set with a single value, creating it if needed
+    async fn upsert_record(&self, zone: &Zone, record: &Record) -> Result<Record, ProviderError> {
+        let ttl = stored_ttl(record.ttl);
+        let body = serde_json::json!([{
+            "subname": subname(&record.name, &zone.name),
+            "type": record.kind.as_str(),
+            "records": [record.content],
+            "ttl": ttl
+        }]);
+
+        let request = self
+            .client
+            .patch(format!("{}/domains/{}/rrsets/", self.base_url, zone.id))
+            .json(&body);
+        self.send::<serde_json::Value>(request)
+            .await?
+            .ok_or_else(|| ProviderError::ZoneNotFound(zone.name.clone()))?;
+        Ok(Record {
+            ttl,
+            ..record.clone()
+        })
+    }
+}
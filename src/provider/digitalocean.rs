@@ -0,0 +1,234 @@
+//! DigitalOcean DNS provider using the v2 domains API.
+
+use super::{
+    DnsProvider, Record, RecordType, Zone, extract_root_domain, relative_name, retry_after,
+};
+use crate::error::ProviderError;
+use async_trait::async_trait;
+use reqwest::{Client, RequestBuilder, StatusCode};
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use std::fmt;
+
+const API_BASE: &str = "https://api.digitalocean.com/v2";
+/// TTL used for records configured with the automatic TTL (`1`), matching
+/// the DigitalOcean default
+const AUTO_TTL: u32 = 1800;
+/// Lowest TTL DigitalOcean accepts
+const MIN_TTL: u32 = 30;
+
+#[derive(Deserialize)]
+struct DoError {
+    #[serde(default)]
+    id: String,
+    #[serde(default)]
+    message: String,
+}
+
+#[derive(Deserialize)]
+struct DomainResponse {
+    domain: DoDomain,
+}
+
+#[derive(Deserialize)]
+struct DoDomain {
+    name: String,
+}
+
+#[derive(Deserialize)]
+struct RecordsResponse {
+    domain_records: Vec<DoRecord>,
+    #[serde(default)]
+    links: Links,
+}
+
+#[derive(Deserialize, Default)]
+struct Links {
+    pages: Option<Pages>,
+}
+
+#[derive(Deserialize)]
+struct Pages {
+    next: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct RecordResponse {
+    domain_record: DoRecord,
+}
+
+#[derive(Deserialize)]
+struct DoRecord {
+    id: u64,
+    /// Name relative to the domain, `@` for the apex
+    name: String,
+    data: String,
+    ttl: u32,
+}
+
+impl DoRecord {
+    fn into_record(self, dns_name: &str, kind: RecordType) -> Record {
+        Record {
+            id: Some(self.id.to_string()),
+            name: dns_name.to_string(),
+            kind,
+            content: self.data,
+            ttl: self.ttl,
+            proxied: None,
+        }
+    }
+}
+
+/// `[providers.digitalocean]` section of the config file
+#[derive(Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DigitalOceanConfig {
+    /// Personal access token with write scope
+    pub api_token: String,
+}
+
+impl fmt::Debug for DigitalOceanConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DigitalOceanConfig")
+            .field("api_token", &"<redacted>")
+            .finish()
+    }
+}
+
+pub struct DigitalOceanProvider {
+    client: Client,
+    api_token: String,
+    base_url: String,
+}
+
+impl DigitalOceanProvider {
+    pub fn new(client: Client, api_token: impl Into<String>) -> Self {
+        Self {
+            client,
+            api_token: api_token.into(),
+            base_url: API_BASE.to_string(),
+        }
+    }
+
+    /// Talk to a different API endpoint, e.g. a proxy or a mock server
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into().trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Send an authenticated request and parse the JSON response
+    async fn send<T: DeserializeOwned>(&self, request: RequestBuilder) -> Result<T, ProviderError> {
+        let resp = request.bearer_auth(&self.api_token).send().await?;
+        let status = resp.status();
+
+        if status == StatusCode::TOO_MANY_REQUESTS {
+            return Err(ProviderError::RateLimited {
+                retry_after: retry_after(resp.headers()),
+            });
+        }
+
+        let body = resp.text().await?;
+        if !status.is_success() {
+            let message = match serde_json::from_str::<DoError>(&body) {
+                Ok(e) => format!("{} ({})", e.message, e.id),
+                Err(_) => format!("HTTP {}", status.as_u16()),
+            };
+            return Err(match status {
+                StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => ProviderError::Auth(message),
+                _ => ProviderError::for_status(status, message),
+            });
+        }
+
+        serde_json::from_str(&body)
+            .map_err(|e| ProviderError::InvalidResponse(format!("HTTP {}: {}", status.as_u16(), e)))
+    }
+}
+
+/// TTL the API stores for a record configured with `ttl`
+fn stored_ttl(ttl: u32) -> u32 {
+    match ttl {
+        1 => AUTO_TTL,
+        ttl => ttl.max(MIN_TTL),
+    }
+}
+
+#[async_trait]
+impl DnsProvider for DigitalOceanProvider {
+    fn name(&self) -> &str {
+        "digitalocean"
+    }
+
+    fn effective_ttl(&self, record: &Record) -> Option<u32> {
+        Some(stored_ttl(record.ttl))
+    }
+
+    /// Look up the domain `dns_name` belongs to; its name doubles as the ID
+    async fn lookup_zone(&self, dns_name: &str) -> Result<Zone, ProviderError> {
+        let domain = extract_root_domain(dns_name);
+        let request = self
+            .client
+            .get(format!("{}/domains/{}", self.base_url, domain));
+
+        match self.send::<DomainResponse>(request).await {
+            Ok(resp) => Ok(Zone {
+                id: resp.domain.name.clone(),
+                name: resp.domain.name,
+            }),
+            Err(ProviderError::NotFound(_)) => Err(ProviderError::ZoneNotFound(domain)),
+            Err(e) => Err(e),
+        }
+    }
+
+    async fn read_record(
+        &self,
+        zone: &Zone,
+        dns_name: &str,
+        kind: RecordType,
+    ) -> Result<Option<Record>, ProviderError> {
+        let relative = relative_name(dns_name, &zone.name, "@");
+        let mut page = 1;
+        loop {
+            let request = self
+                .client
+                .get(format!("{}/domains/{}/records", self.base_url, zone.id))
+                .query(&[("type", kind.as_str()), ("name", dns_name)])
+                .query(&[("page", page)]);
+            let resp: RecordsResponse = self.send(request).await?;
+
+            if let Some(record) = resp
+                .domain_records
+                .into_iter()
+                .find(|r| r.name.eq_ignore_ascii_case(&relative))
+            {
+                return Ok(Some(record.into_record(dns_name, kind)));
+            }
+            if resp.links.pages.and_then(|p| p.next).is_none() {
+                return Ok(None);
+            }
+            page += 1;
+        }
+    }
+
+    async fn upsert_record(&self, zone: &Zone, record: &Record) -> Result<Record, ProviderError> {
+        let ttl = stored_ttl(record.ttl);
+        let body = serde_json::json!({
+            "type": record.kind.as_str(),
+            "name": relative_name(&record.name, &zone.name, "@"),
+            "data": record.content,
+            "ttl": ttl
+        });
+
+        let request = match &record.id {
+            Some(id) => self.client.put(format!(
+                "{}/domains/{}/records/{}",
+                self.base_url, zone.id, id
+            )),
+            None => self
+                .client
+                .post(format!("{}/domains/{}/records", self.base_url, zone.id)),
+        };
+
+        let resp: RecordResponse = self.send(request.json(&body)).await?;
+        Ok(resp.domain_record.into_record(&record.name, record.kind))
+    }
+}
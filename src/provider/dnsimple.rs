@@ -0,0 +1,251 @@
+//! DNSimple provider using the v2 API.
+
+use super::{
+    DnsProvider, Record, RecordType, Zone, extract_root_domain, relative_name, retry_after,
+};
+use crate::error::ProviderError;
+use async_trait::async_trait;
+use reqwest::{Client, RequestBuilder, StatusCode};
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use std::fmt;
+
+const API_BASE: &str = "https://api.dnsimple.com/v2";
+const SANDBOX_API_BASE: &str = "https://api.sandbox.dnsimple.com/v2";
+/// TTL used for records configured with the automatic TTL (`1`), matching
+/// the DNSimple default
+const AUTO_TTL: u32 = 3600;
+
+#[derive(Deserialize)]
+struct DnsimpleError {
+    #[serde(default)]
+    message: String,
+}
+
+#[derive(Deserialize)]
+struct Data<T> {
+    data: T,
+}
+
+#[derive(Deserialize)]
+struct RecordsPage {
+    data: Vec<DnsimpleRecord>,
+    pagination: Pagination,
+}
+
+#[derive(Deserialize)]
+struct Pagination {
+    current_page: u32,
+    total_pages: u32,
+}
+
+#[derive(Deserialize)]
+struct DnsimpleZone {
+    name: String,
+}
+
+#[derive(Deserialize)]
+struct DnsimpleRecord {
+    id: u64,
+    #[serde(rename = "type")]
+    kind: String,
+    /// Name relative to the zone, empty for the apex
+    name: String,
+    content: String,
+    ttl: u32,
+}
+
+impl DnsimpleRecord {
+    fn into_record(self, dns_name: &str, kind: RecordType) -> Record {
+        Record {
+            id: Some(self.id.to_string()),
+            name: dns_name.to_string(),
+            kind,
+            content: self.content,
+            ttl: self.ttl,
+            proxied: None,
+        }
+    }
+}
+
+/// `[providers.dnsimple]` section of the config file
+#[derive(Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DnsimpleConfig {
+    /// Numeric ID of the account owning the zones
+    pub account_id: String,
+    /// Account API token or OAuth access token
+    pub api_token: String,
+    /// Talk to the sandbox environment instead of production
+    #[serde(default)]
+    pub sandbox: bool,
+}
+
+impl fmt::Debug for DnsimpleConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DnsimpleConfig")
+            .field("account_id", &self.account_id)
+            .field("api_token", &"<redacted>")
+            .field("sandbox", &self.sandbox)
+            .finish()
+    }
+}
+
+pub struct DnsimpleProvider {
+    client: Client,
+    account_id: String,
+    api_token: String,
+    base_url: String,
+}
+
+impl DnsimpleProvider {
+    pub fn new(client: Client, config: &DnsimpleConfig) -> Self {
+        let base_url = if config.sandbox {
+            SANDBOX_API_BASE
+        } else {
+            API_BASE
+        };
+        Self {
+            client,
+            account_id: config.account_id.clone(),
+            api_token: config.api_token.clone(),
+            base_url: base_url.to_string(),
+        }
+    }
+
+    /// Talk to a different API endpoint, e.g. a proxy or a mock server
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into().trim_end_matches('/').to_string();
+        self
+    }
+
+    fn zone_url(&self, zone: &str) -> String {
+        format!("{}/{}/zones/{}", self.base_url, self.account_id, zone)
+    }
+
+    /// Send an authenticated request and parse the JSON response, `None` if
+    /// the resource doesn't exist
+    async fn send<T: DeserializeOwned>(
+        &self,
+        request: RequestBuilder,
+    ) -> Result<Option<T>, ProviderError> {
+        let resp = request.bearer_auth(&self.api_token).send().await?;
+        let status = resp.status();
+
+        if status == StatusCode::TOO_MANY_REQUESTS {
+            return Err(ProviderError::RateLimited {
+                retry_after: retry_after(resp.headers()),
+            });
+        }
+        if status == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+
+        let body = resp.text().await?;
+        if !status.is_success() {
+            let message = match serde_json::from_str::<DnsimpleError>(&body) {
+                Ok(e) => e.message,
+                Err(_) => format!("HTTP {}", status.as_u16()),
+            };
+            return Err(match status {
+                StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => ProviderError::Auth(message),
+                _ => ProviderError::for_status(status, message),
+            });
+        }
+
+        serde_json::from_str(&body)
+            .map(Some)
+            .map_err(|e| ProviderError::InvalidResponse(format!("HTTP {}: {}", status.as_u16(), e)))
+    }
+}
+
+/// TTL the API stores for a record configured with `ttl`
+fn stored_ttl(ttl: u32) -> u32 {
+    if ttl == 1 { AUTO_TTL } else { ttl }
+}
+
+#[async_trait]
+impl DnsProvider for DnsimpleProvider {
+    fn name(&self) -> &str {
+        "dnsimple"
+    }
+
+    fn effective_ttl(&self, record: &Record) -> Option<u32> {
+        Some(stored_ttl(record.ttl))
+    }
+
+    /// Look up the zone of the root domain of `dns_name`; its name doubles
+    /// as the ID
+    async fn lookup_zone(&self, dns_name: &str) -> Result<Zone, ProviderError> {
+        let domain = extract_root_domain(dns_name);
+        let request = self.client.get(self.zone_url(&domain));
+
+        match self.send::<Data<DnsimpleZone>>(request).await? {
+            Some(Data { data: zone }) => Ok(Zone {
+                id: zone.name.clone(),
+                name: zone.name,
+            }),
+            None => Err(ProviderError::ZoneNotFound(domain)),
+        }
+    }
+
+    async fn read_record(
+        &self,
+        zone: &Zone,
+        dns_name: &str,
+        kind: RecordType,
+    ) -> Result<Option<Record>, ProviderError> {
+        let relative = relative_name(dns_name, &zone.name, "");
+        let mut page = 1;
+        loop {
+            let request = self
+                .client
+                .get(format!("{}/records", self.zone_url(&zone.id)))
+                .query(&[("name", relative.as_str()), ("type", kind.as_str())])
+                .query(&[("page", page)]);
+            let resp: RecordsPage = self
+                .send(request)
+                .await?
+                .ok_or_else(|| ProviderError::ZoneNotFound(zone.name.clone()))?;
+
+            if let Some(record) = resp
+                .data
+                .into_iter()
+                .find(|r| r.kind == kind.as_str() && r.name.eq_ignore_ascii_case(&relative))
+            {
+                return Ok(Some(record.into_record(dns_name, kind)));
+            }
+            if resp.pagination.current_page >= resp.pagination.total_pages {
+                return Ok(None);
+            }
+            page += 1;
+        }
+    }
+
+    async fn upsert_record(&self, zone: &Zone, record: &Record) -> Result<Record, ProviderError> {
+        let ttl = stored_ttl(record.ttl);
+        let records_url = format!("{}/records", self.zone_url(&zone.id));
+
+        let request = match &record.id {
+            Some(id) => self
+                .client
+                .patch(format!("{}/{}", records_url, id))
+                .json(&serde_json::json!({ "content": record.content, "ttl": ttl })),
+            None => self.client.post(records_url).json(&serde_json::json!({
+                "name": relative_name(&record.name, &zone.name, ""),
+                "type": record.kind.as_str(),
+                "content": record.content,
+                "ttl": ttl
+            })),
+        };
+
+        let stored: Data<DnsimpleRecord> =
+            self.send(request)
+                .await?
+                .ok_or_else(|| ProviderError::RecordNotFound {
+                    name: record.name.clone(),
+                    kind: record.kind,
+                })?;
+        Ok(stored.data.into_record(&record.name, record.kind))
+    }
+}
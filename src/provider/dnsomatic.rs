@@ -0,0 +1,154 @@
+//! DNS-O-Matic provider using its dyndns2 style update endpoint.
+//!
+//! DNS-O-Matic forwards every update to the services configured in the
+//! account, so a single record can keep many of them in sync. The special
+//! hostname `all.dnsomatic.com` updates all of them at once. Like other
+//! dyndns2 services, the endpoint can't read records back, so the agent
+//! updates them once at startup.
+
+use super::{DnsProvider, Record, RecordType, Zone, retry_after};
+use crate::error::ProviderError;
+use async_trait::async_trait;
+use reqwest::header::USER_AGENT;
+use reqwest::{Client, StatusCode};
+use serde::Deserialize;
+use std::fmt;
+
+const API_BASE: &str = "https://updates.dnsomatic.com";
+/// DNS-O-Matic blocks clients without a descriptive user agent
+const AGENT: &str = concat!("ddns-agent/", env!("CARGO_PKG_VERSION"));
+
+/// `[providers.dnsomatic]` section of the config file
+#[derive(Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DnsOMaticConfig {
+    /// OpenDNS / DNS-O-Matic account username
+    pub username: String,
+    pub password: String,
+}
+
+impl fmt::Debug for DnsOMaticConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DnsOMaticConfig")
+            .field("username", &self.username)
+            .field("password", &"<redacted>")
+            .finish()
+    }
+}
+
+pub struct DnsOMaticProvider {
+    client: Client,
+    username: String,
+    password: String,
+    base_url: String,
+}
+
+impl DnsOMaticProvider {
+    pub fn new(client: Client, config: &DnsOMaticConfig) -> Self {
+        Self {
+            client,
+            username: config.username.clone(),
+            password: config.password.clone(),
+            base_url: API_BASE.to_string(),
+        }
+    }
+
+    /// Talk to a different API endpoint, e.g. a proxy or a mock server
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into().trim_end_matches('/').to_string();
+        self
+    }
+}
+
+#[async_trait]
+impl DnsProvider for DnsOMaticProvider {
+    fn name(&self) -> &str {
+        "dnsomatic"
+    }
+
+    fn effective_ttl(&self, _record: &Record) -> Option<u32> {
+        None
+    }
+
+    /// Hostnames are updated as a whole, so each one is its own zone
+    async fn lookup_zone(&self, dns_name: &str) -> Result<Zone, ProviderError> {
+        Ok(Zone {
+            id: dns_name.to_string(),
+            name: dns_name.to_string(),
+        })
+    }
+
+    /// The record with an empty (unknown) value, as it can't be read
+    async fn read_record(
+        &self,
+        _zone: &Zone,
+        dns_name: &str,
+        kind: RecordType,
+    ) -> Result<Option<Record>, ProviderError> {
+        Ok(Some(Record {
+            id: None,
+            name: dns_name.to_string(),
+            kind,
+            content: String::new(),
+            ttl: 1,
+            proxied: None,
+        }))
+    }
+
+    /// Point the hostname, or every service with `all.dnsomatic.com`, at
+    /// `record.content`
+    async fn upsert_record(&self, _zone: &Zone, record: &Record) -> Result<Record, ProviderError> {
+        if record.kind == RecordType::Aaaa {
+            return Err(ProviderError::Api(
+                "DNS-O-Matic only updates IPv4 addresses".into(),
+            ));
+        }
+        // NOCHG keeps the wildcard and MX settings of the services as they are
+        let resp = self
+            .client
+            .get(format!("{}/nic/update", self.base_url))
+            .query(&[
+                ("hostname", record.name.as_str()),
+                ("myip", record.content.as_str()),
+                ("wildcard", "NOCHG"),
+                ("mx", "NOCHG"),
+                ("backmx", "NOCHG"),
+            ])
+            .basic_auth(&self.username, Some(&self.password))
+            .header(USER_AGENT, AGENT)
+            .send()
+            .await?;
+        let status = resp.status();
+
+        if status == StatusCode::TOO_MANY_REQUESTS {
+            let retry_after = retry_after(resp.headers());
+            return Err(ProviderError::RateLimited { retry_after });
+        }
+        let body = resp.text().await?;
+        let answer = body.lines().next().unwrap_or_default().trim();
+        let code = answer.split_whitespace().next().unwrap_or_default();
+
+        match code {
+            "good" | "nochg" => Ok(record.clone()),
+            "badauth" => Err(ProviderError::Auth("badauth".into())),
+            "nohost" => Err(ProviderError::RecordNotFound {
+                name: record.name.clone(),
+                kind: record.kind,
+            }),
+            "notfqdn" | "numhost" | "abuse" | "badagent" | "dnserr" | "911" => {
+                Err(ProviderError::Api(answer.to_string()))
+            }
+            _ if status == StatusCode::UNAUTHORIZED || status == StatusCode::FORBIDDEN => {
+                Err(ProviderError::Auth(format!("HTTP {}", status.as_u16())))
+            }
+            _ if !status.is_success() => Err(ProviderError::for_status(
+                status,
+                format!("HTTP {}", status.as_u16()),
+            )),
+            _ => Err(ProviderError::InvalidResponse(format!(
+                "unexpected answer {:?}",
+                answer
+            ))),
+        }
+    }
+}
@@ -0,0 +1,161 @@
+//! DuckDNS provider using the `update` endpoint.
+//!
+//! DuckDNS can't read records back, so records are reported with an unknown
+//! current value and updated once at startup. Domains are also re-pushed
+//! periodically even if the IP stays the same, so DuckDNS doesn't consider
+//! them stale.
+
+use super::{DnsProvider, Record, RecordType, Zone, retry_after};
+use crate::error::ProviderError;
+use async_trait::async_trait;
+use reqwest::{Client, StatusCode};
+use serde::Deserialize;
+use std::fmt;
+use std::time::Duration;
+
+const API_BASE: &str = "https://www.duckdns.org";
+const DOMAIN: &str = "duckdns.org";
+
+fn default_refresh_hours() -> u64 {
+    24
+}
+
+/// `[providers.duckdns]` section of the config file
+#[derive(Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DuckDnsConfig {
+    /// Account token shown on the DuckDNS dashboard
+    pub token: String,
+    /// Hours after which an unchanged record is pushed again
+    #[serde(default = "default_refresh_hours")]
+    pub refresh_hours: u64,
+}
+
+impl fmt::Debug for DuckDnsConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DuckDnsConfig")
+            .field("token", &"<redacted>")
+            .field("refresh_hours", &self.refresh_hours)
+            .finish()
+    }
+}
+
+pub struct DuckDnsProvider {
+    client: Client,
+    token: String,
+    refresh_interval: Duration,
+    base_url: String,
+}
+
+impl DuckDnsProvider {
+    pub fn new(client: Client, config: &DuckDnsConfig) -> Self {
+        Self {
+            client,
+            token: config.token.clone(),
+            refresh_interval: Duration::from_secs(config.refresh_hours.max(1) * 3600),
+            base_url: API_BASE.to_string(),
+        }
+    }
+
+    /// Talk to a different API endpoint, e.g. a proxy or a mock server
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into().trim_end_matches('/').to_string();
+        self
+    }
+}
+
+/// The DuckDNS subdomain `dns_name` belongs to, e.g. `home` for both
+/// `home.duckdns.org` and `www.home.duckdns.org`
+fn subdomain(dns_name: &str) -> Option<&str> {
+    let prefix = dns_name.strip_suffix(DOMAIN)?.strip_suffix('.')?;
+    prefix.rsplit('.').next().filter(|s| !s.is_empty())
+}
+
+#[async_trait]
+impl DnsProvider for DuckDnsProvider {
+    fn name(&self) -> &str {
+        "duckdns"
+    }
+
+    fn effective_ttl(&self, _record: &Record) -> Option<u32> {
+        None
+    }
+
+    fn refresh_interval(&self) -> Option<Duration> {
+        Some(self.refresh_interval)
+    }
+
+    /// The DuckDNS subdomain of `dns_name`, without asking DuckDNS
+    async fn lookup_zone(&self, dns_name: &str) -> Result<Zone, ProviderError> {
+        let name = dns_name.to_ascii_lowercase();
+        let subdomain =
+            subdomain(&name).ok_or_else(|| ProviderError::ZoneNotFound(dns_name.to_string()))?;
+        Ok(Zone {
+            id: subdomain.to_string(),
+            name: format!("{}.{}", subdomain, DOMAIN),
+        })
+    }
+
+    /// The record with an empty (unknown) value, as it can't be read
+    async fn read_record(
+        &self,
+        _zone: &Zone,
+        dns_name: &str,
+        kind: RecordType,
+    ) -> Result<Option<Record>, ProviderError> {
+        Ok(Some(Record {
+            id: None,
+            name: dns_name.to_string(),
+            kind,
+            content: String::new(),
+            ttl: 1,
+            proxied: None,
+        }))
+    }
+
+    /// Point the subdomain at `record.content`. DuckDNS answers only `OK` or
+    /// `KO`, the latter for both a wrong token and a foreign subdomain.
+    async fn upsert_record(&self, zone: &Zone, record: &Record) -> Result<Record, ProviderError> {
+        let ip_param = match record.kind {
+            RecordType::A => "ip",
+            RecordType::Aaaa => "ipv6",
+        };
+        let resp = self
+            .client
+            .get(format!("{}/update", self.base_url))
+            .query(&[
+                ("domains", zone.id.as_str()),
+                ("token", self.token.as_str()),
+                (ip_param, record.content.as_str()),
+            ])
+            .send()
+            .await
+            // The URL contains the token
+            .map_err(reqwest::Error::without_url)?;
+        let status = resp.status();
+
+        if status == StatusCode::TOO_MANY_REQUESTS {
+            let retry_after = retry_after(resp.headers());
+            return Err(ProviderError::RateLimited { retry_after });
+        }
+        let body = resp.text().await.map_err(reqwest::Error::without_url)?;
+        if !status.is_success() {
+            return Err(ProviderError::for_status(
+                status,
+                format!("HTTP {}", status.as_u16()),
+            ));
+        }
+
+        match body.trim() {
+            "OK" => Ok(record.clone()),
+            "KO" => Err(ProviderError::Auth(format!(
+                "DuckDNS rejected the update of {}, check the token and subdomain",
+                zone.name
+            ))),
+            other => Err(ProviderError::InvalidResponse(format!(
+                "unexpected answer {:?}",
+                other
+            ))),
+        }
+    }
+}
@@ -0,0 +1,157 @@
+//! Generic provider for the dyndns2 `nic/update` protocol spoken by No-IP,
+//! Dyn, many smaller services and router firmwares.
+//!
+//! The protocol can only set the address of a hostname, it can't read
+//! records back. Records are therefore reported with an unknown current
+//! value, and the agent updates them once at startup.
+//!
+//! Wildcard names are sent as their base hostname with `wildcard=ON`, which
+//! makes the service answer for every name below it.
+
+use super::{DnsProvider, Record, RecordType, Zone, retry_after, wildcard_base};
+use crate::error::ProviderError;
+use async_trait::async_trait;
+use reqwest::header::USER_AGENT;
+use reqwest::{Client, StatusCode};
+use serde::Deserialize;
+use std::fmt;
+
+/// Services ask clients to identify themselves and block generic agents
+const AGENT: &str = concat!("ddns-agent/", env!("CARGO_PKG_VERSION"));
+
+fn default_path() -> String {
+    "/nic/update".to_string()
+}
+
+/// `[providers.dyndns2]` section of the config file
+#[derive(Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DynDns2Config {
+    /// Base URL of the service, e.g. `https://dynupdate.no-ip.com`
+    pub url: String,
+    /// Path of the update endpoint
+    #[serde(default = "default_path")]
+    pub path: String,
+    pub username: String,
+    pub password: String,
+}
+
+impl fmt::Debug for DynDns2Config {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DynDns2Config")
+            .field("url", &self.url)
+            .field("path", &self.path)
+            .field("username", &self.username)
+            .field("password", &"<redacted>")
+            .finish()
+    }
+}
+
+pub struct DynDns2Provider {
+    client: Client,
+    url: String,
+    username: String,
+    password: String,
+}
+
+impl DynDns2Provider {
+    pub fn new(client: Client, config: &DynDns2Config) -> Self {
+        Self {
+            client,
+            url: format!(
+                "{}/{}",
+                config.url.trim_end_matches('/'),
+                config.path.trim_start_matches('/')
+            ),
+            username: config.username.clone(),
+            password: config.password.clone(),
+        }
+    }
+}
+
+#[async_trait]
+impl DnsProvider for DynDns2Provider {
+    fn name(&self) -> &str {
+        "dyndns2"
+    }
+
+    fn effective_ttl(&self, _record: &Record) -> Option<u32> {
+        None
+    }
+
+    /// Hostnames are updated as a whole, so each one is its own zone
+    async fn lookup_zone(&self, dns_name: &str) -> Result<Zone, ProviderError> {
+        Ok(Zone {
+            id: dns_name.to_string(),
+            name: dns_name.to_string(),
+        })
+    }
+
+    /// The record with an empty (unknown) value, as it can't be read
+    async fn read_record(
+        &self,
+        _zone: &Zone,
+        dns_name: &str,
+        kind: RecordType,
+    ) -> Result<Option<Record>, ProviderError> {
+        Ok(Some(Record {
+            id: None,
+            name: dns_name.to_string(),
+            kind,
+            content: String::new(),
+            ttl: 1,
+            proxied: None,
+        }))
+    }
+
+    /// Point the hostname at `record.content`
+    async fn upsert_record(&self, _zone: &Zone, record: &Record) -> Result<Record, ProviderError> {
+        let mut request = self.client.get(&self.url);
+        let hostname = match wildcard_base(&record.name) {
+            Some(base) => {
+                request = request.query(&[("wildcard", "ON")]);
+                base
+            }
+            None => &record.name,
+        };
+        let resp = request
+            .query(&[("hostname", hostname), ("myip", record.content.as_str())])
+            .basic_auth(&self.username, Some(&self.password))
+            .header(USER_AGENT, AGENT)
+            .send()
+            .await?;
+        let status = resp.status();
+
+        if status == StatusCode::TOO_MANY_REQUESTS {
+            let retry_after = retry_after(resp.headers());
+            return Err(ProviderError::RateLimited { retry_after });
+        }
+        let body = resp.text().await?;
+        // Some services answer badauth with a 401, others with a 200
+        let answer = body.lines().next().unwrap_or_default().trim();
+        let code = answer.split_whitespace().next().unwrap_or_default();
+
+        match code {
+            "good" | "nochg" => Ok(record.clone()),
+            "badauth" => Err(ProviderError::Auth("badauth".into())),
+            "nohost" => Err(ProviderError::RecordNotFound {
+                name: record.name.clone(),
+                kind: record.kind,
+            }),
+            "!donator" | "notfqdn" | "numhost" | "abuse" | "badagent" | "dnserr" | "911" => {
+                Err(ProviderError::Api(answer.to_string()))
+            }
+            _ if status == StatusCode::UNAUTHORIZED || status == StatusCode::FORBIDDEN => {
+                Err(ProviderError::Auth(format!("HTTP {}", status.as_u16())))
+            }
+            _ if !status.is_success() => Err(ProviderError::for_status(
+                status,
+                format!("HTTP {}", status.as_u16()),
+            )),
+            _ => Err(ProviderError::InvalidResponse(format!(
+                "unexpected answer {:?}",
+                answer
+            ))),
+        }
+    }
+}
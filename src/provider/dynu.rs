@@ -0,0 +1,157 @@
+//! Dynu provider using its IP update endpoint.
+//!
+//! Like the dyndns2 protocol, the endpoint only sets addresses and can't read
+//! records back, so the agent updates them once at startup. With a `group`
+//! configured, a single request updates every hostname in that Dynu group.
+
+use super::{DnsProvider, Record, RecordType, Zone, retry_after};
+use crate::error::ProviderError;
+use async_trait::async_trait;
+use reqwest::header::USER_AGENT;
+use reqwest::{Client, StatusCode};
+use serde::Deserialize;
+use std::fmt;
+
+const API_BASE: &str = "https://api.dynu.com";
+/// Dynu asks clients to identify themselves
+const AGENT: &str = concat!("ddns-agent/", env!("CARGO_PKG_VERSION"));
+
+/// `[providers.dynu]` section of the config file
+#[derive(Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DynuConfig {
+    pub username: String,
+    /// Account or IP update password, in plain text or as its MD5 or
+    /// SHA-256 hex digest
+    pub password: String,
+    /// Update all hostnames of this group (Dynu "location") at once instead
+    /// of the record's hostname
+    #[serde(default)]
+    pub group: Option<String>,
+}
+
+impl fmt::Debug for DynuConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DynuConfig")
+            .field("username", &self.username)
+            .field("password", &"<redacted>")
+            .field("group", &self.group)
+            .finish()
+    }
+}
+
+pub struct DynuProvider {
+    client: Client,
+    username: String,
+    password: String,
+    group: Option<String>,
+    base_url: String,
+}
+
+impl DynuProvider {
+    pub fn new(client: Client, config: &DynuConfig) -> Self {
+        Self {
+            client,
+            username: config.username.clone(),
+            password: config.password.clone(),
+            group: config.group.clone(),
+            base_url: API_BASE.to_string(),
+        }
+    }
+
+    /// Talk to a different API endpoint, e.g. a proxy or a mock server
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into().trim_end_matches('/').to_string();
+        self
+    }
+}
+
+#[async_trait]
+impl DnsProvider for DynuProvider {
+    fn name(&self) -> &str {
+        "dynu"
+    }
+
+    fn effective_ttl(&self, _record: &Record) -> Option<u32> {
+        None
+    }
+
+    /// Hostnames are updated as a whole, so each one is its own zone
+    async fn lookup_zone(&self, dns_name: &str) -> Result<Zone, ProviderError> {
+        Ok(Zone {
+            id: dns_name.to_string(),
+            name: dns_name.to_string(),
+        })
+    }
+
+    /// The record with an empty (unknown) value, as it can't be read
+    async fn read_record(
+        &self,
+        _zone: &Zone,
+        dns_name: &str,
+        kind: RecordType,
+    ) -> Result<Option<Record>, ProviderError> {
+        Ok(Some(Record {
+            id: None,
+            name: dns_name.to_string(),
+            kind,
+            content: String::new(),
+            ttl: 1,
+            proxied: None,
+        }))
+    }
+
+    /// Point the hostname, or every hostname of the group, at
+    /// `record.content`
+    async fn upsert_record(&self, _zone: &Zone, record: &Record) -> Result<Record, ProviderError> {
+        let target = match &self.group {
+            Some(group) => ("location", group.as_str()),
+            None => ("hostname", record.name.as_str()),
+        };
+        // `no` leaves the other address family untouched
+        let (myip, myipv6) = match record.kind {
+            RecordType::A => (record.content.as_str(), "no"),
+            RecordType::Aaaa => ("no", record.content.as_str()),
+        };
+        let resp = self
+            .client
+            .get(format!("{}/nic/update", self.base_url))
+            .query(&[target, ("myip", myip), ("myipv6", myipv6)])
+            .basic_auth(&self.username, Some(&self.password))
+            .header(USER_AGENT, AGENT)
+            .send()
+            .await?;
+        let status = resp.status();
+
+        if status == StatusCode::TOO_MANY_REQUESTS {
+            let retry_after = retry_after(resp.headers());
+            return Err(ProviderError::RateLimited { retry_after });
+        }
+        let body = resp.text().await?;
+        let answer = body.lines().next().unwrap_or_default().trim();
+        let code = answer.split_whitespace().next().unwrap_or_default();
+
+        match code {
+            "good" | "nochg" => Ok(record.clone()),
+            "badauth" => Err(ProviderError::Auth("badauth".into())),
+            "nohost" => Err(ProviderError::RecordNotFound {
+                name: record.name.clone(),
+                kind: record.kind,
+            }),
+            "notfqdn" | "numhost" | "abuse" | "servererror" | "dnserr" | "911" => {
+                Err(ProviderError::Api(answer.to_string()))
+            }
+            _ if status == StatusCode::UNAUTHORIZED || status == StatusCode::FORBIDDEN => {
+                Err(ProviderError::Auth(format!("HTTP {}", status.as_u16())))
+            }
+            _ if !status.is_success() => Err(ProviderError::for_status(
+                status,
+                format!("HTTP {}", status.as_u16()),
+            )),
+            _ => Err(ProviderError::InvalidResponse(format!(
+                "unexpected answer {:?}",
+                answer
+            ))),
+        }
+    }
+}
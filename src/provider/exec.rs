@@ -0,0 +1,157 @@
+//! Provider running a user supplied command for every update.
+//!
+//! The command gets the update in its environment (`DDNS_HOSTNAME`,
+//! `DDNS_TYPE`, `DDNS_IP` and `DDNS_OLD_IP`) and in its arguments, where
+//! `{hostname}`, `{type}`, `{ip}` and `{old_ip}` are replaced. A non-zero
+//! exit fails the update. Commands can't report the current record, so the
+//! agent updates every record once at startup; the previously set address
+//! is kept as the record ID to pass it on as the old IP.
+
+use super::{DnsProvider, Record, RecordType, Zone};
+use crate::error::ProviderError;
+use async_trait::async_trait;
+use log::{debug, warn};
+use serde::Deserialize;
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::process::Command;
+
+fn default_timeout_secs() -> u64 {
+    30
+}
+
+/// `[providers.exec]` section of the config file
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ExecConfig {
+    /// Program to run, looked up in `PATH` unless it is a path
+    pub command: PathBuf,
+    /// Argument templates
+    #[serde(default)]
+    pub args: Vec<String>,
+    /// Kill the command after this long
+    #[serde(default = "default_timeout_secs")]
+    pub timeout_secs: u64,
+}
+
+pub struct ExecProvider {
+    command: PathBuf,
+    args: Vec<String>,
+    timeout: Duration,
+}
+
+impl ExecProvider {
+    pub fn new(config: &ExecConfig) -> Self {
+        Self {
+            command: config.command.clone(),
+            args: config.args.clone(),
+            timeout: Duration::from_secs(config.timeout_secs),
+        }
+    }
+}
+
+#[async_trait]
+impl DnsProvider for ExecProvider {
+    fn name(&self) -> &str {
+        "exec"
+    }
+
+    fn effective_ttl(&self, _record: &Record) -> Option<u32> {
+        None
+    }
+
+    /// Hostnames are updated as a whole, so each one is its own zone
+    async fn lookup_zone(&self, dns_name: &str) -> Result<Zone, ProviderError> {
+        Ok(Zone {
+            id: dns_name.to_string(),
+            name: dns_name.to_string(),
+        })
+    }
+
+    /// The record with an empty (unknown) value, as it can't be read
+    async fn read_record(
+        &self,
+        _zone: &Zone,
+        dns_name: &str,
+        kind: RecordType,
+    ) -> Result<Option<Record>, ProviderError> {
+        Ok(Some(Record {
+            id: None,
+            name: dns_name.to_string(),
+            kind,
+            content: String::new(),
+            ttl: 1,
+            proxied: None,
+        }))
+    }
+
+    /// Run the command and wait for it to succeed
+    async fn upsert_record(&self, _zone: &Zone, record: &Record) -> Result<Record, ProviderError> {
+        let old_ip = record.id.as_deref().unwrap_or_default();
+        let vars = [
+            ("hostname", record.name.as_str()),
+            ("type", record.kind.as_str()),
+            ("ip", record.content.as_str()),
+            ("old_ip", old_ip),
+        ];
+        let args = self.args.iter().map(|arg| {
+            vars.iter().fold(arg.clone(), |arg, (name, value)| {
+                arg.replace(&format!("{{{}}}", name), value)
+            })
+        });
+        let command = self.command.display();
+
+        let child = Command::new(&self.command)
+            .args(args)
+            .envs(
+                vars.iter()
+                    .map(|(name, value)| (format!("DDNS_{}", name.to_uppercase()), value)),
+            )
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|e| ProviderError::Api(format!("Failed to run {}: {}", command, e)))?;
+        let output = tokio::time::timeout(self.timeout, child.wait_with_output())
+            .await
+            .map_err(|_| {
+                ProviderError::Api(format!(
+                    "{} timed out after {}s",
+                    command,
+                    self.timeout.as_secs()
+                ))
+            })?
+            .map_err(|e| ProviderError::Api(format!("Failed to run {}: {}", command, e)))?;
+
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        if !stdout.trim().is_empty() {
+            debug!("[exec] {}: {}", command, stdout.trim());
+        }
+        if output.status.success() {
+            if !stderr.trim().is_empty() {
+                debug!("[exec] {}: {}", command, stderr.trim());
+            }
+            return Ok(Record {
+                id: Some(record.content.clone()),
+                ..record.clone()
+            });
+        }
+
+        for line in stderr.lines().filter(|l| !l.trim().is_empty()) {
+            warn!("[exec] {}: {}", command, line);
+        }
+        let reason = stderr
+            .lines()
+            .rev()
+            .find(|l| !l.trim().is_empty())
+            .map(|l| format!(": {}", l.trim()))
+            .unwrap_or_default();
+        Err(ProviderError::Api(format!(
+            "{} failed with {}{}",
+            command, output.status, reason
+        )))
+    }
+}
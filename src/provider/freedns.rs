@@ -0,0 +1,189 @@
+//! FreeDNS (afraid.org) provider using its dynamic update URLs.
+//!
+//! Every record has its own secret update token, either the randomized
+//! token of the version 2 sync URL or the SHA token of the version 1
+//! `update.php` URL. The endpoints can only set addresses, so the agent
+//! updates records once at startup.
+
+use super::{DnsProvider, Record, RecordType, Zone, retry_after};
+use crate::error::{ConfigError, ProviderError};
+use async_trait::async_trait;
+use reqwest::{Client, StatusCode};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fmt;
+
+fn default_version() -> u8 {
+    2
+}
+
+/// `[providers.freedns]` section of the config file
+#[derive(Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FreeDnsConfig {
+    /// Update token of each hostname, from the "Dynamic DNS" page
+    pub tokens: BTreeMap<String, String>,
+    /// `2` for the randomized sync tokens, `1` for the SHA tokens of the
+    /// legacy update URLs
+    #[serde(default = "default_version")]
+    pub version: u8,
+}
+
+impl fmt::Debug for FreeDnsConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let tokens: BTreeMap<_, _> = self.tokens.keys().map(|k| (k, "<redacted>")).collect();
+        f.debug_struct("FreeDnsConfig")
+            .field("tokens", &tokens)
+            .field("version", &self.version)
+            .finish()
+    }
+}
+
+pub struct FreeDnsProvider {
+    client: Client,
+    /// Tokens by lowercase hostname
+    tokens: BTreeMap<String, String>,
+    version: u8,
+    base_url: String,
+}
+
+impl FreeDnsProvider {
+    pub fn new(client: Client, config: &FreeDnsConfig) -> Result<Self, ConfigError> {
+        let base_url = match config.version {
+            1 => "https://freedns.afraid.org",
+            2 => "https://sync.afraid.org",
+            version => {
+                return Err(ConfigError::Invalid(format!(
+                    "Unknown FreeDNS update version {}, expected 1 or 2",
+                    version
+                )));
+            }
+        };
+        Ok(Self {
+            client,
+            tokens: config
+                .tokens
+                .iter()
+                .map(|(name, token)| (name.to_lowercase(), token.clone()))
+                .collect(),
+            version: config.version,
+            base_url: base_url.to_string(),
+        })
+    }
+
+    /// Talk to a different API endpoint, e.g. a proxy or a mock server
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into().trim_end_matches('/').to_string();
+        self
+    }
+
+    fn token(&self, dns_name: &str) -> Option<&str> {
+        self.tokens
+            .get(&dns_name.to_lowercase())
+            .map(String::as_str)
+    }
+
+    /// Update URL of `token`, which carries the token itself
+    fn update_url(&self, token: &str) -> String {
+        match self.version {
+            1 => format!("{}/dynamic/update.php?{}", self.base_url, token),
+            _ => format!("{}/u/{}/", self.base_url, token),
+        }
+    }
+}
+
+#[async_trait]
+impl DnsProvider for FreeDnsProvider {
+    fn name(&self) -> &str {
+        "freedns"
+    }
+
+    fn effective_ttl(&self, _record: &Record) -> Option<u32> {
+        None
+    }
+
+    /// Hostnames are updated as a whole, so each one is its own zone.
+    /// Fails for hostnames without a token.
+    async fn lookup_zone(&self, dns_name: &str) -> Result<Zone, ProviderError> {
+        if self.token(dns_name).is_none() {
+            return Err(ProviderError::ZoneNotFound(format!(
+                "{} (no token configured)",
+                dns_name
+            )));
+        }
+        Ok(Zone {
+            id: dns_name.to_string(),
+            name: dns_name.to_string(),
+        })
+    }
+
+    /// The record with an empty (unknown) value, as it can't be read
+    async fn read_record(
+        &self,
+        _zone: &Zone,
+        dns_name: &str,
+        kind: RecordType,
+    ) -> Result<Option<Record>, ProviderError> {
+        Ok(Some(Record {
+            id: None,
+            name: dns_name.to_string(),
+            kind,
+            content: String::new(),
+            ttl: 1,
+            proxied: None,
+        }))
+    }
+
+    /// Point the hostname at `record.content`
+    async fn upsert_record(&self, _zone: &Zone, record: &Record) -> Result<Record, ProviderError> {
+        let token = self
+            .token(&record.name)
+            .ok_or_else(|| ProviderError::ZoneNotFound(record.name.clone()))?;
+        let resp = self
+            .client
+            .get(self.update_url(token))
+            .query(&[("address", record.content.as_str())])
+            .send()
+            .await
+            // The URL holds the token
+            .map_err(reqwest::Error::without_url)?;
+        let status = resp.status();
+
+        if status == StatusCode::TOO_MANY_REQUESTS {
+            let retry_after = retry_after(resp.headers());
+            return Err(ProviderError::RateLimited { retry_after });
+        }
+        let body = resp.text().await.map_err(reqwest::Error::without_url)?;
+        let answer = body.trim();
+        let lower = answer.to_lowercase();
+
+        if lower.starts_with("updated")
+            || lower.starts_with("no ip change")
+            || lower.contains("has not changed")
+        {
+            Ok(record.clone())
+        } else if lower.contains("unable to locate") {
+            Err(ProviderError::RecordNotFound {
+                name: record.name.clone(),
+                kind: record.kind,
+            })
+        } else if lower.contains("token")
+            || status == StatusCode::UNAUTHORIZED
+            || status == StatusCode::FORBIDDEN
+        {
+            Err(ProviderError::Auth(answer.to_string()))
+        } else if lower.starts_with("error") {
+            Err(ProviderError::Api(answer.to_string()))
+        } else if !status.is_success() {
+            Err(ProviderError::for_status(
+                status,
+                format!("HTTP {}", status.as_u16()),
+            ))
+        } else {
+            Err(ProviderError::InvalidResponse(format!(
+                "unexpected answer {:?}",
+                answer
+            )))
+        }
+    }
+}
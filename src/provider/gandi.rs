@@ -0,0 +1,196 @@
+//! Gandi LiveDNS provider using the v5 REST API.
+
+use super::{
+    DnsProvider, Record, RecordType, Zone, extract_root_domain, relative_name, retry_after,
+};
+use crate::error::ProviderError;
+use async_trait::async_trait;
+use reqwest::{Client, RequestBuilder, StatusCode};
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use std::fmt;
+
+const API_BASE: &str = "https://api.gandi.net/v5/livedns";
+/// TTL used for records configured with the automatic TTL (`1`), matching
+/// the LiveDNS default
+const AUTO_TTL: u32 = 10800;
+/// Lowest TTL LiveDNS accepts
+const MIN_TTL: u32 = 300;
+
+#[derive(Deserialize)]
+struct GandiError {
+    #[serde(default)]
+    message: String,
+    #[serde(default)]
+    cause: String,
+}
+
+#[derive(Deserialize)]
+struct GandiDomain {
+    fqdn: String,
+}
+
+#[derive(Deserialize)]
+struct RecordSet {
+    rrset_ttl: u32,
+    rrset_values: Vec<String>,
+}
+
+/// `[providers.gandi]` section of the config file
+#[derive(Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct GandiConfig {
+    /// Personal access token with the "Manage domain name technical
+    /// configurations" permission
+    pub api_token: String,
+}
+
+impl fmt::Debug for GandiConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("GandiConfig")
+            .field("api_token", &"<redacted>")
+            .finish()
+    }
+}
+
+pub struct GandiProvider {
+    client: Client,
+    api_token: String,
+    base_url: String,
+}
+
+impl GandiProvider {
+    pub fn new(client: Client, api_token: impl Into<String>) -> Self {
+        Self {
+            client,
+            api_token: api_token.into(),
+            base_url: API_BASE.to_string(),
+        }
+    }
+
+    /// Talk to a different API endpoint, e.g. a proxy or a mock server
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into().trim_end_matches('/').to_string();
+        self
+    }
+
+    fn record_url(&self, zone: &Zone, dns_name: &str, kind: RecordType) -> String {
+        format!(
+            "{}/domains/{}/records/{}/{}",
+            self.base_url,
+            zone.id,
+            relative_name(dns_name, &zone.name, "@"),
+            kind.as_str()
+        )
+    }
+
+    /// Send an authenticated request and parse the JSON response, `None` if
+    /// the resource doesn't exist
+    async fn send<T: DeserializeOwned>(
+        &self,
+        request: RequestBuilder,
+    ) -> Result<Option<T>, ProviderError> {
+        let resp = request.bearer_auth(&self.api_token).send().await?;
+        let status = resp.status();
+
+        if status == StatusCode::TOO_MANY_REQUESTS {
+            let retry_after = retry_after(resp.headers());
+            return Err(ProviderError::RateLimited { retry_after });
+        }
+        if status == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+
+        let body = resp.text().await?;
+        if !status.is_success() {
+            let message = match serde_json::from_str::<GandiError>(&body) {
+                Ok(e) => format!("{} ({})", e.message, e.cause),
+                Err(_) => format!("HTTP {}", status.as_u16()),
+            };
+            return Err(match status {
+                StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => ProviderError::Auth(message),
+                _ => ProviderError::for_status(status, message),
+            });
+        }
+
+        serde_json::from_str(&body)
+            .map(Some)
+            .map_err(|e| ProviderError::InvalidResponse(format!("HTTP {}: {}", status.as_u16(), e)))
+    }
+}
+
+/// TTL the API stores for a record configured with `ttl`
+fn stored_ttl(ttl: u32) -> u32 {
+    match ttl {
+        1 => AUTO_TTL,
+        ttl => ttl.max(MIN_TTL),
+    }
+}
+
+#[async_trait]
+impl DnsProvider for GandiProvider {
+    fn name(&self) -> &str {
+        "gandi"
+    }
+
+    fn effective_ttl(&self, record: &Record) -> Option<u32> {
+        Some(stored_ttl(record.ttl))
+    }
+
+    /// Look up the LiveDNS domain `dns_name` belongs to; its name doubles as
+    /// the ID
+    async fn lookup_zone(&self, dns_name: &str) -> Result<Zone, ProviderError> {
+        let domain = extract_root_domain(dns_name);
+        let request = self
+            .client
+            .get(format!("{}/domains/{}", self.base_url, domain));
+
+        match self.send::<GandiDomain>(request).await? {
+            Some(domain) => Ok(Zone {
+                id: domain.fqdn.clone(),
+                name: domain.fqdn,
+            }),
+            None => Err(ProviderError::ZoneNotFound(domain)),
+        }
+    }
+
+    async fn read_record(
+        &self,
+        zone: &Zone,
+        dns_name: &str,
+        kind: RecordType,
+    ) -> Result<Option<Record>, ProviderError> {
+        let request = self.client.get(self.record_url(zone, dns_name, kind));
+        let set: Option<RecordSet> = self.send(request).await?;
+        Ok(set.map(|set| Record {
+            id: None,
+            name: dns_name.to_string(),
+            kind,
+            content: set.rrset_values.into_iter().next().unwrap_or_default(),
+            ttl: set.rrset_ttl,
+            proxied: None,
+        }))
+    }
+
+    /// Replace the record set with a single value
+    async fn upsert_record(&self, zone: &Zone, record: &Record) -> Result<Record, ProviderError> {
+        let ttl = stored_ttl(record.ttl);
+        let body = serde_json::json!({
+            "rrset_values": [record.content],
+            "rrset_ttl": ttl
+        });
+
+        let request = self
+            .client
+            .put(self.record_url(zone, &record.name, record.kind))
+            .json(&body);
+        // The answer is only a confirmation message
+        self.send::<serde_json::Value>(request)
+            .await?
+            .ok_or_else(|| ProviderError::ZoneNotFound(zone.name.clone()))?;
+        Ok(Record {
+            ttl,
+            ..record.clone()
+        })
+    }
+}
@@ -0,0 +1,236 @@
+//! OAuth2 access tokens for Google Cloud, from a service account key, the
+//! user credentials of `gcloud auth application-default login`, or the GCE
+//! metadata server.
+
+use super::error_from_response;
+use crate::error::{ConfigError, ProviderError};
+use jsonwebtoken::{Algorithm, EncodingKey, Header};
+use reqwest::{Client, RequestBuilder};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use std::{env, fs};
+use tokio::sync::Mutex;
+
+const SCOPE: &str = "https://www.googleapis.com/auth/ndev.clouddns.readwrite";
+const TOKEN_URI: &str = "https://oauth2.googleapis.com/token";
+const METADATA_HOST: &str = "metadata.google.internal";
+/// Refresh tokens this long before they expire
+const EXPIRY_MARGIN: Duration = Duration::from_secs(60);
+
+fn default_token_uri() -> String {
+    TOKEN_URI.to_string()
+}
+
+/// Contents of a credentials JSON file
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum CredentialsFile {
+    ServiceAccount {
+        client_email: String,
+        private_key: String,
+        #[serde(default = "default_token_uri")]
+        token_uri: String,
+        project_id: Option<String>,
+    },
+    AuthorizedUser {
+        client_id: String,
+        client_secret: String,
+        refresh_token: String,
+        quota_project_id: Option<String>,
+    },
+}
+
+enum TokenSource {
+    ServiceAccount {
+        client_email: String,
+        key: EncodingKey,
+        token_uri: String,
+    },
+    AuthorizedUser {
+        client_id: String,
+        client_secret: String,
+        refresh_token: String,
+    },
+    Metadata,
+}
+
+#[derive(Serialize)]
+struct Claims<'a> {
+    iss: &'a str,
+    scope: &'a str,
+    aud: &'a str,
+    iat: u64,
+    exp: u64,
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    expires_in: u64,
+}
+
+/// Hands out cached access tokens, fetching a new one when needed
+pub struct Authenticator {
+    source: TokenSource,
+    cached: Mutex<Option<(String, Instant)>>,
+}
+
+/// The well-known file written by `gcloud auth application-default login`
+fn well_known_file() -> Option<PathBuf> {
+    let dir = match env::var_os("APPDATA") {
+        Some(appdata) if cfg!(windows) => PathBuf::from(appdata).join("gcloud"),
+        _ => PathBuf::from(env::var_os("HOME")?)
+            .join(".config")
+            .join("gcloud"),
+    };
+    Some(dir.join("application_default_credentials.json")).filter(|p| p.exists())
+}
+
+impl Authenticator {
+    /// Load credentials from `file`, falling back to Application Default
+    /// Credentials. Also returns the project named in the credentials file.
+    pub fn new(file: Option<&Path>) -> Result<(Self, Option<String>), ConfigError> {
+        let file = file
+            .map(Path::to_path_buf)
+            .or_else(|| env::var_os("GOOGLE_APPLICATION_CREDENTIALS").map(PathBuf::from))
+            .or_else(well_known_file);
+
+        let (source, project) = match file {
+            None => (TokenSource::Metadata, None),
+            Some(path) => {
+                let raw = fs::read_to_string(&path).map_err(|e| {
+                    ConfigError::Invalid(format!(
+                        "Failed to read Google credentials {}: {}",
+                        path.display(),
+                        e
+                    ))
+                })?;
+                let invalid = |e: &dyn std::fmt::Display| {
+                    ConfigError::Invalid(format!(
+                        "Invalid Google credentials {}: {}",
+                        path.display(),
+                        e
+                    ))
+                };
+                match serde_json::from_str(&raw).map_err(|e| invalid(&e))? {
+                    CredentialsFile::ServiceAccount {
+                        client_email,
+                        private_key,
+                        token_uri,
+                        project_id,
+                    } => (
+                        TokenSource::ServiceAccount {
+                            client_email,
+                            key: EncodingKey::from_rsa_pem(private_key.as_bytes())
+                                .map_err(|e| invalid(&e))?,
+                            token_uri,
+                        },
+                        project_id,
+                    ),
+                    CredentialsFile::AuthorizedUser {
+                        client_id,
+                        client_secret,
+                        refresh_token,
+                        quota_project_id,
+                    } => (
+                        TokenSource::AuthorizedUser {
+                            client_id,
+                            client_secret,
+                            refresh_token,
+                        },
+                        quota_project_id,
+                    ),
+                }
+            }
+        };
+
+        let auth = Self {
+            source,
+            cached: Mutex::new(None),
+        };
+        Ok((auth, project))
+    }
+
+    /// A valid access token
+    pub async fn token(&self, client: &Client) -> Result<String, ProviderError> {
+        let mut cached = self.cached.lock().await;
+        if let Some((token, expires)) = cached.as_ref()
+            && Instant::now() + EXPIRY_MARGIN < *expires
+        {
+            return Ok(token.clone());
+        }
+
+        let resp = fetch(self.request(client)?).await?;
+        let expires = Instant::now() + Duration::from_secs(resp.expires_in);
+        *cached = Some((resp.access_token.clone(), expires));
+        Ok(resp.access_token)
+    }
+
+    fn request(&self, client: &Client) -> Result<RequestBuilder, ProviderError> {
+        Ok(match &self.source {
+            TokenSource::ServiceAccount {
+                client_email,
+                key,
+                token_uri,
+            } => {
+                let now = SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .unwrap_or_default()
+                    .as_secs();
+                let claims = Claims {
+                    iss: client_email,
+                    scope: SCOPE,
+                    aud: token_uri,
+                    iat: now,
+                    exp: now + 3600,
+                };
+                let assertion = jsonwebtoken::encode(&Header::new(Algorithm::RS256), &claims, key)
+                    .map_err(|e| {
+                        ProviderError::Auth(format!("Failed to sign token request: {}", e))
+                    })?;
+                client.post(token_uri).form(&[
+                    ("grant_type", "urn:ietf:params:oauth:grant-type:jwt-bearer"),
+                    ("assertion", &assertion),
+                ])
+            }
+            TokenSource::AuthorizedUser {
+                client_id,
+                client_secret,
+                refresh_token,
+            } => client.post(TOKEN_URI).form(&[
+                ("grant_type", "refresh_token"),
+                ("client_id", client_id),
+                ("client_secret", client_secret),
+                ("refresh_token", refresh_token),
+            ]),
+            TokenSource::Metadata => {
+                let host = env::var("GCE_METADATA_HOST").unwrap_or_else(|_| METADATA_HOST.into());
+                client
+                    .get(format!(
+                        "http://{}/computeMetadata/v1/instance/service-accounts/default/token",
+                        host
+                    ))
+                    .header("Metadata-Flavor", "Google")
+            }
+        })
+    }
+}
+
+async fn fetch(request: RequestBuilder) -> Result<TokenResponse, ProviderError> {
+    let resp = request.send().await?;
+    let status = resp.status();
+    let headers = resp.headers().clone();
+    let body = resp.text().await?;
+
+    if !status.is_success() {
+        return Err(match error_from_response(status, &headers, &body) {
+            ProviderError::Api(message) | ProviderError::NotFound(message) => {
+                ProviderError::Auth(format!("Token request failed: {}", message))
+            }
+            e => e,
+        });
+    }
+    serde_json::from_str(&body)
+        .map_err(|e| ProviderError::InvalidResponse(format!("token response: {}", e)))
+}
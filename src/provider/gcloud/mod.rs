@@ -0,0 +1,304 @@
+//! Google Cloud DNS provider using the v1 REST API.
+//!
+//! Cloud DNS replaces record sets through changes, which must delete the
+//! current record set exactly as stored before adding its new version.
+
+mod auth;
+
+use super::{DnsProvider, Record, RecordType, Zone, extract_root_domain, retry_after};
+use crate::error::{ConfigError, ProviderError};
+use async_trait::async_trait;
+use auth::Authenticator;
+use reqwest::header::HeaderMap;
+use reqwest::{Client, RequestBuilder, StatusCode};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::env;
+use std::path::PathBuf;
+
+const API_BASE: &str = "https://dns.googleapis.com/dns/v1";
+/// TTL used for records configured with the automatic TTL (`1`), which
+/// Cloud DNS doesn't have
+const AUTO_TTL: u32 = 300;
+/// Error reasons Google uses for quota errors, which come as HTTP 403
+const RATE_LIMIT_REASONS: [&str; 3] = [
+    "rateLimitExceeded",
+    "userRateLimitExceeded",
+    "quotaExceeded",
+];
+
+/// Map an unsuccessful response to a [`ProviderError`]
+fn error_from_response(status: StatusCode, headers: &HeaderMap, body: &str) -> ProviderError {
+    let json: serde_json::Value = serde_json::from_str(body).unwrap_or_default();
+    let error = &json["error"];
+    // API errors are objects, OAuth errors a plain code with a description
+    let message = error["message"]
+        .as_str()
+        .or_else(|| json["error_description"].as_str())
+        .or_else(|| error.as_str())
+        .map(str::to_string)
+        .unwrap_or_else(|| format!("HTTP {}", status.as_u16()));
+    let rate_limited = error["errors"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .any(|e| RATE_LIMIT_REASONS.contains(&e["reason"].as_str().unwrap_or_default()));
+
+    if status == StatusCode::TOO_MANY_REQUESTS || rate_limited {
+        let retry_after = retry_after(headers);
+        return ProviderError::RateLimited { retry_after };
+    }
+    match status {
+        StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => ProviderError::Auth(message),
+        _ => ProviderError::for_status(status, message),
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ManagedZonesResponse {
+    #[serde(default)]
+    managed_zones: Vec<ManagedZone>,
+    next_page_token: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ManagedZone {
+    name: String,
+    dns_name: String,
+    #[serde(default)]
+    visibility: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RecordSetsResponse {
+    #[serde(default)]
+    rrsets: Vec<RecordSet>,
+    next_page_token: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+struct RecordSet {
+    name: String,
+    #[serde(rename = "type")]
+    kind: String,
+    ttl: u32,
+    rrdatas: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct Change<'a> {
+    additions: [&'a RecordSet; 1],
+    deletions: Vec<RecordSet>,
+}
+
+#[derive(Deserialize)]
+struct ChangeResponse {
+    #[serde(default)]
+    additions: Vec<RecordSet>,
+}
+
+/// Absolute form of `dns_name` (with the trailing dot) used by the API
+fn fqdn(dns_name: &str) -> String {
+    format!("{}.", dns_name.trim_end_matches('.'))
+}
+
+fn same_name(a: &str, b: &str) -> bool {
+    a.trim_end_matches('.')
+        .eq_ignore_ascii_case(b.trim_end_matches('.'))
+}
+
+/// `[providers.gcloud]` section of the config file
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GcloudConfig {
+    /// Project owning the managed zones, defaults to the project of the
+    /// credentials or `GOOGLE_CLOUD_PROJECT`
+    pub project: Option<String>,
+    /// Service account key or user credentials JSON, defaults to
+    /// Application Default Credentials
+    pub credentials_file: Option<PathBuf>,
+}
+
+pub struct GcloudProvider {
+    client: Client,
+    auth: Authenticator,
+    project: String,
+    base_url: String,
+}
+
+impl GcloudProvider {
+    pub fn new(client: Client, config: &GcloudConfig) -> Result<Self, ConfigError> {
+        let (auth, key_project) = Authenticator::new(config.credentials_file.as_deref())?;
+        let project = config
+            .project
+            .clone()
+            .or(key_project)
+            .or_else(|| env::var("GOOGLE_CLOUD_PROJECT").ok())
+            .ok_or_else(|| {
+                ConfigError::Missing(
+                    "Google Cloud project is required (`project` in [providers.gcloud])".into(),
+                )
+            })?;
+        Ok(Self {
+            client,
+            auth,
+            project,
+            base_url: API_BASE.to_string(),
+        })
+    }
+
+    /// Talk to a different API endpoint, e.g. a proxy or a mock server
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into().trim_end_matches('/').to_string();
+        self
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!("{}/projects/{}{}", self.base_url, self.project, path)
+    }
+
+    /// Send an authenticated request and parse the JSON response
+    async fn send<T: DeserializeOwned>(&self, request: RequestBuilder) -> Result<T, ProviderError> {
+        let token = self.auth.token(&self.client).await?;
+        let resp = request.bearer_auth(token).send().await?;
+        let status = resp.status();
+        let headers = resp.headers().clone();
+        let body = resp.text().await?;
+
+        if !status.is_success() {
+            return Err(error_from_response(status, &headers, &body));
+        }
+        serde_json::from_str(&body)
+            .map_err(|e| ProviderError::InvalidResponse(format!("HTTP {}: {}", status.as_u16(), e)))
+    }
+
+    /// The record set of the given type for `dns_name`, walking all pages
+    async fn find_record_set(
+        &self,
+        zone: &Zone,
+        dns_name: &str,
+        kind: RecordType,
+    ) -> Result<Option<RecordSet>, ProviderError> {
+        let name = fqdn(dns_name);
+        let mut page_token = None;
+        loop {
+            let mut request = self
+                .client
+                .get(self.url(&format!("/managedZones/{}/rrsets", zone.id)))
+                .query(&[("name", name.as_str()), ("type", kind.as_str())]);
+            if let Some(token) = &page_token {
+                request = request.query(&[("pageToken", token)]);
+            }
+            let page: RecordSetsResponse = self.send(request).await?;
+
+            if let Some(set) = page
+                .rrsets
+                .into_iter()
+                .find(|s| same_name(&s.name, dns_name) && s.kind == kind.as_str())
+            {
+                return Ok(Some(set));
+            }
+            match page.next_page_token {
+                Some(token) => page_token = Some(token),
+                None => return Ok(None),
+            }
+        }
+    }
+}
+
+fn into_record(set: RecordSet, dns_name: &str, kind: RecordType) -> Record {
+    Record {
+        id: None,
+        name: dns_name.to_string(),
+        kind,
+        content: set.rrdatas.into_iter().next().unwrap_or_default(),
+        ttl: set.ttl,
+        proxied: None,
+    }
+}
+
+/// TTL the API stores for a record configured with `ttl`
+fn stored_ttl(ttl: u32) -> u32 {
+    if ttl <= 1 { AUTO_TTL } else { ttl }
+}
+
+#[async_trait]
+impl DnsProvider for GcloudProvider {
+    fn name(&self) -> &str {
+        "gcloud"
+    }
+
+    fn effective_ttl(&self, record: &Record) -> Option<u32> {
+        Some(stored_ttl(record.ttl))
+    }
+
+    /// Find the public managed zone of the root domain of `dns_name`
+    async fn lookup_zone(&self, dns_name: &str) -> Result<Zone, ProviderError> {
+        let domain = extract_root_domain(dns_name);
+        let dns_name = fqdn(&domain);
+        let mut page_token = None;
+        loop {
+            let mut request = self
+                .client
+                .get(self.url("/managedZones"))
+                .query(&[("dnsName", dns_name.as_str())]);
+            if let Some(token) = &page_token {
+                request = request.query(&[("pageToken", token)]);
+            }
+            let page: ManagedZonesResponse = self.send(request).await?;
+
+            if let Some(zone) = page
+                .managed_zones
+                .into_iter()
+                .find(|z| same_name(&z.dns_name, &domain) && z.visibility != "private")
+            {
+                return Ok(Zone {
+                    id: zone.name,
+                    name: zone.dns_name.trim_end_matches('.').to_string(),
+                });
+            }
+            match page.next_page_token {
+                Some(token) => page_token = Some(token),
+                None => return Err(ProviderError::ZoneNotFound(domain)),
+            }
+        }
+    }
+
+    async fn read_record(
+        &self,
+        zone: &Zone,
+        dns_name: &str,
+        kind: RecordType,
+    ) -> Result<Option<Record>, ProviderError> {
+        let set = self.find_record_set(zone, dns_name, kind).await?;
+        Ok(set.map(|set| into_record(set, dns_name, kind)))
+    }
+
+    /// Replace the record set, deleting its current version in the same change
+    async fn upsert_record(&self, zone: &Zone, record: &Record) -> Result<Record, ProviderError> {
+        let current = self
+            .find_record_set(zone, &record.name, record.kind)
+            .await?;
+        let desired = RecordSet {
+            name: fqdn(&record.name),
+            kind: record.kind.as_str().to_string(),
+            ttl: stored_ttl(record.ttl),
+            rrdatas: vec![record.content.clone()],
+        };
+        let change = Change {
+            additions: [&desired],
+            deletions: current.into_iter().collect(),
+        };
+
+        let request = self
+            .client
+            .post(self.url(&format!("/managedZones/{}/changes", zone.id)))
+            .json(&change);
+        let resp: ChangeResponse = self.send(request).await?;
+        let stored = resp.additions.into_iter().next().unwrap_or(desired);
+        Ok(into_record(stored, &record.name, record.kind))
+    }
+}
@@ -0,0 +1,219 @@
+//! GoDaddy provider using the v1 domains API.
+
+use super::{
+    DnsProvider, Record, RecordType, Zone, extract_root_domain, relative_name, retry_after,
+};
+use crate::error::ProviderError;
+use async_trait::async_trait;
+use reqwest::header::AUTHORIZATION;
+use reqwest::{Client, RequestBuilder, StatusCode};
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use std::fmt;
+use std::time::Duration;
+
+const API_BASE: &str = "https://api.godaddy.com/v1";
+/// TTL used for records configured with the automatic TTL (`1`), matching
+/// the GoDaddy default
+const AUTO_TTL: u32 = 3600;
+/// Lowest TTL GoDaddy accepts
+const MIN_TTL: u32 = 600;
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GoDaddyError {
+    #[serde(default)]
+    code: String,
+    #[serde(default)]
+    message: String,
+    /// Set on rate limit errors
+    retry_after_sec: Option<u64>,
+}
+
+#[derive(Deserialize)]
+struct GoDaddyDomain {
+    domain: String,
+}
+
+#[derive(Deserialize)]
+struct GoDaddyRecord {
+    data: String,
+    ttl: u32,
+}
+
+/// `[providers.godaddy]` section of the config file
+#[derive(Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct GoDaddyConfig {
+    /// Production API key from the developer portal
+    pub api_key: String,
+    pub api_secret: String,
+}
+
+impl fmt::Debug for GoDaddyConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("GoDaddyConfig")
+            .field("api_key", &self.api_key)
+            .field("api_secret", &"<redacted>")
+            .finish()
+    }
+}
+
+pub struct GoDaddyProvider {
+    client: Client,
+    api_key: String,
+    api_secret: String,
+    base_url: String,
+}
+
+impl GoDaddyProvider {
+    pub fn new(client: Client, config: &GoDaddyConfig) -> Self {
+        Self {
+            client,
+            api_key: config.api_key.clone(),
+            api_secret: config.api_secret.clone(),
+            base_url: API_BASE.to_string(),
+        }
+    }
+
+    /// Talk to a different API endpoint, e.g. a proxy or a mock server
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into().trim_end_matches('/').to_string();
+        self
+    }
+
+    fn records_url(&self, zone: &Zone, dns_name: &str, kind: RecordType) -> String {
+        format!(
+            "{}/domains/{}/records/{}/{}",
+            self.base_url,
+            zone.id,
+            kind.as_str(),
+            relative_name(dns_name, &zone.name, "@")
+        )
+    }
+
+    /// Send an authenticated request and parse the JSON response, `None` if
+    /// the resource doesn't exist
+    async fn send<T: DeserializeOwned>(
+        &self,
+        request: RequestBuilder,
+    ) -> Result<Option<T>, ProviderError> {
+        let resp = request
+            .header(
+                AUTHORIZATION,
+                format!("sso-key {}:{}", self.api_key, self.api_secret),
+            )
+            .send()
+            .await?;
+        let status = resp.status();
+        let retry_after_header = retry_after(resp.headers());
+
+        if status == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+
+        let body = resp.text().await?;
+        if !status.is_success() {
+            let error = serde_json::from_str::<GoDaddyError>(&body).ok();
+            if status == StatusCode::TOO_MANY_REQUESTS {
+                // The delay comes in the body rather than a header
+                let retry_after = retry_after_header.or_else(|| {
+                    error
+                        .and_then(|e| e.retry_after_sec)
+                        .map(Duration::from_secs)
+                });
+                return Err(ProviderError::RateLimited { retry_after });
+            }
+            let message = match error {
+                Some(e) => format!("{} ({})", e.message, e.code),
+                None => format!("HTTP {}", status.as_u16()),
+            };
+            return Err(match status {
+                StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => ProviderError::Auth(message),
+                _ => ProviderError::for_status(status, message),
+            });
+        }
+
+        // Record replacement answers with an empty body
+        let body = if body.trim().is_empty() {
+            "null"
+        } else {
+            &body
+        };
+        serde_json::from_str(body)
+            .map(Some)
+            .map_err(|e| ProviderError::InvalidResponse(format!("HTTP {}: {}", status.as_u16(), e)))
+    }
+}
+
+/// TTL the API stores for a record configured with `ttl`
+fn stored_ttl(ttl: u32) -> u32 {
+    match ttl {
+        1 => AUTO_TTL,
+        ttl => ttl.max(MIN_TTL),
+    }
+}
+
+#[async_trait]
+impl DnsProvider for GoDaddyProvider {
+    fn name(&self) -> &str {
+        "godaddy"
+    }
+
+    fn effective_ttl(&self, record: &Record) -> Option<u32> {
+        Some(stored_ttl(record.ttl))
+    }
+
+    /// Look up the root domain of `dns_name` in the account; its name
+    /// doubles as the ID
+    async fn lookup_zone(&self, dns_name: &str) -> Result<Zone, ProviderError> {
+        let domain = extract_root_domain(dns_name);
+        let request = self
+            .client
+            .get(format!("{}/domains/{}", self.base_url, domain));
+
+        match self.send::<GoDaddyDomain>(request).await? {
+            Some(found) => Ok(Zone {
+                id: found.domain.clone(),
+                name: found.domain,
+            }),
+            None => Err(ProviderError::ZoneNotFound(domain)),
+        }
+    }
+
+    async fn read_record(
+        &self,
+        zone: &Zone,
+        dns_name: &str,
+        kind: RecordType,
+    ) -> Result<Option<Record>, ProviderError> {
+        let request = self.client.get(self.records_url(zone, dns_name, kind));
+        let records: Vec<GoDaddyRecord> = self.send(request).await?.unwrap_or_default();
+        Ok(records.into_iter().next().map(|r| Record {
+            id: None,
+            name: dns_name.to_string(),
+            kind,
+            content: r.data,
+            ttl: r.ttl,
+            proxied: None,
+        }))
+    }
+
+    /// Replace all records of the name and type with a single value
+    async fn upsert_record(&self, zone: &Zone, record: &Record) -> Result<Record, ProviderError> {
+        let ttl = stored_ttl(record.ttl);
+        let body = serde_json::json!([{ "data": record.content, "ttl": ttl }]);
+
+        let request = self
+            .client
+            .put(self.records_url(zone, &record.name, record.kind))
+            .json(&body);
+        self.send::<serde_json::Value>(request)
+            .await?
+            .ok_or_else(|| ProviderError::ZoneNotFound(zone.name.clone()))?;
+        Ok(Record {
+            ttl,
+            ..record.clone()
+        })
+    }
+}
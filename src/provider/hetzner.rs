@@ -0,0 +1,246 @@
+//! Hetzner DNS Console provider using the v1 API.
+
+use super::{
+    DnsProvider, Record, RecordType, Zone, extract_root_domain, relative_name, retry_after,
+};
+use crate::error::ProviderError;
+use async_trait::async_trait;
+use reqwest::{Client, RequestBuilder, StatusCode};
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use std::fmt;
+
+const API_BASE: &str = "https://dns.hetzner.com/api/v1";
+
+#[derive(Deserialize)]
+struct ZonesResponse {
+    zones: Vec<HetznerZone>,
+    #[serde(default)]
+    meta: Meta,
+}
+
+#[derive(Deserialize)]
+struct HetznerZone {
+    id: String,
+    name: String,
+}
+
+#[derive(Deserialize)]
+struct RecordsResponse {
+    records: Vec<HetznerRecord>,
+    #[serde(default)]
+    meta: Meta,
+}
+
+#[derive(Deserialize)]
+struct RecordResponse {
+    record: HetznerRecord,
+}
+
+#[derive(Deserialize)]
+struct HetznerRecord {
+    id: String,
+    #[serde(rename = "type")]
+    kind: String,
+    /// Name relative to the zone, `@` for the apex
+    name: String,
+    value: String,
+    /// Unset for records using the zone default
+    ttl: Option<u32>,
+}
+
+impl HetznerRecord {
+    fn into_record(self, dns_name: &str, kind: RecordType) -> Record {
+        Record {
+            id: Some(self.id),
+            name: dns_name.to_string(),
+            kind,
+            content: self.value,
+            ttl: self.ttl.unwrap_or(1),
+            proxied: None,
+        }
+    }
+}
+
+#[derive(Deserialize, Default)]
+struct Meta {
+    pagination: Option<Pagination>,
+}
+
+#[derive(Deserialize)]
+struct Pagination {
+    page: u32,
+    last_page: u32,
+}
+
+impl Meta {
+    fn has_next(&self) -> bool {
+        self.pagination
+            .as_ref()
+            .is_some_and(|p| p.page < p.last_page)
+    }
+}
+
+/// `[providers.hetzner]` section of the config file
+#[derive(Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct HetznerConfig {
+    /// API token from the DNS Console
+    pub api_token: String,
+}
+
+impl fmt::Debug for HetznerConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HetznerConfig")
+            .field("api_token", &"<redacted>")
+            .finish()
+    }
+}
+
+pub struct HetznerProvider {
+    client: Client,
+    api_token: String,
+    base_url: String,
+}
+
+impl HetznerProvider {
+    pub fn new(client: Client, api_token: impl Into<String>) -> Self {
+        Self {
+            client,
+            api_token: api_token.into(),
+            base_url: API_BASE.to_string(),
+        }
+    }
+
+    /// Talk to a different API endpoint, e.g. a proxy or a mock server
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into().trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Send an authenticated request and parse the JSON response
+    async fn send<T: DeserializeOwned>(&self, request: RequestBuilder) -> Result<T, ProviderError> {
+        let resp = request
+            .header("Auth-API-Token", &self.api_token)
+            .send()
+            .await?;
+        let status = resp.status();
+
+        if status == StatusCode::TOO_MANY_REQUESTS {
+            let retry_after = retry_after(resp.headers());
+            return Err(ProviderError::RateLimited { retry_after });
+        }
+
+        let body = resp.text().await?;
+        if !status.is_success() {
+            // Either `{"message": ...}` or `{"error": {"message": ...}}`
+            let json: serde_json::Value = serde_json::from_str(&body).unwrap_or_default();
+            let message = json["message"]
+                .as_str()
+                .or_else(|| json["error"]["message"].as_str())
+                .map(str::to_string)
+                .unwrap_or_else(|| format!("HTTP {}", status.as_u16()));
+            return Err(match status {
+                StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => ProviderError::Auth(message),
+                _ => ProviderError::for_status(status, message),
+            });
+        }
+
+        serde_json::from_str(&body)
+            .map_err(|e| ProviderError::InvalidResponse(format!("HTTP {}: {}", status.as_u16(), e)))
+    }
+}
+
+#[async_trait]
+impl DnsProvider for HetznerProvider {
+    fn name(&self) -> &str {
+        "hetzner"
+    }
+
+    /// Find the zone of the root domain of `dns_name`
+    async fn lookup_zone(&self, dns_name: &str) -> Result<Zone, ProviderError> {
+        let domain = extract_root_domain(dns_name);
+        let mut page = 1;
+        loop {
+            let request = self
+                .client
+                .get(format!("{}/zones", self.base_url))
+                .query(&[("name", domain.as_str())])
+                .query(&[("page", page)]);
+            let resp: ZonesResponse = match self.send(request).await {
+                Ok(resp) => resp,
+                // Searching for an unknown name is answered with a 404
+                Err(ProviderError::NotFound(_)) => {
+                    return Err(ProviderError::ZoneNotFound(domain));
+                }
+                Err(e) => return Err(e),
+            };
+
+            if let Some(zone) = resp
+                .zones
+                .into_iter()
+                .find(|z| z.name.eq_ignore_ascii_case(&domain))
+            {
+                return Ok(Zone {
+                    id: zone.id,
+                    name: zone.name,
+                });
+            }
+            if !resp.meta.has_next() {
+                return Err(ProviderError::ZoneNotFound(domain));
+            }
+            page += 1;
+        }
+    }
+
+    async fn read_record(
+        &self,
+        zone: &Zone,
+        dns_name: &str,
+        kind: RecordType,
+    ) -> Result<Option<Record>, ProviderError> {
+        let relative = relative_name(dns_name, &zone.name, "@");
+        let mut page = 1;
+        loop {
+            let request = self
+                .client
+                .get(format!("{}/records", self.base_url))
+                .query(&[("zone_id", zone.id.as_str())])
+                .query(&[("page", page)]);
+            let resp: RecordsResponse = self.send(request).await?;
+
+            if let Some(record) = resp
+                .records
+                .into_iter()
+                .find(|r| r.kind == kind.as_str() && r.name.eq_ignore_ascii_case(&relative))
+            {
+                return Ok(Some(record.into_record(dns_name, kind)));
+            }
+            if !resp.meta.has_next() {
+                return Ok(None);
+            }
+            page += 1;
+        }
+    }
+
+    async fn upsert_record(&self, zone: &Zone, record: &Record) -> Result<Record, ProviderError> {
+        let mut body = serde_json::json!({
+            "zone_id": zone.id,
+            "type": record.kind.as_str(),
+            "name": relative_name(&record.name, &zone.name, "@"),
+            "value": record.content
+        });
+        // Leaving the TTL out uses the zone default
+        if record.ttl > 1 {
+            body["ttl"] = record.ttl.into();
+        }
+
+        let request = match &record.id {
+            Some(id) => self.client.put(format!("{}/records/{}", self.base_url, id)),
+            None => self.client.post(format!("{}/records", self.base_url)),
+        };
+
+        let resp: RecordResponse = self.send(request.json(&body)).await?;
+        Ok(resp.record.into_record(&record.name, record.kind))
+    }
+}
@@ -0,0 +1,162 @@
+//! Hurricane Electric Free DNS (dns.he.net) provider using its dynamic
+//! update endpoint.
+//!
+//! Records marked for dynamic DNS in the dns.he.net interface get their own
+//! update key, which only works for that record. The endpoint speaks the
+//! dyndns2 answer codes and can't read records back, so the agent updates
+//! them once at startup.
+
+use super::{DnsProvider, Record, RecordType, Zone, retry_after};
+use crate::error::ProviderError;
+use async_trait::async_trait;
+use reqwest::{Client, StatusCode};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fmt;
+
+const API_BASE: &str = "https://dyn.dns.he.net";
+
+/// `[providers.hurricane]` section of the config file
+#[derive(Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct HurricaneConfig {
+    /// Dynamic DNS key of each hostname, generated from the record's
+    /// "Generate a DDNS key" button
+    pub keys: BTreeMap<String, String>,
+}
+
+impl fmt::Debug for HurricaneConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let keys: BTreeMap<_, _> = self.keys.keys().map(|k| (k, "<redacted>")).collect();
+        f.debug_struct("HurricaneConfig")
+            .field("keys", &keys)
+            .finish()
+    }
+}
+
+pub struct HurricaneProvider {
+    client: Client,
+    /// Keys by lowercase hostname
+    keys: BTreeMap<String, String>,
+    base_url: String,
+}
+
+impl HurricaneProvider {
+    pub fn new(client: Client, config: &HurricaneConfig) -> Self {
+        Self {
+            client,
+            keys: config
+                .keys
+                .iter()
+                .map(|(name, key)| (name.to_lowercase(), key.clone()))
+                .collect(),
+            base_url: API_BASE.to_string(),
+        }
+    }
+
+    /// Talk to a different API endpoint, e.g. a proxy or a mock server
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into().trim_end_matches('/').to_string();
+        self
+    }
+
+    fn key(&self, dns_name: &str) -> Option<&str> {
+        self.keys.get(&dns_name.to_lowercase()).map(String::as_str)
+    }
+}
+
+#[async_trait]
+impl DnsProvider for HurricaneProvider {
+    fn name(&self) -> &str {
+        "hurricane"
+    }
+
+    fn effective_ttl(&self, _record: &Record) -> Option<u32> {
+        None
+    }
+
+    /// Hostnames are updated as a whole, so each one is its own zone.
+    /// Fails for hostnames without a key.
+    async fn lookup_zone(&self, dns_name: &str) -> Result<Zone, ProviderError> {
+        if self.key(dns_name).is_none() {
+            return Err(ProviderError::ZoneNotFound(format!(
+                "{} (no key configured)",
+                dns_name
+            )));
+        }
+        Ok(Zone {
+            id: dns_name.to_string(),
+            name: dns_name.to_string(),
+        })
+    }
+
+    /// The record with an empty (unknown) value, as it can't be read
+    async fn read_record(
+        &self,
+        _zone: &Zone,
+        dns_name: &str,
+        kind: RecordType,
+    ) -> Result<Option<Record>, ProviderError> {
+        Ok(Some(Record {
+            id: None,
+            name: dns_name.to_string(),
+            kind,
+            content: String::new(),
+            ttl: 1,
+            proxied: None,
+        }))
+    }
+
+    /// Point the hostname at `record.content`
+    async fn upsert_record(&self, _zone: &Zone, record: &Record) -> Result<Record, ProviderError> {
+        let key = self
+            .key(&record.name)
+            .ok_or_else(|| ProviderError::ZoneNotFound(record.name.clone()))?;
+        // Posted as a form so the key stays out of the URL
+        let resp = self
+            .client
+            .post(format!("{}/nic/update", self.base_url))
+            .form(&[
+                ("hostname", record.name.as_str()),
+                ("password", key),
+                ("myip", record.content.as_str()),
+            ])
+            .send()
+            .await?;
+        let status = resp.status();
+
+        if status == StatusCode::TOO_MANY_REQUESTS {
+            let retry_after = retry_after(resp.headers());
+            return Err(ProviderError::RateLimited { retry_after });
+        }
+        let body = resp.text().await?;
+        // A wrong key is answered with badauth and a 401
+        let answer = body.lines().next().unwrap_or_default().trim();
+        let code = answer.split_whitespace().next().unwrap_or_default();
+
+        match code {
+            "good" | "nochg" => Ok(record.clone()),
+            "badauth" => Err(ProviderError::Auth(
+                "badauth (wrong key, or the record isn't enabled for dynamic DNS)".into(),
+            )),
+            "nohost" => Err(ProviderError::RecordNotFound {
+                name: record.name.clone(),
+                kind: record.kind,
+            }),
+            "notfqdn" | "abuse" | "badagent" | "dnserr" | "911" => {
+                Err(ProviderError::Api(answer.to_string()))
+            }
+            _ if status == StatusCode::UNAUTHORIZED || status == StatusCode::FORBIDDEN => {
+                Err(ProviderError::Auth(format!("HTTP {}", status.as_u16())))
+            }
+            _ if !status.is_success() => Err(ProviderError::for_status(
+                status,
+                format!("HTTP {}", status.as_u16()),
+            )),
+            _ => Err(ProviderError::InvalidResponse(format!(
+                "unexpected answer {:?}",
+                answer
+            ))),
+        }
+    }
+}
@@ -0,0 +1,239 @@
+//! Infomaniak provider using the v1 public API.
+//!
+//! Responses wrap their payload as `{"result": "success", "data": ...}`, or
+//! `{"result": "error", "error": ...}` on failure.
+
+use super::{
+    DnsProvider, Record, RecordType, Zone, extract_root_domain, relative_name, retry_after,
+};
+use crate::error::ProviderError;
+use async_trait::async_trait;
+use reqwest::{Client, RequestBuilder, StatusCode};
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use std::fmt;
+
+const API_BASE: &str = "https://api.infomaniak.com";
+/// TTL used for records configured with the automatic TTL (`1`), matching
+/// the Infomaniak default
+const AUTO_TTL: u32 = 3600;
+/// Lowest TTL Infomaniak accepts
+const MIN_TTL: u32 = 300;
+
+#[derive(Deserialize)]
+struct Response<T> {
+    result: String,
+    data: Option<T>,
+    error: Option<InfomaniakError>,
+}
+
+#[derive(Deserialize)]
+struct InfomaniakError {
+    #[serde(default)]
+    code: String,
+    #[serde(default)]
+    description: String,
+}
+
+#[derive(Deserialize)]
+struct Product {
+    id: u64,
+    customer_name: String,
+}
+
+#[derive(Deserialize)]
+struct InfomaniakRecord {
+    id: u64,
+    /// Name relative to the domain, `.` for the apex
+    source: String,
+    #[serde(rename = "type")]
+    kind: String,
+    target: String,
+    ttl: u32,
+}
+
+impl InfomaniakRecord {
+    fn into_record(self, dns_name: &str, kind: RecordType) -> Record {
+        Record {
+            id: Some(self.id.to_string()),
+            name: dns_name.to_string(),
+            kind,
+            content: self.target,
+            ttl: self.ttl,
+            proxied: None,
+        }
+    }
+}
+
+/// `[providers.infomaniak]` section of the config file
+#[derive(Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct InfomaniakConfig {
+    /// API token with the `domain:read` and `dns:write` scopes
+    pub api_token: String,
+}
+
+impl fmt::Debug for InfomaniakConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("InfomaniakConfig")
+            .field("api_token", &"<redacted>")
+            .finish()
+    }
+}
+
+pub struct InfomaniakProvider {
+    client: Client,
+    api_token: String,
+    base_url: String,
+}
+
+impl InfomaniakProvider {
+    pub fn new(client: Client, api_token: impl Into<String>) -> Self {
+        Self {
+            client,
+            api_token: api_token.into(),
+            base_url: API_BASE.to_string(),
+        }
+    }
+
+    /// Talk to a different API endpoint, e.g. a proxy or a mock server
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into().trim_end_matches('/').to_string();
+        self
+    }
+
+    fn records_url(&self, zone: &Zone) -> String {
+        format!("{}/1/domain/{}/dns/record", self.base_url, zone.id)
+    }
+
+    /// Send an authenticated request and unwrap the `data` of the response
+    async fn send<T: DeserializeOwned>(&self, request: RequestBuilder) -> Result<T, ProviderError> {
+        let resp = request.bearer_auth(&self.api_token).send().await?;
+        let status = resp.status();
+
+        if status == StatusCode::TOO_MANY_REQUESTS {
+            let retry_after = retry_after(resp.headers());
+            return Err(ProviderError::RateLimited { retry_after });
+        }
+
+        let body = resp.text().await?;
+        let parsed = serde_json::from_str::<Response<T>>(&body);
+        if !status.is_success() {
+            let message = match parsed.ok().and_then(|r| r.error) {
+                Some(e) => format!("{} ({})", e.description, e.code),
+                None => format!("HTTP {}", status.as_u16()),
+            };
+            return Err(match status {
+                StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => ProviderError::Auth(message),
+                _ => ProviderError::for_status(status, message),
+            });
+        }
+
+        let resp = parsed.map_err(|e| {
+            ProviderError::InvalidResponse(format!("HTTP {}: {}", status.as_u16(), e))
+        })?;
+        match (resp.result.as_str(), resp.data, resp.error) {
+            ("success", Some(data), _) => Ok(data),
+            (_, _, Some(e)) => Err(ProviderError::Api(format!(
+                "{} ({})",
+                e.description, e.code
+            ))),
+            (result, _, _) => Err(ProviderError::InvalidResponse(format!(
+                "result {:?} without data",
+                result
+            ))),
+        }
+    }
+}
+
+/// TTL the API stores for a record configured with `ttl`
+fn stored_ttl(ttl: u32) -> u32 {
+    match ttl {
+        1 => AUTO_TTL,
+        ttl => ttl.max(MIN_TTL),
+    }
+}
+
+#[async_trait]
+impl DnsProvider for InfomaniakProvider {
+    fn name(&self) -> &str {
+        "infomaniak"
+    }
+
+    fn effective_ttl(&self, record: &Record) -> Option<u32> {
+        Some(stored_ttl(record.ttl))
+    }
+
+    /// Find the domain product of the root domain of `dns_name`, whose ID
+    /// addresses the zone
+    async fn lookup_zone(&self, dns_name: &str) -> Result<Zone, ProviderError> {
+        let domain = extract_root_domain(dns_name);
+        let request = self
+            .client
+            .get(format!("{}/1/product", self.base_url))
+            .query(&[
+                ("service_name", "domain"),
+                ("customer_name", domain.as_str()),
+            ]);
+        let products: Vec<Product> = self.send(request).await?;
+
+        products
+            .into_iter()
+            .find(|p| p.customer_name.eq_ignore_ascii_case(&domain))
+            .map(|p| Zone {
+                id: p.id.to_string(),
+                name: p.customer_name,
+            })
+            .ok_or(ProviderError::ZoneNotFound(domain))
+    }
+
+    async fn read_record(
+        &self,
+        zone: &Zone,
+        dns_name: &str,
+        kind: RecordType,
+    ) -> Result<Option<Record>, ProviderError> {
+        let source = relative_name(dns_name, &zone.name, ".");
+        let request = self.client.get(self.records_url(zone));
+        let records: Vec<InfomaniakRecord> = self.send(request).await?;
+
+        Ok(records
+            .into_iter()
+            .find(|r| r.kind == kind.as_str() && r.source.eq_ignore_ascii_case(&source))
+            .map(|r| r.into_record(dns_name, kind)))
+    }
+
+    async fn upsert_record(&self, zone: &Zone, record: &Record) -> Result<Record, ProviderError> {
+        let ttl = stored_ttl(record.ttl);
+        let body = serde_json::json!({
+            "source": relative_name(&record.name, &zone.name, "."),
+            "type": record.kind.as_str(),
+            "target": record.content,
+            "ttl": ttl
+        });
+
+        match &record.id {
+            Some(id) => {
+                let request = self
+                    .client
+                    .put(format!("{}/{}", self.records_url(zone), id))
+                    .json(&body);
+                self.send::<serde_json::Value>(request).await?;
+                Ok(Record {
+                    ttl,
+                    ..record.clone()
+                })
+            }
+            None => {
+                // Creation answers with the new record's ID
+                let request = self.client.post(self.records_url(zone)).json(&body);
+                let id: u64 = self.send(request).await?;
+                Ok(Record {
+                    id: Some(id.to_string()),
+                    ttl,
+                    ..record.clone()
+                })
+            }
+        }
+    }
+}
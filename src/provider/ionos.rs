@@ -0,0 +1,235 @@
+//! IONOS (1&1) provider using the v1 hosting DNS API.
+
+use super::{DnsProvider, Record, RecordType, Zone, extract_root_domain, retry_after};
+use crate::error::ProviderError;
+use async_trait::async_trait;
+use reqwest::{Client, RequestBuilder, StatusCode};
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use std::fmt;
+
+const API_BASE: &str = "https://api.hosting.ionos.com/dns/v1";
+/// TTL used for records configured with the automatic TTL (`1`), matching
+/// the IONOS default
+const AUTO_TTL: u32 = 3600;
+/// Lowest TTL IONOS accepts
+const MIN_TTL: u32 = 60;
+
+#[derive(Deserialize)]
+struct IonosError {
+    #[serde(default)]
+    code: String,
+    #[serde(default)]
+    message: String,
+}
+
+#[derive(Deserialize)]
+struct IonosZone {
+    id: String,
+    name: String,
+}
+
+#[derive(Deserialize)]
+struct ZoneRecords {
+    #[serde(default)]
+    records: Vec<IonosRecord>,
+}
+
+#[derive(Deserialize)]
+struct IonosRecord {
+    id: String,
+    /// Fully qualified name
+    name: String,
+    #[serde(rename = "type")]
+    kind: String,
+    content: String,
+    ttl: u32,
+}
+
+impl IonosRecord {
+    fn into_record(self, dns_name: &str, kind: RecordType) -> Record {
+        Record {
+            id: Some(self.id),
+            name: dns_name.to_string(),
+            kind,
+            content: self.content,
+            ttl: self.ttl,
+            proxied: None,
+        }
+    }
+}
+
+/// `[providers.ionos]` section of the config file
+#[derive(Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct IonosConfig {
+    /// Public prefix of the API key from the developer portal
+    pub public_prefix: String,
+    pub secret: String,
+}
+
+impl fmt::Debug for IonosConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("IonosConfig")
+            .field("public_prefix", &self.public_prefix)
+            .field("secret", &"<redacted>")
+            .finish()
+    }
+}
+
+pub struct IonosProvider {
+    client: Client,
+    /// `prefix.secret`, as sent in the `X-API-Key` header
+    api_key: String,
+    base_url: String,
+}
+
+impl IonosProvider {
+    pub fn new(client: Client, config: &IonosConfig) -> Self {
+        Self {
+            client,
+            api_key: format!("{}.{}", config.public_prefix, config.secret),
+            base_url: API_BASE.to_string(),
+        }
+    }
+
+    /// Talk to a different API endpoint, e.g. a proxy or a mock server
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into().trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Send an authenticated request and parse the JSON response
+    async fn send<T: DeserializeOwned>(&self, request: RequestBuilder) -> Result<T, ProviderError> {
+        let resp = request.header("X-API-Key", &self.api_key).send().await?;
+        let status = resp.status();
+
+        if status == StatusCode::TOO_MANY_REQUESTS {
+            let retry_after = retry_after(resp.headers());
+            return Err(ProviderError::RateLimited { retry_after });
+        }
+
+        let body = resp.text().await?;
+        if !status.is_success() {
+            // Errors come as a list
+            let message = match serde_json::from_str::<Vec<IonosError>>(&body) {
+                Ok(errors) if !errors.is_empty() => errors
+                    .iter()
+                    .map(|e| format!("{} ({})", e.message, e.code))
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                _ => format!("HTTP {}", status.as_u16()),
+            };
+            return Err(match status {
+                StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => ProviderError::Auth(message),
+                _ => ProviderError::for_status(status, message),
+            });
+        }
+
+        // Record updates answer with an empty body
+        let body = if body.trim().is_empty() {
+            "null"
+        } else {
+            &body
+        };
+        serde_json::from_str(body)
+            .map_err(|e| ProviderError::InvalidResponse(format!("HTTP {}: {}", status.as_u16(), e)))
+    }
+}
+
+/// TTL the API stores for a record configured with `ttl`
+fn stored_ttl(ttl: u32) -> u32 {
+    match ttl {
+        1 => AUTO_TTL,
+        ttl => ttl.max(MIN_TTL),
+    }
+}
+
+#[async_trait]
+impl DnsProvider for IonosProvider {
+    fn name(&self) -> &str {
+        "ionos"
+    }
+
+    fn effective_ttl(&self, record: &Record) -> Option<u32> {
+        Some(stored_ttl(record.ttl))
+    }
+
+    /// Find the zone of the root domain of `dns_name`
+    async fn lookup_zone(&self, dns_name: &str) -> Result<Zone, ProviderError> {
+        let domain = extract_root_domain(dns_name);
+        let request = self.client.get(format!("{}/zones", self.base_url));
+        let zones: Vec<IonosZone> = self.send(request).await?;
+
+        zones
+            .into_iter()
+            .find(|z| z.name.eq_ignore_ascii_case(&domain))
+            .map(|z| Zone {
+                id: z.id,
+                name: z.name,
+            })
+            .ok_or(ProviderError::ZoneNotFound(domain))
+    }
+
+    async fn read_record(
+        &self,
+        zone: &Zone,
+        dns_name: &str,
+        kind: RecordType,
+    ) -> Result<Option<Record>, ProviderError> {
+        // The zone is returned with its records filtered to the one we want
+        let request = self
+            .client
+            .get(format!("{}/zones/{}", self.base_url, zone.id))
+            .query(&[("recordName", dns_name), ("recordType", kind.as_str())]);
+        let resp: ZoneRecords = self.send(request).await?;
+
+        Ok(resp
+            .records
+            .into_iter()
+            .find(|r| r.kind == kind.as_str() && r.name.eq_ignore_ascii_case(dns_name))
+            .map(|r| r.into_record(dns_name, kind)))
+    }
+
+    async fn upsert_record(&self, zone: &Zone, record: &Record) -> Result<Record, ProviderError> {
+        let ttl = stored_ttl(record.ttl);
+        let records_url = format!("{}/zones/{}/records", self.base_url, zone.id);
+
+        match &record.id {
+            Some(id) => {
+                let body = serde_json::json!({
+                    "content": record.content,
+                    "ttl": ttl,
+                    "disabled": false
+                });
+                let request = self
+                    .client
+                    .put(format!("{}/{}", records_url, id))
+                    .json(&body);
+                self.send::<serde_json::Value>(request).await?;
+                Ok(Record {
+                    ttl,
+                    ..record.clone()
+                })
+            }
+            None => {
+                let body = serde_json::json!([{
+                    "name": record.name,
+                    "type": record.kind.as_str(),
+                    "content": record.content,
+                    "ttl": ttl,
+                    "disabled": false
+                }]);
+                let request = self.client.post(records_url).json(&body);
+                let created: Vec<IonosRecord> = self.send(request).await?;
+                created
+                    .into_iter()
+                    .next()
+                    .map(|r| r.into_record(&record.name, record.kind))
+                    .ok_or_else(|| {
+                        ProviderError::InvalidResponse("no record in the creation answer".into())
+                    })
+            }
+        }
+    }
+}
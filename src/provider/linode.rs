@@ -0,0 +1,243 @@
+//! Linode (Akamai) DNS Manager provider using the v4 API.
+
+use super::{
+    DnsProvider, Record, RecordType, Zone, extract_root_domain, relative_name, retry_after,
+};
+use crate::error::ProviderError;
+use async_trait::async_trait;
+use reqwest::{Client, RequestBuilder, StatusCode};
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use std::fmt;
+
+const API_BASE: &str = "https://api.linode.com/v4";
+/// TTL sent for records configured with the automatic TTL (`1`), meaning
+/// the domain default
+const AUTO_TTL: u32 = 0;
+/// TTLs Linode supports; others are rounded up to the next one
+const SUPPORTED_TTLS: [u32; 14] = [
+    30, 120, 300, 3600, 7200, 14400, 28800, 57600, 86400, 172800, 345600, 604800, 1209600, 2419200,
+];
+
+#[derive(Deserialize)]
+struct LinodeErrors {
+    #[serde(default)]
+    errors: Vec<LinodeError>,
+}
+
+#[derive(Deserialize)]
+struct LinodeError {
+    reason: String,
+}
+
+/// One page of a list endpoint
+#[derive(Deserialize)]
+struct Page<T> {
+    data: Vec<T>,
+    page: u32,
+    pages: u32,
+}
+
+#[derive(Deserialize)]
+struct LinodeDomain {
+    id: u64,
+    domain: String,
+}
+
+#[derive(Deserialize)]
+struct LinodeRecord {
+    id: u64,
+    #[serde(rename = "type")]
+    kind: String,
+    /// Name relative to the domain, empty for the apex
+    name: String,
+    target: String,
+    ttl_sec: u32,
+}
+
+impl LinodeRecord {
+    fn into_record(self, dns_name: &str, kind: RecordType) -> Record {
+        Record {
+            id: Some(self.id.to_string()),
+            name: dns_name.to_string(),
+            kind,
+            content: self.target,
+            ttl: match self.ttl_sec {
+                AUTO_TTL => 1,
+                ttl => ttl,
+            },
+            proxied: None,
+        }
+    }
+}
+
+/// `[providers.linode]` section of the config file
+#[derive(Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LinodeConfig {
+    /// Personal access token with read/write access to Domains
+    pub api_token: String,
+}
+
+impl fmt::Debug for LinodeConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LinodeConfig")
+            .field("api_token", &"<redacted>")
+            .finish()
+    }
+}
+
+pub struct LinodeProvider {
+    client: Client,
+    api_token: String,
+    base_url: String,
+}
+
+impl LinodeProvider {
+    pub fn new(client: Client, api_token: impl Into<String>) -> Self {
+        Self {
+            client,
+            api_token: api_token.into(),
+            base_url: API_BASE.to_string(),
+        }
+    }
+
+    /// Talk to a different API endpoint, e.g. a proxy or a mock server
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into().trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Send an authenticated request and parse the JSON response
+    async fn send<T: DeserializeOwned>(&self, request: RequestBuilder) -> Result<T, ProviderError> {
+        let resp = request.bearer_auth(&self.api_token).send().await?;
+        let status = resp.status();
+
+        if status == StatusCode::TOO_MANY_REQUESTS {
+            let retry_after = retry_after(resp.headers());
+            return Err(ProviderError::RateLimited { retry_after });
+        }
+
+        let body = resp.text().await?;
+        if !status.is_success() {
+            let message = serde_json::from_str::<LinodeErrors>(&body)
+                .ok()
+                .filter(|e| !e.errors.is_empty())
+                .map(|e| {
+                    e.errors
+                        .into_iter()
+                        .map(|e| e.reason)
+                        .collect::<Vec<_>>()
+                        .join("; ")
+                })
+                .unwrap_or_else(|| format!("HTTP {}", status.as_u16()));
+            return Err(match status {
+                StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => ProviderError::Auth(message),
+                _ => ProviderError::for_status(status, message),
+            });
+        }
+
+        serde_json::from_str(&body)
+            .map_err(|e| ProviderError::InvalidResponse(format!("HTTP {}: {}", status.as_u16(), e)))
+    }
+}
+
+/// TTL the API stores for a record configured with `ttl`, `1` for the
+/// domain default
+fn stored_ttl(ttl: u32) -> u32 {
+    if ttl == 1 {
+        return 1;
+    }
+    SUPPORTED_TTLS
+        .into_iter()
+        .find(|supported| *supported >= ttl)
+        .unwrap_or(SUPPORTED_TTLS[SUPPORTED_TTLS.len() - 1])
+}
+
+#[async_trait]
+impl DnsProvider for LinodeProvider {
+    fn name(&self) -> &str {
+        "linode"
+    }
+
+    fn effective_ttl(&self, record: &Record) -> Option<u32> {
+        Some(stored_ttl(record.ttl))
+    }
+
+    /// Find the domain of the root domain of `dns_name`
+    async fn lookup_zone(&self, dns_name: &str) -> Result<Zone, ProviderError> {
+        let domain = extract_root_domain(dns_name);
+        let filter = serde_json::json!({ "domain": domain }).to_string();
+        let mut page = 1;
+        loop {
+            let request = self
+                .client
+                .get(format!("{}/domains", self.base_url))
+                .header("X-Filter", &filter)
+                .query(&[("page", page)]);
+            let resp: Page<LinodeDomain> = self.send(request).await?;
+
+            if let Some(found) = resp
+                .data
+                .into_iter()
+                .find(|d| d.domain.eq_ignore_ascii_case(&domain))
+            {
+                return Ok(Zone {
+                    id: found.id.to_string(),
+                    name: found.domain,
+                });
+            }
+            if resp.page >= resp.pages {
+                return Err(ProviderError::ZoneNotFound(domain));
+            }
+            page += 1;
+        }
+    }
+
+    async fn read_record(
+        &self,
+        zone: &Zone,
+        dns_name: &str,
+        kind: RecordType,
+    ) -> Result<Option<Record>, ProviderError> {
+        let relative = relative_name(dns_name, &zone.name, "");
+        let mut page = 1;
+        loop {
+            let request = self
+                .client
+                .get(format!("{}/domains/{}/records", self.base_url, zone.id))
+                .query(&[("page", page)]);
+            let resp: Page<LinodeRecord> = self.send(request).await?;
+
+            if let Some(record) = resp
+                .data
+                .into_iter()
+                .find(|r| r.kind == kind.as_str() && r.name.eq_ignore_ascii_case(&relative))
+            {
+                return Ok(Some(record.into_record(dns_name, kind)));
+            }
+            if resp.page >= resp.pages {
+                return Ok(None);
+            }
+            page += 1;
+        }
+    }
+
+    async fn upsert_record(&self, zone: &Zone, record: &Record) -> Result<Record, ProviderError> {
+        let body = serde_json::json!({
+            "type": record.kind.as_str(),
+            "name": relative_name(&record.name, &zone.name, ""),
+            "target": record.content,
+            "ttl_sec": if record.ttl == 1 { AUTO_TTL } else { record.ttl }
+        });
+
+        let records_url = format!("{}/domains/{}/records", self.base_url, zone.id);
+        let request = match &record.id {
+            Some(id) => self.client.put(format!("{}/{}", records_url, id)),
+            None => self.client.post(records_url),
+        };
+
+        let stored: LinodeRecord = self.send(request.json(&body)).await?;
+        Ok(stored.into_record(&record.name, record.kind))
+    }
+}
@@ -0,0 +1,200 @@
+//! Loopia provider using its DynDNS update endpoint.
+//!
+//! Updates are authenticated with the Loopia account login, or with the
+//! separate credentials Loopia can issue for a single subdomain. The endpoint
+//! speaks the dyndns2 answer codes and can't read records back, so the agent
+//! updates them once at startup.
+
+use super::{DnsProvider, Record, RecordType, Zone, retry_after};
+use crate::error::{ConfigError, ProviderError};
+use async_trait::async_trait;
+use reqwest::header::USER_AGENT;
+use reqwest::{Client, StatusCode};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fmt;
+
+const API_BASE: &str = "https://dyndns.loopia.se";
+const AGENT: &str = concat!("ddns-agent/", env!("CARGO_PKG_VERSION"));
+
+/// Login used for DynDNS updates
+#[derive(Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LoopiaCredentials {
+    pub username: String,
+    pub password: String,
+}
+
+impl fmt::Debug for LoopiaCredentials {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LoopiaCredentials")
+            .field("username", &self.username)
+            .field("password", &"<redacted>")
+            .finish()
+    }
+}
+
+/// `[providers.loopia]` section of the config file
+#[derive(Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LoopiaConfig {
+    /// Account login, used for hostnames without their own credentials
+    #[serde(default)]
+    pub username: Option<String>,
+    #[serde(default)]
+    pub password: Option<String>,
+    /// Credentials of single subdomains by hostname
+    #[serde(default)]
+    pub hosts: BTreeMap<String, LoopiaCredentials>,
+}
+
+impl fmt::Debug for LoopiaConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LoopiaConfig")
+            .field("username", &self.username)
+            .field("password", &self.password.as_ref().map(|_| "<redacted>"))
+            .field("hosts", &self.hosts)
+            .finish()
+    }
+}
+
+pub struct LoopiaProvider {
+    client: Client,
+    account: Option<LoopiaCredentials>,
+    /// Subdomain credentials by lowercase hostname
+    hosts: BTreeMap<String, LoopiaCredentials>,
+    base_url: String,
+}
+
+impl LoopiaProvider {
+    pub fn new(client: Client, config: &LoopiaConfig) -> Result<Self, ConfigError> {
+        let account = match (&config.username, &config.password) {
+            (Some(username), Some(password)) => Some(LoopiaCredentials {
+                username: username.clone(),
+                password: password.clone(),
+            }),
+            (None, None) => None,
+            _ => {
+                return Err(ConfigError::Invalid(
+                    "[providers.loopia] needs both `username` and `password`, or neither".into(),
+                ));
+            }
+        };
+        Ok(Self {
+            client,
+            account,
+            hosts: config
+                .hosts
+                .iter()
+                .map(|(name, credentials)| (name.to_lowercase(), credentials.clone()))
+                .collect(),
+            base_url: API_BASE.to_string(),
+        })
+    }
+
+    /// Talk to a different API endpoint, e.g. a proxy or a mock server
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into().trim_end_matches('/').to_string();
+        self
+    }
+
+    fn credentials(&self, dns_name: &str) -> Option<&LoopiaCredentials> {
+        self.hosts
+            .get(&dns_name.to_lowercase())
+            .or(self.account.as_ref())
+    }
+}
+
+#[async_trait]
+impl DnsProvider for LoopiaProvider {
+    fn name(&self) -> &str {
+        "loopia"
+    }
+
+    fn effective_ttl(&self, _record: &Record) -> Option<u32> {
+        None
+    }
+
+    /// Hostnames are updated as a whole, so each one is its own zone.
+    /// Fails for hostnames without credentials.
+    async fn lookup_zone(&self, dns_name: &str) -> Result<Zone, ProviderError> {
+        if self.credentials(dns_name).is_none() {
+            return Err(ProviderError::ZoneNotFound(format!(
+                "{} (no credentials configured)",
+                dns_name
+            )));
+        }
+        Ok(Zone {
+            id: dns_name.to_string(),
+            name: dns_name.to_string(),
+        })
+    }
+
+    /// The record with an empty (unknown) value, as it can't be read
+    async fn read_record(
+        &self,
+        _zone: &Zone,
+        dns_name: &str,
+        kind: RecordType,
+    ) -> Result<Option<Record>, ProviderError> {
+        Ok(Some(Record {
+            id: None,
+            name: dns_name.to_string(),
+            kind,
+            content: String::new(),
+            ttl: 1,
+            proxied: None,
+        }))
+    }
+
+    /// Point the hostname at `record.content`
+    async fn upsert_record(&self, _zone: &Zone, record: &Record) -> Result<Record, ProviderError> {
+        let credentials = self
+            .credentials(&record.name)
+            .ok_or_else(|| ProviderError::ZoneNotFound(record.name.clone()))?;
+        let resp = self
+            .client
+            .get(format!("{}/", self.base_url))
+            .query(&[
+                ("system", "custom"),
+                ("hostname", record.name.as_str()),
+                ("myip", record.content.as_str()),
+            ])
+            .basic_auth(&credentials.username, Some(&credentials.password))
+            .header(USER_AGENT, AGENT)
+            .send()
+            .await?;
+        let status = resp.status();
+
+        if status == StatusCode::TOO_MANY_REQUESTS {
+            let retry_after = retry_after(resp.headers());
+            return Err(ProviderError::RateLimited { retry_after });
+        }
+        let body = resp.text().await?;
+        let answer = body.lines().next().unwrap_or_default().trim();
+        let code = answer.split_whitespace().next().unwrap_or_default();
+
+        match code {
+            "good" | "nochg" => Ok(record.clone()),
+            "badauth" => Err(ProviderError::Auth("badauth".into())),
+            "nohost" => Err(ProviderError::RecordNotFound {
+                name: record.name.clone(),
+                kind: record.kind,
+            }),
+            "notfqdn" | "numhost" | "abuse" | "badagent" | "dnserr" | "911" => {
+                Err(ProviderError::Api(answer.to_string()))
+            }
+            _ if status == StatusCode::UNAUTHORIZED || status == StatusCode::FORBIDDEN => {
+                Err(ProviderError::Auth(format!("HTTP {}", status.as_u16())))
+            }
+            _ if !status.is_success() => Err(ProviderError::for_status(
+                status,
+                format!("HTTP {}", status.as_u16()),
+            )),
+            _ => Err(ProviderError::InvalidResponse(format!(
+                "unexpected answer {:?}",
+                answer
+            ))),
+        }
+    }
+}
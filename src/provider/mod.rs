@@ -0,0 +1,776 @@
+//! DNS provider abstraction.
+//!
+//! A provider knows how to find the zone a hostname belongs to, read the
+//! current state of a record in that zone, and write a new value back.
+
+use crate::config::ProvidersConfig;
+use crate::error::{ConfigError, ProviderError};
+use crate::ip::IpVersion;
+use async_trait::async_trait;
+use reqwest::Client;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+
+#[cfg(feature = "azure")]
+pub mod azure;
+#[cfg(feature = "cloudflare")]
+pub mod cloudflare;
+#[cfg(feature = "cloudns")]
+pub mod cloudns;
+#[cfg(feature = "desec")]
+pub mod desec;
+#[cfg(feature = "digitalocean")]
+pub mod digitalocean;
+#[cfg(feature = "dnsimple")]
+pub mod dnsimple;
+#[cfg(feature = "dnsomatic")]
+pub mod dnsomatic;
+#[cfg(feature = "duckdns")]
+pub mod duckdns;
+#[cfg(feature = "dyndns2")]
+pub mod dyndns2;
+#[cfg(feature = "dynu")]
+pub mod dynu;
+#[cfg(feature = "exec")]
+pub mod exec;
+#[cfg(feature = "freedns")]
+pub mod freedns;
+#[cfg(feature = "gandi")]
+pub mod gandi;
+#[cfg(feature = "gcloud")]
+pub mod gcloud;
+#[cfg(feature = "godaddy")]
+pub mod godaddy;
+#[cfg(feature = "hetzner")]
+pub mod hetzner;
+#[cfg(feature = "hurricane")]
+pub mod hurricane;
+#[cfg(feature = "infomaniak")]
+pub mod infomaniak;
+#[cfg(feature = "ionos")]
+pub mod ionos;
+#[cfg(feature = "linode")]
+pub mod linode;
+#[cfg(feature = "loopia")]
+pub mod loopia;
+#[cfg(feature = "namecheap")]
+pub mod namecheap;
+#[cfg(feature = "namecom")]
+pub mod namecom;
+#[cfg(feature = "netlify")]
+pub mod netlify;
+#[cfg(feature = "njalla")]
+pub mod njalla;
+#[cfg(feature = "ovh")]
+pub mod ovh;
+#[cfg(feature = "pihole")]
+pub mod pihole;
+#[cfg(feature = "porkbun")]
+pub mod porkbun;
+#[cfg(feature = "powerdns")]
+pub mod powerdns;
+#[cfg(feature = "rfc2136")]
+pub mod rfc2136;
+#[cfg(feature = "route53")]
+pub mod route53;
+#[cfg(feature = "scaleway")]
+pub mod scaleway;
+#[cfg(feature = "script")]
+pub mod script;
+#[cfg(feature = "technitium")]
+pub mod technitium;
+#[cfg(feature = "tunnelbroker")]
+pub mod tunnelbroker;
+#[cfg(feature = "vultr")]
+pub mod vultr;
+#[cfg(feature = "webhook")]
+pub mod webhook;
+
+mod rate_limit;
+mod suffix;
+#[cfg(any(feature = "cloudflare", feature = "route53"))]
+mod timestamp;
+
+pub use rate_limit::retry_after;
+
+#[cfg(feature = "azure")]
+pub use azure::{AzureConfig, AzureProvider};
+#[cfg(feature = "cloudflare")]
+pub use cloudflare::{
+    CloudflareAccount, CloudflareConfig, CloudflareLoadBalancer, CloudflarePoolProvider,
+    CloudflareProvider,
+};
+#[cfg(feature = "cloudns")]
+pub use cloudns::{ClouDnsConfig, ClouDnsProvider};
+#[cfg(feature = "desec")]
+pub use desec::{DesecConfig, DesecProvider};
+#[cfg(feature = "digitalocean")]
+pub use digitalocean::{DigitalOceanConfig, DigitalOceanProvider};
+#[cfg(feature = "dnsimple")]
+pub use dnsimple::{DnsimpleConfig, DnsimpleProvider};
+#[cfg(feature = "dnsomatic")]
+pub use dnsomatic::{DnsOMaticConfig, DnsOMaticProvider};
+#[cfg(feature = "duckdns")]
+pub use duckdns::{DuckDnsConfig, DuckDnsProvider};
+#[cfg(feature = "dyndns2")]
+pub use dyndns2::{DynDns2Config, DynDns2Provider};
+#[cfg(feature = "dynu")]
+pub use dynu::{DynuConfig, DynuProvider};
+#[cfg(feature = "exec")]
+pub use exec::{ExecConfig, ExecProvider};
+#[cfg(feature = "freedns")]
+pub use freedns::{FreeDnsConfig, FreeDnsProvider};
+#[cfg(feature = "gandi")]
+pub use gandi::{GandiConfig, GandiProvider};
+#[cfg(feature = "gcloud")]
+pub use gcloud::{GcloudConfig, GcloudProvider};
+#[cfg(feature = "godaddy")]
+pub use godaddy::{GoDaddyConfig, GoDaddyProvider};
+#[cfg(feature = "hetzner")]
+pub use hetzner::{HetznerConfig, HetznerProvider};
+#[cfg(feature = "hurricane")]
+pub use hurricane::{HurricaneConfig, HurricaneProvider};
+#[cfg(feature = "infomaniak")]
+pub use infomaniak::{InfomaniakConfig, InfomaniakProvider};
+#[cfg(feature = "ionos")]
+pub use ionos::{IonosConfig, IonosProvider};
+#[cfg(feature = "linode")]
+pub use linode::{LinodeConfig, LinodeProvider};
+#[cfg(feature = "loopia")]
+pub use loopia::{LoopiaConfig, LoopiaProvider};
+#[cfg(feature = "namecheap")]
+pub use namecheap::{NamecheapConfig, NamecheapProvider};
+#[cfg(feature = "namecom")]
+pub use namecom::{NameComConfig, NameComProvider};
+#[cfg(feature = "netlify")]
+pub use netlify::{NetlifyConfig, NetlifyProvider};
+#[cfg(feature = "njalla")]
+pub use njalla::{NjallaConfig, NjallaProvider};
+#[cfg(feature = "ovh")]
+pub use ovh::{OvhConfig, OvhProvider};
+#[cfg(feature = "pihole")]
+pub use pihole::{PiholeConfig, PiholeProvider};
+#[cfg(feature = "porkbun")]
+pub use porkbun::{PorkbunConfig, PorkbunProvider};
+#[cfg(feature = "powerdns")]
+pub use powerdns::{PowerDnsConfig, PowerDnsProvider};
+#[cfg(feature = "rfc2136")]
+pub use rfc2136::{Rfc2136Config, Rfc2136Provider};
+#[cfg(feature = "route53")]
+pub use route53::{Route53Config, Route53Provider, Route53Routing};
+#[cfg(feature = "scaleway")]
+pub use scaleway::{ScalewayConfig, ScalewayProvider};
+#[cfg(feature = "script")]
+pub use script::{ScriptConfig, ScriptProvider};
+#[cfg(feature = "technitium")]
+pub use technitium::{TechnitiumConfig, TechnitiumProvider};
+#[cfg(feature = "tunnelbroker")]
+pub use tunnelbroker::{TunnelbrokerConfig, TunnelbrokerProvider};
+#[cfg(feature = "vultr")]
+pub use vultr::{VultrConfig, VultrProvider};
+#[cfg(feature = "webhook")]
+pub use webhook::{WebhookConfig, WebhookProvider};
+
+/// DNS record types managed by the agent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RecordType {
+    A,
+    Aaaa,
+}
+
+impl RecordType {
+    /// Address record type for the given IP family
+    pub fn for_version(version: IpVersion) -> Self {
+        match version {
+            IpVersion::V4 => RecordType::A,
+            IpVersion::V6 => RecordType::Aaaa,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RecordType::A => "A",
+            RecordType::Aaaa => "AAAA",
+        }
+    }
+}
+
+impl fmt::Display for RecordType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A DNS zone as identified by the provider.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Zone {
+    pub id: String,
+    pub name: String,
+}
+
+/// A single DNS record.
+///
+/// `id` is `None` for records that don't exist at the provider yet.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Record {
+    pub id: Option<String>,
+    pub name: String,
+    pub kind: RecordType,
+    pub content: String,
+    /// TTL in seconds, 1 means "automatic" where the provider supports it
+    pub ttl: u32,
+    /// Provider-specific proxy flag (Cloudflare's orange cloud)
+    pub proxied: Option<bool>,
+}
+
+/// A service record pointing clients at a host and port, e.g.
+/// `_minecraft._tcp.example.com`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SrvRecord {
+    /// Full name, service and protocol labels included
+    pub name: String,
+    pub priority: u16,
+    pub weight: u16,
+    pub port: u16,
+    /// Host name offering the service
+    pub target: String,
+    /// TTL in seconds, 1 means "automatic" where the provider supports it
+    pub ttl: u32,
+}
+
+impl SrvRecord {
+    /// Whether both point at the same target and port with the same
+    /// priority and weight, whatever their TTLs
+    pub fn same_target(&self, other: &SrvRecord) -> bool {
+        (self.priority, self.weight, self.port) == (other.priority, other.weight, other.port)
+            && self
+                .target
+                .trim_end_matches('.')
+                .eq_ignore_ascii_case(other.target.trim_end_matches('.'))
+    }
+}
+
+#[async_trait]
+pub trait DnsProvider: Send + Sync {
+    /// Short name of the provider used in log messages
+    fn name(&self) -> &str;
+
+    /// How often records have to be written again even if the IP didn't
+    /// change, for providers that treat records nobody updates as stale
+    fn refresh_interval(&self) -> Option<Duration> {
+        None
+    }
+
+    /// TTL the provider stores for `record`, e.g. its default for the
+    /// automatic TTL (`1`) or its minimum for lower ones; `None` if the
+    /// agent can't set TTLs there
+    fn effective_ttl(&self, record: &Record) -> Option<u32> {
+        Some(record.ttl)
+    }
+
+    /// Whether the records written here are what the nameservers of their
+    /// zone answer, so resolvers can tell whether they are up to date;
+    /// `false` for providers like load balancers, where the zone is another
+    /// object and the name resolves in a way of its own
+    fn publishes_records(&self) -> bool {
+        true
+    }
+
+    /// Find the zone that contains `dns_name`
+    async fn lookup_zone(&self, dns_name: &str) -> Result<Zone, ProviderError>;
+
+    /// Read the record of the given type for `dns_name`, if it exists
+    async fn read_record(
+        &self,
+        zone: &Zone,
+        dns_name: &str,
+        kind: RecordType,
+    ) -> Result<Option<Record>, ProviderError>;
+
+    /// Create or update a record, returning the record as stored by the provider
+    async fn upsert_record(&self, zone: &Zone, record: &Record) -> Result<Record, ProviderError>;
+
+    /// Update several existing records of `zone` in one request, returning
+    /// them as stored in the same order. Either all of them are written or
+    /// none.
+    async fn update_records(
+        &self,
+        _zone: &Zone,
+        _records: &[Record],
+    ) -> Result<Vec<Record>, ProviderError> {
+        Err(ProviderError::Unsupported {
+            provider: self.name().to_string(),
+            action: "update records in batches",
+        })
+    }
+
+    /// Every address record of `dns_name` of type `kind`, for records
+    /// shared by several agents
+    async fn read_record_set(
+        &self,
+        _zone: &Zone,
+        _dns_name: &str,
+        _kind: RecordType,
+    ) -> Result<Vec<Record>, ProviderError> {
+        Err(ProviderError::Unsupported {
+            provider: self.name().to_string(),
+            action: "share record sets",
+        })
+    }
+
+    /// Add `record` to the record set of its name, keeping the other
+    /// addresses
+    async fn add_address(&self, _zone: &Zone, _record: &Record) -> Result<Record, ProviderError> {
+        Err(ProviderError::Unsupported {
+            provider: self.name().to_string(),
+            action: "share record sets",
+        })
+    }
+
+    /// Remove `record`, as read or written before, from the record set of
+    /// its name
+    async fn delete_address(&self, _zone: &Zone, _record: &Record) -> Result<(), ProviderError> {
+        Err(ProviderError::Unsupported {
+            provider: self.name().to_string(),
+            action: "delete records",
+        })
+    }
+
+    /// Values of the TXT records of `dns_name`
+    async fn read_txt(&self, _zone: &Zone, _dns_name: &str) -> Result<Vec<String>, ProviderError> {
+        Err(ProviderError::Unsupported {
+            provider: self.name().to_string(),
+            action: "manage TXT records",
+        })
+    }
+
+    /// Add a TXT record with `value` next to the existing ones of
+    /// `dns_name`, e.g. an ACME DNS-01 challenge
+    async fn add_txt(
+        &self,
+        _zone: &Zone,
+        _dns_name: &str,
+        _value: &str,
+    ) -> Result<(), ProviderError> {
+        Err(ProviderError::Unsupported {
+            provider: self.name().to_string(),
+            action: "manage TXT records",
+        })
+    }
+
+    /// Delete the TXT records of `dns_name` with `value`, if there are any
+    async fn delete_txt(
+        &self,
+        _zone: &Zone,
+        _dns_name: &str,
+        _value: &str,
+    ) -> Result<(), ProviderError> {
+        Err(ProviderError::Unsupported {
+            provider: self.name().to_string(),
+            action: "manage TXT records",
+        })
+    }
+
+    /// Read the SRV record of `dns_name`, if it exists
+    async fn read_srv(
+        &self,
+        _zone: &Zone,
+        _dns_name: &str,
+    ) -> Result<Option<SrvRecord>, ProviderError> {
+        Err(ProviderError::Unsupported {
+            provider: self.name().to_string(),
+            action: "manage SRV records",
+        })
+    }
+
+    /// Create the SRV record of `record.name` or replace the existing one
+    async fn upsert_srv(&self, _zone: &Zone, _record: &SrvRecord) -> Result<(), ProviderError> {
+        Err(ProviderError::Unsupported {
+            provider: self.name().to_string(),
+            action: "manage SRV records",
+        })
+    }
+}
+
+/// TTL of the TXT records added through [`DnsProvider::add_txt`], short so
+/// a retried challenge isn't stuck behind a cached old value
+#[cfg(any(feature = "cloudflare", feature = "rfc2136"))]
+const TXT_TTL: u32 = 60;
+
+/// Extract root domain from DNS name (e.g., "sub.example.com" -> "example.com",
+/// "home.example.co.uk" -> "example.co.uk"), using the Public Suffix List
+pub fn extract_root_domain(dns_name: &str) -> String {
+    suffix::registrable_domain(dns_name)
+}
+
+/// `dns_name` in the ASCII form used by DNS and provider APIs: lowercase,
+/// with internationalized labels in punycode (`bücher.example` ->
+/// `xn--bcher-kva.example`). `None` if it isn't a valid IDN.
+pub fn ascii_name(dns_name: &str) -> Option<String> {
+    if dns_name.is_ascii() {
+        return Some(dns_name.to_ascii_lowercase());
+    }
+    idna::domain_to_ascii(dns_name).ok()
+}
+
+/// Name covered by the wildcard `dns_name` (`*.lab.example.com` ->
+/// `lab.example.com`), `None` if it isn't one
+pub fn wildcard_base(dns_name: &str) -> Option<&str> {
+    dns_name.strip_prefix("*.")
+}
+
+/// Name of `dns_name` relative to `zone` as provider APIs expect it
+/// (`home.example.com` in `example.com` -> `home`), with `apex` standing for
+/// the zone itself. Case doesn't matter, and names outside `zone` are kept.
+pub fn relative_name(dns_name: &str, zone: &str, apex: &str) -> String {
+    if dns_name.eq_ignore_ascii_case(zone) {
+        return apex.to_string();
+    }
+    match dns_name.len().checked_sub(zone.len() + 1) {
+        Some(dot)
+            if dns_name.as_bytes()[dot] == b'.'
+                && dns_name[dot + 1..].eq_ignore_ascii_case(zone) =>
+        {
+            dns_name[..dot].to_string()
+        }
+        _ => dns_name.to_string(),
+    }
+}
+
+/// Instantiate every provider configured in `config`, keyed by the name
+/// records use to refer to them
+#[allow(unused_mut, unused_variables)] // every provider is an optional feature
+pub fn from_config(
+    config: &ProvidersConfig,
+    client: &Client,
+) -> Result<BTreeMap<String, Arc<dyn DnsProvider>>, ConfigError> {
+    let mut providers: BTreeMap<String, Arc<dyn DnsProvider>> = BTreeMap::new();
+    #[cfg(feature = "azure")]
+    if let Some(azure) = &config.azure {
+        providers.insert(
+            "azure".to_string(),
+            Arc::new(AzureProvider::new(client.clone(), azure)?),
+        );
+    }
+    #[cfg(feature = "cloudflare")]
+    if let Some(cloudflare) = &config.cloudflare {
+        providers.insert(
+            "cloudflare".to_string(),
+            Arc::new(
+                CloudflareProvider::new(client.clone(), &cloudflare.api_token)
+                    .with_comment(cloudflare.comment.clone())
+                    .with_tags(cloudflare.tags.clone()),
+            ),
+        );
+        for (name, account) in &cloudflare.accounts {
+            providers.insert(
+                format!("cloudflare.{}", name),
+                Arc::new(
+                    CloudflareProvider::new(client.clone(), &account.api_token)
+                        .with_account(name)
+                        .with_comment(cloudflare.comment.clone())
+                        .with_tags(cloudflare.tags.clone()),
+                ),
+            );
+        }
+        if let Some(pool) = &cloudflare.load_balancer {
+            providers.insert(
+                "cloudflare.load_balancer".to_string(),
+                Arc::new(CloudflarePoolProvider::new(
+                    client.clone(),
+                    &cloudflare.api_token,
+                    pool,
+                )),
+            );
+        }
+    }
+    #[cfg(feature = "cloudns")]
+    if let Some(cloudns) = &config.cloudns {
+        providers.insert(
+            "cloudns".to_string(),
+            Arc::new(ClouDnsProvider::new(client.clone(), cloudns)?),
+        );
+    }
+    #[cfg(feature = "desec")]
+    if let Some(desec) = &config.desec {
+        providers.insert(
+            "desec".to_string(),
+            Arc::new(DesecProvider::new(client.clone(), &desec.token)),
+        );
+    }
+    #[cfg(feature = "digitalocean")]
+    if let Some(digitalocean) = &config.digitalocean {
+        providers.insert(
+            "digitalocean".to_string(),
+            Arc::new(DigitalOceanProvider::new(
+                client.clone(),
+                &digitalocean.api_token,
+            )),
+        );
+    }
+    #[cfg(feature = "dnsimple")]
+    if let Some(dnsimple) = &config.dnsimple {
+        providers.insert(
+            "dnsimple".to_string(),
+            Arc::new(DnsimpleProvider::new(client.clone(), dnsimple)),
+        );
+    }
+    #[cfg(feature = "dnsomatic")]
+    if let Some(dnsomatic) = &config.dnsomatic {
+        providers.insert(
+            "dnsomatic".to_string(),
+            Arc::new(DnsOMaticProvider::new(client.clone(), dnsomatic)),
+        );
+    }
+    #[cfg(feature = "duckdns")]
+    if let Some(duckdns) = &config.duckdns {
+        providers.insert(
+            "duckdns".to_string(),
+            Arc::new(DuckDnsProvider::new(client.clone(), duckdns)),
+        );
+    }
+    #[cfg(feature = "dyndns2")]
+    if let Some(dyndns2) = &config.dyndns2 {
+        providers.insert(
+            "dyndns2".to_string(),
+            Arc::new(DynDns2Provider::new(client.clone(), dyndns2)),
+        );
+    }
+    #[cfg(feature = "dynu")]
+    if let Some(dynu) = &config.dynu {
+        providers.insert(
+            "dynu".to_string(),
+            Arc::new(DynuProvider::new(client.clone(), dynu)),
+        );
+    }
+    #[cfg(feature = "exec")]
+    if let Some(exec) = &config.exec {
+        providers.insert("exec".to_string(), Arc::new(ExecProvider::new(exec)));
+    }
+    #[cfg(feature = "freedns")]
+    if let Some(freedns) = &config.freedns {
+        providers.insert(
+            "freedns".to_string(),
+            Arc::new(FreeDnsProvider::new(client.clone(), freedns)?),
+        );
+    }
+    #[cfg(feature = "gandi")]
+    if let Some(gandi) = &config.gandi {
+        providers.insert(
+            "gandi".to_string(),
+            Arc::new(GandiProvider::new(client.clone(), &gandi.api_token)),
+        );
+    }
+    #[cfg(feature = "gcloud")]
+    if let Some(gcloud) = &config.gcloud {
+        providers.insert(
+            "gcloud".to_string(),
+            Arc::new(GcloudProvider::new(client.clone(), gcloud)?),
+        );
+    }
+    #[cfg(feature = "godaddy")]
+    if let Some(godaddy) = &config.godaddy {
+        providers.insert(
+            "godaddy".to_string(),
+            Arc::new(GoDaddyProvider::new(client.clone(), godaddy)),
+        );
+    }
+    #[cfg(feature = "hetzner")]
+    if let Some(hetzner) = &config.hetzner {
+        providers.insert(
+            "hetzner".to_string(),
+            Arc::new(HetznerProvider::new(client.clone(), &hetzner.api_token)),
+        );
+    }
+    #[cfg(feature = "hurricane")]
+    if let Some(hurricane) = &config.hurricane {
+        providers.insert(
+            "hurricane".to_string(),
+            Arc::new(HurricaneProvider::new(client.clone(), hurricane)),
+        );
+    }
+    #[cfg(feature = "infomaniak")]
+    if let Some(infomaniak) = &config.infomaniak {
+        providers.insert(
+            "infomaniak".to_string(),
+            Arc::new(InfomaniakProvider::new(
+                client.clone(),
+                &infomaniak.api_token,
+            )),
+        );
+    }
+    #[cfg(feature = "ionos")]
+    if let Some(ionos) = &config.ionos {
+        providers.insert(
+            "ionos".to_string(),
+            Arc::new(IonosProvider::new(client.clone(), ionos)),
+        );
+    }
+    #[cfg(feature = "linode")]
+    if let Some(linode) = &config.linode {
+        providers.insert(
+            "linode".to_string(),
+            Arc::new(LinodeProvider::new(client.clone(), &linode.api_token)),
+        );
+    }
+    #[cfg(feature = "loopia")]
+    if let Some(loopia) = &config.loopia {
+        providers.insert(
+            "loopia".to_string(),
+            Arc::new(LoopiaProvider::new(client.clone(), loopia)?),
+        );
+    }
+    #[cfg(feature = "namecheap")]
+    if let Some(namecheap) = &config.namecheap {
+        providers.insert(
+            "namecheap".to_string(),
+            Arc::new(NamecheapProvider::new(client.clone(), namecheap)),
+        );
+    }
+    #[cfg(feature = "namecom")]
+    if let Some(namecom) = &config.namecom {
+        providers.insert(
+            "namecom".to_string(),
+            Arc::new(NameComProvider::new(client.clone(), namecom)),
+        );
+    }
+    #[cfg(feature = "netlify")]
+    if let Some(netlify) = &config.netlify {
+        providers.insert(
+            "netlify".to_string(),
+            Arc::new(NetlifyProvider::new(client.clone(), &netlify.api_token)),
+        );
+    }
+    #[cfg(feature = "njalla")]
+    if let Some(njalla) = &config.njalla {
+        providers.insert(
+            "njalla".to_string(),
+            Arc::new(NjallaProvider::new(client.clone(), &njalla.api_token)),
+        );
+    }
+    #[cfg(feature = "ovh")]
+    if let Some(ovh) = &config.ovh {
+        providers.insert(
+            "ovh".to_string(),
+            Arc::new(OvhProvider::new(client.clone(), ovh)),
+        );
+    }
+    #[cfg(feature = "pihole")]
+    if let Some(pihole) = &config.pihole {
+        providers.insert(
+            "pihole".to_string(),
+            Arc::new(PiholeProvider::new(client.clone(), pihole)),
+        );
+    }
+    #[cfg(feature = "porkbun")]
+    if let Some(porkbun) = &config.porkbun {
+        providers.insert(
+            "porkbun".to_string(),
+            Arc::new(PorkbunProvider::new(client.clone(), porkbun)),
+        );
+    }
+    #[cfg(feature = "powerdns")]
+    if let Some(powerdns) = &config.powerdns {
+        providers.insert(
+            "powerdns".to_string(),
+            Arc::new(PowerDnsProvider::new(client.clone(), powerdns)),
+        );
+    }
+    #[cfg(feature = "rfc2136")]
+    if let Some(rfc2136) = &config.rfc2136 {
+        providers.insert(
+            "rfc2136".to_string(),
+            Arc::new(Rfc2136Provider::new(rfc2136)?),
+        );
+    }
+    #[cfg(feature = "route53")]
+    if let Some(route53) = &config.route53 {
+        providers.insert(
+            "route53".to_string(),
+            Arc::new(Route53Provider::new(client.clone(), route53)),
+        );
+    }
+    #[cfg(feature = "scaleway")]
+    if let Some(scaleway) = &config.scaleway {
+        providers.insert(
+            "scaleway".to_string(),
+            Arc::new(ScalewayProvider::new(client.clone(), &scaleway.secret_key)),
+        );
+    }
+    #[cfg(feature = "technitium")]
+    if let Some(technitium) = &config.technitium {
+        providers.insert(
+            "technitium".to_string(),
+            Arc::new(TechnitiumProvider::new(client.clone(), technitium)),
+        );
+    }
+    #[cfg(feature = "tunnelbroker")]
+    if let Some(tunnelbroker) = &config.tunnelbroker {
+        providers.insert(
+            "tunnelbroker".to_string(),
+            Arc::new(TunnelbrokerProvider::new(client.clone(), tunnelbroker)),
+        );
+    }
+    #[cfg(feature = "vultr")]
+    if let Some(vultr) = &config.vultr {
+        providers.insert(
+            "vultr".to_string(),
+            Arc::new(VultrProvider::new(client.clone(), &vultr.api_key)),
+        );
+    }
+    #[cfg(feature = "webhook")]
+    if let Some(webhook) = &config.webhook {
+        providers.insert(
+            "webhook".to_string(),
+            Arc::new(WebhookProvider::new(client.clone(), webhook)?),
+        );
+    }
+    #[cfg(feature = "script")]
+    if let Some(script) = &config.script {
+        providers.insert("script".to_string(), Arc::new(ScriptProvider::new(script)?));
+    }
+    Ok(providers)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn relative_name_strips_the_zone() {
+        assert_eq!(
+            relative_name("home.example.com", "example.com", "@"),
+            "home"
+        );
+        assert_eq!(relative_name("a.b.example.com", "example.com", ""), "a.b");
+    }
+
+    #[test]
+    fn relative_name_of_the_apex() {
+        assert_eq!(relative_name("example.com", "example.com", "@"), "@");
+        assert_eq!(relative_name("Example.COM", "example.com", ""), "");
+    }
+
+    #[test]
+    fn relative_name_ignores_case() {
+        assert_eq!(
+            relative_name("Home.Example.com", "example.com", "@"),
+            "Home"
+        );
+    }
+
+    #[test]
+    fn relative_name_keeps_names_outside_the_zone() {
+        assert_eq!(
+            relative_name("home.myexample.com", "example.com", "@"),
+            "home.myexample.com"
+        );
+        assert_eq!(
+            relative_name("home.example.org", "example.com", "@"),
+            "home.example.org"
+        );
+        assert_eq!(relative_name("com", "example.com", "@"), "com");
+    }
+}
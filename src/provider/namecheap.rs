@@ -0,0 +1,186 @@
+//! Namecheap provider using the Dynamic DNS update endpoint.
+//!
+//! The endpoint can only set the address of an existing host, it can't read
+//! records back. Records are therefore reported with an unknown current
+//! value, and the agent updates them once at startup.
+
+use super::{DnsProvider, Record, RecordType, Zone, extract_root_domain, retry_after};
+use crate::error::ProviderError;
+use async_trait::async_trait;
+use reqwest::{Client, StatusCode};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fmt;
+
+const API_BASE: &str = "https://dynamicdns.park-your-domain.com";
+
+#[derive(Deserialize)]
+struct InterfaceResponse {
+    #[serde(rename = "ErrCount")]
+    err_count: u32,
+    /// `Err1`, `Err2`, ... holding the error messages
+    #[serde(default)]
+    errors: BTreeMap<String, String>,
+    #[serde(rename = "IP")]
+    ip: Option<String>,
+}
+
+/// Host label of `dns_name` in `domain`, `@` for the apex
+fn host(dns_name: &str, domain: &str) -> String {
+    if dns_name.eq_ignore_ascii_case(domain) {
+        return "@".to_string();
+    }
+    dns_name
+        .strip_suffix(&format!(".{}", domain))
+        .unwrap_or(dns_name)
+        .to_string()
+}
+
+fn unsupported(kind: RecordType) -> ProviderError {
+    ProviderError::Api(format!(
+        "Namecheap Dynamic DNS only supports A records, not {}",
+        kind
+    ))
+}
+
+/// `[providers.namecheap]` section of the config file
+#[derive(Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct NamecheapConfig {
+    /// Dynamic DNS password of the domain (not the account password)
+    pub password: String,
+    /// Domain the records belong to, defaults to the root domain of each
+    /// record. Needed for domains like `example.co.uk`.
+    #[serde(default)]
+    pub domain: Option<String>,
+}
+
+impl fmt::Debug for NamecheapConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("NamecheapConfig")
+            .field("password", &"<redacted>")
+            .field("domain", &self.domain)
+            .finish()
+    }
+}
+
+pub struct NamecheapProvider {
+    client: Client,
+    password: String,
+    domain: Option<String>,
+    base_url: String,
+}
+
+impl NamecheapProvider {
+    pub fn new(client: Client, config: &NamecheapConfig) -> Self {
+        Self {
+            client,
+            password: config.password.clone(),
+            domain: config.domain.clone(),
+            base_url: API_BASE.to_string(),
+        }
+    }
+
+    /// Talk to a different API endpoint, e.g. a proxy or a mock server
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into().trim_end_matches('/').to_string();
+        self
+    }
+}
+
+#[async_trait]
+impl DnsProvider for NamecheapProvider {
+    fn name(&self) -> &str {
+        "namecheap"
+    }
+
+    fn effective_ttl(&self, _record: &Record) -> Option<u32> {
+        None
+    }
+
+    /// The configured domain or the root domain of `dns_name`, without
+    /// asking Namecheap
+    async fn lookup_zone(&self, dns_name: &str) -> Result<Zone, ProviderError> {
+        let domain = self
+            .domain
+            .clone()
+            .unwrap_or_else(|| extract_root_domain(dns_name));
+        Ok(Zone {
+            id: domain.clone(),
+            name: domain,
+        })
+    }
+
+    /// The record with an empty (unknown) value, as it can't be read
+    async fn read_record(
+        &self,
+        _zone: &Zone,
+        dns_name: &str,
+        kind: RecordType,
+    ) -> Result<Option<Record>, ProviderError> {
+        if kind != RecordType::A {
+            return Err(unsupported(kind));
+        }
+        Ok(Some(Record {
+            id: None,
+            name: dns_name.to_string(),
+            kind,
+            content: String::new(),
+            ttl: 1,
+            proxied: None,
+        }))
+    }
+
+    /// Point the host at `record.content`; the TTL is left as configured at
+    /// Namecheap
+    async fn upsert_record(&self, zone: &Zone, record: &Record) -> Result<Record, ProviderError> {
+        if record.kind != RecordType::A {
+            return Err(unsupported(record.kind));
+        }
+        let host = host(&record.name, &zone.name);
+        let resp = self
+            .client
+            .get(format!("{}/update", self.base_url))
+            .query(&[
+                ("host", host.as_str()),
+                ("domain", zone.name.as_str()),
+                ("password", self.password.as_str()),
+                ("ip", record.content.as_str()),
+            ])
+            .send()
+            .await
+            // The URL contains the password
+            .map_err(reqwest::Error::without_url)?;
+        let status = resp.status();
+
+        if status == StatusCode::TOO_MANY_REQUESTS {
+            let retry_after = retry_after(resp.headers());
+            return Err(ProviderError::RateLimited { retry_after });
+        }
+        let body = resp.text().await.map_err(reqwest::Error::without_url)?;
+        if !status.is_success() {
+            let message = format!("HTTP {}", status.as_u16());
+            return Err(match status {
+                StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => ProviderError::Auth(message),
+                _ => ProviderError::for_status(status, message),
+            });
+        }
+
+        let resp: InterfaceResponse = quick_xml::de::from_str(&body).map_err(|e| {
+            ProviderError::InvalidResponse(format!("HTTP {}: {}", status.as_u16(), e))
+        })?;
+        if resp.err_count > 0 {
+            let message = resp.errors.into_values().collect::<Vec<_>>().join("; ");
+            return Err(if message.to_ascii_lowercase().contains("password") {
+                ProviderError::Auth(message)
+            } else {
+                ProviderError::Api(message)
+            });
+        }
+
+        Ok(Record {
+            content: resp.ip.unwrap_or_else(|| record.content.clone()),
+            ..record.clone()
+        })
+    }
+}
@@ -0,0 +1,240 @@
+//! Name.com provider using the v4 core API.
+
+use super::{
+    DnsProvider, Record, RecordType, Zone, extract_root_domain, relative_name, retry_after,
+};
+use crate::error::ProviderError;
+use async_trait::async_trait;
+use reqwest::{Client, RequestBuilder, StatusCode};
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use std::fmt;
+
+const API_BASE: &str = "https://api.name.com/v4";
+/// TTL used for records configured with the automatic TTL (`1`), the lowest
+/// TTL Name.com accepts
+const MIN_TTL: u32 = 300;
+/// Records requested per page
+const PAGE_SIZE: u32 = 1000;
+
+#[derive(Deserialize)]
+struct NameComError {
+    #[serde(default)]
+    message: String,
+    #[serde(default)]
+    details: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct NameComDomain {
+    domain_name: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RecordsPage {
+    #[serde(default)]
+    records: Vec<NameComRecord>,
+    /// Absent on the last page
+    next_page: Option<u32>,
+}
+
+#[derive(Deserialize)]
+struct NameComRecord {
+    id: u64,
+    /// Name relative to the domain, absent for the apex
+    #[serde(default)]
+    host: String,
+    #[serde(rename = "type")]
+    kind: String,
+    answer: String,
+    ttl: u32,
+}
+
+impl NameComRecord {
+    fn into_record(self, dns_name: &str, kind: RecordType) -> Record {
+        Record {
+            id: Some(self.id.to_string()),
+            name: dns_name.to_string(),
+            kind,
+            content: self.answer,
+            ttl: self.ttl,
+            proxied: None,
+        }
+    }
+}
+
+/// `[providers.namecom]` section of the config file
+#[derive(Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct NameComConfig {
+    /// Account username
+    pub username: String,
+    /// Production API token from the account settings
+    pub api_token: String,
+}
+
+impl fmt::Debug for NameComConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("NameComConfig")
+            .field("username", &self.username)
+            .field("api_token", &"<redacted>")
+            .finish()
+    }
+}
+
+pub struct NameComProvider {
+    client: Client,
+    username: String,
+    api_token: String,
+    base_url: String,
+}
+
+impl NameComProvider {
+    pub fn new(client: Client, config: &NameComConfig) -> Self {
+        Self {
+            client,
+            username: config.username.clone(),
+            api_token: config.api_token.clone(),
+            base_url: API_BASE.to_string(),
+        }
+    }
+
+    /// Talk to a different API endpoint, e.g. a proxy or a mock server
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into().trim_end_matches('/').to_string();
+        self
+    }
+
+    fn records_url(&self, zone: &Zone) -> String {
+        format!("{}/domains/{}/records", self.base_url, zone.id)
+    }
+
+    /// Send an authenticated request and parse the JSON response, `None` if
+    /// the resource doesn't exist
+    async fn send<T: DeserializeOwned>(
+        &self,
+        request: RequestBuilder,
+    ) -> Result<Option<T>, ProviderError> {
+        let resp = request
+            .basic_auth(&self.username, Some(&self.api_token))
+            .send()
+            .await?;
+        let status = resp.status();
+
+        if status == StatusCode::TOO_MANY_REQUESTS {
+            let retry_after = retry_after(resp.headers());
+            return Err(ProviderError::RateLimited { retry_after });
+        }
+        if status == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+
+        let body = resp.text().await?;
+        if !status.is_success() {
+            let message = match serde_json::from_str::<NameComError>(&body) {
+                Ok(e) if e.details.is_empty() => e.message,
+                Ok(e) => format!("{}: {}", e.message, e.details),
+                Err(_) => format!("HTTP {}", status.as_u16()),
+            };
+            return Err(match status {
+                StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => ProviderError::Auth(message),
+                _ => ProviderError::for_status(status, message),
+            });
+        }
+
+        serde_json::from_str(&body)
+            .map(Some)
+            .map_err(|e| ProviderError::InvalidResponse(format!("HTTP {}: {}", status.as_u16(), e)))
+    }
+}
+
+/// TTL the API stores for a record configured with `ttl`
+fn stored_ttl(ttl: u32) -> u32 {
+    ttl.max(MIN_TTL)
+}
+
+#[async_trait]
+impl DnsProvider for NameComProvider {
+    fn name(&self) -> &str {
+        "namecom"
+    }
+
+    fn effective_ttl(&self, record: &Record) -> Option<u32> {
+        Some(stored_ttl(record.ttl))
+    }
+
+    /// Look up the root domain of `dns_name` in the account; its name
+    /// doubles as the ID
+    async fn lookup_zone(&self, dns_name: &str) -> Result<Zone, ProviderError> {
+        let domain = extract_root_domain(dns_name);
+        let request = self
+            .client
+            .get(format!("{}/domains/{}", self.base_url, domain));
+
+        match self.send::<NameComDomain>(request).await? {
+            Some(found) => Ok(Zone {
+                id: found.domain_name.clone(),
+                name: found.domain_name,
+            }),
+            None => Err(ProviderError::ZoneNotFound(domain)),
+        }
+    }
+
+    async fn read_record(
+        &self,
+        zone: &Zone,
+        dns_name: &str,
+        kind: RecordType,
+    ) -> Result<Option<Record>, ProviderError> {
+        let host = relative_name(dns_name, &zone.name, "");
+        let mut page = 1;
+        loop {
+            let request = self
+                .client
+                .get(self.records_url(zone))
+                .query(&[("page", page), ("perPage", PAGE_SIZE)]);
+            let resp: RecordsPage = self
+                .send(request)
+                .await?
+                .ok_or_else(|| ProviderError::ZoneNotFound(zone.name.clone()))?;
+
+            if let Some(record) = resp
+                .records
+                .into_iter()
+                .find(|r| r.kind == kind.as_str() && r.host.eq_ignore_ascii_case(&host))
+            {
+                return Ok(Some(record.into_record(dns_name, kind)));
+            }
+            match resp.next_page {
+                Some(next) if next > page => page = next,
+                _ => return Ok(None),
+            }
+        }
+    }
+
+    async fn upsert_record(&self, zone: &Zone, record: &Record) -> Result<Record, ProviderError> {
+        let body = serde_json::json!({
+            "host": relative_name(&record.name, &zone.name, ""),
+            "type": record.kind.as_str(),
+            "answer": record.content,
+            "ttl": stored_ttl(record.ttl)
+        });
+        let request = match &record.id {
+            Some(id) => self
+                .client
+                .put(format!("{}/{}", self.records_url(zone), id)),
+            None => self.client.post(self.records_url(zone)),
+        };
+
+        let stored: NameComRecord =
+            self.send(request.json(&body))
+                .await?
+                .ok_or_else(|| ProviderError::RecordNotFound {
+                    name: record.name.clone(),
+                    kind: record.kind,
+                })?;
+        Ok(stored.into_record(&record.name, record.kind))
+    }
+}
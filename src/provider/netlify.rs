@@ -0,0 +1,221 @@
+//! Netlify DNS provider using the v1 API.
+//!
+//! Netlify records can't be edited, so an update creates the record with the
+//! new value and then deletes the old one.
+
+use super::{DnsProvider, Record, RecordType, Zone, extract_root_domain, retry_after};
+use crate::error::ProviderError;
+use async_trait::async_trait;
+use reqwest::{Client, RequestBuilder, StatusCode};
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use std::fmt;
+
+const API_BASE: &str = "https://api.netlify.com/api/v1";
+/// TTL used for records configured with the automatic TTL (`1`), matching
+/// the Netlify default
+const AUTO_TTL: u32 = 3600;
+/// Zones requested per page
+const PAGE_SIZE: usize = 100;
+
+#[derive(Deserialize)]
+struct NetlifyError {
+    #[serde(default)]
+    message: String,
+}
+
+#[derive(Deserialize)]
+struct NetlifyZone {
+    id: String,
+    name: String,
+}
+
+#[derive(Deserialize)]
+struct NetlifyRecord {
+    id: String,
+    /// Fully qualified name
+    hostname: String,
+    #[serde(rename = "type")]
+    kind: String,
+    value: String,
+    #[serde(default)]
+    ttl: Option<u32>,
+}
+
+impl NetlifyRecord {
+    fn into_record(self, dns_name: &str, kind: RecordType) -> Record {
+        Record {
+            id: Some(self.id),
+            name: dns_name.to_string(),
+            kind,
+            content: self.value,
+            ttl: self.ttl.unwrap_or(AUTO_TTL),
+            proxied: None,
+        }
+    }
+}
+
+/// `[providers.netlify]` section of the config file
+#[derive(Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct NetlifyConfig {
+    /// Personal access token from the user settings
+    pub api_token: String,
+}
+
+impl fmt::Debug for NetlifyConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("NetlifyConfig")
+            .field("api_token", &"<redacted>")
+            .finish()
+    }
+}
+
+pub struct NetlifyProvider {
+    client: Client,
+    api_token: String,
+    base_url: String,
+}
+
+impl NetlifyProvider {
+    pub fn new(client: Client, api_token: impl Into<String>) -> Self {
+        Self {
+            client,
+            api_token: api_token.into(),
+            base_url: API_BASE.to_string(),
+        }
+    }
+
+    /// Talk to a different API endpoint, e.g. a proxy or a mock server
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into().trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Send an authenticated request and parse the JSON response
+    async fn send<T: DeserializeOwned>(&self, request: RequestBuilder) -> Result<T, ProviderError> {
+        let resp = request.bearer_auth(&self.api_token).send().await?;
+        let status = resp.status();
+
+        if status == StatusCode::TOO_MANY_REQUESTS {
+            let retry_after = retry_after(resp.headers());
+            return Err(ProviderError::RateLimited { retry_after });
+        }
+
+        let body = resp.text().await?;
+        if !status.is_success() {
+            let message = serde_json::from_str::<NetlifyError>(&body)
+                .ok()
+                .map(|e| e.message)
+                .filter(|m| !m.is_empty())
+                .unwrap_or_else(|| format!("HTTP {}", status.as_u16()));
+            return Err(match status {
+                StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => ProviderError::Auth(message),
+                StatusCode::NOT_FOUND => ProviderError::NotFound(message),
+                _ => ProviderError::for_status(status, message),
+            });
+        }
+
+        // Deletions answer with 204 and no body
+        let body = if body.trim().is_empty() {
+            "null"
+        } else {
+            &body
+        };
+        serde_json::from_str(body)
+            .map_err(|e| ProviderError::InvalidResponse(format!("HTTP {}: {}", status.as_u16(), e)))
+    }
+}
+
+/// TTL the API stores for a record configured with `ttl`
+fn stored_ttl(ttl: u32) -> u32 {
+    if ttl == 1 { AUTO_TTL } else { ttl }
+}
+
+#[async_trait]
+impl DnsProvider for NetlifyProvider {
+    fn name(&self) -> &str {
+        "netlify"
+    }
+
+    fn effective_ttl(&self, record: &Record) -> Option<u32> {
+        Some(stored_ttl(record.ttl))
+    }
+
+    /// Find the zone of the root domain of `dns_name`
+    async fn lookup_zone(&self, dns_name: &str) -> Result<Zone, ProviderError> {
+        let domain = extract_root_domain(dns_name);
+        let mut page = 1;
+        loop {
+            let request = self
+                .client
+                .get(format!("{}/dns_zones", self.base_url))
+                .query(&[("page", page), ("per_page", PAGE_SIZE)]);
+            let zones: Vec<NetlifyZone> = self.send(request).await?;
+            let count = zones.len();
+
+            if let Some(zone) = zones
+                .into_iter()
+                .find(|z| z.name.eq_ignore_ascii_case(&domain))
+            {
+                return Ok(Zone {
+                    id: zone.id,
+                    name: zone.name,
+                });
+            }
+            if count < PAGE_SIZE {
+                return Err(ProviderError::ZoneNotFound(domain));
+            }
+            page += 1;
+        }
+    }
+
+    async fn read_record(
+        &self,
+        zone: &Zone,
+        dns_name: &str,
+        kind: RecordType,
+    ) -> Result<Option<Record>, ProviderError> {
+        let request = self.client.get(format!(
+            "{}/dns_zones/{}/dns_records",
+            self.base_url, zone.id
+        ));
+        let records: Vec<NetlifyRecord> = self.send(request).await?;
+
+        Ok(records
+            .into_iter()
+            .find(|r| {
+                r.kind == kind.as_str()
+                    && r.hostname
+                        .trim_end_matches('.')
+                        .eq_ignore_ascii_case(dns_name)
+            })
+            .map(|r| r.into_record(dns_name, kind)))
+    }
+
+    /// Create the record with the new value, then delete the previous one so
+    /// the name keeps resolving in between
+    async fn upsert_record(&self, zone: &Zone, record: &Record) -> Result<Record, ProviderError> {
+        let ttl = stored_ttl(record.ttl);
+        let records_url = format!("{}/dns_zones/{}/dns_records", self.base_url, zone.id);
+        let body = serde_json::json!({
+            "type": record.kind.as_str(),
+            "hostname": record.name,
+            "value": record.content,
+            "ttl": ttl
+        });
+
+        let created: NetlifyRecord = self
+            .send(self.client.post(&records_url).json(&body))
+            .await?;
+        if let Some(id) = &record.id {
+            let request = self.client.delete(format!("{}/{}", records_url, id));
+            match self.send::<serde_json::Value>(request).await {
+                // Already deleted, e.g. in the dashboard
+                Ok(_) | Err(ProviderError::NotFound(_)) => {}
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(created.into_record(&record.name, record.kind))
+    }
+}
@@ -0,0 +1,243 @@
+//! Njalla provider using its JSON-RPC style API.
+//!
+//! Every call is a POST of `{"method", "params"}` to a single endpoint, which
+//! answers with either a `result` or an `error` object.
+
+use super::{
+    DnsProvider, Record, RecordType, Zone, extract_root_domain, relative_name, retry_after,
+};
+use crate::error::ProviderError;
+use async_trait::async_trait;
+use reqwest::header::AUTHORIZATION;
+use reqwest::{Client, StatusCode};
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use std::fmt;
+
+const API_BASE: &str = "https://njal.la/api/1";
+/// TTL used for records configured with the automatic TTL (`1`), matching
+/// the Njalla default
+const AUTO_TTL: u32 = 10800;
+
+#[derive(Deserialize)]
+struct Response<T> {
+    result: Option<T>,
+    error: Option<NjallaError>,
+}
+
+#[derive(Deserialize)]
+struct NjallaError {
+    #[serde(default)]
+    code: i64,
+    #[serde(default)]
+    message: String,
+}
+
+#[derive(Deserialize)]
+struct Domains {
+    domains: Vec<NjallaDomain>,
+}
+
+#[derive(Deserialize)]
+struct NjallaDomain {
+    name: String,
+}
+
+#[derive(Deserialize)]
+struct Records {
+    records: Vec<NjallaRecord>,
+}
+
+#[derive(Deserialize)]
+struct NjallaRecord {
+    /// Sent as a number or a string depending on the call
+    id: Value,
+    /// Name relative to the domain, `@` for the apex
+    name: String,
+    #[serde(rename = "type")]
+    kind: String,
+    content: String,
+    ttl: u32,
+}
+
+impl NjallaRecord {
+    fn id(&self) -> String {
+        match &self.id {
+            Value::String(id) => id.clone(),
+            id => id.to_string(),
+        }
+    }
+
+    fn into_record(self, dns_name: &str, kind: RecordType) -> Record {
+        Record {
+            id: Some(self.id()),
+            name: dns_name.to_string(),
+            kind,
+            content: self.content,
+            ttl: self.ttl,
+            proxied: None,
+        }
+    }
+}
+
+/// `[providers.njalla]` section of the config file
+#[derive(Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct NjallaConfig {
+    /// API token from the settings page
+    pub api_token: String,
+}
+
+impl fmt::Debug for NjallaConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("NjallaConfig")
+            .field("api_token", &"<redacted>")
+            .finish()
+    }
+}
+
+pub struct NjallaProvider {
+    client: Client,
+    api_token: String,
+    base_url: String,
+}
+
+impl NjallaProvider {
+    pub fn new(client: Client, api_token: impl Into<String>) -> Self {
+        Self {
+            client,
+            api_token: api_token.into(),
+            base_url: API_BASE.to_string(),
+        }
+    }
+
+    /// Talk to a different API endpoint, e.g. a proxy or a mock server
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into().trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Call `method` and parse its result
+    async fn call<T: DeserializeOwned>(
+        &self,
+        method: &str,
+        params: Value,
+    ) -> Result<T, ProviderError> {
+        let resp = self
+            .client
+            .post(format!("{}/", self.base_url))
+            .header(AUTHORIZATION, format!("Njalla {}", self.api_token))
+            .json(&serde_json::json!({ "method": method, "params": params }))
+            .send()
+            .await?;
+        let status = resp.status();
+
+        if status == StatusCode::TOO_MANY_REQUESTS {
+            let retry_after = retry_after(resp.headers());
+            return Err(ProviderError::RateLimited { retry_after });
+        }
+        if status == StatusCode::UNAUTHORIZED || status == StatusCode::FORBIDDEN {
+            return Err(ProviderError::Auth(format!("HTTP {}", status.as_u16())));
+        }
+
+        let body = resp.text().await?;
+        if !status.is_success() {
+            return Err(ProviderError::for_status(
+                status,
+                format!("HTTP {}", status.as_u16()),
+            ));
+        }
+
+        let resp: Response<T> = serde_json::from_str(&body).map_err(|e| {
+            ProviderError::InvalidResponse(format!("HTTP {}: {}", status.as_u16(), e))
+        })?;
+        match (resp.result, resp.error) {
+            // Errors carry HTTP-like codes
+            (_, Some(e)) if e.code == 401 || e.code == 403 => Err(ProviderError::Auth(e.message)),
+            (_, Some(e)) => Err(ProviderError::Api(format!("{} ({})", e.message, e.code))),
+            (Some(result), None) => Ok(result),
+            (None, None) => Err(ProviderError::InvalidResponse(format!(
+                "no result for {}",
+                method
+            ))),
+        }
+    }
+}
+
+/// TTL the API stores for a record configured with `ttl`
+fn stored_ttl(ttl: u32) -> u32 {
+    if ttl == 1 { AUTO_TTL } else { ttl }
+}
+
+#[async_trait]
+impl DnsProvider for NjallaProvider {
+    fn name(&self) -> &str {
+        "njalla"
+    }
+
+    fn effective_ttl(&self, record: &Record) -> Option<u32> {
+        Some(stored_ttl(record.ttl))
+    }
+
+    /// Find the root domain of `dns_name` in the account; its name doubles
+    /// as the ID
+    async fn lookup_zone(&self, dns_name: &str) -> Result<Zone, ProviderError> {
+        let domain = extract_root_domain(dns_name);
+        let resp: Domains = self.call("list-domains", serde_json::json!({})).await?;
+
+        resp.domains
+            .into_iter()
+            .find(|d| d.name.eq_ignore_ascii_case(&domain))
+            .map(|d| Zone {
+                id: d.name.clone(),
+                name: d.name,
+            })
+            .ok_or(ProviderError::ZoneNotFound(domain))
+    }
+
+    async fn read_record(
+        &self,
+        zone: &Zone,
+        dns_name: &str,
+        kind: RecordType,
+    ) -> Result<Option<Record>, ProviderError> {
+        let relative = relative_name(dns_name, &zone.name, "@");
+        let resp: Records = self
+            .call("list-records", serde_json::json!({ "domain": zone.id }))
+            .await?;
+
+        Ok(resp
+            .records
+            .into_iter()
+            .find(|r| r.kind == kind.as_str() && r.name.eq_ignore_ascii_case(&relative))
+            .map(|r| r.into_record(dns_name, kind)))
+    }
+
+    async fn upsert_record(&self, zone: &Zone, record: &Record) -> Result<Record, ProviderError> {
+        let ttl = stored_ttl(record.ttl);
+
+        let stored: NjallaRecord = match &record.id {
+            Some(id) => {
+                let params = serde_json::json!({
+                    "domain": zone.id,
+                    "id": id,
+                    "content": record.content,
+                    "ttl": ttl
+                });
+                self.call("edit-record", params).await?
+            }
+            None => {
+                let params = serde_json::json!({
+                    "domain": zone.id,
+                    "type": record.kind.as_str(),
+                    "name": relative_name(&record.name, &zone.name, "@"),
+                    "content": record.content,
+                    "ttl": ttl
+                });
+                self.call("add-record", params).await?
+            }
+        };
+        Ok(stored.into_record(&record.name, record.kind))
+    }
+}
@@ -0,0 +1,370 @@
+//! OVHcloud DNS provider using the signed `/1.0` API.
+//!
+//! Every request is signed with the application secret and consumer key.
+//! Record changes only go live once the zone is refreshed, which
+//! [`OvhProvider::upsert_record`] does after each write.
+
+use super::{DnsProvider, Record, RecordType, Zone, extract_root_domain, retry_after};
+use crate::error::ProviderError;
+use async_trait::async_trait;
+use reqwest::{Client, Method, Response, StatusCode, Url};
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use sha1::{Digest, Sha1};
+use std::fmt;
+use std::fmt::Write;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::sync::OnceCell;
+
+/// TTL sent for records configured with the automatic TTL (`1`), meaning
+/// the zone default
+const AUTO_TTL: u32 = 0;
+
+/// API endpoint of the given name, or the URL itself
+fn endpoint_url(endpoint: &str) -> String {
+    match endpoint {
+        "ovh-eu" => "https://eu.api.ovh.com/1.0",
+        "ovh-ca" => "https://ca.api.ovh.com/1.0",
+        "ovh-us" => "https://api.us.ovhcloud.com/1.0",
+        url => url,
+    }
+    .trim_end_matches('/')
+    .to_string()
+}
+
+fn default_endpoint() -> String {
+    "ovh-eu".to_string()
+}
+
+fn now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or_default()
+}
+
+/// The `$1$` signature OVH expects in `X-Ovh-Signature`
+fn signature(
+    application_secret: &str,
+    consumer_key: &str,
+    method: &Method,
+    url: &str,
+    body: &str,
+    timestamp: i64,
+) -> String {
+    let digest = Sha1::digest(format!(
+        "{}+{}+{}+{}+{}+{}",
+        application_secret, consumer_key, method, url, body, timestamp
+    ));
+    digest.iter().fold("$1$".to_string(), |mut out, b| {
+        let _ = write!(out, "{:02x}", b);
+        out
+    })
+}
+
+#[derive(Deserialize)]
+struct OvhError {
+    #[serde(default)]
+    class: String,
+    #[serde(default)]
+    message: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct OvhZone {
+    name: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct OvhRecord {
+    id: u64,
+    sub_domain: String,
+    field_type: String,
+    target: String,
+    #[serde(default)]
+    ttl: u32,
+}
+
+impl OvhRecord {
+    fn into_record(self, dns_name: &str, kind: RecordType) -> Record {
+        Record {
+            id: Some(self.id.to_string()),
+            name: dns_name.to_string(),
+            kind,
+            content: self.target,
+            ttl: self.ttl,
+            proxied: None,
+        }
+    }
+}
+
+/// Name of `dns_name` relative to `zone`, empty for the apex
+fn sub_domain(dns_name: &str, zone: &str) -> String {
+    if dns_name.eq_ignore_ascii_case(zone) {
+        return String::new();
+    }
+    dns_name
+        .strip_suffix(&format!(".{}", zone))
+        .unwrap_or(dns_name)
+        .to_string()
+}
+
+/// `[providers.ovh]` section of the config file
+#[derive(Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct OvhConfig {
+    /// `ovh-eu`, `ovh-ca`, `ovh-us` or the URL of the API
+    #[serde(default = "default_endpoint")]
+    pub endpoint: String,
+    pub application_key: String,
+    pub application_secret: String,
+    /// Consumer key granted `GET`, `POST` and `PUT` on `/domain/zone/*`
+    pub consumer_key: String,
+}
+
+impl fmt::Debug for OvhConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("OvhConfig")
+            .field("endpoint", &self.endpoint)
+            .field("application_key", &self.application_key)
+            .field("application_secret", &"<redacted>")
+            .field("consumer_key", &"<redacted>")
+            .finish()
+    }
+}
+
+pub struct OvhProvider {
+    client: Client,
+    application_key: String,
+    application_secret: String,
+    consumer_key: String,
+    base_url: String,
+    /// Offset of the API clock to ours, signatures must use server time
+    time_delta: OnceCell<i64>,
+}
+
+impl OvhProvider {
+    pub fn new(client: Client, config: &OvhConfig) -> Self {
+        Self {
+            client,
+            application_key: config.application_key.clone(),
+            application_secret: config.application_secret.clone(),
+            consumer_key: config.consumer_key.clone(),
+            base_url: endpoint_url(&config.endpoint),
+            time_delta: OnceCell::new(),
+        }
+    }
+
+    /// Talk to a different API endpoint, e.g. a proxy or a mock server
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into().trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Current time of the API server
+    async fn timestamp(&self) -> Result<i64, ProviderError> {
+        let delta = self
+            .time_delta
+            .get_or_try_init(|| async {
+                let resp = self
+                    .client
+                    .get(format!("{}/auth/time", self.base_url))
+                    .send()
+                    .await?;
+                let body = response_body(resp)
+                    .await?
+                    .ok_or_else(|| ProviderError::Api("HTTP 404 for /auth/time".into()))?;
+                let server: i64 = body.trim().parse().map_err(|e| {
+                    ProviderError::InvalidResponse(format!("server time {:?}: {}", body, e))
+                })?;
+                Ok::<_, ProviderError>(server - now())
+            })
+            .await?;
+        Ok(now() + delta)
+    }
+
+    /// Send a signed request and parse the JSON response, `None` if the
+    /// resource doesn't exist
+    async fn send<T: DeserializeOwned>(
+        &self,
+        method: Method,
+        path: &str,
+        query: &[(&str, &str)],
+        body: Option<serde_json::Value>,
+    ) -> Result<Option<T>, ProviderError> {
+        let url = Url::parse_with_params(&format!("{}{}", self.base_url, path), query)
+            .map_err(|e| ProviderError::Api(e.to_string()))?;
+        let url = url.as_str().trim_end_matches('?').to_string();
+        let body = body.map(|b| b.to_string()).unwrap_or_default();
+        let timestamp = self.timestamp().await?;
+
+        let mut request = self
+            .client
+            .request(method.clone(), &url)
+            .header("X-Ovh-Application", &self.application_key)
+            .header("X-Ovh-Consumer", &self.consumer_key)
+            .header("X-Ovh-Timestamp", timestamp.to_string())
+            .header(
+                "X-Ovh-Signature",
+                signature(
+                    &self.application_secret,
+                    &self.consumer_key,
+                    &method,
+                    &url,
+                    &body,
+                    timestamp,
+                ),
+            );
+        if !body.is_empty() {
+            request = request
+                .header("content-type", "application/json")
+                .body(body);
+        }
+
+        let Some(body) = response_body(request.send().await?).await? else {
+            return Ok(None);
+        };
+        // Writes answer with an empty body or `null`
+        let body = if body.trim().is_empty() {
+            "null"
+        } else {
+            &body
+        };
+        serde_json::from_str(body)
+            .map(Some)
+            .map_err(|e| ProviderError::InvalidResponse(e.to_string()))
+    }
+
+    async fn get_record(&self, zone: &Zone, id: u64) -> Result<Option<OvhRecord>, ProviderError> {
+        let path = format!("/domain/zone/{}/record/{}", zone.id, id);
+        self.send(Method::GET, &path, &[], None).await
+    }
+}
+
+/// Body of a successful response, `None` for a 404
+async fn response_body(resp: Response) -> Result<Option<String>, ProviderError> {
+    let status = resp.status();
+    if status == StatusCode::TOO_MANY_REQUESTS {
+        let retry_after = retry_after(resp.headers());
+        return Err(ProviderError::RateLimited { retry_after });
+    }
+    if status == StatusCode::NOT_FOUND {
+        return Ok(None);
+    }
+
+    let body = resp.text().await?;
+    if !status.is_success() {
+        let message = match serde_json::from_str::<OvhError>(&body) {
+            Ok(e) => format!("{} ({})", e.message, e.class),
+            Err(_) => format!("HTTP {}", status.as_u16()),
+        };
+        return Err(match status {
+            StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => ProviderError::Auth(message),
+            _ => ProviderError::for_status(status, message),
+        });
+    }
+    Ok(Some(body))
+}
+
+/// TTL the API stores for a record configured with `ttl`
+fn stored_ttl(ttl: u32) -> u32 {
+    if ttl == 1 { AUTO_TTL } else { ttl }
+}
+
+#[async_trait]
+impl DnsProvider for OvhProvider {
+    fn name(&self) -> &str {
+        "ovh"
+    }
+
+    fn effective_ttl(&self, record: &Record) -> Option<u32> {
+        Some(stored_ttl(record.ttl))
+    }
+
+    /// Look up the zone of the root domain of `dns_name`; its name doubles
+    /// as the ID
+    async fn lookup_zone(&self, dns_name: &str) -> Result<Zone, ProviderError> {
+        let domain = extract_root_domain(dns_name);
+        let path = format!("/domain/zone/{}", domain);
+        match self.send::<OvhZone>(Method::GET, &path, &[], None).await? {
+            Some(zone) => Ok(Zone {
+                id: zone.name.clone(),
+                name: zone.name,
+            }),
+            None => Err(ProviderError::ZoneNotFound(domain)),
+        }
+    }
+
+    async fn read_record(
+        &self,
+        zone: &Zone,
+        dns_name: &str,
+        kind: RecordType,
+    ) -> Result<Option<Record>, ProviderError> {
+        let sub_domain = sub_domain(dns_name, &zone.name);
+        let path = format!("/domain/zone/{}/record", zone.id);
+        let query = [("fieldType", kind.as_str()), ("subDomain", &sub_domain)];
+        let ids: Vec<u64> = self
+            .send(Method::GET, &path, &query, None)
+            .await?
+            .unwrap_or_default();
+
+        for id in ids {
+            if let Some(record) = self.get_record(zone, id).await?
+                && record.field_type == kind.as_str()
+                && record.sub_domain.eq_ignore_ascii_case(&sub_domain)
+            {
+                return Ok(Some(record.into_record(dns_name, kind)));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Write the record, then refresh the zone so the change is served
+    async fn upsert_record(&self, zone: &Zone, record: &Record) -> Result<Record, ProviderError> {
+        let ttl = stored_ttl(record.ttl);
+        let not_found = || ProviderError::ZoneNotFound(zone.name.clone());
+
+        let id = match &record.id {
+            Some(id) => {
+                let path = format!("/domain/zone/{}/record/{}", zone.id, id);
+                let body = serde_json::json!({ "target": record.content, "ttl": ttl });
+                self.send::<serde_json::Value>(Method::PUT, &path, &[], Some(body))
+                    .await?
+                    .ok_or_else(not_found)?;
+                id.parse()
+                    .map_err(|_| ProviderError::Api(format!("Invalid record ID {:?}", id)))?
+            }
+            None => {
+                let path = format!("/domain/zone/{}/record", zone.id);
+                let body = serde_json::json!({
+                    "fieldType": record.kind.as_str(),
+                    "subDomain": sub_domain(&record.name, &zone.name),
+                    "target": record.content,
+                    "ttl": ttl
+                });
+                let created: OvhRecord = self
+                    .send(Method::POST, &path, &[], Some(body))
+                    .await?
+                    .ok_or_else(not_found)?;
+                created.id
+            }
+        };
+
+        let path = format!("/domain/zone/{}/refresh", zone.id);
+        self.send::<serde_json::Value>(Method::POST, &path, &[], None)
+            .await?
+            .ok_or_else(not_found)?;
+
+        let stored =
+            self.get_record(zone, id)
+                .await?
+                .ok_or_else(|| ProviderError::RecordNotFound {
+                    name: record.name.clone(),
+                    kind: record.kind,
+                })?;
+        Ok(stored.into_record(&record.name, record.kind))
+    }
+}
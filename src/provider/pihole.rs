@@ -0,0 +1,276 @@
+//! Pi-hole provider for local DNS records, using the v6 API.
+//!
+//! Local records are `address hostname` entries of the `dns.hosts` setting,
+//! served to the LAN clients of the Pi-hole only. Entries can't be edited,
+//! so an update adds the entry with the new address and then removes the
+//! old one, which is kept as the record ID.
+
+use super::{DnsProvider, Record, RecordType, Zone, retry_after, wildcard_base};
+use crate::error::ProviderError;
+use async_trait::async_trait;
+use reqwest::{Client, RequestBuilder, Response, StatusCode};
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use std::fmt;
+use std::net::IpAddr;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+
+/// Log in again this long before the session expires
+const EXPIRY_MARGIN: Duration = Duration::from_secs(60);
+
+fn default_url() -> String {
+    "http://pi.hole".to_string()
+}
+
+#[derive(Deserialize)]
+struct PiholeError {
+    error: ErrorDetails,
+}
+
+#[derive(Deserialize)]
+struct ErrorDetails {
+    #[serde(default)]
+    message: String,
+    hint: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Auth {
+    session: Session,
+}
+
+#[derive(Deserialize)]
+struct Session {
+    valid: bool,
+    sid: Option<String>,
+    /// Seconds until the session expires
+    validity: u64,
+}
+
+#[derive(Deserialize)]
+struct HostsConfig {
+    config: DnsConfig,
+}
+
+#[derive(Deserialize)]
+struct DnsConfig {
+    dns: Hosts,
+}
+
+#[derive(Deserialize)]
+struct Hosts {
+    hosts: Vec<String>,
+}
+
+/// `[providers.pihole]` section of the config file
+#[derive(Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PiholeConfig {
+    /// Address of the web interface
+    #[serde(default = "default_url")]
+    pub url: String,
+    /// App password from Settings > Web interface / API, or the login
+    /// password. Leave out if the Pi-hole has no password.
+    #[serde(default)]
+    pub password: Option<String>,
+}
+
+impl fmt::Debug for PiholeConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PiholeConfig")
+            .field("url", &self.url)
+            .field("password", &self.password.as_ref().map(|_| "<redacted>"))
+            .finish()
+    }
+}
+
+pub struct PiholeProvider {
+    client: Client,
+    url: String,
+    password: Option<String>,
+    /// Session ID and its expiry
+    session: Mutex<Option<(String, Instant)>>,
+}
+
+impl PiholeProvider {
+    pub fn new(client: Client, config: &PiholeConfig) -> Self {
+        Self {
+            client,
+            url: config.url.trim_end_matches('/').to_string(),
+            password: config.password.clone(),
+            session: Mutex::new(None),
+        }
+    }
+
+    /// ID of a valid session, logging in if needed. `None` without a password.
+    async fn session(&self) -> Result<Option<String>, ProviderError> {
+        let Some(password) = &self.password else {
+            return Ok(None);
+        };
+        let mut session = self.session.lock().await;
+        if let Some((sid, expires)) = session.as_ref()
+            && Instant::now() + EXPIRY_MARGIN < *expires
+        {
+            return Ok(Some(sid.clone()));
+        }
+
+        let request = self
+            .client
+            .post(format!("{}/api/auth", self.url))
+            .json(&serde_json::json!({ "password": password }));
+        let auth: Auth = parse(request.send().await?).await?;
+        match auth.session {
+            Session {
+                valid: true,
+                sid: Some(sid),
+                validity,
+            } => {
+                *session = Some((sid.clone(), Instant::now() + Duration::from_secs(validity)));
+                Ok(Some(sid))
+            }
+            // Without a password configured on the Pi-hole every session is
+            // valid but has no ID
+            Session { valid: true, .. } => Ok(None),
+            Session { valid: false, .. } => Err(ProviderError::Auth("Login refused".into())),
+        }
+    }
+
+    /// Send `request` in the session and parse the JSON response
+    async fn send<T: DeserializeOwned>(&self, request: RequestBuilder) -> Result<T, ProviderError> {
+        let request = match self.session().await? {
+            Some(sid) => request.header("X-FTL-SID", sid),
+            None => request,
+        };
+        let result = parse(request.send().await?).await;
+        if matches!(result, Err(ProviderError::Auth(_))) {
+            // Log in again next time, the session may have been dropped
+            *self.session.lock().await = None;
+        }
+        result
+    }
+
+    /// URL of the `dns.hosts` entry `entry`
+    fn entry_url(&self, entry: &str) -> String {
+        format!(
+            "{}/api/config/dns/hosts/{}",
+            self.url,
+            entry.replace(' ', "%20")
+        )
+    }
+}
+
+/// Parse a JSON response, mapping API errors
+async fn parse<T: DeserializeOwned>(resp: Response) -> Result<T, ProviderError> {
+    let status = resp.status();
+    if status == StatusCode::TOO_MANY_REQUESTS {
+        let retry_after = retry_after(resp.headers());
+        return Err(ProviderError::RateLimited { retry_after });
+    }
+
+    let body = resp.text().await?;
+    if !status.is_success() {
+        let message = match serde_json::from_str::<PiholeError>(&body) {
+            Ok(PiholeError {
+                error:
+                    ErrorDetails {
+                        message,
+                        hint: Some(hint),
+                    },
+            }) => format!("{} ({})", message, hint),
+            Ok(e) => e.error.message,
+            Err(_) => format!("HTTP {}", status.as_u16()),
+        };
+        return Err(match status {
+            StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => ProviderError::Auth(message),
+            _ => ProviderError::for_status(status, message),
+        });
+    }
+
+    // Changes answer with 201 or 204 and no body
+    let body = if body.trim().is_empty() {
+        "null"
+    } else {
+        &body
+    };
+    serde_json::from_str(body)
+        .map_err(|e| ProviderError::InvalidResponse(format!("HTTP {}: {}", status.as_u16(), e)))
+}
+
+#[async_trait]
+impl DnsProvider for PiholeProvider {
+    fn name(&self) -> &str {
+        "pihole"
+    }
+
+    fn effective_ttl(&self, _record: &Record) -> Option<u32> {
+        None
+    }
+
+    /// Local records aren't grouped in zones, so each hostname is its own.
+    /// Logs in so bad passwords fail at startup.
+    async fn lookup_zone(&self, dns_name: &str) -> Result<Zone, ProviderError> {
+        if wildcard_base(dns_name).is_some() {
+            return Err(ProviderError::Unsupported {
+                provider: self.name().to_string(),
+                action: "serve wildcard records",
+            });
+        }
+        self.session().await?;
+        Ok(Zone {
+            id: dns_name.to_string(),
+            name: dns_name.to_string(),
+        })
+    }
+
+    async fn read_record(
+        &self,
+        _zone: &Zone,
+        dns_name: &str,
+        kind: RecordType,
+    ) -> Result<Option<Record>, ProviderError> {
+        let request = self
+            .client
+            .get(format!("{}/api/config/dns/hosts", self.url));
+        let resp: HostsConfig = self.send(request).await?;
+
+        Ok(resp.config.dns.hosts.into_iter().find_map(|entry| {
+            let mut fields = entry.split_whitespace();
+            let address: IpAddr = fields.next()?.parse().ok()?;
+            let matches = address.is_ipv4() == (kind == RecordType::A)
+                && fields.any(|name| name.eq_ignore_ascii_case(dns_name));
+            matches.then(|| Record {
+                id: Some(entry.clone()),
+                name: dns_name.to_string(),
+                kind,
+                content: address.to_string(),
+                // Local records are served without a configurable TTL
+                ttl: 1,
+                proxied: None,
+            })
+        }))
+    }
+
+    /// Add the entry with the new address, then remove the previous one
+    async fn upsert_record(&self, _zone: &Zone, record: &Record) -> Result<Record, ProviderError> {
+        let entry = format!("{} {}", record.content, record.name);
+        if record.id.as_deref() == Some(entry.as_str()) {
+            return Ok(record.clone());
+        }
+
+        let request = self.client.put(self.entry_url(&entry));
+        self.send::<serde_json::Value>(request).await?;
+        if let Some(previous) = &record.id {
+            let request = self.client.delete(self.entry_url(previous));
+            match self.send::<serde_json::Value>(request).await {
+                // Already removed, e.g. in the web interface
+                Ok(_) | Err(ProviderError::NotFound(_)) => {}
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(Record {
+            id: Some(entry),
+            ..record.clone()
+        })
+    }
+}
@@ -0,0 +1,257 @@
+//! Porkbun provider using the v3 JSON API.
+//!
+//! Every call is a `POST` carrying the API key pair in its JSON body, even
+//! the read-only ones.
+
+use super::{
+    DnsProvider, Record, RecordType, Zone, extract_root_domain, relative_name, retry_after,
+};
+use crate::error::ProviderError;
+use async_trait::async_trait;
+use reqwest::{Client, StatusCode};
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use serde_json::{Value, json};
+use std::fmt;
+
+const API_BASE: &str = "https://api.porkbun.com/api/json/v3";
+/// Lowest TTL Porkbun accepts, also used for the automatic TTL (`1`)
+const MIN_TTL: u32 = 600;
+/// Domains returned per `domain/listAll` call
+const DOMAINS_PAGE_SIZE: usize = 1000;
+
+#[derive(Deserialize)]
+struct PorkbunError {
+    #[serde(default)]
+    message: String,
+}
+
+#[derive(Deserialize)]
+struct DomainsResponse {
+    #[serde(default)]
+    domains: Vec<PorkbunDomain>,
+}
+
+#[derive(Deserialize)]
+struct PorkbunDomain {
+    domain: String,
+}
+
+#[derive(Deserialize)]
+struct RecordsResponse {
+    #[serde(default)]
+    records: Vec<PorkbunRecord>,
+}
+
+#[derive(Deserialize)]
+struct PorkbunRecord {
+    id: String,
+    content: String,
+    /// A number in a string
+    ttl: String,
+}
+
+impl PorkbunRecord {
+    fn into_record(self, dns_name: &str, kind: RecordType) -> Record {
+        Record {
+            id: Some(self.id),
+            name: dns_name.to_string(),
+            kind,
+            content: self.content,
+            ttl: self.ttl.parse().unwrap_or(MIN_TTL),
+            proxied: None,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct CreateResponse {
+    id: Value,
+}
+
+/// `[providers.porkbun]` section of the config file
+#[derive(Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PorkbunConfig {
+    pub api_key: String,
+    pub secret_api_key: String,
+}
+
+impl fmt::Debug for PorkbunConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PorkbunConfig")
+            .field("api_key", &self.api_key)
+            .field("secret_api_key", &"<redacted>")
+            .finish()
+    }
+}
+
+pub struct PorkbunProvider {
+    client: Client,
+    api_key: String,
+    secret_api_key: String,
+    base_url: String,
+}
+
+impl PorkbunProvider {
+    pub fn new(client: Client, config: &PorkbunConfig) -> Self {
+        Self {
+            client,
+            api_key: config.api_key.clone(),
+            secret_api_key: config.secret_api_key.clone(),
+            base_url: API_BASE.to_string(),
+        }
+    }
+
+    /// Talk to a different API endpoint, e.g. a proxy or a mock server
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into().trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Post `body` with the API keys added and parse the JSON response
+    async fn send<T: DeserializeOwned>(
+        &self,
+        path: &str,
+        mut body: Value,
+    ) -> Result<T, ProviderError> {
+        body["apikey"] = self.api_key.clone().into();
+        body["secretapikey"] = self.secret_api_key.clone().into();
+        let resp = self
+            .client
+            .post(format!("{}{}", self.base_url, path))
+            .json(&body)
+            .send()
+            .await?;
+        let status = resp.status();
+
+        if status == StatusCode::TOO_MANY_REQUESTS {
+            let retry_after = retry_after(resp.headers());
+            return Err(ProviderError::RateLimited { retry_after });
+        }
+
+        let body = resp.text().await?;
+        if !status.is_success() {
+            let message = match serde_json::from_str::<PorkbunError>(&body) {
+                Ok(e) => e.message,
+                Err(_) => format!("HTTP {}", status.as_u16()),
+            };
+            // Bad keys are answered with a plain 400
+            let auth = status == StatusCode::UNAUTHORIZED
+                || status == StatusCode::FORBIDDEN
+                || message.contains("API key")
+                || message.contains("API access");
+            return Err(if auth {
+                ProviderError::Auth(message)
+            } else {
+                ProviderError::for_status(status, message)
+            });
+        }
+
+        serde_json::from_str(&body)
+            .map_err(|e| ProviderError::InvalidResponse(format!("HTTP {}: {}", status.as_u16(), e)))
+    }
+}
+
+/// TTL the API stores for a record configured with `ttl`
+fn stored_ttl(ttl: u32) -> u32 {
+    ttl.max(MIN_TTL)
+}
+
+#[async_trait]
+impl DnsProvider for PorkbunProvider {
+    fn name(&self) -> &str {
+        "porkbun"
+    }
+
+    fn effective_ttl(&self, record: &Record) -> Option<u32> {
+        Some(stored_ttl(record.ttl))
+    }
+
+    /// Find the root domain of `dns_name` among the account's domains; its
+    /// name doubles as the ID
+    async fn lookup_zone(&self, dns_name: &str) -> Result<Zone, ProviderError> {
+        let domain = extract_root_domain(dns_name);
+        let mut start = 0;
+        loop {
+            let resp: DomainsResponse = self
+                .send("/domain/listAll", json!({ "start": start.to_string() }))
+                .await?;
+            let count = resp.domains.len();
+
+            if let Some(found) = resp
+                .domains
+                .into_iter()
+                .find(|d| d.domain.eq_ignore_ascii_case(&domain))
+            {
+                return Ok(Zone {
+                    id: found.domain.clone(),
+                    name: found.domain,
+                });
+            }
+            if count < DOMAINS_PAGE_SIZE {
+                return Err(ProviderError::ZoneNotFound(domain));
+            }
+            start += count;
+        }
+    }
+
+    async fn read_record(
+        &self,
+        zone: &Zone,
+        dns_name: &str,
+        kind: RecordType,
+    ) -> Result<Option<Record>, ProviderError> {
+        let path = format!(
+            "/dns/retrieveByNameType/{}/{}/{}",
+            zone.id,
+            kind.as_str(),
+            relative_name(dns_name, &zone.name, "")
+        );
+        let resp: RecordsResponse = self.send(&path, json!({})).await?;
+        Ok(resp
+            .records
+            .into_iter()
+            .next()
+            .map(|r| r.into_record(dns_name, kind)))
+    }
+
+    async fn upsert_record(&self, zone: &Zone, record: &Record) -> Result<Record, ProviderError> {
+        let ttl = stored_ttl(record.ttl);
+        let subdomain = relative_name(&record.name, &zone.name, "");
+
+        let id = match &record.id {
+            Some(id) => {
+                let path = format!(
+                    "/dns/editByNameType/{}/{}/{}",
+                    zone.id,
+                    record.kind.as_str(),
+                    subdomain
+                );
+                let body = json!({ "content": record.content, "ttl": ttl.to_string() });
+                self.send::<Value>(&path, body).await?;
+                id.clone()
+            }
+            None => {
+                let body = json!({
+                    "name": subdomain,
+                    "type": record.kind.as_str(),
+                    "content": record.content,
+                    "ttl": ttl.to_string()
+                });
+                let created: CreateResponse =
+                    self.send(&format!("/dns/create/{}", zone.id), body).await?;
+                match created.id {
+                    Value::String(id) => id,
+                    id => id.to_string(),
+                }
+            }
+        };
+
+        Ok(Record {
+            id: Some(id),
+            ttl,
+            ..record.clone()
+        })
+    }
+}
@@ -0,0 +1,231 @@
+//! PowerDNS Authoritative provider using the built-in HTTP API.
+//!
+//! Records are addressed as RRsets by name and type, and updated by
+//! replacing the whole RRset in a zone PATCH.
+
+use super::{DnsProvider, Record, RecordType, Zone, retry_after};
+use crate::error::ProviderError;
+use async_trait::async_trait;
+use reqwest::{Client, RequestBuilder, StatusCode};
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use std::fmt;
+
+/// TTL used for records configured with the automatic TTL (`1`), which
+/// RRsets don't have
+const AUTO_TTL: u32 = 300;
+
+fn default_server_id() -> String {
+    "localhost".to_string()
+}
+
+#[derive(Deserialize)]
+struct PowerDnsError {
+    #[serde(default)]
+    error: String,
+}
+
+#[derive(Deserialize)]
+struct PowerDnsZone {
+    id: String,
+    /// Fully qualified, with the trailing dot
+    name: String,
+    #[serde(default)]
+    rrsets: Vec<RRset>,
+}
+
+#[derive(Deserialize)]
+struct RRset {
+    /// Fully qualified, with the trailing dot
+    name: String,
+    #[serde(rename = "type")]
+    kind: String,
+    ttl: u32,
+    records: Vec<RRsetRecord>,
+}
+
+#[derive(Deserialize)]
+struct RRsetRecord {
+    content: String,
+    #[serde(default)]
+    disabled: bool,
+}
+
+/// `dns_name` in canonical form, with the trailing dot
+fn canonical(dns_name: &str) -> String {
+    format!("{}.", dns_name.trim_end_matches('.'))
+}
+
+/// `[providers.powerdns]` section of the config file
+#[derive(Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PowerDnsConfig {
+    /// Address of the API (the `webserver-address` and `webserver-port`
+    /// settings), e.g. `http://127.0.0.1:8081`
+    pub url: String,
+    /// The `api-key` setting of the server
+    pub api_key: String,
+    /// Server to manage zones on
+    #[serde(default = "default_server_id")]
+    pub server_id: String,
+}
+
+impl fmt::Debug for PowerDnsConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PowerDnsConfig")
+            .field("url", &self.url)
+            .field("api_key", &"<redacted>")
+            .field("server_id", &self.server_id)
+            .finish()
+    }
+}
+
+pub struct PowerDnsProvider {
+    client: Client,
+    api_key: String,
+    /// `{url}/api/v1/servers/{server_id}`
+    server_url: String,
+}
+
+impl PowerDnsProvider {
+    pub fn new(client: Client, config: &PowerDnsConfig) -> Self {
+        Self {
+            client,
+            api_key: config.api_key.clone(),
+            server_url: format!(
+                "{}/api/v1/servers/{}",
+                config.url.trim_end_matches('/'),
+                config.server_id
+            ),
+        }
+    }
+
+    fn zone_url(&self, zone: &Zone) -> String {
+        format!("{}/zones/{}", self.server_url, zone.id)
+    }
+
+    /// Send an authenticated request and parse the JSON response
+    async fn send<T: DeserializeOwned>(&self, request: RequestBuilder) -> Result<T, ProviderError> {
+        let resp = request.header("X-API-Key", &self.api_key).send().await?;
+        let status = resp.status();
+
+        if status == StatusCode::TOO_MANY_REQUESTS {
+            let retry_after = retry_after(resp.headers());
+            return Err(ProviderError::RateLimited { retry_after });
+        }
+
+        let body = resp.text().await?;
+        if !status.is_success() {
+            let message = serde_json::from_str::<PowerDnsError>(&body)
+                .ok()
+                .map(|e| e.error)
+                .filter(|m| !m.is_empty())
+                .unwrap_or_else(|| format!("HTTP {}", status.as_u16()));
+            return Err(match status {
+                StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => ProviderError::Auth(message),
+                _ => ProviderError::for_status(status, message),
+            });
+        }
+
+        // Zone PATCHes answer with 204 and no body
+        let body = if body.trim().is_empty() {
+            "null"
+        } else {
+            &body
+        };
+        serde_json::from_str(body)
+            .map_err(|e| ProviderError::InvalidResponse(format!("HTTP {}: {}", status.as_u16(), e)))
+    }
+}
+
+/// TTL the API stores for a record configured with `ttl`
+fn stored_ttl(ttl: u32) -> u32 {
+    if ttl == 1 { AUTO_TTL } else { ttl }
+}
+
+#[async_trait]
+impl DnsProvider for PowerDnsProvider {
+    fn name(&self) -> &str {
+        "powerdns"
+    }
+
+    fn effective_ttl(&self, record: &Record) -> Option<u32> {
+        Some(stored_ttl(record.ttl))
+    }
+
+    /// Find the most specific zone on the server containing `dns_name`
+    async fn lookup_zone(&self, dns_name: &str) -> Result<Zone, ProviderError> {
+        let name = canonical(dns_name).to_lowercase();
+        let request = self.client.get(format!("{}/zones", self.server_url));
+        let zones: Vec<PowerDnsZone> = self.send(request).await?;
+
+        zones
+            .into_iter()
+            .filter(|z| {
+                let zone = z.name.to_lowercase();
+                name == zone || name.ends_with(&format!(".{}", zone))
+            })
+            .max_by_key(|z| z.name.len())
+            .map(|z| Zone {
+                id: z.id,
+                name: z.name.trim_end_matches('.').to_string(),
+            })
+            .ok_or_else(|| ProviderError::ZoneNotFound(dns_name.to_string()))
+    }
+
+    async fn read_record(
+        &self,
+        zone: &Zone,
+        dns_name: &str,
+        kind: RecordType,
+    ) -> Result<Option<Record>, ProviderError> {
+        let name = canonical(dns_name);
+        // Servers before 4.7 ignore the filter and return every RRset
+        let request = self
+            .client
+            .get(self.zone_url(zone))
+            .query(&[("rrset_name", name.as_str()), ("rrset_type", kind.as_str())]);
+        let resp: PowerDnsZone = self.send(request).await?;
+
+        Ok(resp
+            .rrsets
+            .into_iter()
+            .find(|r| r.kind == kind.as_str() && r.name.eq_ignore_ascii_case(&name))
+            .and_then(|rrset| {
+                let ttl = rrset.ttl;
+                rrset
+                    .records
+                    .into_iter()
+                    .find(|r| !r.disabled)
+                    .map(|r| Record {
+                        id: None,
+                        name: dns_name.to_string(),
+                        kind,
+                        content: r.content,
+                        ttl,
+                        proxied: None,
+                    })
+            }))
+    }
+
+    /// Replace the RRset of the name and type with a single record
+    async fn upsert_record(&self, zone: &Zone, record: &Record) -> Result<Record, ProviderError> {
+        let ttl = stored_ttl(record.ttl);
+        let body = serde_json::json!({
+            "rrsets": [{
+                "name": canonical(&record.name),
+                "type": record.kind.as_str(),
+                "ttl": ttl,
+                "changetype": "REPLACE",
+                "records": [{ "content": record.content, "disabled": false }]
+            }]
+        });
+
+        let request = self.client.patch(self.zone_url(zone)).json(&body);
+        self.send::<serde_json::Value>(request).await?;
+        Ok(Record {
+            ttl,
+            ..record.clone()
+        })
+    }
+}
@@ -0,0 +1,131 @@
+//! How long a rate limited provider wants us to wait.
+//!
+//! `Retry-After` holds a number of seconds or an HTTP date. Many APIs also
+//! say when their rate limit window resets instead: `RateLimit-Reset` or
+//! `X-RateLimit-Reset` with seconds or a Unix time, or the `t` parameter of
+//! the structured `RateLimit` header Cloudflare sends.
+
+use reqwest::header::{HeaderMap, RETRY_AFTER};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Reset values this large are Unix times rather than seconds to wait
+const UNIX_TIME_THRESHOLD: u64 = 1_000_000_000;
+
+/// Delay the headers of a rate limited response ask for, `None` if they
+/// don't say
+pub fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    retry_after_at(headers, SystemTime::now())
+}
+
+/// [`retry_after`] for a response received at `now`
+fn retry_after_at(headers: &HeaderMap, now: SystemTime) -> Option<Duration> {
+    let header = |name: &str| {
+        headers
+            .get(name)
+            .and_then(|v| v.to_str().ok())
+            .map(str::trim)
+    };
+    if let Some(value) = header(RETRY_AFTER.as_str()) {
+        if let Ok(secs) = value.parse() {
+            return Some(Duration::from_secs(secs));
+        }
+        // Any of the three HTTP date formats, a past date asks for no delay
+        if let Ok(at) = httpdate::parse_http_date(value) {
+            return Some(at.duration_since(now).unwrap_or_default());
+        }
+    }
+
+    let reset = header("ratelimit-reset")
+        .or_else(|| header("x-ratelimit-reset"))
+        .and_then(|v| v.parse::<u64>().ok())
+        .or_else(|| header("ratelimit").and_then(structured_reset))?;
+    if reset < UNIX_TIME_THRESHOLD {
+        return Some(Duration::from_secs(reset));
+    }
+    let at = UNIX_EPOCH.checked_add(Duration::from_secs(reset))?;
+    Some(at.duration_since(now).unwrap_or_default())
+}
+
+/// `t` parameter of a header like `RateLimit: "default";r=0;t=30`
+fn structured_reset(value: &str) -> Option<u64> {
+    value
+        .split([';', ','])
+        .find_map(|param| param.trim().strip_prefix("t=")?.parse().ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::HeaderValue;
+
+    /// Tue, 14 Nov 2023 22:13:20 GMT
+    fn now() -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(1_700_000_000)
+    }
+
+    fn wait(name: &'static str, value: &str) -> Option<Duration> {
+        let mut headers = HeaderMap::new();
+        headers.insert(name, HeaderValue::from_str(value).unwrap());
+        retry_after_at(&headers, now())
+    }
+
+    #[test]
+    fn retry_after_in_seconds() {
+        assert_eq!(wait("retry-after", "120"), Some(Duration::from_secs(120)));
+        assert_eq!(wait("retry-after", " 0 "), Some(Duration::ZERO));
+    }
+
+    #[test]
+    fn retry_after_in_each_http_date_format() {
+        let expected = Some(Duration::from_secs(37));
+        assert_eq!(
+            wait("retry-after", "Tue, 14 Nov 2023 22:13:57 GMT"),
+            expected
+        );
+        assert_eq!(
+            wait("retry-after", "Tuesday, 14-Nov-23 22:13:57 GMT"),
+            expected
+        );
+        assert_eq!(wait("retry-after", "Tue Nov 14 22:13:57 2023"), expected);
+    }
+
+    #[test]
+    fn retry_after_in_the_past_asks_for_no_delay() {
+        assert_eq!(
+            wait("retry-after", "Mon, 13 Nov 2023 22:13:57 GMT"),
+            Some(Duration::ZERO)
+        );
+    }
+
+    #[test]
+    fn malformed_retry_after_is_ignored() {
+        for value in [
+            "soon",
+            "-5",
+            "1.5",
+            "Tue, 14 Nov 2023 22:13:57",
+            "Tue, 32 Nov 2023 22:13:57 GMT",
+            "Tue, 14 Foo 2023 22:13:57 GMT",
+            "Tue, 14 Nov 2023 25:13:57 GMT",
+        ] {
+            assert_eq!(wait("retry-after", value), None, "{:?}", value);
+        }
+    }
+
+    #[test]
+    fn rate_limit_resets() {
+        assert_eq!(wait("ratelimit-reset", "30"), Some(Duration::from_secs(30)));
+        assert_eq!(
+            wait("x-ratelimit-reset", "1700000060"),
+            Some(Duration::from_secs(60))
+        );
+        assert_eq!(
+            wait("ratelimit", "\"default\";r=0;t=15"),
+            Some(Duration::from_secs(15))
+        );
+        assert_eq!(
+            wait("x-ratelimit-reset", "1699999000"),
+            Some(Duration::ZERO)
+        );
+    }
+}
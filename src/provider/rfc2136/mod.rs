@@ -0,0 +1,479 @@
+//! Provider for authoritative name servers accepting RFC 2136 dynamic
+//! updates, such as BIND, Knot or PowerDNS.
+//!
+//! Messages are sent over TCP and signed with a TSIG key, which the server
+//! must allow to update the zone. Records are read with regular signed
+//! queries to the same server.
+
+mod tsig;
+mod wire;
+
+use super::{DnsProvider, Record, RecordType, SrvRecord, TXT_TTL, Zone};
+use crate::error::{ConfigError, ProviderError};
+use async_trait::async_trait;
+use serde::Deserialize;
+use std::collections::hash_map::RandomState;
+use std::fmt;
+use std::hash::BuildHasher;
+use std::net::{IpAddr, SocketAddr};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tsig::TsigKey;
+use wire::{
+    CLASS_ANY, CLASS_IN, CLASS_NONE, MessageBuilder, OPCODE_QUERY, OPCODE_UPDATE, TYPE_SOA,
+    TYPE_SRV, TYPE_TXT,
+};
+
+const DEFAULT_PORT: u16 = 53;
+/// TTL used for records configured with the automatic TTL (`1`), which
+/// dynamic updates don't have
+const AUTO_TTL: u32 = 300;
+/// Limit for connecting to the server and for each exchange
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+fn default_algorithm() -> String {
+    tsig::ALGORITHM.to_string()
+}
+
+/// `server` with the default port added if it has none
+fn server_address(server: &str) -> String {
+    if server.parse::<SocketAddr>().is_ok() {
+        return server.to_string();
+    }
+    match server.parse::<IpAddr>() {
+        Ok(ip) => SocketAddr::new(ip, DEFAULT_PORT).to_string(),
+        Err(_) if server.contains(':') => server.to_string(),
+        Err(_) => format!("{}:{}", server, DEFAULT_PORT),
+    }
+}
+
+fn record_type(kind: RecordType) -> u16 {
+    match kind {
+        RecordType::A => wire::TYPE_A,
+        RecordType::Aaaa => wire::TYPE_AAAA,
+    }
+}
+
+/// RDATA of an address record
+fn address_rdata(record: &Record) -> Result<Vec<u8>, ProviderError> {
+    let invalid = || {
+        ProviderError::Api(format!(
+            "Invalid {} record content {:?}",
+            record.kind, record.content
+        ))
+    };
+    match (record.kind, record.content.parse::<IpAddr>()) {
+        (RecordType::A, Ok(IpAddr::V4(ip))) => Ok(ip.octets().to_vec()),
+        (RecordType::Aaaa, Ok(IpAddr::V6(ip))) => Ok(ip.octets().to_vec()),
+        _ => Err(invalid()),
+    }
+}
+
+/// RDATA of a TXT record holding `value`, split into character strings of
+/// at most 255 bytes
+fn txt_rdata(value: &str) -> Vec<u8> {
+    let mut rdata = Vec::new();
+    for chunk in value.as_bytes().chunks(255) {
+        rdata.push(chunk.len() as u8);
+        rdata.extend(chunk);
+    }
+    rdata
+}
+
+/// Value of a TXT record, its character strings joined
+fn txt_value(rdata: &[u8]) -> String {
+    let mut value = Vec::new();
+    let mut rest = rdata;
+    while let Some((&len, tail)) = rest.split_first() {
+        let len = usize::from(len).min(tail.len());
+        value.extend(&tail[..len]);
+        rest = &tail[len..];
+    }
+    String::from_utf8_lossy(&value).into_owned()
+}
+
+/// RDATA of an SRV record, with an uncompressed target
+fn srv_rdata(record: &SrvRecord) -> Result<Vec<u8>, ProviderError> {
+    let mut rdata = Vec::new();
+    rdata.extend(record.priority.to_be_bytes());
+    rdata.extend(record.weight.to_be_bytes());
+    rdata.extend(record.port.to_be_bytes());
+    wire::write_name(&mut rdata, &record.target)?;
+    Ok(rdata)
+}
+
+/// Address in the RDATA of an address record
+fn rdata_address(kind: RecordType, rdata: &[u8]) -> Option<IpAddr> {
+    match kind {
+        RecordType::A => <[u8; 4]>::try_from(rdata).ok().map(IpAddr::from),
+        RecordType::Aaaa => <[u8; 16]>::try_from(rdata).ok().map(IpAddr::from),
+    }
+}
+
+/// `[providers.rfc2136]` section of the config file
+#[derive(Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Rfc2136Config {
+    /// Primary name server of the zone, `host` or `host:port`
+    pub server: String,
+    /// Zone the records belong to
+    pub zone: String,
+    /// Name of the TSIG key
+    pub key_name: String,
+    /// Base64 secret of the TSIG key
+    pub key_secret: String,
+    /// Only `hmac-sha256` is supported
+    #[serde(default = "default_algorithm")]
+    pub key_algorithm: String,
+}
+
+impl fmt::Debug for Rfc2136Config {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Rfc2136Config")
+            .field("server", &self.server)
+            .field("zone", &self.zone)
+            .field("key_name", &self.key_name)
+            .field("key_secret", &"<redacted>")
+            .field("key_algorithm", &self.key_algorithm)
+            .finish()
+    }
+}
+
+pub struct Rfc2136Provider {
+    server: String,
+    zone: String,
+    key: TsigKey,
+}
+
+impl Rfc2136Provider {
+    pub fn new(config: &Rfc2136Config) -> Result<Self, ConfigError> {
+        if !config.key_algorithm.eq_ignore_ascii_case(tsig::ALGORITHM) {
+            return Err(ConfigError::Invalid(format!(
+                "Unsupported TSIG algorithm {:?}, only {} is supported",
+                config.key_algorithm,
+                tsig::ALGORITHM
+            )));
+        }
+        Ok(Self {
+            server: server_address(&config.server),
+            zone: config.zone.trim_end_matches('.').to_lowercase(),
+            key: TsigKey::new(&config.key_name, &config.key_secret)?,
+        })
+    }
+
+    fn network_error(&self, e: impl fmt::Display) -> ProviderError {
+        ProviderError::Unavailable(format!("Exchange with {} failed: {}", self.server, e))
+    }
+
+    /// Sign `msg`, send it and return the verified response
+    async fn exchange(&self, mut msg: Vec<u8>) -> Result<(Vec<u8>, wire::Response), ProviderError> {
+        let id = wire::read_u16(&msg, 0)?;
+        let request_mac = self.key.sign(&mut msg)?;
+
+        let exchange = async {
+            let mut stream = TcpStream::connect(&self.server).await?;
+            stream.write_all(&(msg.len() as u16).to_be_bytes()).await?;
+            stream.write_all(&msg).await?;
+            let len = stream.read_u16().await? as usize;
+            let mut buf = vec![0; len];
+            stream.read_exact(&mut buf).await?;
+            Ok::<_, std::io::Error>(buf)
+        };
+        let buf = tokio::time::timeout(TIMEOUT, exchange)
+            .await
+            .map_err(|e| self.network_error(e))?
+            .map_err(|e| self.network_error(e))?;
+
+        let response = wire::parse(&buf)?;
+        if response.id != id {
+            return Err(ProviderError::InvalidResponse(format!(
+                "response ID {} doesn't match request ID {}",
+                response.id, id
+            )));
+        }
+        let signed = response
+            .additional
+            .last()
+            .is_some_and(|r| r.kind == wire::TYPE_TSIG);
+        // Servers may answer errors unsigned
+        if signed || response.rcode == 0 {
+            self.key.verify(&buf, &response, &request_mac)?;
+        }
+        Ok((buf, response))
+    }
+
+    /// Error for a response code other than NOERROR
+    fn rcode_error(&self, rcode: u16) -> ProviderError {
+        let name = wire::rcode_name(rcode);
+        match rcode {
+            // NOTAUTH, BADSIG, BADKEY, BADTIME
+            9 | 16..=18 => ProviderError::Auth(format!("{} answered {}", self.server, name)),
+            5 => ProviderError::Auth(format!(
+                "{} refused the update, check that the key may update {}",
+                self.server, self.zone
+            )),
+            10 => ProviderError::ZoneNotFound(self.zone.clone()),
+            // SERVFAIL
+            2 => ProviderError::Unavailable(format!("{} answered {}", self.server, name)),
+            _ => ProviderError::Api(format!("{} answered {}", self.server, name)),
+        }
+    }
+
+    /// Add (class IN) or delete (class NONE) a single address, keeping the
+    /// rest of the record set
+    async fn update_address(
+        &self,
+        zone: &Zone,
+        record: &Record,
+        class: u16,
+        ttl: u32,
+    ) -> Result<(), ProviderError> {
+        let msg = MessageBuilder::new(message_id(), OPCODE_UPDATE)
+            .question(&zone.name, TYPE_SOA, CLASS_IN)?
+            .update(
+                &record.name,
+                record_type(record.kind),
+                class,
+                ttl,
+                &address_rdata(record)?,
+            )?
+            .finish();
+        let (_, response) = self.exchange(msg).await?;
+        match response.rcode {
+            0 => Ok(()),
+            rcode => Err(self.rcode_error(rcode)),
+        }
+    }
+
+    /// Add (class IN) or delete (class NONE) the TXT record of `dns_name`
+    /// holding `value`, leaving the other records of the name alone
+    async fn update_txt(
+        &self,
+        zone: &Zone,
+        dns_name: &str,
+        class: u16,
+        ttl: u32,
+        value: &str,
+    ) -> Result<(), ProviderError> {
+        let msg = MessageBuilder::new(message_id(), OPCODE_UPDATE)
+            .question(&zone.name, TYPE_SOA, CLASS_IN)?
+            .update(dns_name, TYPE_TXT, class, ttl, &txt_rdata(value))?
+            .finish();
+        let (_, response) = self.exchange(msg).await?;
+        match response.rcode {
+            0 => Ok(()),
+            rcode => Err(self.rcode_error(rcode)),
+        }
+    }
+}
+
+/// Random message ID
+fn message_id() -> u16 {
+    RandomState::new().hash_one(std::time::SystemTime::now()) as u16
+}
+
+/// TTL the API stores for a record configured with `ttl`
+fn stored_ttl(ttl: u32) -> u32 {
+    if ttl == 1 { AUTO_TTL } else { ttl }
+}
+
+#[async_trait]
+impl DnsProvider for Rfc2136Provider {
+    fn name(&self) -> &str {
+        "rfc2136"
+    }
+
+    fn effective_ttl(&self, record: &Record) -> Option<u32> {
+        Some(stored_ttl(record.ttl))
+    }
+
+    /// The configured zone, if it contains `dns_name`
+    async fn lookup_zone(&self, dns_name: &str) -> Result<Zone, ProviderError> {
+        let name = dns_name.trim_end_matches('.').to_lowercase();
+        if name != self.zone && !name.ends_with(&format!(".{}", self.zone)) {
+            return Err(ProviderError::ZoneNotFound(dns_name.to_string()));
+        }
+        Ok(Zone {
+            id: self.zone.clone(),
+            name: self.zone.clone(),
+        })
+    }
+
+    async fn read_record(
+        &self,
+        zone: &Zone,
+        dns_name: &str,
+        kind: RecordType,
+    ) -> Result<Option<Record>, ProviderError> {
+        Ok(self
+            .read_record_set(zone, dns_name, kind)
+            .await?
+            .into_iter()
+            .next())
+    }
+
+    /// Replace the record set with a single address
+    async fn upsert_record(&self, zone: &Zone, record: &Record) -> Result<Record, ProviderError> {
+        let ttl = stored_ttl(record.ttl);
+        let kind = record_type(record.kind);
+        let msg = MessageBuilder::new(message_id(), OPCODE_UPDATE)
+            .question(&zone.name, TYPE_SOA, CLASS_IN)?
+            // Delete the RRset, then add the new address
+            .update(&record.name, kind, CLASS_ANY, 0, &[])?
+            .update(&record.name, kind, CLASS_IN, ttl, &address_rdata(record)?)?
+            .finish();
+
+        let (_, response) = self.exchange(msg).await?;
+        if response.rcode != 0 {
+            return Err(self.rcode_error(response.rcode));
+        }
+        Ok(Record {
+            id: None,
+            ttl,
+            ..record.clone()
+        })
+    }
+
+    async fn read_record_set(
+        &self,
+        _zone: &Zone,
+        dns_name: &str,
+        kind: RecordType,
+    ) -> Result<Vec<Record>, ProviderError> {
+        let msg = MessageBuilder::new(message_id(), OPCODE_QUERY)
+            .question(dns_name, record_type(kind), CLASS_IN)?
+            .finish();
+        let (buf, response) = self.exchange(msg).await?;
+        match response.rcode {
+            0 => {}
+            // NXDOMAIN
+            3 => return Ok(Vec::new()),
+            rcode => return Err(self.rcode_error(rcode)),
+        }
+
+        let name = dns_name.trim_end_matches('.').to_lowercase();
+        Ok(response
+            .answers
+            .iter()
+            .filter(|r| r.kind == record_type(kind) && r.name == name)
+            .filter_map(|r| {
+                let ip = rdata_address(kind, &buf[r.rdata.clone()])?;
+                Some(Record {
+                    id: None,
+                    name: dns_name.to_string(),
+                    kind,
+                    content: ip.to_string(),
+                    ttl: r.ttl,
+                    proxied: None,
+                })
+            })
+            .collect())
+    }
+
+    async fn add_address(&self, zone: &Zone, record: &Record) -> Result<Record, ProviderError> {
+        let ttl = stored_ttl(record.ttl);
+        self.update_address(zone, record, CLASS_IN, ttl).await?;
+        Ok(Record {
+            id: None,
+            ttl,
+            ..record.clone()
+        })
+    }
+
+    async fn delete_address(&self, zone: &Zone, record: &Record) -> Result<(), ProviderError> {
+        self.update_address(zone, record, CLASS_NONE, 0).await
+    }
+
+    async fn read_srv(
+        &self,
+        _zone: &Zone,
+        dns_name: &str,
+    ) -> Result<Option<SrvRecord>, ProviderError> {
+        let msg = MessageBuilder::new(message_id(), OPCODE_QUERY)
+            .question(dns_name, TYPE_SRV, CLASS_IN)?
+            .finish();
+        let (buf, response) = self.exchange(msg).await?;
+        match response.rcode {
+            0 => {}
+            // NXDOMAIN
+            3 => return Ok(None),
+            rcode => return Err(self.rcode_error(rcode)),
+        }
+
+        let name = dns_name.trim_end_matches('.').to_lowercase();
+        let Some(answer) = response
+            .answers
+            .iter()
+            .find(|r| r.kind == TYPE_SRV && r.name == name)
+        else {
+            return Ok(None);
+        };
+        // The target may be compressed, so it is read from the whole message
+        let at = answer.rdata.start;
+        let mut target_at = at + 6;
+        Ok(Some(SrvRecord {
+            name: dns_name.to_string(),
+            priority: wire::read_u16(&buf, at)?,
+            weight: wire::read_u16(&buf, at + 2)?,
+            port: wire::read_u16(&buf, at + 4)?,
+            target: wire::read_name(&buf, &mut target_at)?,
+            ttl: answer.ttl,
+        }))
+    }
+
+    /// Replace the record set with a single SRV record
+    async fn upsert_srv(&self, zone: &Zone, record: &SrvRecord) -> Result<(), ProviderError> {
+        let msg = MessageBuilder::new(message_id(), OPCODE_UPDATE)
+            .question(&zone.name, TYPE_SOA, CLASS_IN)?
+            .update(&record.name, TYPE_SRV, CLASS_ANY, 0, &[])?
+            .update(
+                &record.name,
+                TYPE_SRV,
+                CLASS_IN,
+                stored_ttl(record.ttl),
+                &srv_rdata(record)?,
+            )?
+            .finish();
+        let (_, response) = self.exchange(msg).await?;
+        match response.rcode {
+            0 => Ok(()),
+            rcode => Err(self.rcode_error(rcode)),
+        }
+    }
+
+    async fn read_txt(&self, _zone: &Zone, dns_name: &str) -> Result<Vec<String>, ProviderError> {
+        let msg = MessageBuilder::new(message_id(), OPCODE_QUERY)
+            .question(dns_name, TYPE_TXT, CLASS_IN)?
+            .finish();
+        let (buf, response) = self.exchange(msg).await?;
+        match response.rcode {
+            0 => {}
+            // NXDOMAIN
+            3 => return Ok(Vec::new()),
+            rcode => return Err(self.rcode_error(rcode)),
+        }
+
+        let name = dns_name.trim_end_matches('.').to_lowercase();
+        Ok(response
+            .answers
+            .iter()
+            .filter(|r| r.kind == TYPE_TXT && r.name == name)
+            .map(|r| txt_value(&buf[r.rdata.clone()]))
+            .collect())
+    }
+
+    async fn add_txt(&self, zone: &Zone, dns_name: &str, value: &str) -> Result<(), ProviderError> {
+        self.update_txt(zone, dns_name, CLASS_IN, TXT_TTL, value)
+            .await
+    }
+
+    async fn delete_txt(
+        &self,
+        zone: &Zone,
+        dns_name: &str,
+        value: &str,
+    ) -> Result<(), ProviderError> {
+        self.update_txt(zone, dns_name, CLASS_NONE, 0, value).await
+    }
+}
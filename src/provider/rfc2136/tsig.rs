@@ -0,0 +1,211 @@
+//! TSIG (RFC 8945) signatures with HMAC-SHA256.
+
+use super::wire::{self, CLASS_ANY, Response, TYPE_TSIG};
+use crate::error::{ConfigError, ProviderError};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use hmac::{Hmac, KeyInit, Mac};
+use sha2::Sha256;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub const ALGORITHM: &str = "hmac-sha256";
+/// Allowed difference between the signing time and the verifier's clock
+const FUDGE: u16 = 300;
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+/// Fields of a TSIG record after its MAC
+struct TsigRecord {
+    key_name: String,
+    algorithm: String,
+    time_signed: u64,
+    fudge: u16,
+    mac: Vec<u8>,
+    original_id: u16,
+    error: u16,
+    other: Vec<u8>,
+    /// Offset of the record in the message
+    start: usize,
+}
+
+impl TsigRecord {
+    fn parse(msg: &[u8], record: &wire::ResourceRecord) -> Result<Self, ProviderError> {
+        let truncated = || ProviderError::InvalidResponse("truncated TSIG record".into());
+        let mut pos = record.rdata.start;
+        let algorithm = wire::read_name(msg, &mut pos)?;
+        let time = msg.get(pos..pos + 6).ok_or_else(truncated)?;
+        let time_signed = time.iter().fold(0u64, |t, b| t << 8 | *b as u64);
+        let fudge = wire::read_u16(msg, pos + 6)?;
+        let mac_len = wire::read_u16(msg, pos + 8)? as usize;
+        pos += 10;
+        let mac = msg.get(pos..pos + mac_len).ok_or_else(truncated)?.to_vec();
+        pos += mac_len;
+        let original_id = wire::read_u16(msg, pos)?;
+        let error = wire::read_u16(msg, pos + 2)?;
+        let other_len = wire::read_u16(msg, pos + 4)? as usize;
+        pos += 6;
+        let other = msg
+            .get(pos..pos + other_len)
+            .ok_or_else(truncated)?
+            .to_vec();
+        if pos + other_len > record.rdata.end {
+            return Err(truncated());
+        }
+
+        Ok(Self {
+            key_name: record.name.clone(),
+            algorithm,
+            time_signed,
+            fudge,
+            mac,
+            original_id,
+            error,
+            other,
+            start: record.start,
+        })
+    }
+}
+
+/// Append the TSIG variables covered by the MAC
+fn write_variables(
+    buf: &mut Vec<u8>,
+    key_name: &str,
+    algorithm: &str,
+    time_signed: u64,
+    fudge: u16,
+    error: u16,
+    other: &[u8],
+) -> Result<(), ProviderError> {
+    wire::write_name(buf, key_name)?;
+    buf.extend(CLASS_ANY.to_be_bytes());
+    buf.extend(0u32.to_be_bytes());
+    wire::write_name(buf, algorithm)?;
+    buf.extend(&time_signed.to_be_bytes()[2..]);
+    buf.extend(fudge.to_be_bytes());
+    buf.extend(error.to_be_bytes());
+    buf.extend((other.len() as u16).to_be_bytes());
+    buf.extend(other);
+    Ok(())
+}
+
+/// A shared secret known to the name server under `name`
+pub struct TsigKey {
+    name: String,
+    secret: Vec<u8>,
+}
+
+impl TsigKey {
+    /// Key `name` with its secret in base64, as printed by `tsig-keygen`
+    pub fn new(name: &str, secret: &str) -> Result<Self, ConfigError> {
+        let secret = STANDARD
+            .decode(secret.trim())
+            .map_err(|e| ConfigError::Invalid(format!("Invalid TSIG key secret: {}", e)))?;
+        Ok(Self {
+            name: name.trim_end_matches('.').to_lowercase(),
+            secret,
+        })
+    }
+
+    fn mac(&self) -> Hmac<Sha256> {
+        Hmac::<Sha256>::new_from_slice(&self.secret).expect("HMAC accepts any key length")
+    }
+
+    /// Sign `msg`, appending the TSIG record. Returns the MAC the response
+    /// signature covers.
+    pub fn sign(&self, msg: &mut Vec<u8>) -> Result<Vec<u8>, ProviderError> {
+        let time_signed = now();
+        let mut data = msg.clone();
+        write_variables(&mut data, &self.name, ALGORITHM, time_signed, FUDGE, 0, &[])?;
+        let mut mac = self.mac();
+        mac.update(&data);
+        let mac = mac.finalize().into_bytes().to_vec();
+
+        let original_id = wire::read_u16(msg, 0)?;
+        let mut rdata = Vec::new();
+        wire::write_name(&mut rdata, ALGORITHM)?;
+        rdata.extend(&time_signed.to_be_bytes()[2..]);
+        rdata.extend(FUDGE.to_be_bytes());
+        rdata.extend((mac.len() as u16).to_be_bytes());
+        rdata.extend(&mac);
+        rdata.extend(original_id.to_be_bytes());
+        // No error, no other data
+        rdata.extend([0; 4]);
+
+        wire::write_name(msg, &self.name)?;
+        msg.extend(TYPE_TSIG.to_be_bytes());
+        msg.extend(CLASS_ANY.to_be_bytes());
+        msg.extend(0u32.to_be_bytes());
+        msg.extend((rdata.len() as u16).to_be_bytes());
+        msg.extend(rdata);
+        wire::increment_arcount(msg);
+        Ok(mac)
+    }
+
+    /// Check the signature of a response to a request signed with
+    /// `request_mac`
+    pub fn verify(
+        &self,
+        msg: &[u8],
+        response: &Response,
+        request_mac: &[u8],
+    ) -> Result<(), ProviderError> {
+        let record = response
+            .additional
+            .last()
+            .filter(|r| r.kind == TYPE_TSIG)
+            .ok_or_else(|| ProviderError::Auth("Response is not signed".into()))?;
+        let tsig = TsigRecord::parse(msg, record)?;
+
+        // Errors are answered without a MAC
+        if tsig.error != 0 {
+            return Err(ProviderError::Auth(format!(
+                "TSIG key {} rejected: {}",
+                self.name,
+                wire::rcode_name(tsig.error)
+            )));
+        }
+        if tsig.key_name != self.name || tsig.algorithm != ALGORITHM {
+            return Err(ProviderError::Auth(format!(
+                "Response signed with unexpected key {} ({})",
+                tsig.key_name, tsig.algorithm
+            )));
+        }
+
+        // The MAC covers the message as it was before the TSIG record was
+        // added, with the ID of the request
+        let mut data = Vec::with_capacity(msg.len() + request_mac.len() + 2);
+        data.extend((request_mac.len() as u16).to_be_bytes());
+        data.extend(request_mac);
+        let start = data.len();
+        data.extend(&msg[..tsig.start]);
+        data[start..start + 2].copy_from_slice(&tsig.original_id.to_be_bytes());
+        let arcount = wire::read_u16(msg, 10)?.saturating_sub(1);
+        data[start + 10..start + 12].copy_from_slice(&arcount.to_be_bytes());
+        write_variables(
+            &mut data,
+            &tsig.key_name,
+            &tsig.algorithm,
+            tsig.time_signed,
+            tsig.fudge,
+            tsig.error,
+            &tsig.other,
+        )?;
+
+        let mut mac = self.mac();
+        mac.update(&data);
+        mac.verify_slice(&tsig.mac)
+            .map_err(|_| ProviderError::Auth("Invalid TSIG signature on response".into()))?;
+
+        if now().abs_diff(tsig.time_signed) > tsig.fudge as u64 {
+            return Err(ProviderError::Auth(
+                "Response signed too far from the local time, check the clock".into(),
+            ));
+        }
+        Ok(())
+    }
+}
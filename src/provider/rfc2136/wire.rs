@@ -0,0 +1,260 @@
+//! Just enough of the DNS wire format (RFC 1035) to query and update
+//! address, TXT and SRV records.
+
+use crate::error::ProviderError;
+use std::ops::Range;
+
+pub const TYPE_A: u16 = 1;
+pub const TYPE_SOA: u16 = 6;
+pub const TYPE_TXT: u16 = 16;
+pub const TYPE_AAAA: u16 = 28;
+pub const TYPE_SRV: u16 = 33;
+pub const TYPE_TSIG: u16 = 250;
+
+pub const CLASS_IN: u16 = 1;
+/// Deletes a single record in an update (RFC 2136 section 2.5.4)
+pub const CLASS_NONE: u16 = 254;
+pub const CLASS_ANY: u16 = 255;
+
+pub const OPCODE_QUERY: u16 = 0;
+pub const OPCODE_UPDATE: u16 = 5;
+
+const HEADER_LEN: usize = 12;
+/// Offset of the additional record count in the header
+const ARCOUNT_OFFSET: usize = 10;
+/// Compression pointers followed before a name is considered a loop
+const MAX_POINTERS: usize = 64;
+
+fn truncated() -> ProviderError {
+    ProviderError::InvalidResponse("truncated DNS message".into())
+}
+
+/// Append `name` in uncompressed wire format, lowercased as TSIG requires
+pub fn write_name(buf: &mut Vec<u8>, name: &str) -> Result<(), ProviderError> {
+    let name = name.trim_end_matches('.');
+    let start = buf.len();
+    if !name.is_empty() {
+        for label in name.split('.') {
+            if label.is_empty() || label.len() > 63 {
+                return Err(ProviderError::Api(format!("Invalid DNS name {:?}", name)));
+            }
+            buf.push(label.len() as u8);
+            buf.extend(label.bytes().map(|b| b.to_ascii_lowercase()));
+        }
+    }
+    buf.push(0);
+    if buf.len() - start > 255 {
+        return Err(ProviderError::Api(format!("DNS name too long: {}", name)));
+    }
+    Ok(())
+}
+
+/// Builds a message section by section
+pub struct MessageBuilder {
+    buf: Vec<u8>,
+    /// Entries in the question (zone), answer (prerequisite), authority
+    /// (update) and additional sections
+    counts: [u16; 4],
+}
+
+impl MessageBuilder {
+    pub fn new(id: u16, opcode: u16) -> Self {
+        let mut buf = Vec::with_capacity(512);
+        buf.extend(id.to_be_bytes());
+        buf.extend((opcode << 11).to_be_bytes());
+        buf.extend([0; 8]);
+        Self {
+            buf,
+            counts: [0; 4],
+        }
+    }
+
+    /// Add an entry to the question section, the zone section of updates
+    pub fn question(mut self, name: &str, kind: u16, class: u16) -> Result<Self, ProviderError> {
+        write_name(&mut self.buf, name)?;
+        self.buf.extend(kind.to_be_bytes());
+        self.buf.extend(class.to_be_bytes());
+        self.counts[0] += 1;
+        Ok(self)
+    }
+
+    /// Add a record to the authority section, the update section of updates
+    pub fn update(
+        mut self,
+        name: &str,
+        kind: u16,
+        class: u16,
+        ttl: u32,
+        rdata: &[u8],
+    ) -> Result<Self, ProviderError> {
+        write_name(&mut self.buf, name)?;
+        self.buf.extend(kind.to_be_bytes());
+        self.buf.extend(class.to_be_bytes());
+        self.buf.extend(ttl.to_be_bytes());
+        self.buf.extend((rdata.len() as u16).to_be_bytes());
+        self.buf.extend(rdata);
+        self.counts[2] += 1;
+        Ok(self)
+    }
+
+    pub fn finish(mut self) -> Vec<u8> {
+        for (i, count) in self.counts.iter().enumerate() {
+            let at = 4 + 2 * i;
+            self.buf[at..at + 2].copy_from_slice(&count.to_be_bytes());
+        }
+        self.buf
+    }
+}
+
+/// Add one to the additional record count of `msg`
+pub fn increment_arcount(msg: &mut [u8]) {
+    let count = read_u16(msg, ARCOUNT_OFFSET).unwrap_or_default();
+    msg[ARCOUNT_OFFSET..ARCOUNT_OFFSET + 2].copy_from_slice(&(count + 1).to_be_bytes());
+}
+
+pub fn read_u16(msg: &[u8], at: usize) -> Result<u16, ProviderError> {
+    msg.get(at..at + 2)
+        .map(|b| u16::from_be_bytes([b[0], b[1]]))
+        .ok_or_else(truncated)
+}
+
+pub fn read_u32(msg: &[u8], at: usize) -> Result<u32, ProviderError> {
+    msg.get(at..at + 4)
+        .map(|b| u32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+        .ok_or_else(truncated)
+}
+
+/// Read the possibly compressed name at `*pos`, lowercased and without the
+/// trailing dot, and move `*pos` past it
+pub fn read_name(msg: &[u8], pos: &mut usize) -> Result<String, ProviderError> {
+    let mut labels: Vec<String> = Vec::new();
+    let mut at = *pos;
+    let mut end = None;
+    let mut pointers = 0;
+    loop {
+        let len = *msg.get(at).ok_or_else(truncated)? as usize;
+        match len {
+            0 => {
+                end.get_or_insert(at + 1);
+                break;
+            }
+            len if len & 0xC0 == 0xC0 => {
+                let target = (read_u16(msg, at)? & 0x3FFF) as usize;
+                end.get_or_insert(at + 2);
+                pointers += 1;
+                if pointers > MAX_POINTERS {
+                    return Err(ProviderError::InvalidResponse(
+                        "compression loop in DNS name".into(),
+                    ));
+                }
+                at = target;
+            }
+            len => {
+                let label = msg.get(at + 1..at + 1 + len).ok_or_else(truncated)?;
+                labels.push(String::from_utf8_lossy(label).to_lowercase());
+                at += 1 + len;
+            }
+        }
+    }
+    *pos = end.unwrap_or(at);
+    Ok(labels.join("."))
+}
+
+/// A resource record of a parsed message
+pub struct ResourceRecord {
+    pub name: String,
+    pub kind: u16,
+    pub ttl: u32,
+    /// Offset of the record in the message
+    pub start: usize,
+    /// Position of the RDATA in the message
+    pub rdata: Range<usize>,
+}
+
+/// The parts of a response the provider looks at
+pub struct Response {
+    pub id: u16,
+    pub rcode: u16,
+    pub answers: Vec<ResourceRecord>,
+    pub additional: Vec<ResourceRecord>,
+}
+
+fn read_record(msg: &[u8], pos: &mut usize) -> Result<ResourceRecord, ProviderError> {
+    let start = *pos;
+    let name = read_name(msg, pos)?;
+    let kind = read_u16(msg, *pos)?;
+    let ttl = read_u32(msg, *pos + 4)?;
+    let len = read_u16(msg, *pos + 8)? as usize;
+    let rdata = *pos + 10..*pos + 10 + len;
+    if rdata.end > msg.len() {
+        return Err(truncated());
+    }
+    *pos = rdata.end;
+    Ok(ResourceRecord {
+        name,
+        kind,
+        ttl,
+        start,
+        rdata,
+    })
+}
+
+pub fn parse(msg: &[u8]) -> Result<Response, ProviderError> {
+    if msg.len() < HEADER_LEN {
+        return Err(truncated());
+    }
+    let id = read_u16(msg, 0)?;
+    let flags = read_u16(msg, 2)?;
+    if flags & 0x8000 == 0 {
+        return Err(ProviderError::InvalidResponse(
+            "DNS message is not a response".into(),
+        ));
+    }
+    let counts = [
+        read_u16(msg, 4)?,
+        read_u16(msg, 6)?,
+        read_u16(msg, 8)?,
+        read_u16(msg, ARCOUNT_OFFSET)?,
+    ];
+
+    let mut pos = HEADER_LEN;
+    for _ in 0..counts[0] {
+        read_name(msg, &mut pos)?;
+        pos += 4;
+    }
+    let mut sections = [Vec::new(), Vec::new(), Vec::new()];
+    for (section, &count) in sections.iter_mut().zip(&counts[1..]) {
+        for _ in 0..count {
+            section.push(read_record(msg, &mut pos)?);
+        }
+    }
+    let [answers, _, additional] = sections;
+
+    Ok(Response {
+        id,
+        rcode: flags & 0x000F,
+        answers,
+        additional,
+    })
+}
+
+/// Name of a response code, for error messages
+pub fn rcode_name(rcode: u16) -> String {
+    match rcode {
+        0 => "NOERROR".into(),
+        1 => "FORMERR".into(),
+        2 => "SERVFAIL".into(),
+        3 => "NXDOMAIN".into(),
+        4 => "NOTIMP".into(),
+        5 => "REFUSED".into(),
+        6 => "YXDOMAIN".into(),
+        7 => "YXRRSET".into(),
+        8 => "NXRRSET".into(),
+        9 => "NOTAUTH".into(),
+        10 => "NOTZONE".into(),
+        16 => "BADSIG".into(),
+        17 => "BADKEY".into(),
+        18 => "BADTIME".into(),
+        rcode => format!("RCODE {}", rcode),
+    }
+}
@@ -0,0 +1,193 @@
+//! AWS credential chain: environment, shared credentials file, then the
+//! EC2 instance metadata service (IMDSv2).
+
+use super::sigv4::Credentials;
+use crate::error::ProviderError;
+use crate::provider::timestamp;
+use log::debug;
+use reqwest::Client;
+use serde::Deserialize;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
+use std::{env, fs};
+use tokio::sync::Mutex;
+
+const IMDS_ENDPOINT: &str = "http://169.254.169.254";
+const IMDS_TIMEOUT: Duration = Duration::from_secs(1);
+/// Refresh temporary credentials this long before they expire
+const EXPIRY_MARGIN: Duration = Duration::from_secs(300);
+
+/// Where the credentials of a [`Route53Provider`](super::Route53Provider)
+/// come from
+pub enum CredentialSource {
+    /// Keys given in the config file
+    Static(Credentials),
+    /// The standard AWS chain, resolved on first use and cached
+    Chain {
+        profile: Option<String>,
+        cached: Mutex<Option<Credentials>>,
+    },
+}
+
+impl CredentialSource {
+    pub fn chain(profile: Option<String>) -> Self {
+        CredentialSource::Chain {
+            profile,
+            cached: Mutex::new(None),
+        }
+    }
+
+    pub async fn get(&self, client: &Client) -> Result<Credentials, ProviderError> {
+        let (profile, cached) = match self {
+            CredentialSource::Static(credentials) => return Ok(credentials.clone()),
+            CredentialSource::Chain { profile, cached } => (profile, cached),
+        };
+
+        let mut cached = cached.lock().await;
+        if let Some(credentials) = cached.as_ref() {
+            let fresh = credentials
+                .expires
+                .is_none_or(|t| SystemTime::now() + EXPIRY_MARGIN < t);
+            if fresh {
+                return Ok(credentials.clone());
+            }
+        }
+
+        let credentials = resolve(profile.as_deref(), client).await?;
+        *cached = Some(credentials.clone());
+        Ok(credentials)
+    }
+}
+
+async fn resolve(profile: Option<&str>, client: &Client) -> Result<Credentials, ProviderError> {
+    if let Some(credentials) = from_env() {
+        debug!("Using AWS credentials from the environment");
+        return Ok(credentials);
+    }
+    if let Some(credentials) = from_profile(profile) {
+        debug!("Using AWS credentials from the shared credentials file");
+        return Ok(credentials);
+    }
+    match from_imds(client).await {
+        Ok(credentials) => {
+            debug!("Using AWS credentials of the instance role");
+            Ok(credentials)
+        }
+        Err(e) => {
+            debug!("Instance metadata service unavailable: {}", e);
+            Err(ProviderError::Auth(
+                "no AWS credentials found (environment, shared credentials file or instance role)"
+                    .to_string(),
+            ))
+        }
+    }
+}
+
+fn from_env() -> Option<Credentials> {
+    Some(Credentials {
+        access_key_id: env::var("AWS_ACCESS_KEY_ID").ok()?,
+        secret_access_key: env::var("AWS_SECRET_ACCESS_KEY").ok()?,
+        session_token: env::var("AWS_SESSION_TOKEN").ok(),
+        expires: None,
+    })
+}
+
+fn credentials_file() -> Option<PathBuf> {
+    if let Ok(path) = env::var("AWS_SHARED_CREDENTIALS_FILE") {
+        return Some(PathBuf::from(path));
+    }
+    let home = env::var_os("HOME").or_else(|| env::var_os("USERPROFILE"))?;
+    Some(PathBuf::from(home).join(".aws").join("credentials"))
+}
+
+fn from_profile(profile: Option<&str>) -> Option<Credentials> {
+    let profile = profile
+        .map(str::to_string)
+        .or_else(|| env::var("AWS_PROFILE").ok())
+        .unwrap_or_else(|| "default".to_string());
+    let raw = fs::read_to_string(credentials_file()?).ok()?;
+
+    let (mut in_profile, mut key, mut secret, mut token) = (false, None, None, None);
+    for line in raw.lines().map(str::trim) {
+        if line.starts_with('#') || line.starts_with(';') {
+            continue;
+        }
+        if let Some(section) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            in_profile = section.trim() == profile;
+            continue;
+        }
+        if !in_profile {
+            continue;
+        }
+        if let Some((k, v)) = line.split_once('=') {
+            let value = Some(v.trim().to_string());
+            match k.trim() {
+                "aws_access_key_id" => key = value,
+                "aws_secret_access_key" => secret = value,
+                "aws_session_token" => token = value,
+                _ => {}
+            }
+        }
+    }
+
+    Some(Credentials {
+        access_key_id: key?,
+        secret_access_key: secret?,
+        session_token: token,
+        expires: None,
+    })
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct ImdsCredentials {
+    access_key_id: String,
+    secret_access_key: String,
+    token: String,
+    expiration: String,
+}
+
+async fn from_imds(client: &Client) -> Result<Credentials, reqwest::Error> {
+    let endpoint =
+        env::var("AWS_EC2_METADATA_SERVICE_ENDPOINT").unwrap_or_else(|_| IMDS_ENDPOINT.into());
+    let endpoint = endpoint.trim_end_matches('/');
+
+    let token = client
+        .put(format!("{}/latest/api/token", endpoint))
+        .header("x-aws-ec2-metadata-token-ttl-seconds", "21600")
+        .timeout(IMDS_TIMEOUT)
+        .send()
+        .await?
+        .error_for_status()?
+        .text()
+        .await?;
+
+    let base = format!("{}/latest/meta-data/iam/security-credentials/", endpoint);
+    let role = client
+        .get(&base)
+        .header("x-aws-ec2-metadata-token", &token)
+        .timeout(IMDS_TIMEOUT)
+        .send()
+        .await?
+        .error_for_status()?
+        .text()
+        .await?;
+    let role = role.lines().next().unwrap_or_default().trim();
+
+    let creds: ImdsCredentials = client
+        .get(format!("{}{}", base, role))
+        .header("x-aws-ec2-metadata-token", &token)
+        .timeout(IMDS_TIMEOUT)
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+
+    Ok(Credentials {
+        access_key_id: creds.access_key_id,
+        secret_access_key: creds.secret_access_key,
+        session_token: Some(creds.token),
+        expires: timestamp::parse(&creds.expiration),
+    })
+}
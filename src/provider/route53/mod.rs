@@ -0,0 +1,503 @@
+//! AWS Route53 DNS provider using the `2013-04-01` REST API.
+//!
+//! Requests are signed with SigV4; credentials come from the config file or
+//! the standard AWS chain (environment, shared credentials file, instance
+//! role).
+
+mod credentials;
+mod sigv4;
+
+use super::{DnsProvider, Record, RecordType, Zone, ascii_name, extract_root_domain, retry_after};
+use crate::error::ProviderError;
+use async_trait::async_trait;
+use credentials::CredentialSource;
+use reqwest::{Client, Method, StatusCode, Url};
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use sigv4::Credentials;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::time::SystemTime;
+
+const API_BASE: &str = "https://route53.amazonaws.com";
+const API_VERSION: &str = "2013-04-01";
+/// Route53 is a global service signed for `us-east-1`
+const SIGNING_REGION: &str = "us-east-1";
+/// TTL used for records configured with the automatic TTL (`1`), which
+/// Route53 doesn't have
+const AUTO_TTL: u32 = 300;
+
+/// Error codes meaning the credentials are invalid or lack permissions
+const AUTH_ERROR_CODES: [&str; 7] = [
+    "AccessDenied",
+    "ExpiredToken",
+    "IncompleteSignature",
+    "InvalidClientTokenId",
+    "MissingAuthenticationToken",
+    "SignatureDoesNotMatch",
+    "UnrecognizedClientException",
+];
+/// Error codes asking us to slow down
+const THROTTLING_ERROR_CODES: [&str; 3] = [
+    "Throttling",
+    "ThrottlingException",
+    "PriorRequestNotComplete",
+];
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct ErrorResponse {
+    error: AwsError,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct AwsError {
+    code: String,
+    #[serde(default)]
+    message: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct ListHostedZonesResponse {
+    hosted_zones: HostedZones,
+    is_truncated: bool,
+    #[serde(rename = "NextDNSName")]
+    next_dns_name: Option<String>,
+    next_hosted_zone_id: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct HostedZones {
+    #[serde(rename = "HostedZone", default)]
+    zones: Vec<HostedZone>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct HostedZone {
+    id: String,
+    name: String,
+    config: Option<HostedZoneConfig>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct HostedZoneConfig {
+    #[serde(default)]
+    private_zone: bool,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct ListRecordSetsResponse {
+    resource_record_sets: RecordSets,
+}
+
+#[derive(Deserialize)]
+struct RecordSets {
+    #[serde(rename = "ResourceRecordSet", default)]
+    sets: Vec<RecordSet>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct RecordSet {
+    name: String,
+    #[serde(rename = "Type")]
+    kind: String,
+    set_identifier: Option<String>,
+    #[serde(rename = "TTL")]
+    ttl: Option<u32>,
+    resource_records: Option<ResourceRecords>,
+}
+
+#[derive(Deserialize)]
+struct ResourceRecords {
+    #[serde(rename = "ResourceRecord", default)]
+    records: Vec<ResourceRecord>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct ResourceRecord {
+    value: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct ChangeResponse {
+    #[allow(dead_code)] // only parsed to validate the response
+    change_info: ChangeInfo,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct ChangeInfo {
+    #[allow(dead_code)]
+    id: String,
+}
+
+/// Whether a Route53 name (`home.example.com.`) refers to `dns_name`
+fn same_name(route53: &str, dns_name: &str) -> bool {
+    unescape(route53)
+        .trim_end_matches('.')
+        .eq_ignore_ascii_case(dns_name.trim_end_matches('.'))
+}
+
+/// Decode the octal escapes Route53 uses in the names it returns, e.g.
+/// `\052` for the `*` of wildcard records
+fn unescape(name: &str) -> String {
+    let mut out = String::with_capacity(name.len());
+    let mut rest = name;
+    while let Some(at) = rest.find('\\') {
+        out.push_str(&rest[..at]);
+        let escaped = rest
+            .get(at + 1..at + 4)
+            .filter(|digits| digits.bytes().all(|b| b.is_ascii_digit()))
+            .and_then(|digits| u8::from_str_radix(digits, 8).ok());
+        match escaped {
+            Some(byte) => {
+                out.push(char::from(byte));
+                rest = &rest[at + 4..];
+            }
+            None => {
+                out.push('\\');
+                rest = &rest[at + 1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+fn xml_escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+/// `[providers.route53]` section of the config file.
+///
+/// Leave the keys unset to use the standard AWS credential chain.
+#[derive(Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Route53Config {
+    pub access_key_id: Option<String>,
+    pub secret_access_key: Option<String>,
+    pub session_token: Option<String>,
+    /// Profile of the shared credentials file, defaults to `AWS_PROFILE`
+    pub profile: Option<String>,
+    /// Weighted or latency record sets to update, by hostname, instead of
+    /// the simple record of the name
+    pub routing: BTreeMap<String, Route53Routing>,
+}
+
+/// `[providers.route53.routing."<hostname>"]` section: the record set of a
+/// routing policy the agent updates. Exactly one of `weight` and `region`
+/// is set.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Route53Routing {
+    /// `SetIdentifier` telling the record sets of the name apart
+    pub set_identifier: String,
+    /// Weight of a weighted record set
+    #[serde(default)]
+    pub weight: Option<u8>,
+    /// AWS region of a latency record set
+    #[serde(default)]
+    pub region: Option<String>,
+    /// Route53 health check answering for the record set
+    #[serde(default)]
+    pub health_check_id: Option<String>,
+}
+
+impl Route53Routing {
+    /// Elements of the `ResourceRecordSet` placed between its type and TTL
+    fn policy_xml(&self) -> String {
+        let mut xml = format!(
+            "<SetIdentifier>{}</SetIdentifier>",
+            xml_escape(&self.set_identifier)
+        );
+        if let Some(weight) = self.weight {
+            xml.push_str(&format!("<Weight>{}</Weight>", weight));
+        }
+        if let Some(region) = &self.region {
+            xml.push_str(&format!("<Region>{}</Region>", xml_escape(region)));
+        }
+        xml
+    }
+}
+
+impl fmt::Debug for Route53Config {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Route53Config")
+            .field("access_key_id", &self.access_key_id)
+            .field(
+                "secret_access_key",
+                &self.secret_access_key.as_ref().map(|_| "<redacted>"),
+            )
+            .field(
+                "session_token",
+                &self.session_token.as_ref().map(|_| "<redacted>"),
+            )
+            .field("profile", &self.profile)
+            .field("routing", &self.routing)
+            .finish()
+    }
+}
+
+pub struct Route53Provider {
+    client: Client,
+    credentials: CredentialSource,
+    /// Routed record sets by lowercase hostname
+    routing: BTreeMap<String, Route53Routing>,
+    base_url: String,
+}
+
+impl Route53Provider {
+    pub fn new(client: Client, config: &Route53Config) -> Self {
+        let credentials = match (&config.access_key_id, &config.secret_access_key) {
+            (Some(key), Some(secret)) => CredentialSource::Static(Credentials {
+                access_key_id: key.clone(),
+                secret_access_key: secret.clone(),
+                session_token: config.session_token.clone(),
+                expires: None,
+            }),
+            _ => CredentialSource::chain(config.profile.clone()),
+        };
+        let routing = config
+            .routing
+            .iter()
+            .map(|(name, routing)| {
+                let name = name.trim_end_matches('.');
+                let name = ascii_name(name).unwrap_or_else(|| name.to_string());
+                (name, routing.clone())
+            })
+            .collect();
+        Self {
+            client,
+            credentials,
+            routing,
+            base_url: API_BASE.to_string(),
+        }
+    }
+
+    /// Talk to a different API endpoint, e.g. a proxy or a mock server
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into().trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Send a signed request and parse the XML response
+    async fn send<T: DeserializeOwned>(
+        &self,
+        method: Method,
+        path: &str,
+        query: &[(&str, &str)],
+        body: String,
+    ) -> Result<T, ProviderError> {
+        let mut url = format!("{}/{}{}", self.base_url, API_VERSION, path);
+        if !query.is_empty() {
+            url = format!("{}?{}", url, sigv4::canonical_query(query));
+        }
+        let url = Url::parse(&url).map_err(|e| ProviderError::Api(e.to_string()))?;
+
+        let credentials = self.credentials.get(&self.client).await?;
+        let headers = sigv4::sign(
+            &credentials,
+            method.as_str(),
+            &url,
+            body.as_bytes(),
+            SIGNING_REGION,
+            "route53",
+            SystemTime::now(),
+        );
+        let mut request = self.client.request(method, url);
+        for (name, value) in headers {
+            request = request.header(name, value);
+        }
+        if !body.is_empty() {
+            request = request.header("content-type", "application/xml").body(body);
+        }
+
+        let resp = request.send().await?;
+        let status = resp.status();
+        let retry_after = retry_after(resp.headers());
+        let body = resp.text().await?;
+
+        if status.is_success() {
+            return quick_xml::de::from_str(&body).map_err(|e| {
+                ProviderError::InvalidResponse(format!("HTTP {}: {}", status.as_u16(), e))
+            });
+        }
+
+        let error = quick_xml::de::from_str::<ErrorResponse>(&body)
+            .map(|r| r.error)
+            .ok();
+        let code = error.as_ref().map(|e| e.code.as_str()).unwrap_or_default();
+        let message = match &error {
+            Some(e) => format!("{}: {}", e.code, e.message),
+            None => format!("HTTP {}", status.as_u16()),
+        };
+
+        if status == StatusCode::TOO_MANY_REQUESTS || THROTTLING_ERROR_CODES.contains(&code) {
+            return Err(ProviderError::RateLimited { retry_after });
+        }
+        if status == StatusCode::UNAUTHORIZED
+            || status == StatusCode::FORBIDDEN
+            || AUTH_ERROR_CODES.contains(&code)
+        {
+            return Err(ProviderError::Auth(message));
+        }
+        Err(ProviderError::for_status(status, message))
+    }
+}
+
+/// TTL the API stores for a record configured with `ttl`
+fn stored_ttl(ttl: u32) -> u32 {
+    if ttl <= 1 { AUTO_TTL } else { ttl }
+}
+
+#[async_trait]
+impl DnsProvider for Route53Provider {
+    fn name(&self) -> &str {
+        "route53"
+    }
+
+    fn effective_ttl(&self, record: &Record) -> Option<u32> {
+        Some(stored_ttl(record.ttl))
+    }
+
+    /// Find the public hosted zone of the root domain of `dns_name`
+    async fn lookup_zone(&self, dns_name: &str) -> Result<Zone, ProviderError> {
+        let domain = extract_root_domain(dns_name);
+        let mut next: Option<(String, String)> = None;
+        loop {
+            let mut query = vec![("dnsname", domain.as_str())];
+            if let Some((name, id)) = &next {
+                query = vec![("dnsname", name.as_str()), ("hostedzoneid", id.as_str())];
+            }
+            let page: ListHostedZonesResponse = self
+                .send(Method::GET, "/hostedzonesbyname", &query, String::new())
+                .await?;
+
+            let mut past_domain = false;
+            for zone in page.hosted_zones.zones {
+                if !same_name(&zone.name, &domain) {
+                    // Zones are sorted by name, so there are no more matches
+                    past_domain = true;
+                    break;
+                }
+                if zone.config.is_some_and(|c| c.private_zone) {
+                    continue;
+                }
+                return Ok(Zone {
+                    id: zone.id.trim_start_matches("/hostedzone/").to_string(),
+                    name: zone.name.trim_end_matches('.').to_string(),
+                });
+            }
+
+            match (
+                page.is_truncated,
+                page.next_dns_name,
+                page.next_hosted_zone_id,
+            ) {
+                (true, Some(name), Some(id)) if !past_domain => next = Some((name, id)),
+                _ => return Err(ProviderError::ZoneNotFound(domain)),
+            }
+        }
+    }
+
+    async fn read_record(
+        &self,
+        zone: &Zone,
+        dns_name: &str,
+        kind: RecordType,
+    ) -> Result<Option<Record>, ProviderError> {
+        // Listing starts at the requested name, type and set identifier, so
+        // the record is either first or missing
+        let identifier = self
+            .routing
+            .get(dns_name)
+            .map(|r| r.set_identifier.as_str());
+        let mut query = vec![
+            ("name", dns_name),
+            ("type", kind.as_str()),
+            ("maxitems", "1"),
+        ];
+        if let Some(identifier) = identifier {
+            query.push(("identifier", identifier));
+        }
+        let page: ListRecordSetsResponse = self
+            .send(
+                Method::GET,
+                &format!("/hostedzone/{}/rrset", zone.id),
+                &query,
+                String::new(),
+            )
+            .await?;
+
+        let set = page.resource_record_sets.sets.into_iter().find(|s| {
+            same_name(&s.name, dns_name)
+                && s.kind == kind.as_str()
+                && s.set_identifier.as_deref() == identifier
+        });
+        Ok(set.map(|set| Record {
+            id: None,
+            name: dns_name.to_string(),
+            kind,
+            content: set
+                .resource_records
+                .and_then(|r| r.records.into_iter().next())
+                .map(|r| r.value)
+                .unwrap_or_default(),
+            ttl: set.ttl.unwrap_or(AUTO_TTL),
+            proxied: None,
+        }))
+    }
+
+    /// Replace the record set with a single value using an `UPSERT` change,
+    /// keeping the routing policy and health check configured for the name
+    async fn upsert_record(&self, zone: &Zone, record: &Record) -> Result<Record, ProviderError> {
+        let ttl = stored_ttl(record.ttl);
+        let routing = self.routing.get(&record.name);
+        let health_check = routing
+            .and_then(|r| r.health_check_id.as_ref())
+            .map(|id| format!("<HealthCheckId>{}</HealthCheckId>", xml_escape(id)))
+            .unwrap_or_default();
+        let body = format!(
+            concat!(
+                r#"<?xml version="1.0" encoding="UTF-8"?>"#,
+                r#"<ChangeResourceRecordSetsRequest xmlns="https://route53.amazonaws.com/doc/2013-04-01/">"#,
+                "<ChangeBatch><Comment>ddns-agent</Comment><Changes><Change><Action>UPSERT</Action>",
+                "<ResourceRecordSet><Name>{}</Name><Type>{}</Type>{}<TTL>{}</TTL>",
+                "<ResourceRecords><ResourceRecord><Value>{}</Value></ResourceRecord></ResourceRecords>",
+                "{}</ResourceRecordSet></Change></Changes></ChangeBatch>",
+                "</ChangeResourceRecordSetsRequest>"
+            ),
+            xml_escape(&record.name),
+            record.kind.as_str(),
+            routing.map(Route53Routing::policy_xml).unwrap_or_default(),
+            ttl,
+            xml_escape(&record.content),
+            health_check
+        );
+
+        let _: ChangeResponse = self
+            .send(
+                Method::POST,
+                &format!("/hostedzone/{}/rrset/", zone.id),
+                &[],
+                body,
+            )
+            .await?;
+        Ok(Record {
+            ttl,
+            proxied: None,
+            ..record.clone()
+        })
+    }
+}
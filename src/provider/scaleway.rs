@@ -0,0 +1,247 @@
+//! Scaleway provider using the v2beta1 domain API.
+
+use super::{
+    DnsProvider, Record, RecordType, Zone, extract_root_domain, relative_name, retry_after,
+};
+use crate::error::ProviderError;
+use async_trait::async_trait;
+use reqwest::{Client, RequestBuilder, StatusCode};
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use std::fmt;
+
+const API_BASE: &str = "https://api.scaleway.com/domain/v2beta1";
+/// TTL used for records configured with the automatic TTL (`1`), matching
+/// the Scaleway default
+const AUTO_TTL: u32 = 3600;
+/// Records requested per page
+const PAGE_SIZE: usize = 100;
+
+#[derive(Deserialize)]
+struct ScalewayError {
+    #[serde(default)]
+    message: String,
+}
+
+#[derive(Deserialize)]
+struct ZonesPage {
+    dns_zones: Vec<ScalewayZone>,
+}
+
+#[derive(Deserialize)]
+struct ScalewayZone {
+    domain: String,
+    /// Set when the zone is delegated below the domain
+    #[serde(default)]
+    subdomain: String,
+}
+
+#[derive(Deserialize)]
+struct RecordsPage {
+    records: Vec<ScalewayRecord>,
+    #[serde(default)]
+    total_count: usize,
+}
+
+#[derive(Deserialize)]
+struct ScalewayRecord {
+    id: String,
+    /// Name relative to the zone, empty for the apex
+    name: String,
+    #[serde(rename = "type")]
+    kind: String,
+    data: String,
+    ttl: u32,
+}
+
+impl ScalewayRecord {
+    fn into_record(self, dns_name: &str, kind: RecordType) -> Record {
+        Record {
+            id: Some(self.id),
+            name: dns_name.to_string(),
+            kind,
+            content: self.data,
+            ttl: self.ttl,
+            proxied: None,
+        }
+    }
+}
+
+/// `[providers.scaleway]` section of the config file
+#[derive(Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ScalewayConfig {
+    /// Secret key of an API key allowed to manage the domains
+    pub secret_key: String,
+}
+
+impl fmt::Debug for ScalewayConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ScalewayConfig")
+            .field("secret_key", &"<redacted>")
+            .finish()
+    }
+}
+
+pub struct ScalewayProvider {
+    client: Client,
+    secret_key: String,
+    base_url: String,
+}
+
+impl ScalewayProvider {
+    pub fn new(client: Client, secret_key: impl Into<String>) -> Self {
+        Self {
+            client,
+            secret_key: secret_key.into(),
+            base_url: API_BASE.to_string(),
+        }
+    }
+
+    /// Talk to a different API endpoint, e.g. a proxy or a mock server
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into().trim_end_matches('/').to_string();
+        self
+    }
+
+    fn records_url(&self, zone: &Zone) -> String {
+        format!("{}/dns-zones/{}/records", self.base_url, zone.id)
+    }
+
+    /// Send an authenticated request and parse the JSON response
+    async fn send<T: DeserializeOwned>(&self, request: RequestBuilder) -> Result<T, ProviderError> {
+        let resp = request
+            .header("X-Auth-Token", &self.secret_key)
+            .send()
+            .await?;
+        let status = resp.status();
+
+        if status == StatusCode::TOO_MANY_REQUESTS {
+            let retry_after = retry_after(resp.headers());
+            return Err(ProviderError::RateLimited { retry_after });
+        }
+
+        let body = resp.text().await?;
+        if !status.is_success() {
+            let message = serde_json::from_str::<ScalewayError>(&body)
+                .ok()
+                .map(|e| e.message)
+                .filter(|m| !m.is_empty())
+                .unwrap_or_else(|| format!("HTTP {}", status.as_u16()));
+            return Err(match status {
+                StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => ProviderError::Auth(message),
+                _ => ProviderError::for_status(status, message),
+            });
+        }
+
+        serde_json::from_str(&body)
+            .map_err(|e| ProviderError::InvalidResponse(format!("HTTP {}: {}", status.as_u16(), e)))
+    }
+}
+
+/// TTL the API stores for a record configured with `ttl`
+fn stored_ttl(ttl: u32) -> u32 {
+    if ttl == 1 { AUTO_TTL } else { ttl }
+}
+
+#[async_trait]
+impl DnsProvider for ScalewayProvider {
+    fn name(&self) -> &str {
+        "scaleway"
+    }
+
+    fn effective_ttl(&self, record: &Record) -> Option<u32> {
+        Some(stored_ttl(record.ttl))
+    }
+
+    /// Look up the zone of the root domain of `dns_name`; its name doubles
+    /// as the ID
+    async fn lookup_zone(&self, dns_name: &str) -> Result<Zone, ProviderError> {
+        let domain = extract_root_domain(dns_name);
+        let request = self
+            .client
+            .get(format!("{}/dns-zones", self.base_url))
+            .query(&[("dns_zone", domain.as_str())]);
+        let resp: ZonesPage = self.send(request).await?;
+
+        let zone = resp
+            .dns_zones
+            .into_iter()
+            .map(|z| match z.subdomain.as_str() {
+                "" => z.domain,
+                sub => format!("{}.{}", sub, z.domain),
+            })
+            .find(|name| name.eq_ignore_ascii_case(&domain))
+            .ok_or(ProviderError::ZoneNotFound(domain))?;
+        Ok(Zone {
+            id: zone.clone(),
+            name: zone,
+        })
+    }
+
+    async fn read_record(
+        &self,
+        zone: &Zone,
+        dns_name: &str,
+        kind: RecordType,
+    ) -> Result<Option<Record>, ProviderError> {
+        let relative = relative_name(dns_name, &zone.name, "");
+        let mut page = 1;
+        let mut seen = 0;
+        loop {
+            let request = self
+                .client
+                .get(self.records_url(zone))
+                .query(&[("name", relative.as_str()), ("type", kind.as_str())])
+                .query(&[("page", page), ("page_size", PAGE_SIZE)]);
+            let resp: RecordsPage = self.send(request).await?;
+            let count = resp.records.len();
+            seen += count;
+
+            if let Some(record) = resp
+                .records
+                .into_iter()
+                .find(|r| r.kind == kind.as_str() && r.name.eq_ignore_ascii_case(&relative))
+            {
+                return Ok(Some(record.into_record(dns_name, kind)));
+            }
+            if count == 0 || seen >= resp.total_count {
+                return Ok(None);
+            }
+            page += 1;
+        }
+    }
+
+    /// Replace all records of the name and type with a single value in one
+    /// change set
+    async fn upsert_record(&self, zone: &Zone, record: &Record) -> Result<Record, ProviderError> {
+        let ttl = stored_ttl(record.ttl);
+        let name = relative_name(&record.name, &zone.name, "");
+        let body = serde_json::json!({
+            "changes": [{
+                "set": {
+                    "id_fields": { "name": name, "type": record.kind.as_str() },
+                    "records": [{
+                        "name": name,
+                        "type": record.kind.as_str(),
+                        "data": record.content,
+                        "ttl": ttl
+                    }]
+                }
+            }],
+            "return_all_records": false
+        });
+
+        let request = self.client.patch(self.records_url(zone)).json(&body);
+        let resp: RecordsPage = self.send(request).await?;
+        Ok(resp
+            .records
+            .into_iter()
+            .find(|r| r.kind == record.kind.as_str() && r.name.eq_ignore_ascii_case(&name))
+            .map(|r| r.into_record(&record.name, record.kind))
+            .unwrap_or_else(|| Record {
+                ttl,
+                ..record.clone()
+            }))
+    }
+}
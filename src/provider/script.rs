@@ -0,0 +1,349 @@
+//! DNS provider implemented by a user supplied [Rhai](https://rhai.rs) script.
+//!
+//! The script defines the three provider operations as functions:
+//!
+//! ```rhai
+//! fn lookup_zone(dns_name) { #{ id: "...", name: "example.com" } }
+//! fn read_record(zone, dns_name, type) { #{ id: "...", name: dns_name, type: type, content: "1.2.3.4", ttl: 300 } }
+//! fn upsert_record(zone, record) { record }
+//! ```
+//!
+//! `read_record` returns `()` when the record doesn't exist. The `options`
+//! table from the config file is available to every function as the global
+//! constant `options`, and the following helpers are registered:
+//!
+//! - `http(method, url, headers, body)` returns `#{ status, headers, body }`,
+//!   with lowercase header names
+//! - `http_get(url, headers)` is a shorthand for a `GET` without body
+//! - `json_decode(text)` / `json_encode(value)`
+//!
+//! Failures can be reported as a plain `throw "message"`, or as a map
+//! telling the agent what went wrong:
+//!
+//! ```rhai
+//! throw #{ kind: "auth", message: "invalid API key" };
+//! throw #{ kind: "rate_limited", retry_after: 30 };
+//! throw #{ kind: "invalid_response", message: "..." };
+//! throw #{ kind: "not_found", message: "no record with this ID" };
+//! throw #{ kind: "unavailable", message: "HTTP 503" };
+//! ```
+//!
+//! `not_found` thrown by `lookup_zone` means the account has no zone for
+//! the name. `unavailable` lets another provider take over, as for an
+//! outage.
+//!
+//! `json_decode` throws `invalid_response` on malformed input.
+//!
+//! Scripts run on a blocking thread, so the helpers perform plain blocking
+//! requests.
+
+use super::{DnsProvider, Record, RecordType, Zone};
+use crate::error::{ConfigError, ProviderError};
+use async_trait::async_trait;
+use log::{debug, info};
+use rhai::{AST, Dynamic, Engine, EvalAltResult, FuncArgs, Map, Position, Scope};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+
+/// `[providers.script]` section of the config file
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ScriptConfig {
+    /// Path to the `.rhai` script
+    pub path: PathBuf,
+    /// Arbitrary settings passed to the script as `options`
+    #[serde(default)]
+    pub options: serde_json::Map<String, serde_json::Value>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct ScriptZone {
+    id: String,
+    name: String,
+}
+
+#[derive(Serialize, Deserialize)]
+struct ScriptRecord {
+    #[serde(default)]
+    id: Option<String>,
+    name: String,
+    #[serde(rename = "type")]
+    kind: String,
+    content: String,
+    #[serde(default = "auto_ttl")]
+    ttl: u32,
+    #[serde(default)]
+    proxied: Option<bool>,
+}
+
+fn auto_ttl() -> u32 {
+    1
+}
+
+struct Script {
+    engine: Engine,
+    ast: AST,
+}
+
+pub struct ScriptProvider {
+    name: String,
+    script: Arc<Script>,
+}
+
+/// Blocking client shared by all scripts, created on first use so it is
+/// never built or dropped inside the async runtime
+fn http_client() -> &'static reqwest::blocking::Client {
+    static CLIENT: OnceLock<reqwest::blocking::Client> = OnceLock::new();
+    CLIENT.get_or_init(reqwest::blocking::Client::new)
+}
+
+fn http(method: &str, url: &str, headers: Map, body: &str) -> Result<Map, Box<EvalAltResult>> {
+    let method = reqwest::Method::from_bytes(method.to_ascii_uppercase().as_bytes())
+        .map_err(|e| e.to_string())?;
+    let mut request = http_client().request(method, url);
+    for (key, value) in headers {
+        request = request.header(key.as_str(), value.to_string());
+    }
+    if !body.is_empty() {
+        request = request.body(body.to_string());
+    }
+
+    let resp = request.send().map_err(|e| e.to_string())?;
+    let status = resp.status().as_u16() as i64;
+    let mut headers = Map::new();
+    for (key, value) in resp.headers() {
+        if let Ok(value) = value.to_str() {
+            headers.insert(key.as_str().into(), value.into());
+        }
+    }
+    let body = resp.text().map_err(|e| e.to_string())?;
+
+    let mut result = Map::new();
+    result.insert("status".into(), status.into());
+    result.insert("headers".into(), headers.into());
+    result.insert("body".into(), body.into());
+    Ok(result)
+}
+
+fn build_engine(options: Dynamic) -> Engine {
+    let mut engine = Engine::new();
+    // Resolved by the engine rather than pushed into the call scope, so
+    // helper functions called by the entry points can read it as well.
+    // `on_var` is flagged as a volatile API through `deprecated`.
+    #[allow(deprecated)]
+    engine.on_var(move |name, _, _| match name {
+        "options" => Ok(Some(options.clone())),
+        _ => Ok(None),
+    });
+    engine.on_print(|s| info!("[script] {}", s));
+    engine.on_debug(|s, _, _| debug!("[script] {}", s));
+    engine.register_fn("http", http);
+    engine.register_fn("http_get", |url: &str, headers: Map| {
+        http("GET", url, headers, "")
+    });
+    engine.register_fn(
+        "json_decode",
+        |text: &str| -> Result<Dynamic, Box<EvalAltResult>> {
+            let value: serde_json::Value = serde_json::from_str(text).map_err(|e| {
+                let mut error = Map::new();
+                error.insert("kind".into(), "invalid_response".into());
+                error.insert("message".into(), e.to_string().into());
+                EvalAltResult::ErrorRuntime(error.into(), Position::NONE)
+            })?;
+            rhai::serde::to_dynamic(value)
+        },
+    );
+    engine.register_fn(
+        "json_encode",
+        |value: Dynamic| -> Result<String, Box<EvalAltResult>> {
+            serde_json::to_string(&value).map_err(|e| e.to_string().into())
+        },
+    );
+    engine
+}
+
+/// Translate an error raised by script function `func`, honouring the
+/// error maps described in the module docs
+fn script_error(func: &str, err: EvalAltResult) -> ProviderError {
+    match err {
+        EvalAltResult::ErrorInFunctionCall(_, _, inner, _) => script_error(func, *inner),
+        EvalAltResult::ErrorRuntime(value, _) if value.is_map() => {
+            let error = value.cast::<Map>();
+            let field = |name: &str| error.get(name).cloned();
+            let message = field("message")
+                .and_then(|m| m.into_string().ok())
+                .unwrap_or_default();
+            let kind = field("kind").and_then(|k| k.into_string().ok());
+            match kind.as_deref() {
+                Some("auth") => ProviderError::Auth(message),
+                Some("rate_limited") => ProviderError::RateLimited {
+                    retry_after: field("retry_after")
+                        .and_then(|v| v.as_int().ok())
+                        .map(|secs| Duration::from_secs(secs.max(0) as u64)),
+                },
+                Some("invalid_response") => ProviderError::InvalidResponse(message),
+                Some("not_found") => ProviderError::NotFound(message),
+                Some("unavailable") => ProviderError::Unavailable(message),
+                _ => ProviderError::Api(format!("Script function `{}` failed: {}", func, message)),
+            }
+        }
+        other => ProviderError::Api(format!("Script function `{}` failed: {}", func, other)),
+    }
+}
+
+fn parse_kind(kind: &str) -> Result<RecordType, ProviderError> {
+    match kind {
+        "A" => Ok(RecordType::A),
+        "AAAA" => Ok(RecordType::Aaaa),
+        other => Err(ProviderError::InvalidResponse(format!(
+            "unsupported record type from script: {}",
+            other
+        ))),
+    }
+}
+
+impl ScriptRecord {
+    fn into_record(self) -> Result<Record, ProviderError> {
+        Ok(Record {
+            id: self.id,
+            name: self.name,
+            kind: parse_kind(&self.kind)?,
+            content: self.content,
+            ttl: self.ttl,
+            proxied: self.proxied,
+        })
+    }
+}
+
+impl ScriptProvider {
+    /// Compile the script referenced by `config`
+    pub fn new(config: &ScriptConfig) -> Result<Self, ConfigError> {
+        let options = rhai::serde::to_dynamic(&config.options)
+            .map_err(|e| ConfigError::Invalid(format!("Invalid script options: {}", e)))?;
+        let engine = build_engine(options);
+        let ast = engine.compile_file(config.path.clone()).map_err(|e| {
+            ConfigError::Invalid(format!(
+                "Failed to load script {}: {}",
+                config.path.display(),
+                e
+            ))
+        })?;
+
+        for func in ["lookup_zone", "read_record", "upsert_record"] {
+            if !ast.iter_functions().any(|f| f.name == func) {
+                return Err(ConfigError::Invalid(format!(
+                    "Script {} does not define `{}`",
+                    config.path.display(),
+                    func
+                )));
+            }
+        }
+
+        let name = config
+            .path
+            .file_stem()
+            .map(|s| format!("script:{}", s.to_string_lossy()))
+            .unwrap_or_else(|| "script".to_string());
+
+        Ok(Self {
+            name,
+            script: Arc::new(Script { engine, ast }),
+        })
+    }
+
+    /// Call a script function on a blocking thread
+    async fn call(
+        &self,
+        func: &'static str,
+        args: impl FuncArgs + Send + 'static,
+    ) -> Result<Dynamic, ProviderError> {
+        let script = self.script.clone();
+        tokio::task::spawn_blocking(move || {
+            script
+                .engine
+                .call_fn::<Dynamic>(&mut Scope::new(), &script.ast, func, args)
+                .map_err(|e| script_error(func, *e))
+        })
+        .await
+        .map_err(|e| ProviderError::Api(format!("Script function `{}` panicked: {}", func, e)))?
+    }
+}
+
+fn to_dynamic<T: Serialize>(value: &T) -> Result<Dynamic, ProviderError> {
+    rhai::serde::to_dynamic(value).map_err(|e| ProviderError::Api(e.to_string()))
+}
+
+fn from_dynamic<T: for<'de> Deserialize<'de>>(
+    func: &str,
+    value: &Dynamic,
+) -> Result<T, ProviderError> {
+    rhai::serde::from_dynamic(value).map_err(|e| {
+        ProviderError::InvalidResponse(format!("invalid value returned by `{}`: {}", func, e))
+    })
+}
+
+#[async_trait]
+impl DnsProvider for ScriptProvider {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn lookup_zone(&self, dns_name: &str) -> Result<Zone, ProviderError> {
+        let result = self
+            .call("lookup_zone", (dns_name.to_string(),))
+            .await
+            .map_err(|e| match e {
+                // The zone is all there is to miss here
+                ProviderError::NotFound(_) => ProviderError::ZoneNotFound(dns_name.to_string()),
+                e => e,
+            })?;
+        let zone: ScriptZone = from_dynamic("lookup_zone", &result)?;
+        Ok(Zone {
+            id: zone.id,
+            name: zone.name,
+        })
+    }
+
+    async fn read_record(
+        &self,
+        zone: &Zone,
+        dns_name: &str,
+        kind: RecordType,
+    ) -> Result<Option<Record>, ProviderError> {
+        let zone = to_dynamic(&ScriptZone {
+            id: zone.id.clone(),
+            name: zone.name.clone(),
+        })?;
+        let result = self
+            .call(
+                "read_record",
+                (zone, dns_name.to_string(), kind.as_str().to_string()),
+            )
+            .await?;
+        if result.is_unit() {
+            return Ok(None);
+        }
+        let record: ScriptRecord = from_dynamic("read_record", &result)?;
+        record.into_record().map(Some)
+    }
+
+    async fn upsert_record(&self, zone: &Zone, record: &Record) -> Result<Record, ProviderError> {
+        let zone = to_dynamic(&ScriptZone {
+            id: zone.id.clone(),
+            name: zone.name.clone(),
+        })?;
+        let arg = to_dynamic(&ScriptRecord {
+            id: record.id.clone(),
+            name: record.name.clone(),
+            kind: record.kind.as_str().to_string(),
+            content: record.content.clone(),
+            ttl: record.ttl,
+            proxied: record.proxied,
+        })?;
+        let result = self.call("upsert_record", (zone, arg)).await?;
+        let updated: ScriptRecord = from_dynamic("upsert_record", &result)?;
+        updated.into_record()
+    }
+}
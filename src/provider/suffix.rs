@@ -0,0 +1,57 @@
+//! Registrable domains according to the Public Suffix List.
+//!
+//! Only the ICANN section of the list is bundled: the private section holds
+//! suffixes like `github.io` whose subdomains aren't zones of their own at a
+//! DNS provider. Its internationalized rules are kept in punycode, like the
+//! names they are matched against.
+
+use super::ascii_name;
+use std::collections::HashSet;
+use std::sync::LazyLock;
+
+static RULES: LazyLock<HashSet<String>> = LazyLock::new(|| {
+    include_str!("public_suffix_list.dat")
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty() && !l.starts_with("//"))
+        .filter_map(|rule| {
+            let (prefix, suffix) = match rule.strip_prefix('!') {
+                Some(suffix) => ("!", suffix),
+                None => match rule.strip_prefix("*.") {
+                    Some(suffix) => ("*.", suffix),
+                    None => ("", rule),
+                },
+            };
+            Some(format!("{}{}", prefix, ascii_name(suffix)?))
+        })
+        .collect()
+});
+
+/// Number of trailing labels of `labels` forming its public suffix, using
+/// the implicit `*` rule when no listed one matches
+fn suffix_len(labels: &[&str]) -> usize {
+    for i in 0..labels.len() {
+        let candidate = labels[i..].join(".");
+        if RULES.contains(format!("!{}", candidate).as_str()) {
+            return labels.len() - i - 1;
+        }
+        if RULES.contains(candidate.as_str())
+            || (i + 1 < labels.len()
+                && RULES.contains(format!("*.{}", labels[i + 1..].join(".")).as_str()))
+        {
+            return labels.len() - i;
+        }
+    }
+    1
+}
+
+/// Registrable domain of `dns_name`, i.e. its public suffix plus one label
+/// (`home.example.co.uk` -> `example.co.uk`). A name that is a public
+/// suffix itself is returned as is.
+pub(crate) fn registrable_domain(dns_name: &str) -> String {
+    let name = dns_name.trim_end_matches('.');
+    let name = ascii_name(name).unwrap_or_else(|| name.to_lowercase());
+    let labels: Vec<&str> = name.split('.').collect();
+    let len = (suffix_len(&labels) + 1).min(labels.len());
+    labels[labels.len() - len..].join(".")
+}
@@ -0,0 +1,237 @@
+//! Technitium DNS Server provider using its HTTP API.
+//!
+//! Calls are POSTed as forms carrying the API token, and answer with HTTP
+//! 200 and a `status` of `ok`, `error` or `invalid-token`. Address records
+//! are identified by their value, so records report their current address
+//! as ID.
+
+use super::{DnsProvider, Record, RecordType, Zone, retry_after};
+use crate::error::ProviderError;
+use async_trait::async_trait;
+use reqwest::{Client, StatusCode};
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use std::fmt;
+
+/// TTL used for records configured with the automatic TTL (`1`), matching
+/// the Technitium default
+const AUTO_TTL: u32 = 3600;
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Response<T> {
+    status: String,
+    #[serde(default)]
+    error_message: String,
+    response: Option<T>,
+}
+
+#[derive(Deserialize)]
+struct Zones {
+    zones: Vec<TechnitiumZone>,
+}
+
+#[derive(Deserialize)]
+struct TechnitiumZone {
+    name: String,
+}
+
+#[derive(Deserialize)]
+struct Records {
+    records: Vec<TechnitiumRecord>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TechnitiumRecord {
+    name: String,
+    #[serde(rename = "type")]
+    kind: String,
+    ttl: u32,
+    #[serde(default)]
+    disabled: bool,
+    r_data: RData,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RData {
+    #[serde(default)]
+    ip_address: String,
+}
+
+/// `[providers.technitium]` section of the config file
+#[derive(Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TechnitiumConfig {
+    /// Address of the web console, e.g. `http://127.0.0.1:5380`
+    pub url: String,
+    /// API token created under Administration > Sessions
+    pub api_token: String,
+}
+
+impl fmt::Debug for TechnitiumConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TechnitiumConfig")
+            .field("url", &self.url)
+            .field("api_token", &"<redacted>")
+            .finish()
+    }
+}
+
+pub struct TechnitiumProvider {
+    client: Client,
+    url: String,
+    api_token: String,
+}
+
+impl TechnitiumProvider {
+    pub fn new(client: Client, config: &TechnitiumConfig) -> Self {
+        Self {
+            client,
+            url: config.url.trim_end_matches('/').to_string(),
+            api_token: config.api_token.clone(),
+        }
+    }
+
+    /// Call `api/{path}` with `params` and parse its `response`
+    async fn call<T: DeserializeOwned>(
+        &self,
+        path: &str,
+        params: &[(&str, &str)],
+    ) -> Result<T, ProviderError> {
+        let mut form = vec![("token", self.api_token.as_str())];
+        form.extend_from_slice(params);
+        let resp = self
+            .client
+            .post(format!("{}/api/{}", self.url, path))
+            .form(&form)
+            .send()
+            .await?;
+        let status = resp.status();
+
+        if status == StatusCode::TOO_MANY_REQUESTS {
+            let retry_after = retry_after(resp.headers());
+            return Err(ProviderError::RateLimited { retry_after });
+        }
+
+        let body = resp.text().await?;
+        if !status.is_success() {
+            return Err(match status {
+                StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => {
+                    ProviderError::Auth(format!("HTTP {}", status.as_u16()))
+                }
+                _ => ProviderError::for_status(status, format!("HTTP {}", status.as_u16())),
+            });
+        }
+
+        let resp: Response<T> = serde_json::from_str(&body).map_err(|e| {
+            ProviderError::InvalidResponse(format!("HTTP {}: {}", status.as_u16(), e))
+        })?;
+        match (resp.status.as_str(), resp.response) {
+            ("ok", Some(response)) => Ok(response),
+            ("ok", None) => Err(ProviderError::InvalidResponse(format!(
+                "no response from {}",
+                path
+            ))),
+            ("invalid-token", _) => Err(ProviderError::Auth("Invalid API token".into())),
+            _ => Err(ProviderError::Api(resp.error_message)),
+        }
+    }
+}
+
+/// TTL the API stores for a record configured with `ttl`
+fn stored_ttl(ttl: u32) -> u32 {
+    if ttl == 1 { AUTO_TTL } else { ttl }
+}
+
+#[async_trait]
+impl DnsProvider for TechnitiumProvider {
+    fn name(&self) -> &str {
+        "technitium"
+    }
+
+    fn effective_ttl(&self, record: &Record) -> Option<u32> {
+        Some(stored_ttl(record.ttl))
+    }
+
+    /// Find the most specific zone on the server containing `dns_name`
+    async fn lookup_zone(&self, dns_name: &str) -> Result<Zone, ProviderError> {
+        let name = dns_name.to_lowercase();
+        let resp: Zones = self.call("zones/list", &[]).await?;
+
+        resp.zones
+            .into_iter()
+            .filter(|z| {
+                let zone = z.name.to_lowercase();
+                name == zone || name.ends_with(&format!(".{}", zone))
+            })
+            .max_by_key(|z| z.name.len())
+            .map(|z| Zone {
+                id: z.name.clone(),
+                name: z.name,
+            })
+            .ok_or_else(|| ProviderError::ZoneNotFound(dns_name.to_string()))
+    }
+
+    async fn read_record(
+        &self,
+        zone: &Zone,
+        dns_name: &str,
+        kind: RecordType,
+    ) -> Result<Option<Record>, ProviderError> {
+        let params = [
+            ("domain", dns_name),
+            ("zone", zone.id.as_str()),
+            ("listZone", "false"),
+        ];
+        let resp: Records = self.call("zones/records/get", &params).await?;
+
+        Ok(resp
+            .records
+            .into_iter()
+            .find(|r| {
+                r.kind == kind.as_str() && !r.disabled && r.name.eq_ignore_ascii_case(dns_name)
+            })
+            .map(|r| Record {
+                id: Some(r.r_data.ip_address.clone()),
+                name: dns_name.to_string(),
+                kind,
+                content: r.r_data.ip_address,
+                ttl: r.ttl,
+                proxied: None,
+            }))
+    }
+
+    /// Change the address of the record `record.id` points at, or replace
+    /// all records of the name and type when there is none
+    async fn upsert_record(&self, zone: &Zone, record: &Record) -> Result<Record, ProviderError> {
+        let ttl = stored_ttl(record.ttl);
+        let ttl_param = ttl.to_string();
+        let mut params = vec![
+            ("domain", record.name.as_str()),
+            ("zone", zone.id.as_str()),
+            ("type", record.kind.as_str()),
+            ("ttl", ttl_param.as_str()),
+        ];
+
+        let path = match &record.id {
+            Some(current) => {
+                params.push(("ipAddress", current));
+                params.push(("newIpAddress", &record.content));
+                "zones/records/update"
+            }
+            None => {
+                params.push(("ipAddress", &record.content));
+                params.push(("overwrite", "true"));
+                "zones/records/add"
+            }
+        };
+        self.call::<serde_json::Value>(path, &params).await?;
+        Ok(Record {
+            id: Some(record.content.clone()),
+            ttl,
+            ..record.clone()
+        })
+    }
+}
@@ -0,0 +1,132 @@
+//! UTC timestamps like `2024-01-31T23:59:59Z`, as some APIs send and expect
+//! them.
+//!
+//! Dates are converted with Howard Hinnant's algorithms, which count years
+//! from March so the leap day comes last.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// `2024-01-31T23:59:59Z` timestamp of `time`, the epoch for earlier times
+pub fn format(time: SystemTime) -> String {
+    let secs = time
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default();
+    let (year, month, day) = civil_from_days((secs / 86_400) as i64);
+    let rem = secs % 86_400;
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        rem / 3600,
+        rem % 3600 / 60,
+        rem % 60
+    )
+}
+
+/// Time of a `2024-01-31T23:59:59Z` timestamp, ignoring fractions of a
+/// second
+#[cfg(any(test, feature = "route53"))]
+pub fn parse(value: &str) -> Option<SystemTime> {
+    let (date, time) = value.trim_end_matches('Z').split_once('T')?;
+    let mut date = date.split('-').map(str::parse::<u32>);
+    let (year, month, day) = (date.next()?.ok()?, date.next()?.ok()?, date.next()?.ok()?);
+    let mut time = time
+        .split(':')
+        .map(|p| p.split('.').next().unwrap_or(p).parse::<u64>());
+    let (hour, minute, second) = (time.next()?.ok()?, time.next()?.ok()?, time.next()?.ok()?);
+    if date.next().is_some() || time.next().is_some() {
+        return None;
+    }
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return None;
+    }
+    if hour > 23 || minute > 59 || second > 60 {
+        return None;
+    }
+
+    let days = u64::try_from(days_from_civil(year.into(), month, day)).ok()?;
+    let secs = days * 86_400 + hour * 3600 + minute * 60 + second;
+    UNIX_EPOCH.checked_add(std::time::Duration::from_secs(secs))
+}
+
+/// (year, month, day) of the date `days` days after 1970-01-01
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+/// Days from 1970-01-01 to a (year, month, day) date, negative before it
+#[cfg(any(test, feature = "route53"))]
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year.rem_euclid(400);
+    let month = i64::from(month);
+    let doy = (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + i64::from(day) - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn at(secs: u64) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(secs)
+    }
+
+    #[test]
+    fn formats_utc_timestamps() {
+        assert_eq!(format(UNIX_EPOCH), "1970-01-01T00:00:00Z");
+        assert_eq!(format(at(1_700_000_000)), "2023-11-14T22:13:20Z");
+        // Leap day, and the last second of a leap year
+        assert_eq!(format(at(951_782_400)), "2000-02-29T00:00:00Z");
+        assert_eq!(format(at(1_735_689_599)), "2024-12-31T23:59:59Z");
+        assert_eq!(
+            format(UNIX_EPOCH - Duration::from_secs(1)),
+            "1970-01-01T00:00:00Z"
+        );
+    }
+
+    #[test]
+    fn parses_utc_timestamps() {
+        assert_eq!(parse("2023-11-14T22:13:20Z"), Some(at(1_700_000_000)));
+        assert_eq!(parse("2000-02-29T00:00:00Z"), Some(at(951_782_400)));
+        assert_eq!(parse("2023-11-14T22:13:20.123Z"), Some(at(1_700_000_000)));
+    }
+
+    #[test]
+    fn parsing_reverses_formatting() {
+        for secs in [0, 86_399, 951_868_799, 1_709_251_199, 4_102_444_800] {
+            assert_eq!(parse(&format(at(secs))), Some(at(secs)));
+        }
+    }
+
+    #[test]
+    fn rejects_malformed_timestamps() {
+        for value in [
+            "",
+            "2023-11-14",
+            "2023-11-14 22:13:20Z",
+            "2023-13-14T22:13:20Z",
+            "2023-11-32T22:13:20Z",
+            "2023-11-14T24:13:20Z",
+            "2023-11-14T22:13Z",
+            "2023-11-14-01T22:13:20Z",
+            "1969-12-31T23:59:59Z",
+        ] {
+            assert_eq!(parse(value), None, "{:?}", value);
+        }
+    }
+}
@@ -0,0 +1,170 @@
+//! Hurricane Electric tunnelbroker.net provider, keeping the IPv4 endpoint
+//! of 6in4 tunnels pointed at the current address.
+//!
+//! Tunnels aren't DNS records, so each configured hostname is mapped to the
+//! tunnel it terminates. The update endpoint speaks the dyndns2 answer codes
+//! and can't read the endpoint back, so the agent updates it once at startup.
+
+use super::{DnsProvider, Record, RecordType, Zone, retry_after};
+use crate::error::ProviderError;
+use async_trait::async_trait;
+use reqwest::header::USER_AGENT;
+use reqwest::{Client, StatusCode};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fmt;
+
+const API_BASE: &str = "https://ipv4.tunnelbroker.net";
+const AGENT: &str = concat!("ddns-agent/", env!("CARGO_PKG_VERSION"));
+
+/// `[providers.tunnelbroker]` section of the config file
+#[derive(Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TunnelbrokerConfig {
+    /// tunnelbroker.net account name
+    pub username: String,
+    /// Update key from the tunnel's "Advanced" tab, or the account password
+    pub update_key: String,
+    /// Numeric tunnel ID by record hostname
+    pub tunnels: BTreeMap<String, String>,
+}
+
+impl fmt::Debug for TunnelbrokerConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TunnelbrokerConfig")
+            .field("username", &self.username)
+            .field("update_key", &"<redacted>")
+            .field("tunnels", &self.tunnels)
+            .finish()
+    }
+}
+
+pub struct TunnelbrokerProvider {
+    client: Client,
+    username: String,
+    update_key: String,
+    /// Tunnel IDs by lowercase hostname
+    tunnels: BTreeMap<String, String>,
+    base_url: String,
+}
+
+impl TunnelbrokerProvider {
+    pub fn new(client: Client, config: &TunnelbrokerConfig) -> Self {
+        Self {
+            client,
+            username: config.username.clone(),
+            update_key: config.update_key.clone(),
+            tunnels: config
+                .tunnels
+                .iter()
+                .map(|(name, id)| (name.to_lowercase(), id.clone()))
+                .collect(),
+            base_url: API_BASE.to_string(),
+        }
+    }
+
+    /// Talk to a different API endpoint, e.g. a proxy or a mock server
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into().trim_end_matches('/').to_string();
+        self
+    }
+
+    fn tunnel(&self, dns_name: &str) -> Option<&str> {
+        self.tunnels
+            .get(&dns_name.to_lowercase())
+            .map(String::as_str)
+    }
+}
+
+#[async_trait]
+impl DnsProvider for TunnelbrokerProvider {
+    fn name(&self) -> &str {
+        "tunnelbroker"
+    }
+
+    fn effective_ttl(&self, _record: &Record) -> Option<u32> {
+        None
+    }
+
+    /// Each hostname stands for its tunnel, so it is its own zone. Fails for
+    /// hostnames without a tunnel.
+    async fn lookup_zone(&self, dns_name: &str) -> Result<Zone, ProviderError> {
+        let tunnel = self.tunnel(dns_name).ok_or_else(|| {
+            ProviderError::ZoneNotFound(format!("{} (no tunnel configured)", dns_name))
+        })?;
+        Ok(Zone {
+            id: tunnel.to_string(),
+            name: dns_name.to_string(),
+        })
+    }
+
+    /// The endpoint with an empty (unknown) value, as it can't be read
+    async fn read_record(
+        &self,
+        _zone: &Zone,
+        dns_name: &str,
+        kind: RecordType,
+    ) -> Result<Option<Record>, ProviderError> {
+        Ok(Some(Record {
+            id: None,
+            name: dns_name.to_string(),
+            kind,
+            content: String::new(),
+            ttl: 1,
+            proxied: None,
+        }))
+    }
+
+    /// Point the tunnel's IPv4 endpoint at `record.content`
+    async fn upsert_record(&self, zone: &Zone, record: &Record) -> Result<Record, ProviderError> {
+        if record.kind == RecordType::Aaaa {
+            return Err(ProviderError::Api(
+                "Tunnel endpoints are IPv4 addresses".into(),
+            ));
+        }
+        let resp = self
+            .client
+            .get(format!("{}/nic/update", self.base_url))
+            .query(&[
+                ("hostname", zone.id.as_str()),
+                ("myip", record.content.as_str()),
+            ])
+            .basic_auth(&self.username, Some(&self.update_key))
+            .header(USER_AGENT, AGENT)
+            .send()
+            .await?;
+        let status = resp.status();
+
+        if status == StatusCode::TOO_MANY_REQUESTS {
+            let retry_after = retry_after(resp.headers());
+            return Err(ProviderError::RateLimited { retry_after });
+        }
+        let body = resp.text().await?;
+        let answer = body.lines().next().unwrap_or_default().trim();
+        let code = answer.split_whitespace().next().unwrap_or_default();
+
+        match code {
+            "good" | "nochg" => Ok(record.clone()),
+            "badauth" => Err(ProviderError::Auth("badauth".into())),
+            "nohost" => Err(ProviderError::RecordNotFound {
+                name: format!("{} (tunnel {})", record.name, zone.id),
+                kind: record.kind,
+            }),
+            // Rejected endpoints, e.g. one that doesn't answer pings
+            "abuse" | "badagent" | "dnserr" | "911" | "-ERROR:" => {
+                Err(ProviderError::Api(answer.to_string()))
+            }
+            _ if status == StatusCode::UNAUTHORIZED || status == StatusCode::FORBIDDEN => {
+                Err(ProviderError::Auth(format!("HTTP {}", status.as_u16())))
+            }
+            _ if !status.is_success() => Err(ProviderError::for_status(
+                status,
+                format!("HTTP {}", status.as_u16()),
+            )),
+            _ => Err(ProviderError::InvalidResponse(format!(
+                "unexpected answer {:?}",
+                answer
+            ))),
+        }
+    }
+}
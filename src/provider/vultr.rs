@@ -0,0 +1,280 @@
+//! Vultr DNS provider using the v2 API.
+
+use super::{
+    DnsProvider, Record, RecordType, Zone, extract_root_domain, relative_name, retry_after,
+};
+use crate::error::ProviderError;
+use async_trait::async_trait;
+use reqwest::{Client, RequestBuilder, StatusCode};
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use std::fmt;
+
+const API_BASE: &str = "https://api.vultr.com/v2";
+/// TTL used for records configured with the automatic TTL (`1`), matching
+/// the Vultr default
+const AUTO_TTL: u32 = 300;
+/// Items requested per page of a list endpoint
+const PAGE_SIZE: &str = "500";
+
+#[derive(Deserialize)]
+struct VultrError {
+    #[serde(default)]
+    error: String,
+}
+
+#[derive(Deserialize)]
+struct DomainsResponse {
+    domains: Vec<VultrDomain>,
+    #[serde(default)]
+    meta: Meta,
+}
+
+#[derive(Deserialize)]
+struct VultrDomain {
+    domain: String,
+}
+
+#[derive(Deserialize)]
+struct RecordsResponse {
+    records: Vec<VultrRecord>,
+    #[serde(default)]
+    meta: Meta,
+}
+
+#[derive(Deserialize)]
+struct RecordResponse {
+    record: VultrRecord,
+}
+
+#[derive(Deserialize)]
+struct VultrRecord {
+    id: String,
+    #[serde(rename = "type")]
+    kind: String,
+    /// Name relative to the domain, empty for the apex
+    name: String,
+    data: String,
+    ttl: u32,
+}
+
+impl VultrRecord {
+    fn into_record(self, dns_name: &str, kind: RecordType) -> Record {
+        Record {
+            id: Some(self.id),
+            name: dns_name.to_string(),
+            kind,
+            content: self.data,
+            ttl: self.ttl,
+            proxied: None,
+        }
+    }
+}
+
+#[derive(Deserialize, Default)]
+struct Meta {
+    #[serde(default)]
+    links: Links,
+}
+
+#[derive(Deserialize, Default)]
+struct Links {
+    /// Cursor of the next page, empty on the last one
+    #[serde(default)]
+    next: String,
+}
+
+impl Meta {
+    fn next_cursor(self) -> Option<String> {
+        Some(self.links.next).filter(|c| !c.is_empty())
+    }
+}
+
+/// `[providers.vultr]` section of the config file
+#[derive(Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct VultrConfig {
+    /// API key from the account settings, with the agent's address in its
+    /// access control list
+    pub api_key: String,
+}
+
+impl fmt::Debug for VultrConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("VultrConfig")
+            .field("api_key", &"<redacted>")
+            .finish()
+    }
+}
+
+pub struct VultrProvider {
+    client: Client,
+    api_key: String,
+    base_url: String,
+}
+
+impl VultrProvider {
+    pub fn new(client: Client, api_key: impl Into<String>) -> Self {
+        Self {
+            client,
+            api_key: api_key.into(),
+            base_url: API_BASE.to_string(),
+        }
+    }
+
+    /// Talk to a different API endpoint, e.g. a proxy or a mock server
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into().trim_end_matches('/').to_string();
+        self
+    }
+
+    /// GET one page of a list endpoint
+    fn list(&self, path: &str, cursor: Option<&str>) -> RequestBuilder {
+        let request = self
+            .client
+            .get(format!("{}{}", self.base_url, path))
+            .query(&[("per_page", PAGE_SIZE)]);
+        match cursor {
+            Some(cursor) => request.query(&[("cursor", cursor)]),
+            None => request,
+        }
+    }
+
+    /// Send an authenticated request and parse the JSON response
+    async fn send<T: DeserializeOwned>(&self, request: RequestBuilder) -> Result<T, ProviderError> {
+        let resp = request.bearer_auth(&self.api_key).send().await?;
+        let status = resp.status();
+
+        if status == StatusCode::TOO_MANY_REQUESTS {
+            let retry_after = retry_after(resp.headers());
+            return Err(ProviderError::RateLimited { retry_after });
+        }
+
+        let body = resp.text().await?;
+        if !status.is_success() {
+            let message = serde_json::from_str::<VultrError>(&body)
+                .ok()
+                .map(|e| e.error)
+                .filter(|e| !e.is_empty())
+                .unwrap_or_else(|| format!("HTTP {}", status.as_u16()));
+            return Err(match status {
+                StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => ProviderError::Auth(message),
+                _ => ProviderError::for_status(status, message),
+            });
+        }
+
+        // Updates answer with 204 and no body
+        let body = if body.trim().is_empty() {
+            "null"
+        } else {
+            &body
+        };
+        serde_json::from_str(body)
+            .map_err(|e| ProviderError::InvalidResponse(format!("HTTP {}: {}", status.as_u16(), e)))
+    }
+}
+
+/// TTL the API stores for a record configured with `ttl`
+fn stored_ttl(ttl: u32) -> u32 {
+    if ttl == 1 { AUTO_TTL } else { ttl }
+}
+
+#[async_trait]
+impl DnsProvider for VultrProvider {
+    fn name(&self) -> &str {
+        "vultr"
+    }
+
+    fn effective_ttl(&self, record: &Record) -> Option<u32> {
+        Some(stored_ttl(record.ttl))
+    }
+
+    /// Find the root domain of `dns_name` among the account's domains; its
+    /// name doubles as the ID
+    async fn lookup_zone(&self, dns_name: &str) -> Result<Zone, ProviderError> {
+        let domain = extract_root_domain(dns_name);
+        let mut cursor = None;
+        loop {
+            let request = self.list("/domains", cursor.as_deref());
+            let resp: DomainsResponse = self.send(request).await?;
+
+            if let Some(found) = resp
+                .domains
+                .into_iter()
+                .find(|d| d.domain.eq_ignore_ascii_case(&domain))
+            {
+                return Ok(Zone {
+                    id: found.domain.clone(),
+                    name: found.domain,
+                });
+            }
+            cursor = match resp.meta.next_cursor() {
+                Some(next) => Some(next),
+                None => return Err(ProviderError::ZoneNotFound(domain)),
+            };
+        }
+    }
+
+    async fn read_record(
+        &self,
+        zone: &Zone,
+        dns_name: &str,
+        kind: RecordType,
+    ) -> Result<Option<Record>, ProviderError> {
+        let relative = relative_name(dns_name, &zone.name, "");
+        let path = format!("/domains/{}/records", zone.id);
+        let mut cursor = None;
+        loop {
+            let request = self.list(&path, cursor.as_deref());
+            let resp: RecordsResponse = self.send(request).await?;
+
+            if let Some(record) = resp
+                .records
+                .into_iter()
+                .find(|r| r.kind == kind.as_str() && r.name.eq_ignore_ascii_case(&relative))
+            {
+                return Ok(Some(record.into_record(dns_name, kind)));
+            }
+            cursor = match resp.meta.next_cursor() {
+                Some(next) => Some(next),
+                None => return Ok(None),
+            };
+        }
+    }
+
+    async fn upsert_record(&self, zone: &Zone, record: &Record) -> Result<Record, ProviderError> {
+        let ttl = stored_ttl(record.ttl);
+        let name = relative_name(&record.name, &zone.name, "");
+        let records_url = format!("{}/domains/{}/records", self.base_url, zone.id);
+
+        match &record.id {
+            Some(id) => {
+                let body = serde_json::json!({
+                    "name": name,
+                    "data": record.content,
+                    "ttl": ttl
+                });
+                let request = self
+                    .client
+                    .patch(format!("{}/{}", records_url, id))
+                    .json(&body);
+                self.send::<serde_json::Value>(request).await?;
+                Ok(Record {
+                    ttl,
+                    ..record.clone()
+                })
+            }
+            None => {
+                let body = serde_json::json!({
+                    "name": name,
+                    "type": record.kind.as_str(),
+                    "data": record.content,
+                    "ttl": ttl
+                });
+                let request = self.client.post(records_url).json(&body);
+                let created: RecordResponse = self.send(request).await?;
+                Ok(created.record.into_record(&record.name, record.kind))
+            }
+        }
+    }
+}
@@ -0,0 +1,214 @@
+//! Generic provider sending an HTTP request described in the config file.
+//!
+//! The method, URL, headers and body are templates in which `{ip}`,
+//! `{hostname}` and `{type}` are replaced for each update. This covers the
+//! many small dynamic DNS services with a plain update URL. Like the dyndns2
+//! protocol, such URLs can't read records back, so the agent updates them
+//! once at startup.
+
+use super::{DnsProvider, Record, RecordType, Zone, retry_after};
+use crate::error::{ConfigError, ProviderError};
+use async_trait::async_trait;
+use regex::Regex;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use reqwest::{Client, Method, StatusCode};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fmt;
+
+fn default_method() -> String {
+    "GET".to_string()
+}
+
+/// `[providers.webhook]` section of the config file
+#[derive(Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct WebhookConfig {
+    #[serde(default = "default_method")]
+    pub method: String,
+    /// URL template, e.g. `https://dyn.example/update?host={hostname}&ip={ip}`
+    pub url: String,
+    /// Header templates
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    /// Body template, no body if unset
+    #[serde(default)]
+    pub body: Option<String>,
+    /// Status codes meaning success, any 2xx if empty
+    #[serde(default)]
+    pub success_status: Vec<u16>,
+    /// Regex the response body must match for the update to count as done
+    #[serde(default)]
+    pub success_body: Option<String>,
+}
+
+impl fmt::Debug for WebhookConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // The query and the headers usually carry credentials
+        let url = self.url.split('?').next().unwrap_or_default();
+        let headers: BTreeMap<_, _> = self.headers.keys().map(|k| (k, "<redacted>")).collect();
+        f.debug_struct("WebhookConfig")
+            .field("method", &self.method)
+            .field("url", &url)
+            .field("headers", &headers)
+            .field("body", &self.body.as_ref().map(|_| "<redacted>"))
+            .field("success_status", &self.success_status)
+            .field("success_body", &self.success_body)
+            .finish()
+    }
+}
+
+pub struct WebhookProvider {
+    client: Client,
+    method: Method,
+    url: String,
+    headers: Vec<(HeaderName, String)>,
+    body: Option<String>,
+    success_status: Vec<StatusCode>,
+    success_body: Option<Regex>,
+}
+
+/// `template` with the placeholders replaced for `record`
+fn render(template: &str, record: &Record) -> String {
+    template
+        .replace("{ip}", &record.content)
+        .replace("{hostname}", &record.name)
+        .replace("{type}", record.kind.as_str())
+}
+
+impl WebhookProvider {
+    pub fn new(client: Client, config: &WebhookConfig) -> Result<Self, ConfigError> {
+        let invalid = |what: &str, e: &dyn fmt::Display| {
+            ConfigError::Invalid(format!("Invalid webhook {}: {}", what, e))
+        };
+        let method = Method::from_bytes(config.method.to_uppercase().as_bytes())
+            .map_err(|e| invalid("method", &e))?;
+        let headers = config
+            .headers
+            .iter()
+            .map(|(name, value)| {
+                let name = HeaderName::from_bytes(name.as_bytes())
+                    .map_err(|e| invalid("header name", &e))?;
+                Ok((name, value.clone()))
+            })
+            .collect::<Result<_, ConfigError>>()?;
+        let success_status = config
+            .success_status
+            .iter()
+            .map(|code| StatusCode::from_u16(*code).map_err(|e| invalid("success_status", &e)))
+            .collect::<Result<_, _>>()?;
+        let success_body = config
+            .success_body
+            .as_deref()
+            .map(Regex::new)
+            .transpose()
+            .map_err(|e| invalid("success_body", &e))?;
+
+        Ok(Self {
+            client,
+            method,
+            url: config.url.clone(),
+            headers,
+            body: config.body.clone(),
+            success_status,
+            success_body,
+        })
+    }
+
+    fn headers(&self, record: &Record) -> Result<HeaderMap, ProviderError> {
+        self.headers
+            .iter()
+            .map(|(name, template)| {
+                let value = HeaderValue::from_str(&render(template, record))
+                    .map_err(|e| ProviderError::Api(format!("Invalid {} header: {}", name, e)))?;
+                Ok((name.clone(), value))
+            })
+            .collect()
+    }
+
+    fn is_success(&self, status: StatusCode) -> bool {
+        if self.success_status.is_empty() {
+            status.is_success()
+        } else {
+            self.success_status.contains(&status)
+        }
+    }
+}
+
+#[async_trait]
+impl DnsProvider for WebhookProvider {
+    fn name(&self) -> &str {
+        "webhook"
+    }
+
+    fn effective_ttl(&self, _record: &Record) -> Option<u32> {
+        None
+    }
+
+    /// Hostnames are updated as a whole, so each one is its own zone
+    async fn lookup_zone(&self, dns_name: &str) -> Result<Zone, ProviderError> {
+        Ok(Zone {
+            id: dns_name.to_string(),
+            name: dns_name.to_string(),
+        })
+    }
+
+    /// The record with an empty (unknown) value, as it can't be read
+    async fn read_record(
+        &self,
+        _zone: &Zone,
+        dns_name: &str,
+        kind: RecordType,
+    ) -> Result<Option<Record>, ProviderError> {
+        Ok(Some(Record {
+            id: None,
+            name: dns_name.to_string(),
+            kind,
+            content: String::new(),
+            ttl: 1,
+            proxied: None,
+        }))
+    }
+
+    /// Send the rendered request and check the answer
+    async fn upsert_record(&self, _zone: &Zone, record: &Record) -> Result<Record, ProviderError> {
+        let mut request = self
+            .client
+            .request(self.method.clone(), render(&self.url, record))
+            .headers(self.headers(record)?);
+        if let Some(body) = &self.body {
+            request = request.body(render(body, record));
+        }
+        let resp = request
+            .send()
+            .await
+            // The URL may hold credentials
+            .map_err(reqwest::Error::without_url)?;
+        let status = resp.status();
+
+        if status == StatusCode::TOO_MANY_REQUESTS {
+            let retry_after = retry_after(resp.headers());
+            return Err(ProviderError::RateLimited { retry_after });
+        }
+        let body = resp.text().await.map_err(reqwest::Error::without_url)?;
+        let answer = body.lines().next().unwrap_or_default().trim();
+
+        if !self.is_success(status) {
+            let message = if answer.is_empty() {
+                format!("HTTP {}", status.as_u16())
+            } else {
+                format!("HTTP {}: {}", status.as_u16(), answer)
+            };
+            return Err(match status {
+                StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => ProviderError::Auth(message),
+                _ => ProviderError::for_status(status, message),
+            });
+        }
+        match &self.success_body {
+            Some(pattern) if !pattern.is_match(&body) => Err(ProviderError::InvalidResponse(
+                format!("unexpected answer {:?}", answer),
+            )),
+            _ => Ok(record.clone()),
+        }
+    }
+}
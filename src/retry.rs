@@ -0,0 +1,229 @@
+//! Retrying transient failures.
+//!
+//! Provider requests and IP detections failing for a moment, e.g. with a
+//! server error or a dropped connection, are sent again after a delay that
+//! doubles with every attempt. Half of each delay is random, so agents that
+//! failed together don't retry in step. A provider saying how long to wait,
+//! with `Retry-After` or a similar header, is waited for instead.
+
+use crate::error::{IpDetectionError, ProviderError};
+use log::warn;
+use std::collections::hash_map::RandomState;
+use std::fmt::Display;
+use std::hash::{BuildHasher, Hasher};
+use std::time::Duration;
+use tokio::time::{Instant, sleep};
+
+/// Upper bound of the delay a provider may ask for, so a bogus
+/// `Retry-After` can't stall the agent
+pub const MAX_RETRY_AFTER: Duration = Duration::from_secs(60);
+
+/// How often and for how long failed requests are retried
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Attempts in total, the first one included
+    pub max_attempts: u32,
+    /// Delay before the first retry
+    pub initial_delay: Duration,
+    /// Upper bound of a single delay
+    pub max_delay: Duration,
+    /// No further attempt is made this long after the first one
+    pub max_elapsed: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 4,
+            initial_delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(30),
+            max_elapsed: Duration::from_secs(120),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Delay before the next attempt once `attempt` attempts failed, `None`
+    /// when the attempts or the time since `started` have run out
+    pub fn next_delay(&self, attempt: u32, started: Instant) -> Option<Duration> {
+        if attempt >= self.max_attempts {
+            return None;
+        }
+        let full = self
+            .initial_delay
+            .saturating_mul(1 << attempt.saturating_sub(1).min(31))
+            .min(self.max_delay);
+        let delay = full / 2 + full.mul_f64(random_fraction()) / 2;
+        (started.elapsed() + delay <= self.max_elapsed).then_some(delay)
+    }
+
+    /// [`RetryPolicy::next_delay`], or the delay the other side asked for
+    /// with `retry_after`, capped at [`MAX_RETRY_AFTER`]. `None` as well when
+    /// that delay would end past `max_elapsed`, as waiting for less is
+    /// pointless.
+    pub fn delay_for(
+        &self,
+        attempt: u32,
+        started: Instant,
+        retry_after: Option<Duration>,
+    ) -> Option<Duration> {
+        let Some(delay) = retry_after else {
+            return self.next_delay(attempt, started);
+        };
+        let delay = delay.min(MAX_RETRY_AFTER);
+        let remaining = self.max_elapsed.saturating_sub(started.elapsed());
+        (attempt < self.max_attempts && delay <= remaining).then_some(delay)
+    }
+}
+
+/// Errors [`retry`] can tell apart
+pub trait Retryable: Display {
+    /// Whether trying again later may succeed
+    fn is_transient(&self) -> bool;
+
+    /// Delay the other side asked for before trying again
+    fn retry_after(&self) -> Option<Duration> {
+        None
+    }
+}
+
+impl Retryable for ProviderError {
+    fn is_transient(&self) -> bool {
+        ProviderError::is_transient(self)
+    }
+
+    fn retry_after(&self) -> Option<Duration> {
+        ProviderError::retry_after(self)
+    }
+}
+
+impl Retryable for IpDetectionError {
+    fn is_transient(&self) -> bool {
+        IpDetectionError::is_transient(self)
+    }
+}
+
+/// Random number in `[0, 1)`
+fn random_fraction() -> f64 {
+    let bits = RandomState::new().build_hasher().finish();
+    (bits >> 11) as f64 / (1u64 << 53) as f64
+}
+
+/// Run `op` until it succeeds, fails with an error that isn't transient, or
+/// `policy` gives up, logging each retry of `what`
+pub async fn retry<T, E, F, Fut>(policy: &RetryPolicy, what: &str, mut op: F) -> Result<T, E>
+where
+    E: Retryable,
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, E>>,
+{
+    let started = Instant::now();
+    let mut attempt = 1;
+    loop {
+        let err = match op().await {
+            Err(e) if e.is_transient() => e,
+            result => return result,
+        };
+        let Some(delay) = policy.delay_for(attempt, started, err.retry_after()) else {
+            return Err(err);
+        };
+        warn!("{}: {}, retrying in {:.1}s", what, err, delay.as_secs_f64());
+        sleep(delay).await;
+        attempt += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy() -> RetryPolicy {
+        RetryPolicy {
+            max_attempts: 10,
+            initial_delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(8),
+            max_elapsed: Duration::from_secs(3600),
+        }
+    }
+
+    #[test]
+    fn delays_double_with_half_of_them_random() {
+        let started = Instant::now();
+        for (attempt, full) in [(1, 1), (2, 2), (3, 4), (4, 8)] {
+            let full = Duration::from_secs(full);
+            for _ in 0..100 {
+                let delay = policy().next_delay(attempt, started).unwrap();
+                assert!(delay >= full / 2 && delay <= full, "{:?}", delay);
+            }
+        }
+    }
+
+    #[test]
+    fn delays_stop_growing_at_max_delay() {
+        let started = Instant::now();
+        let max = policy().max_delay;
+        for attempt in [5, 9, u32::MAX - 1] {
+            let policy = RetryPolicy {
+                max_attempts: u32::MAX,
+                ..policy()
+            };
+            let delay = policy.next_delay(attempt, started).unwrap();
+            assert!(delay >= max / 2 && delay <= max, "{:?}", delay);
+        }
+    }
+
+    #[test]
+    fn gives_up_after_max_attempts() {
+        let started = Instant::now();
+        assert!(policy().next_delay(9, started).is_some());
+        assert!(policy().next_delay(10, started).is_none());
+        let once = RetryPolicy {
+            max_attempts: 1,
+            ..policy()
+        };
+        assert!(once.next_delay(1, started).is_none());
+    }
+
+    #[test]
+    fn gives_up_once_max_elapsed_would_pass() {
+        let policy = RetryPolicy {
+            max_elapsed: Duration::from_millis(400),
+            ..policy()
+        };
+        // Even the shortest first delay is 500ms
+        assert!(policy.next_delay(1, Instant::now()).is_none());
+    }
+
+    #[test]
+    fn waits_as_long_as_asked_within_the_budget() {
+        let started = Instant::now();
+        let asked = Some(Duration::from_secs(600));
+        assert_eq!(
+            policy().delay_for(1, started, asked),
+            Some(Duration::from_secs(600).min(MAX_RETRY_AFTER))
+        );
+        assert_eq!(
+            policy().delay_for(1, started, Some(Duration::from_millis(1))),
+            Some(Duration::from_millis(1))
+        );
+        assert!(policy().delay_for(10, started, asked).is_none());
+    }
+
+    #[test]
+    fn gives_up_when_asked_to_wait_past_max_elapsed() {
+        let policy = RetryPolicy {
+            max_elapsed: Duration::from_secs(10),
+            ..policy()
+        };
+        let started = Instant::now();
+        assert!(
+            policy
+                .delay_for(1, started, Some(Duration::from_secs(30)))
+                .is_none()
+        );
+        assert_eq!(
+            policy.delay_for(1, started, Some(Duration::from_secs(5))),
+            Some(Duration::from_secs(5))
+        );
+    }
+}
@@ -0,0 +1,84 @@
+//! Address and route changes from an rtnetlink socket.
+
+use std::io;
+use std::mem;
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+use tokio::io::unix::AsyncFd;
+
+/// Multicast groups announcing changes that can move the public IP
+const GROUPS: libc::c_int = libc::RTMGRP_LINK
+    | libc::RTMGRP_IPV4_IFADDR
+    | libc::RTMGRP_IPV6_IFADDR
+    | libc::RTMGRP_IPV4_ROUTE
+    | libc::RTMGRP_IPV6_ROUTE;
+
+pub(super) struct Events {
+    socket: AsyncFd<OwnedFd>,
+    buf: Vec<u8>,
+}
+
+impl Events {
+    pub(super) fn subscribe() -> io::Result<Self> {
+        // SAFETY: plain socket creation, the result is checked below
+        let fd = unsafe {
+            libc::socket(
+                libc::AF_NETLINK,
+                libc::SOCK_RAW | libc::SOCK_NONBLOCK | libc::SOCK_CLOEXEC,
+                libc::NETLINK_ROUTE,
+            )
+        };
+        if fd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        // SAFETY: `fd` is a freshly created socket owned by nobody else
+        let socket = unsafe { OwnedFd::from_raw_fd(fd) };
+
+        // SAFETY: all-zero is a valid `sockaddr_nl`, the kernel picks the
+        // port ID
+        let mut addr: libc::sockaddr_nl = unsafe { mem::zeroed() };
+        addr.nl_family = libc::AF_NETLINK as libc::sa_family_t;
+        addr.nl_groups = GROUPS as u32;
+        // SAFETY: `addr` is a valid `sockaddr_nl` of the given size
+        let bound = unsafe {
+            libc::bind(
+                socket.as_raw_fd(),
+                (&addr as *const libc::sockaddr_nl).cast(),
+                mem::size_of::<libc::sockaddr_nl>() as libc::socklen_t,
+            )
+        };
+        if bound < 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        Ok(Self {
+            socket: AsyncFd::new(socket)?,
+            buf: vec![0; 16 * 1024],
+        })
+    }
+
+    /// Wait for the next batch of notifications; their content doesn't
+    /// matter, any of them warrants a new check
+    pub(super) async fn next(&mut self) -> io::Result<()> {
+        loop {
+            let mut ready = self.socket.readable().await?;
+            let buf = &mut self.buf;
+            let received = ready.try_io(|socket| {
+                // SAFETY: `buf` is valid for writes of its length
+                let len = unsafe {
+                    libc::recv(socket.as_raw_fd(), buf.as_mut_ptr().cast(), buf.len(), 0)
+                };
+                if len < 0 {
+                    return Err(io::Error::last_os_error());
+                }
+                Ok(())
+            });
+            match received {
+                Ok(Ok(())) => return Ok(()),
+                // The kernel dropped notifications, so something changed
+                Ok(Err(e)) if e.raw_os_error() == Some(libc::ENOBUFS) => return Ok(()),
+                Ok(Err(e)) => return Err(e),
+                Err(_would_block) => continue,
+            }
+        }
+    }
+}
@@ -0,0 +1,85 @@
+//! Network changes from the System Configuration dynamic store.
+//!
+//! The global IPv4 and IPv6 state changes whenever the primary service does,
+//! e.g. on a Wi-Fi switch or a VPN going up or down, and the per-interface
+//! keys change with every address.
+
+use core_foundation::array::CFArray;
+use core_foundation::runloop::{CFRunLoop, kCFRunLoopCommonModes};
+use core_foundation::string::CFString;
+use std::io;
+use std::sync::{Arc, mpsc};
+use std::thread;
+use system_configuration::dynamic_store::{
+    SCDynamicStore, SCDynamicStoreBuilder, SCDynamicStoreCallBackContext,
+};
+use tokio::sync::Notify;
+
+const KEYS: [&str; 2] = ["State:/Network/Global/IPv4", "State:/Network/Global/IPv6"];
+const PATTERNS: [&str; 2] = [
+    "State:/Network/Interface/[^/]+/IPv4",
+    "State:/Network/Interface/[^/]+/IPv6",
+];
+
+pub(super) struct Events {
+    notify: Arc<Notify>,
+    /// Run loop of the thread delivering the notifications
+    run_loop: CFRunLoop,
+}
+
+fn changed(_store: SCDynamicStore, _keys: CFArray<CFString>, notify: &mut Arc<Notify>) {
+    notify.notify_one();
+}
+
+/// Register for the changes and run the thread's run loop until stopped
+fn watch(notify: Arc<Notify>, started: mpsc::Sender<io::Result<CFRunLoop>>) {
+    let store = SCDynamicStoreBuilder::new("ddns-agent")
+        .callback_context(SCDynamicStoreCallBackContext {
+            callout: changed,
+            info: notify,
+        })
+        .build();
+    let keys = CFArray::from_CFTypes(&KEYS.map(CFString::from_static_string));
+    let patterns = CFArray::from_CFTypes(&PATTERNS.map(CFString::from_static_string));
+    if !store.set_notification_keys(&keys, &patterns) {
+        let _ = started.send(Err(io::Error::other(
+            "could not register for dynamic store notifications",
+        )));
+        return;
+    }
+
+    let run_loop = CFRunLoop::get_current();
+    let source = store.create_run_loop_source();
+    // SAFETY: reading an immutable constant of the framework
+    run_loop.add_source(&source, unsafe { kCFRunLoopCommonModes });
+    if started.send(Ok(run_loop)).is_ok() {
+        CFRunLoop::run_current();
+    }
+}
+
+impl Events {
+    pub(super) fn subscribe() -> io::Result<Self> {
+        let notify = Arc::new(Notify::new());
+        let (started, registered) = mpsc::channel();
+        let context = notify.clone();
+        thread::Builder::new()
+            .name("network-watch".to_string())
+            .spawn(move || watch(context, started))?;
+        let run_loop = registered
+            .recv()
+            .map_err(|_| io::Error::other("network watch thread exited"))??;
+        Ok(Self { notify, run_loop })
+    }
+
+    /// Wait for the next notification; a change before the call counts too
+    pub(super) async fn next(&mut self) -> io::Result<()> {
+        self.notify.notified().await;
+        Ok(())
+    }
+}
+
+impl Drop for Events {
+    fn drop(&mut self) {
+        self.run_loop.stop();
+    }
+}
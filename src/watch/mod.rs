@@ -0,0 +1,65 @@
+//! Network change notifications.
+//!
+//! Lets the agent check the public IP as soon as the local network
+//! configuration changes, instead of polling at a short interval. Events
+//! come in bursts (an interface goes up, gets an address, then a route), so
+//! [`NetworkWatcher::changed`] waits for them to settle before returning.
+
+use std::io;
+use std::time::Duration;
+
+#[cfg(target_os = "linux")]
+mod linux;
+#[cfg(target_os = "macos")]
+mod macos;
+#[cfg(windows)]
+mod windows;
+#[cfg(target_os = "linux")]
+use linux::Events;
+#[cfg(target_os = "macos")]
+use macos::Events;
+#[cfg(windows)]
+use windows::Events;
+
+/// Quiet time after an event before the network counts as settled
+const SETTLE: Duration = Duration::from_secs(1);
+
+pub struct NetworkWatcher {
+    events: Events,
+}
+
+impl NetworkWatcher {
+    /// Subscribe to address and route changes, failing with
+    /// [`io::ErrorKind::Unsupported`] on platforms without notifications
+    pub fn new() -> io::Result<Self> {
+        Ok(Self {
+            events: Events::subscribe()?,
+        })
+    }
+
+    /// Wait for the next change of the network configuration
+    pub async fn changed(&mut self) -> io::Result<()> {
+        self.events.next().await?;
+        while let Ok(event) = tokio::time::timeout(SETTLE, self.events.next()).await {
+            event?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+struct Events;
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+impl Events {
+    fn subscribe() -> io::Result<Self> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "network change notifications are not supported on this platform",
+        ))
+    }
+
+    async fn next(&mut self) -> io::Result<()> {
+        std::future::pending().await
+    }
+}
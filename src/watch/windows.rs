@@ -0,0 +1,91 @@
+//! Interface and address changes from the IP Helper notification API.
+
+use std::ffi::c_void;
+use std::io;
+use tokio::sync::Notify;
+use windows_sys::Win32::Foundation::{HANDLE, NO_ERROR, WIN32_ERROR};
+use windows_sys::Win32::NetworkManagement::IpHelper::{
+    CancelMibChangeNotify2, MIB_IPINTERFACE_ROW, MIB_NOTIFICATION_TYPE, MIB_UNICASTIPADDRESS_ROW,
+    NotifyIpInterfaceChange, NotifyUnicastIpAddressChange,
+};
+use windows_sys::Win32::Networking::WinSock::AF_UNSPEC;
+
+pub(super) struct Events {
+    handles: Vec<HANDLE>,
+    /// Context of the callbacks, which run on a system thread; freed only
+    /// after they have been cancelled
+    notify: Box<Notify>,
+}
+
+// SAFETY: the handles are only used to cancel the notifications on drop,
+// which may happen on any thread
+unsafe impl Send for Events {}
+
+unsafe extern "system" fn interface_changed(
+    context: *const c_void,
+    _row: *const MIB_IPINTERFACE_ROW,
+    _kind: MIB_NOTIFICATION_TYPE,
+) {
+    // SAFETY: `context` is the `Notify` owned by `Events`, which outlives
+    // the registration
+    unsafe { &*context.cast::<Notify>() }.notify_one();
+}
+
+unsafe extern "system" fn address_changed(
+    context: *const c_void,
+    _row: *const MIB_UNICASTIPADDRESS_ROW,
+    _kind: MIB_NOTIFICATION_TYPE,
+) {
+    // SAFETY: as in `interface_changed`
+    unsafe { &*context.cast::<Notify>() }.notify_one();
+}
+
+fn check(status: WIN32_ERROR) -> io::Result<()> {
+    if status == NO_ERROR {
+        Ok(())
+    } else {
+        Err(io::Error::from_raw_os_error(status as i32))
+    }
+}
+
+impl Events {
+    pub(super) fn subscribe() -> io::Result<Self> {
+        let mut events = Self {
+            handles: Vec::new(),
+            notify: Box::new(Notify::new()),
+        };
+        let context: *const c_void = (&*events.notify as *const Notify).cast();
+
+        let mut handle: HANDLE = std::ptr::null_mut();
+        // SAFETY: `context` stays valid until the handle is cancelled on drop
+        check(unsafe {
+            NotifyIpInterfaceChange(AF_UNSPEC, Some(interface_changed), context, 0, &mut handle)
+        })?;
+        events.handles.push(handle);
+
+        let mut handle: HANDLE = std::ptr::null_mut();
+        // SAFETY: as above
+        check(unsafe {
+            NotifyUnicastIpAddressChange(AF_UNSPEC, Some(address_changed), context, 0, &mut handle)
+        })?;
+        events.handles.push(handle);
+
+        Ok(events)
+    }
+
+    /// Wait for the next notification; a change before the call counts too
+    pub(super) async fn next(&mut self) -> io::Result<()> {
+        self.notify.notified().await;
+        Ok(())
+    }
+}
+
+impl Drop for Events {
+    fn drop(&mut self) {
+        for handle in &self.handles {
+            // SAFETY: `handle` came from a successful registration. The call
+            // waits for running callbacks, so `notify` is freed after them.
+            unsafe { CancelMibChangeNotify2(*handle) };
+        }
+    }
+}
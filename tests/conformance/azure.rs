@@ -0,0 +1,142 @@
+use crate::*;
+use ddns_agent::provider::{AzureConfig, AzureProvider};
+use serde_json::{Value, json};
+use wiremock::matchers::{
+    any, body_partial_json, body_string_contains, header, method, path, query_param,
+};
+use wiremock::{Mock, ResponseTemplate};
+
+const SUBSCRIPTION: &str = "00000000-0000-0000-0000-000000000000";
+const RESOURCE_GROUP: &str = "dns";
+const TENANT: &str = "tenant-1";
+const ACCESS_TOKEN: &str = "test-token";
+const RECORD_SET_ID: &str = "/subscriptions/00000000-0000-0000-0000-000000000000/resourceGroups/dns/providers/Microsoft.Network/dnszones/example.com/A/home";
+
+struct Azure;
+
+fn zone_path() -> String {
+    format!(
+        "/subscriptions/{}/resourceGroups/{}/providers/Microsoft.Network/dnsZones/{}",
+        SUBSCRIPTION, RESOURCE_GROUP, ZONE_NAME
+    )
+}
+
+fn record_set(content: &str) -> Value {
+    json!({
+        "id": RECORD_SET_ID,
+        "name": "home",
+        "type": "Microsoft.Network/dnszones/A",
+        "etag": "etag-1",
+        "properties": {
+            "fqdn": format!("{}.", RECORD_NAME),
+            "TTL": TTL,
+            "ARecords": [{ "ipv4Address": content }],
+            "provisioningState": "Succeeded"
+        }
+    })
+}
+
+#[async_trait]
+impl Fixture for Azure {
+    fn provider(&self, server: &MockServer) -> Arc<dyn DnsProvider> {
+        let config = AzureConfig {
+            subscription_id: SUBSCRIPTION.to_string(),
+            resource_group: RESOURCE_GROUP.to_string(),
+            zone: None,
+            tenant_id: Some(TENANT.to_string()),
+            client_id: Some("client-1".to_string()),
+            client_secret: Some("secret".to_string()),
+            authority_host: Some(server.uri()),
+        };
+        let provider = AzureProvider::new(reqwest::Client::new(), &config)
+            .unwrap()
+            .with_base_url(server.uri());
+        Arc::new(provider)
+    }
+
+    fn zone(&self) -> Zone {
+        Zone {
+            id: ZONE_NAME.to_string(),
+            name: ZONE_NAME.to_string(),
+        }
+    }
+
+    fn record_id(&self) -> Option<&'static str> {
+        Some(RECORD_SET_ID)
+    }
+
+    /// Record sets are addressed by name
+    fn updates_by_id(&self) -> bool {
+        false
+    }
+
+    async fn mount(&self, server: &MockServer, scenario: Scenario) {
+        let response = match scenario {
+            // Record sets are addressed by name, there is nothing to page through
+            Scenario::Success | Scenario::Paginated => None,
+            Scenario::AuthFailure => Some(ResponseTemplate::new(401).set_body_json(json!({
+                "error": "invalid_client",
+                "error_description": "AADSTS7000215: Invalid client secret provided."
+            }))),
+            Scenario::RateLimited => Some(
+                ResponseTemplate::new(429)
+                    .insert_header("Retry-After", RETRY_AFTER_SECS.to_string().as_str()),
+            ),
+            Scenario::MalformedJson => {
+                Some(ResponseTemplate::new(200).set_body_string("{\"access_token\": "))
+            }
+        };
+        if let Some(response) = response {
+            Mock::given(any())
+                .respond_with(response)
+                .mount(server)
+                .await;
+            return;
+        }
+
+        Mock::given(method("POST"))
+            .and(path(format!("/{}/oauth2/v2.0/token", TENANT)))
+            .and(body_string_contains("grant_type=client_credentials"))
+            .and(body_string_contains("client_id=client-1"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "token_type": "Bearer",
+                "expires_in": 3599,
+                "ext_expires_in": 3599,
+                "access_token": ACCESS_TOKEN
+            })))
+            .mount(server)
+            .await;
+
+        Mock::given(method("GET"))
+            .and(path(zone_path()))
+            .and(query_param("api-version", "2018-05-01"))
+            .and(header("authorization", format!("Bearer {}", ACCESS_TOKEN)))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "id": format!("/subscriptions/{}/resourceGroups/{}/providers/Microsoft.Network/dnszones/{}", SUBSCRIPTION, RESOURCE_GROUP, ZONE_NAME),
+                "name": ZONE_NAME,
+                "type": "Microsoft.Network/dnszones",
+                "location": "global",
+                "properties": { "zoneType": "Public" }
+            })))
+            .mount(server)
+            .await;
+
+        let record_path = format!("{}/A/home", zone_path());
+        Mock::given(method("GET"))
+            .and(path(record_path.as_str()))
+            .respond_with(ResponseTemplate::new(200).set_body_json(record_set(OLD_IP)))
+            .mount(server)
+            .await;
+
+        Mock::given(method("PUT"))
+            .and(path(record_path.as_str()))
+            .and(body_partial_json(json!({
+                "properties": { "ARecords": [{ "ipv4Address": NEW_IP }] }
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(record_set(NEW_IP)))
+            .mount(server)
+            .await;
+    }
+}
+
+conformance!(Azure);
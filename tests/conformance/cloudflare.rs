@@ -0,0 +1,539 @@
+use crate::*;
+use ddns_agent::SrvRecord;
+use ddns_agent::owner;
+use ddns_agent::provider::{CloudflareLoadBalancer, CloudflarePoolProvider, CloudflareProvider};
+use serde_json::{Value, json};
+use wiremock::matchers::{any, body_partial_json, header, method, path, query_param};
+use wiremock::{Mock, ResponseTemplate};
+
+const TOKEN: &str = "test-token";
+
+struct Cloudflare;
+
+fn envelope(result: Value, page: u32, total_pages: u32) -> ResponseTemplate {
+    ResponseTemplate::new(200).set_body_json(json!({
+        "success": true,
+        "errors": [],
+        "result": result,
+        "result_info": { "page": page, "per_page": 1, "total_pages": total_pages }
+    }))
+}
+
+fn record(id: &str, name: &str, content: &str) -> Value {
+    json!({
+        "id": id,
+        "type": "A",
+        "name": name,
+        "content": content,
+        "ttl": TTL,
+        "proxied": false
+    })
+}
+
+#[async_trait]
+impl Fixture for Cloudflare {
+    fn provider(&self, server: &MockServer) -> Arc<dyn DnsProvider> {
+        Arc::new(CloudflareProvider::new(reqwest::Client::new(), TOKEN).with_base_url(server.uri()))
+    }
+
+    async fn mount(&self, server: &MockServer, scenario: Scenario) {
+        let response = match scenario {
+            Scenario::Success | Scenario::Paginated => None,
+            Scenario::AuthFailure => Some(ResponseTemplate::new(403).set_body_json(json!({
+                "success": false,
+                "errors": [{ "code": 10000, "message": "Authentication error" }],
+                "result": null
+            }))),
+            Scenario::RateLimited => Some(
+                ResponseTemplate::new(429)
+                    .insert_header("Retry-After", RETRY_AFTER_SECS.to_string().as_str()),
+            ),
+            Scenario::MalformedJson => {
+                Some(ResponseTemplate::new(200).set_body_string("<html>oops</html>"))
+            }
+        };
+        if let Some(response) = response {
+            Mock::given(any())
+                .respond_with(response)
+                .mount(server)
+                .await;
+            return;
+        }
+
+        Mock::given(method("GET"))
+            .and(path("/zones"))
+            .and(query_param("name", ZONE_NAME))
+            .and(header("authorization", format!("Bearer {}", TOKEN)))
+            .respond_with(envelope(
+                json!([{ "id": ZONE_ID, "name": ZONE_NAME }]),
+                1,
+                1,
+            ))
+            .mount(server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/zones"))
+            .and(query_param("name", UNKNOWN_ZONE))
+            .respond_with(envelope(json!([]), 1, 0))
+            .mount(server)
+            .await;
+
+        let records = format!("/zones/{}/dns_records", ZONE_ID);
+        let target = record(RECORD_ID, RECORD_NAME, OLD_IP);
+        if scenario == Scenario::Paginated {
+            Mock::given(method("GET"))
+                .and(path(records.as_str()))
+                .and(query_param("page", "1"))
+                .respond_with(envelope(
+                    json!([record("record-0", "other.example.com", OLD_IP)]),
+                    1,
+                    2,
+                ))
+                .mount(server)
+                .await;
+            Mock::given(method("GET"))
+                .and(path(records.as_str()))
+                .and(query_param("page", "2"))
+                .respond_with(envelope(json!([target]), 2, 2))
+                .mount(server)
+                .await;
+        } else {
+            Mock::given(method("GET"))
+                .and(path(records.as_str()))
+                .and(query_param("type", "A"))
+                .and(query_param("name", RECORD_NAME))
+                .respond_with(envelope(json!([target]), 1, 1))
+                .mount(server)
+                .await;
+        }
+
+        Mock::given(method("PUT"))
+            .and(path(format!("{}/{}", records, RECORD_ID)))
+            .and(body_partial_json(json!({ "type": "A", "content": NEW_IP })))
+            .respond_with(envelope(record(RECORD_ID, RECORD_NAME, NEW_IP), 1, 1))
+            .mount(server)
+            .await;
+    }
+}
+
+conformance!(Cloudflare);
+
+#[tokio::test]
+async fn ambiguous_zone() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/zones"))
+        .and(query_param("page", "1"))
+        .respond_with(envelope(
+            json!([{ "id": ZONE_ID, "name": ZONE_NAME }]),
+            1,
+            2,
+        ))
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/zones"))
+        .and(query_param("page", "2"))
+        .respond_with(envelope(
+            json!([
+                { "id": "zone-0", "name": "home.example.com" },
+                { "id": "zone-2", "name": ZONE_NAME }
+            ]),
+            2,
+            2,
+        ))
+        .mount(&server)
+        .await;
+
+    let err = Cloudflare
+        .provider(&server)
+        .lookup_zone(RECORD_NAME)
+        .await
+        .unwrap_err();
+    assert!(matches!(err, ProviderError::AmbiguousZone(_)), "{:?}", err);
+}
+
+#[tokio::test]
+async fn txt_challenge() {
+    let server = MockServer::start().await;
+    let records = format!("/zones/{}/dns_records", ZONE_ID);
+    let name = "_acme-challenge.example.com";
+    Mock::given(method("GET"))
+        .and(path("/zones"))
+        .respond_with(envelope(
+            json!([{ "id": ZONE_ID, "name": ZONE_NAME }]),
+            1,
+            1,
+        ))
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path(records.as_str()))
+        .and(body_partial_json(
+            json!({ "type": "TXT", "name": name, "content": "token" }),
+        ))
+        .respond_with(envelope(json!({ "id": "txt-1" }), 1, 1))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path(records.as_str()))
+        .and(query_param("type", "TXT"))
+        .respond_with(envelope(
+            json!([
+                { "id": "txt-0", "name": name, "content": "\"other\"" },
+                { "id": "txt-1", "name": name, "content": "\"token\"" }
+            ]),
+            1,
+            1,
+        ))
+        .mount(&server)
+        .await;
+    Mock::given(method("DELETE"))
+        .and(path(format!("{}/txt-1", records)))
+        .respond_with(envelope(json!({ "id": "txt-1" }), 1, 1))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let provider = Cloudflare.provider(&server);
+    ddns_agent::acme::set_txt(provider.as_ref(), "example.com", "token")
+        .await
+        .unwrap();
+    ddns_agent::acme::clear_txt(provider.as_ref(), "*.example.com", "token")
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn srv_record() {
+    let server = MockServer::start().await;
+    let records = format!("/zones/{}/dns_records", ZONE_ID);
+    let name = "_minecraft._tcp.home.example.com";
+    Mock::given(method("GET"))
+        .and(path(records.as_str()))
+        .and(query_param("type", "SRV"))
+        .respond_with(envelope(
+            json!([{
+                "id": "srv-1",
+                "name": name,
+                "ttl": 300,
+                "data": { "priority": 0, "weight": 5, "port": 25565, "target": "home.example.com" }
+            }]),
+            1,
+            1,
+        ))
+        .mount(&server)
+        .await;
+    Mock::given(method("PUT"))
+        .and(path(format!("{}/srv-1", records)))
+        .and(body_partial_json(json!({
+            "type": "SRV",
+            "name": name,
+            "data": { "port": 25566, "target": "home.example.com" }
+        })))
+        .respond_with(envelope(json!({ "id": "srv-1" }), 1, 1))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let provider = Cloudflare.provider(&server);
+    let zone = Cloudflare.zone();
+    let current = provider.read_srv(&zone, name).await.unwrap().unwrap();
+    assert_eq!((current.weight, current.port), (5, 25565));
+
+    let wanted = SrvRecord {
+        port: 25566,
+        ..current.clone()
+    };
+    assert!(!current.same_target(&wanted));
+    provider.upsert_srv(&zone, &wanted).await.unwrap();
+}
+
+#[tokio::test]
+async fn ownership_marker() {
+    let server = MockServer::start().await;
+    let records = format!("/zones/{}/dns_records", ZONE_ID);
+    let marker = format!("_ddns-owner.{}", RECORD_NAME);
+    Mock::given(method("GET"))
+        .and(path(records.as_str()))
+        .and(query_param("type", "TXT"))
+        .and(query_param("name", marker.as_str()))
+        .respond_with(envelope(
+            json!([{
+                "id": "txt-1",
+                "name": marker,
+                "content": "\"heritage=ddns-agent,owner=office\""
+            }]),
+            1,
+            1,
+        ))
+        .mount(&server)
+        .await;
+    Mock::given(method("DELETE"))
+        .and(path(format!("{}/txt-1", records)))
+        .respond_with(envelope(json!({ "id": "txt-1" }), 1, 1))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path(records.as_str()))
+        .and(body_partial_json(json!({
+            "type": "TXT",
+            "name": marker,
+            "content": "heritage=ddns-agent,owner=home"
+        })))
+        .respond_with(envelope(json!({ "id": "txt-2" }), 1, 1))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let provider = Cloudflare.provider(&server);
+    let zone = Cloudflare.zone();
+    let err = owner::claim(provider.as_ref(), &zone, RECORD_NAME, "home", false, false)
+        .await
+        .unwrap_err();
+    assert!(
+        matches!(&err, ProviderError::Owned { owner, .. } if owner == "office"),
+        "{:?}",
+        err
+    );
+
+    // Our own marker is left as it is
+    owner::claim(
+        provider.as_ref(),
+        &zone,
+        RECORD_NAME,
+        "office",
+        false,
+        false,
+    )
+    .await
+    .unwrap();
+    owner::claim(provider.as_ref(), &zone, RECORD_NAME, "home", true, false)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn shared_record_set() {
+    let server = MockServer::start().await;
+    let records = format!("/zones/{}/dns_records", ZONE_ID);
+    Mock::given(method("GET"))
+        .and(path(records.as_str()))
+        .and(query_param("type", "A"))
+        .respond_with(envelope(
+            json!([
+                record("rec-1", RECORD_NAME, "192.0.2.1"),
+                record("rec-2", RECORD_NAME, OLD_IP)
+            ]),
+            1,
+            1,
+        ))
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path(records.as_str()))
+        .and(body_partial_json(
+            json!({ "type": "A", "name": RECORD_NAME, "content": NEW_IP }),
+        ))
+        .respond_with(envelope(record("rec-3", RECORD_NAME, NEW_IP), 1, 1))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("DELETE"))
+        .and(path(format!("{}/rec-2", records)))
+        .respond_with(envelope(json!({ "id": "rec-2" }), 1, 1))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let provider = Cloudflare.provider(&server);
+    let zone = Cloudflare.zone();
+    let members = provider
+        .read_record_set(&zone, RECORD_NAME, RecordType::A)
+        .await
+        .unwrap();
+    assert_eq!(members.len(), 2);
+
+    let added = provider
+        .add_address(
+            &zone,
+            &Record {
+                content: NEW_IP.to_string(),
+                ..members[1].clone()
+            },
+        )
+        .await
+        .unwrap();
+    assert_eq!(added.id.as_deref(), Some("rec-3"));
+    provider.delete_address(&zone, &members[1]).await.unwrap();
+}
+
+#[tokio::test]
+async fn comment_and_tags() {
+    let server = MockServer::start().await;
+    let records = format!("/zones/{}/dns_records", ZONE_ID);
+    Mock::given(method("PUT"))
+        .and(path(format!("{}/{}", records, RECORD_ID)))
+        .and(body_partial_json(json!({ "tags": ["env:home"] })))
+        .respond_with(envelope(record(RECORD_ID, RECORD_NAME, NEW_IP), 1, 1))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let provider = CloudflareProvider::new(reqwest::Client::new(), TOKEN)
+        .with_base_url(server.uri())
+        .with_comment(Some("ddns-agent on nas, updated {time}".to_string()))
+        .with_tags(vec!["env:home".to_string()]);
+    let record = Record {
+        id: Some(RECORD_ID.to_string()),
+        name: RECORD_NAME.to_string(),
+        kind: RecordType::A,
+        content: NEW_IP.to_string(),
+        ttl: TTL,
+        proxied: Some(false),
+    };
+    provider
+        .upsert_record(&Cloudflare.zone(), &record)
+        .await
+        .unwrap();
+
+    let requests = server.received_requests().await.unwrap();
+    let body: Value = serde_json::from_slice(&requests[0].body).unwrap();
+    let comment = body["comment"].as_str().unwrap();
+    let pattern =
+        regex::Regex::new(r"^ddns-agent on nas, updated \d{4}-\d{2}-\d{2}T\d{2}:\d{2}:\d{2}Z$")
+            .unwrap();
+    assert!(pattern.is_match(comment), "{}", comment);
+}
+
+#[tokio::test]
+async fn batch_update() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path(format!("/zones/{}/dns_records/batch", ZONE_ID)))
+        .and(body_partial_json(json!({
+            "puts": [
+                { "id": "rec-1", "name": RECORD_NAME, "content": NEW_IP },
+                { "id": "rec-2", "name": "vpn.example.com", "content": NEW_IP }
+            ]
+        })))
+        .respond_with(envelope(
+            json!({
+                "puts": [
+                    record("rec-1", RECORD_NAME, NEW_IP),
+                    record("rec-2", "vpn.example.com", NEW_IP)
+                ]
+            }),
+            1,
+            1,
+        ))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let record = |id: &str, name: &str| Record {
+        id: Some(id.to_string()),
+        name: name.to_string(),
+        kind: RecordType::A,
+        content: NEW_IP.to_string(),
+        ttl: TTL,
+        proxied: Some(false),
+    };
+    let updated = Cloudflare
+        .provider(&server)
+        .update_records(
+            &Cloudflare.zone(),
+            &[
+                record("rec-1", RECORD_NAME),
+                record("rec-2", "vpn.example.com"),
+            ],
+        )
+        .await
+        .unwrap();
+    assert_eq!(updated[1].id.as_deref(), Some("rec-2"));
+    assert_eq!(updated[1].content, NEW_IP);
+}
+
+#[tokio::test]
+async fn further_account() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/zones"))
+        .and(header("authorization", "Bearer work-token"))
+        .respond_with(envelope(
+            json!([{ "id": ZONE_ID, "name": ZONE_NAME }]),
+            1,
+            1,
+        ))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let provider = CloudflareProvider::new(reqwest::Client::new(), "work-token")
+        .with_account("work")
+        .with_base_url(server.uri());
+    assert_eq!(provider.name(), "cloudflare.work");
+    let zone = provider.lookup_zone(RECORD_NAME).await.unwrap();
+    assert_eq!(zone.id, ZONE_ID);
+}
+
+#[tokio::test]
+async fn load_balancer_origin() {
+    let server = MockServer::start().await;
+    let pool_path = "/accounts/acct-1/load_balancers/pools/pool-1";
+    Mock::given(method("GET"))
+        .and(path(pool_path))
+        .respond_with(envelope(
+            json!({
+                "id": "pool-1",
+                "name": "home-pool",
+                "origins": [
+                    { "name": "home", "address": OLD_IP, "enabled": true, "weight": 1 },
+                    { "name": "office", "address": "192.0.2.9", "enabled": true, "weight": 1 }
+                ]
+            }),
+            1,
+            1,
+        ))
+        .mount(&server)
+        .await;
+    Mock::given(method("PATCH"))
+        .and(path(pool_path))
+        .and(body_partial_json(json!({
+            "origins": [
+                { "name": "home", "address": NEW_IP, "enabled": true, "weight": 1 },
+                { "name": "office", "address": "192.0.2.9", "enabled": true, "weight": 1 }
+            ]
+        })))
+        .respond_with(envelope(json!({ "id": "pool-1" }), 1, 1))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let pool = CloudflareLoadBalancer {
+        account_id: "acct-1".to_string(),
+        pool_id: "pool-1".to_string(),
+    };
+    let provider = CloudflarePoolProvider::new(reqwest::Client::new(), TOKEN, &pool)
+        .with_base_url(server.uri());
+    let zone = provider.lookup_zone("home").await.unwrap();
+    assert_eq!(zone.name, "home-pool");
+    let record = provider
+        .read_record(&zone, "home", RecordType::A)
+        .await
+        .unwrap()
+        .expect("origin is found");
+    assert_eq!(record.content, OLD_IP);
+    provider
+        .upsert_record(
+            &zone,
+            &Record {
+                content: NEW_IP.to_string(),
+                ..record
+            },
+        )
+        .await
+        .unwrap();
+}
@@ -0,0 +1,116 @@
+use crate::*;
+use ddns_agent::provider::{ClouDnsConfig, ClouDnsProvider};
+use serde_json::{Value, json};
+use wiremock::matchers::{any, body_string_contains, method, path};
+use wiremock::{Mock, ResponseTemplate};
+
+const SUB_AUTH_USER: &str = "ddns";
+const PASSWORD: &str = "test-password";
+
+struct ClouDns;
+
+fn record(id: &str, host: &str, content: &str) -> Value {
+    json!({
+        "id": id,
+        "type": "A",
+        "host": host,
+        "record": content,
+        "dynamicurl_status": 0,
+        "failover": "0",
+        "ttl": TTL.to_string(),
+        "status": 1
+    })
+}
+
+#[async_trait]
+impl Fixture for ClouDns {
+    fn provider(&self, server: &MockServer) -> Arc<dyn DnsProvider> {
+        let config = ClouDnsConfig {
+            auth_id: None,
+            sub_auth_id: None,
+            sub_auth_user: Some(SUB_AUTH_USER.to_string()),
+            auth_password: PASSWORD.to_string(),
+        };
+        Arc::new(
+            ClouDnsProvider::new(reqwest::Client::new(), &config)
+                .unwrap()
+                .with_base_url(server.uri()),
+        )
+    }
+
+    fn zone(&self) -> Zone {
+        Zone {
+            id: ZONE_NAME.to_string(),
+            name: ZONE_NAME.to_string(),
+        }
+    }
+
+    async fn mount(&self, server: &MockServer, scenario: Scenario) {
+        let response = match scenario {
+            Scenario::Success | Scenario::Paginated => None,
+            Scenario::AuthFailure => Some(ResponseTemplate::new(200).set_body_json(json!({
+                "status": "Failed",
+                "statusDescription": "Invalid authentication, incorrect auth-id or auth-password."
+            }))),
+            Scenario::RateLimited => Some(
+                ResponseTemplate::new(429)
+                    .insert_header("Retry-After", RETRY_AFTER_SECS.to_string().as_str()),
+            ),
+            Scenario::MalformedJson => {
+                Some(ResponseTemplate::new(200).set_body_string("{\"name\": "))
+            }
+        };
+        if let Some(response) = response {
+            Mock::given(any())
+                .respond_with(response)
+                .mount(server)
+                .await;
+            return;
+        }
+
+        let credentials = format!("sub-auth-user={}&auth-password={}", SUB_AUTH_USER, PASSWORD);
+        Mock::given(method("POST"))
+            .and(path("/dns/get-zone-info.json"))
+            .and(body_string_contains(credentials.as_str()))
+            .and(body_string_contains(format!("domain-name={}", ZONE_NAME)))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "name": ZONE_NAME,
+                "type": "master",
+                "zone": "domain",
+                "status": "1"
+            })))
+            .mount(server)
+            .await;
+
+        // Records are filtered server side and not paged; the paginated
+        // scenario adds a record of another type ahead of the target
+        let mut records = json!({ RECORD_ID: record(RECORD_ID, "home", OLD_IP) });
+        if scenario == Scenario::Paginated {
+            let mut other = record("record-2", "home", "2001:db8::1");
+            other["type"] = json!("AAAA");
+            records = json!({ "record-2": other, RECORD_ID: records[RECORD_ID] });
+        }
+        Mock::given(method("POST"))
+            .and(path("/dns/records.json"))
+            .and(body_string_contains("host=home&type=A"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(records))
+            .mount(server)
+            .await;
+
+        Mock::given(method("POST"))
+            .and(path("/dns/mod-record.json"))
+            .and(body_string_contains(format!(
+                "host=home&record={}&ttl={}&record-id={}",
+                NEW_IP, TTL, RECORD_ID
+            )))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "status": "Success",
+                "statusDescription": "The record was modified successfully."
+            })))
+            .expect(1)
+            .mount(server)
+            .await;
+    }
+}
+
+conformance!(ClouDns);
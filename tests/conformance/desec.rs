@@ -0,0 +1,100 @@
+use crate::*;
+use ddns_agent::provider::DesecProvider;
+use serde_json::json;
+use wiremock::matchers::{any, body_json, header, method, path, query_param};
+use wiremock::{Mock, ResponseTemplate};
+
+const TOKEN: &str = "test-token";
+/// deSEC's minimum TTL, which `TTL` is raised to
+const DESEC_TTL: u32 = 3600;
+
+struct Desec;
+
+#[async_trait]
+impl Fixture for Desec {
+    fn provider(&self, server: &MockServer) -> Arc<dyn DnsProvider> {
+        Arc::new(DesecProvider::new(reqwest::Client::new(), TOKEN).with_base_url(server.uri()))
+    }
+
+    fn zone(&self) -> Zone {
+        Zone {
+            id: ZONE_NAME.to_string(),
+            name: ZONE_NAME.to_string(),
+        }
+    }
+
+    fn record_id(&self) -> Option<&'static str> {
+        None
+    }
+
+    async fn mount(&self, server: &MockServer, scenario: Scenario) {
+        let response = match scenario {
+            // Record sets are addressed by name, there is nothing to page through
+            Scenario::Success | Scenario::Paginated => None,
+            Scenario::AuthFailure => Some(
+                ResponseTemplate::new(401).set_body_json(json!({ "detail": "Invalid token." })),
+            ),
+            Scenario::RateLimited => Some(
+                ResponseTemplate::new(429)
+                    .insert_header("Retry-After", RETRY_AFTER_SECS.to_string().as_str())
+                    .set_body_json(json!({
+                        "detail": "Request was throttled. Expected available in 30 seconds."
+                    })),
+            ),
+            Scenario::MalformedJson => Some(ResponseTemplate::new(200).set_body_string("[{")),
+        };
+        if let Some(response) = response {
+            Mock::given(any())
+                .respond_with(response)
+                .mount(server)
+                .await;
+            return;
+        }
+
+        Mock::given(method("GET"))
+            .and(path("/domains/"))
+            .and(query_param("owns_qname", RECORD_NAME))
+            .and(header("authorization", format!("Token {}", TOKEN)))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!([{
+                "name": ZONE_NAME,
+                "minimum_ttl": DESEC_TTL,
+                "created": "2024-01-01T00:00:00.000000Z",
+                "published": "2024-01-01T00:00:00.000000Z",
+                "touched": "2024-01-01T00:00:00.000000Z"
+            }])))
+            .mount(server)
+            .await;
+
+        Mock::given(method("GET"))
+            .and(path(format!("/domains/{}/rrsets/home/A/", ZONE_NAME)))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "name": format!("{}.", RECORD_NAME),
+                "subname": "home",
+                "type": "A",
+                "records": [OLD_IP],
+                "ttl": DESEC_TTL
+            })))
+            .mount(server)
+            .await;
+
+        Mock::given(method("PATCH"))
+            .and(path(format!("/domains/{}/rrsets/", ZONE_NAME)))
+            .and(body_json(json!([{
+                "subname": "home",
+                "type": "A",
+                "records": [NEW_IP],
+                "ttl": DESEC_TTL
+            }])))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!([{
+                "name": format!("{}.", RECORD_NAME),
+                "subname": "home",
+                "type": "A",
+                "records": [NEW_IP],
+                "ttl": DESEC_TTL
+            }])))
+            .mount(server)
+            .await;
+    }
+}
+
+conformance!(Desec);
@@ -0,0 +1,129 @@
+use crate::*;
+use ddns_agent::provider::DigitalOceanProvider;
+use serde_json::{Value, json};
+use wiremock::matchers::{any, body_partial_json, header, method, path, query_param};
+use wiremock::{Mock, ResponseTemplate};
+
+const TOKEN: &str = "test-token";
+
+struct DigitalOcean;
+
+fn record(id: u64, name: &str, content: &str) -> Value {
+    json!({
+        "id": id,
+        "type": "A",
+        "name": name,
+        "data": content,
+        "priority": null,
+        "port": null,
+        "ttl": TTL,
+        "weight": null,
+        "flags": null,
+        "tag": null
+    })
+}
+
+fn records(records: Value, next: Option<&str>) -> ResponseTemplate {
+    let links = match next {
+        Some(next) => json!({ "pages": { "next": next } }),
+        None => json!({}),
+    };
+    ResponseTemplate::new(200).set_body_json(json!({
+        "domain_records": records,
+        "links": links,
+        "meta": { "total": 2 }
+    }))
+}
+
+#[async_trait]
+impl Fixture for DigitalOcean {
+    fn provider(&self, server: &MockServer) -> Arc<dyn DnsProvider> {
+        Arc::new(
+            DigitalOceanProvider::new(reqwest::Client::new(), TOKEN).with_base_url(server.uri()),
+        )
+    }
+
+    fn zone(&self) -> Zone {
+        Zone {
+            id: ZONE_NAME.to_string(),
+            name: ZONE_NAME.to_string(),
+        }
+    }
+
+    fn record_id(&self) -> Option<&'static str> {
+        Some("1")
+    }
+
+    async fn mount(&self, server: &MockServer, scenario: Scenario) {
+        let response = match scenario {
+            Scenario::Success | Scenario::Paginated => None,
+            Scenario::AuthFailure => Some(ResponseTemplate::new(401).set_body_json(json!({
+                "id": "Unauthorized",
+                "message": "Unable to authenticate you"
+            }))),
+            Scenario::RateLimited => Some(
+                ResponseTemplate::new(429)
+                    .insert_header("Retry-After", RETRY_AFTER_SECS.to_string().as_str())
+                    .set_body_json(
+                        json!({ "id": "too_many_requests", "message": "API rate limit exceeded" }),
+                    ),
+            ),
+            Scenario::MalformedJson => {
+                Some(ResponseTemplate::new(200).set_body_string("{\"domain\": "))
+            }
+        };
+        if let Some(response) = response {
+            Mock::given(any())
+                .respond_with(response)
+                .mount(server)
+                .await;
+            return;
+        }
+
+        Mock::given(method("GET"))
+            .and(path(format!("/domains/{}", ZONE_NAME)))
+            .and(header("authorization", format!("Bearer {}", TOKEN)))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "domain": { "name": ZONE_NAME, "ttl": 1800, "zone_file": "" }
+            })))
+            .mount(server)
+            .await;
+
+        let records_path = format!("/domains/{}/records", ZONE_NAME);
+        let target = record(1, "home", OLD_IP);
+        let pages = match scenario {
+            Scenario::Paginated => vec![
+                records(
+                    json!([record(2, "other", OLD_IP)]),
+                    Some(&format!("{}{}?page=2", server.uri(), records_path)),
+                ),
+                records(json!([target]), None),
+            ],
+            _ => vec![records(json!([target]), None)],
+        };
+        for (i, page) in pages.into_iter().enumerate() {
+            Mock::given(method("GET"))
+                .and(path(records_path.as_str()))
+                .and(query_param("type", "A"))
+                .and(query_param("name", RECORD_NAME))
+                .and(query_param("page", (i + 1).to_string()))
+                .respond_with(page)
+                .mount(server)
+                .await;
+        }
+
+        Mock::given(method("PUT"))
+            .and(path(format!("{}/1", records_path)))
+            .and(body_partial_json(
+                json!({ "type": "A", "name": "home", "data": NEW_IP }),
+            ))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(json!({ "domain_record": record(1, "home", NEW_IP) })),
+            )
+            .mount(server)
+            .await;
+    }
+}
+
+conformance!(DigitalOcean);
@@ -0,0 +1,129 @@
+use crate::*;
+use ddns_agent::provider::{DnsimpleConfig, DnsimpleProvider};
+use serde_json::{Value, json};
+use wiremock::matchers::{any, body_json, header, method, path, query_param};
+use wiremock::{Mock, ResponseTemplate};
+
+const ACCOUNT_ID: &str = "1010";
+const TOKEN: &str = "test-token";
+
+struct Dnsimple;
+
+fn record(id: u64, name: &str, content: &str) -> Value {
+    json!({
+        "id": id,
+        "zone_id": ZONE_NAME,
+        "parent_id": null,
+        "name": name,
+        "content": content,
+        "ttl": TTL,
+        "priority": null,
+        "type": "A",
+        "regions": ["global"],
+        "system_record": false
+    })
+}
+
+fn records(records: Value, page: u32, total_pages: u32) -> ResponseTemplate {
+    ResponseTemplate::new(200).set_body_json(json!({
+        "data": records,
+        "pagination": {
+            "current_page": page,
+            "per_page": 1,
+            "total_entries": total_pages,
+            "total_pages": total_pages
+        }
+    }))
+}
+
+#[async_trait]
+impl Fixture for Dnsimple {
+    fn provider(&self, server: &MockServer) -> Arc<dyn DnsProvider> {
+        let config = DnsimpleConfig {
+            account_id: ACCOUNT_ID.to_string(),
+            api_token: TOKEN.to_string(),
+            sandbox: true,
+        };
+        Arc::new(DnsimpleProvider::new(reqwest::Client::new(), &config).with_base_url(server.uri()))
+    }
+
+    fn zone(&self) -> Zone {
+        Zone {
+            id: ZONE_NAME.to_string(),
+            name: ZONE_NAME.to_string(),
+        }
+    }
+
+    fn record_id(&self) -> Option<&'static str> {
+        Some("1")
+    }
+
+    async fn mount(&self, server: &MockServer, scenario: Scenario) {
+        let response = match scenario {
+            Scenario::Success | Scenario::Paginated => None,
+            Scenario::AuthFailure => Some(
+                ResponseTemplate::new(401)
+                    .set_body_json(json!({ "message": "Authentication failed" })),
+            ),
+            Scenario::RateLimited => Some(
+                ResponseTemplate::new(429)
+                    .insert_header("Retry-After", RETRY_AFTER_SECS.to_string().as_str()),
+            ),
+            Scenario::MalformedJson => {
+                Some(ResponseTemplate::new(200).set_body_string("{\"data\": "))
+            }
+        };
+        if let Some(response) = response {
+            Mock::given(any())
+                .respond_with(response)
+                .mount(server)
+                .await;
+            return;
+        }
+
+        let zone_path = format!("/{}/zones/{}", ACCOUNT_ID, ZONE_NAME);
+        Mock::given(method("GET"))
+            .and(path(zone_path.as_str()))
+            .and(header(
+                "authorization",
+                format!("Bearer {}", TOKEN).as_str(),
+            ))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "data": { "id": 1, "account_id": 1010, "name": ZONE_NAME, "reverse": false }
+            })))
+            .mount(server)
+            .await;
+
+        let records_path = format!("{}/records", zone_path);
+        let target = record(1, "home", OLD_IP);
+        let pages = match scenario {
+            Scenario::Paginated => vec![
+                records(json!([record(2, "other", OLD_IP)]), 1, 2),
+                records(json!([target]), 2, 2),
+            ],
+            _ => vec![records(json!([target]), 1, 1)],
+        };
+        for (i, page) in pages.into_iter().enumerate() {
+            Mock::given(method("GET"))
+                .and(path(records_path.as_str()))
+                .and(query_param("name", "home"))
+                .and(query_param("type", "A"))
+                .and(query_param("page", (i + 1).to_string()))
+                .respond_with(page)
+                .mount(server)
+                .await;
+        }
+
+        Mock::given(method("PATCH"))
+            .and(path(format!("{}/1", records_path)))
+            .and(body_json(json!({ "content": NEW_IP, "ttl": TTL })))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(json!({ "data": record(1, "home", NEW_IP) })),
+            )
+            .mount(server)
+            .await;
+    }
+}
+
+conformance!(Dnsimple);
@@ -0,0 +1,71 @@
+use crate::*;
+use ddns_agent::provider::{DuckDnsConfig, DuckDnsProvider};
+use wiremock::matchers::{any, method, path, query_param};
+use wiremock::{Mock, ResponseTemplate};
+
+const TOKEN: &str = "a7c4d0ad-114e-40ef-ba1d-d217904a50f2";
+
+struct DuckDns;
+
+#[async_trait]
+impl Fixture for DuckDns {
+    fn provider(&self, server: &MockServer) -> Arc<dyn DnsProvider> {
+        let config = DuckDnsConfig {
+            token: TOKEN.to_string(),
+            refresh_hours: 24,
+        };
+        Arc::new(DuckDnsProvider::new(reqwest::Client::new(), &config).with_base_url(server.uri()))
+    }
+
+    fn record_name(&self) -> &'static str {
+        "home.duckdns.org"
+    }
+
+    fn zone(&self) -> Zone {
+        Zone {
+            id: "home".to_string(),
+            name: "home.duckdns.org".to_string(),
+        }
+    }
+
+    fn record_id(&self) -> Option<&'static str> {
+        None
+    }
+
+    fn reads_records(&self) -> bool {
+        false
+    }
+
+    async fn mount(&self, server: &MockServer, scenario: Scenario) {
+        let response = match scenario {
+            // A single update request, nothing to page through
+            Scenario::Success | Scenario::Paginated => None,
+            Scenario::AuthFailure => Some(ResponseTemplate::new(200).set_body_string("KO")),
+            Scenario::RateLimited => Some(
+                ResponseTemplate::new(429)
+                    .insert_header("Retry-After", RETRY_AFTER_SECS.to_string().as_str()),
+            ),
+            Scenario::MalformedJson => {
+                Some(ResponseTemplate::new(200).set_body_string("<html>oops"))
+            }
+        };
+        if let Some(response) = response {
+            Mock::given(any())
+                .respond_with(response)
+                .mount(server)
+                .await;
+            return;
+        }
+
+        Mock::given(method("GET"))
+            .and(path("/update"))
+            .and(query_param("domains", "home"))
+            .and(query_param("token", TOKEN))
+            .and(query_param("ip", NEW_IP))
+            .respond_with(ResponseTemplate::new(200).set_body_string("OK"))
+            .mount(server)
+            .await;
+    }
+}
+
+conformance!(DuckDns);
@@ -0,0 +1,96 @@
+use crate::*;
+use ddns_agent::provider::{DynDns2Config, DynDns2Provider};
+use wiremock::matchers::{any, header, header_regex, method, path, query_param};
+use wiremock::{Mock, ResponseTemplate};
+
+struct DynDns2;
+
+#[async_trait]
+impl Fixture for DynDns2 {
+    fn provider(&self, server: &MockServer) -> Arc<dyn DnsProvider> {
+        let config = DynDns2Config {
+            url: server.uri(),
+            path: "/nic/update".to_string(),
+            username: "user".to_string(),
+            password: "pass".to_string(),
+        };
+        Arc::new(DynDns2Provider::new(reqwest::Client::new(), &config))
+    }
+
+    fn zone(&self) -> Zone {
+        Zone {
+            id: RECORD_NAME.to_string(),
+            name: RECORD_NAME.to_string(),
+        }
+    }
+
+    fn record_id(&self) -> Option<&'static str> {
+        None
+    }
+
+    fn reads_records(&self) -> bool {
+        false
+    }
+
+    async fn mount(&self, server: &MockServer, scenario: Scenario) {
+        let response = match scenario {
+            // A single update request, nothing to page through
+            Scenario::Success | Scenario::Paginated => None,
+            Scenario::AuthFailure => Some(ResponseTemplate::new(401).set_body_string("badauth")),
+            Scenario::RateLimited => Some(
+                ResponseTemplate::new(429)
+                    .insert_header("Retry-After", RETRY_AFTER_SECS.to_string().as_str()),
+            ),
+            Scenario::MalformedJson => {
+                Some(ResponseTemplate::new(200).set_body_string("<html>oops"))
+            }
+        };
+        if let Some(response) = response {
+            Mock::given(any())
+                .respond_with(response)
+                .mount(server)
+                .await;
+            return;
+        }
+
+        Mock::given(method("GET"))
+            .and(path("/nic/update"))
+            .and(query_param("hostname", RECORD_NAME))
+            .and(query_param("myip", NEW_IP))
+            // user:pass
+            .and(header("authorization", "Basic dXNlcjpwYXNz"))
+            .and(header_regex("user-agent", "^ddns-agent/"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(format!("good {}", NEW_IP)))
+            .mount(server)
+            .await;
+    }
+}
+
+conformance!(DynDns2);
+
+#[tokio::test]
+async fn wildcard_record() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/nic/update"))
+        .and(query_param("hostname", RECORD_NAME))
+        .and(query_param("wildcard", "ON"))
+        .respond_with(ResponseTemplate::new(200).set_body_string(format!("good {}", NEW_IP)))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let record = Record {
+        id: None,
+        name: format!("*.{}", RECORD_NAME),
+        kind: RecordType::A,
+        content: NEW_IP.to_string(),
+        ttl: 1,
+        proxied: None,
+    };
+    DynDns2
+        .provider(&server)
+        .upsert_record(&DynDns2.zone(), &record)
+        .await
+        .unwrap();
+}
@@ -0,0 +1,103 @@
+//! The provider runs a local command instead of talking HTTP, so it is tested
+//! with small shell scripts here rather than through [`Fixture`].
+
+use crate::*;
+use ddns_agent::provider::{ExecConfig, ExecProvider};
+
+fn provider(script: &str, timeout_secs: u64) -> ExecProvider {
+    ExecProvider::new(&ExecConfig {
+        command: "sh".into(),
+        args: vec![
+            "-c".to_string(),
+            script.to_string(),
+            "sh".to_string(),
+            "{hostname}".to_string(),
+            "{ip}".to_string(),
+        ],
+        timeout_secs,
+    })
+}
+
+async fn update(provider: &ExecProvider) -> Result<Record, ProviderError> {
+    let zone = provider.lookup_zone(RECORD_NAME).await.unwrap();
+    let record = provider
+        .read_record(&zone, RECORD_NAME, RecordType::A)
+        .await
+        .unwrap()
+        .expect("record exists");
+    let desired = Record {
+        content: NEW_IP.to_string(),
+        ..record
+    };
+    provider.upsert_record(&zone, &desired).await
+}
+
+#[tokio::test]
+async fn success() {
+    let dir = std::env::temp_dir().join(format!("ddns-agent-exec-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let out = dir.join("update");
+    let script = format!(
+        r#"echo "$1 $2 $DDNS_TYPE $DDNS_IP [$DDNS_OLD_IP]" > "{}""#,
+        out.display()
+    );
+    let provider = provider(&script, 5);
+
+    let updated = update(&provider).await.unwrap();
+    assert_eq!(updated.content, NEW_IP);
+    let written = std::fs::read_to_string(&out).unwrap();
+    assert_eq!(
+        written.trim(),
+        format!("{} {} A {} []", RECORD_NAME, NEW_IP, NEW_IP)
+    );
+
+    // The next update is told about the address set before
+    let zone = provider.lookup_zone(RECORD_NAME).await.unwrap();
+    let desired = Record {
+        content: OLD_IP.to_string(),
+        ..updated
+    };
+    provider.upsert_record(&zone, &desired).await.unwrap();
+    let written = std::fs::read_to_string(&out).unwrap();
+    assert!(
+        written.trim().ends_with(&format!("[{}]", NEW_IP)),
+        "{}",
+        written
+    );
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn failure() {
+    let provider = provider("echo working; echo 'bad token' >&2; exit 3", 5);
+    let err = update(&provider).await.unwrap_err();
+    match err {
+        ProviderError::Api(message) => {
+            assert!(message.contains("bad token"), "{}", message);
+            assert!(message.contains('3'), "{}", message);
+        }
+        err => panic!("unexpected error {:?}", err),
+    }
+}
+
+#[tokio::test]
+async fn timeout() {
+    let provider = provider("sleep 10", 1);
+    let err = update(&provider).await.unwrap_err();
+    assert!(
+        matches!(&err, ProviderError::Api(m) if m.contains("timed out")),
+        "{:?}",
+        err
+    );
+}
+
+#[tokio::test]
+async fn missing_command() {
+    let provider = ExecProvider::new(&ExecConfig {
+        command: "/nonexistent/ddns-hook".into(),
+        args: Vec::new(),
+        timeout_secs: 5,
+    });
+    let err = update(&provider).await.unwrap_err();
+    assert!(matches!(err, ProviderError::Api(_)), "{:?}", err);
+}
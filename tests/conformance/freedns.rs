@@ -0,0 +1,87 @@
+use crate::*;
+use ddns_agent::provider::{FreeDnsConfig, FreeDnsProvider};
+use wiremock::matchers::{any, method, path, query_param};
+use wiremock::{Mock, ResponseTemplate};
+
+const TOKEN: &str = "r4nd0mT0k3n";
+
+struct FreeDns {
+    version: u8,
+}
+
+#[async_trait]
+impl Fixture for FreeDns {
+    fn provider(&self, server: &MockServer) -> Arc<dyn DnsProvider> {
+        let config = FreeDnsConfig {
+            tokens: [(RECORD_NAME.to_string(), TOKEN.to_string())].into(),
+            version: self.version,
+        };
+        Arc::new(
+            FreeDnsProvider::new(reqwest::Client::new(), &config)
+                .unwrap()
+                .with_base_url(server.uri()),
+        )
+    }
+
+    fn zone(&self) -> Zone {
+        Zone {
+            id: RECORD_NAME.to_string(),
+            name: RECORD_NAME.to_string(),
+        }
+    }
+
+    fn record_id(&self) -> Option<&'static str> {
+        None
+    }
+
+    fn reads_records(&self) -> bool {
+        false
+    }
+
+    async fn mount(&self, server: &MockServer, scenario: Scenario) {
+        let response = match scenario {
+            // A single update request, nothing to page through
+            Scenario::Success | Scenario::Paginated => None,
+            Scenario::AuthFailure => {
+                Some(ResponseTemplate::new(200).set_body_string("ERROR: Invalid update token."))
+            }
+            Scenario::RateLimited => Some(
+                ResponseTemplate::new(429)
+                    .insert_header("Retry-After", RETRY_AFTER_SECS.to_string().as_str()),
+            ),
+            Scenario::MalformedJson => {
+                Some(ResponseTemplate::new(200).set_body_string("<html>oops"))
+            }
+        };
+        if let Some(response) = response {
+            Mock::given(any())
+                .respond_with(response)
+                .mount(server)
+                .await;
+            return;
+        }
+
+        let update = match self.version {
+            1 => Mock::given(method("GET"))
+                .and(path("/dynamic/update.php"))
+                .and(query_param(TOKEN, "")),
+            _ => Mock::given(method("GET")).and(path(format!("/u/{}/", TOKEN))),
+        };
+        update
+            .and(query_param("address", NEW_IP))
+            .respond_with(ResponseTemplate::new(200).set_body_string(format!(
+                "Updated 1 host(s) {} to {} in 0.21 seconds",
+                RECORD_NAME, NEW_IP
+            )))
+            .mount(server)
+            .await;
+    }
+}
+
+conformance!(FreeDns { version: 2 });
+
+mod version_1 {
+    use super::*;
+
+    conformance!(FreeDns { version: 1 });
+}
@@ -0,0 +1,91 @@
+use crate::*;
+use ddns_agent::provider::GandiProvider;
+use serde_json::json;
+use wiremock::matchers::{any, body_json, header, method, path};
+use wiremock::{Mock, ResponseTemplate};
+
+const TOKEN: &str = "test-token";
+
+struct Gandi;
+
+#[async_trait]
+impl Fixture for Gandi {
+    fn provider(&self, server: &MockServer) -> Arc<dyn DnsProvider> {
+        Arc::new(GandiProvider::new(reqwest::Client::new(), TOKEN).with_base_url(server.uri()))
+    }
+
+    fn zone(&self) -> Zone {
+        Zone {
+            id: ZONE_NAME.to_string(),
+            name: ZONE_NAME.to_string(),
+        }
+    }
+
+    fn record_id(&self) -> Option<&'static str> {
+        None
+    }
+
+    async fn mount(&self, server: &MockServer, scenario: Scenario) {
+        let response = match scenario {
+            // Record sets are addressed by name, there is nothing to page through
+            Scenario::Success | Scenario::Paginated => None,
+            Scenario::AuthFailure => Some(ResponseTemplate::new(401).set_body_json(json!({
+                "code": 401,
+                "message": "The server could not verify that you authorized to access the document you requested.",
+                "object": "HTTPUnauthorized",
+                "cause": "Unauthorized"
+            }))),
+            Scenario::RateLimited => Some(
+                ResponseTemplate::new(429)
+                    .insert_header("Retry-After", RETRY_AFTER_SECS.to_string().as_str()),
+            ),
+            Scenario::MalformedJson => {
+                Some(ResponseTemplate::new(200).set_body_string("{\"fqdn\": "))
+            }
+        };
+        if let Some(response) = response {
+            Mock::given(any())
+                .respond_with(response)
+                .mount(server)
+                .await;
+            return;
+        }
+
+        Mock::given(method("GET"))
+            .and(path(format!("/domains/{}", ZONE_NAME)))
+            .and(header("authorization", format!("Bearer {}", TOKEN)))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "fqdn": ZONE_NAME,
+                "automatic_snapshots": true,
+                "domain_href": format!("{}/domains/{}", server.uri(), ZONE_NAME)
+            })))
+            .mount(server)
+            .await;
+
+        let record_path = format!("/domains/{}/records/home/A", ZONE_NAME);
+        Mock::given(method("GET"))
+            .and(path(record_path.as_str()))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "rrset_name": "home",
+                "rrset_type": "A",
+                "rrset_ttl": TTL,
+                "rrset_values": [OLD_IP]
+            })))
+            .mount(server)
+            .await;
+
+        Mock::given(method("PUT"))
+            .and(path(record_path.as_str()))
+            .and(body_json(
+                json!({ "rrset_values": [NEW_IP], "rrset_ttl": TTL }),
+            ))
+            .respond_with(
+                ResponseTemplate::new(201)
+                    .set_body_json(json!({ "message": "DNS Record Created" })),
+            )
+            .mount(server)
+            .await;
+    }
+}
+
+conformance!(Gandi);
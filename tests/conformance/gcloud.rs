@@ -0,0 +1,164 @@
+use crate::*;
+use ddns_agent::provider::{GcloudConfig, GcloudProvider};
+use serde_json::{Value, json};
+use std::fs;
+use wiremock::matchers::{
+    any, body_partial_json, body_string_contains, header, method, path, query_param,
+    query_param_is_missing,
+};
+use wiremock::{Mock, ResponseTemplate};
+
+const PROJECT: &str = "test-project";
+const ACCESS_TOKEN: &str = "ya29.test";
+const PRIVATE_KEY: &str = include_str!("gcloud-test-key.pem");
+
+struct Gcloud;
+
+fn rrset(content: &str) -> Value {
+    json!({
+        "kind": "dns#resourceRecordSet",
+        "name": format!("{}.", RECORD_NAME),
+        "type": "A",
+        "ttl": TTL,
+        "rrdatas": [content]
+    })
+}
+
+fn zones(zones: Value, next: Option<&str>) -> ResponseTemplate {
+    ResponseTemplate::new(200).set_body_json(json!({
+        "managedZones": zones,
+        "nextPageToken": next
+    }))
+}
+
+fn zone(name: &str, visibility: &str) -> Value {
+    json!({
+        "name": name,
+        "dnsName": format!("{}.", ZONE_NAME),
+        "visibility": visibility
+    })
+}
+
+#[async_trait]
+impl Fixture for Gcloud {
+    fn provider(&self, server: &MockServer) -> Arc<dyn DnsProvider> {
+        let key = json!({
+            "type": "service_account",
+            "project_id": PROJECT,
+            "private_key_id": "1",
+            "private_key": PRIVATE_KEY,
+            "client_email": "ddns@test-project.iam.gserviceaccount.com",
+            "token_uri": format!("{}/token", server.uri())
+        });
+        let file = std::env::temp_dir().join(format!(
+            "ddns-agent-gcloud-{}.json",
+            server.address().port()
+        ));
+        fs::write(&file, key.to_string()).unwrap();
+
+        let config = GcloudConfig {
+            project: None,
+            credentials_file: Some(file),
+        };
+        let provider = GcloudProvider::new(reqwest::Client::new(), &config)
+            .unwrap()
+            .with_base_url(server.uri());
+        Arc::new(provider)
+    }
+
+    fn record_id(&self) -> Option<&'static str> {
+        None
+    }
+
+    async fn mount(&self, server: &MockServer, scenario: Scenario) {
+        let response = match scenario {
+            Scenario::Success | Scenario::Paginated => None,
+            Scenario::AuthFailure => Some(ResponseTemplate::new(400).set_body_json(json!({
+                "error": "invalid_grant",
+                "error_description": "Invalid JWT Signature."
+            }))),
+            Scenario::RateLimited => Some(
+                ResponseTemplate::new(429)
+                    .insert_header("Retry-After", RETRY_AFTER_SECS.to_string().as_str()),
+            ),
+            Scenario::MalformedJson => {
+                Some(ResponseTemplate::new(200).set_body_string("{\"access_token\": "))
+            }
+        };
+        if let Some(response) = response {
+            Mock::given(any())
+                .respond_with(response)
+                .mount(server)
+                .await;
+            return;
+        }
+
+        Mock::given(method("POST"))
+            .and(path("/token"))
+            .and(body_string_contains(
+                "grant_type=urn%3Aietf%3Aparams%3Aoauth%3Agrant-type%3Ajwt-bearer",
+            ))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "access_token": ACCESS_TOKEN,
+                "expires_in": 3599,
+                "token_type": "Bearer"
+            })))
+            .mount(server)
+            .await;
+
+        let base = format!("/projects/{}/managedZones", PROJECT);
+        let bearer = header("authorization", format!("Bearer {}", ACCESS_TOKEN));
+        let first_page = match scenario {
+            Scenario::Paginated => zones(json!([zone("internal", "private")]), Some("p2")),
+            _ => zones(json!([zone(ZONE_ID, "public")]), None),
+        };
+        Mock::given(method("GET"))
+            .and(path(base.as_str()))
+            .and(query_param("dnsName", format!("{}.", ZONE_NAME)))
+            .and(query_param_is_missing("pageToken"))
+            .and(bearer)
+            .respond_with(first_page)
+            .mount(server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path(base.as_str()))
+            .and(query_param("dnsName", format!("{}.", UNKNOWN_ZONE)))
+            .respond_with(zones(json!([]), None))
+            .mount(server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path(base.as_str()))
+            .and(query_param("pageToken", "p2"))
+            .respond_with(zones(json!([zone(ZONE_ID, "public")]), None))
+            .mount(server)
+            .await;
+
+        Mock::given(method("GET"))
+            .and(path(format!("{}/{}/rrsets", base, ZONE_ID)))
+            .and(query_param("name", format!("{}.", RECORD_NAME)))
+            .and(query_param("type", "A"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "rrsets": [rrset(OLD_IP)]
+            })))
+            .mount(server)
+            .await;
+
+        Mock::given(method("POST"))
+            .and(path(format!("{}/{}/changes", base, ZONE_ID)))
+            .and(body_partial_json(json!({
+                "deletions": [{ "type": "A", "rrdatas": [OLD_IP] }],
+                "additions": [{ "type": "A", "rrdatas": [NEW_IP] }]
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "kind": "dns#change",
+                "id": "1",
+                "status": "pending",
+                "additions": [rrset(NEW_IP)],
+                "deletions": [rrset(OLD_IP)]
+            })))
+            .mount(server)
+            .await;
+    }
+}
+
+conformance!(Gcloud);
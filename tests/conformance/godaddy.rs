@@ -0,0 +1,91 @@
+use crate::*;
+use ddns_agent::provider::{GoDaddyConfig, GoDaddyProvider};
+use serde_json::json;
+use wiremock::matchers::{any, body_json, header, method, path};
+use wiremock::{Mock, ResponseTemplate};
+
+/// GoDaddy's minimum TTL, which `TTL` is raised to
+const GODADDY_TTL: u32 = 600;
+
+struct GoDaddy;
+
+#[async_trait]
+impl Fixture for GoDaddy {
+    fn provider(&self, server: &MockServer) -> Arc<dyn DnsProvider> {
+        let config = GoDaddyConfig {
+            api_key: "key".to_string(),
+            api_secret: "secret".to_string(),
+        };
+        Arc::new(GoDaddyProvider::new(reqwest::Client::new(), &config).with_base_url(server.uri()))
+    }
+
+    fn zone(&self) -> Zone {
+        Zone {
+            id: ZONE_NAME.to_string(),
+            name: ZONE_NAME.to_string(),
+        }
+    }
+
+    fn record_id(&self) -> Option<&'static str> {
+        None
+    }
+
+    async fn mount(&self, server: &MockServer, scenario: Scenario) {
+        let response = match scenario {
+            // Records are addressed by name and type, there is nothing to page through
+            Scenario::Success | Scenario::Paginated => None,
+            Scenario::AuthFailure => Some(ResponseTemplate::new(401).set_body_json(json!({
+                "code": "UNABLE_TO_AUTHENTICATE",
+                "message": "Unauthorized : Could not authenticate API key/secret"
+            }))),
+            // The delay is only given in the body
+            Scenario::RateLimited => Some(ResponseTemplate::new(429).set_body_json(json!({
+                "code": "TOO_MANY_REQUESTS",
+                "message": "Too many requests",
+                "retryAfterSec": RETRY_AFTER_SECS
+            }))),
+            Scenario::MalformedJson => {
+                Some(ResponseTemplate::new(200).set_body_string("{\"domain\": "))
+            }
+        };
+        if let Some(response) = response {
+            Mock::given(any())
+                .respond_with(response)
+                .mount(server)
+                .await;
+            return;
+        }
+
+        Mock::given(method("GET"))
+            .and(path(format!("/domains/{}", ZONE_NAME)))
+            .and(header("authorization", "sso-key key:secret"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "domain": ZONE_NAME,
+                "domainId": 1,
+                "status": "ACTIVE"
+            })))
+            .mount(server)
+            .await;
+
+        let records_path = format!("/domains/{}/records/A/home", ZONE_NAME);
+        Mock::given(method("GET"))
+            .and(path(records_path.as_str()))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!([{
+                "data": OLD_IP,
+                "name": "home",
+                "ttl": GODADDY_TTL,
+                "type": "A"
+            }])))
+            .mount(server)
+            .await;
+
+        Mock::given(method("PUT"))
+            .and(path(records_path.as_str()))
+            .and(body_json(json!([{ "data": NEW_IP, "ttl": GODADDY_TTL }])))
+            .respond_with(ResponseTemplate::new(200))
+            .mount(server)
+            .await;
+    }
+}
+
+conformance!(GoDaddy);
@@ -0,0 +1,114 @@
+use crate::*;
+use ddns_agent::provider::HetznerProvider;
+use serde_json::{Value, json};
+use wiremock::matchers::{any, body_json, header, method, path, query_param};
+use wiremock::{Mock, ResponseTemplate};
+
+const TOKEN: &str = "test-token";
+
+struct Hetzner;
+
+fn record(id: &str, name: &str, content: &str) -> Value {
+    json!({
+        "id": id,
+        "type": "A",
+        "name": name,
+        "value": content,
+        "ttl": TTL,
+        "zone_id": ZONE_ID,
+        "created": "2024-01-01 00:00:00.000 +0000 UTC",
+        "modified": "2024-01-01 00:00:00.000 +0000 UTC"
+    })
+}
+
+fn records(records: Value, page: u32, last_page: u32) -> ResponseTemplate {
+    ResponseTemplate::new(200).set_body_json(json!({
+        "records": records,
+        "meta": {
+            "pagination": {
+                "page": page,
+                "per_page": 1,
+                "last_page": last_page,
+                "total_entries": last_page
+            }
+        }
+    }))
+}
+
+#[async_trait]
+impl Fixture for Hetzner {
+    fn provider(&self, server: &MockServer) -> Arc<dyn DnsProvider> {
+        Arc::new(HetznerProvider::new(reqwest::Client::new(), TOKEN).with_base_url(server.uri()))
+    }
+
+    async fn mount(&self, server: &MockServer, scenario: Scenario) {
+        let response = match scenario {
+            Scenario::Success | Scenario::Paginated => None,
+            Scenario::AuthFailure => Some(
+                ResponseTemplate::new(401)
+                    .set_body_json(json!({ "message": "Invalid authentication credentials" })),
+            ),
+            Scenario::RateLimited => Some(
+                ResponseTemplate::new(429)
+                    .insert_header("Retry-After", RETRY_AFTER_SECS.to_string().as_str()),
+            ),
+            Scenario::MalformedJson => {
+                Some(ResponseTemplate::new(200).set_body_string("{\"zones\": "))
+            }
+        };
+        if let Some(response) = response {
+            Mock::given(any())
+                .respond_with(response)
+                .mount(server)
+                .await;
+            return;
+        }
+
+        Mock::given(method("GET"))
+            .and(path("/zones"))
+            .and(query_param("name", ZONE_NAME))
+            .and(header("auth-api-token", TOKEN))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "zones": [{ "id": ZONE_ID, "name": ZONE_NAME, "ttl": 86400, "status": "verified" }],
+                "meta": { "pagination": { "page": 1, "per_page": 100, "last_page": 1, "total_entries": 1 } }
+            })))
+            .mount(server)
+            .await;
+
+        let target = record(RECORD_ID, "home", OLD_IP);
+        let pages = match scenario {
+            Scenario::Paginated => vec![
+                records(json!([record("record-2", "other", OLD_IP)]), 1, 2),
+                records(json!([target]), 2, 2),
+            ],
+            _ => vec![records(json!([target]), 1, 1)],
+        };
+        for (i, page) in pages.into_iter().enumerate() {
+            Mock::given(method("GET"))
+                .and(path("/records"))
+                .and(query_param("zone_id", ZONE_ID))
+                .and(query_param("page", (i + 1).to_string()))
+                .respond_with(page)
+                .mount(server)
+                .await;
+        }
+
+        Mock::given(method("PUT"))
+            .and(path(format!("/records/{}", RECORD_ID)))
+            .and(body_json(json!({
+                "zone_id": ZONE_ID,
+                "type": "A",
+                "name": "home",
+                "value": NEW_IP,
+                "ttl": TTL
+            })))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(json!({ "record": record(RECORD_ID, "home", NEW_IP) })),
+            )
+            .mount(server)
+            .await;
+    }
+}
+
+conformance!(Hetzner);
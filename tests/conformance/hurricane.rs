@@ -0,0 +1,68 @@
+use crate::*;
+use ddns_agent::provider::{HurricaneConfig, HurricaneProvider};
+use wiremock::matchers::{any, body_string_contains, method, path};
+use wiremock::{Mock, ResponseTemplate};
+
+const KEY: &str = "dDnSk3y";
+
+struct Hurricane;
+
+#[async_trait]
+impl Fixture for Hurricane {
+    fn provider(&self, server: &MockServer) -> Arc<dyn DnsProvider> {
+        let config = HurricaneConfig {
+            keys: [(RECORD_NAME.to_string(), KEY.to_string())].into(),
+        };
+        Arc::new(
+            HurricaneProvider::new(reqwest::Client::new(), &config).with_base_url(server.uri()),
+        )
+    }
+
+    fn zone(&self) -> Zone {
+        Zone {
+            id: RECORD_NAME.to_string(),
+            name: RECORD_NAME.to_string(),
+        }
+    }
+
+    fn record_id(&self) -> Option<&'static str> {
+        None
+    }
+
+    fn reads_records(&self) -> bool {
+        false
+    }
+
+    async fn mount(&self, server: &MockServer, scenario: Scenario) {
+        let response = match scenario {
+            // A single update request, nothing to page through
+            Scenario::Success | Scenario::Paginated => None,
+            Scenario::AuthFailure => Some(ResponseTemplate::new(401).set_body_string("badauth")),
+            Scenario::RateLimited => Some(
+                ResponseTemplate::new(429)
+                    .insert_header("Retry-After", RETRY_AFTER_SECS.to_string().as_str()),
+            ),
+            Scenario::MalformedJson => {
+                Some(ResponseTemplate::new(200).set_body_string("<html>oops"))
+            }
+        };
+        if let Some(response) = response {
+            Mock::given(any())
+                .respond_with(response)
+                .mount(server)
+                .await;
+            return;
+        }
+
+        Mock::given(method("POST"))
+            .and(path("/nic/update"))
+            .and(body_string_contains(format!("hostname={}", RECORD_NAME)))
+            .and(body_string_contains(format!("password={}", KEY)))
+            .and(body_string_contains(format!("myip={}", NEW_IP)))
+            .respond_with(ResponseTemplate::new(200).set_body_string(format!("good {}", NEW_IP)))
+            .mount(server)
+            .await;
+    }
+}
+
+conformance!(Hurricane);
@@ -0,0 +1,105 @@
+use crate::*;
+use ddns_agent::provider::InfomaniakProvider;
+use serde_json::{Value, json};
+use wiremock::matchers::{any, body_json, header, method, path, query_param};
+use wiremock::{Mock, ResponseTemplate};
+
+const TOKEN: &str = "test-token";
+const DOMAIN_ID: u64 = 4242;
+
+struct Infomaniak;
+
+fn data(data: Value) -> ResponseTemplate {
+    ResponseTemplate::new(200).set_body_json(json!({ "result": "success", "data": data }))
+}
+
+fn record(id: u64, source: &str, target: &str) -> Value {
+    json!({ "id": id, "source": source, "type": "A", "target": target, "ttl": TTL })
+}
+
+#[async_trait]
+impl Fixture for Infomaniak {
+    fn provider(&self, server: &MockServer) -> Arc<dyn DnsProvider> {
+        Arc::new(InfomaniakProvider::new(reqwest::Client::new(), TOKEN).with_base_url(server.uri()))
+    }
+
+    fn zone(&self) -> Zone {
+        Zone {
+            id: DOMAIN_ID.to_string(),
+            name: ZONE_NAME.to_string(),
+        }
+    }
+
+    fn record_id(&self) -> Option<&'static str> {
+        Some("1")
+    }
+
+    async fn mount(&self, server: &MockServer, scenario: Scenario) {
+        let response = match scenario {
+            Scenario::Success | Scenario::Paginated => None,
+            Scenario::AuthFailure => Some(ResponseTemplate::new(401).set_body_json(json!({
+                "result": "error",
+                "error": { "code": "not_authorized", "description": "Authorization required" }
+            }))),
+            Scenario::RateLimited => Some(
+                ResponseTemplate::new(429)
+                    .insert_header("Retry-After", RETRY_AFTER_SECS.to_string().as_str()),
+            ),
+            Scenario::MalformedJson => {
+                Some(ResponseTemplate::new(200).set_body_string("{\"result\": "))
+            }
+        };
+        if let Some(response) = response {
+            Mock::given(any())
+                .respond_with(response)
+                .mount(server)
+                .await;
+            return;
+        }
+
+        Mock::given(method("GET"))
+            .and(path("/1/product"))
+            .and(query_param("service_name", "domain"))
+            .and(query_param("customer_name", ZONE_NAME))
+            .and(header(
+                "authorization",
+                format!("Bearer {}", TOKEN).as_str(),
+            ))
+            .respond_with(data(json!([
+                { "id": DOMAIN_ID, "service_name": "domain", "customer_name": ZONE_NAME }
+            ])))
+            .mount(server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/1/product"))
+            .and(query_param("customer_name", UNKNOWN_ZONE))
+            .respond_with(data(json!([])))
+            .mount(server)
+            .await;
+
+        // The records aren't paged; the paginated scenario lists the record
+        // after others
+        let records_path = format!("/1/domain/{}/dns/record", DOMAIN_ID);
+        let records = match scenario {
+            Scenario::Paginated => json!([record(2, "other", OLD_IP), record(1, "home", OLD_IP)]),
+            _ => json!([record(1, "home", OLD_IP)]),
+        };
+        Mock::given(method("GET"))
+            .and(path(records_path.as_str()))
+            .respond_with(data(records))
+            .mount(server)
+            .await;
+
+        Mock::given(method("PUT"))
+            .and(path(format!("{}/1", records_path)))
+            .and(body_json(
+                json!({ "source": "home", "type": "A", "target": NEW_IP, "ttl": TTL }),
+            ))
+            .respond_with(data(json!(true)))
+            .expect(1)
+            .mount(server)
+            .await;
+    }
+}
+
+conformance!(Infomaniak);
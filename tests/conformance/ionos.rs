@@ -0,0 +1,97 @@
+use crate::*;
+use ddns_agent::provider::{IonosConfig, IonosProvider};
+use serde_json::{Value, json};
+use wiremock::matchers::{any, body_json, header, method, path, query_param};
+use wiremock::{Mock, ResponseTemplate};
+
+const PREFIX: &str = "prefix";
+const SECRET: &str = "secret";
+
+struct Ionos;
+
+fn zone(name: &str, id: &str) -> Value {
+    json!({ "name": name, "id": id, "type": "NATIVE" })
+}
+
+#[async_trait]
+impl Fixture for Ionos {
+    fn provider(&self, server: &MockServer) -> Arc<dyn DnsProvider> {
+        let config = IonosConfig {
+            public_prefix: PREFIX.to_string(),
+            secret: SECRET.to_string(),
+        };
+        Arc::new(IonosProvider::new(reqwest::Client::new(), &config).with_base_url(server.uri()))
+    }
+
+    async fn mount(&self, server: &MockServer, scenario: Scenario) {
+        let response = match scenario {
+            Scenario::Success | Scenario::Paginated => None,
+            Scenario::AuthFailure => Some(ResponseTemplate::new(401).set_body_json(json!([{
+                "code": "UNAUTHORIZED",
+                "message": "The customer is not authorized to do this operation."
+            }]))),
+            Scenario::RateLimited => Some(
+                ResponseTemplate::new(429)
+                    .insert_header("Retry-After", RETRY_AFTER_SECS.to_string().as_str()),
+            ),
+            Scenario::MalformedJson => Some(ResponseTemplate::new(200).set_body_string("[{")),
+        };
+        if let Some(response) = response {
+            Mock::given(any())
+                .respond_with(response)
+                .mount(server)
+                .await;
+            return;
+        }
+
+        // The API doesn't page zones; the paginated scenario lists the zone
+        // after others
+        let zones = match scenario {
+            Scenario::Paginated => json!([zone("example.org", "zone-2"), zone(ZONE_NAME, ZONE_ID)]),
+            _ => json!([zone(ZONE_NAME, ZONE_ID)]),
+        };
+        Mock::given(method("GET"))
+            .and(path("/zones"))
+            .and(header(
+                "x-api-key",
+                format!("{}.{}", PREFIX, SECRET).as_str(),
+            ))
+            .respond_with(ResponseTemplate::new(200).set_body_json(zones))
+            .mount(server)
+            .await;
+
+        Mock::given(method("GET"))
+            .and(path(format!("/zones/{}", ZONE_ID)))
+            .and(query_param("recordName", RECORD_NAME))
+            .and(query_param("recordType", "A"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "name": ZONE_NAME,
+                "id": ZONE_ID,
+                "type": "NATIVE",
+                "records": [{
+                    "name": RECORD_NAME,
+                    "rootName": ZONE_NAME,
+                    "type": "A",
+                    "content": OLD_IP,
+                    "changeDate": "2024-01-01T00:00:00.000Z",
+                    "ttl": TTL,
+                    "disabled": false,
+                    "id": RECORD_ID
+                }]
+            })))
+            .mount(server)
+            .await;
+
+        Mock::given(method("PUT"))
+            .and(path(format!("/zones/{}/records/{}", ZONE_ID, RECORD_ID)))
+            .and(body_json(
+                json!({ "content": NEW_IP, "ttl": TTL, "disabled": false }),
+            ))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(server)
+            .await;
+    }
+}
+
+conformance!(Ionos);
@@ -0,0 +1,134 @@
+use crate::*;
+use ddns_agent::provider::LinodeProvider;
+use serde_json::{Value, json};
+use wiremock::matchers::{any, body_json, header, method, path, query_param};
+use wiremock::{Mock, ResponseTemplate};
+
+const TOKEN: &str = "test-token";
+const DOMAIN_ID: u64 = 1;
+const LINODE_RECORD_ID: u64 = 1;
+
+struct Linode;
+
+fn record(id: u64, name: &str, target: &str) -> Value {
+    json!({
+        "id": id,
+        "type": "A",
+        "name": name,
+        "target": target,
+        "ttl_sec": TTL,
+        "priority": 0,
+        "weight": 0,
+        "port": 0
+    })
+}
+
+fn page(data: Value, page: u32, pages: u32) -> ResponseTemplate {
+    ResponseTemplate::new(200).set_body_json(json!({
+        "data": data,
+        "page": page,
+        "pages": pages,
+        "results": pages
+    }))
+}
+
+#[async_trait]
+impl Fixture for Linode {
+    fn provider(&self, server: &MockServer) -> Arc<dyn DnsProvider> {
+        Arc::new(LinodeProvider::new(reqwest::Client::new(), TOKEN).with_base_url(server.uri()))
+    }
+
+    fn zone(&self) -> Zone {
+        Zone {
+            id: DOMAIN_ID.to_string(),
+            name: ZONE_NAME.to_string(),
+        }
+    }
+
+    fn record_id(&self) -> Option<&'static str> {
+        Some("1")
+    }
+
+    async fn mount(&self, server: &MockServer, scenario: Scenario) {
+        let response = match scenario {
+            Scenario::Success | Scenario::Paginated => None,
+            Scenario::AuthFailure => Some(
+                ResponseTemplate::new(401)
+                    .set_body_json(json!({ "errors": [{ "reason": "Invalid Token" }] })),
+            ),
+            Scenario::RateLimited => Some(
+                ResponseTemplate::new(429)
+                    .insert_header("Retry-After", RETRY_AFTER_SECS.to_string().as_str()),
+            ),
+            Scenario::MalformedJson => {
+                Some(ResponseTemplate::new(200).set_body_string("{\"data\": "))
+            }
+        };
+        if let Some(response) = response {
+            Mock::given(any())
+                .respond_with(response)
+                .mount(server)
+                .await;
+            return;
+        }
+
+        Mock::given(method("GET"))
+            .and(path("/domains"))
+            .and(header("authorization", format!("Bearer {}", TOKEN).as_str()))
+            .and(header("x-filter", format!("{{\"domain\":\"{}\"}}", ZONE_NAME).as_str()))
+            .respond_with(page(
+                json!([{ "id": DOMAIN_ID, "domain": ZONE_NAME, "type": "master", "status": "active" }]),
+                1,
+                1,
+            ))
+            .mount(server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/domains"))
+            .and(header(
+                "x-filter",
+                format!("{{\"domain\":\"{}\"}}", UNKNOWN_ZONE).as_str(),
+            ))
+            .respond_with(page(json!([]), 1, 1))
+            .mount(server)
+            .await;
+
+        let target = record(LINODE_RECORD_ID, "home", OLD_IP);
+        let pages = match scenario {
+            Scenario::Paginated => vec![
+                page(json!([record(2, "other", OLD_IP)]), 1, 2),
+                page(json!([target]), 2, 2),
+            ],
+            _ => vec![page(json!([target]), 1, 1)],
+        };
+        for (i, response) in pages.into_iter().enumerate() {
+            Mock::given(method("GET"))
+                .and(path(format!("/domains/{}/records", DOMAIN_ID)))
+                .and(query_param("page", (i + 1).to_string()))
+                .respond_with(response)
+                .mount(server)
+                .await;
+        }
+
+        Mock::given(method("PUT"))
+            .and(path(format!(
+                "/domains/{}/records/{}",
+                DOMAIN_ID, LINODE_RECORD_ID
+            )))
+            .and(body_json(json!({
+                "type": "A",
+                "name": "home",
+                "target": NEW_IP,
+                "ttl_sec": TTL
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(record(
+                LINODE_RECORD_ID,
+                "home",
+                NEW_IP,
+            )))
+            .mount(server)
+            .await;
+    }
+}
+
+conformance!(Linode);
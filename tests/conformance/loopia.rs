@@ -0,0 +1,94 @@
+use crate::*;
+use ddns_agent::provider::loopia::LoopiaCredentials;
+use ddns_agent::provider::{LoopiaConfig, LoopiaProvider};
+use wiremock::matchers::{any, header, header_regex, method, path, query_param};
+use wiremock::{Mock, ResponseTemplate};
+
+struct Loopia {
+    /// Use credentials of the subdomain instead of the account login
+    subdomain: bool,
+}
+
+#[async_trait]
+impl Fixture for Loopia {
+    fn provider(&self, server: &MockServer) -> Arc<dyn DnsProvider> {
+        let credentials = LoopiaCredentials {
+            username: "user".to_string(),
+            password: "pass".to_string(),
+        };
+        let config = if self.subdomain {
+            LoopiaConfig {
+                username: Some("account".to_string()),
+                password: Some("secret".to_string()),
+                hosts: [(RECORD_NAME.to_string(), credentials)].into(),
+            }
+        } else {
+            LoopiaConfig {
+                username: Some(credentials.username),
+                password: Some(credentials.password),
+                hosts: Default::default(),
+            }
+        };
+        Arc::new(
+            LoopiaProvider::new(reqwest::Client::new(), &config)
+                .unwrap()
+                .with_base_url(server.uri()),
+        )
+    }
+
+    fn zone(&self) -> Zone {
+        Zone {
+            id: RECORD_NAME.to_string(),
+            name: RECORD_NAME.to_string(),
+        }
+    }
+
+    fn record_id(&self) -> Option<&'static str> {
+        None
+    }
+
+    fn reads_records(&self) -> bool {
+        false
+    }
+
+    async fn mount(&self, server: &MockServer, scenario: Scenario) {
+        let response = match scenario {
+            // A single update request, nothing to page through
+            Scenario::Success | Scenario::Paginated => None,
+            Scenario::AuthFailure => Some(ResponseTemplate::new(200).set_body_string("badauth")),
+            Scenario::RateLimited => Some(
+                ResponseTemplate::new(429)
+                    .insert_header("Retry-After", RETRY_AFTER_SECS.to_string().as_str()),
+            ),
+            Scenario::MalformedJson => {
+                Some(ResponseTemplate::new(200).set_body_string("<html>oops"))
+            }
+        };
+        if let Some(response) = response {
+            Mock::given(any())
+                .respond_with(response)
+                .mount(server)
+                .await;
+            return;
+        }
+
+        Mock::given(method("GET"))
+            .and(path("/"))
+            .and(query_param("hostname", RECORD_NAME))
+            .and(query_param("myip", NEW_IP))
+            // user:pass
+            .and(header("authorization", "Basic dXNlcjpwYXNz"))
+            .and(header_regex("user-agent", "^ddns-agent/"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("good"))
+            .mount(server)
+            .await;
+    }
+}
+
+conformance!(Loopia { subdomain: false });
+
+mod subdomain {
+    use super::*;
+
+    conformance!(Loopia { subdomain: true });
+}
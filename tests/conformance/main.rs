@@ -0,0 +1,388 @@
+//! Provider conformance suite.
+//!
+//! Every [`DnsProvider`] is run through the same scenarios against a mock
+//! HTTP server serving canned API responses. To add a provider, implement
+//! [`Fixture`] for it in a new module and invoke [`conformance!`] there.
+
+use async_trait::async_trait;
+use ddns_agent::{DnsProvider, ProviderError, Record, RecordType, Zone};
+use std::sync::Arc;
+use std::time::Duration;
+use wiremock::matchers::any;
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+#[cfg(feature = "azure")]
+mod azure;
+#[cfg(feature = "cloudflare")]
+mod cloudflare;
+#[cfg(feature = "cloudns")]
+mod cloudns;
+#[cfg(feature = "desec")]
+mod desec;
+#[cfg(feature = "digitalocean")]
+mod digitalocean;
+#[cfg(feature = "dnsimple")]
+mod dnsimple;
+#[cfg(feature = "dnsomatic")]
+mod dnsomatic;
+#[cfg(feature = "duckdns")]
+mod duckdns;
+#[cfg(feature = "dyndns2")]
+mod dyndns2;
+#[cfg(feature = "dynu")]
+mod dynu;
+#[cfg(all(feature = "exec", unix))]
+mod exec;
+#[cfg(feature = "freedns")]
+mod freedns;
+#[cfg(feature = "gandi")]
+mod gandi;
+#[cfg(feature = "gcloud")]
+mod gcloud;
+#[cfg(feature = "godaddy")]
+mod godaddy;
+#[cfg(feature = "hetzner")]
+mod hetzner;
+#[cfg(feature = "hurricane")]
+mod hurricane;
+#[cfg(feature = "infomaniak")]
+mod infomaniak;
+#[cfg(feature = "ionos")]
+mod ionos;
+#[cfg(feature = "linode")]
+mod linode;
+#[cfg(feature = "loopia")]
+mod loopia;
+#[cfg(feature = "namecheap")]
+mod namecheap;
+#[cfg(feature = "namecom")]
+mod namecom;
+#[cfg(feature = "netlify")]
+mod netlify;
+#[cfg(feature = "njalla")]
+mod njalla;
+#[cfg(feature = "ovh")]
+mod ovh;
+#[cfg(feature = "pihole")]
+mod pihole;
+#[cfg(feature = "porkbun")]
+mod porkbun;
+#[cfg(feature = "powerdns")]
+mod powerdns;
+#[cfg(feature = "rfc2136")]
+mod rfc2136;
+#[cfg(feature = "route53")]
+mod route53;
+#[cfg(feature = "scaleway")]
+mod scaleway;
+#[cfg(feature = "script")]
+mod script;
+#[cfg(feature = "technitium")]
+mod technitium;
+#[cfg(feature = "tunnelbroker")]
+mod tunnelbroker;
+#[cfg(feature = "vultr")]
+mod vultr;
+#[cfg(feature = "webhook")]
+mod webhook;
+
+pub const ZONE_ID: &str = "zone-1";
+pub const ZONE_NAME: &str = "example.com";
+pub const RECORD_ID: &str = "record-1";
+pub const RECORD_NAME: &str = "home.example.com";
+pub const OLD_IP: &str = "192.0.2.1";
+pub const NEW_IP: &str = "192.0.2.2";
+pub const TTL: u32 = 300;
+pub const RETRY_AFTER_SECS: u64 = 30;
+/// ID of a record deleted since it was looked up
+pub const STALE_RECORD_ID: &str = "record-0";
+/// Domain the account doesn't hold, and a name in it
+pub const UNKNOWN_ZONE: &str = "example.net";
+pub const UNKNOWN_NAME: &str = "home.example.net";
+
+/// Canned API behaviour a fixture has to reproduce
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Scenario {
+    /// Zone `ZONE_NAME` holds an `A` record `RECORD_NAME` pointing at
+    /// `OLD_IP`, and updating it succeeds
+    Success,
+    /// Every request is rejected because of bad credentials
+    AuthFailure,
+    /// Every request is rate limited with a `Retry-After` of
+    /// `RETRY_AFTER_SECS`
+    RateLimited,
+    /// Every response body is invalid JSON
+    MalformedJson,
+    /// Like `Success`, but the record is only listed on the second page
+    Paginated,
+}
+
+#[async_trait]
+pub trait Fixture: Sync {
+    /// Provider under test, talking to `server`
+    fn provider(&self, server: &MockServer) -> Arc<dyn DnsProvider>;
+
+    /// Mount the mocks reproducing `scenario` on `server`
+    async fn mount(&self, server: &MockServer, scenario: Scenario);
+
+    /// Zone the provider reports for `ZONE_NAME`
+    fn zone(&self) -> Zone {
+        Zone {
+            id: ZONE_ID.to_string(),
+            name: ZONE_NAME.to_string(),
+        }
+    }
+
+    /// Name of the record, for providers that only serve their own domain
+    fn record_name(&self) -> &'static str {
+        RECORD_NAME
+    }
+
+    /// ID the provider reports for the record, `None` for providers that
+    /// address records by name
+    fn record_id(&self) -> Option<&'static str> {
+        Some(RECORD_ID)
+    }
+
+    /// ID the provider reports after the update, for providers that replace
+    /// records instead of editing them
+    fn updated_record_id(&self) -> Option<&'static str> {
+        self.record_id()
+    }
+
+    /// Whether the provider updates records by the ID it reported, rather
+    /// than by name or by replacing them
+    fn updates_by_id(&self) -> bool {
+        self.record_id().is_some()
+    }
+
+    /// Whether the provider tells names outside the zones of the account
+    /// apart, rather than serving any name
+    fn knows_zones(&self) -> bool {
+        self.reads_records()
+    }
+
+    /// Whether the provider reads zones and records from its API. Update-only
+    /// providers have the error scenarios checked on `upsert_record` instead.
+    fn reads_records(&self) -> bool {
+        true
+    }
+}
+
+async fn setup(fixture: &impl Fixture, scenario: Scenario) -> (MockServer, Arc<dyn DnsProvider>) {
+    let server = MockServer::start().await;
+    fixture.mount(&server, scenario).await;
+    let provider = fixture.provider(&server);
+    (server, provider)
+}
+
+fn desired(fixture: &impl Fixture) -> Record {
+    Record {
+        id: fixture.record_id().map(str::to_string),
+        name: fixture.record_name().to_string(),
+        kind: RecordType::A,
+        content: NEW_IP.to_string(),
+        ttl: TTL,
+        proxied: None,
+    }
+}
+
+/// The error of the first request the provider sends
+async fn first_error(fixture: &impl Fixture, provider: &dyn DnsProvider) -> ProviderError {
+    if fixture.reads_records() {
+        provider
+            .lookup_zone(fixture.record_name())
+            .await
+            .unwrap_err()
+    } else {
+        provider
+            .upsert_record(&fixture.zone(), &desired(fixture))
+            .await
+            .unwrap_err()
+    }
+}
+
+async fn read_and_update(fixture: &impl Fixture, provider: &dyn DnsProvider) {
+    let record_id = fixture.record_id();
+    let zone = provider.lookup_zone(fixture.record_name()).await.unwrap();
+    assert_eq!(zone, fixture.zone());
+    if !fixture.reads_records() {
+        let updated = provider
+            .upsert_record(&zone, &desired(fixture))
+            .await
+            .unwrap();
+        assert_eq!(updated.content, NEW_IP);
+        return;
+    }
+
+    let record = provider
+        .read_record(&zone, fixture.record_name(), RecordType::A)
+        .await
+        .unwrap()
+        .expect("record exists");
+    assert_eq!(record.id.as_deref(), record_id);
+    assert_eq!(record.name, fixture.record_name());
+    assert_eq!(record.kind, RecordType::A);
+    assert_eq!(record.content, OLD_IP);
+
+    let desired = Record {
+        content: NEW_IP.to_string(),
+        ..record
+    };
+    let updated = provider.upsert_record(&zone, &desired).await.unwrap();
+    assert_eq!(updated.id.as_deref(), fixture.updated_record_id());
+    assert_eq!(updated.content, NEW_IP);
+    if let Some(ttl) = provider.effective_ttl(&desired) {
+        assert_eq!(updated.ttl, ttl, "stored TTL");
+    }
+}
+
+pub async fn success(fixture: &impl Fixture) {
+    let (_server, provider) = setup(fixture, Scenario::Success).await;
+    read_and_update(fixture, provider.as_ref()).await;
+}
+
+pub async fn auth_failure(fixture: &impl Fixture) {
+    let (_server, provider) = setup(fixture, Scenario::AuthFailure).await;
+    let err = first_error(fixture, provider.as_ref()).await;
+    assert!(matches!(err, ProviderError::Auth(_)), "{:?}", err);
+    if !fixture.reads_records() {
+        return;
+    }
+
+    let err = provider
+        .read_record(&fixture.zone(), fixture.record_name(), RecordType::A)
+        .await
+        .unwrap_err();
+    assert!(matches!(err, ProviderError::Auth(_)), "{:?}", err);
+}
+
+pub async fn rate_limited(fixture: &impl Fixture) {
+    let (_server, provider) = setup(fixture, Scenario::RateLimited).await;
+    let err = first_error(fixture, provider.as_ref()).await;
+    match err {
+        ProviderError::RateLimited { retry_after } => {
+            assert_eq!(retry_after, Some(Duration::from_secs(RETRY_AFTER_SECS)))
+        }
+        other => panic!("expected rate limit error, got {:?}", other),
+    }
+}
+
+pub async fn malformed_json(fixture: &impl Fixture) {
+    let (_server, provider) = setup(fixture, Scenario::MalformedJson).await;
+    let err = first_error(fixture, provider.as_ref()).await;
+    assert!(
+        matches!(err, ProviderError::InvalidResponse(_)),
+        "{:?}",
+        err
+    );
+    if !fixture.reads_records() {
+        return;
+    }
+
+    let err = provider
+        .read_record(&fixture.zone(), fixture.record_name(), RecordType::A)
+        .await
+        .unwrap_err();
+    assert!(
+        matches!(err, ProviderError::InvalidResponse(_)),
+        "{:?}",
+        err
+    );
+}
+
+pub async fn pagination(fixture: &impl Fixture) {
+    let (_server, provider) = setup(fixture, Scenario::Paginated).await;
+    read_and_update(fixture, provider.as_ref()).await;
+}
+
+/// An API failing with a 5xx is reported as unavailable, so another
+/// provider may take over
+pub async fn server_error(fixture: &impl Fixture) {
+    let server = MockServer::start().await;
+    Mock::given(any())
+        .respond_with(ResponseTemplate::new(503).set_body_string("Service Unavailable"))
+        .mount(&server)
+        .await;
+    let provider = fixture.provider(&server);
+    let err = first_error(fixture, provider.as_ref()).await;
+    assert!(matches!(err, ProviderError::Unavailable(_)), "{:?}", err);
+}
+
+/// A record remembered under an ID that no longer exists is reported as not
+/// found, so the agent looks it up again and finds its current ID
+pub async fn stale_id(fixture: &impl Fixture) {
+    if !fixture.reads_records() || !fixture.updates_by_id() {
+        return;
+    }
+    let (_server, provider) = setup(fixture, Scenario::Success).await;
+    let stale = Record {
+        id: Some(STALE_RECORD_ID.to_string()),
+        ..desired(fixture)
+    };
+    let err = provider
+        .upsert_record(&fixture.zone(), &stale)
+        .await
+        .unwrap_err();
+    assert!(err.is_not_found(), "{:?}", err);
+    read_and_update(fixture, provider.as_ref()).await;
+}
+
+/// A name outside every zone of the account is reported as such, not as a
+/// missing record or a failed request
+pub async fn unknown_zone(fixture: &impl Fixture) {
+    if !fixture.knows_zones() {
+        return;
+    }
+    let (_server, provider) = setup(fixture, Scenario::Success).await;
+    let err = provider.lookup_zone(UNKNOWN_NAME).await.unwrap_err();
+    assert!(matches!(err, ProviderError::ZoneNotFound(_)), "{:?}", err);
+    // The known zone still works
+    read_and_update(fixture, provider.as_ref()).await;
+}
+
+/// Generate one test per scenario for the given fixture
+#[macro_export]
+macro_rules! conformance {
+    ($fixture:expr) => {
+        #[tokio::test]
+        async fn success() {
+            $crate::success(&$fixture).await
+        }
+
+        #[tokio::test]
+        async fn auth_failure() {
+            $crate::auth_failure(&$fixture).await
+        }
+
+        #[tokio::test]
+        async fn rate_limited() {
+            $crate::rate_limited(&$fixture).await
+        }
+
+        #[tokio::test]
+        async fn malformed_json() {
+            $crate::malformed_json(&$fixture).await
+        }
+
+        #[tokio::test]
+        async fn pagination() {
+            $crate::pagination(&$fixture).await
+        }
+
+        #[tokio::test]
+        async fn server_error() {
+            $crate::server_error(&$fixture).await
+        }
+
+        #[tokio::test]
+        async fn stale_id() {
+            $crate::stale_id(&$fixture).await
+        }
+
+        #[tokio::test]
+        async fn unknown_zone() {
+            $crate::unknown_zone(&$fixture).await
+        }
+    };
+}
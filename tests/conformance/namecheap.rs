@@ -0,0 +1,88 @@
+use crate::*;
+use ddns_agent::provider::{NamecheapConfig, NamecheapProvider};
+use wiremock::matchers::{any, method, path, query_param};
+use wiremock::{Mock, ResponseTemplate};
+
+const PASSWORD: &str = "ddns-password";
+
+struct Namecheap;
+
+fn xml(body: String) -> ResponseTemplate {
+    ResponseTemplate::new(200).set_body_raw(body, "text/html")
+}
+
+fn interface_response(ip: &str, errors: &[&str]) -> String {
+    let errors: String = errors
+        .iter()
+        .enumerate()
+        .map(|(i, e)| format!("<Err{0}>{1}</Err{0}>", i + 1, e))
+        .collect();
+    format!(
+        "<?xml version=\"1.0\" encoding=\"utf-16\"?><interface-response><Command>SETDNSHOST</Command><Language>eng</Language><IP>{}</IP><ErrCount>{}</ErrCount><errors>{}</errors><ResponseCount>0</ResponseCount><Done>true</Done><debug><![CDATA[]]></debug></interface-response>",
+        ip,
+        errors.len(),
+        errors
+    )
+}
+
+#[async_trait]
+impl Fixture for Namecheap {
+    fn provider(&self, server: &MockServer) -> Arc<dyn DnsProvider> {
+        let config = NamecheapConfig {
+            password: PASSWORD.to_string(),
+            domain: None,
+        };
+        Arc::new(
+            NamecheapProvider::new(reqwest::Client::new(), &config).with_base_url(server.uri()),
+        )
+    }
+
+    fn zone(&self) -> Zone {
+        Zone {
+            id: ZONE_NAME.to_string(),
+            name: ZONE_NAME.to_string(),
+        }
+    }
+
+    fn record_id(&self) -> Option<&'static str> {
+        None
+    }
+
+    fn reads_records(&self) -> bool {
+        false
+    }
+
+    async fn mount(&self, server: &MockServer, scenario: Scenario) {
+        let response = match scenario {
+            // A single update request, nothing to page through
+            Scenario::Success | Scenario::Paginated => None,
+            Scenario::AuthFailure => {
+                Some(xml(interface_response(NEW_IP, &["Passwords do not match"])))
+            }
+            Scenario::RateLimited => Some(
+                ResponseTemplate::new(429)
+                    .insert_header("Retry-After", RETRY_AFTER_SECS.to_string().as_str()),
+            ),
+            Scenario::MalformedJson => Some(xml("<html><body>oops".to_string())),
+        };
+        if let Some(response) = response {
+            Mock::given(any())
+                .respond_with(response)
+                .mount(server)
+                .await;
+            return;
+        }
+
+        Mock::given(method("GET"))
+            .and(path("/update"))
+            .and(query_param("host", "home"))
+            .and(query_param("domain", ZONE_NAME))
+            .and(query_param("password", PASSWORD))
+            .and(query_param("ip", NEW_IP))
+            .respond_with(xml(interface_response(NEW_IP, &[])))
+            .mount(server)
+            .await;
+    }
+}
+
+conformance!(Namecheap);
@@ -0,0 +1,105 @@
+use crate::*;
+use ddns_agent::provider::{NameComConfig, NameComProvider};
+use serde_json::{Value, json};
+use wiremock::matchers::{any, body_json, header, method, path, query_param};
+use wiremock::{Mock, ResponseTemplate};
+
+struct NameCom;
+
+fn record(id: u64, host: &str, answer: &str) -> Value {
+    json!({
+        "id": id,
+        "domainName": ZONE_NAME,
+        "host": host,
+        "fqdn": format!("{}.{}.", host, ZONE_NAME),
+        "type": "A",
+        "answer": answer,
+        "ttl": TTL
+    })
+}
+
+#[async_trait]
+impl Fixture for NameCom {
+    fn provider(&self, server: &MockServer) -> Arc<dyn DnsProvider> {
+        let config = NameComConfig {
+            username: "user".to_string(),
+            api_token: "pass".to_string(),
+        };
+        Arc::new(NameComProvider::new(reqwest::Client::new(), &config).with_base_url(server.uri()))
+    }
+
+    fn zone(&self) -> Zone {
+        Zone {
+            id: ZONE_NAME.to_string(),
+            name: ZONE_NAME.to_string(),
+        }
+    }
+
+    fn record_id(&self) -> Option<&'static str> {
+        Some("1")
+    }
+
+    async fn mount(&self, server: &MockServer, scenario: Scenario) {
+        let response = match scenario {
+            Scenario::Success | Scenario::Paginated => None,
+            Scenario::AuthFailure => Some(
+                ResponseTemplate::new(401).set_body_json(json!({ "message": "Unauthenticated" })),
+            ),
+            Scenario::RateLimited => Some(
+                ResponseTemplate::new(429)
+                    .insert_header("Retry-After", RETRY_AFTER_SECS.to_string().as_str()),
+            ),
+            Scenario::MalformedJson => {
+                Some(ResponseTemplate::new(200).set_body_string("{\"records\": "))
+            }
+        };
+        if let Some(response) = response {
+            Mock::given(any())
+                .respond_with(response)
+                .mount(server)
+                .await;
+            return;
+        }
+
+        Mock::given(method("GET"))
+            .and(path(format!("/domains/{}", ZONE_NAME)))
+            .and(header("authorization", "Basic dXNlcjpwYXNz"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "domainName": ZONE_NAME,
+                "locked": true,
+                "autorenewEnabled": true
+            })))
+            .mount(server)
+            .await;
+
+        let records_path = format!("/domains/{}/records", ZONE_NAME);
+        let target = record(1, "home", OLD_IP);
+        let pages = match scenario {
+            Scenario::Paginated => vec![
+                json!({ "records": [record(2, "other", OLD_IP)], "nextPage": 2, "lastPage": 2 }),
+                json!({ "records": [target], "lastPage": 2 }),
+            ],
+            _ => vec![json!({ "records": [target], "lastPage": 1 })],
+        };
+        for (i, page) in pages.into_iter().enumerate() {
+            Mock::given(method("GET"))
+                .and(path(records_path.as_str()))
+                .and(query_param("page", (i + 1).to_string()))
+                .respond_with(ResponseTemplate::new(200).set_body_json(page))
+                .mount(server)
+                .await;
+        }
+
+        Mock::given(method("PUT"))
+            .and(path(format!("{}/1", records_path)))
+            .and(body_json(
+                json!({ "host": "home", "type": "A", "answer": NEW_IP, "ttl": TTL }),
+            ))
+            .respond_with(ResponseTemplate::new(200).set_body_json(record(1, "home", NEW_IP)))
+            .expect(1)
+            .mount(server)
+            .await;
+    }
+}
+
+conformance!(NameCom);
@@ -0,0 +1,128 @@
+use crate::*;
+use ddns_agent::provider::NetlifyProvider;
+use serde_json::{Value, json};
+use wiremock::matchers::{any, body_json, header, method, path, query_param};
+use wiremock::{Mock, ResponseTemplate};
+
+const TOKEN: &str = "test-token";
+/// ID of the record created by the update
+const NEW_RECORD_ID: &str = "record-2";
+
+struct Netlify;
+
+fn zone(id: &str, name: &str) -> Value {
+    json!({ "id": id, "name": name, "account_slug": "me", "dedicated": false })
+}
+
+fn record(id: &str, content: &str) -> Value {
+    json!({
+        "id": id,
+        "hostname": RECORD_NAME,
+        "type": "A",
+        "value": content,
+        "ttl": TTL,
+        "dns_zone_id": ZONE_ID,
+        "managed": false
+    })
+}
+
+#[async_trait]
+impl Fixture for Netlify {
+    fn provider(&self, server: &MockServer) -> Arc<dyn DnsProvider> {
+        Arc::new(NetlifyProvider::new(reqwest::Client::new(), TOKEN).with_base_url(server.uri()))
+    }
+
+    fn updated_record_id(&self) -> Option<&'static str> {
+        Some(NEW_RECORD_ID)
+    }
+
+    /// Records are replaced, and a stale one is already gone
+    fn updates_by_id(&self) -> bool {
+        false
+    }
+
+    async fn mount(&self, server: &MockServer, scenario: Scenario) {
+        let response = match scenario {
+            Scenario::Success | Scenario::Paginated => None,
+            Scenario::AuthFailure => Some(
+                ResponseTemplate::new(401)
+                    .set_body_json(json!({ "code": 401, "message": "Access Denied" })),
+            ),
+            Scenario::RateLimited => Some(
+                ResponseTemplate::new(429)
+                    .insert_header("Retry-After", RETRY_AFTER_SECS.to_string().as_str()),
+            ),
+            Scenario::MalformedJson => Some(ResponseTemplate::new(200).set_body_string("[{")),
+        };
+        if let Some(response) = response {
+            Mock::given(any())
+                .respond_with(response)
+                .mount(server)
+                .await;
+            return;
+        }
+
+        // Records come in a single list, so the zone is the one listed on
+        // the second page instead
+        let target = zone(ZONE_ID, ZONE_NAME);
+        let pages = match scenario {
+            Scenario::Paginated => vec![
+                (0..100)
+                    .map(|i| zone(&format!("zone-other-{}", i), &format!("example{}.com", i)))
+                    .collect(),
+                vec![target],
+            ],
+            _ => vec![vec![target]],
+        };
+        for (i, zones) in pages.into_iter().enumerate() {
+            Mock::given(method("GET"))
+                .and(path("/dns_zones"))
+                .and(query_param("page", (i + 1).to_string()))
+                .and(header(
+                    "authorization",
+                    format!("Bearer {}", TOKEN).as_str(),
+                ))
+                .respond_with(ResponseTemplate::new(200).set_body_json(zones))
+                .mount(server)
+                .await;
+        }
+
+        let records_path = format!("/dns_zones/{}/dns_records", ZONE_ID);
+        Mock::given(method("GET"))
+            .and(path(records_path.as_str()))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!([
+                {
+                    "id": "record-other",
+                    "hostname": ZONE_NAME,
+                    "type": "A",
+                    "value": OLD_IP,
+                    "ttl": TTL
+                },
+                record(RECORD_ID, OLD_IP)
+            ])))
+            .mount(server)
+            .await;
+
+        Mock::given(method("POST"))
+            .and(path(records_path.as_str()))
+            .and(body_json(json!({
+                "type": "A",
+                "hostname": RECORD_NAME,
+                "value": NEW_IP,
+                "ttl": TTL
+            })))
+            .respond_with(ResponseTemplate::new(201).set_body_json(record(NEW_RECORD_ID, NEW_IP)))
+            .expect(1)
+            .mount(server)
+            .await;
+
+        Mock::given(method("DELETE"))
+            .and(path(format!("{}/{}", records_path, RECORD_ID)))
+            .respond_with(ResponseTemplate::new(204))
+            .expect(1)
+            .mount(server)
+            .await;
+    }
+}
+
+conformance!(Netlify);
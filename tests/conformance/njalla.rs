@@ -0,0 +1,106 @@
+use crate::*;
+use ddns_agent::provider::NjallaProvider;
+use serde_json::{Value, json};
+use wiremock::matchers::{any, body_json, body_partial_json, header, method, path};
+use wiremock::{Mock, ResponseTemplate};
+
+const TOKEN: &str = "test-token";
+
+struct Njalla;
+
+fn record(id: u64, name: &str, content: &str) -> Value {
+    json!({ "id": id, "name": name, "type": "A", "content": content, "ttl": TTL })
+}
+
+fn result(result: Value) -> ResponseTemplate {
+    ResponseTemplate::new(200).set_body_json(json!({ "jsonrpc": "2.0", "result": result }))
+}
+
+#[async_trait]
+impl Fixture for Njalla {
+    fn provider(&self, server: &MockServer) -> Arc<dyn DnsProvider> {
+        Arc::new(NjallaProvider::new(reqwest::Client::new(), TOKEN).with_base_url(server.uri()))
+    }
+
+    fn zone(&self) -> Zone {
+        Zone {
+            id: ZONE_NAME.to_string(),
+            name: ZONE_NAME.to_string(),
+        }
+    }
+
+    fn record_id(&self) -> Option<&'static str> {
+        Some("1")
+    }
+
+    async fn mount(&self, server: &MockServer, scenario: Scenario) {
+        let response = match scenario {
+            Scenario::Success | Scenario::Paginated => None,
+            // Errors are answered with a 200 and an error object
+            Scenario::AuthFailure => Some(ResponseTemplate::new(200).set_body_json(json!({
+                "jsonrpc": "2.0",
+                "error": { "code": 403, "message": "Permission denied" }
+            }))),
+            Scenario::RateLimited => Some(
+                ResponseTemplate::new(429)
+                    .insert_header("Retry-After", RETRY_AFTER_SECS.to_string().as_str()),
+            ),
+            Scenario::MalformedJson => {
+                Some(ResponseTemplate::new(200).set_body_string("{\"result\": "))
+            }
+        };
+        if let Some(response) = response {
+            Mock::given(any())
+                .respond_with(response)
+                .mount(server)
+                .await;
+            return;
+        }
+
+        // The API doesn't page; the paginated scenario lists the domain and
+        // record after others
+        let (domains, records) = match scenario {
+            Scenario::Paginated => (
+                json!([{ "name": "example.org" }, { "name": ZONE_NAME }]),
+                json!([record(2, "other", OLD_IP), record(1, "home", OLD_IP)]),
+            ),
+            _ => (
+                json!([{ "name": ZONE_NAME }]),
+                json!([record(1, "home", OLD_IP)]),
+            ),
+        };
+        Mock::given(method("POST"))
+            .and(path("/"))
+            .and(header(
+                "authorization",
+                format!("Njalla {}", TOKEN).as_str(),
+            ))
+            .and(body_partial_json(json!({ "method": "list-domains" })))
+            .respond_with(result(json!({ "domains": domains })))
+            .mount(server)
+            .await;
+
+        Mock::given(method("POST"))
+            .and(path("/"))
+            .and(body_json(json!({
+                "method": "list-records",
+                "params": { "domain": ZONE_NAME }
+            })))
+            .respond_with(result(json!({ "records": records })))
+            .mount(server)
+            .await;
+
+        Mock::given(method("POST"))
+            .and(path("/"))
+            .and(body_json(json!({
+                "method": "edit-record",
+                "params": { "domain": ZONE_NAME, "id": "1", "content": NEW_IP, "ttl": TTL }
+            })))
+            .respond_with(result(record(1, "home", NEW_IP)))
+            .expect(1)
+            .mount(server)
+            .await;
+    }
+}
+
+conformance!(Njalla);
@@ -0,0 +1,131 @@
+use crate::*;
+use ddns_agent::provider::{OvhConfig, OvhProvider};
+use serde_json::{Value, json};
+use wiremock::matchers::{any, body_json, header, header_regex, method, path, query_param};
+use wiremock::{Mock, ResponseTemplate};
+
+const APPLICATION_KEY: &str = "app-key";
+
+struct Ovh;
+
+fn record(content: &str) -> Value {
+    json!({
+        "id": 1,
+        "zone": ZONE_NAME,
+        "subDomain": "home",
+        "fieldType": "A",
+        "target": content,
+        "ttl": TTL
+    })
+}
+
+#[async_trait]
+impl Fixture for Ovh {
+    fn provider(&self, server: &MockServer) -> Arc<dyn DnsProvider> {
+        let config = OvhConfig {
+            endpoint: server.uri(),
+            application_key: APPLICATION_KEY.to_string(),
+            application_secret: "app-secret".to_string(),
+            consumer_key: "consumer-key".to_string(),
+        };
+        Arc::new(OvhProvider::new(reqwest::Client::new(), &config))
+    }
+
+    fn zone(&self) -> Zone {
+        Zone {
+            id: ZONE_NAME.to_string(),
+            name: ZONE_NAME.to_string(),
+        }
+    }
+
+    fn record_id(&self) -> Option<&'static str> {
+        Some("1")
+    }
+
+    async fn mount(&self, server: &MockServer, scenario: Scenario) {
+        let response = match scenario {
+            // Records are looked up by name and type, there is nothing to page through
+            Scenario::Success | Scenario::Paginated => None,
+            Scenario::AuthFailure => Some(ResponseTemplate::new(403).set_body_json(json!({
+                "errorCode": "INVALID_CREDENTIAL",
+                "httpCode": "403 Forbidden",
+                "message": "This credential is not valid",
+                "class": "Client::Forbidden"
+            }))),
+            Scenario::RateLimited => Some(
+                ResponseTemplate::new(429)
+                    .insert_header("Retry-After", RETRY_AFTER_SECS.to_string().as_str()),
+            ),
+            Scenario::MalformedJson => {
+                Some(ResponseTemplate::new(200).set_body_string("{\"name\": "))
+            }
+        };
+        if let Some(response) = response {
+            Mock::given(any())
+                .respond_with(response)
+                .mount(server)
+                .await;
+            return;
+        }
+
+        Mock::given(method("GET"))
+            .and(path("/auth/time"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("1700000000"))
+            .mount(server)
+            .await;
+
+        let zone_path = format!("/domain/zone/{}", ZONE_NAME);
+        let signed = header_regex("x-ovh-signature", r"^\$1\$[0-9a-f]{40}$");
+        Mock::given(method("GET"))
+            .and(path(zone_path.as_str()))
+            .and(header("x-ovh-application", APPLICATION_KEY))
+            .and(header("x-ovh-consumer", "consumer-key"))
+            .and(header_regex("x-ovh-timestamp", r"^17000000\d\d$"))
+            .and(signed)
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "name": ZONE_NAME,
+                "dnssecSupported": true,
+                "hasDnsAnycast": false,
+                "nameServers": ["dns1.ovh.net", "ns1.ovh.net"]
+            })))
+            .mount(server)
+            .await;
+
+        Mock::given(method("GET"))
+            .and(path(format!("{}/record", zone_path)))
+            .and(query_param("fieldType", "A"))
+            .and(query_param("subDomain", "home"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!([1])))
+            .mount(server)
+            .await;
+
+        // The stored record changes once the update went through
+        Mock::given(method("GET"))
+            .and(path(format!("{}/record/1", zone_path)))
+            .respond_with(ResponseTemplate::new(200).set_body_json(record(OLD_IP)))
+            .up_to_n_times(1)
+            .mount(server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path(format!("{}/record/1", zone_path)))
+            .respond_with(ResponseTemplate::new(200).set_body_json(record(NEW_IP)))
+            .mount(server)
+            .await;
+
+        Mock::given(method("PUT"))
+            .and(path(format!("{}/record/1", zone_path)))
+            .and(body_json(json!({ "target": NEW_IP, "ttl": TTL })))
+            .respond_with(ResponseTemplate::new(200).set_body_string("null"))
+            .mount(server)
+            .await;
+
+        Mock::given(method("POST"))
+            .and(path(format!("{}/refresh", zone_path)))
+            .respond_with(ResponseTemplate::new(200).set_body_string("null"))
+            .expect(1)
+            .mount(server)
+            .await;
+    }
+}
+
+conformance!(Ovh);
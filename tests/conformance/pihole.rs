@@ -0,0 +1,128 @@
+use crate::*;
+use ddns_agent::provider::{PiholeConfig, PiholeProvider};
+use serde_json::json;
+use wiremock::matchers::{any, body_json, header, method, path};
+use wiremock::{Mock, ResponseTemplate};
+
+const PASSWORD: &str = "app-password";
+const SID: &str = "session-id";
+/// `dns.hosts` entries before and after the update
+const OLD_ENTRY: &str = "192.0.2.1 home.example.com";
+const NEW_ENTRY: &str = "192.0.2.2 home.example.com";
+
+struct Pihole;
+
+#[async_trait]
+impl Fixture for Pihole {
+    fn provider(&self, server: &MockServer) -> Arc<dyn DnsProvider> {
+        let config = PiholeConfig {
+            url: server.uri(),
+            password: Some(PASSWORD.to_string()),
+        };
+        Arc::new(PiholeProvider::new(reqwest::Client::new(), &config))
+    }
+
+    fn zone(&self) -> Zone {
+        Zone {
+            id: RECORD_NAME.to_string(),
+            name: RECORD_NAME.to_string(),
+        }
+    }
+
+    /// Entries are identified by their whole line
+    fn record_id(&self) -> Option<&'static str> {
+        Some(OLD_ENTRY)
+    }
+
+    fn updated_record_id(&self) -> Option<&'static str> {
+        Some(NEW_ENTRY)
+    }
+
+    /// Entries are replaced, and a stale one is already gone
+    fn updates_by_id(&self) -> bool {
+        false
+    }
+
+    /// Local DNS serves any name, so no name is outside a zone
+    fn knows_zones(&self) -> bool {
+        false
+    }
+
+    async fn mount(&self, server: &MockServer, scenario: Scenario) {
+        let response = match scenario {
+            Scenario::Success | Scenario::Paginated => None,
+            Scenario::AuthFailure => Some(ResponseTemplate::new(401).set_body_json(json!({
+                "error": { "key": "unauthorized", "message": "Unauthorized", "hint": null },
+                "took": 0.001
+            }))),
+            Scenario::RateLimited => Some(
+                ResponseTemplate::new(429)
+                    .insert_header("Retry-After", RETRY_AFTER_SECS.to_string().as_str()),
+            ),
+            Scenario::MalformedJson => {
+                Some(ResponseTemplate::new(200).set_body_string("{\"session\": "))
+            }
+        };
+        if let Some(response) = response {
+            Mock::given(any())
+                .respond_with(response)
+                .mount(server)
+                .await;
+            return;
+        }
+
+        Mock::given(method("POST"))
+            .and(path("/api/auth"))
+            .and(body_json(json!({ "password": PASSWORD })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "session": {
+                    "valid": true,
+                    "totp": false,
+                    "sid": SID,
+                    "csrf": "csrf",
+                    "validity": 1800,
+                    "message": "app-password correct"
+                },
+                "took": 0.001
+            })))
+            // The session is reused
+            .expect(1)
+            .mount(server)
+            .await;
+
+        // The list isn't paged; the paginated scenario has the entry after
+        // others, including the host's AAAA entry
+        let hosts = match scenario {
+            Scenario::Paginated => json!([
+                "192.0.2.10 nas.example.com",
+                "2001:db8::1 home.example.com",
+                OLD_ENTRY
+            ]),
+            _ => json!([OLD_ENTRY]),
+        };
+        Mock::given(method("GET"))
+            .and(path("/api/config/dns/hosts"))
+            .and(header("x-ftl-sid", SID))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "config": { "dns": { "hosts": hosts } },
+                "took": 0.001
+            })))
+            .mount(server)
+            .await;
+
+        for (verb, entry) in [("PUT", NEW_ENTRY), ("DELETE", OLD_ENTRY)] {
+            Mock::given(method(verb))
+                .and(path(format!(
+                    "/api/config/dns/hosts/{}",
+                    entry.replace(' ', "%20")
+                )))
+                .and(header("x-ftl-sid", SID))
+                .respond_with(ResponseTemplate::new(if verb == "PUT" { 201 } else { 204 }))
+                .expect(1)
+                .mount(server)
+                .await;
+        }
+    }
+}
+
+conformance!(Pihole);
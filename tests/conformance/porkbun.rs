@@ -0,0 +1,122 @@
+use crate::*;
+use ddns_agent::provider::{PorkbunConfig, PorkbunProvider};
+use serde_json::{Value, json};
+use wiremock::matchers::{any, body_partial_json, method, path};
+use wiremock::{Mock, ResponseTemplate};
+
+const API_KEY: &str = "pk1_test";
+
+struct Porkbun;
+
+fn domain(name: &str) -> Value {
+    json!({ "domain": name, "status": "ACTIVE", "tld": "com", "autoRenew": 1 })
+}
+
+fn domains(domains: Vec<Value>) -> ResponseTemplate {
+    ResponseTemplate::new(200).set_body_json(json!({ "status": "SUCCESS", "domains": domains }))
+}
+
+#[async_trait]
+impl Fixture for Porkbun {
+    fn provider(&self, server: &MockServer) -> Arc<dyn DnsProvider> {
+        let config = PorkbunConfig {
+            api_key: API_KEY.to_string(),
+            secret_api_key: "sk1_test".to_string(),
+        };
+        Arc::new(PorkbunProvider::new(reqwest::Client::new(), &config).with_base_url(server.uri()))
+    }
+
+    fn zone(&self) -> Zone {
+        Zone {
+            id: ZONE_NAME.to_string(),
+            name: ZONE_NAME.to_string(),
+        }
+    }
+
+    /// Records are edited by name and type
+    fn updates_by_id(&self) -> bool {
+        false
+    }
+
+    async fn mount(&self, server: &MockServer, scenario: Scenario) {
+        let response = match scenario {
+            Scenario::Success | Scenario::Paginated => None,
+            Scenario::AuthFailure => Some(ResponseTemplate::new(400).set_body_json(json!({
+                "status": "ERROR",
+                "message": "Invalid API key. (002)"
+            }))),
+            Scenario::RateLimited => Some(
+                ResponseTemplate::new(429)
+                    .insert_header("Retry-After", RETRY_AFTER_SECS.to_string().as_str()),
+            ),
+            Scenario::MalformedJson => {
+                Some(ResponseTemplate::new(200).set_body_string("{\"status\": "))
+            }
+        };
+        if let Some(response) = response {
+            Mock::given(any())
+                .respond_with(response)
+                .mount(server)
+                .await;
+            return;
+        }
+
+        let keys = json!({ "apikey": API_KEY, "secretapikey": "sk1_test" });
+        let pages = match scenario {
+            // A full page of other domains first
+            Scenario::Paginated => vec![
+                domains(
+                    (0..1000)
+                        .map(|i| domain(&format!("other-{}.com", i)))
+                        .collect(),
+                ),
+                domains(vec![domain(ZONE_NAME)]),
+            ],
+            _ => vec![domains(vec![domain(ZONE_NAME)])],
+        };
+        for (i, page) in pages.into_iter().enumerate() {
+            Mock::given(method("POST"))
+                .and(path("/domain/listAll"))
+                .and(body_partial_json(&keys))
+                .and(body_partial_json(
+                    json!({ "start": (i * 1000).to_string() }),
+                ))
+                .respond_with(page)
+                .mount(server)
+                .await;
+        }
+
+        Mock::given(method("POST"))
+            .and(path(format!(
+                "/dns/retrieveByNameType/{}/A/home",
+                ZONE_NAME
+            )))
+            .and(body_partial_json(&keys))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "status": "SUCCESS",
+                "records": [{
+                    "id": RECORD_ID,
+                    "name": RECORD_NAME,
+                    "type": "A",
+                    "content": OLD_IP,
+                    "ttl": "600",
+                    "prio": "0",
+                    "notes": ""
+                }]
+            })))
+            .mount(server)
+            .await;
+
+        Mock::given(method("POST"))
+            .and(path(format!("/dns/editByNameType/{}/A/home", ZONE_NAME)))
+            .and(body_partial_json(&keys))
+            .and(body_partial_json(
+                json!({ "content": NEW_IP, "ttl": "600" }),
+            ))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "status": "SUCCESS" })))
+            .mount(server)
+            .await;
+    }
+}
+
+conformance!(Porkbun);
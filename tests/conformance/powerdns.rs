@@ -0,0 +1,105 @@
+use crate::*;
+use ddns_agent::provider::{PowerDnsConfig, PowerDnsProvider};
+use serde_json::json;
+use wiremock::matchers::{any, body_json, header, method, path, query_param};
+use wiremock::{Mock, ResponseTemplate};
+
+const API_KEY: &str = "test-key";
+
+struct PowerDns;
+
+#[async_trait]
+impl Fixture for PowerDns {
+    fn provider(&self, server: &MockServer) -> Arc<dyn DnsProvider> {
+        let config = PowerDnsConfig {
+            url: server.uri(),
+            api_key: API_KEY.to_string(),
+            server_id: "localhost".to_string(),
+        };
+        Arc::new(PowerDnsProvider::new(reqwest::Client::new(), &config))
+    }
+
+    fn zone(&self) -> Zone {
+        Zone {
+            id: format!("{}.", ZONE_NAME),
+            name: ZONE_NAME.to_string(),
+        }
+    }
+
+    fn record_id(&self) -> Option<&'static str> {
+        None
+    }
+
+    async fn mount(&self, server: &MockServer, scenario: Scenario) {
+        let response = match scenario {
+            Scenario::Success | Scenario::Paginated => None,
+            Scenario::AuthFailure => {
+                Some(ResponseTemplate::new(401).set_body_string("Unauthorized"))
+            }
+            Scenario::RateLimited => Some(
+                ResponseTemplate::new(429)
+                    .insert_header("Retry-After", RETRY_AFTER_SECS.to_string().as_str()),
+            ),
+            Scenario::MalformedJson => Some(ResponseTemplate::new(200).set_body_string("[{")),
+        };
+        if let Some(response) = response {
+            Mock::given(any())
+                .respond_with(response)
+                .mount(server)
+                .await;
+            return;
+        }
+
+        // The API doesn't page; the paginated scenario serves a parent zone
+        // too, which must lose to the more specific one
+        let zone = |name: &str| json!({ "id": format!("{}.", name), "name": format!("{}.", name), "kind": "Native" });
+        let zones = match scenario {
+            Scenario::Paginated => json!([zone("com"), zone(ZONE_NAME), zone("example.org")]),
+            _ => json!([zone(ZONE_NAME)]),
+        };
+        Mock::given(method("GET"))
+            .and(path("/api/v1/servers/localhost/zones"))
+            .and(header("x-api-key", API_KEY))
+            .respond_with(ResponseTemplate::new(200).set_body_json(zones))
+            .mount(server)
+            .await;
+
+        let zone_path = format!("/api/v1/servers/localhost/zones/{}.", ZONE_NAME);
+        let record_name = format!("{}.", RECORD_NAME);
+        Mock::given(method("GET"))
+            .and(path(zone_path.as_str()))
+            .and(query_param("rrset_name", record_name.as_str()))
+            .and(query_param("rrset_type", "A"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "id": format!("{}.", ZONE_NAME),
+                "name": format!("{}.", ZONE_NAME),
+                "rrsets": [{
+                    "name": record_name,
+                    "type": "A",
+                    "ttl": TTL,
+                    "records": [{ "content": OLD_IP, "disabled": false }],
+                    "comments": []
+                }]
+            })))
+            .mount(server)
+            .await;
+
+        Mock::given(method("PATCH"))
+            .and(path(zone_path.as_str()))
+            .and(body_json(json!({
+                "rrsets": [{
+                    "name": record_name,
+                    "type": "A",
+                    "ttl": TTL,
+                    "changetype": "REPLACE",
+                    "records": [{ "content": NEW_IP, "disabled": false }]
+                }]
+            })))
+            .respond_with(ResponseTemplate::new(204))
+            .expect(1)
+            .mount(server)
+            .await;
+    }
+}
+
+conformance!(PowerDns);
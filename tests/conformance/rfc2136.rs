@@ -0,0 +1,317 @@
+//! The provider speaks DNS instead of HTTP, so it is tested against a small
+//! TCP name server here rather than through [`Fixture`].
+
+use crate::*;
+use ddns_agent::provider::{Rfc2136Config, Rfc2136Provider};
+use hmac::{Hmac, KeyInit, Mac};
+use sha2::Sha256;
+use std::net::Ipv4Addr;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+const KEY_NAME: &str = "ddns-key";
+/// `tsig-keygen` style secret, 32 bytes of 0x2a
+const KEY_SECRET: &str = "KioqKioqKioqKioqKioqKioqKioqKioqKioqKioqKio=";
+const SECRET: [u8; 32] = [0x2a; 32];
+
+#[derive(Clone, Copy, PartialEq)]
+enum Behaviour {
+    /// Answer queries and apply updates
+    Serve,
+    /// Reject the key with an unsigned NOTAUTH/BADKEY answer
+    BadKey,
+    /// Sign answers with a different secret
+    Forged,
+    /// Fail every request with an unsigned SERVFAIL answer
+    ServFail,
+}
+
+fn wire_name(name: &str) -> Vec<u8> {
+    let mut buf = Vec::new();
+    for label in name.split('.').filter(|l| !l.is_empty()) {
+        buf.push(label.len() as u8);
+        buf.extend(label.to_lowercase().bytes());
+    }
+    buf.push(0);
+    buf
+}
+
+fn u16_at(msg: &[u8], at: usize) -> u16 {
+    u16::from_be_bytes([msg[at], msg[at + 1]])
+}
+
+/// Skip the uncompressed name at `pos`
+fn skip_name(msg: &[u8], mut pos: usize) -> usize {
+    while msg[pos] != 0 {
+        pos += 1 + msg[pos] as usize;
+    }
+    pos + 1
+}
+
+/// Type, class, RDATA and end of the record at `pos`
+fn record_at(msg: &[u8], pos: usize) -> (u16, u16, &[u8], usize) {
+    let pos = skip_name(msg, pos);
+    let len = u16_at(msg, pos + 8) as usize;
+    let rdata = &msg[pos + 10..pos + 10 + len];
+    (
+        u16_at(msg, pos),
+        u16_at(msg, pos + 2),
+        rdata,
+        pos + 10 + len,
+    )
+}
+
+/// TSIG variables for a MAC over a message without error or other data
+fn variables(time: &[u8]) -> Vec<u8> {
+    let mut buf = wire_name(KEY_NAME);
+    buf.extend([0, 255, 0, 0, 0, 0]);
+    buf.extend(wire_name("hmac-sha256"));
+    buf.extend(time);
+    buf.extend([1, 44, 0, 0, 0, 0]);
+    buf
+}
+
+fn mac(secret: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret).unwrap();
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// Verify the request signature, returning the request MAC and the offset
+/// of its TSIG record
+fn verify_request(msg: &[u8]) -> (Vec<u8>, usize) {
+    let counts: Vec<u16> = (0..4).map(|i| u16_at(msg, 4 + 2 * i)).collect();
+    let mut pos = 12;
+    for _ in 0..counts[0] {
+        pos = skip_name(msg, pos) + 4;
+    }
+    for _ in 0..counts[1] + counts[2] + counts[3] - 1 {
+        pos = record_at(msg, pos).3;
+    }
+    let tsig_start = pos;
+    let (kind, class, rdata, _) = record_at(msg, pos);
+    assert_eq!((kind, class), (250, 255), "last record is TSIG");
+
+    let alg_end = skip_name(rdata, 0);
+    assert_eq!(&rdata[..alg_end], wire_name("hmac-sha256").as_slice());
+    let time = &rdata[alg_end..alg_end + 6];
+    let mac_len = u16_at(rdata, alg_end + 8) as usize;
+    let request_mac = rdata[alg_end + 10..alg_end + 10 + mac_len].to_vec();
+
+    let mut data = msg[..tsig_start].to_vec();
+    data[10..12].copy_from_slice(&(counts[3] - 1).to_be_bytes());
+    data.extend(variables(time));
+    assert_eq!(mac(&SECRET, &data), request_mac, "request MAC");
+    (request_mac, tsig_start)
+}
+
+/// Append a TSIG record signing `response` for `request_mac`
+fn sign_response(response: &mut Vec<u8>, request_mac: &[u8], secret: &[u8]) {
+    let time = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+        .to_be_bytes()[2..]
+        .to_vec();
+    let mut data = (request_mac.len() as u16).to_be_bytes().to_vec();
+    data.extend(request_mac);
+    data.extend(response.as_slice());
+    data.extend(variables(&time));
+    let mac = mac(secret, &data);
+
+    let mut rdata = wire_name("hmac-sha256");
+    rdata.extend(&time);
+    rdata.extend([1, 44]);
+    rdata.extend((mac.len() as u16).to_be_bytes());
+    rdata.extend(&mac);
+    rdata.extend(&response[..2]);
+    rdata.extend([0, 0, 0, 0]);
+
+    response.extend(wire_name(KEY_NAME));
+    response.extend([0, 250, 0, 255, 0, 0, 0, 0]);
+    response.extend((rdata.len() as u16).to_be_bytes());
+    response.extend(rdata);
+    let arcount = u16_at(response, 10) + 1;
+    response[10..12].copy_from_slice(&arcount.to_be_bytes());
+}
+
+/// Answer to one request, with `address` holding the served A record
+fn answer(request: &[u8], behaviour: Behaviour, address: &Mutex<Ipv4Addr>) -> Vec<u8> {
+    let opcode = (u16_at(request, 2) >> 11) & 0xF;
+    let question_end = skip_name(request, 12) + 4;
+    let mut response = request[..2].to_vec();
+
+    if behaviour == Behaviour::BadKey {
+        // NOTAUTH with an unsigned TSIG record carrying BADKEY
+        response.extend((0x8000 | opcode << 11 | 9).to_be_bytes());
+        response.extend([0, 1, 0, 0, 0, 0, 0, 1]);
+        response.extend(&request[12..question_end]);
+        response.extend(wire_name(KEY_NAME));
+        response.extend([0, 250, 0, 255, 0, 0, 0, 0]);
+        let mut rdata = wire_name("hmac-sha256");
+        rdata.extend([0, 0, 0, 0, 0, 0, 1, 44, 0, 0]);
+        rdata.extend(&request[..2]);
+        rdata.extend([0, 17, 0, 0]);
+        response.extend((rdata.len() as u16).to_be_bytes());
+        response.extend(rdata);
+        return response;
+    }
+
+    if behaviour == Behaviour::ServFail {
+        response.extend((0x8000 | opcode << 11 | 2).to_be_bytes());
+        response.extend([0, 1, 0, 0, 0, 0, 0, 0]);
+        response.extend(&request[12..question_end]);
+        return response;
+    }
+
+    let (request_mac, _) = verify_request(request);
+    response.extend((0x8000 | opcode << 11 | 0x0400).to_be_bytes());
+    match opcode {
+        0 => {
+            assert_eq!(u16_at(request, question_end - 4), 1, "A query");
+            response.extend([0, 1, 0, 1, 0, 0, 0, 0]);
+            response.extend(&request[12..question_end]);
+            // Pointer to the question name
+            response.extend([0xC0, 12, 0, 1, 0, 1]);
+            response.extend(TTL.to_be_bytes());
+            response.extend([0, 4]);
+            response.extend(address.lock().unwrap().octets());
+        }
+        5 => {
+            assert_eq!(&request[12..question_end - 4], wire_name(ZONE_NAME));
+            assert_eq!(u16_at(request, question_end - 4), 6, "zone section is SOA");
+            let (kind, class, rdata, next) = record_at(request, question_end);
+            assert_eq!((kind, class, rdata.len()), (1, 255, 0), "delete A RRset");
+            let (kind, class, rdata, _) = record_at(request, next);
+            assert_eq!((kind, class), (1, 1), "add A record");
+            *address.lock().unwrap() = Ipv4Addr::from(<[u8; 4]>::try_from(rdata).unwrap());
+            response.extend([0, 1, 0, 0, 0, 0, 0, 0]);
+            response.extend(&request[12..question_end]);
+        }
+        opcode => panic!("unexpected opcode {}", opcode),
+    }
+
+    let secret: &[u8] = if behaviour == Behaviour::Forged {
+        b"another secret"
+    } else {
+        &SECRET
+    };
+    sign_response(&mut response, &request_mac, secret);
+    response
+}
+
+fn provider(server: String) -> Arc<dyn DnsProvider> {
+    let config = Rfc2136Config {
+        server,
+        zone: ZONE_NAME.to_string(),
+        key_name: KEY_NAME.to_string(),
+        key_secret: KEY_SECRET.to_string(),
+        key_algorithm: "hmac-sha256".to_string(),
+    };
+    Arc::new(Rfc2136Provider::new(&config).unwrap())
+}
+
+/// Start a name server serving `OLD_IP` for `RECORD_NAME`
+async fn name_server(behaviour: Behaviour) -> (Arc<dyn DnsProvider>, Arc<Mutex<Ipv4Addr>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let server = listener.local_addr().unwrap().to_string();
+    let address = Arc::new(Mutex::new(OLD_IP.parse().unwrap()));
+
+    let served = address.clone();
+    tokio::spawn(async move {
+        while let Ok((mut stream, _)) = listener.accept().await {
+            let len = stream.read_u16().await.unwrap() as usize;
+            let mut request = vec![0; len];
+            stream.read_exact(&mut request).await.unwrap();
+            let response = answer(&request, behaviour, &served);
+            stream
+                .write_all(&(response.len() as u16).to_be_bytes())
+                .await
+                .unwrap();
+            stream.write_all(&response).await.unwrap();
+        }
+    });
+
+    (provider(server), address)
+}
+
+#[tokio::test]
+async fn success() {
+    let (provider, address) = name_server(Behaviour::Serve).await;
+    let zone = provider.lookup_zone(RECORD_NAME).await.unwrap();
+    assert_eq!(zone.name, ZONE_NAME);
+
+    let record = provider
+        .read_record(&zone, RECORD_NAME, RecordType::A)
+        .await
+        .unwrap()
+        .expect("record exists");
+    assert_eq!(record.content, OLD_IP);
+    assert_eq!(record.ttl, TTL);
+
+    let desired = Record {
+        content: NEW_IP.to_string(),
+        ..record
+    };
+    let updated = provider.upsert_record(&zone, &desired).await.unwrap();
+    assert_eq!(updated.content, NEW_IP);
+    assert_eq!(address.lock().unwrap().to_string(), NEW_IP);
+}
+
+#[tokio::test]
+async fn auth_failure() {
+    let (provider, _) = name_server(Behaviour::BadKey).await;
+    let zone = provider.lookup_zone(RECORD_NAME).await.unwrap();
+    let err = provider
+        .read_record(&zone, RECORD_NAME, RecordType::A)
+        .await
+        .unwrap_err();
+    assert!(matches!(err, ProviderError::Auth(_)), "{:?}", err);
+}
+
+#[tokio::test]
+async fn forged_response() {
+    let (provider, _) = name_server(Behaviour::Forged).await;
+    let zone = provider.lookup_zone(RECORD_NAME).await.unwrap();
+    let err = provider
+        .read_record(&zone, RECORD_NAME, RecordType::A)
+        .await
+        .unwrap_err();
+    assert!(matches!(err, ProviderError::Auth(_)), "{:?}", err);
+}
+
+#[tokio::test]
+async fn outside_zone() {
+    let (provider, _) = name_server(Behaviour::Serve).await;
+    let err = provider.lookup_zone("home.example.org").await.unwrap_err();
+    assert!(matches!(err, ProviderError::ZoneNotFound(_)), "{:?}", err);
+}
+
+#[tokio::test]
+async fn server_failure() {
+    let (provider, _) = name_server(Behaviour::ServFail).await;
+    let zone = provider.lookup_zone(RECORD_NAME).await.unwrap();
+    let err = provider
+        .read_record(&zone, RECORD_NAME, RecordType::A)
+        .await
+        .unwrap_err();
+    assert!(matches!(err, ProviderError::Unavailable(_)), "{:?}", err);
+}
+
+#[tokio::test]
+async fn unreachable_server() {
+    // Nothing listens on the port once the listener is gone
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let server = listener.local_addr().unwrap().to_string();
+    drop(listener);
+
+    let provider = provider(server);
+    let zone = provider.lookup_zone(RECORD_NAME).await.unwrap();
+    let err = provider
+        .read_record(&zone, RECORD_NAME, RecordType::A)
+        .await
+        .unwrap_err();
+    assert!(err.is_outage(), "{:?}", err);
+}
@@ -0,0 +1,231 @@
+use crate::*;
+use ddns_agent::provider::{Route53Config, Route53Provider, Route53Routing};
+use wiremock::matchers::{
+    any, body_string_contains, header_regex, method, path, query_param, query_param_is_missing,
+};
+use wiremock::{Mock, ResponseTemplate};
+
+const ACCESS_KEY_ID: &str = "AKIDEXAMPLE";
+
+struct Route53;
+
+fn xml(status: u16, body: String) -> ResponseTemplate {
+    ResponseTemplate::new(status).set_body_raw(body, "text/xml")
+}
+
+fn error(code: &str) -> String {
+    format!(
+        "<ErrorResponse><Error><Type>Sender</Type><Code>{}</Code><Message>denied</Message></Error><RequestId>1</RequestId></ErrorResponse>",
+        code
+    )
+}
+
+fn zones(zones: &[(&str, bool)], next: Option<&str>) -> String {
+    let zones: String = zones
+        .iter()
+        .map(|(id, private)| {
+            format!(
+                "<HostedZone><Id>/hostedzone/{}</Id><Name>{}.</Name><CallerReference>ref</CallerReference><Config><PrivateZone>{}</PrivateZone></Config><ResourceRecordSetCount>3</ResourceRecordSetCount></HostedZone>",
+                id, ZONE_NAME, private
+            )
+        })
+        .collect();
+    let next = match next {
+        Some(id) => format!(
+            "<IsTruncated>true</IsTruncated><NextDNSName>{}.</NextDNSName><NextHostedZoneId>{}</NextHostedZoneId>",
+            ZONE_NAME, id
+        ),
+        None => "<IsTruncated>false</IsTruncated>".to_string(),
+    };
+    format!(
+        "<ListHostedZonesByNameResponse><HostedZones>{}</HostedZones><DNSName>{}.</DNSName>{}<MaxItems>100</MaxItems></ListHostedZonesByNameResponse>",
+        zones, ZONE_NAME, next
+    )
+}
+
+#[async_trait]
+impl Fixture for Route53 {
+    fn provider(&self, server: &MockServer) -> Arc<dyn DnsProvider> {
+        let config = Route53Config {
+            access_key_id: Some(ACCESS_KEY_ID.to_string()),
+            secret_access_key: Some("secret".to_string()),
+            ..Route53Config::default()
+        };
+        Arc::new(Route53Provider::new(reqwest::Client::new(), &config).with_base_url(server.uri()))
+    }
+
+    fn record_id(&self) -> Option<&'static str> {
+        None
+    }
+
+    async fn mount(&self, server: &MockServer, scenario: Scenario) {
+        let response = match scenario {
+            Scenario::Success | Scenario::Paginated => None,
+            Scenario::AuthFailure => Some(xml(403, error("InvalidClientTokenId"))),
+            Scenario::RateLimited => Some(
+                xml(400, error("Throttling"))
+                    .insert_header("Retry-After", RETRY_AFTER_SECS.to_string().as_str()),
+            ),
+            Scenario::MalformedJson => Some(xml(200, "<html>oops".to_string())),
+        };
+        if let Some(response) = response {
+            Mock::given(any())
+                .respond_with(response)
+                .mount(server)
+                .await;
+            return;
+        }
+
+        let signed = header_regex(
+            "authorization",
+            &format!(
+                "^AWS4-HMAC-SHA256 Credential={}/\\d{{8}}/us-east-1/route53/aws4_request, SignedHeaders=host;x-amz-date, Signature=[0-9a-f]{{64}}$",
+                ACCESS_KEY_ID
+            ),
+        );
+        let first_page = match scenario {
+            Scenario::Paginated => zones(&[("zone-private", true)], Some(ZONE_ID)),
+            _ => zones(&[(ZONE_ID, false)], None),
+        };
+        Mock::given(method("GET"))
+            .and(path("/2013-04-01/hostedzonesbyname"))
+            .and(query_param("dnsname", ZONE_NAME))
+            .and(query_param_is_missing("hostedzoneid"))
+            .and(signed)
+            .respond_with(xml(200, first_page))
+            .mount(server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/2013-04-01/hostedzonesbyname"))
+            .and(query_param("dnsname", UNKNOWN_ZONE))
+            .respond_with(xml(200, zones(&[], None)))
+            .mount(server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/2013-04-01/hostedzonesbyname"))
+            .and(query_param("dnsname", format!("{}.", ZONE_NAME)))
+            .and(query_param("hostedzoneid", ZONE_ID))
+            .respond_with(xml(200, zones(&[(ZONE_ID, false)], None)))
+            .mount(server)
+            .await;
+
+        Mock::given(method("GET"))
+            .and(path(format!("/2013-04-01/hostedzone/{}/rrset", ZONE_ID)))
+            .and(query_param("name", RECORD_NAME))
+            .and(query_param("type", "A"))
+            .respond_with(xml(
+                200,
+                format!(
+                    "<ListResourceRecordSetsResponse><ResourceRecordSets><ResourceRecordSet><Name>{}.</Name><Type>A</Type><TTL>{}</TTL><ResourceRecords><ResourceRecord><Value>{}</Value></ResourceRecord></ResourceRecords></ResourceRecordSet></ResourceRecordSets><IsTruncated>false</IsTruncated><MaxItems>1</MaxItems></ListResourceRecordSetsResponse>",
+                    RECORD_NAME, TTL, OLD_IP
+                ),
+            ))
+            .mount(server)
+            .await;
+
+        Mock::given(method("POST"))
+            .and(path(format!("/2013-04-01/hostedzone/{}/rrset/", ZONE_ID)))
+            .and(body_string_contains("<Action>UPSERT</Action>"))
+            .and(body_string_contains(format!("<Value>{}</Value>", NEW_IP)))
+            .respond_with(xml(
+                200,
+                "<ChangeResourceRecordSetsResponse><ChangeInfo><Id>/change/C1</Id><Status>PENDING</Status><SubmittedAt>2024-01-01T00:00:00Z</SubmittedAt></ChangeInfo></ChangeResourceRecordSetsResponse>".to_string(),
+            ))
+            .mount(server)
+            .await;
+    }
+}
+
+conformance!(Route53);
+
+#[tokio::test]
+async fn wildcard_record() {
+    let server = MockServer::start().await;
+    let name = format!("*.{}", ZONE_NAME);
+    Mock::given(method("GET"))
+        .and(path(format!("/2013-04-01/hostedzone/{}/rrset", ZONE_ID)))
+        .and(query_param("name", name.as_str()))
+        .respond_with(xml(
+            200,
+            format!(
+                "<ListResourceRecordSetsResponse><ResourceRecordSets><ResourceRecordSet><Name>\\052.{}.</Name><Type>A</Type><TTL>{}</TTL><ResourceRecords><ResourceRecord><Value>{}</Value></ResourceRecord></ResourceRecords></ResourceRecordSet></ResourceRecordSets><IsTruncated>false</IsTruncated><MaxItems>1</MaxItems></ListResourceRecordSetsResponse>",
+                ZONE_NAME, TTL, OLD_IP
+            ),
+        ))
+        .mount(&server)
+        .await;
+
+    let record = Route53
+        .provider(&server)
+        .read_record(&Route53.zone(), &name, RecordType::A)
+        .await
+        .unwrap()
+        .expect("wildcard record is found");
+    assert_eq!(record.content, OLD_IP);
+}
+
+#[tokio::test]
+async fn weighted_record_set() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path(format!("/2013-04-01/hostedzone/{}/rrset", ZONE_ID)))
+        .and(query_param("name", RECORD_NAME))
+        .and(query_param("identifier", "home"))
+        .respond_with(xml(
+            200,
+            format!(
+                "<ListResourceRecordSetsResponse><ResourceRecordSets><ResourceRecordSet><Name>{}.</Name><Type>A</Type><SetIdentifier>home</SetIdentifier><Weight>10</Weight><TTL>{}</TTL><ResourceRecords><ResourceRecord><Value>{}</Value></ResourceRecord></ResourceRecords></ResourceRecordSet></ResourceRecordSets><IsTruncated>false</IsTruncated><MaxItems>1</MaxItems></ListResourceRecordSetsResponse>",
+                RECORD_NAME, TTL, OLD_IP
+            ),
+        ))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path(format!("/2013-04-01/hostedzone/{}/rrset/", ZONE_ID)))
+        .and(body_string_contains(
+            "<Type>A</Type><SetIdentifier>home</SetIdentifier><Weight>10</Weight><TTL>",
+        ))
+        .and(body_string_contains(
+            "</ResourceRecords><HealthCheckId>abcdef11-2222-3333-4444-555555fedcba</HealthCheckId>",
+        ))
+        .respond_with(xml(
+            200,
+            "<ChangeResourceRecordSetsResponse><ChangeInfo><Id>/change/C1</Id><Status>PENDING</Status><SubmittedAt>2024-01-01T00:00:00Z</SubmittedAt></ChangeInfo></ChangeResourceRecordSetsResponse>".to_string(),
+        ))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let routing = Route53Routing {
+        set_identifier: "home".to_string(),
+        weight: Some(10),
+        region: None,
+        health_check_id: Some("abcdef11-2222-3333-4444-555555fedcba".to_string()),
+    };
+    let config = Route53Config {
+        access_key_id: Some(ACCESS_KEY_ID.to_string()),
+        secret_access_key: Some("secret".to_string()),
+        routing: [(RECORD_NAME.to_string(), routing)].into(),
+        ..Route53Config::default()
+    };
+    let provider =
+        Route53Provider::new(reqwest::Client::new(), &config).with_base_url(server.uri());
+    let zone = Route53.zone();
+    let record = provider
+        .read_record(&zone, RECORD_NAME, RecordType::A)
+        .await
+        .unwrap()
+        .expect("weighted record set is found");
+    assert_eq!(record.content, OLD_IP);
+    provider
+        .upsert_record(
+            &zone,
+            &Record {
+                content: NEW_IP.to_string(),
+                ..record
+            },
+        )
+        .await
+        .unwrap();
+}
@@ -0,0 +1,137 @@
+use crate::*;
+use ddns_agent::provider::ScalewayProvider;
+use serde_json::{Value, json};
+use wiremock::matchers::{any, body_json, header, method, path, query_param};
+use wiremock::{Mock, ResponseTemplate};
+
+const SECRET_KEY: &str = "test-secret-key";
+
+struct Scaleway;
+
+fn record(id: &str, name: &str, data: &str) -> Value {
+    json!({
+        "id": id,
+        "name": name,
+        "type": "A",
+        "data": data,
+        "ttl": TTL,
+        "priority": 0,
+        "comment": null
+    })
+}
+
+fn records(records: Value, total_count: usize) -> ResponseTemplate {
+    ResponseTemplate::new(200).set_body_json(json!({
+        "records": records,
+        "total_count": total_count
+    }))
+}
+
+#[async_trait]
+impl Fixture for Scaleway {
+    fn provider(&self, server: &MockServer) -> Arc<dyn DnsProvider> {
+        Arc::new(
+            ScalewayProvider::new(reqwest::Client::new(), SECRET_KEY).with_base_url(server.uri()),
+        )
+    }
+
+    fn zone(&self) -> Zone {
+        Zone {
+            id: ZONE_NAME.to_string(),
+            name: ZONE_NAME.to_string(),
+        }
+    }
+
+    /// Records are set by name and type
+    fn updates_by_id(&self) -> bool {
+        false
+    }
+
+    async fn mount(&self, server: &MockServer, scenario: Scenario) {
+        let response = match scenario {
+            Scenario::Success | Scenario::Paginated => None,
+            Scenario::AuthFailure => Some(ResponseTemplate::new(401).set_body_json(
+                json!({ "message": "authentication is denied", "type": "denied_authentication" }),
+            )),
+            Scenario::RateLimited => Some(
+                ResponseTemplate::new(429)
+                    .insert_header("Retry-After", RETRY_AFTER_SECS.to_string().as_str()),
+            ),
+            Scenario::MalformedJson => {
+                Some(ResponseTemplate::new(200).set_body_string("{\"records\": "))
+            }
+        };
+        if let Some(response) = response {
+            Mock::given(any())
+                .respond_with(response)
+                .mount(server)
+                .await;
+            return;
+        }
+
+        Mock::given(method("GET"))
+            .and(path("/dns-zones"))
+            .and(query_param("dns_zone", ZONE_NAME))
+            .and(header("x-auth-token", SECRET_KEY))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "dns_zones": [{
+                    "domain": ZONE_NAME,
+                    "subdomain": "",
+                    "ns": ["ns0.dom.scw.cloud", "ns1.dom.scw.cloud"],
+                    "status": "active"
+                }],
+                "total_count": 1
+            })))
+            .mount(server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/dns-zones"))
+            .and(query_param("dns_zone", UNKNOWN_ZONE))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "dns_zones": [],
+                "total_count": 0
+            })))
+            .mount(server)
+            .await;
+
+        let records_path = format!("/dns-zones/{}/records", ZONE_NAME);
+        let target = record(RECORD_ID, "home", OLD_IP);
+        let pages = match scenario {
+            Scenario::Paginated => vec![
+                records(json!([record("record-2", "other", OLD_IP)]), 2),
+                records(json!([target]), 2),
+            ],
+            _ => vec![records(json!([target]), 1)],
+        };
+        for (i, page) in pages.into_iter().enumerate() {
+            Mock::given(method("GET"))
+                .and(path(records_path.as_str()))
+                .and(query_param("name", "home"))
+                .and(query_param("type", "A"))
+                .and(query_param("page", (i + 1).to_string()))
+                .respond_with(page)
+                .mount(server)
+                .await;
+        }
+
+        Mock::given(method("PATCH"))
+            .and(path(records_path.as_str()))
+            .and(body_json(json!({
+                "changes": [{
+                    "set": {
+                        "id_fields": { "name": "home", "type": "A" },
+                        "records": [{ "name": "home", "type": "A", "data": NEW_IP, "ttl": TTL }]
+                    }
+                }],
+                "return_all_records": false
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "records": [record(RECORD_ID, "home", NEW_IP)]
+            })))
+            .expect(1)
+            .mount(server)
+            .await;
+    }
+}
+
+conformance!(Scaleway);
@@ -0,0 +1,92 @@
+use crate::*;
+use ddns_agent::provider::{ScriptConfig, ScriptProvider};
+use serde_json::{Value, json};
+use std::path::PathBuf;
+use wiremock::matchers::{any, body_partial_json, header, method, path, query_param};
+use wiremock::{Mock, ResponseTemplate};
+
+const API_KEY: &str = "test-key";
+
+struct Script;
+
+fn record(id: &str, name: &str, content: &str) -> Value {
+    json!({ "id": id, "name": name, "type": "A", "value": content, "ttl": TTL })
+}
+
+#[async_trait]
+impl Fixture for Script {
+    fn provider(&self, server: &MockServer) -> Arc<dyn DnsProvider> {
+        let options = json!({ "base_url": server.uri(), "api_key": API_KEY });
+        let config = ScriptConfig {
+            path: PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/conformance/provider.rhai"),
+            options: options.as_object().cloned().unwrap(),
+        };
+        Arc::new(ScriptProvider::new(&config).unwrap())
+    }
+
+    async fn mount(&self, server: &MockServer, scenario: Scenario) {
+        let response = match scenario {
+            Scenario::Success | Scenario::Paginated => None,
+            Scenario::AuthFailure => {
+                Some(ResponseTemplate::new(401).set_body_string("invalid API key"))
+            }
+            Scenario::RateLimited => Some(
+                ResponseTemplate::new(429)
+                    .insert_header("Retry-After", RETRY_AFTER_SECS.to_string().as_str()),
+            ),
+            Scenario::MalformedJson => {
+                Some(ResponseTemplate::new(200).set_body_string("{\"id\": "))
+            }
+        };
+        if let Some(response) = response {
+            Mock::given(any())
+                .respond_with(response)
+                .mount(server)
+                .await;
+            return;
+        }
+
+        Mock::given(method("GET"))
+            .and(path(format!("/domains/{}", ZONE_NAME)))
+            .and(header("authorization", format!("Bearer {}", API_KEY)))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(json!({ "id": ZONE_ID, "name": ZONE_NAME })),
+            )
+            .mount(server)
+            .await;
+
+        let records = format!("/domains/{}/records", ZONE_ID);
+        let target = record(RECORD_ID, RECORD_NAME, OLD_IP);
+        let pages = match scenario {
+            Scenario::Paginated => vec![
+                json!({ "records": [record("record-0", "other.example.com", OLD_IP)], "next_page": 2 }),
+                json!({ "records": [target], "next_page": null }),
+            ],
+            _ => vec![json!({ "records": [target], "next_page": null })],
+        };
+        for (i, page) in pages.into_iter().enumerate() {
+            Mock::given(method("GET"))
+                .and(path(records.as_str()))
+                .and(query_param("name", RECORD_NAME))
+                .and(query_param("type", "A"))
+                .and(query_param("page", (i + 1).to_string()))
+                .respond_with(ResponseTemplate::new(200).set_body_json(page))
+                .mount(server)
+                .await;
+        }
+
+        Mock::given(method("PUT"))
+            .and(path(format!("{}/{}", records, RECORD_ID)))
+            .and(body_partial_json(json!({ "value": NEW_IP })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(record(
+                RECORD_ID,
+                RECORD_NAME,
+                NEW_IP,
+            )))
+            .mount(server)
+            .await;
+    }
+}
+
+conformance!(Script);
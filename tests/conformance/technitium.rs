@@ -0,0 +1,110 @@
+use crate::*;
+use ddns_agent::provider::{TechnitiumConfig, TechnitiumProvider};
+use serde_json::{Value, json};
+use wiremock::matchers::{any, body_string_contains, method, path};
+use wiremock::{Mock, ResponseTemplate};
+
+const TOKEN: &str = "test-token";
+
+struct Technitium;
+
+fn ok(response: Value) -> ResponseTemplate {
+    ResponseTemplate::new(200).set_body_json(json!({ "status": "ok", "response": response }))
+}
+
+#[async_trait]
+impl Fixture for Technitium {
+    fn provider(&self, server: &MockServer) -> Arc<dyn DnsProvider> {
+        let config = TechnitiumConfig {
+            url: server.uri(),
+            api_token: TOKEN.to_string(),
+        };
+        Arc::new(TechnitiumProvider::new(reqwest::Client::new(), &config))
+    }
+
+    fn zone(&self) -> Zone {
+        Zone {
+            id: ZONE_NAME.to_string(),
+            name: ZONE_NAME.to_string(),
+        }
+    }
+
+    /// Records are identified by their address
+    fn record_id(&self) -> Option<&'static str> {
+        Some(OLD_IP)
+    }
+
+    fn updated_record_id(&self) -> Option<&'static str> {
+        Some(NEW_IP)
+    }
+
+    async fn mount(&self, server: &MockServer, scenario: Scenario) {
+        let response = match scenario {
+            Scenario::Success | Scenario::Paginated => None,
+            Scenario::AuthFailure => Some(ResponseTemplate::new(200).set_body_json(json!({
+                "status": "invalid-token",
+                "errorMessage": "Invalid token or session expired."
+            }))),
+            Scenario::RateLimited => Some(
+                ResponseTemplate::new(429)
+                    .insert_header("Retry-After", RETRY_AFTER_SECS.to_string().as_str()),
+            ),
+            Scenario::MalformedJson => {
+                Some(ResponseTemplate::new(200).set_body_string("{\"status\": "))
+            }
+        };
+        if let Some(response) = response {
+            Mock::given(any())
+                .respond_with(response)
+                .mount(server)
+                .await;
+            return;
+        }
+
+        // Zones aren't paged; the paginated scenario serves a parent zone
+        // too, which must lose to the more specific one
+        let zone = |name: &str| json!({ "name": name, "type": "Primary", "disabled": false });
+        let zones = match scenario {
+            Scenario::Paginated => json!([zone("com"), zone(ZONE_NAME)]),
+            _ => json!([zone(ZONE_NAME)]),
+        };
+        Mock::given(method("POST"))
+            .and(path("/api/zones/list"))
+            .and(body_string_contains(format!("token={}", TOKEN)))
+            .respond_with(ok(json!({ "zones": zones })))
+            .mount(server)
+            .await;
+
+        Mock::given(method("POST"))
+            .and(path("/api/zones/records/get"))
+            .and(body_string_contains(format!(
+                "domain={}&zone={}",
+                RECORD_NAME, ZONE_NAME
+            )))
+            .respond_with(ok(json!({
+                "zone": { "name": ZONE_NAME, "type": "Primary" },
+                "records": [{
+                    "disabled": false,
+                    "name": RECORD_NAME,
+                    "type": "A",
+                    "ttl": TTL,
+                    "rData": { "ipAddress": OLD_IP }
+                }]
+            })))
+            .mount(server)
+            .await;
+
+        Mock::given(method("POST"))
+            .and(path("/api/zones/records/update"))
+            .and(body_string_contains(format!(
+                "type=A&ttl={}&ipAddress={}&newIpAddress={}",
+                TTL, OLD_IP, NEW_IP
+            )))
+            .respond_with(ok(json!({})))
+            .expect(1)
+            .mount(server)
+            .await;
+    }
+}
+
+conformance!(Technitium);
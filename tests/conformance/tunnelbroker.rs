@@ -0,0 +1,72 @@
+use crate::*;
+use ddns_agent::provider::{TunnelbrokerConfig, TunnelbrokerProvider};
+use wiremock::matchers::{any, header, header_regex, method, path, query_param};
+use wiremock::{Mock, ResponseTemplate};
+
+const TUNNEL_ID: &str = "123456";
+
+struct Tunnelbroker;
+
+#[async_trait]
+impl Fixture for Tunnelbroker {
+    fn provider(&self, server: &MockServer) -> Arc<dyn DnsProvider> {
+        let config = TunnelbrokerConfig {
+            username: "user".to_string(),
+            update_key: "pass".to_string(),
+            tunnels: [(RECORD_NAME.to_string(), TUNNEL_ID.to_string())].into(),
+        };
+        Arc::new(
+            TunnelbrokerProvider::new(reqwest::Client::new(), &config).with_base_url(server.uri()),
+        )
+    }
+
+    fn zone(&self) -> Zone {
+        Zone {
+            id: TUNNEL_ID.to_string(),
+            name: RECORD_NAME.to_string(),
+        }
+    }
+
+    fn record_id(&self) -> Option<&'static str> {
+        None
+    }
+
+    fn reads_records(&self) -> bool {
+        false
+    }
+
+    async fn mount(&self, server: &MockServer, scenario: Scenario) {
+        let response = match scenario {
+            // A single update request, nothing to page through
+            Scenario::Success | Scenario::Paginated => None,
+            Scenario::AuthFailure => Some(ResponseTemplate::new(401).set_body_string("badauth")),
+            Scenario::RateLimited => Some(
+                ResponseTemplate::new(429)
+                    .insert_header("Retry-After", RETRY_AFTER_SECS.to_string().as_str()),
+            ),
+            Scenario::MalformedJson => {
+                Some(ResponseTemplate::new(200).set_body_string("<html>oops"))
+            }
+        };
+        if let Some(response) = response {
+            Mock::given(any())
+                .respond_with(response)
+                .mount(server)
+                .await;
+            return;
+        }
+
+        Mock::given(method("GET"))
+            .and(path("/nic/update"))
+            .and(query_param("hostname", TUNNEL_ID))
+            .and(query_param("myip", NEW_IP))
+            // user:pass
+            .and(header("authorization", "Basic dXNlcjpwYXNz"))
+            .and(header_regex("user-agent", "^ddns-agent/"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(format!("good {}", NEW_IP)))
+            .mount(server)
+            .await;
+    }
+}
+
+conformance!(Tunnelbroker);
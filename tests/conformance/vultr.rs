@@ -0,0 +1,119 @@
+use crate::*;
+use ddns_agent::provider::VultrProvider;
+use serde_json::{Value, json};
+use wiremock::matchers::{
+    any, body_json, header, method, path, query_param, query_param_is_missing,
+};
+use wiremock::{Mock, ResponseTemplate};
+
+const API_KEY: &str = "test-key";
+
+struct Vultr;
+
+fn record(id: &str, name: &str, data: &str) -> Value {
+    json!({
+        "id": id,
+        "type": "A",
+        "name": name,
+        "data": data,
+        "priority": -1,
+        "ttl": TTL
+    })
+}
+
+fn records(records: Value, next: &str) -> ResponseTemplate {
+    ResponseTemplate::new(200).set_body_json(json!({
+        "records": records,
+        "meta": { "total": 2, "links": { "next": next, "prev": "" } }
+    }))
+}
+
+#[async_trait]
+impl Fixture for Vultr {
+    fn provider(&self, server: &MockServer) -> Arc<dyn DnsProvider> {
+        Arc::new(VultrProvider::new(reqwest::Client::new(), API_KEY).with_base_url(server.uri()))
+    }
+
+    fn zone(&self) -> Zone {
+        Zone {
+            id: ZONE_NAME.to_string(),
+            name: ZONE_NAME.to_string(),
+        }
+    }
+
+    async fn mount(&self, server: &MockServer, scenario: Scenario) {
+        let response = match scenario {
+            Scenario::Success | Scenario::Paginated => None,
+            Scenario::AuthFailure => Some(ResponseTemplate::new(401).set_body_json(json!({
+                "error": "Invalid API token.",
+                "status": 401
+            }))),
+            Scenario::RateLimited => Some(
+                ResponseTemplate::new(429)
+                    .insert_header("Retry-After", RETRY_AFTER_SECS.to_string().as_str()),
+            ),
+            Scenario::MalformedJson => {
+                Some(ResponseTemplate::new(200).set_body_string("{\"domains\": "))
+            }
+        };
+        if let Some(response) = response {
+            Mock::given(any())
+                .respond_with(response)
+                .mount(server)
+                .await;
+            return;
+        }
+
+        Mock::given(method("GET"))
+            .and(path("/domains"))
+            .and(header("authorization", format!("Bearer {}", API_KEY).as_str()))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "domains": [{ "domain": ZONE_NAME, "date_created": "2024-01-01T00:00:00+00:00", "dns_sec": "disabled" }],
+                "meta": { "total": 1, "links": { "next": "", "prev": "" } }
+            })))
+            .mount(server)
+            .await;
+
+        let records_path = format!("/domains/{}/records", ZONE_NAME);
+        let target = record(RECORD_ID, "home", OLD_IP);
+        match scenario {
+            Scenario::Paginated => {
+                Mock::given(method("GET"))
+                    .and(path(records_path.as_str()))
+                    .and(query_param_is_missing("cursor"))
+                    .respond_with(records(
+                        json!([record("record-2", "other", OLD_IP)]),
+                        "page-2",
+                    ))
+                    .mount(server)
+                    .await;
+                Mock::given(method("GET"))
+                    .and(path(records_path.as_str()))
+                    .and(query_param("cursor", "page-2"))
+                    .respond_with(records(json!([target]), ""))
+                    .mount(server)
+                    .await;
+            }
+            _ => {
+                Mock::given(method("GET"))
+                    .and(path(records_path.as_str()))
+                    .respond_with(records(json!([target]), ""))
+                    .mount(server)
+                    .await;
+            }
+        }
+
+        Mock::given(method("PATCH"))
+            .and(path(format!("{}/{}", records_path, RECORD_ID)))
+            .and(body_json(json!({
+                "name": "home",
+                "data": NEW_IP,
+                "ttl": TTL
+            })))
+            .respond_with(ResponseTemplate::new(204))
+            .mount(server)
+            .await;
+    }
+}
+
+conformance!(Vultr);
@@ -0,0 +1,73 @@
+use crate::*;
+use ddns_agent::provider::{WebhookConfig, WebhookProvider};
+use wiremock::matchers::{any, body_string, header, method, path, query_param};
+use wiremock::{Mock, ResponseTemplate};
+
+const TOKEN: &str = "s3cr3t";
+
+struct Webhook;
+
+#[async_trait]
+impl Fixture for Webhook {
+    fn provider(&self, server: &MockServer) -> Arc<dyn DnsProvider> {
+        let config = WebhookConfig {
+            method: "post".to_string(),
+            url: format!("{}/update?host={{hostname}}&token={}", server.uri(), TOKEN),
+            headers: [("X-Record-Type".to_string(), "{type}".to_string())].into(),
+            body: Some(r#"{"address": "{ip}"}"#.to_string()),
+            success_status: vec![200, 202],
+            success_body: Some("^(good|nochg)".to_string()),
+        };
+        Arc::new(WebhookProvider::new(reqwest::Client::new(), &config).unwrap())
+    }
+
+    fn zone(&self) -> Zone {
+        Zone {
+            id: RECORD_NAME.to_string(),
+            name: RECORD_NAME.to_string(),
+        }
+    }
+
+    fn record_id(&self) -> Option<&'static str> {
+        None
+    }
+
+    fn reads_records(&self) -> bool {
+        false
+    }
+
+    async fn mount(&self, server: &MockServer, scenario: Scenario) {
+        let response = match scenario {
+            // A single update request, nothing to page through
+            Scenario::Success | Scenario::Paginated => None,
+            Scenario::AuthFailure => Some(ResponseTemplate::new(403).set_body_string("denied")),
+            Scenario::RateLimited => Some(
+                ResponseTemplate::new(429)
+                    .insert_header("Retry-After", RETRY_AFTER_SECS.to_string().as_str()),
+            ),
+            Scenario::MalformedJson => {
+                Some(ResponseTemplate::new(200).set_body_string("<html>oops"))
+            }
+        };
+        if let Some(response) = response {
+            Mock::given(any())
+                .respond_with(response)
+                .mount(server)
+                .await;
+            return;
+        }
+
+        Mock::given(method("POST"))
+            .and(path("/update"))
+            .and(query_param("host", RECORD_NAME))
+            .and(query_param("token", TOKEN))
+            .and(header("x-record-type", "A"))
+            .and(body_string(format!(r#"{{"address": "{}"}}"#, NEW_IP)))
+            .respond_with(ResponseTemplate::new(202).set_body_string(format!("good {}", NEW_IP)))
+            .expect(1)
+            .mount(server)
+            .await;
+    }
+}
+
+conformance!(Webhook);